@@ -0,0 +1,597 @@
+//! A high-level client for the instrument service.
+//!
+//! The [`Client`] connects to an instrumented application and returns a
+//! stream of [`Changes`], which hold the tasks, resources, and async ops that
+//! changed, with their updates already merged into the state the client has
+//! seen so far, and with field names and metadata resolved. The complete
+//! state is available from [`Updates::state`].
+//!
+//! # Examples
+//!
+//! Printing each task which completes:
+//!
+//! ```no_run
+//! # async fn docs() -> Result<(), Box<dyn std::error::Error>> {
+//! use console_api::client::Client;
+//!
+//! let mut client = Client::connect("http://127.0.0.1:6669").await?;
+//! let mut updates = client.watch().await?;
+//! while let Some(changes) = updates.next().await {
+//!     for task in changes?.tasks {
+//!         if task.dropped_at.is_some() {
+//!             let name = task.name.as_deref().unwrap_or("<unnamed>");
+//!             println!("{name} completed after {} polls", task.polls);
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    async_ops, field, instrument, resources, tasks, Attribute as PbAttribute, Field as PbField,
+    Location, Metadata, PollStats,
+};
+use futures_core::Stream;
+use instrument::instrument_client::InstrumentClient;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tonic::{
+    transport::{Channel, Endpoint},
+    Status, Streaming,
+};
+
+/// A client for an instrumented application's console server.
+#[derive(Clone, Debug)]
+pub struct Client {
+    inner: InstrumentClient<Channel>,
+}
+
+/// The stream of updates from a [`Client::watch`] call.
+///
+/// Each update is merged into the [`State`], and the entities it changed are
+/// returned as [`Changes`].
+#[derive(Debug)]
+pub struct Updates {
+    stream: Streaming<instrument::Update>,
+    state: State,
+}
+
+/// Everything a client has been told about the application.
+#[derive(Clone, Debug, Default)]
+pub struct State {
+    tasks: HashMap<u64, Task>,
+    resources: HashMap<u64, Resource>,
+    async_ops: HashMap<u64, AsyncOp>,
+    metadata: HashMap<u64, Arc<Metadata>>,
+    now: Option<SystemTime>,
+}
+
+/// The entities changed by one update, as they are after the update.
+#[derive(Clone, Debug, Default)]
+pub struct Changes {
+    /// The time the update was sent, according to the application.
+    pub now: Option<SystemTime>,
+    /// Tasks which were spawned or whose stats changed.
+    pub tasks: Vec<Task>,
+    /// Resources which were created or whose stats changed.
+    pub resources: Vec<Resource>,
+    /// Async ops which were created or whose stats changed.
+    pub async_ops: Vec<AsyncOp>,
+    /// The number of events the application dropped since the last update,
+    /// because its event buffer was full.
+    ///
+    /// When this is nonzero, the state may be missing entities, or have stale
+    /// stats for them.
+    pub dropped_events: u64,
+}
+
+/// A task in the application.
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub id: u64,
+    pub kind: tasks::task::Kind,
+    /// The value of the task's `task.name` field, if it has one.
+    pub name: Option<String>,
+    /// The target of the task's span, such as `tokio::task`.
+    pub target: Option<String>,
+    /// Where the task was spawned.
+    pub location: Option<Location>,
+    /// The task span's fields, by name.
+    pub fields: Vec<(String, field::Value)>,
+    pub created_at: Option<SystemTime>,
+    /// When the task completed, or `None` if it's still running.
+    pub dropped_at: Option<SystemTime>,
+    pub wakes: u64,
+    pub waker_clones: u64,
+    pub waker_drops: u64,
+    pub self_wakes: u64,
+    pub last_wake: Option<SystemTime>,
+    pub polls: u64,
+    pub first_poll: Option<SystemTime>,
+    pub last_poll_started: Option<SystemTime>,
+    pub last_poll_ended: Option<SystemTime>,
+    /// The total time the task has spent being polled.
+    pub busy_time: Duration,
+    /// The total time the task has spent waiting to be polled after being
+    /// woken.
+    pub scheduled_time: Duration,
+    /// Why the task completed, if it has.
+    pub end_reason: Option<tasks::EndReason>,
+}
+
+/// A resource in the application, such as a mutex or a timer.
+#[derive(Clone, Debug)]
+pub struct Resource {
+    pub id: u64,
+    pub parent_id: Option<u64>,
+    /// The kind of resource, such as `Timer` or `Sync`.
+    pub kind: String,
+    /// The resource's type, such as `Sleep` or `Semaphore`.
+    pub concrete_type: String,
+    pub location: Option<Location>,
+    /// Whether the resource is an implementation detail of another resource.
+    pub is_internal: bool,
+    pub created_at: Option<SystemTime>,
+    /// When the resource was dropped, or `None` if it's still alive.
+    pub dropped_at: Option<SystemTime>,
+    pub attributes: Vec<Attribute>,
+}
+
+/// An asynchronous operation on a resource, such as acquiring a semaphore.
+#[derive(Clone, Debug)]
+pub struct AsyncOp {
+    pub id: u64,
+    /// The method called on the resource, such as `Semaphore::acquire`.
+    pub source: String,
+    pub parent_id: Option<u64>,
+    pub resource_id: Option<u64>,
+    /// The task which is awaiting the operation, if it's been polled.
+    pub task_id: Option<u64>,
+    pub location: Option<Location>,
+    pub created_at: Option<SystemTime>,
+    /// When the operation completed, or `None` if it's still pending.
+    pub dropped_at: Option<SystemTime>,
+    pub polls: u64,
+    pub busy_time: Duration,
+    pub attributes: Vec<Attribute>,
+}
+
+/// A named value which describes a resource's or async op's current state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: field::Value,
+    /// The unit of the value, such as `ms`.
+    pub unit: Option<String>,
+}
+
+// === impl Client ===
+
+impl Client {
+    /// Connects to the console server at `dst`, such as
+    /// `http://127.0.0.1:6669`.
+    pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    {
+        let inner = InstrumentClient::connect(dst).await?;
+        Ok(Self { inner })
+    }
+
+    /// Returns a client which uses an existing `channel`.
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: InstrumentClient::new(channel),
+        }
+    }
+
+    /// Starts watching for updates.
+    ///
+    /// The first update holds every entity the application currently knows
+    /// about, and later updates hold what changed since the one before.
+    pub async fn watch(&mut self) -> Result<Updates, Status> {
+        let stream = self
+            .inner
+            .watch_updates(instrument::InstrumentRequest {})
+            .await?
+            .into_inner();
+        Ok(Updates {
+            stream,
+            state: State::default(),
+        })
+    }
+
+    /// Pauses the application's updates, until [`Client::resume`] is called.
+    pub async fn pause(&mut self) -> Result<(), Status> {
+        self.inner
+            .pause(instrument::PauseRequest::default())
+            .await?;
+        Ok(())
+    }
+
+    /// Resumes the application's updates after [`Client::pause`].
+    pub async fn resume(&mut self) -> Result<(), Status> {
+        self.inner.resume(instrument::ResumeRequest {}).await?;
+        Ok(())
+    }
+
+    /// Returns the generated client, for calls which this client doesn't
+    /// wrap.
+    pub fn into_inner(self) -> InstrumentClient<Channel> {
+        self.inner
+    }
+}
+
+// === impl Updates ===
+
+impl Updates {
+    /// Waits for the next update, and returns what it changed, or `None`
+    /// once the server closes the stream.
+    pub async fn next(&mut self) -> Option<Result<Changes, Status>> {
+        match self.stream.message().await {
+            Ok(Some(update)) => Some(Ok(self.state.apply(update))),
+            Ok(None) => None,
+            Err(status) => Some(Err(status)),
+        }
+    }
+
+    /// Returns the state after every update received so far.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+}
+
+impl Stream for Updates {
+    type Item = Result<Changes, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        Pin::new(&mut this.stream)
+            .poll_next(cx)
+            .map(|update| Some(update?.map(|update| this.state.apply(update))))
+    }
+}
+
+// === impl State ===
+
+impl State {
+    /// Merges `update` into the state, and returns the entities it changed.
+    pub fn apply(&mut self, update: instrument::Update) -> Changes {
+        let mut changes = Changes {
+            now: update.now.and_then(system_time),
+            ..Changes::default()
+        };
+        if changes.now.is_some() {
+            self.now = changes.now;
+        }
+
+        // Metadata is sent with the first update which refers to it, so it
+        // must be registered before anything else.
+        for new in update.new_metadata.into_iter().flat_map(|new| new.metadata) {
+            if let (Some(id), Some(metadata)) = (new.id, new.metadata) {
+                self.metadata.insert(id.id, Arc::new(metadata));
+            }
+        }
+
+        if let Some(update) = update.task_update {
+            changes.dropped_events += update.dropped_events;
+            changes.tasks = self.apply_tasks(update);
+        }
+        if let Some(update) = update.resource_update {
+            changes.dropped_events += update.dropped_events;
+            changes.resources = self.apply_resources(update);
+        }
+        if let Some(update) = update.async_op_update {
+            changes.dropped_events += update.dropped_events;
+            changes.async_ops = self.apply_async_ops(update);
+        }
+        changes
+    }
+
+    /// Returns the time of the latest update, according to the application.
+    pub fn now(&self) -> Option<SystemTime> {
+        self.now
+    }
+
+    pub fn task(&self, id: u64) -> Option<&Task> {
+        self.tasks.get(&id)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.values()
+    }
+
+    pub fn resource(&self, id: u64) -> Option<&Resource> {
+        self.resources.get(&id)
+    }
+
+    pub fn resources(&self) -> impl Iterator<Item = &Resource> {
+        self.resources.values()
+    }
+
+    pub fn async_op(&self, id: u64) -> Option<&AsyncOp> {
+        self.async_ops.get(&id)
+    }
+
+    pub fn async_ops(&self) -> impl Iterator<Item = &AsyncOp> {
+        self.async_ops.values()
+    }
+
+    fn apply_tasks(&mut self, update: tasks::TaskUpdate) -> Vec<Task> {
+        let mut changed = Vec::new();
+        for task in update.new_tasks {
+            let Some(id) = task.id else { continue };
+            let metadata = task
+                .metadata
+                .and_then(|meta| self.metadata.get(&meta.id))
+                .cloned();
+            let fields = self.fields(task.fields);
+            let name = fields
+                .iter()
+                .find(|(name, _)| name == "task.name")
+                .map(|(_, value)| value.to_string());
+            self.tasks.insert(
+                id.id,
+                Task {
+                    id: id.id,
+                    kind: tasks::task::Kind::try_from(task.kind).unwrap_or_default(),
+                    name,
+                    target: metadata.map(|meta| meta.target.clone()),
+                    location: task.location,
+                    fields,
+                    created_at: None,
+                    dropped_at: None,
+                    wakes: 0,
+                    waker_clones: 0,
+                    waker_drops: 0,
+                    self_wakes: 0,
+                    last_wake: None,
+                    polls: 0,
+                    first_poll: None,
+                    last_poll_started: None,
+                    last_poll_ended: None,
+                    busy_time: Duration::ZERO,
+                    scheduled_time: Duration::ZERO,
+                    end_reason: None,
+                },
+            );
+            changed.push(id.id);
+        }
+
+        for (id, stats) in update.stats_update {
+            let Some(task) = self.tasks.get_mut(&id) else {
+                continue;
+            };
+            task.created_at = stats.created_at.and_then(system_time);
+            task.dropped_at = stats.dropped_at.and_then(system_time);
+            task.wakes = stats.wakes;
+            task.waker_clones = stats.waker_clones;
+            task.waker_drops = stats.waker_drops;
+            task.self_wakes = stats.self_wakes;
+            task.last_wake = stats.last_wake.and_then(system_time);
+            let polls = PollTimes::from(stats.poll_stats);
+            task.polls = polls.polls;
+            task.first_poll = polls.first_poll;
+            task.last_poll_started = polls.last_poll_started;
+            task.last_poll_ended = polls.last_poll_ended;
+            task.busy_time = polls.busy_time;
+            task.scheduled_time = stats.scheduled_time.and_then(duration).unwrap_or_default();
+            task.end_reason = stats
+                .end_reason
+                .and_then(|reason| tasks::EndReason::try_from(reason).ok());
+            changed.push(id);
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+            .into_iter()
+            .filter_map(|id| self.tasks.get(&id).cloned())
+            .collect()
+    }
+
+    fn apply_resources(&mut self, update: resources::ResourceUpdate) -> Vec<Resource> {
+        let mut changed = Vec::new();
+        for resource in update.new_resources {
+            let Some(id) = resource.id else { continue };
+            let kind = match resource.kind.and_then(|kind| kind.kind) {
+                Some(resources::resource::kind::Kind::Known(known))
+                    if known == resources::resource::kind::Known::Timer as i32 =>
+                {
+                    "Timer".to_owned()
+                }
+                Some(resources::resource::kind::Kind::Other(other)) => other,
+                _ => String::new(),
+            };
+            self.resources.insert(
+                id.id,
+                Resource {
+                    id: id.id,
+                    parent_id: resource.parent_resource_id.map(|id| id.id),
+                    kind,
+                    concrete_type: resource.concrete_type,
+                    location: resource.location,
+                    is_internal: resource.is_internal,
+                    created_at: None,
+                    dropped_at: None,
+                    attributes: Vec::new(),
+                },
+            );
+            changed.push(id.id);
+        }
+
+        for (id, stats) in update.stats_update {
+            let attributes = self.attributes(stats.attributes);
+            let Some(resource) = self.resources.get_mut(&id) else {
+                continue;
+            };
+            resource.created_at = stats.created_at.and_then(system_time);
+            resource.dropped_at = stats.dropped_at.and_then(system_time);
+            resource.attributes = attributes;
+            changed.push(id);
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+            .into_iter()
+            .filter_map(|id| self.resources.get(&id).cloned())
+            .collect()
+    }
+
+    fn apply_async_ops(&mut self, update: async_ops::AsyncOpUpdate) -> Vec<AsyncOp> {
+        let mut changed = Vec::new();
+        for op in update.new_async_ops {
+            let Some(id) = op.id else { continue };
+            self.async_ops.insert(
+                id.id,
+                AsyncOp {
+                    id: id.id,
+                    source: op.source,
+                    parent_id: op.parent_async_op_id.map(|id| id.id),
+                    resource_id: op.resource_id.map(|id| id.id),
+                    task_id: None,
+                    location: op.location,
+                    created_at: None,
+                    dropped_at: None,
+                    polls: 0,
+                    busy_time: Duration::ZERO,
+                    attributes: Vec::new(),
+                },
+            );
+            changed.push(id.id);
+        }
+
+        for (id, stats) in update.stats_update {
+            let attributes = self.attributes(stats.attributes);
+            let Some(op) = self.async_ops.get_mut(&id) else {
+                continue;
+            };
+            op.task_id = stats.task_id.map(|id| id.id);
+            op.created_at = stats.created_at.and_then(system_time);
+            op.dropped_at = stats.dropped_at.and_then(system_time);
+            let polls = PollTimes::from(stats.poll_stats);
+            op.polls = polls.polls;
+            op.busy_time = polls.busy_time;
+            op.attributes = attributes;
+            changed.push(id);
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+            .into_iter()
+            .filter_map(|id| self.async_ops.get(&id).cloned())
+            .collect()
+    }
+
+    /// Resolves the names of `fields`, which may refer to their metadata's
+    /// list of field names.
+    fn fields(&self, fields: Vec<PbField>) -> Vec<(String, field::Value)> {
+        fields
+            .into_iter()
+            .filter_map(|field| self.field(field))
+            .collect()
+    }
+
+    fn field(&self, field: PbField) -> Option<(String, field::Value)> {
+        let name = match field.name? {
+            field::Name::StrName(name) => name,
+            field::Name::NameIdx(idx) => {
+                let metadata = self.metadata.get(&field.metadata_id?.id)?;
+                metadata.field_names.get(idx as usize)?.clone()
+            }
+        };
+        Some((name, field.value?))
+    }
+
+    fn attributes(&self, attributes: Vec<PbAttribute>) -> Vec<Attribute> {
+        attributes
+            .into_iter()
+            .filter_map(|attribute| {
+                let (name, value) = self.field(attribute.field?)?;
+                Some(Attribute {
+                    name,
+                    value,
+                    unit: attribute.unit,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The times from a [`PollStats`].
+/// Merges `update` into the earlier update `pending`, so that applying
+/// `pending` to a [`State`] has the same result as applying both in turn.
+///
+/// Since each update carries the latest stats for every task, resource and
+/// async op which changed, the stats in `update` replace those in `pending`,
+/// while new objects, poll ops and metadata are appended.
+pub fn coalesce(pending: &mut instrument::Update, update: instrument::Update) {
+    pending.now = update.now;
+    pending.lag = update.lag.or(pending.lag);
+    if let Some(new_metadata) = update.new_metadata {
+        pending
+            .new_metadata
+            .get_or_insert_with(Default::default)
+            .metadata
+            .extend(new_metadata.metadata);
+    }
+    if let Some(task_update) = update.task_update {
+        let pending = pending.task_update.get_or_insert_with(Default::default);
+        pending.new_tasks.extend(task_update.new_tasks);
+        pending.stats_update.extend(task_update.stats_update);
+        pending.dropped_events += task_update.dropped_events;
+    }
+    if let Some(resource_update) = update.resource_update {
+        let pending = pending.resource_update.get_or_insert_with(Default::default);
+        pending.new_resources.extend(resource_update.new_resources);
+        pending.stats_update.extend(resource_update.stats_update);
+        pending.new_poll_ops.extend(resource_update.new_poll_ops);
+        pending.dropped_events += resource_update.dropped_events;
+    }
+    if let Some(async_op_update) = update.async_op_update {
+        let pending = pending.async_op_update.get_or_insert_with(Default::default);
+        pending.new_async_ops.extend(async_op_update.new_async_ops);
+        pending.stats_update.extend(async_op_update.stats_update);
+        pending.dropped_events += async_op_update.dropped_events;
+    }
+}
+
+#[derive(Default)]
+struct PollTimes {
+    polls: u64,
+    first_poll: Option<SystemTime>,
+    last_poll_started: Option<SystemTime>,
+    last_poll_ended: Option<SystemTime>,
+    busy_time: Duration,
+}
+
+impl From<Option<PollStats>> for PollTimes {
+    fn from(stats: Option<PollStats>) -> Self {
+        let Some(stats) = stats else {
+            return Self::default();
+        };
+        Self {
+            polls: stats.polls,
+            first_poll: stats.first_poll.and_then(system_time),
+            last_poll_started: stats.last_poll_started.and_then(system_time),
+            last_poll_ended: stats.last_poll_ended.and_then(system_time),
+            busy_time: stats.busy_time.and_then(duration).unwrap_or_default(),
+        }
+    }
+}
+
+fn system_time(timestamp: prost_types::Timestamp) -> Option<SystemTime> {
+    SystemTime::try_from(timestamp).ok()
+}
+
+fn duration(duration: prost_types::Duration) -> Option<Duration> {
+    Duration::try_from(duration).ok()
+}
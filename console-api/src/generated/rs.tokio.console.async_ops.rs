@@ -64,6 +64,21 @@ pub struct AsyncOp {
     /// The resources's ID.
     #[prost(message, optional, tag = "5")]
     pub resource_id: ::core::option::Option<super::common::Id>,
+    /// The location in the instrumented program's code where the async op was
+    /// created, if it is known.
+    ///
+    /// This is only known when the instrumented program records backtraces of
+    /// async ops, since resources' instrumentation doesn't record where their
+    /// async ops are created.
+    #[prost(message, optional, tag = "6")]
+    pub location: ::core::option::Option<super::common::Location>,
+    /// A backtrace of where the async op was created.
+    ///
+    /// This is only set when the instrumented program records backtraces of
+    /// async ops, which is disabled by default since capturing backtraces is
+    /// expensive.
+    #[prost(string, optional, tag = "7")]
+    pub backtrace: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Statistics associated with a given async operation.
 #[derive(Clone, PartialEq, ::prost::Message)]
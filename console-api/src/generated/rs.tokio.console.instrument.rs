@@ -4,7 +4,10 @@
 ///
 /// TODO: In the future allow for the request to specify
 /// only the data that the caller cares about (i.e. only
-/// tasks but no resources)
+/// tasks but no resources). Once it can, add an RPC which
+/// changes what an existing stream of updates includes, so
+/// that a client can narrow or widen it without starting a
+/// new stream and receiving every task again.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct InstrumentRequest {}
 /// TaskDetailsRequest requests the stream of updates about
@@ -15,12 +18,38 @@ pub struct TaskDetailsRequest {
     #[prost(message, optional, tag = "1")]
     pub id: ::core::option::Option<super::common::Id>,
 }
+/// TasksDetailsRequest requests a single stream of updates about each of the
+/// tasks identified in the request.
+///
+/// The updates for each task are interleaved in the stream, and identified by
+/// their `TaskDetails.task_id`. Tasks which don't exist are ignored, unless
+/// none of the requested tasks exist.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TasksDetailsRequest {
+    /// Identifies the tasks for which details were requested.
+    #[prost(message, repeated, tag = "1")]
+    pub ids: ::prost::alloc::vec::Vec<super::common::Id>,
+}
 /// PauseRequest requests the stream of updates to pause.
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct PauseRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseRequest {
+    /// A human-readable description of the client requesting the pause, such
+    /// as the name of the program and its process ID.
+    ///
+    /// This is reported to all clients in `State.paused_by`, so that a pause
+    /// from another console session can be identified.
+    #[prost(string, optional, tag = "1")]
+    pub client: ::core::option::Option<::prost::alloc::string::String>,
+}
 /// ResumeRequest requests the stream of updates to resume after a pause.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct ResumeRequest {}
+/// StepRequest requests that a paused stream of updates publishes a single
+/// update, containing everything that has changed since the last update.
+///
+/// If the stream is not paused, this has no effect.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StepRequest {}
 /// Update carries all information regarding tasks, resources, async operations
 /// and resource operations in one message. There are a couple of reasons to combine all
 /// of these into a single message:
@@ -49,15 +78,47 @@ pub struct Update {
     /// Any new span metadata that was registered since the last update.
     #[prost(message, optional, tag = "5")]
     pub new_metadata: ::core::option::Option<super::common::RegisterMetadata>,
+    /// How far the client receiving this update has fallen behind.
+    ///
+    /// This is only set once the client has fallen behind since it connected.
+    #[prost(message, optional, tag = "6")]
+    pub lag: ::core::option::Option<ClientLag>,
+}
+/// How far a client has fallen behind in receiving updates.
+///
+/// When a client's buffer of updates is full, the updates which can't be sent
+/// to it are merged together, and sent as a single update once the client has
+/// caught up. If the merged update grows too large to send, the client is
+/// disconnected.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ClientLag {
+    /// The number of earlier updates which couldn't be sent to the client, and
+    /// were merged into this update.
+    #[prost(uint64, tag = "1")]
+    pub coalesced_updates: u64,
+    /// The total number of updates which couldn't be sent to the client since
+    /// it connected.
+    #[prost(uint64, tag = "2")]
+    pub total_coalesced_updates: u64,
 }
 /// StateRequest requests the current state of the aggregator.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct StateRequest {}
 /// State carries the current state of the aggregator.
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct State {
     #[prost(enumeration = "Temporality", tag = "1")]
     pub temporality: i32,
+    /// The time at which the aggregator was paused, if it is currently paused.
+    #[prost(message, optional, tag = "2")]
+    pub paused_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// A description of the client which paused the aggregator, if it is
+    /// currently paused.
+    ///
+    /// This is the `client` from the `PauseRequest`, if one was provided,
+    /// followed by the client's remote address, if it is known.
+    #[prost(string, optional, tag = "3")]
+    pub paused_by: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// `PauseResponse` is the value returned after a pause request.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
@@ -65,6 +126,97 @@ pub struct PauseResponse {}
 /// `ResumeResponse` is the value returned after a resume request.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct ResumeResponse {}
+/// `StepResponse` is the value returned after a step request.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StepResponse {}
+/// ServerInfoRequest requests information about the server.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ServerInfoRequest {}
+/// `ServerInfo` describes the server and how it is configured, so that clients
+/// can check whether they are compatible with it.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ServerInfo {
+    /// The version of the `console-api` crate that the server was built with.
+    #[prost(string, tag = "1")]
+    pub api_version: ::prost::alloc::string::String,
+    /// The RPCs that the server supports, such as `pause` or `step`.
+    #[prost(string, repeated, tag = "2")]
+    pub capabilities: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// How long the server retains data about completed tasks, dropped
+    /// resources, and dropped async ops.
+    #[prost(message, optional, tag = "3")]
+    pub retention: ::core::option::Option<::prost_types::Duration>,
+    /// How often the server publishes updates to clients.
+    #[prost(message, optional, tag = "4")]
+    pub publish_interval: ::core::option::Option<::prost_types::Duration>,
+}
+/// DumpTaskRequest requests a dump of the task identified in the request.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct DumpTaskRequest {
+    /// Identifies the task to dump.
+    #[prost(message, optional, tag = "1")]
+    pub id: ::core::option::Option<super::common::Id>,
+}
+/// `TaskDump` is everything the server knows about a task at the moment it
+/// was requested, for inspecting a task which seems to be stuck.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TaskDump {
+    /// The system time when the dump was taken.
+    #[prost(message, optional, tag = "1")]
+    pub now: ::core::option::Option<::prost_types::Timestamp>,
+    /// The task, with the full values of all of its fields.
+    #[prost(message, optional, tag = "2")]
+    pub task: ::core::option::Option<super::tasks::Task>,
+    /// The task's stats, as of `now`.
+    #[prost(message, optional, tag = "3")]
+    pub stats: ::core::option::Option<super::tasks::Stats>,
+    /// A backtrace of where the task's future is currently suspended.
+    ///
+    /// This is only set by servers which are able to capture one, such as
+    /// those built against a runtime which supports task dumps.
+    #[prost(string, optional, tag = "4")]
+    pub backtrace: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// AbortTaskRequest requests that the task identified in the request is
+/// aborted.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct AbortTaskRequest {
+    /// Identifies the task to abort.
+    #[prost(message, optional, tag = "1")]
+    pub id: ::core::option::Option<super::common::Id>,
+}
+/// `AbortTaskResponse` is the value returned after the task has been told to
+/// abort.
+///
+/// The task may not finish aborting until it is next polled; its stats will
+/// show when it does.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct AbortTaskResponse {}
+/// A message sent by the framed transport, which streams the same updates as
+/// `WatchUpdates` and `WatchState` over a plain TCP or Unix domain socket,
+/// rather than over gRPC.
+///
+/// Each frame is prefixed by its encoded length, as a 4-byte big-endian
+/// integer. The framed transport only sends frames; it doesn't accept any
+/// requests, so a client connected with it can't pause the server or watch a
+/// task's details.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Frame {
+    #[prost(oneof = "frame::Message", tags = "1, 2")]
+    pub message: ::core::option::Option<frame::Message>,
+}
+/// Nested message and enum types in `Frame`.
+pub mod frame {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// An update, as sent by `WatchUpdates`.
+        #[prost(message, tag = "1")]
+        Update(super::Update),
+        /// The state of the aggregator, as sent by `WatchState`.
+        #[prost(message, tag = "2")]
+        State(super::State),
+    }
+}
 /// The time "state" of the aggregator.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -246,6 +398,39 @@ pub mod instrument_client {
                 );
             self.inner.server_streaming(req, path, codec).await
         }
+        /// Produces a single stream of updates describing the activity of each of a set of tasks.
+        ///
+        /// This is equivalent to calling `WatchTaskDetails` for each task, without
+        /// opening a stream per task.
+        pub async fn watch_tasks_details(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TasksDetailsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::super::tasks::TaskDetails>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rs.tokio.console.instrument.Instrument/WatchTasksDetails",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "rs.tokio.console.instrument.Instrument",
+                        "WatchTasksDetails",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
         /// Produces a stream of state of the aggregator.
         pub async fn watch_state(
             &mut self,
@@ -324,6 +509,108 @@ pub mod instrument_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Requests that a paused stream publishes exactly one more update.
+        pub async fn step(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StepRequest>,
+        ) -> std::result::Result<tonic::Response<super::StepResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rs.tokio.console.instrument.Instrument/Step",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("rs.tokio.console.instrument.Instrument", "Step"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns information about the server and how it is configured.
+        pub async fn get_server_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ServerInfoRequest>,
+        ) -> std::result::Result<tonic::Response<super::ServerInfo>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rs.tokio.console.instrument.Instrument/GetServerInfo",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "rs.tokio.console.instrument.Instrument",
+                        "GetServerInfo",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns a one-off dump of everything the server knows about a task.
+        pub async fn dump_task(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DumpTaskRequest>,
+        ) -> std::result::Result<tonic::Response<super::TaskDump>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rs.tokio.console.instrument.Instrument/DumpTask",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("rs.tokio.console.instrument.Instrument", "DumpTask"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Requests that a task is aborted, if the server is able to abort it.
+        pub async fn abort_task(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AbortTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AbortTaskResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rs.tokio.console.instrument.Instrument/AbortTask",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("rs.tokio.console.instrument.Instrument", "AbortTask"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -370,6 +657,26 @@ pub mod instrument_server {
             tonic::Response<Self::WatchTaskDetailsStream>,
             tonic::Status,
         >;
+        /// Server streaming response type for the WatchTasksDetails method.
+        type WatchTasksDetailsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::tasks::TaskDetails,
+                    tonic::Status,
+                >,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Produces a single stream of updates describing the activity of each of a set of tasks.
+        ///
+        /// This is equivalent to calling `WatchTaskDetails` for each task, without
+        /// opening a stream per task.
+        async fn watch_tasks_details(
+            &self,
+            request: tonic::Request<super::TasksDetailsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchTasksDetailsStream>,
+            tonic::Status,
+        >;
         /// Server streaming response type for the WatchState method.
         type WatchStateStream: tonic::codegen::tokio_stream::Stream<
                 Item = std::result::Result<super::State, tonic::Status>,
@@ -391,6 +698,29 @@ pub mod instrument_server {
             &self,
             request: tonic::Request<super::ResumeRequest>,
         ) -> std::result::Result<tonic::Response<super::ResumeResponse>, tonic::Status>;
+        /// Requests that a paused stream publishes exactly one more update.
+        async fn step(
+            &self,
+            request: tonic::Request<super::StepRequest>,
+        ) -> std::result::Result<tonic::Response<super::StepResponse>, tonic::Status>;
+        /// Returns information about the server and how it is configured.
+        async fn get_server_info(
+            &self,
+            request: tonic::Request<super::ServerInfoRequest>,
+        ) -> std::result::Result<tonic::Response<super::ServerInfo>, tonic::Status>;
+        /// Returns a one-off dump of everything the server knows about a task.
+        async fn dump_task(
+            &self,
+            request: tonic::Request<super::DumpTaskRequest>,
+        ) -> std::result::Result<tonic::Response<super::TaskDump>, tonic::Status>;
+        /// Requests that a task is aborted, if the server is able to abort it.
+        async fn abort_task(
+            &self,
+            request: tonic::Request<super::AbortTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AbortTaskResponse>,
+            tonic::Status,
+        >;
     }
     /// `InstrumentServer<T>` implements `Instrument` as a service.
     #[derive(Debug)]
@@ -561,6 +891,53 @@ pub mod instrument_server {
                     };
                     Box::pin(fut)
                 }
+                "/rs.tokio.console.instrument.Instrument/WatchTasksDetails" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchTasksDetailsSvc<T: Instrument>(pub Arc<T>);
+                    impl<
+                        T: Instrument,
+                    > tonic::server::ServerStreamingService<super::TasksDetailsRequest>
+                    for WatchTasksDetailsSvc<T> {
+                        type Response = super::super::tasks::TaskDetails;
+                        type ResponseStream = T::WatchTasksDetailsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TasksDetailsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Instrument>::watch_tasks_details(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = WatchTasksDetailsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/rs.tokio.console.instrument.Instrument/WatchState" => {
                     #[allow(non_camel_case_types)]
                     struct WatchStateSvc<T: Instrument>(pub Arc<T>);
@@ -693,6 +1070,184 @@ pub mod instrument_server {
                     };
                     Box::pin(fut)
                 }
+                "/rs.tokio.console.instrument.Instrument/Step" => {
+                    #[allow(non_camel_case_types)]
+                    struct StepSvc<T: Instrument>(pub Arc<T>);
+                    impl<T: Instrument> tonic::server::UnaryService<super::StepRequest>
+                    for StepSvc<T> {
+                        type Response = super::StepResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StepRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Instrument>::step(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = StepSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rs.tokio.console.instrument.Instrument/GetServerInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetServerInfoSvc<T: Instrument>(pub Arc<T>);
+                    impl<
+                        T: Instrument,
+                    > tonic::server::UnaryService<super::ServerInfoRequest>
+                    for GetServerInfoSvc<T> {
+                        type Response = super::ServerInfo;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ServerInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Instrument>::get_server_info(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetServerInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rs.tokio.console.instrument.Instrument/DumpTask" => {
+                    #[allow(non_camel_case_types)]
+                    struct DumpTaskSvc<T: Instrument>(pub Arc<T>);
+                    impl<
+                        T: Instrument,
+                    > tonic::server::UnaryService<super::DumpTaskRequest>
+                    for DumpTaskSvc<T> {
+                        type Response = super::TaskDump;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DumpTaskRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Instrument>::dump_task(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DumpTaskSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rs.tokio.console.instrument.Instrument/AbortTask" => {
+                    #[allow(non_camel_case_types)]
+                    struct AbortTaskSvc<T: Instrument>(pub Arc<T>);
+                    impl<
+                        T: Instrument,
+                    > tonic::server::UnaryService<super::AbortTaskRequest>
+                    for AbortTaskSvc<T> {
+                        type Response = super::AbortTaskResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AbortTaskRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Instrument>::abort_task(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AbortTaskSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
@@ -50,6 +50,12 @@ pub struct TaskDetails {
     /// woken and when it is next polled.
     #[prost(message, optional, tag = "5")]
     pub scheduled_times_histogram: ::core::option::Option<DurationHistogram>,
+    /// The task's most recent polls, oldest first.
+    ///
+    /// Only polls which have ended are included. This is empty for servers
+    /// which don't record recent polls.
+    #[prost(message, repeated, tag = "6")]
+    pub recent_polls: ::prost::alloc::vec::Vec<Poll>,
     /// A histogram of task poll durations.
     ///
     /// This is either:
@@ -77,6 +83,16 @@ pub mod task_details {
         Histogram(super::DurationHistogram),
     }
 }
+/// A single poll of a task.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Poll {
+    /// When the poll started.
+    #[prost(message, optional, tag = "1")]
+    pub started_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// How long the poll took.
+    #[prost(message, optional, tag = "2")]
+    pub duration: ::core::option::Option<::prost_types::Duration>,
+}
 /// Data recorded when a new task is spawned.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Task {
@@ -164,7 +180,7 @@ pub mod task {
     }
 }
 /// Task performance statistics.
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Stats {
     /// Timestamp of when the task was spawned.
     #[prost(message, optional, tag = "1")]
@@ -203,6 +219,60 @@ pub struct Stats {
     /// some resource.
     #[prost(message, optional, tag = "9")]
     pub scheduled_time: ::core::option::Option<::prost_types::Duration>,
+    /// A summary of the task's poll times histogram.
+    ///
+    /// This is sent with every update, unlike the histogram itself, which is
+    /// only sent in the task's details. It is not present if the task has not
+    /// yet completed a poll.
+    #[prost(message, optional, tag = "10")]
+    pub poll_times_summary: ::core::option::Option<PollTimesSummary>,
+    /// If the task panicked, how it panicked.
+    ///
+    /// This is set as soon as the task panics, so a task which has panicked
+    /// may not have been dropped yet.
+    #[prost(message, optional, tag = "11")]
+    pub panic: ::core::option::Option<Panic>,
+    /// If the task was aborted, who aborted it.
+    ///
+    /// This is only known for tasks which were aborted through
+    /// `console_subscriber::abort` or by a client, rather than by calling `abort`
+    /// directly.
+    #[prost(message, optional, tag = "12")]
+    pub abort: ::core::option::Option<Abort>,
+    /// Why the task ended, once it has been dropped.
+    #[prost(enumeration = "EndReason", optional, tag = "13")]
+    pub end_reason: ::core::option::Option<i32>,
+}
+/// A panic in a task.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Panic {
+    /// The panic's message, if its payload was a string.
+    #[prost(string, optional, tag = "1")]
+    pub message: ::core::option::Option<::prost::alloc::string::String>,
+    /// Where in the code the task panicked.
+    #[prost(message, optional, tag = "2")]
+    pub location: ::core::option::Option<super::common::Location>,
+}
+/// A request to abort a task.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Abort {
+    /// The task which aborted this task, if it was aborted from inside a task.
+    #[prost(message, optional, tag = "1")]
+    pub aborted_by: ::core::option::Option<super::common::Id>,
+    /// Where in the code the task was aborted.
+    #[prost(message, optional, tag = "2")]
+    pub location: ::core::option::Option<super::common::Location>,
+}
+/// A compact summary of a task's poll times histogram.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PollTimesSummary {
+    /// The 99th percentile poll time.
+    #[prost(message, optional, tag = "1")]
+    pub p99: ::core::option::Option<::prost_types::Duration>,
+    /// The longest poll time, including any outliers which exceeded the
+    /// histogram's maximum value.
+    #[prost(message, optional, tag = "2")]
+    pub max: ::core::option::Option<::prost_types::Duration>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DurationHistogram {
@@ -220,3 +290,41 @@ pub struct DurationHistogram {
     #[prost(uint64, optional, tag = "4")]
     pub highest_outlier: ::core::option::Option<u64>,
 }
+/// Why a task ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum EndReason {
+    /// The task was dropped without panicking or being aborted through
+    /// `console_subscriber::abort`.
+    ///
+    /// The task most likely ran to completion, but it may also have been
+    /// cancelled in a way which the target can't see, such as by its `JoinSet`
+    /// being dropped or its runtime shutting down.
+    Completed = 0,
+    /// The task was aborted before it completed.
+    Cancelled = 1,
+    /// The task panicked.
+    Panicked = 2,
+}
+impl EndReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Completed => "COMPLETED",
+            Self::Cancelled => "CANCELLED",
+            Self::Panicked => "PANICKED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "COMPLETED" => Some(Self::Completed),
+            "CANCELLED" => Some(Self::Cancelled),
+            "PANICKED" => Some(Self::Panicked),
+            _ => None,
+        }
+    }
+}
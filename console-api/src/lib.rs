@@ -2,6 +2,9 @@
 
 /// Represents the operations performed by an async runtime.
 pub mod async_ops;
+/// A client which merges the instrument service's updates into typed state.
+#[cfg(feature = "transport")]
+pub mod client;
 /// Represents unique id's and Rust source locations.
 mod common;
 /// Represents interactions between the console-subscriber and a console client observing it.
@@ -13,3 +16,7 @@ pub mod tasks;
 /// Represents events on the tracing subsystem: thread registration and span activities.
 pub mod trace;
 pub use common::*;
+
+/// The version of this crate, which servers report to clients in
+/// [`instrument::ServerInfo`].
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,137 @@
+#![cfg(feature = "transport")]
+
+use console_api::{
+    client::{coalesce, State},
+    field,
+    instrument::Update,
+    register_metadata::NewMetadata,
+    resources, tasks, Field, Id, MetaId, Metadata, PollStats, RegisterMetadata,
+};
+use std::time::{Duration, SystemTime};
+
+fn task_stats(polls: u64, dropped: bool) -> tasks::Stats {
+    tasks::Stats {
+        created_at: Some(SystemTime::UNIX_EPOCH.into()),
+        dropped_at: dropped.then(|| (SystemTime::UNIX_EPOCH + Duration::from_secs(5)).into()),
+        wakes: polls,
+        poll_stats: Some(PollStats {
+            polls,
+            busy_time: Some(prost_types::Duration {
+                seconds: 1,
+                nanos: 0,
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn merges_updates() {
+    let mut state = State::default();
+
+    let changes = state.apply(Update {
+        new_metadata: Some(RegisterMetadata {
+            metadata: vec![NewMetadata {
+                id: Some(MetaId { id: 1 }),
+                metadata: Some(Metadata {
+                    name: "runtime.spawn".to_owned(),
+                    target: "tokio::task".to_owned(),
+                    field_names: vec!["kind".to_owned(), "task.name".to_owned()],
+                    ..Default::default()
+                }),
+            }],
+        }),
+        task_update: Some(tasks::TaskUpdate {
+            new_tasks: vec![tasks::Task {
+                id: Some(Id { id: 7 }),
+                metadata: Some(MetaId { id: 1 }),
+                fields: vec![Field {
+                    metadata_id: Some(MetaId { id: 1 }),
+                    name: Some(field::Name::NameIdx(1)),
+                    value: Some("worker".into()),
+                }],
+                ..Default::default()
+            }],
+            stats_update: [(7, task_stats(1, false))].into(),
+            dropped_events: 2,
+        }),
+        resource_update: Some(resources::ResourceUpdate {
+            new_resources: vec![resources::Resource {
+                id: Some(Id { id: 3 }),
+                concrete_type: "Sleep".to_owned(),
+                kind: Some(resources::resource::Kind {
+                    kind: Some(resources::resource::kind::Kind::Known(
+                        resources::resource::kind::Known::Timer as i32,
+                    )),
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    assert_eq!(changes.dropped_events, 2);
+    assert_eq!(changes.tasks.len(), 1);
+    let task = &changes.tasks[0];
+    assert_eq!(task.name.as_deref(), Some("worker"));
+    assert_eq!(task.target.as_deref(), Some("tokio::task"));
+    assert_eq!(task.polls, 1);
+    assert_eq!(task.busy_time, Duration::from_secs(1));
+    assert_eq!(task.dropped_at, None);
+    assert_eq!(changes.resources[0].kind, "Timer");
+
+    // Later updates only hold stats, which are merged into the known tasks.
+    let changes = state.apply(Update {
+        task_update: Some(tasks::TaskUpdate {
+            stats_update: [(7, task_stats(4, true)), (99, task_stats(1, false))].into(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    assert_eq!(changes.tasks.len(), 1);
+    assert!(changes.resources.is_empty());
+    let task = state.task(7).unwrap();
+    assert_eq!(task.name.as_deref(), Some("worker"));
+    assert_eq!(task.polls, 4);
+    assert_eq!(
+        task.dropped_at,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(5))
+    );
+    assert_eq!(state.tasks().count(), 1);
+}
+
+#[test]
+fn coalesced_updates_apply_like_the_updates_they_merge() {
+    let new_task = |id, polls| Update {
+        task_update: Some(tasks::TaskUpdate {
+            new_tasks: vec![tasks::Task {
+                id: Some(Id { id }),
+                ..Default::default()
+            }],
+            stats_update: [(id, task_stats(polls, false))].into(),
+            dropped_events: 1,
+        }),
+        ..Default::default()
+    };
+    let mut pending = new_task(7, 1);
+    coalesce(
+        &mut pending,
+        Update {
+            task_update: Some(tasks::TaskUpdate {
+                stats_update: [(7, task_stats(4, true))].into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+    coalesce(&mut pending, new_task(8, 2));
+
+    let mut state = State::default();
+    let changes = state.apply(pending);
+    assert_eq!(changes.dropped_events, 2);
+    let task = state.task(7).unwrap();
+    assert_eq!(task.polls, 4);
+    assert!(task.dropped_at.is_some());
+    assert_eq!(state.task(8).unwrap().polls, 2);
+}
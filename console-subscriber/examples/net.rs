@@ -0,0 +1,65 @@
+//! Runs a TCP echo server with a few clients, so that the listener and its
+//! connections show up as `net` resources in the console.
+use std::time::Duration;
+
+use console_subscriber::{net::TcpListener, ConsoleLayer};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    task, time,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ConsoleLayer::builder()
+        .with_default_env()
+        .publish_interval(Duration::from_millis(100))
+        .init();
+
+    let listener = TcpListener::new(tokio::net::TcpListener::bind("127.0.0.1:0").await?);
+    let addr = listener.local_addr()?;
+
+    let server = task::Builder::new()
+        .name("accept-loop")
+        .spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await?;
+                task::Builder::new()
+                    .name("echo")
+                    .spawn(async move {
+                        let mut buf = [0; 1024];
+                        while let Ok(n @ 1..) = stream.read(&mut buf).await {
+                            if stream.write_all(&buf[..n]).await.is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .unwrap();
+            }
+            #[allow(unreachable_code)]
+            Ok::<(), std::io::Error>(())
+        })
+        .unwrap();
+
+    for i in 0..3 {
+        task::Builder::new()
+            .name("client")
+            .spawn(async move {
+                loop {
+                    let mut stream = TcpStream::connect(addr).await?;
+                    let mut buf = [0; 5];
+                    for _ in 0..(i + 1) * 5 {
+                        stream.write_all(b"hello").await?;
+                        stream.read_exact(&mut buf).await?;
+                        time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+                #[allow(unreachable_code)]
+                Ok::<(), std::io::Error>(())
+            })
+            .unwrap();
+    }
+
+    server.await??;
+    Ok(())
+}
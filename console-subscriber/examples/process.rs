@@ -0,0 +1,30 @@
+//! Spawns child processes and waits on them, so they show up as `process`
+//! resources in the console.
+use std::time::Duration;
+
+use console_subscriber::{process::Child, ConsoleLayer};
+use tokio::{process::Command, task, time};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ConsoleLayer::builder()
+        .with_default_env()
+        .publish_interval(Duration::from_millis(100))
+        .init();
+
+    let waiter = task::Builder::new()
+        .name("wait-for-sleep")
+        .spawn(async {
+            loop {
+                let mut child = Child::new(Command::new("sleep").arg("5").spawn()?);
+                child.wait().await?;
+                time::sleep(Duration::from_secs(1)).await;
+            }
+            #[allow(unreachable_code)]
+            Ok::<(), std::io::Error>(())
+        })
+        .unwrap();
+
+    waiter.await??;
+    Ok(())
+}
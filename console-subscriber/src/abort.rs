@@ -0,0 +1,151 @@
+//! Aborting tasks so that the console can show who aborted them, and letting
+//! the console abort tasks.
+//!
+//! Tokio doesn't record anything when a task is aborted, so a task which was
+//! aborted looks like any other task which completed. Aborting a task through
+//! these functions emits an event saying which task aborted it, and where.
+//!
+//! The subscriber only observes tasks through their spans, so it has no
+//! handle to abort a task with unless the task's handle is given to it.
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Aborts the task behind `handle`, recording the task which aborted it and
+/// where, so that the console can show them in the aborted task's details.
+///
+/// This is otherwise the same as [`JoinHandle::abort`]. Tasks which are
+/// aborted in other ways, such as by dropping the `JoinSet` they were spawned
+/// on, aren't recorded.
+///
+/// Recording which task aborted another requires Tokio's unstable task IDs,
+/// so this only aborts the task unless built with `--cfg tokio_unstable`.
+#[track_caller]
+pub fn abort<T>(handle: &JoinHandle<T>) {
+    #[cfg(tokio_unstable)]
+    record(handle.id());
+    handle.abort();
+}
+
+/// Aborts the task behind `handle`, recording the task which aborted it and
+/// where.
+///
+/// This is the same as [`abort`], for an [`AbortHandle`].
+#[track_caller]
+pub fn abort_handle(handle: &AbortHandle) {
+    #[cfg(tokio_unstable)]
+    record(handle.id());
+    handle.abort();
+}
+
+/// Lets clients, such as `tokio-console`, abort the task behind `handle`.
+///
+/// Clients abort tasks through the [`AbortTask`] RPC, and the task's details
+/// then show that it was aborted by the console. The task can still be
+/// aborted in any other way.
+///
+/// Finding the task which a client asks to abort requires Tokio's unstable
+/// task IDs, so this does nothing unless built with `--cfg tokio_unstable`.
+///
+/// [`AbortTask`]: console_api::instrument::instrument_server::Instrument::abort_task
+pub fn allow_abort<T>(handle: &JoinHandle<T>) {
+    allow_abort_handle(handle.abort_handle());
+}
+
+/// Lets clients, such as `tokio-console`, abort the task behind `handle`.
+///
+/// This is the same as [`allow_abort`], for an [`AbortHandle`].
+#[cfg_attr(not(tokio_unstable), allow(unused_variables))]
+pub fn allow_abort_handle(handle: AbortHandle) {
+    #[cfg(tokio_unstable)]
+    abortable::insert(handle);
+}
+
+/// Aborts the task with the Tokio task ID `id`, if it was allowed to be
+/// aborted and hasn't completed, returning whether it was.
+#[cfg(tokio_unstable)]
+pub(crate) fn abort_allowed(id: u64) -> bool {
+    abortable::abort(id)
+}
+
+#[cfg(not(tokio_unstable))]
+pub(crate) fn abort_allowed(_: u64) -> bool {
+    false
+}
+
+/// The handles of the tasks which clients are allowed to abort, by their
+/// Tokio task IDs.
+#[cfg(tokio_unstable)]
+mod abortable {
+    use std::{
+        collections::HashMap,
+        sync::{Mutex, OnceLock},
+    };
+    use tokio::task::AbortHandle;
+
+    struct Abortable {
+        handles: HashMap<u64, AbortHandle>,
+        /// How many handles there were after the finished tasks' handles
+        /// were last removed, so that they're only removed once there are
+        /// twice as many, rather than on every insert.
+        pruned_len: usize,
+    }
+
+    fn abortable() -> std::sync::MutexGuard<'static, Abortable> {
+        static ABORTABLE: OnceLock<Mutex<Abortable>> = OnceLock::new();
+        ABORTABLE
+            .get_or_init(|| {
+                Mutex::new(Abortable {
+                    handles: HashMap::new(),
+                    pruned_len: 0,
+                })
+            })
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(super) fn insert(handle: AbortHandle) {
+        let Some(id) = super::tokio_id(handle.id()) else {
+            return;
+        };
+        let mut abortable = abortable();
+        if abortable.handles.len() >= 2 * abortable.pruned_len.max(64) {
+            abortable.handles.retain(|_, handle| !handle.is_finished());
+            abortable.pruned_len = abortable.handles.len();
+        }
+        abortable.handles.insert(id, handle);
+    }
+
+    pub(super) fn abort(id: u64) -> bool {
+        match abortable().handles.remove(&id) {
+            Some(handle) if !handle.is_finished() => {
+                handle.abort();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns a Tokio task ID as a number.
+#[cfg(tokio_unstable)]
+fn tokio_id(id: tokio::task::Id) -> Option<u64> {
+    // Tokio only exposes task IDs as a number through their `Display`
+    // implementation, which is also how they're recorded on spawn spans.
+    id.to_string().parse().ok()
+}
+
+#[cfg(tokio_unstable)]
+#[track_caller]
+fn record(id: tokio::task::Id) {
+    let Some(id) = tokio_id(id) else {
+        return;
+    };
+    let location = std::panic::Location::caller();
+    tracing::event!(
+        target: "runtime::task::abort",
+        tracing::Level::TRACE,
+        task.id = id,
+        loc.file = location.file(),
+        loc.line = location.line(),
+        loc.col = location.column(),
+    );
+}
@@ -1,4 +1,5 @@
 use std::{
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering::*},
         Arc,
@@ -8,12 +9,13 @@ use std::{
 
 use console_api as proto;
 use prost::Message;
-use proto::resources::resource;
+use proto::{client::coalesce, resources::resource};
 use tokio::sync::{mpsc, Notify};
 use tracing_core::{span::Id, Metadata};
 
 use super::{Command, Event, Shared, Watch};
 use crate::{
+    snapshot,
     stats::{self, Unsent},
     ToProto, WatchRequest,
 };
@@ -45,6 +47,9 @@ pub struct Aggregator {
     /// How long to keep task data after a task has completed.
     retention: Duration,
 
+    /// Where to write the final snapshot of the state, if anywhere.
+    final_snapshot_path: Option<PathBuf>,
+
     /// Shared state, including a `Notify` that triggers a flush when the event
     /// buffer is approaching capacity.
     shared: Arc<Shared>,
@@ -53,7 +58,7 @@ pub struct Aggregator {
     state_watchers: ShrinkVec<Watch<proto::instrument::State>>,
 
     /// Currently active RPCs streaming task events.
-    watchers: ShrinkVec<Watch<proto::instrument::Update>>,
+    watchers: ShrinkVec<UpdateWatch>,
 
     /// Currently active RPCs streaming task details events, by task ID.
     details_watchers: ShrinkMap<Id, Vec<Watch<proto::tasks::TaskDetails>>>,
@@ -94,11 +99,28 @@ pub struct Aggregator {
     /// The time "state" of the aggregator, such as paused or live.
     temporality: proto::instrument::Temporality,
 
+    /// When the aggregator was paused, and a description of the client that
+    /// paused it, if it is currently paused.
+    paused: Option<(Instant, Option<String>)>,
+
     /// Used to anchor monotonic timestamps to a base `SystemTime`, to produce a
     /// timestamp that can be sent over the wire.
     base_time: stats::TimeAnchor,
 }
 
+/// A client streaming updates, which merges together the updates that can't
+/// be sent to it while it's falling behind.
+struct UpdateWatch {
+    watch: Watch<proto::instrument::Update>,
+    /// The updates which couldn't be sent to the client yet, merged together.
+    pending: Option<proto::instrument::Update>,
+    /// The number of updates merged into `pending`.
+    coalesced: u64,
+    /// The number of updates which couldn't be sent since the client
+    /// connected.
+    total_coalesced: u64,
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Flush {
     pub(crate) should_flush: Notify,
@@ -124,6 +146,7 @@ struct Task {
     metadata: &'static Metadata<'static>,
     fields: Vec<proto::Field>,
     location: Option<proto::Location>,
+    parent_id: Option<Id>,
 }
 
 struct AsyncOp {
@@ -133,6 +156,8 @@ struct AsyncOp {
     resource_id: Id,
     metadata: &'static Metadata<'static>,
     source: String,
+    location: Option<proto::Location>,
+    backtrace: Option<String>,
 }
 
 impl Aggregator {
@@ -148,6 +173,7 @@ impl Aggregator {
             rpcs,
             publish_interval: builder.publish_interval,
             retention: builder.retention,
+            final_snapshot_path: builder.final_snapshot_path.clone(),
             events,
             watchers: Default::default(),
             details_watchers: Default::default(),
@@ -162,6 +188,7 @@ impl Aggregator {
             async_op_stats: IdData::default(),
             poll_ops: Default::default(),
             temporality: proto::instrument::Temporality::Live,
+            paused: None,
             base_time,
         }
     }
@@ -191,6 +218,7 @@ impl Aggregator {
 
                 // a new command from a client
                 cmd = self.rpcs.recv() => {
+                    let mut step = false;
                     match cmd {
                         Some(Command::Instrument(subscription)) => {
                             self.add_instrument_subscription(subscription);
@@ -201,11 +229,40 @@ impl Aggregator {
                         Some(Command::WatchState(subscription)) => {
                             self.add_state_subscription(subscription);
                         }
-                        Some(Command::Pause) => {
+                        Some(Command::Pause { paused_by }) => {
                             self.temporality = proto::instrument::Temporality::Paused;
+                            // if another client already paused the aggregator,
+                            // keep reporting when and by whom it was first paused.
+                            self.paused.get_or_insert_with(|| (Instant::now(), paused_by));
                         }
                         Some(Command::Resume) => {
                             self.temporality = proto::instrument::Temporality::Live;
+                            self.paused = None;
+                        }
+                        Some(Command::Step) => {
+                            // a live aggregator publishes updates on its own.
+                            step = self.temporality == proto::instrument::Temporality::Paused;
+                        }
+                        Some(Command::DumpTask { id, dump }) => {
+                            if let Some(task_dump) = self.dump_task(&id) {
+                                let _ = dump.send(task_dump);
+                            }
+                        }
+                        Some(Command::AbortTask { id, aborted }) => {
+                            let _ = aborted.send(self.abort_task(&id));
+                        }
+                        Some(Command::Snapshot(written)) => {
+                            let res = self.write_final_snapshot();
+                            match written {
+                                Some(written) => {
+                                    let _ = written.send(res);
+                                }
+                                None => {
+                                    if let Err(error) = res {
+                                        tracing::warn!(%error, "failed to write the final snapshot");
+                                    }
+                                }
+                            }
                         }
                         None => {
                             tracing::debug!("rpc channel closed, terminating");
@@ -213,7 +270,7 @@ impl Aggregator {
                         }
                     };
 
-                    false
+                    step
                 }
             };
 
@@ -243,6 +300,7 @@ impl Aggregator {
                 };
             }
             tracing::debug!(
+                aborts = counts.abort,
                 async_resource_ops = counts.async_resource_op,
                 metadatas = counts.metadata,
                 poll_ops = counts.poll_op,
@@ -298,6 +356,7 @@ impl Aggregator {
                 new_metadata: Some(proto::RegisterMetadata {
                     metadata: (*self.all_metadata).clone(),
                 }),
+                lag: None,
             };
             let message_size = update.encoded_len();
             if message_size < MAX_MESSAGE_SIZE {
@@ -337,7 +396,7 @@ impl Aggregator {
             ),
         }
 
-        self.watchers.push(subscription);
+        self.watchers.push(UpdateWatch::new(subscription));
     }
 
     fn task_update(&mut self, include: Include) -> proto::tasks::TaskUpdate {
@@ -366,38 +425,136 @@ impl Aggregator {
     }
 
     /// Add the task details subscription to the watchers after sending the first update,
-    /// if the task is found.
+    /// if any of the tasks are found.
+    ///
+    /// The updates for all of the tasks are sent on the same stream, so each
+    /// task's watchers hold a clone of the stream's sender.
     fn add_task_detail_subscription(
         &mut self,
         watch_request: WatchRequest<proto::tasks::TaskDetails>,
     ) {
         let WatchRequest {
-            id,
+            ids,
             stream_sender,
             buffer,
         } = watch_request;
-        tracing::debug!(id = ?id, "new task details subscription");
-        if let Some(stats) = self.task_stats.get(&id) {
-            let (tx, rx) = mpsc::channel(buffer);
-            let subscription = Watch(tx);
-            let now = Some(self.base_time.to_timestamp(Instant::now()));
-            // Send back the stream receiver.
-            // Then send the initial state --- if this fails, the subscription is already dead.
-            if stream_sender.send(rx).is_ok()
-                && subscription.update(&proto::tasks::TaskDetails {
-                    task_id: Some(id.clone().into()),
-                    now,
-                    poll_times_histogram: Some(stats.poll_duration_histogram()),
-                    scheduled_times_histogram: Some(stats.scheduled_duration_histogram()),
-                })
-            {
+        tracing::debug!(ids = ?ids, "new task details subscription");
+        let found = ids
+            .into_iter()
+            .filter_map(|id| Some((self.task_stats.get(&id)?, id)))
+            .collect::<Vec<_>>();
+        // If none of the tasks are found, drop `stream_sender` which will
+        // result in a not found error
+        if found.is_empty() {
+            return;
+        }
+
+        // Each publish sends an update for every task, so make room for all of
+        // them.
+        let (tx, rx) = mpsc::channel(buffer * found.len());
+        // Send back the stream receiver.
+        if stream_sender.send(rx).is_err() {
+            return;
+        }
+        let now = Some(self.base_time.to_timestamp(Instant::now()));
+        for (stats, id) in found {
+            let subscription = Watch(tx.clone());
+            // Send the initial state --- if this fails, the subscription is already dead.
+            if subscription.update(&proto::tasks::TaskDetails {
+                task_id: Some(id.clone().into()),
+                now,
+                poll_times_histogram: Some(stats.poll_duration_histogram()),
+                scheduled_times_histogram: Some(stats.scheduled_duration_histogram()),
+                recent_polls: stats.recent_polls(&self.base_time),
+            }) {
                 self.details_watchers
-                    .entry(id.clone())
+                    .entry(id)
                     .or_default()
                     .push(subscription);
             }
         }
-        // If the task is not found, drop `stream_sender` which will result in a not found error
+    }
+
+    /// Returns everything the aggregator knows about the task with `id`, if
+    /// it exists.
+    fn dump_task(&self, id: &Id) -> Option<proto::instrument::TaskDump> {
+        let task = self.tasks.get(id)?;
+        let stats = self.task_stats.get(id)?;
+        Some(proto::instrument::TaskDump {
+            now: Some(self.base_time.to_timestamp(Instant::now())),
+            task: Some(task.to_proto(&self.base_time)),
+            stats: Some(stats.to_proto(&self.base_time)),
+            // Tokio only dumps where tasks are suspended through a runtime's
+            // `Handle`, all at once and with `--cfg tokio_taskdump`, so the
+            // subscriber can't capture where one task is suspended.
+            backtrace: None,
+        })
+    }
+
+    /// Aborts a task which a client asked to abort, recording that it was
+    /// aborted by the client.
+    #[allow(clippy::result_large_err)]
+    fn abort_task(&self, id: &Id) -> Result<(), tonic::Status> {
+        let task = self
+            .tasks
+            .get(id)
+            .ok_or_else(|| tonic::Status::not_found("task not found"))?;
+        let aborted = task.tokio_id().is_some_and(crate::abort::abort_allowed);
+        if !aborted {
+            return Err(tonic::Status::failed_precondition(
+                "the task has completed, or wasn't allowed to be aborted with `console_subscriber::allow_abort`",
+            ));
+        }
+        if let Some(stats) = self.task_stats.get(id) {
+            // No task aborted it, and it wasn't aborted from anywhere in the
+            // program.
+            stats.record_abort(proto::tasks::Abort::default(), Instant::now());
+        }
+        Ok(())
+    }
+
+    /// Returns an update with everything the aggregator knows about, without
+    /// marking any of it as sent, for the final snapshot.
+    fn snapshot(&mut self) -> proto::instrument::Update {
+        proto::instrument::Update {
+            now: Some(self.base_time.to_timestamp(Instant::now())),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks: self.tasks.as_proto_list(Include::All, &self.base_time),
+                stats_update: self.task_stats.as_proto(Include::All, &self.base_time),
+                dropped_events: self.shared.dropped_tasks.load(Acquire) as u64,
+            }),
+            resource_update: Some(proto::resources::ResourceUpdate {
+                new_resources: self.resources.as_proto_list(Include::All, &self.base_time),
+                stats_update: self.resource_stats.as_proto(Include::All, &self.base_time),
+                new_poll_ops: self.poll_ops.clone(),
+                dropped_events: self.shared.dropped_resources.load(Acquire) as u64,
+            }),
+            async_op_update: Some(proto::async_ops::AsyncOpUpdate {
+                new_async_ops: self.async_ops.as_proto_list(Include::All, &self.base_time),
+                stats_update: self.async_op_stats.as_proto(Include::All, &self.base_time),
+                dropped_events: self.shared.dropped_async_ops.load(Acquire) as u64,
+            }),
+            new_metadata: Some(proto::RegisterMetadata {
+                metadata: (*self.all_metadata).clone(),
+            }),
+            lag: None,
+        }
+    }
+
+    /// Writes the final snapshot, if a path for it is configured.
+    fn write_final_snapshot(&mut self) -> std::io::Result<()> {
+        let Some(path) = self.final_snapshot_path.clone() else {
+            return Ok(());
+        };
+        // Include any events which haven't been aggregated yet, such as the
+        // spawn of a task which panicked as soon as it was polled.
+        while let Some(Some(event)) = recv_now_or_never(&mut self.events) {
+            self.update_state(event);
+        }
+        let snapshot = self.snapshot();
+        snapshot::write(&path, snapshot)?;
+        tracing::debug!(path = %path.display(), "wrote final snapshot");
+        Ok(())
     }
 
     /// Add a state subscription to the watchers.
@@ -409,6 +566,14 @@ impl Aggregator {
     fn publish_state(&mut self) {
         let state = proto::instrument::State {
             temporality: self.temporality.into(),
+            paused_at: self
+                .paused
+                .as_ref()
+                .map(|&(at, _)| self.base_time.to_timestamp(at)),
+            paused_by: self
+                .paused
+                .as_ref()
+                .and_then(|(_, paused_by)| paused_by.clone()),
         };
         self.state_watchers
             .retain_and_shrink(|watch| watch.update(&state));
@@ -416,8 +581,9 @@ impl Aggregator {
 
     /// Publish the current state to all active watchers.
     ///
-    /// This drops any watchers which have closed the RPC, or whose update
-    /// channel has filled up.
+    /// This drops any watchers which have closed the RPC, or which have fallen
+    /// too far behind (see [`UpdateWatch::publish`]). Any task details
+    /// watchers whose channel has filled up are dropped as well.
     fn publish(&mut self) {
         let new_metadata = if !self.new_metadata.is_empty() {
             Some(proto::RegisterMetadata {
@@ -436,10 +602,11 @@ impl Aggregator {
             task_update,
             resource_update,
             async_op_update,
+            lag: None,
         };
 
         self.watchers
-            .retain_and_shrink(|watch: &Watch<proto::instrument::Update>| watch.update(&update));
+            .retain_and_shrink(|watch| watch.publish(&update));
 
         let stats = &self.task_stats;
         // Assuming there are much fewer task details subscribers than there are
@@ -451,6 +618,7 @@ impl Aggregator {
                     now: Some(self.base_time.to_timestamp(Instant::now())),
                     poll_times_histogram: Some(task_stats.poll_duration_histogram()),
                     scheduled_times_histogram: Some(task_stats.scheduled_duration_histogram()),
+                    recent_polls: task_stats.recent_polls(&self.base_time),
                 };
                 watchers.retain(|watch| watch.update(&details));
                 !watchers.is_empty()
@@ -475,6 +643,7 @@ impl Aggregator {
                 stats,
                 fields,
                 location,
+                parent_id,
             } => {
                 self.tasks.insert(
                     id.clone(),
@@ -484,13 +653,39 @@ impl Aggregator {
                         metadata,
                         fields,
                         location,
-                        // TODO: parents
+                        parent_id,
                     },
                 );
 
                 self.task_stats.insert(id, stats);
             }
 
+            Event::Abort {
+                task_id,
+                aborted_by,
+                location,
+                at,
+            } => {
+                // Aborts are rare, so look the task up by its Tokio task ID
+                // rather than keeping an index of them.
+                let Some((id, _)) = self
+                    .tasks
+                    .all()
+                    .find(|(_, task)| task.has_tokio_id(task_id))
+                else {
+                    return;
+                };
+                if let Some(stats) = self.task_stats.get(id) {
+                    stats.record_abort(
+                        proto::tasks::Abort {
+                            aborted_by: aborted_by.map(Into::into),
+                            location,
+                        },
+                        at,
+                    );
+                }
+            }
+
             Event::Resource {
                 id,
                 parent_id,
@@ -549,6 +744,8 @@ impl Aggregator {
                 metadata,
                 parent_id,
                 stats,
+                location,
+                backtrace,
             } => {
                 self.async_ops.insert(
                     id.clone(),
@@ -559,6 +756,8 @@ impl Aggregator {
                         metadata,
                         source,
                         parent_id,
+                        location,
+                        backtrace,
                     },
                 );
 
@@ -580,6 +779,7 @@ fn recv_now_or_never<T>(receiver: &mut mpsc::Receiver<T>) -> Option<Option<T>> {
 
 /// Count of events received in each aggregator drain cycle.
 struct EventCounts {
+    abort: usize,
     async_resource_op: usize,
     metadata: usize,
     poll_op: usize,
@@ -590,6 +790,7 @@ struct EventCounts {
 impl EventCounts {
     fn new() -> Self {
         Self {
+            abort: 0,
             async_resource_op: 0,
             metadata: 0,
             poll_op: 0,
@@ -606,12 +807,29 @@ impl EventCounts {
             Event::PollOp { .. } => self.poll_op += 1,
             Event::Resource { .. } => self.resource += 1,
             Event::Spawn { .. } => self.spawn += 1,
+            Event::Abort { .. } => self.abort += 1,
         }
     }
 
     /// Total number of events recorded.
     fn total(&self) -> usize {
-        self.async_resource_op + self.metadata + self.poll_op + self.resource + self.spawn
+        self.abort
+            + self.async_resource_op
+            + self.metadata
+            + self.poll_op
+            + self.resource
+            + self.spawn
+    }
+}
+
+// The aggregator stops when the server is dropped, including when the runtime
+// it runs on shuts down, which is the last chance to write the final
+// snapshot.
+impl Drop for Aggregator {
+    fn drop(&mut self) {
+        if let Err(error) = self.write_final_snapshot() {
+            tracing::warn!(%error, "failed to write the final snapshot");
+        }
     }
 }
 
@@ -649,6 +867,93 @@ impl<T: Clone> Watch<T> {
     }
 }
 
+// ==== impl UpdateWatch ===
+
+impl UpdateWatch {
+    fn new(watch: Watch<proto::instrument::Update>) -> Self {
+        Self {
+            watch,
+            pending: None,
+            coalesced: 0,
+            total_coalesced: 0,
+        }
+    }
+
+    /// Sends `update` to the client, along with any earlier updates which
+    /// couldn't be sent to it yet.
+    ///
+    /// If the client's channel is full, the update is merged into the pending
+    /// updates instead, so that a client which falls behind doesn't stall the
+    /// aggregator or lose data. Returns `false` if the client has closed the
+    /// RPC, or if the pending updates have grown too large to send, in which
+    /// case the client should be dropped.
+    fn publish(&mut self, update: &proto::instrument::Update) -> bool {
+        let mut update = match self.pending.take() {
+            Some(mut pending) => {
+                coalesce(&mut pending, update.clone());
+                pending
+            }
+            None => update.clone(),
+        };
+        match self.watch.0.try_reserve() {
+            Ok(permit) => {
+                if self.total_coalesced > 0 {
+                    update.lag = Some(proto::instrument::ClientLag {
+                        coalesced_updates: self.coalesced,
+                        total_coalesced_updates: self.total_coalesced,
+                    });
+                }
+                permit.send(Ok(update));
+                self.coalesced = 0;
+                true
+            }
+            Err(mpsc::error::TrySendError::Full(())) => {
+                self.coalesced += 1;
+                self.total_coalesced += 1;
+                let message_size = update.encoded_len();
+                if message_size >= MAX_MESSAGE_SIZE {
+                    tracing::warn!(
+                        coalesced_updates = self.coalesced,
+                        message_size,
+                        max_message_size = MAX_MESSAGE_SIZE,
+                        "client has fallen too far behind, dropping it",
+                    );
+                    return false;
+                }
+                tracing::debug!(
+                    coalesced_updates = self.coalesced,
+                    "client is falling behind, coalescing updates",
+                );
+                self.pending = Some(update);
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(())) => false,
+        }
+    }
+}
+
+impl Task {
+    /// Returns `true` if this task's spawn span recorded `id` as its Tokio
+    /// task ID.
+    fn has_tokio_id(&self, id: u64) -> bool {
+        self.tokio_id() == Some(id)
+    }
+
+    /// Returns the Tokio task ID which this task's spawn span recorded, if
+    /// it recorded one.
+    fn tokio_id(&self) -> Option<u64> {
+        self.fields
+            .iter()
+            .find_map(|field| match (&field.name, &field.value) {
+                (
+                    Some(proto::field::Name::StrName(name)),
+                    Some(proto::field::Value::U64Val(value)),
+                ) if name == "task.id" => Some(*value),
+                _ => None,
+            })
+    }
+}
+
 impl ToProto for Task {
     type Output = proto::tasks::Task;
 
@@ -658,7 +963,8 @@ impl ToProto for Task {
             // TODO: more kinds of tasks...
             kind: proto::tasks::task::Kind::Spawn as i32,
             metadata: Some(self.metadata.into()),
-            parents: Vec::new(), // TODO: implement parents nicely
+            // TODO: only the nearest parent task is recorded for now.
+            parents: self.parent_id.iter().cloned().map(Into::into).collect(),
             fields: self.fields.clone(),
             location: self.location.clone(),
         }
@@ -711,6 +1017,8 @@ impl ToProto for AsyncOp {
             resource_id: Some(self.resource_id.clone().into()),
             source: self.source.clone(),
             parent_async_op_id: self.parent_id.clone().map(Into::into),
+            location: self.location.clone(),
+            backtrace: self.backtrace.clone(),
         }
     }
 }
@@ -724,3 +1032,148 @@ impl Unsent for AsyncOp {
         self.is_dirty.load(Acquire)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an update at `secs`, which spawns the task, resource and
+    /// async op `id`, records a poll op on the resource, and reports that
+    /// the task has been polled `polls` times.
+    fn update(secs: i64, id: u64, polls: u64) -> proto::instrument::Update {
+        let id = Some(proto::Id { id });
+        proto::instrument::Update {
+            now: Some(prost_types::Timestamp {
+                seconds: secs,
+                nanos: 0,
+            }),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks: vec![proto::tasks::Task {
+                    id,
+                    ..Default::default()
+                }],
+                stats_update: [(
+                    1,
+                    proto::tasks::Stats {
+                        poll_stats: Some(proto::PollStats {
+                            polls,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+                dropped_events: 1,
+            }),
+            resource_update: Some(proto::resources::ResourceUpdate {
+                new_resources: vec![proto::resources::Resource {
+                    id,
+                    ..Default::default()
+                }],
+                new_poll_ops: vec![proto::resources::PollOp {
+                    resource_id: id,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            async_op_update: Some(proto::async_ops::AsyncOpUpdate {
+                new_async_ops: vec![proto::async_ops::AsyncOp {
+                    id,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn polls(update: &proto::instrument::Update) -> u64 {
+        update.task_update.as_ref().unwrap().stats_update[&1]
+            .poll_stats
+            .as_ref()
+            .unwrap()
+            .polls
+    }
+
+    #[test]
+    fn coalesce_replaces_stats_and_appends_new_objects() {
+        let mut pending = update(1, 1, 1);
+        coalesce(&mut pending, update(2, 2, 5));
+
+        assert_eq!(pending.now.unwrap().seconds, 2);
+        assert_eq!(polls(&pending), 5);
+        let task_update = pending.task_update.as_ref().unwrap();
+        assert_eq!(task_update.new_tasks.len(), 2);
+        assert_eq!(task_update.dropped_events, 2);
+        let resource_update = pending.resource_update.as_ref().unwrap();
+        assert_eq!(resource_update.new_resources.len(), 2);
+        let poll_ops: Vec<_> = resource_update
+            .new_poll_ops
+            .iter()
+            .map(|op| op.resource_id.unwrap().id)
+            .collect();
+        assert_eq!(poll_ops, [1, 2]);
+        let async_op_update = pending.async_op_update.as_ref().unwrap();
+        assert_eq!(async_op_update.new_async_ops.len(), 2);
+    }
+
+    #[test]
+    fn publish_coalesces_while_the_client_is_behind() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut watch = UpdateWatch::new(Watch(tx));
+
+        assert!(watch.publish(&update(1, 1, 1)));
+        // The channel is full, so these are merged.
+        assert!(watch.publish(&update(2, 2, 2)));
+        assert!(watch.publish(&update(3, 3, 3)));
+
+        let first = rx.try_recv().unwrap().unwrap();
+        assert_eq!(first.lag, None);
+        assert!(watch.publish(&update(4, 4, 4)));
+        let coalesced = rx.try_recv().unwrap().unwrap();
+        assert_eq!(coalesced.now.unwrap().seconds, 4);
+        assert_eq!(polls(&coalesced), 4);
+        assert_eq!(coalesced.task_update.unwrap().new_tasks.len(), 3);
+        assert_eq!(
+            coalesced.lag,
+            Some(proto::instrument::ClientLag {
+                coalesced_updates: 2,
+                total_coalesced_updates: 2,
+            })
+        );
+
+        // The total is still reported once the client has caught up.
+        assert!(watch.publish(&update(5, 5, 5)));
+        let caught_up = rx.try_recv().unwrap().unwrap();
+        assert_eq!(
+            caught_up.lag,
+            Some(proto::instrument::ClientLag {
+                coalesced_updates: 0,
+                total_coalesced_updates: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn publish_drops_clients_which_fall_too_far_behind() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut watch = UpdateWatch::new(Watch(tx));
+        assert!(watch.publish(&update(1, 1, 1)));
+
+        let mut oversized = update(2, 2, 2);
+        oversized.task_update.as_mut().unwrap().new_tasks[0].fields = vec![proto::Field {
+            value: Some(proto::field::Value::StrVal("x".repeat(MAX_MESSAGE_SIZE))),
+            ..Default::default()
+        }];
+        assert!(!watch.publish(&oversized));
+    }
+
+    #[test]
+    fn publish_drops_closed_clients() {
+        let (tx, rx) = mpsc::channel(1);
+        let mut watch = UpdateWatch::new(Watch(tx));
+        drop(rx);
+        assert!(!watch.publish(&update(1, 1, 1)));
+    }
+}
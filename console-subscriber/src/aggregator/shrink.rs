@@ -102,10 +102,10 @@ impl<T> ShrinkVec<T> {
         self.shrink.try_shrink_vec(&mut self.vec)
     }
 
-    pub(crate) fn retain_and_shrink(&mut self, f: impl FnMut(&T) -> bool) {
+    pub(crate) fn retain_and_shrink(&mut self, f: impl FnMut(&mut T) -> bool) {
         let len0 = self.len();
 
-        self.retain(f);
+        self.retain_mut(f);
 
         if self.len() < len0 {
             tracing::debug!(
@@ -1,5 +1,7 @@
 use super::{ConsoleLayer, Server};
 #[cfg(unix)]
+use crate::peers::AllowedPeers;
+#[cfg(unix)]
 use std::path::Path;
 use std::{
     net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs},
@@ -23,8 +25,8 @@ pub struct Builder {
     /// the aggregator task.
     pub(super) event_buffer_capacity: usize,
 
-    /// The maximum number of updates to buffer per-client before the client is
-    /// dropped.
+    /// The maximum number of updates to buffer per-client before updates are
+    /// merged together.
     pub(super) client_buffer_capacity: usize,
 
     /// The interval between publishing updates to clients.
@@ -36,15 +38,30 @@ pub struct Builder {
     /// The address on which to serve the RPC server.
     pub(super) server_addr: ServerAddr,
 
+    /// The users and groups allowed to connect to a Unix domain socket.
+    #[cfg(unix)]
+    pub(super) allowed_peers: AllowedPeers,
+
+    /// Settings for the gRPC server, applied when it's started by
+    /// [`Server::serve`].
+    pub(super) server_settings: ServerSettings,
+
     /// If and where to save a recording of the events.
     pub(super) recording_path: Option<PathBuf>,
 
+    /// If and where to write a final snapshot of the state on panic or
+    /// shutdown.
+    pub(super) final_snapshot_path: Option<PathBuf>,
+
     /// The filter environment variable to use for `tracing` events.
     pub(super) filter_env_var: String,
 
     /// Whether to trace events coming from the subscriber thread
     self_trace: bool,
 
+    /// Whether to capture a backtrace of where each async op is created.
+    pub(super) async_op_backtraces: bool,
+
     /// The maximum value for the task poll duration histogram.
     ///
     /// Any polls exceeding this duration will be clamped to this value. Higher
@@ -72,9 +89,14 @@ impl Default for Builder {
             poll_duration_max: ConsoleLayer::DEFAULT_POLL_DURATION_MAX,
             scheduled_duration_max: ConsoleLayer::DEFAULT_SCHEDULED_DURATION_MAX,
             server_addr: ServerAddr::Tcp(SocketAddr::new(Server::DEFAULT_IP, Server::DEFAULT_PORT)),
+            #[cfg(unix)]
+            allowed_peers: AllowedPeers::default(),
+            server_settings: ServerSettings::default(),
             recording_path: None,
+            final_snapshot_path: None,
             filter_env_var: "RUST_LOG".to_string(),
             self_trace: false,
+            async_op_backtraces: false,
             #[cfg(feature = "grpc-web")]
             enable_grpc_web: false,
         }
@@ -98,7 +120,11 @@ impl Builder {
     /// Sets the maximum capacity of updates to buffer for each subscribed
     /// client, if that client is not reading from the RPC stream.
     ///
-    /// When this channel is at capacity, the client may be disconnected.
+    /// When this channel is at capacity, the updates which can't be sent to
+    /// the client are merged together into a single update, which is sent
+    /// once the client catches up. If the merged update grows too large to
+    /// send, the client is disconnected. How many updates a client has missed
+    /// is reported to it in each update's `lag`.
     ///
     /// By default, this is [`ConsoleLayer::DEFAULT_CLIENT_BUFFER_CAPACITY`].
     pub fn client_buffer_capacity(self, client_buffer_capacity: usize) -> Self {
@@ -190,6 +216,146 @@ impl Builder {
         }
     }
 
+    /// Allows the user with the ID `uid` to connect to the server when it
+    /// listens on a [Unix domain socket].
+    ///
+    /// By default, any user which is able to open the socket may connect to
+    /// it. Once any users or groups are allowed with this method or
+    /// [`Builder::allow_peer_gid`], connections from any other peer are
+    /// closed, as are connections whose peer credentials can't be read. This
+    /// has no effect when the server listens on a TCP address.
+    ///
+    /// This method may be called multiple times to allow several users.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use console_subscriber::Builder;
+    /// # #[cfg(unix)]
+    /// use std::path::Path;
+    ///
+    /// // Unix domain sockets are only available on Unix-compatible operating systems.
+    /// #[cfg(unix)]
+    /// let builder = Builder::default()
+    ///     .server_addr(Path::new("/tmp/tokio-console"))
+    ///     .allow_peer_uid(1000);
+    /// ```
+    ///
+    /// [Unix domain socket]: https://en.wikipedia.org/wiki/Unix_domain_socket
+    #[cfg(unix)]
+    pub fn allow_peer_uid(mut self, uid: u32) -> Self {
+        self.allowed_peers.uids.push(uid);
+        self
+    }
+
+    /// Allows users in the group with the ID `gid` to connect to the server
+    /// when it listens on a [Unix domain socket].
+    ///
+    /// The group is the primary group of the connecting process. See
+    /// [`Builder::allow_peer_uid`] for how allowed peers are checked.
+    ///
+    /// This method may be called multiple times to allow several groups.
+    ///
+    /// [Unix domain socket]: https://en.wikipedia.org/wiki/Unix_domain_socket
+    #[cfg(unix)]
+    pub fn allow_peer_gid(mut self, gid: u32) -> Self {
+        self.allowed_peers.gids.push(gid);
+        self
+    }
+
+    /// Limits how many requests each connection to the server may have in
+    /// flight at once.
+    ///
+    /// Each running `watch_*` stream is a request, so this limits how many
+    /// streams a single client can hold open. By default, there is no limit.
+    ///
+    /// This setting is used when the server is started by [`Server::serve`],
+    /// [`Builder::init`], or [`Builder::spawn`]. [`Server::serve_with`] uses
+    /// the settings of the [`tonic`] server it's passed instead.
+    ///
+    /// [`tonic`]: https://docs.rs/tonic/
+    pub fn concurrency_limit_per_connection(mut self, limit: usize) -> Self {
+        self.server_settings.concurrency_limit_per_connection = Some(limit);
+        self
+    }
+
+    /// Sets how long the server waits for a request's handler to respond
+    /// before failing the request.
+    ///
+    /// This bounds how long starting a stream or a call such as `pause` may
+    /// take, not how long a stream may run. By default, there is no timeout.
+    ///
+    /// See [`Builder::concurrency_limit_per_connection`] for when this
+    /// setting is used.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.server_settings.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long a connection to the server may be idle before the server
+    /// sends an HTTP/2 ping, to check that the client is still there.
+    ///
+    /// Connections whose clients don't answer within the
+    /// [`Builder::http2_keepalive_timeout`] are closed, which frees the
+    /// streams of clients which went away without closing their connection.
+    /// By default, no pings are sent.
+    ///
+    /// See [`Builder::concurrency_limit_per_connection`] for when this
+    /// setting is used.
+    pub fn http2_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.server_settings.http2_keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long the server waits for a client to answer an HTTP/2 ping
+    /// before closing its connection.
+    ///
+    /// This has no effect unless [`Builder::http2_keepalive_interval`] is
+    /// set. By default, this is 20 seconds.
+    ///
+    /// See [`Builder::concurrency_limit_per_connection`] for when this
+    /// setting is used.
+    pub fn http2_keepalive_timeout(mut self, timeout: Duration) -> Self {
+        self.server_settings.http2_keepalive_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how many streams a client may open at once on each HTTP/2
+    /// connection, by advertising the `SETTINGS_MAX_CONCURRENT_STREAMS`
+    /// setting.
+    ///
+    /// By default, there is no limit. See
+    /// [`Builder::concurrency_limit_per_connection`] for when this setting is
+    /// used.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.server_settings.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Sets whether `TCP_NODELAY` is set on connections accepted by the
+    /// server.
+    ///
+    /// By default, this is `false`. This has no effect when the server
+    /// listens on a Unix domain socket. See
+    /// [`Builder::concurrency_limit_per_connection`] for when this setting is
+    /// used.
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.server_settings.tcp_nodelay = enabled;
+        self
+    }
+
+    /// Sets how long a TCP connection to the server may be idle before TCP
+    /// keepalive probes are sent, or disables them if this is `None`.
+    ///
+    /// By default, keepalive probes are not sent. This has no effect when the
+    /// server listens on a Unix domain socket. See
+    /// [`Builder::concurrency_limit_per_connection`] for when this setting is
+    /// used.
+    pub fn tcp_keepalive(mut self, keepalive: Option<Duration>) -> Self {
+        self.server_settings.tcp_keepalive = keepalive;
+        self
+    }
+
     /// Sets the path to record the events to the file system.
     ///
     /// By default, this is initially `None`. Methods like
@@ -205,6 +371,47 @@ impl Builder {
         }
     }
 
+    /// Sets the path to write a final snapshot of the aggregator's state to
+    /// when the process panics or shuts down.
+    ///
+    /// The snapshot includes every task, resource and async op which hasn't
+    /// been dropped for exceeding the [retention period], along with their
+    /// stats. It's written as a single frame in the same format as the
+    /// framed transport, so it can be opened with `tokio-console
+    /// snapshot://localhost/path/to/snapshot`, which shows the warnings for
+    /// the tasks in it as well. This acts as a black-box recorder for crashes where no
+    /// console was connected.
+    ///
+    /// The snapshot is written when:
+    ///
+    /// * any thread panics, including tasks whose panics are caught by the
+    ///   runtime, so a later panic overwrites the snapshot of an earlier one.
+    ///   The panicking thread doesn't wait for the aggregator to write it, so
+    ///   it may not be written if the panic exits the process,
+    /// * the aggregator stops, such as when the [`Server`] is dropped or the
+    ///   runtime it runs on shuts down, or
+    /// * the application calls [`write_final_snapshot`], which it should do as
+    ///   it shuts down gracefully, since the aggregator's thread may just be
+    ///   stopped when the process exits.
+    ///
+    /// Only the first [`ConsoleLayer`] built with a final snapshot path
+    /// writes snapshots.
+    ///
+    /// By default, this is initially `None`. Methods like
+    /// [`init`][`crate::init`] and [`spawn`][`crate::spawn`] will take the
+    /// value from the `TOKIO_CONSOLE_FINAL_SNAPSHOT_PATH` [environment
+    /// variable] before falling back on that default.
+    ///
+    /// [retention period]: Builder::retention
+    /// [`write_final_snapshot`]: crate::write_final_snapshot
+    /// [environment variable]: `Builder::with_default_env`
+    pub fn final_snapshot_path(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            final_snapshot_path: Some(path.into()),
+            ..self
+        }
+    }
+
     /// Sets the environment variable used to configure which `tracing` events
     /// are logged to stdout.
     ///
@@ -274,6 +481,21 @@ impl Builder {
         Self { self_trace, ..self }
     }
 
+    /// Sets whether a backtrace is captured when each async op is created.
+    ///
+    /// The backtraces are sent to clients along with the location in the
+    /// program's code where each async op was created, which resources'
+    /// instrumentation doesn't otherwise record.
+    ///
+    /// By default, this is `false`, since capturing a backtrace for every
+    /// async op is expensive.
+    pub fn enable_async_op_backtraces(self, async_op_backtraces: bool) -> Self {
+        Self {
+            async_op_backtraces,
+            ..self
+        }
+    }
+
     /// Sets whether to enable the grpc-web support.
     ///
     /// By default, this is `false`. If enabled, the console subscriber will
@@ -303,26 +525,72 @@ impl Builder {
 
     /// Configures this builder from a standard set of environment variables:
     ///
-    /// | **Environment Variable**         | **Purpose**                                                  | **Default Value** |
-    /// |----------------------------------|--------------------------------------------------------------|-------------------|
-    /// | `TOKIO_CONSOLE_RETENTION`        | The duration of seconds to accumulate completed tracing data | 3600s (1h)        |
-    /// | `TOKIO_CONSOLE_BIND`             | a HOST:PORT description, such as `localhost:1234`            | `127.0.0.1:6669`  |
-    /// | `TOKIO_CONSOLE_PUBLISH_INTERVAL` | The duration to wait between sending updates to the console  | 1000ms (1s)       |
-    /// | `TOKIO_CONSOLE_RECORD_PATH`      | The file path to save a recording                            | None              |
+    /// | **Environment Variable**                     | **Purpose**                                                              | **Default Value** |
+    /// |----------------------------------------------|--------------------------------------------------------------------------|-------------------|
+    /// | `TOKIO_CONSOLE_RETENTION`                    | The duration of seconds to accumulate completed tracing data             | 3600s (1h)        |
+    /// | `TOKIO_CONSOLE_BIND`                         | a HOST:PORT description, such as `localhost:1234`                        | `127.0.0.1:6669`  |
+    /// | `TOKIO_CONSOLE_PUBLISH_INTERVAL`             | The duration to wait between sending updates to the console              | 1000ms (1s)       |
+    /// | `TOKIO_CONSOLE_RECORD_PATH`                  | The file path to save a recording                                        | None              |
+    /// | `TOKIO_CONSOLE_FINAL_SNAPSHOT_PATH`          | The file path to write a snapshot to on panic or shutdown                | None              |
+    /// | `TOKIO_CONSOLE_BUFFER_CAPACITY`              | The number of events to buffer before they're dropped                    | 102400            |
+    /// | `TOKIO_CONSOLE_CLIENT_BUFFER_CAPACITY`       | The number of updates to buffer per client before they're merged         | 4096              |
+    /// | `TOKIO_CONSOLE_POLL_DURATION_MAX`            | The maximum value of task poll duration histograms                       | 1s                |
+    /// | `TOKIO_CONSOLE_SCHEDULED_DURATION_MAX`       | The maximum value of task scheduled duration histograms                  | 1s                |
+    /// | `TOKIO_CONSOLE_FILTER_ENV_VAR`               | The environment variable to read `init`'s log filter from                | `RUST_LOG`        |
+    /// | `TOKIO_CONSOLE_SELF_TRACE`                   | Whether to record the console subscriber thread's own tasks              | false             |
+    /// | `TOKIO_CONSOLE_ASYNC_OP_BACKTRACES`          | Whether to capture a backtrace where each async op is created            | false             |
+    /// | `TOKIO_CONSOLE_ALLOW_PEER_UIDS`              | Comma-separated user IDs allowed to connect to a Unix domain socket      | Any user          |
+    /// | `TOKIO_CONSOLE_ALLOW_PEER_GIDS`              | Comma-separated group IDs allowed to connect to a Unix domain socket     | Any group         |
+    /// | `TOKIO_CONSOLE_CONCURRENCY_LIMIT`            | The number of requests each connection may have in flight                | No limit          |
+    /// | `TOKIO_CONSOLE_REQUEST_TIMEOUT`              | The duration to wait for a request's handler to respond                  | No timeout        |
+    /// | `TOKIO_CONSOLE_HTTP2_KEEPALIVE_INTERVAL`     | The duration a connection may be idle before it's pinged                 | No pings          |
+    /// | `TOKIO_CONSOLE_HTTP2_KEEPALIVE_TIMEOUT`      | The duration to wait for a ping to be answered                           | 20s               |
+    /// | `TOKIO_CONSOLE_MAX_CONCURRENT_STREAMS`       | The number of streams a client may open on each connection               | No limit          |
+    /// | `TOKIO_CONSOLE_TCP_NODELAY`                  | Whether to set `TCP_NODELAY` on accepted connections                     | false             |
+    /// | `TOKIO_CONSOLE_TCP_KEEPALIVE`                | The duration a TCP connection may be idle before keepalive probes        | No probes         |
+    /// | `TOKIO_CONSOLE_GRPC_WEB`                     | Whether to serve gRPC-Web, with the "grpc-web" feature flag              | false             |
+    ///
+    /// Durations are written like `30s` or `1h 30m`, and flags are `true` or
+    /// `false`. Each variable sets the builder option of the same name, such
+    /// as [`Builder::client_buffer_capacity`] for
+    /// `TOKIO_CONSOLE_CLIENT_BUFFER_CAPACITY`, whose documentation describes
+    /// it further. Variables which aren't set leave the builder's value
+    /// unchanged, and this panics if a variable is set to a value which can't
+    /// be parsed.
+    ///
+    /// On Unix platforms, `TOKIO_CONSOLE_BIND` may also be `systemd`, to serve
+    /// on the first socket passed by systemd socket activation, or
+    /// `systemd:NAME`, to serve on the socket named `NAME` by its unit's
+    /// `FileDescriptorName=`. See [`ServerAddr::Systemd`]. It may also be
+    /// `local`, to serve on a Unix domain socket which `tokio-console
+    /// --discover=local` finds. See [`ServerAddr::local`].
     pub fn with_default_env(mut self) -> Self {
         if let Some(retention) = duration_from_env("TOKIO_CONSOLE_RETENTION") {
             self.retention = retention;
         }
 
         if let Ok(bind) = std::env::var("TOKIO_CONSOLE_BIND") {
-            self.server_addr = ServerAddr::Tcp(
-                bind.to_socket_addrs()
-                    .expect(
-                        "TOKIO_CONSOLE_BIND must be formatted as HOST:PORT, such as localhost:4321",
-                    )
-                    .next()
-                    .expect("tokio console could not resolve TOKIO_CONSOLE_BIND"),
-            );
+            self.server_addr = match systemd_socket_name(&bind) {
+                #[cfg(unix)]
+                _ if bind == "local" => ServerAddr::local()
+                    .expect("tokio console could not create a local socket for TOKIO_CONSOLE_BIND"),
+                #[cfg(not(unix))]
+                _ if bind == "local" => {
+                    panic!("local sockets are only supported on Unix platforms")
+                }
+                #[cfg(unix)]
+                Some(name) => ServerAddr::Systemd(name),
+                #[cfg(not(unix))]
+                Some(_) => panic!("systemd sockets are only supported on Unix platforms"),
+                None => ServerAddr::Tcp(
+                    bind.to_socket_addrs()
+                        .expect(
+                            "TOKIO_CONSOLE_BIND must be formatted as HOST:PORT, such as localhost:4321",
+                        )
+                        .next()
+                        .expect("tokio console could not resolve TOKIO_CONSOLE_BIND"),
+                ),
+            };
         }
 
         if let Some(interval) = duration_from_env("TOKIO_CONSOLE_PUBLISH_INTERVAL") {
@@ -333,10 +601,82 @@ impl Builder {
             self.recording_path = Some(path.into());
         }
 
+        if let Ok(path) = std::env::var("TOKIO_CONSOLE_FINAL_SNAPSHOT_PATH") {
+            self.final_snapshot_path = Some(path.into());
+        }
+
         if let Some(capacity) = usize_from_env("TOKIO_CONSOLE_BUFFER_CAPACITY") {
             self.event_buffer_capacity = capacity;
         }
 
+        if let Some(capacity) = usize_from_env("TOKIO_CONSOLE_CLIENT_BUFFER_CAPACITY") {
+            self.client_buffer_capacity = capacity;
+        }
+
+        if let Some(max) = duration_from_env("TOKIO_CONSOLE_POLL_DURATION_MAX") {
+            self.poll_duration_max = max;
+        }
+
+        if let Some(max) = duration_from_env("TOKIO_CONSOLE_SCHEDULED_DURATION_MAX") {
+            self.scheduled_duration_max = max;
+        }
+
+        if let Ok(var) = std::env::var("TOKIO_CONSOLE_FILTER_ENV_VAR") {
+            self.filter_env_var = var;
+        }
+
+        if let Some(self_trace) = bool_from_env("TOKIO_CONSOLE_SELF_TRACE") {
+            self.self_trace = self_trace;
+        }
+
+        if let Some(backtraces) = bool_from_env("TOKIO_CONSOLE_ASYNC_OP_BACKTRACES") {
+            self.async_op_backtraces = backtraces;
+        }
+
+        #[cfg(unix)]
+        if let Some(uids) = ids_from_env("TOKIO_CONSOLE_ALLOW_PEER_UIDS") {
+            self.allowed_peers.uids.extend(uids);
+        }
+
+        #[cfg(unix)]
+        if let Some(gids) = ids_from_env("TOKIO_CONSOLE_ALLOW_PEER_GIDS") {
+            self.allowed_peers.gids.extend(gids);
+        }
+
+        let settings = &mut self.server_settings;
+        if let Some(limit) = usize_from_env("TOKIO_CONSOLE_CONCURRENCY_LIMIT") {
+            settings.concurrency_limit_per_connection = Some(limit);
+        }
+
+        if let Some(timeout) = duration_from_env("TOKIO_CONSOLE_REQUEST_TIMEOUT") {
+            settings.request_timeout = Some(timeout);
+        }
+
+        if let Some(interval) = duration_from_env("TOKIO_CONSOLE_HTTP2_KEEPALIVE_INTERVAL") {
+            settings.http2_keepalive_interval = Some(interval);
+        }
+
+        if let Some(timeout) = duration_from_env("TOKIO_CONSOLE_HTTP2_KEEPALIVE_TIMEOUT") {
+            settings.http2_keepalive_timeout = Some(timeout);
+        }
+
+        if let Some(max) = u32_from_env("TOKIO_CONSOLE_MAX_CONCURRENT_STREAMS") {
+            settings.max_concurrent_streams = Some(max);
+        }
+
+        if let Some(enabled) = bool_from_env("TOKIO_CONSOLE_TCP_NODELAY") {
+            settings.tcp_nodelay = enabled;
+        }
+
+        if let Some(keepalive) = duration_from_env("TOKIO_CONSOLE_TCP_KEEPALIVE") {
+            settings.tcp_keepalive = Some(keepalive);
+        }
+
+        #[cfg(feature = "grpc-web")]
+        if let Some(enabled) = bool_from_env("TOKIO_CONSOLE_GRPC_WEB") {
+            self.enable_grpc_web = enabled;
+        }
+
         self
     }
 
@@ -380,6 +720,7 @@ impl Builder {
     /// | `TOKIO_CONSOLE_BIND`                | A HOST:PORT description, such as `localhost:1234`                         | `127.0.0.1:6669`  |
     /// | `TOKIO_CONSOLE_PUBLISH_INTERVAL`    | The number of milliseconds to wait between sending updates to the console | 1000ms (1s)       |
     /// | `TOKIO_CONSOLE_RECORD_PATH`         | The file path to save a recording                                         | None              |
+    /// | `TOKIO_CONSOLE_FINAL_SNAPSHOT_PATH` | The file path to write a snapshot to on panic or shutdown                 | None              |
     /// | `RUST_LOG`                          | Configures what events are logged events. See [`Targets`] for details.    | "error"           |
     ///
     /// If the "env-filter" crate feature flag is enabled, the `RUST_LOG`
@@ -515,6 +856,8 @@ impl Builder {
         let self_trace = self.self_trace;
         #[cfg(feature = "grpc-web")]
         let enable_grpc_web = self.enable_grpc_web;
+        #[cfg(feature = "grpc-web")]
+        let server_settings = self.server_settings.clone();
 
         let (layer, server) = self.build();
         let filter =
@@ -539,7 +882,7 @@ impl Builder {
                     #[cfg(feature = "grpc-web")]
                     if enable_grpc_web {
                         server
-                            .serve_with_grpc_web(tonic::transport::Server::builder())
+                            .serve_with_grpc_web(server_settings.server())
                             .await
                             .expect("console subscriber server failed");
                         return;
@@ -560,9 +903,10 @@ impl Builder {
 /// Specifies the address on which a [`Server`] should listen.
 ///
 /// This type is passed as an argument to the [`Builder::server_addr`]
-/// method, and may be either a TCP socket address, or a [Unix domain socket]
-/// (UDS) address. Unix domain sockets are only supported on Unix-compatible
-/// operating systems, such as Linux, BSDs, and macOS.
+/// method, and may be either a TCP socket address, a [Unix domain socket]
+/// (UDS) address, or a socket passed by systemd. Unix domain sockets and
+/// systemd sockets are only supported on Unix-compatible operating systems,
+/// such as Linux, BSDs, and macOS.
 ///
 /// [`Server`]: crate::Server
 /// [Unix domain socket]: https://en.wikipedia.org/wiki/Unix_domain_socket
@@ -574,6 +918,17 @@ pub enum ServerAddr {
     /// A Unix socket address.
     #[cfg(unix)]
     Unix(PathBuf),
+    /// A listening socket passed to the process by [systemd socket
+    /// activation], which may be a TCP or Unix domain socket.
+    ///
+    /// This is the name of the socket, set with `FileDescriptorName=` in its
+    /// socket unit, or `None` to use the first socket which was passed. The
+    /// socket unit controls the socket's permissions, and systemd keeps it
+    /// open while the process is restarted.
+    ///
+    /// [systemd socket activation]: https://www.freedesktop.org/software/systemd/man/systemd.socket.html
+    #[cfg(unix)]
+    Systemd(Option<String>),
 }
 
 impl From<SocketAddr> for ServerAddr {
@@ -603,6 +958,46 @@ where
     }
 }
 
+impl ServerAddr {
+    /// Returns a Unix domain socket address in the directory which
+    /// `tokio-console --discover=local` searches, so that the console can
+    /// list this process without being told where it's listening.
+    ///
+    /// The socket is `tokio-console/NAME-PID.sock` in `$XDG_RUNTIME_DIR`, or
+    /// in the temporary directory if that isn't set, where `NAME` is the
+    /// file name of the process's executable and `PID` is its process ID.
+    /// This creates the directory if it doesn't exist, and removes a socket
+    /// left at the same path by an earlier process which had the same ID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use console_subscriber::{Builder, ServerAddr};
+    /// // Unix domain sockets are only available on Unix-compatible operating systems.
+    /// #[cfg(unix)]
+    /// let builder = Builder::default().server_addr(ServerAddr::local()?);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn local() -> std::io::Result<Self> {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("tokio-console");
+        std::fs::create_dir_all(&dir)?;
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_name()?.to_str().map(str::to_owned))
+            .unwrap_or_else(|| "process".to_owned());
+        let path = dir.join(format!("{name}-{}.sock", std::process::id()));
+        match std::fs::remove_file(&path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+        Ok(ServerAddr::Unix(path))
+    }
+}
+
 #[cfg(unix)]
 impl From<PathBuf> for ServerAddr {
     fn from(path: PathBuf) -> ServerAddr {
@@ -617,6 +1012,41 @@ impl<'a> From<&'a Path> for ServerAddr {
     }
 }
 
+/// Settings for the [`tonic`] server which serves the instrument service.
+///
+/// [`tonic`]: https://docs.rs/tonic/
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ServerSettings {
+    pub(crate) concurrency_limit_per_connection: Option<usize>,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) http2_keepalive_interval: Option<Duration>,
+    pub(crate) http2_keepalive_timeout: Option<Duration>,
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) tcp_nodelay: bool,
+    pub(crate) tcp_keepalive: Option<Duration>,
+}
+
+impl ServerSettings {
+    /// Returns a [`tonic`] server builder with these settings.
+    ///
+    /// [`tonic`]: https://docs.rs/tonic/
+    pub(crate) fn server(&self) -> tonic::transport::Server {
+        let mut server = tonic::transport::Server::builder()
+            .http2_keepalive_interval(self.http2_keepalive_interval)
+            .http2_keepalive_timeout(self.http2_keepalive_timeout)
+            .max_concurrent_streams(self.max_concurrent_streams)
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(limit) = self.concurrency_limit_per_connection {
+            server = server.concurrency_limit_per_connection(limit);
+        }
+        if let Some(timeout) = self.request_timeout {
+            server = server.timeout(timeout);
+        }
+        server
+    }
+}
+
 /// Initializes the console [tracing `Subscriber`][sub] and starts the console
 /// subscriber [`Server`] on its own background thread.
 ///
@@ -655,8 +1085,13 @@ impl<'a> From<&'a Path> for ServerAddr {
 /// | `TOKIO_CONSOLE_BIND`                | A HOST:PORT description, such as `localhost:1234`                         | `127.0.0.1:6669`  |
 /// | `TOKIO_CONSOLE_PUBLISH_INTERVAL`    | The number of milliseconds to wait between sending updates to the console | 1000ms (1s)       |
 /// | `TOKIO_CONSOLE_RECORD_PATH`         | The file path to save a recording                                         | None              |
+/// | `TOKIO_CONSOLE_FINAL_SNAPSHOT_PATH` | The file path to write a snapshot to on panic or shutdown                 | None              |
 /// | `RUST_LOG`                          | Configures what events are logged events. See [`Targets`] for details.    | "error"           |
 ///
+/// The other environment variables read by [`Builder::with_default_env`],
+/// such as those sizing the subscriber's buffers and tuning its server, are
+/// also used.
+///
 /// If the "env-filter" crate feature flag is enabled, the `RUST_LOG`
 /// environment variable will be parsed using the [`EnvFilter`] type from
 /// `tracing-subscriber`. If the "env-filter" feature is **not** enabled, the
@@ -759,6 +1194,15 @@ where
     ConsoleLayer::builder().with_default_env().spawn::<S>()
 }
 
+/// Parses a `TOKIO_CONSOLE_BIND` of `systemd`, for the first socket passed
+/// by systemd, or `systemd:NAME`, for the socket with that name.
+fn systemd_socket_name(bind: &str) -> Option<Option<String>> {
+    match bind.strip_prefix("systemd")? {
+        "" => Some(None),
+        name => Some(Some(name.strip_prefix(':')?.to_owned())),
+    }
+}
+
 fn duration_from_env(var_name: &str) -> Option<Duration> {
     let var = std::env::var(var_name).ok()?;
     match var.parse::<humantime::Duration>() {
@@ -770,6 +1214,43 @@ fn duration_from_env(var_name: &str) -> Option<Duration> {
     }
 }
 
+fn u32_from_env(var_name: &str) -> Option<u32> {
+    let var = std::env::var(var_name).ok()?;
+    match var.parse::<u32>() {
+        Ok(num) => Some(num),
+        Err(e) => panic!("failed to parse a u32 from `{}={:?}`: {}", var_name, var, e),
+    }
+}
+
+fn bool_from_env(var_name: &str) -> Option<bool> {
+    let var = std::env::var(var_name).ok()?;
+    match var.parse::<bool>() {
+        Ok(flag) => Some(flag),
+        Err(e) => panic!(
+            "failed to parse a bool from `{}={:?}`: {}",
+            var_name, var, e
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn ids_from_env(var_name: &str) -> Option<Vec<u32>> {
+    let var = std::env::var(var_name).ok()?;
+    let ids = var
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<u32>())
+        .collect::<Result<Vec<_>, _>>();
+    match ids {
+        Ok(ids) => Some(ids),
+        Err(e) => panic!(
+            "failed to parse a list of IDs from `{}={:?}`: {}",
+            var_name, var, e
+        ),
+    }
+}
+
 fn usize_from_env(var_name: &str) -> Option<usize> {
     let var = std::env::var(var_name).ok()?;
     match var.parse::<usize>() {
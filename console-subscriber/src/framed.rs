@@ -0,0 +1,165 @@
+//! The framed transport, which streams updates to clients as length-prefixed
+//! protobuf messages over a plain socket.
+//!
+//! See [`Server::serve_framed`](crate::Server::serve_framed) for details.
+use crate::{Command, Watch};
+use console_api as proto;
+use prost::Message;
+use proto::instrument::{frame, Frame};
+use std::io;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+};
+
+/// A client's subscription to the updates and state which are sent to it as
+/// frames.
+pub(crate) struct Frames {
+    updates: mpsc::Receiver<Result<proto::instrument::Update, tonic::Status>>,
+    states: mpsc::Receiver<Result<proto::instrument::State, tonic::Status>>,
+}
+
+impl Frames {
+    /// Subscribes to the aggregator's updates and state.
+    ///
+    /// Returns `None` if the aggregator isn't running.
+    pub(crate) async fn subscribe(
+        subscribe: &mpsc::Sender<Command>,
+        buffer: usize,
+    ) -> Option<Self> {
+        let (updates_tx, updates) = mpsc::channel(buffer);
+        let (states_tx, states) = mpsc::channel(buffer);
+        let subscribed = async {
+            subscribe
+                .send(Command::Instrument(Watch(updates_tx)))
+                .await?;
+            subscribe.send(Command::WatchState(Watch(states_tx))).await
+        };
+        if subscribed.await.is_err() {
+            tracing::debug!("cannot start new watch, aggregation task is not running");
+            return None;
+        }
+        Some(Self { updates, states })
+    }
+
+    /// Returns the next encoded frame, or `None` once the aggregator stops
+    /// sending the client updates.
+    pub(crate) async fn next(&mut self) -> Option<Vec<u8>> {
+        // Each stream ends if the aggregator drops its watcher, such as when
+        // the client falls too far behind, so stop sending the other stream
+        // as well.
+        let message = tokio::select! {
+            update = self.updates.recv() => frame::Message::Update(update?.ok()?),
+            state = self.states.recv() => frame::Message::State(state?.ok()?),
+        };
+        Some(
+            Frame {
+                message: Some(message),
+            }
+            .encode_to_vec(),
+        )
+    }
+}
+
+/// Streams frames to a client on `socket`, each prefixed by its length, until
+/// it disconnects or the aggregator stops sending it updates.
+pub(crate) async fn send_frames(
+    mut socket: impl AsyncWrite + Unpin,
+    subscribe: mpsc::Sender<Command>,
+    buffer: usize,
+) -> io::Result<()> {
+    let Some(mut frames) = Frames::subscribe(&subscribe, buffer).await else {
+        return Ok(());
+    };
+    while let Some(frame) = frames.next().await {
+        let len = u32::try_from(frame.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "frame is too large"))?;
+        socket.write_all(&len.to_be_bytes()).await?;
+        socket.write_all(&frame).await?;
+    }
+    Ok(())
+}
+
+/// Streams frames to a client which connects to `socket` with a WebSocket,
+/// each as a binary message, until it disconnects or the aggregator stops
+/// sending it updates.
+#[cfg(feature = "websocket")]
+pub(crate) async fn send_websocket_frames(
+    socket: impl tokio::io::AsyncRead + AsyncWrite + Unpin,
+    subscribe: mpsc::Sender<Command>,
+    buffer: usize,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut websocket = tokio_tungstenite::accept_async(socket).await?;
+    let Some(mut frames) = Frames::subscribe(&subscribe, buffer).await else {
+        return websocket.close(None).await;
+    };
+    loop {
+        tokio::select! {
+            frame = frames.next() => match frame {
+                Some(frame) => websocket.send(Message::Binary(frame)).await?,
+                None => return websocket.close(None).await,
+            },
+            // The client doesn't send anything but control messages, but they
+            // have to be read for pings to be answered and closes to be seen.
+            message = websocket.next() => match message {
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// Reads the next length-prefixed frame, or `None` if the stream ended.
+    async fn read_frame(io: &mut (impl tokio::io::AsyncRead + Unpin)) -> Option<Frame> {
+        let mut len = [0; 4];
+        io.read_exact(&mut len).await.ok()?;
+        let mut frame = vec![0; u32::from_be_bytes(len) as usize];
+        io.read_exact(&mut frame).await.unwrap();
+        Some(Frame::decode(&frame[..]).unwrap())
+    }
+
+    #[tokio::test]
+    async fn frames_are_length_prefixed() {
+        let (subscribe, mut commands) = mpsc::channel(2);
+        let (socket, mut client) = tokio::io::duplex(1024);
+        let send = tokio::spawn(send_frames(socket, subscribe, 4));
+
+        let Some(Command::Instrument(Watch(updates))) = commands.recv().await else {
+            panic!("the client should watch updates");
+        };
+        let Some(Command::WatchState(Watch(states))) = commands.recv().await else {
+            panic!("the client should watch the state");
+        };
+
+        let update = proto::instrument::Update {
+            now: Some(std::time::SystemTime::UNIX_EPOCH.into()),
+            ..Default::default()
+        };
+        updates.send(Ok(update.clone())).await.unwrap();
+        let frame = read_frame(&mut client).await.unwrap();
+        assert_eq!(frame.message, Some(frame::Message::Update(update)));
+
+        let state = proto::instrument::State {
+            temporality: proto::instrument::Temporality::Paused.into(),
+            ..Default::default()
+        };
+        states.send(Ok(state.clone())).await.unwrap();
+        let frame = read_frame(&mut client).await.unwrap();
+        assert_eq!(frame.message, Some(frame::Message::State(state)));
+
+        // The client is disconnected once the aggregator stops sending it
+        // updates.
+        drop((updates, states));
+        assert!(read_frame(&mut client).await.is_none());
+        send.await.unwrap().unwrap();
+    }
+}
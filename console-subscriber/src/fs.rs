@@ -0,0 +1,138 @@
+//! Instrumented filesystem operations.
+//!
+//! Tokio runs [`tokio::fs`] operations on its blocking pool, so they only
+//! show up in the console as blocking tasks, without the path they operate
+//! on. The functions in this module run the same operations, recording each
+//! as an `fs` resource with the path as an attribute, and awaiting it as an
+//! async op on that resource. The console shows which task is waiting on
+//! each operation, the path, and how long the operation has taken, so stalls
+//! on a slow disk can be traced to the task which issued them.
+//!
+//! This module requires the "fs" crate feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn docs() -> std::io::Result<()> {
+//! // Reads the file, recording the read and its path.
+//! let config = console_subscriber::fs::read_to_string("config.toml").await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::trace;
+use std::{fs::Metadata, future::Future, io, panic::Location, path::Path};
+
+/// Reads the entire contents of a file into a bytes vector.
+///
+/// This is recorded as an `fs::read` async op.
+///
+/// See [`tokio::fs::read`].
+#[track_caller]
+pub fn read(path: impl AsRef<Path>) -> impl Future<Output = io::Result<Vec<u8>>> {
+    let span = OpSpans::new("fs::read", path.as_ref());
+    span.run("poll_read", tokio::fs::read(path))
+}
+
+/// Reads the entire contents of a file into a string.
+///
+/// This is recorded as an `fs::read_to_string` async op.
+///
+/// See [`tokio::fs::read_to_string`].
+#[track_caller]
+pub fn read_to_string(path: impl AsRef<Path>) -> impl Future<Output = io::Result<String>> {
+    let span = OpSpans::new("fs::read_to_string", path.as_ref());
+    span.run("poll_read_to_string", tokio::fs::read_to_string(path))
+}
+
+/// Writes a slice as the entire contents of a file.
+///
+/// This is recorded as an `fs::write` async op.
+///
+/// See [`tokio::fs::write`].
+#[track_caller]
+pub fn write(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> impl Future<Output = io::Result<()>> {
+    let span = OpSpans::new("fs::write", path.as_ref());
+    span.run("poll_write", tokio::fs::write(path, contents))
+}
+
+/// Queries the file system metadata of a path.
+///
+/// This is recorded as an `fs::metadata` async op.
+///
+/// See [`tokio::fs::metadata`].
+#[track_caller]
+pub fn metadata(path: impl AsRef<Path>) -> impl Future<Output = io::Result<Metadata>> {
+    let span = OpSpans::new("fs::metadata", path.as_ref());
+    span.run("poll_metadata", tokio::fs::metadata(path))
+}
+
+/// Recursively creates a directory and all of its missing parents.
+///
+/// This is recorded as an `fs::create_dir_all` async op.
+///
+/// See [`tokio::fs::create_dir_all`].
+#[track_caller]
+pub fn create_dir_all(path: impl AsRef<Path>) -> impl Future<Output = io::Result<()>> {
+    let span = OpSpans::new("fs::create_dir_all", path.as_ref());
+    span.run("poll_create_dir_all", tokio::fs::create_dir_all(path))
+}
+
+/// Removes a file from the filesystem.
+///
+/// This is recorded as an `fs::remove_file` async op.
+///
+/// See [`tokio::fs::remove_file`].
+#[track_caller]
+pub fn remove_file(path: impl AsRef<Path>) -> impl Future<Output = io::Result<()>> {
+    let span = OpSpans::new("fs::remove_file", path.as_ref());
+    span.run("poll_remove_file", tokio::fs::remove_file(path))
+}
+
+/// Removes a directory, after removing all of its contents.
+///
+/// This is recorded as an `fs::remove_dir_all` async op.
+///
+/// See [`tokio::fs::remove_dir_all`].
+#[track_caller]
+pub fn remove_dir_all(path: impl AsRef<Path>) -> impl Future<Output = io::Result<()>> {
+    let span = OpSpans::new("fs::remove_dir_all", path.as_ref());
+    span.run("poll_remove_dir_all", tokio::fs::remove_dir_all(path))
+}
+
+/// The spans of a filesystem operation: a resource for the operation, and
+/// the async op which awaits it.
+struct OpSpans {
+    resource: tracing::Span,
+    async_op: tracing::Span,
+}
+
+impl OpSpans {
+    /// Records the operation `name` on `path`, at the location of the
+    /// caller.
+    #[track_caller]
+    fn new(name: &'static str, path: &Path) -> Self {
+        let path = path.display().to_string();
+        let resource = trace::resource_span(name, "fs", Location::caller());
+        resource.in_scope(|| {
+            tracing::trace!(
+                target: "runtime::resource::state_update",
+                path = path.as_str(),
+            );
+        });
+        let async_op = trace::async_op_span(&resource, name);
+        async_op.in_scope(|| {
+            tracing::trace!(
+                target: "runtime::resource::async_op::state_update",
+                path = path.as_str(),
+            );
+        });
+        Self { resource, async_op }
+    }
+
+    async fn run<F: Future>(self, poll_op_name: &'static str, future: F) -> F::Output {
+        trace::in_async_op(&self.resource, &self.async_op, poll_op_name, future).await
+    }
+}
@@ -3,6 +3,7 @@ use console_api as proto;
 use proto::{instrument::instrument_server::InstrumentServer, resources::resource};
 use serde::Serialize;
 use std::{
+    backtrace::Backtrace,
     cell::RefCell,
     fmt,
     net::{IpAddr, Ipv4Addr},
@@ -17,7 +18,7 @@ use thread_local::ThreadLocal;
 use tokio::net::UnixListener;
 use tokio::sync::{mpsc, oneshot};
 #[cfg(unix)]
-use tokio_stream::wrappers::UnixListenerStream;
+use tokio_stream::{wrappers::UnixListenerStream, StreamExt};
 use tracing_core::{
     span::{self, Id},
     subscriber::{self, Subscriber},
@@ -29,16 +30,41 @@ use tracing_subscriber::{
     Layer,
 };
 
+mod abort;
 mod aggregator;
 mod attribute;
 mod builder;
 mod callsites;
+#[cfg(feature = "framed")]
+mod framed;
+#[cfg(feature = "fs")]
+pub mod fs;
+#[cfg(feature = "net")]
+pub mod net;
+mod panics;
+#[cfg(unix)]
+mod peers;
+#[cfg(feature = "process")]
+pub mod process;
 mod record;
+#[cfg(feature = "signal")]
+pub mod signal;
+mod snapshot;
 mod stack;
 mod stats;
 pub(crate) mod sync;
+#[cfg(unix)]
+mod systemd;
+#[cfg(any(
+    feature = "fs",
+    feature = "net",
+    feature = "process",
+    feature = "signal"
+))]
+mod trace;
 mod visitors;
 
+pub use abort::{abort, abort_handle, allow_abort, allow_abort_handle};
 pub use aggregator::Aggregator;
 pub use builder::{Builder, ServerAddr};
 use callsites::Callsites;
@@ -47,8 +73,9 @@ use stack::SpanStack;
 use visitors::{AsyncOpVisitor, ResourceVisitor, ResourceVisitorResult, TaskVisitor, WakerVisitor};
 
 pub use builder::{init, spawn};
+pub use snapshot::write_final_snapshot;
 
-use crate::visitors::{PollOpVisitor, StateUpdateVisitor};
+use crate::visitors::{AbortVisitor, PollOpVisitor, StateUpdateVisitor};
 
 /// A [`ConsoleLayer`] is a [`tracing_subscriber::Layer`] that records [`tracing`]
 /// spans and events emitted by the async runtime.
@@ -82,6 +109,12 @@ pub struct ConsoleLayer {
     /// they might all have their own sets of waker ops.
     waker_callsites: Callsites<16>,
 
+    /// Set of callsites for events recording which task aborted another.
+    ///
+    /// These are only emitted by [`abort`] and [`abort_handle`], so there
+    /// are very few of them.
+    abort_callsites: Callsites<8>,
+
     /// Set of callsites for spans representing resources
     ///
     /// TODO: Take some time to determine more reasonable numbers
@@ -128,6 +161,9 @@ pub struct ConsoleLayer {
     ///
     /// By default, this is one second.
     max_scheduled_duration_nanos: u64,
+
+    /// Whether to capture a backtrace of where each async op is created.
+    async_op_backtraces: bool,
 }
 
 /// A gRPC [`Server`] that implements the [`tokio-console` wire format][wire].
@@ -144,8 +180,13 @@ pub struct ConsoleLayer {
 pub struct Server {
     subscribe: mpsc::Sender<Command>,
     addr: ServerAddr,
+    #[cfg(unix)]
+    allowed_peers: peers::AllowedPeers,
+    settings: builder::ServerSettings,
     aggregator: Option<Aggregator>,
     client_buffer: usize,
+    publish_interval: Duration,
+    retention: Duration,
 }
 
 pub(crate) trait ToProto {
@@ -179,12 +220,30 @@ enum Command {
     Instrument(Watch<proto::instrument::Update>),
     WatchTaskDetail(WatchRequest<proto::tasks::TaskDetails>),
     WatchState(Watch<proto::instrument::State>),
-    Pause,
+    Pause {
+        /// A description of the client that requested the pause, if known.
+        paused_by: Option<String>,
+    },
     Resume,
+    Step,
+    /// Dump everything known about a task, if it exists.
+    DumpTask {
+        id: Id,
+        dump: oneshot::Sender<proto::instrument::TaskDump>,
+    },
+    /// Abort a task, if it was allowed to be aborted.
+    AbortTask {
+        id: Id,
+        aborted: oneshot::Sender<Result<(), tonic::Status>>,
+    },
+    /// Write the final snapshot, and report whether it was written, if the
+    /// caller waits for it.
+    Snapshot(Option<std::sync::mpsc::SyncSender<std::io::Result<()>>>),
 }
 
 struct WatchRequest<T> {
-    id: Id,
+    /// The tasks to watch, whose updates are all sent on the same stream.
+    ids: Vec<Id>,
     stream_sender: oneshot::Sender<mpsc::Receiver<Result<T, tonic::Status>>>,
     buffer: usize,
 }
@@ -198,6 +257,23 @@ enum Event {
         stats: Arc<stats::TaskStats>,
         fields: Vec<proto::Field>,
         location: Option<proto::Location>,
+        /// The task whose span was entered when this task was spawned, if
+        /// any.
+        ///
+        /// Blocking tasks such as `tokio::fs` operations are spawned from
+        /// inside the task that awaits them, so this lets clients attach
+        /// them to the task that issued them.
+        parent_id: Option<span::Id>,
+    },
+    /// A task was aborted through [`abort`] or [`abort_handle`].
+    Abort {
+        /// The Tokio task ID of the aborted task, rather than its span ID,
+        /// since that's all its `JoinHandle` knows.
+        task_id: u64,
+        /// The task whose span was entered when the task was aborted, if any.
+        aborted_by: Option<span::Id>,
+        location: Option<proto::Location>,
+        at: Instant,
     },
     Resource {
         id: span::Id,
@@ -223,6 +299,8 @@ enum Event {
         resource_id: span::Id,
         metadata: &'static Metadata<'static>,
         source: String,
+        location: Option<proto::Location>,
+        backtrace: Option<String>,
 
         stats: Arc<stats::AsyncOpStats>,
     },
@@ -273,6 +351,7 @@ impl ConsoleLayer {
             ?config.retention,
             ?config.server_addr,
             ?config.recording_path,
+            ?config.final_snapshot_path,
             ?config.filter_env_var,
             ?config.poll_duration_max,
             ?config.scheduled_duration_max,
@@ -284,6 +363,12 @@ impl ConsoleLayer {
         let (subscribe, rpcs) = mpsc::channel(256);
         let shared = Arc::new(Shared::default());
         let aggregator = Aggregator::new(events, rpcs, &config, shared.clone(), base_time.clone());
+        // The snapshot's hook is installed after, so that it runs after the
+        // panicking task has been recorded.
+        panics::install();
+        if config.final_snapshot_path.is_some() {
+            snapshot::install(&subscribe);
+        }
         // Conservatively, start to trigger a flush when half the channel is full.
         // This tries to reduce the chance of losing events to a full channel.
         let flush_under_capacity = config.event_buffer_capacity / 2;
@@ -294,8 +379,13 @@ impl ConsoleLayer {
         let server = Server {
             aggregator: Some(aggregator),
             addr: config.server_addr,
+            #[cfg(unix)]
+            allowed_peers: config.allowed_peers,
+            settings: config.server_settings,
             subscribe,
             client_buffer: config.client_buffer_capacity,
+            publish_interval: config.publish_interval,
+            retention: config.retention,
         };
         let layer = Self {
             current_spans: ThreadLocal::new(),
@@ -304,6 +394,7 @@ impl ConsoleLayer {
             flush_under_capacity,
             spawn_callsites: Callsites::default(),
             waker_callsites: Callsites::default(),
+            abort_callsites: Callsites::default(),
             resource_callsites: Callsites::default(),
             async_op_callsites: Callsites::default(),
             async_op_poll_callsites: Callsites::default(),
@@ -314,6 +405,7 @@ impl ConsoleLayer {
             base_time,
             max_poll_duration_nanos: config.poll_duration_max.as_nanos() as u64,
             max_scheduled_duration_nanos: config.scheduled_duration_max.as_nanos() as u64,
+            async_op_backtraces: config.async_op_backtraces,
         };
         (layer, server)
     }
@@ -332,8 +424,9 @@ impl ConsoleLayer {
     /// Default maximum capacity for th echannel of events sent from a
     /// [`Server`] to each subscribed client.
     ///
-    /// When this capacity is exhausted, the client is assumed to be inactive,
-    /// and may be disconnected.
+    /// When this capacity is exhausted, the updates which can't be sent to the
+    /// client are merged together until it catches up, and the client is
+    /// disconnected if they grow too large to send.
     ///
     /// See also [`Builder::client_buffer_capacity`].
     pub const DEFAULT_CLIENT_BUFFER_CAPACITY: usize = 1024 * 4;
@@ -540,6 +633,10 @@ where
                 self.waker_callsites.insert(meta);
                 &self.shared.dropped_tasks
             }
+            (_, AbortVisitor::ABORT_EVENT_TARGET) => {
+                self.abort_callsites.insert(meta);
+                &self.shared.dropped_tasks
+            }
             (ResourceVisitor::RES_SPAN_NAME, _) => {
                 self.resource_callsites.insert(meta);
                 &self.shared.dropped_resources
@@ -578,6 +675,9 @@ where
             let mut task_visitor = TaskVisitor::new(metadata.into());
             attrs.record(&mut task_visitor);
             let (fields, location) = task_visitor.result();
+            let parent_id = self.current_spans.get().and_then(|stack| {
+                self.first_entered(&stack.borrow(), |id| self.is_id_spawned(id, &ctx))
+            });
             self.record(|| record::Event::Spawn {
                 id: id.into_u64(),
                 at: self.base_time.to_system_time(at),
@@ -595,6 +695,7 @@ where
                     metadata,
                     fields,
                     location,
+                    parent_id,
                 };
                 (event, stats)
             }) {
@@ -656,6 +757,10 @@ where
                 });
 
                 if let Some(resource_id) = resource_id {
+                    let backtrace = self
+                        .async_op_backtraces
+                        .then(|| Backtrace::force_capture().to_string());
+                    let location = backtrace.as_deref().and_then(creation_location);
                     if let Some(stats) =
                         self.send_stats(&self.shared.dropped_async_ops, move || {
                             let stats = Arc::new(stats::AsyncOpStats::new(
@@ -669,6 +774,8 @@ where
                                 resource_id,
                                 metadata,
                                 source,
+                                location,
+                                backtrace,
                                 stats: stats.clone(),
                             };
                             (event, stats)
@@ -716,6 +823,27 @@ where
             return;
         }
 
+        if self.abort_callsites.contains(metadata) {
+            let at = Instant::now();
+            let mut visitor = AbortVisitor::default();
+            event.record(&mut visitor);
+            if let Some((task_id, location)) = visitor.result() {
+                let aborted_by = self.current_spans.get().and_then(|stack| {
+                    self.first_entered(&stack.borrow(), |id| self.is_id_spawned(id, &ctx))
+                });
+                self.send_stats(&self.shared.dropped_tasks, || {
+                    let event = Event::Abort {
+                        task_id,
+                        aborted_by,
+                        location,
+                        at,
+                    };
+                    (event, ())
+                });
+            }
+            return;
+        }
+
         if self.poll_op_callsites.contains(metadata) {
             let resource_id = self.current_spans.get().and_then(|stack| {
                 self.first_entered(&stack.borrow(), |id| self.is_id_resource(id, &ctx))
@@ -878,7 +1006,114 @@ impl fmt::Debug for ConsoleLayer {
     }
 }
 
+/// Accepts connections from `$listener` forever, serving the framed transport
+/// to each connection for which `$allowed` is `true`.
+#[cfg(feature = "framed")]
+macro_rules! accept_frames {
+    ($server:ident, $send:path, $listener:expr, |$socket:ident| $allowed:expr) => {{
+        let listener = $listener;
+        loop {
+            let ($socket, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    // Errors such as running out of file descriptors are
+                    // usually transient, so they only fail the connection
+                    // being accepted, not the server.
+                    tracing::warn!(%error, "failed to accept a framed connection");
+                    continue;
+                }
+            };
+            if !$allowed {
+                continue;
+            }
+            tracing::debug!("starting a new framed watch");
+            let send = $send($socket, $server.subscribe.clone(), $server.client_buffer);
+            spawn_named(
+                async move {
+                    if let Err(error) = send.await {
+                        tracing::debug!(%error, "framed watch ended");
+                    }
+                },
+                "console::serve_framed",
+            );
+        }
+    }};
+}
+
+/// Serves one of the framed transports on `$server`'s address, spawning the
+/// aggregator and calling `$send` to stream frames to each client.
+#[cfg(feature = "framed")]
+macro_rules! serve_frames {
+    ($server:ident, $send:path) => {{
+        let aggregator = $server
+            .aggregator
+            .take()
+            .expect("cannot start server multiple times");
+        let aggregate = spawn_named(aggregator.run(), "console::aggregate");
+
+        // The server only stops if its listener can't be bound.
+        let res: std::io::Result<std::convert::Infallible> = async {
+            match $server.addr {
+                ServerAddr::Tcp(addr) => {
+                    accept_frames!(
+                        $server,
+                        $send,
+                        tokio::net::TcpListener::bind(addr).await?,
+                        |socket| true
+                    )
+                }
+                #[cfg(unix)]
+                ServerAddr::Unix(ref path) => {
+                    accept_frames!($server, $send, UnixListener::bind(path)?, |socket| {
+                        $server.allowed_peers.allows(&socket)
+                    })
+                }
+                #[cfg(unix)]
+                ServerAddr::Systemd(ref name) => match systemd::listener(name.as_deref())? {
+                    systemd::Listener::Tcp(listener) => {
+                        accept_frames!(
+                            $server,
+                            $send,
+                            tokio::net::TcpListener::from_std(listener)?,
+                            |socket| true
+                        )
+                    }
+                    systemd::Listener::Unix(listener) => {
+                        accept_frames!(
+                            $server,
+                            $send,
+                            UnixListener::from_std(listener)?,
+                            |socket| $server.allowed_peers.allows(&socket)
+                        )
+                    }
+                },
+            }
+        }
+        .await;
+        aggregate.abort();
+        match res {
+            Ok(never) => match never {},
+            Err(error) => Err(error.into()),
+        }
+    }};
+}
+
 impl Server {
+    /// The RPCs which are reported in [`proto::instrument::ServerInfo`].
+    const CAPABILITIES: &'static [&'static str] = &[
+        "watch_updates",
+        "watch_task_details",
+        "watch_tasks_details",
+        "watch_state",
+        "pause",
+        "resume",
+        "step",
+        "get_server_info",
+        "dump_task",
+        #[cfg(tokio_unstable)]
+        "abort_task",
+    ];
+
     // XXX(eliza): why is `SocketAddr::new` not `const`???
     /// A [`Server`] by default binds socket address 127.0.0.1 to service remote
     /// procedure calls.
@@ -906,10 +1141,12 @@ impl Server {
     /// [environment variable]: `Builder::with_default_env`
     pub const DEFAULT_PORT: u16 = 6669;
 
-    /// Starts the gRPC service with the default gRPC settings.
+    /// Starts the gRPC service with the gRPC settings configured on the
+    /// [`Builder`].
     ///
-    /// To configure gRPC server settings before starting the server, use
-    /// [`serve_with`] instead. This method is equivalent to calling [`serve_with`]
+    /// The builder's settings include [`Builder::concurrency_limit_per_connection`],
+    /// [`Builder::request_timeout`], and [`Builder::tcp_keepalive`], among
+    /// others. If none are set, this is equivalent to calling [`serve_with`]
     /// and providing the default gRPC server settings:
     ///
     /// ```rust
@@ -918,9 +1155,14 @@ impl Server {
     /// server.serve_with(tonic::transport::Server::default()).await
     /// # }
     /// ```
+    ///
+    /// To configure gRPC server settings which the builder doesn't have, use
+    /// [`serve_with`] instead.
+    ///
     /// [`serve_with`]: Server::serve_with
     pub async fn serve(self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-        self.serve_with(tonic::transport::Server::default()).await
+        let builder = self.settings.server();
+        self.serve_with(builder).await
     }
 
     /// Starts the gRPC service with the given [`tonic`] gRPC transport server
@@ -938,6 +1180,10 @@ impl Server {
         mut builder: tonic::transport::Server,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
         let addr = self.addr.clone();
+        #[cfg(unix)]
+        let allowed_peers = self.allowed_peers.clone();
+        #[cfg(unix)]
+        let settings = self.settings.clone();
         let ServerParts {
             instrument_server,
             aggregator,
@@ -952,9 +1198,30 @@ impl Server {
             #[cfg(unix)]
             ServerAddr::Unix(path) => {
                 let incoming = UnixListener::bind(path)?;
-                let serve = router.serve_with_incoming(UnixListenerStream::new(incoming));
+                let incoming = UnixListenerStream::new(incoming)
+                    .filter(move |accepted| allowed_peers.allows_accepted(accepted));
+                let serve = router.serve_with_incoming(incoming);
                 spawn_named(serve, "console::serve").await
             }
+            #[cfg(unix)]
+            ServerAddr::Systemd(name) => match systemd::listener(name.as_deref())? {
+                systemd::Listener::Tcp(listener) => {
+                    let listener = tokio::net::TcpListener::from_std(listener)?;
+                    let incoming = tonic::transport::server::TcpIncoming::from_listener(
+                        listener,
+                        settings.tcp_nodelay,
+                        settings.tcp_keepalive,
+                    )?;
+                    let serve = router.serve_with_incoming(incoming);
+                    spawn_named(serve, "console::serve").await
+                }
+                systemd::Listener::Unix(listener) => {
+                    let incoming = UnixListenerStream::new(UnixListener::from_std(listener)?)
+                        .filter(move |accepted| allowed_peers.allows_accepted(accepted));
+                    let serve = router.serve_with_incoming(incoming);
+                    spawn_named(serve, "console::serve").await
+                }
+            },
         };
         aggregate.abort();
         res?.map_err(Into::into)
@@ -1063,6 +1330,10 @@ impl Server {
         builder: tonic::transport::Server,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
         let addr = self.addr.clone();
+        #[cfg(unix)]
+        let allowed_peers = self.allowed_peers.clone();
+        #[cfg(unix)]
+        let settings = self.settings.clone();
         let ServerParts {
             instrument_server,
             aggregator,
@@ -1079,14 +1350,150 @@ impl Server {
             #[cfg(unix)]
             ServerAddr::Unix(path) => {
                 let incoming = UnixListener::bind(path)?;
-                let serve = router.serve_with_incoming(UnixListenerStream::new(incoming));
+                let incoming = UnixListenerStream::new(incoming)
+                    .filter(move |accepted| allowed_peers.allows_accepted(accepted));
+                let serve = router.serve_with_incoming(incoming);
                 spawn_named(serve, "console::serve").await
             }
+            #[cfg(unix)]
+            ServerAddr::Systemd(name) => match systemd::listener(name.as_deref())? {
+                systemd::Listener::Tcp(listener) => {
+                    let listener = tokio::net::TcpListener::from_std(listener)?;
+                    let incoming = tonic::transport::server::TcpIncoming::from_listener(
+                        listener,
+                        settings.tcp_nodelay,
+                        settings.tcp_keepalive,
+                    )?;
+                    let serve = router.serve_with_incoming(incoming);
+                    spawn_named(serve, "console::serve").await
+                }
+                systemd::Listener::Unix(listener) => {
+                    let incoming = UnixListenerStream::new(UnixListener::from_std(listener)?)
+                        .filter(move |accepted| allowed_peers.allows_accepted(accepted));
+                    let serve = router.serve_with_incoming(incoming);
+                    spawn_named(serve, "console::serve").await
+                }
+            },
         };
         aggregate.abort();
         res?.map_err(Into::into)
     }
 
+    /// Starts the framed transport, which streams updates to clients without
+    /// gRPC.
+    ///
+    /// Each client which connects to the [server address] is sent a stream of
+    /// [`Frame`]s, each prefixed by its encoded length as a 4-byte big-endian
+    /// integer. The frames carry the same updates and aggregator state as the
+    /// gRPC [`Server::serve`] would stream to the client, but the framed
+    /// transport doesn't accept any requests, so clients can't pause the
+    /// aggregator or watch a task's details.
+    ///
+    /// The console connects to the framed transport with a `framed://` target
+    /// address, or `framed+file://` for a Unix domain socket.
+    ///
+    /// This spawns both the server task and the event aggregation worker
+    /// task on the current async runtime.
+    ///
+    /// This requires the "framed" crate feature. It doesn't remove
+    /// console-subscriber's dependency on [`tonic`], which the gRPC server
+    /// is always built with, but only lets a target avoid running that
+    /// server.
+    ///
+    /// [server address]: crate::Builder::server_addr
+    /// [`Frame`]: proto::instrument::Frame
+    /// [`tonic`]: https://docs.rs/tonic/
+    #[cfg(feature = "framed")]
+    pub async fn serve_framed(
+        mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        serve_frames!(self, framed::send_frames)
+    }
+
+    /// Starts the framed transport over WebSockets, so that clients can
+    /// connect through proxies which only pass HTTP/1.1 through.
+    ///
+    /// This is the same as [`Server::serve_framed`], except that clients
+    /// connect to the [server address] with a WebSocket, and each [`Frame`] is
+    /// sent as a binary message rather than being prefixed by its length.
+    /// The WebSocket is served without TLS, which a proxy in front of the
+    /// server may add.
+    ///
+    /// The console connects to it with a `ws://` or `wss://` target address.
+    ///
+    /// [server address]: crate::Builder::server_addr
+    /// [`Frame`]: proto::instrument::Frame
+    #[cfg(feature = "websocket")]
+    pub async fn serve_websocket(
+        mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        serve_frames!(self, framed::send_websocket_frames)
+    }
+
+    /// Starts the framed transport over QUIC, which survives changes to the
+    /// client's address and lossy links better than a TCP connection.
+    ///
+    /// This is the same as [`Server::serve_framed`], except that it listens
+    /// for QUIC connections on the UDP port of the [server address], and
+    /// sends the frames on a unidirectional stream which it opens to each
+    /// client. The `config` provides the server's TLS certificate, which QUIC
+    /// requires.
+    ///
+    /// This doesn't serve gRPC over HTTP/3. Like the other framed transports,
+    /// it only streams updates, so clients can't pause the aggregator or
+    /// watch a task's details over it.
+    ///
+    /// The console connects to it with a `quic://` target address.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the server address is a Unix domain socket.
+    ///
+    /// [server address]: crate::Builder::server_addr
+    #[cfg(feature = "quic")]
+    pub async fn serve_quic(
+        mut self,
+        config: quinn::ServerConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let addr = match self.addr {
+            ServerAddr::Tcp(addr) => addr,
+            #[cfg(unix)]
+            ServerAddr::Unix(_) => {
+                return Err("QUIC can't be served on a Unix domain socket".into());
+            }
+            #[cfg(unix)]
+            ServerAddr::Systemd(_) => {
+                return Err("QUIC can't be served on a socket passed by systemd".into());
+            }
+        };
+        let endpoint = quinn::Endpoint::server(config, addr)?;
+        let aggregator = self
+            .aggregator
+            .take()
+            .expect("cannot start server multiple times");
+        let aggregate = spawn_named(aggregator.run(), "console::aggregate");
+
+        while let Some(incoming) = endpoint.accept().await {
+            tracing::debug!(client.addr = %incoming.remote_address(), "starting a new framed watch");
+            let subscribe = self.subscribe.clone();
+            let buffer = self.client_buffer;
+            spawn_named(
+                async move {
+                    let send = async {
+                        let stream = incoming.await?.open_uni().await?;
+                        framed::send_frames(stream, subscribe, buffer).await
+                    };
+                    if let Err(error) = send.await {
+                        tracing::debug!(%error, "framed watch ended");
+                    }
+                },
+                "console::serve_quic",
+            );
+        }
+        aggregate.abort();
+        Ok(())
+    }
+
     /// Returns the parts needed to spawn a gRPC server and the aggregator that
     /// supplies it.
     ///
@@ -1191,6 +1598,8 @@ impl proto::instrument::instrument_server::Instrument for Server {
         tokio_stream::wrappers::ReceiverStream<Result<proto::instrument::Update, tonic::Status>>;
     type WatchTaskDetailsStream =
         tokio_stream::wrappers::ReceiverStream<Result<proto::tasks::TaskDetails, tonic::Status>>;
+    type WatchTasksDetailsStream =
+        tokio_stream::wrappers::ReceiverStream<Result<proto::tasks::TaskDetails, tonic::Status>>;
     type WatchStateStream =
         tokio_stream::wrappers::ReceiverStream<Result<proto::instrument::State, tonic::Status>>;
     async fn watch_updates(
@@ -1233,7 +1642,7 @@ impl proto::instrument::instrument_server::Instrument for Server {
         // Check with the aggregator task to request a stream if the task exists.
         let (stream_sender, stream_recv) = oneshot::channel();
         permit.send(Command::WatchTaskDetail(WatchRequest {
-            id,
+            ids: vec![id],
             stream_sender,
             buffer: self.client_buffer,
         }));
@@ -1248,6 +1657,45 @@ impl proto::instrument::instrument_server::Instrument for Server {
         Ok(tonic::Response::new(stream))
     }
 
+    async fn watch_tasks_details(
+        &self,
+        req: tonic::Request<proto::instrument::TasksDetailsRequest>,
+    ) -> Result<tonic::Response<Self::WatchTasksDetailsStream>, tonic::Status> {
+        let ids = req
+            .into_inner()
+            .ids
+            .into_iter()
+            // `tracing` reserves span ID 0 for niche optimization for `Option<Id>`.
+            .map(|id| std::num::NonZeroU64::new(id.id).map(Id::from_non_zero_u64))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| tonic::Status::invalid_argument("task_id cannot be 0"))?;
+        if ids.is_empty() {
+            return Err(tonic::Status::invalid_argument("missing task_ids"));
+        }
+
+        let permit = self.subscribe.reserve().await.map_err(|_| {
+            tonic::Status::internal("cannot start new watch, aggregation task is not running")
+        })?;
+
+        // Check with the aggregator task to request a stream if any of the
+        // tasks exist.
+        let (stream_sender, stream_recv) = oneshot::channel();
+        permit.send(Command::WatchTaskDetail(WatchRequest {
+            ids,
+            stream_sender,
+            buffer: self.client_buffer,
+        }));
+        // If the aggregator drops the sender, none of the tasks exist.
+        let rx = stream_recv.await.map_err(|_| {
+            tracing::warn!("none of the requested tasks were found");
+            tonic::Status::not_found("tasks not found")
+        })?;
+
+        tracing::debug!("tasks details watch started");
+        let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+        Ok(tonic::Response::new(stream))
+    }
+
     async fn watch_state(
         &self,
         _req: tonic::Request<proto::instrument::StateRequest>,
@@ -1265,11 +1713,22 @@ impl proto::instrument::instrument_server::Instrument for Server {
 
     async fn pause(
         &self,
-        _req: tonic::Request<proto::instrument::PauseRequest>,
+        req: tonic::Request<proto::instrument::PauseRequest>,
     ) -> Result<tonic::Response<proto::instrument::PauseResponse>, tonic::Status> {
-        self.subscribe.send(Command::Pause).await.map_err(|_| {
-            tonic::Status::internal("cannot pause, aggregation task is not running")
-        })?;
+        let remote_addr = req.remote_addr();
+        let client = req.into_inner().client.filter(|client| !client.is_empty());
+        let paused_by = match (client, remote_addr) {
+            (Some(client), Some(addr)) => Some(format!("{client} ({addr})")),
+            (Some(client), None) => Some(client),
+            (None, Some(addr)) => Some(addr.to_string()),
+            (None, None) => None,
+        };
+        self.subscribe
+            .send(Command::Pause { paused_by })
+            .await
+            .map_err(|_| {
+                tonic::Status::internal("cannot pause, aggregation task is not running")
+            })?;
         Ok(tonic::Response::new(proto::instrument::PauseResponse {}))
     }
 
@@ -1282,6 +1741,90 @@ impl proto::instrument::instrument_server::Instrument for Server {
         })?;
         Ok(tonic::Response::new(proto::instrument::ResumeResponse {}))
     }
+
+    async fn get_server_info(
+        &self,
+        _req: tonic::Request<proto::instrument::ServerInfoRequest>,
+    ) -> Result<tonic::Response<proto::instrument::ServerInfo>, tonic::Status> {
+        Ok(tonic::Response::new(proto::instrument::ServerInfo {
+            api_version: proto::VERSION.to_string(),
+            capabilities: Self::CAPABILITIES.iter().map(|&c| c.to_owned()).collect(),
+            retention: Some(self.retention.try_into().unwrap_or_default()),
+            publish_interval: Some(self.publish_interval.try_into().unwrap_or_default()),
+        }))
+    }
+
+    async fn step(
+        &self,
+        _req: tonic::Request<proto::instrument::StepRequest>,
+    ) -> Result<tonic::Response<proto::instrument::StepResponse>, tonic::Status> {
+        self.subscribe
+            .send(Command::Step)
+            .await
+            .map_err(|_| tonic::Status::internal("cannot step, aggregation task is not running"))?;
+        Ok(tonic::Response::new(proto::instrument::StepResponse {}))
+    }
+
+    async fn dump_task(
+        &self,
+        req: tonic::Request<proto::instrument::DumpTaskRequest>,
+    ) -> Result<tonic::Response<proto::instrument::TaskDump>, tonic::Status> {
+        let task_id = req
+            .into_inner()
+            .id
+            .ok_or_else(|| tonic::Status::invalid_argument("missing task_id"))?
+            .id;
+        // `tracing` reserves span ID 0 for niche optimization for `Option<Id>`.
+        let id = std::num::NonZeroU64::new(task_id)
+            .map(Id::from_non_zero_u64)
+            .ok_or_else(|| tonic::Status::invalid_argument("task_id cannot be 0"))?;
+
+        let (dump, dumped) = oneshot::channel();
+        self.subscribe
+            .send(Command::DumpTask { id, dump })
+            .await
+            .map_err(|_| {
+                tonic::Status::internal("cannot dump task, aggregation task is not running")
+            })?;
+        // If the aggregator drops the sender, the task doesn't exist.
+        let dump = dumped
+            .await
+            .map_err(|_| tonic::Status::not_found("task not found"))?;
+        Ok(tonic::Response::new(dump))
+    }
+
+    async fn abort_task(
+        &self,
+        req: tonic::Request<proto::instrument::AbortTaskRequest>,
+    ) -> Result<tonic::Response<proto::instrument::AbortTaskResponse>, tonic::Status> {
+        // Tasks are only aborted by their Tokio task IDs, which are unstable.
+        if !cfg!(tokio_unstable) {
+            return Err(tonic::Status::unimplemented(
+                "aborting tasks requires building with `--cfg tokio_unstable`",
+            ));
+        }
+        let task_id = req
+            .into_inner()
+            .id
+            .ok_or_else(|| tonic::Status::invalid_argument("missing task_id"))?
+            .id;
+        // `tracing` reserves span ID 0 for niche optimization for `Option<Id>`.
+        let id = std::num::NonZeroU64::new(task_id)
+            .map(Id::from_non_zero_u64)
+            .ok_or_else(|| tonic::Status::invalid_argument("task_id cannot be 0"))?;
+
+        let (aborted, abort) = oneshot::channel();
+        let not_running =
+            || tonic::Status::internal("cannot abort task, aggregation task is not running");
+        self.subscribe
+            .send(Command::AbortTask { id, aborted })
+            .await
+            .map_err(|_| not_running())?;
+        abort.await.map_err(|_| not_running())??;
+        Ok(tonic::Response::new(
+            proto::instrument::AbortTaskResponse {},
+        ))
+    }
 }
 
 impl WakeOp {
@@ -1299,6 +1842,36 @@ impl WakeOp {
     }
 }
 
+/// Finds where an async op was created in the instrumented program's own
+/// code, from a backtrace captured when the async op was created.
+///
+/// The first frame from outside of this crate, the standard library and any
+/// dependencies is assumed to be where the instrumented program created the
+/// async op.
+fn creation_location(backtrace: &str) -> Option<proto::Location> {
+    const DEPENDENCY_PATHS: &[&str] = &["/rustc/", "/.cargo/registry/", "/.cargo/git/"];
+    let this_crate = env!("CARGO_MANIFEST_DIR").replace('\\', "/");
+    backtrace
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("at "))
+        .map(|path| path.replace('\\', "/"))
+        .filter(|path| {
+            !path.starts_with(&this_crate) && !DEPENDENCY_PATHS.iter().any(|dep| path.contains(dep))
+        })
+        .find_map(|path| {
+            let mut parts = path.rsplitn(3, ':');
+            let column = parts.next()?.parse().ok()?;
+            let line = parts.next()?.parse().ok()?;
+            let file = parts.next()?.to_owned();
+            Some(proto::Location {
+                file: Some(file),
+                module_path: None,
+                line: Some(line),
+                column: Some(column),
+            })
+        })
+}
+
 #[track_caller]
 pub(crate) fn spawn_named<T>(
     task: impl std::future::Future<Output = T> + Send + 'static,
@@ -0,0 +1,282 @@
+//! Instrumented network sockets.
+//!
+//! Tokio does not emit resource instrumentation for its [`tokio::net`]
+//! types. The wrappers in this module record TCP listeners, TCP streams, and
+//! UDP sockets as `net` resources, with their local and peer addresses as
+//! attributes. Streams accepted by an instrumented [`TcpListener`] are
+//! recorded as children of the listener, and accepting a connection is
+//! recorded as an async op, so the console can show which task runs each
+//! accept loop and how many connections each listener has open.
+//!
+//! This module requires the "net" crate feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn docs() -> std::io::Result<()> {
+//! use console_subscriber::net::TcpListener;
+//!
+//! let listener = TcpListener::new(tokio::net::TcpListener::bind("127.0.0.1:8080").await?);
+//! loop {
+//!     let (stream, _peer) = listener.accept().await?;
+//!     tokio::spawn(async move {
+//!         // ...
+//!         # drop(stream);
+//!     });
+//! }
+//! # }
+//! ```
+use crate::trace;
+use std::{
+    io,
+    net::SocketAddr,
+    ops::Deref,
+    panic::Location,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// A [`tokio::net::TcpListener`] which is recorded as a resource by the
+/// console.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct TcpListener {
+    inner: tokio::net::TcpListener,
+    resource_span: tracing::Span,
+    location: &'static Location<'static>,
+}
+
+/// A [`tokio::net::TcpStream`] which is recorded as a resource by the
+/// console.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct TcpStream {
+    inner: tokio::net::TcpStream,
+    _resource_span: tracing::Span,
+}
+
+/// A [`tokio::net::UdpSocket`] which is recorded as a resource by the
+/// console.
+///
+/// This dereferences to the wrapped socket, so all of its methods are
+/// available. Sending and receiving are not recorded as async ops.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct UdpSocket {
+    inner: tokio::net::UdpSocket,
+    _resource_span: tracing::Span,
+}
+
+// === impl TcpListener ===
+
+impl TcpListener {
+    /// Wraps `listener` so that it is recorded as a resource.
+    ///
+    /// The location of the caller is recorded as the resource's location.
+    #[track_caller]
+    pub fn new(listener: tokio::net::TcpListener) -> Self {
+        let location = Location::caller();
+        let resource_span = trace::resource_span("TcpListener", "net", location);
+        resource_span.in_scope(|| {
+            record_local_addr(listener.local_addr());
+            tracing::trace!(
+                target: "runtime::resource::state_update",
+                accepted = 0u64,
+                accepted.op = "add",
+            );
+        });
+        Self {
+            inner: listener,
+            resource_span,
+            location,
+        }
+    }
+
+    /// Accepts a new incoming connection.
+    ///
+    /// This is recorded as a `TcpListener::accept` async op on the listener's
+    /// resource, and the returned stream is recorded as a child of the
+    /// listener, with the same location as the listener.
+    ///
+    /// See [`tokio::net::TcpListener::accept`].
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = trace::async_op(
+            &self.resource_span,
+            "TcpListener::accept",
+            "poll_accept",
+            self.inner.accept(),
+        )
+        .await?;
+        let stream = self.resource_span.in_scope(|| {
+            tracing::trace!(
+                target: "runtime::resource::state_update",
+                accepted = 1u64,
+                accepted.op = "add",
+            );
+            TcpStream::new_at(stream, self.location)
+        });
+        Ok((stream, addr))
+    }
+
+    /// Returns the local address that this listener is bound to.
+    ///
+    /// See [`tokio::net::TcpListener::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns a reference to the wrapped [`tokio::net::TcpListener`].
+    pub fn get_ref(&self) -> &tokio::net::TcpListener {
+        &self.inner
+    }
+
+    /// Consumes this `TcpListener`, returning the wrapped
+    /// [`tokio::net::TcpListener`].
+    pub fn into_inner(self) -> tokio::net::TcpListener {
+        self.inner
+    }
+}
+
+// === impl TcpStream ===
+
+impl TcpStream {
+    /// Wraps `stream` so that it is recorded as a resource.
+    ///
+    /// The location of the caller is recorded as the resource's location.
+    #[track_caller]
+    pub fn new(stream: tokio::net::TcpStream) -> Self {
+        Self::new_at(stream, Location::caller())
+    }
+
+    fn new_at(stream: tokio::net::TcpStream, location: &'static Location<'static>) -> Self {
+        let resource_span = trace::resource_span("TcpStream", "net", location);
+        resource_span.in_scope(|| {
+            record_local_addr(stream.local_addr());
+            record_peer_addr(stream.peer_addr());
+        });
+        Self {
+            inner: stream,
+            _resource_span: resource_span,
+        }
+    }
+
+    /// Returns a reference to the wrapped [`tokio::net::TcpStream`].
+    pub fn get_ref(&self) -> &tokio::net::TcpStream {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped [`tokio::net::TcpStream`].
+    pub fn get_mut(&mut self) -> &mut tokio::net::TcpStream {
+        &mut self.inner
+    }
+
+    /// Consumes this `TcpStream`, returning the wrapped
+    /// [`tokio::net::TcpStream`].
+    ///
+    /// The stream will no longer be recorded by the console.
+    pub fn into_inner(self) -> tokio::net::TcpStream {
+        self.inner
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+// === impl UdpSocket ===
+
+impl UdpSocket {
+    /// Wraps `socket` so that it is recorded as a resource.
+    ///
+    /// The location of the caller is recorded as the resource's location.
+    #[track_caller]
+    pub fn new(socket: tokio::net::UdpSocket) -> Self {
+        let resource_span = trace::resource_span("UdpSocket", "net", Location::caller());
+        resource_span.in_scope(|| {
+            record_local_addr(socket.local_addr());
+            record_peer_addr(socket.peer_addr());
+        });
+        Self {
+            inner: socket,
+            _resource_span: resource_span,
+        }
+    }
+
+    /// Consumes this `UdpSocket`, returning the wrapped
+    /// [`tokio::net::UdpSocket`].
+    pub fn into_inner(self) -> tokio::net::UdpSocket {
+        self.inner
+    }
+}
+
+impl Deref for UdpSocket {
+    type Target = tokio::net::UdpSocket;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// Records the socket's local address, if it is known.
+///
+/// This must be called while the socket's resource span is entered.
+fn record_local_addr(addr: io::Result<SocketAddr>) {
+    if let Ok(addr) = addr {
+        tracing::trace!(
+            target: "runtime::resource::state_update",
+            local_addr = addr.to_string().as_str(),
+        );
+    }
+}
+
+/// Records the socket's peer address, if it is connected.
+///
+/// This must be called while the socket's resource span is entered.
+fn record_peer_addr(addr: io::Result<SocketAddr>) {
+    if let Ok(addr) = addr {
+        tracing::trace!(
+            target: "runtime::resource::state_update",
+            peer_addr = addr.to_string().as_str(),
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Recording which task panicked, from a panic hook.
+use crate::{stats::TaskStats, ConsoleLayer};
+use console_api as proto;
+use std::{any::Any, panic, sync::Arc, sync::Once};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// Installs a panic hook which records the panic in the stats of the task
+/// being polled on the panicking thread, if there is one.
+///
+/// The hook is only installed once, however many [`ConsoleLayer`]s are built,
+/// and it calls the previously installed hook first.
+pub(crate) fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            hook(info);
+            let location = info.location().map(|location| proto::Location {
+                file: Some(location.file().to_owned()),
+                module_path: None,
+                line: Some(location.line()),
+                column: Some(location.column()),
+            });
+            record(proto::tasks::Panic {
+                message: message(info.payload()),
+                location,
+            });
+        }));
+    });
+}
+
+/// Records `panic` in the stats of the task whose span is entered on this
+/// thread.
+///
+/// This finds the [`ConsoleLayer`] through the current `tracing` dispatcher,
+/// and the task's stats through the [`Registry`] it's layered on, so nothing
+/// is recorded if the dispatcher doesn't use a `Registry`.
+fn record(panic: proto::tasks::Panic) {
+    tracing::dispatcher::get_default(|dispatch| {
+        let (Some(layer), Some(registry)) = (
+            dispatch.downcast_ref::<ConsoleLayer>(),
+            dispatch.downcast_ref::<Registry>(),
+        ) else {
+            return;
+        };
+        let Some(stack) = layer.current_spans.get() else {
+            return;
+        };
+        // The stack is already borrowed if the layer itself panicked.
+        let Ok(stack) = stack.try_borrow() else {
+            return;
+        };
+        let is_task = |id: &_| {
+            registry
+                .span(id)
+                .is_some_and(|span| layer.is_spawn(span.metadata()))
+        };
+        let Some(span) = layer
+            .first_entered(&stack, is_task)
+            .and_then(|id| registry.span(&id))
+        else {
+            return;
+        };
+        if let Some(stats) = span.extensions().get::<Arc<TaskStats>>() {
+            stats.record_panic(panic.clone());
+        };
+    });
+}
+
+/// Returns the message of a panic with `payload`, if it's a string, as it is
+/// for panics from the `panic!` macro.
+fn message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+}
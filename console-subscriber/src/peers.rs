@@ -0,0 +1,104 @@
+//! Authorizing clients which connect to a Unix domain socket by their peer
+//! credentials.
+use std::io;
+use tokio::net::UnixStream;
+
+/// The users and groups which are allowed to connect to the server's Unix
+/// domain socket.
+///
+/// If no users or groups are allowed, any peer which is able to open the
+/// socket is allowed to connect.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AllowedPeers {
+    pub(crate) uids: Vec<u32>,
+    pub(crate) gids: Vec<u32>,
+}
+
+impl AllowedPeers {
+    /// Returns `true` if the peer on the other end of `stream` is allowed to
+    /// connect.
+    ///
+    /// Connections whose credentials can't be read are refused, unless every
+    /// peer is allowed.
+    pub(crate) fn allows(&self, stream: &UnixStream) -> bool {
+        if self.uids.is_empty() && self.gids.is_empty() {
+            return true;
+        }
+        match stream.peer_cred() {
+            Ok(cred) if self.uids.contains(&cred.uid()) || self.gids.contains(&cred.gid()) => true,
+            Ok(cred) => {
+                tracing::warn!(
+                    uid = cred.uid(),
+                    gid = cred.gid(),
+                    pid = ?cred.pid(),
+                    "refusing connection from a peer which is not allowed"
+                );
+                false
+            }
+            Err(error) => {
+                tracing::warn!(%error, "refusing connection, cannot read its peer credentials");
+                false
+            }
+        }
+    }
+
+    /// Returns `true` if `accepted` is an error, which is passed on to the
+    /// server, or a stream whose peer is allowed to connect.
+    pub(crate) fn allows_accepted(&self, accepted: &io::Result<UnixStream>) -> bool {
+        accepted.as_ref().map_or(true, |stream| self.allows(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a connected pair of streams, and the credentials of the
+    /// current process, which is the peer on both ends.
+    fn pair() -> (UnixStream, tokio::net::unix::UCred) {
+        let (stream, _peer) = UnixStream::pair().unwrap();
+        let cred = stream.peer_cred().unwrap();
+        (stream, cred)
+    }
+
+    #[tokio::test]
+    async fn allows_listed_uids() {
+        let (stream, cred) = pair();
+        let allowed = AllowedPeers {
+            uids: vec![cred.uid()],
+            gids: Vec::new(),
+        };
+        assert!(allowed.allows(&stream));
+    }
+
+    #[tokio::test]
+    async fn refuses_other_uids() {
+        let (stream, cred) = pair();
+        let allowed = AllowedPeers {
+            uids: vec![cred.uid().wrapping_add(1)],
+            gids: Vec::new(),
+        };
+        assert!(!allowed.allows(&stream));
+    }
+
+    #[tokio::test]
+    async fn allows_listed_gids() {
+        let (stream, cred) = pair();
+        let allowed = AllowedPeers {
+            uids: vec![cred.uid().wrapping_add(1)],
+            gids: vec![cred.gid()],
+        };
+        assert!(allowed.allows(&stream));
+        let refused = AllowedPeers {
+            uids: Vec::new(),
+            gids: vec![cred.gid().wrapping_add(1)],
+        };
+        assert!(!refused.allows(&stream));
+    }
+
+    #[tokio::test]
+    async fn allows_everyone_by_default() {
+        let (stream, _) = pair();
+        assert!(AllowedPeers::default().allows(&stream));
+    }
+}
@@ -0,0 +1,155 @@
+//! Instrumented child processes.
+//!
+//! Tokio does not emit resource instrumentation for [`tokio::process::Child`].
+//! Wrapping a child process in a [`Child`] records it as a `process` resource,
+//! with its PID and exit status as attributes. Waiting on the child is
+//! recorded as an async op, so the console shows which task is awaiting it,
+//! and how long the process has been running.
+//!
+//! This module requires the "process" crate feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn docs() -> std::io::Result<()> {
+//! use tokio::process::Command;
+//!
+//! let child = Command::new("sleep").arg("10").spawn()?;
+//! let mut child = console_subscriber::process::Child::new(child);
+//! let status = child.wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::trace;
+use std::{io, panic::Location, process::ExitStatus};
+
+/// A [`tokio::process::Child`] which is recorded as a resource by the
+/// console.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct Child {
+    inner: tokio::process::Child,
+    resource_span: tracing::Span,
+}
+
+impl Child {
+    /// Wraps `child` so that it is recorded as a resource.
+    ///
+    /// The location of the caller is recorded as the resource's location.
+    #[track_caller]
+    pub fn new(child: tokio::process::Child) -> Self {
+        let resource_span = trace::resource_span("Child", "process", Location::caller());
+        resource_span.in_scope(|| {
+            if let Some(pid) = child.id() {
+                tracing::trace!(
+                    target: "runtime::resource::state_update",
+                    pid = pid as u64,
+                    pid.op = "override",
+                );
+            }
+            tracing::trace!(
+                target: "runtime::resource::state_update",
+                exited = false,
+            );
+        });
+        Self {
+            inner: child,
+            resource_span,
+        }
+    }
+
+    /// Returns the OS-assigned process identifier of the child, if it is
+    /// still running.
+    ///
+    /// See [`tokio::process::Child::id`].
+    pub fn id(&self) -> Option<u32> {
+        self.inner.id()
+    }
+
+    /// Waits for the child to exit, returning its exit status.
+    ///
+    /// This is recorded as a `Child::wait` async op on the child's resource.
+    ///
+    /// See [`tokio::process::Child::wait`].
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        let status = trace::async_op(
+            &self.resource_span,
+            "Child::wait",
+            "poll_wait",
+            self.inner.wait(),
+        )
+        .await;
+        if let Ok(status) = status {
+            self.record_exit(status);
+        }
+        status
+    }
+
+    /// Attempts to collect the exit status of the child, if it has already
+    /// exited.
+    ///
+    /// See [`tokio::process::Child::try_wait`].
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        let status = self.inner.try_wait();
+        if let Ok(Some(status)) = status {
+            self.record_exit(status);
+        }
+        status
+    }
+
+    /// Forces the child to exit, and waits for it to do so.
+    ///
+    /// See [`tokio::process::Child::kill`].
+    pub async fn kill(&mut self) -> io::Result<()> {
+        trace::async_op(
+            &self.resource_span,
+            "Child::kill",
+            "poll_kill",
+            self.inner.kill(),
+        )
+        .await
+    }
+
+    /// Returns a reference to the wrapped [`tokio::process::Child`].
+    pub fn get_ref(&self) -> &tokio::process::Child {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped [`tokio::process::Child`].
+    ///
+    /// Waiting on the child through this reference will not be recorded by
+    /// the console.
+    pub fn get_mut(&mut self) -> &mut tokio::process::Child {
+        &mut self.inner
+    }
+
+    /// Consumes this `Child`, returning the wrapped
+    /// [`tokio::process::Child`].
+    pub fn into_inner(self) -> tokio::process::Child {
+        self.inner
+    }
+
+    fn record_exit(&self, status: ExitStatus) {
+        self.resource_span.in_scope(|| {
+            tracing::trace!(
+                target: "runtime::resource::state_update",
+                exited = true,
+            );
+            if let Some(code) = status.code() {
+                tracing::trace!(
+                    target: "runtime::resource::state_update",
+                    exit_code = code as i64,
+                    exit_code.op = "override",
+                );
+            }
+        });
+    }
+}
+
+impl From<tokio::process::Child> for Child {
+    #[track_caller]
+    fn from(child: tokio::process::Child) -> Self {
+        Self::new(child)
+    }
+}
@@ -0,0 +1,202 @@
+//! Instrumented signal listeners.
+//!
+//! Tokio does not emit resource instrumentation for [`tokio::signal`]
+//! listeners. The listeners in this module are recorded as `signal`
+//! resources, with the signal they listen for and the number of times
+//! listeners have received it as attributes. Tokio shares one handler
+//! between all the listeners for a signal, so each signal is recorded as a
+//! single resource, which lives for as long as the process. Waiting for a
+//! signal is recorded as an async op, so the console shows which task is
+//! listening for it.
+//!
+//! This makes it possible to check from the console whether, for example, a
+//! shutdown handler has been installed and whether it has ever fired.
+//!
+//! This module requires the "signal" crate feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn docs() -> std::io::Result<()> {
+//! // Waits for ctrl-c, recording the listener as a resource.
+//! console_subscriber::signal::ctrl_c().await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::trace;
+use std::{
+    collections::HashMap,
+    io,
+    panic::Location,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+/// Completes when a "ctrl-c" notification is sent to the process.
+///
+/// Every call is recorded on the same `CtrlC` resource, whose location is
+/// that of the first call.
+///
+/// See [`tokio::signal::ctrl_c`].
+#[track_caller]
+pub fn ctrl_c() -> impl std::future::Future<Output = io::Result<()>> {
+    let resource_span = signal_resource(Listened::CtrlC, "CtrlC", Location::caller(), || {
+        tracing::trace!(
+            target: "runtime::resource::state_update",
+            signal = "ctrl_c",
+        );
+    });
+    async move {
+        let res = trace::async_op(
+            &resource_span,
+            "ctrl_c",
+            "poll_ctrl_c",
+            tokio::signal::ctrl_c(),
+        )
+        .await;
+        if res.is_ok() {
+            resource_span.in_scope(|| record_fired(1));
+        }
+        res
+    }
+}
+
+/// A listener for a Unix signal, which is recorded as a resource by the
+/// console.
+///
+/// See [`tokio::signal::unix::Signal`].
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct Signal {
+    inner: tokio::signal::unix::Signal,
+    resource_span: tracing::Span,
+}
+
+#[cfg(unix)]
+impl Signal {
+    /// Creates a new listener for signals of the given `kind`.
+    ///
+    /// All the listeners for a signal are recorded on the same resource,
+    /// whose location is that of the first listener's creation.
+    ///
+    /// See [`tokio::signal::unix::signal`].
+    #[track_caller]
+    pub fn new(kind: tokio::signal::unix::SignalKind) -> io::Result<Self> {
+        let location = Location::caller();
+        let inner = tokio::signal::unix::signal(kind)?;
+        let listened = Listened::Unix(kind.as_raw_value());
+        let resource_span =
+            signal_resource(listened, "Signal", location, || match signal_name(kind) {
+                Some(name) => tracing::trace!(
+                    target: "runtime::resource::state_update",
+                    signal = name,
+                ),
+                None => tracing::trace!(
+                    target: "runtime::resource::state_update",
+                    signal = kind.as_raw_value() as i64,
+                    signal.op = "override",
+                ),
+            });
+        Ok(Self {
+            inner,
+            resource_span,
+        })
+    }
+
+    /// Receives the next signal notification.
+    ///
+    /// This is recorded as a `Signal::recv` async op on the listener's
+    /// resource.
+    ///
+    /// See [`tokio::signal::unix::Signal::recv`].
+    pub async fn recv(&mut self) -> Option<()> {
+        let res = trace::async_op(
+            &self.resource_span,
+            "Signal::recv",
+            "poll_recv",
+            self.inner.recv(),
+        )
+        .await;
+        if res.is_some() {
+            self.resource_span.in_scope(|| record_fired(1));
+        }
+        res
+    }
+
+    /// Consumes this `Signal`, returning the wrapped
+    /// [`tokio::signal::unix::Signal`].
+    pub fn into_inner(self) -> tokio::signal::unix::Signal {
+        self.inner
+    }
+}
+
+/// A signal which is listened for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Listened {
+    CtrlC,
+    #[cfg(unix)]
+    Unix(std::os::raw::c_int),
+}
+
+/// Returns the resource span for the `listened` signal, creating it if this
+/// is the first listener for the signal.
+///
+/// `record_signal` records the signal's attribute when the resource is
+/// created. It is called while the new resource span is entered.
+fn signal_resource(
+    listened: Listened,
+    concrete_type: &'static str,
+    location: &'static Location<'static>,
+    record_signal: impl FnOnce(),
+) -> tracing::Span {
+    static RESOURCES: OnceLock<Mutex<HashMap<Listened, tracing::Span>>> = OnceLock::new();
+
+    let mut resources = RESOURCES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    match resources.get(&listened) {
+        // A span created before the subscriber was installed is disabled, so
+        // it is replaced rather than reused.
+        Some(span) if !span.is_disabled() => span.clone(),
+        _ => {
+            let span = trace::resource_span(concrete_type, "signal", location);
+            span.in_scope(|| {
+                record_signal();
+                record_fired(0);
+            });
+            resources.insert(listened, span.clone());
+            span
+        }
+    }
+}
+
+/// Adds `n` to the number of times a signal was received.
+///
+/// This must be called while the listener's resource span is entered.
+fn record_fired(n: u64) {
+    tracing::trace!(
+        target: "runtime::resource::state_update",
+        fired = n,
+        fired.op = "add",
+    );
+}
+
+#[cfg(unix)]
+fn signal_name(kind: tokio::signal::unix::SignalKind) -> Option<&'static str> {
+    use tokio::signal::unix::SignalKind;
+    let name = match kind {
+        k if k == SignalKind::alarm() => "SIGALRM",
+        k if k == SignalKind::child() => "SIGCHLD",
+        k if k == SignalKind::hangup() => "SIGHUP",
+        k if k == SignalKind::interrupt() => "SIGINT",
+        k if k == SignalKind::io() => "SIGIO",
+        k if k == SignalKind::pipe() => "SIGPIPE",
+        k if k == SignalKind::quit() => "SIGQUIT",
+        k if k == SignalKind::terminate() => "SIGTERM",
+        k if k == SignalKind::user_defined1() => "SIGUSR1",
+        k if k == SignalKind::user_defined2() => "SIGUSR2",
+        k if k == SignalKind::window_change() => "SIGWINCH",
+        _ => return None,
+    };
+    Some(name)
+}
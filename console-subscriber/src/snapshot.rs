@@ -0,0 +1,172 @@
+//! Writing a final snapshot of the aggregator's state when the process panics
+//! or shuts down, for when no console was connected to see what happened.
+//!
+//! See [`Builder::final_snapshot_path`](crate::Builder::final_snapshot_path)
+//! for details.
+use crate::Command;
+use console_api as proto;
+use prost::Message;
+use proto::instrument::{frame, Frame};
+use std::{
+    fs,
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    sync::{mpsc as std_mpsc, OnceLock},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// The aggregator which writes the final snapshot, if one is configured.
+///
+/// This only holds a weak reference, so that it doesn't keep the aggregator
+/// running once its server has been dropped.
+static SNAPSHOTS: OnceLock<mpsc::WeakSender<Command>> = OnceLock::new();
+
+/// How long [`write_final_snapshot`] waits for the aggregator to write a
+/// snapshot.
+///
+/// The aggregator might be running on the same thread as the caller, in
+/// which case it can't write the snapshot until the caller returns.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Installs a panic hook which asks the aggregator behind `subscribe` to write
+/// its final snapshot, if there isn't one installed already.
+pub(crate) fn install(subscribe: &mpsc::Sender<Command>) {
+    if SNAPSHOTS.set(subscribe.downgrade()).is_err() {
+        tracing::warn!("a final snapshot is already configured, only the first is written");
+        return;
+    }
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        hook(info);
+        // The hook doesn't wait for the snapshot to be written, since the
+        // aggregator may be running on the panicking thread, such as when a
+        // task panics on a single-threaded runtime. The aggregator writes it
+        // the next time it runs.
+        if let Err(error) = send(None) {
+            tracing::warn!(%error, "final snapshot not written");
+        }
+    }));
+}
+
+/// Writes a snapshot of every task, resource and async op which the
+/// aggregator knows about, including their stats, to the path configured with
+/// [`Builder::final_snapshot_path`].
+///
+/// Applications can call this as they shut down gracefully, since the
+/// aggregator may not get the chance to write the snapshot itself before the
+/// process exits. This blocks until the snapshot has been written, for up to
+/// a second.
+///
+/// # Errors
+///
+/// This returns an error if no final snapshot path is configured, if the
+/// aggregator isn't running or doesn't write the snapshot in time, or if
+/// writing the snapshot fails.
+///
+/// [`Builder::final_snapshot_path`]: crate::Builder::final_snapshot_path
+pub fn write_final_snapshot() -> io::Result<()> {
+    request()
+}
+
+fn request() -> io::Result<()> {
+    let (tx, rx) = std_mpsc::sync_channel(1);
+    send(Some(tx))?;
+    rx.recv_timeout(TIMEOUT).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            "the aggregator did not write the snapshot in time",
+        )
+    })?
+}
+
+/// Asks the aggregator to write the final snapshot, and to report whether
+/// it was written on `written`, if it's set.
+fn send(written: Option<std_mpsc::SyncSender<io::Result<()>>>) -> io::Result<()> {
+    let subscribe = SNAPSHOTS
+        .get()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no final snapshot path is set"))?
+        .upgrade()
+        .ok_or_else(not_running)?;
+    // This may be called from inside a runtime, or while panicking, so don't
+    // block waiting for room in the channel.
+    subscribe
+        .try_send(Command::Snapshot(written))
+        .map_err(|_| not_running())
+}
+
+fn not_running() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "the aggregator is not running")
+}
+
+/// Writes `update` to `path`, as a single [`Frame`] prefixed by its length,
+/// which is the same format as the framed transport.
+///
+/// The snapshot is written to a temporary file next to `path`, which then
+/// replaces it, so that a snapshot which was already written isn't lost if
+/// the process exits while the next one is being written.
+pub(crate) fn write(path: &Path, update: proto::instrument::Update) -> io::Result<()> {
+    let frame = Frame {
+        message: Some(frame::Message::Update(update)),
+    }
+    .encode_to_vec();
+    let len = u32::try_from(frame.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "snapshot is too large"))?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let written = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&len.to_be_bytes())?;
+        file.write_all(&frame)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_are_read_back() {
+        let path = std::env::temp_dir().join(format!(
+            "console-subscriber-snapshot-{}",
+            std::process::id()
+        ));
+        let update = |secs| proto::instrument::Update {
+            now: Some(prost_types::Timestamp {
+                seconds: secs,
+                nanos: 0,
+            }),
+            ..Default::default()
+        };
+        let read = || {
+            let raw = fs::read(&path).unwrap();
+            let (len, frame) = raw.split_at(4);
+            assert_eq!(
+                u32::from_be_bytes(len.try_into().unwrap()) as usize,
+                frame.len()
+            );
+            match Frame::decode(frame).unwrap().message {
+                Some(frame::Message::Update(update)) => update,
+                message => panic!("expected an update, got {message:?}"),
+            }
+        };
+
+        write(&path, update(1)).unwrap();
+        assert_eq!(read(), update(1));
+        // A later snapshot replaces the earlier one.
+        write(&path, update(2)).unwrap();
+        assert_eq!(read(), update(2));
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        assert!(!Path::new(&tmp_path).exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
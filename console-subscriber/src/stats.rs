@@ -5,6 +5,7 @@ use hdrhistogram::{
     serialization::{Serializer, V2Serializer},
 };
 use std::cmp;
+use std::collections::VecDeque;
 use std::sync::{
     atomic::{AtomicBool, AtomicUsize, Ordering::*},
     Arc,
@@ -14,6 +15,10 @@ use tracing::span::Id;
 
 use console_api as proto;
 
+/// How many of each task's most recent polls are kept, to be shown in its
+/// details.
+const RECENT_POLLS: usize = 128;
+
 /// A type which records whether it has unsent updates.
 ///
 /// If something implementing this trait has been changed since the last time
@@ -64,8 +69,17 @@ pub(crate) struct TaskStats {
     waker_drops: AtomicUsize,
     self_wakes: AtomicUsize,
 
+    /// How the task panicked, if it has.
+    panic: Mutex<Option<proto::tasks::Panic>>,
+    /// Who aborted the task, if it was aborted through [`crate::abort`].
+    abort: Mutex<Option<proto::tasks::Abort>>,
+
     /// Poll durations and other stats.
     poll_stats: PollStats<Histogram>,
+
+    /// When each of the task's most recent polls started, and how long it
+    /// took, oldest first.
+    recent_polls: Mutex<VecDeque<(Instant, Duration)>>,
 }
 
 /// Stats associated with an async operation.
@@ -182,6 +196,9 @@ impl TaskStats {
             waker_clones: AtomicUsize::new(0),
             waker_drops: AtomicUsize::new(0),
             self_wakes: AtomicUsize::new(0),
+            panic: Mutex::new(None),
+            abort: Mutex::new(None),
+            recent_polls: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -229,10 +246,54 @@ impl TaskStats {
     }
 
     pub(crate) fn end_poll(&self, at: Instant) {
-        self.poll_stats.end_poll(at);
+        if let Some(poll) = self.poll_stats.end_poll(at) {
+            let mut recent_polls = self.recent_polls.lock();
+            if recent_polls.len() == RECENT_POLLS {
+                recent_polls.pop_front();
+            }
+            recent_polls.push_back(poll);
+        }
+        self.make_dirty();
+    }
+
+    /// Records that the task panicked.
+    ///
+    /// Only the first panic is kept, since a task isn't polled again once it
+    /// has panicked.
+    pub(crate) fn record_panic(&self, panic: proto::tasks::Panic) {
+        self.panic.lock().get_or_insert(panic);
         self.make_dirty();
     }
 
+    /// Records who aborted the task, `at` the time it was aborted.
+    ///
+    /// Only the first abort is kept, since aborting a task again does nothing,
+    /// and aborts after the task was dropped are ignored, since the task had
+    /// already completed.
+    pub(crate) fn record_abort(&self, abort: proto::tasks::Abort, at: Instant) {
+        if self
+            .dropped_at
+            .lock()
+            .is_some_and(|dropped_at| dropped_at <= at)
+        {
+            return;
+        }
+        self.abort.lock().get_or_insert(abort);
+        self.make_dirty();
+    }
+
+    /// Returns why the task ended, if it has been dropped.
+    fn end_reason(&self) -> Option<proto::tasks::EndReason> {
+        self.dropped_at.lock().as_ref()?;
+        Some(if self.panic.lock().is_some() {
+            proto::tasks::EndReason::Panicked
+        } else if self.abort.lock().is_some() {
+            proto::tasks::EndReason::Cancelled
+        } else {
+            proto::tasks::EndReason::Completed
+        })
+    }
+
     pub(crate) fn drop_task(&self, dropped_at: Instant) {
         if self.is_dropped.swap(true, AcqRel) {
             // The task was already dropped.
@@ -250,6 +311,18 @@ impl TaskStats {
         proto::tasks::task_details::PollTimesHistogram::Histogram(hist)
     }
 
+    /// Returns the task's most recent polls, oldest first.
+    pub(crate) fn recent_polls(&self, base_time: &TimeAnchor) -> Vec<proto::tasks::Poll> {
+        self.recent_polls
+            .lock()
+            .iter()
+            .map(|&(started, duration)| proto::tasks::Poll {
+                started_at: Some(base_time.to_timestamp(started)),
+                duration: duration.try_into().ok(),
+            })
+            .collect()
+    }
+
     pub(crate) fn scheduled_duration_histogram(&self) -> proto::tasks::DurationHistogram {
         self.poll_stats
             .timestamps
@@ -269,6 +342,9 @@ impl ToProto for TaskStats {
 
     fn to_proto(&self, base_time: &TimeAnchor) -> Self::Output {
         let poll_stats = Some(self.poll_stats.to_proto(base_time));
+        // This locks `dropped_at` itself, so it can't be called while the
+        // lock is held below.
+        let end_reason = self.end_reason();
         let timestamps = self.poll_stats.timestamps.lock();
         proto::tasks::Stats {
             poll_stats,
@@ -291,6 +367,10 @@ impl ToProto for TaskStats {
                         Default::default()
                     }),
             ),
+            poll_times_summary: timestamps.poll_histogram.summary(),
+            panic: self.panic.lock().clone(),
+            abort: self.abort.lock().clone(),
+            end_reason: end_reason.map(Into::into),
         }
     }
 }
@@ -527,10 +607,12 @@ impl<H: RecordDuration> PollStats<H> {
         timestamps.scheduled_time += elapsed;
     }
 
-    fn end_poll(&self, at: Instant) {
+    /// Records that a poll ended `at` the given time, returning when the
+    /// outermost poll started and how long it took, if it has now ended.
+    fn end_poll(&self, at: Instant) -> Option<(Instant, Duration)> {
         // Are we ending the last current poll?
         if self.current_polls.fetch_sub(1, AcqRel) > 1 {
-            return;
+            return None;
         }
 
         let mut timestamps = self.timestamps.lock();
@@ -541,7 +623,7 @@ impl<H: RecordDuration> PollStats<H> {
                     "a poll ended, but start timestamp was recorded. \
                      this is probably a `console-subscriber` bug"
                 );
-                return;
+                return None;
             }
         };
 
@@ -554,7 +636,7 @@ impl<H: RecordDuration> PollStats<H> {
                     was before its start timestamp\nstart = {:?}\n  end = {:?}",
                     started, at
                 );
-                return;
+                return None;
             }
         };
 
@@ -562,6 +644,7 @@ impl<H: RecordDuration> PollStats<H> {
         timestamps.poll_histogram.record_duration(elapsed);
 
         timestamps.busy_time += elapsed;
+        Some((started, elapsed))
     }
 }
 
@@ -630,6 +713,21 @@ impl Histogram {
         }
     }
 
+    /// Returns a summary of the histogram, or `None` if nothing has been
+    /// recorded.
+    fn summary(&self) -> Option<proto::tasks::PollTimesSummary> {
+        if self.histogram.is_empty() {
+            return None;
+        }
+        let max = cmp::max(self.max_outlier, Some(self.histogram.max())).unwrap_or_default();
+        Some(proto::tasks::PollTimesSummary {
+            p99: Duration::from_nanos(self.histogram.value_at_quantile(0.99))
+                .try_into()
+                .ok(),
+            max: Duration::from_nanos(max).try_into().ok(),
+        })
+    }
+
     fn to_proto(&self) -> proto::tasks::DurationHistogram {
         let mut serializer = V2Serializer::new();
         let mut raw_histogram = Vec::new();
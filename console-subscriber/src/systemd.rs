@@ -0,0 +1,88 @@
+//! Accepting a listening socket passed to the process by systemd socket
+//! activation.
+//!
+//! systemd passes sockets as file descriptors starting at 3, and describes
+//! them with the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment
+//! variables, as documented in [`sd_listen_fds(3)`].
+//!
+//! [`sd_listen_fds(3)`]: https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html
+use std::{
+    env, io,
+    net::TcpListener,
+    os::{
+        fd::{FromRawFd, IntoRawFd, RawFd},
+        unix::net::UnixListener,
+    },
+};
+
+/// The first file descriptor systemd passes sockets as.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// A listening socket passed by systemd.
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+/// Returns the listening socket passed by systemd with the given `name`,
+/// which is set by `FileDescriptorName=` in the socket unit, or the first
+/// one if `name` is `None`.
+///
+/// The socket is returned in non-blocking mode, so that it can be used with
+/// Tokio.
+pub(crate) fn listener(name: Option<&str>) -> io::Result<Listener> {
+    let fd = listen_fd(name)?;
+    // Safety: systemd passed the process this file descriptor, and it's only
+    // taken once, since a server is only started once.
+    let tcp = unsafe { TcpListener::from_raw_fd(fd) };
+    // A Unix domain socket has no IP address.
+    let listener = if tcp.local_addr().is_ok() {
+        Listener::Tcp(tcp)
+    } else {
+        // Safety: this is the same file descriptor, which `tcp` gave up.
+        Listener::Unix(unsafe { UnixListener::from_raw_fd(tcp.into_raw_fd()) })
+    };
+    match listener {
+        Listener::Tcp(ref tcp) => tcp.set_nonblocking(true)?,
+        Listener::Unix(ref unix) => unix.set_nonblocking(true)?,
+    }
+    Ok(listener)
+}
+
+/// Returns the file descriptor of the socket named `name`, or of the first
+/// socket.
+fn listen_fd(name: Option<&str>) -> io::Result<RawFd> {
+    let not_activated = || {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "no sockets were passed by systemd socket activation",
+        )
+    };
+    // The sockets may have been meant for a parent process, which didn't
+    // clear the variables before starting this one.
+    let pid = env::var("LISTEN_PID").map_err(|_| not_activated())?;
+    if pid.parse::<u32>().ok() != Some(std::process::id()) {
+        return Err(not_activated());
+    }
+    let fds = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|fds| fds.parse::<RawFd>().ok())
+        .filter(|&fds| fds > 0)
+        .ok_or_else(not_activated)?;
+
+    let index = match name {
+        None => 0,
+        Some(name) => env::var("LISTEN_FDNAMES")
+            .unwrap_or_default()
+            .split(':')
+            .position(|passed| passed == name)
+            .filter(|&index| (index as RawFd) < fds)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("systemd did not pass a socket named {name:?}"),
+                )
+            })? as RawFd,
+    };
+    Ok(LISTEN_FDS_START + index)
+}
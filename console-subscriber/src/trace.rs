@@ -0,0 +1,92 @@
+//! Helpers for emitting resource and async op instrumentation for types which
+//! the async runtime does not instrument itself.
+//!
+//! These emit `tracing` data with the same shape as Tokio's own resource
+//! instrumentation, so that the [`ConsoleLayer`](crate::ConsoleLayer) records
+//! them like any other resource.
+use std::{future::Future, panic::Location, task::Poll};
+
+/// Returns a new resource span for a resource created at `location`.
+pub(crate) fn resource_span(
+    concrete_type: &'static str,
+    kind: &'static str,
+    location: &'static Location<'static>,
+) -> tracing::Span {
+    tracing::trace_span!(
+        parent: None,
+        "runtime.resource",
+        concrete_type = concrete_type,
+        kind = kind,
+        loc.file = location.file(),
+        loc.line = location.line(),
+        loc.col = location.column(),
+    )
+}
+
+/// Runs `future` as an async op on the resource represented by
+/// `resource_span`.
+///
+/// Each poll of `future` enters the resource and async op spans and records a
+/// poll op named `poll_op_name`, so that the task awaiting the op is
+/// associated with it.
+#[cfg(any(feature = "net", feature = "process", feature = "signal"))]
+pub(crate) async fn async_op<F>(
+    resource_span: &tracing::Span,
+    source: &'static str,
+    poll_op_name: &'static str,
+    future: F,
+) -> F::Output
+where
+    F: Future,
+{
+    let async_op_span = async_op_span(resource_span, source);
+    in_async_op(resource_span, &async_op_span, poll_op_name, future).await
+}
+
+/// Returns a new async op span for an async op on the resource represented
+/// by `resource_span`.
+///
+/// This is only needed to record attributes of the async op before running
+/// it with [`in_async_op`]; otherwise, use [`async_op`].
+pub(crate) fn async_op_span(resource_span: &tracing::Span, source: &'static str) -> tracing::Span {
+    resource_span.in_scope(|| tracing::trace_span!("runtime.resource.async_op", source = source))
+}
+
+/// Runs `future` as the async op represented by `async_op_span`, on the
+/// resource represented by `resource_span`.
+///
+/// See [`async_op`].
+pub(crate) async fn in_async_op<F>(
+    resource_span: &tracing::Span,
+    async_op_span: &tracing::Span,
+    poll_op_name: &'static str,
+    future: F,
+) -> F::Output
+where
+    F: Future,
+{
+    let async_op_poll_span =
+        async_op_span.in_scope(|| tracing::trace_span!("runtime.resource.async_op.poll"));
+
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        let _resource_enter = resource_span.enter();
+        let _async_op_enter = async_op_span.enter();
+        let _async_op_poll_enter = async_op_poll_span.enter();
+        poll_op(poll_op_name, future.as_mut().poll(cx))
+    })
+    .await
+}
+
+/// Records a poll op with the readiness of `poll`.
+///
+/// This must be called while a resource span and an async op span are
+/// entered.
+pub(crate) fn poll_op<T>(op_name: &'static str, poll: Poll<T>) -> Poll<T> {
+    tracing::trace!(
+        target: "runtime::resource::poll_op",
+        op_name = op_name,
+        is_ready = poll.is_ready(),
+    );
+    poll
+}
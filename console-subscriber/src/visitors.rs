@@ -123,6 +123,29 @@ pub(crate) struct WakerVisitor {
     op: Option<WakeOp>,
 }
 
+/// Used to extract the fields needed to construct
+/// an Event::Abort from the metadata of a tracing event
+/// that has the following shape:
+///
+/// tracing::trace!(
+///     target: "runtime::task::abort",
+///     task.id = 12,
+///     loc.file = "some_file.rs",
+///     loc.line = 555,
+///     loc.col = 5,
+/// );
+///
+/// Fields:
+/// task.id - the Tokio task ID of the task being aborted, as in its spawn span
+/// loc.file, loc.line, loc.col - where the task was aborted
+#[derive(Default)]
+pub(crate) struct AbortVisitor {
+    task_id: Option<u64>,
+    line: Option<u32>,
+    file: Option<String>,
+    column: Option<u32>,
+}
+
 /// Used to extract the fields needed to construct
 /// an Event::PollOp from the metadata of a tracing event
 /// that has the following shape:
@@ -407,6 +430,41 @@ impl Visit for WakerVisitor {
     }
 }
 
+impl AbortVisitor {
+    pub(crate) const ABORT_EVENT_TARGET: &'static str = "runtime::task::abort";
+    const TASK_ID_FIELD_NAME: &'static str = "task.id";
+
+    pub(crate) fn result(self) -> Option<(u64, Option<proto::Location>)> {
+        let task_id = self.task_id?;
+        let location = self.file.map(|file| proto::Location {
+            file: Some(file),
+            line: self.line,
+            column: self.column,
+            ..Default::default()
+        });
+        Some((task_id, location))
+    }
+}
+
+impl Visit for AbortVisitor {
+    fn record_debug(&mut self, _: &field::Field, _: &dyn std::fmt::Debug) {}
+
+    fn record_u64(&mut self, field: &tracing_core::Field, value: u64) {
+        match field.name() {
+            Self::TASK_ID_FIELD_NAME => self.task_id = Some(value),
+            LOCATION_LINE => self.line = Some(value as u32),
+            LOCATION_COLUMN => self.column = Some(value as u32),
+            _ => {}
+        }
+    }
+
+    fn record_str(&mut self, field: &tracing_core::Field, value: &str) {
+        if field.name() == LOCATION_FILE {
+            self.file = Some(value.to_string());
+        }
+    }
+}
+
 impl PollOpVisitor {
     pub(crate) const POLL_OP_EVENT_TARGET: &'static str = "runtime::resource::poll_op";
     const OP_NAME_FIELD_NAME: &'static str = "op_name";
@@ -0,0 +1,30 @@
+mod support;
+use std::future;
+
+use support::{assert_tasks, spawn_named, ExpectedTask, MAIN_TASK_NAME};
+
+#[test]
+fn aborts_are_recorded() {
+    let expected_tasks = vec![
+        ExpectedTask::default()
+            .match_name("aborted-by-join-handle".into())
+            .expect_aborted_by(MAIN_TASK_NAME, file!()),
+        ExpectedTask::default()
+            .match_name("aborted-by-abort-handle".into())
+            .expect_aborted_by(MAIN_TASK_NAME, file!()),
+    ];
+
+    let future = async {
+        let join_handle = spawn_named("aborted-by-join-handle", future::pending::<()>());
+        console_subscriber::abort(&join_handle);
+        let error = join_handle.await.expect_err("the task should be aborted");
+        assert!(error.is_cancelled());
+
+        let join_handle = spawn_named("aborted-by-abort-handle", future::pending::<()>());
+        console_subscriber::abort_handle(&join_handle.abort_handle());
+        let error = join_handle.await.expect_err("the task should be aborted");
+        assert!(error.is_cancelled());
+    };
+
+    assert_tasks(expected_tasks, future);
+}
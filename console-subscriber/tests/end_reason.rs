@@ -0,0 +1,53 @@
+mod support;
+use std::future;
+
+use console_api::tasks::EndReason;
+use support::{assert_task, spawn_named, ExpectedTask};
+
+#[test]
+fn completed_tasks_end_as_completed() {
+    let expected_task = ExpectedTask::default()
+        .match_name("completing".into())
+        .expect_end_reason(EndReason::Completed);
+
+    let future = async {
+        spawn_named("completing", async {})
+            .await
+            .expect("the task should complete");
+    };
+
+    assert_task(expected_task, future);
+}
+
+#[test]
+fn aborted_tasks_end_as_cancelled() {
+    let expected_task = ExpectedTask::default()
+        .match_name("aborted".into())
+        .expect_end_reason(EndReason::Cancelled);
+
+    let future = async {
+        let join_handle = spawn_named("aborted", future::pending::<()>());
+        console_subscriber::abort(&join_handle);
+        let error = join_handle.await.expect_err("the task should be aborted");
+        assert!(error.is_cancelled());
+    };
+
+    assert_task(expected_task, future);
+}
+
+#[test]
+fn panicked_tasks_end_as_panicked() {
+    let expected_task = ExpectedTask::default()
+        .match_name("panicking".into())
+        .expect_end_reason(EndReason::Panicked);
+
+    let future = async {
+        let join_handle = spawn_named("panicking", async {
+            panic!("the task panicked");
+        });
+        let error = join_handle.await.expect_err("the task should panic");
+        assert!(error.is_panic());
+    };
+
+    assert_task(expected_task, future);
+}
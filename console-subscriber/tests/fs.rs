@@ -0,0 +1,26 @@
+mod support;
+use support::{assert_resources, ExpectedResource};
+
+#[test]
+fn operations_are_recorded_with_their_path() {
+    let path =
+        std::env::temp_dir().join(format!("console-subscriber-fs-test-{}", std::process::id()));
+    std::fs::write(&path, "hello").unwrap();
+
+    let expected_resources = vec![ExpectedResource::default()
+        .match_concrete_type("fs::read_to_string")
+        .expect_kind("fs")
+        .expect_attribute_value("path", &path.display().to_string())
+        .expect_dropped(true)];
+
+    let read = path.clone();
+    let future = async move {
+        let contents = console_subscriber::fs::read_to_string(read)
+            .await
+            .expect("reading the file failed");
+        assert_eq!(contents, "hello");
+    };
+
+    assert_resources(expected_resources, future);
+    std::fs::remove_file(path).unwrap();
+}
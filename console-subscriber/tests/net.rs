@@ -0,0 +1,38 @@
+use console_subscriber::net::TcpListener;
+use tokio::io::AsyncWriteExt;
+
+mod support;
+use support::{assert_resources, ExpectedResource};
+
+#[test]
+fn accepted_streams_are_recorded() {
+    let expected_resources = vec![
+        ExpectedResource::default()
+            .match_concrete_type("TcpListener")
+            .expect_kind("net")
+            .expect_attribute("local_addr")
+            .expect_attribute_value("accepted", "1"),
+        ExpectedResource::default()
+            .match_concrete_type("TcpStream")
+            .expect_kind("net")
+            .expect_attribute("local_addr")
+            .expect_attribute("peer_addr")
+            .expect_dropped(true),
+    ];
+
+    let future = async {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("binding the listener failed");
+        let addr = listener.local_addr().unwrap();
+        let listener = TcpListener::new(listener);
+        let mut client = tokio::net::TcpStream::connect(addr)
+            .await
+            .expect("connecting failed");
+        let (stream, _peer) = listener.accept().await.expect("accepting failed");
+        client.shutdown().await.unwrap();
+        drop(stream);
+    };
+
+    assert_resources(expected_resources, future);
+}
@@ -0,0 +1,19 @@
+mod support;
+use support::{assert_task, spawn_named, ExpectedTask};
+
+#[test]
+fn panics_are_recorded() {
+    let expected_task = ExpectedTask::default()
+        .match_name("panicking".into())
+        .expect_panic("the task panicked", file!());
+
+    let future = async {
+        let join_handle = spawn_named("panicking", async {
+            panic!("the task panicked");
+        });
+        let error = join_handle.await.expect_err("the task should panic");
+        assert!(error.is_panic());
+    };
+
+    assert_task(expected_task, future);
+}
@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use std::{path::PathBuf, time::Duration};
+
+use console_api::instrument::{instrument_client::InstrumentClient, InstrumentRequest};
+use console_subscriber::ConsoleLayer;
+use hyper_util::rt::TokioIo;
+use tokio::net::UnixStream;
+use tonic::transport::{Endpoint, Uri};
+use tower::service_fn;
+
+/// Serves the console on a Unix domain socket, which only the user
+/// `allowed_uid` may connect to, and returns whether a client could watch
+/// its updates.
+async fn watch_updates_as_allowed(name: &str, allowed_uid: u32) -> bool {
+    let path = std::env::temp_dir().join(format!(
+        "console-subscriber-{name}-{}.sock",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let (_layer, server) = ConsoleLayer::builder()
+        .server_addr(path.as_path())
+        .allow_peer_uid(allowed_uid)
+        .build();
+    let serve = tokio::spawn(async move { server.serve().await.unwrap() });
+    while !path.exists() {
+        tokio::task::yield_now().await;
+    }
+
+    let socket = path.clone();
+    let watch = async {
+        let channel =
+            Endpoint::try_from("http://[::]:6669")?
+                .connect_with_connector(service_fn(move |_: Uri| {
+                    let path: PathBuf = path.clone();
+                    async move {
+                        Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?))
+                    }
+                }))
+                .await?;
+        let mut updates = InstrumentClient::new(channel)
+            .watch_updates(InstrumentRequest {})
+            .await?
+            .into_inner();
+        updates.message().await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    let watched = tokio::time::timeout(Duration::from_secs(10), watch)
+        .await
+        .expect("watching updates timed out");
+    serve.abort();
+    let _ = std::fs::remove_file(socket);
+    watched.is_ok()
+}
+
+/// Returns the ID of the user which the test runs as.
+fn current_uid() -> u32 {
+    let (stream, _peer) = UnixStream::pair().unwrap();
+    stream.peer_cred().unwrap().uid()
+}
+
+#[tokio::test]
+async fn allowed_peers_are_served() {
+    assert!(watch_updates_as_allowed("allowed", current_uid()).await);
+}
+
+#[tokio::test]
+async fn refused_peers_are_dropped() {
+    assert!(!watch_updates_as_allowed("refused", current_uid().wrapping_add(1)).await);
+}
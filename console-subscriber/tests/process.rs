@@ -0,0 +1,30 @@
+use console_subscriber::process::Child;
+use tokio::process::Command;
+
+mod support;
+use support::{assert_resources, ExpectedResource};
+
+#[test]
+fn child_is_recorded_with_its_pid_and_exit() {
+    let expected_resources = vec![ExpectedResource::default()
+        .match_concrete_type("Child")
+        .expect_kind("process")
+        .expect_attribute("pid")
+        .expect_attribute_value("exited", "true")
+        .expect_attribute_value("exit_code", "0")];
+
+    let future = async {
+        // Any program which exits successfully will do, and this test's own
+        // binary is one which is available on every platform.
+        let child = Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("spawning the child failed");
+        let mut child = Child::new(child);
+        let status = child.wait().await.expect("waiting for the child failed");
+        assert!(status.success());
+    };
+
+    assert_resources(expected_resources, future);
+}
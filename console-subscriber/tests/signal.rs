@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use console_subscriber::signal;
+
+mod support;
+use support::{assert_resources, ExpectedResource};
+
+#[test]
+fn listeners_for_a_signal_share_a_resource() {
+    let expected_resources = vec![
+        ExpectedResource::default()
+            .match_concrete_type("CtrlC")
+            .expect_kind("signal")
+            .expect_attribute_value("signal", "ctrl_c")
+            .expect_count(1),
+        #[cfg(unix)]
+        ExpectedResource::default()
+            .match_concrete_type("Signal")
+            .expect_kind("signal")
+            .expect_attribute_value("signal", "SIGUSR1")
+            .expect_count(1),
+    ];
+
+    let future = async {
+        for _ in 0..2 {
+            let _ = tokio::time::timeout(Duration::from_millis(10), signal::ctrl_c()).await;
+        }
+
+        #[cfg(unix)]
+        for _ in 0..2 {
+            let kind = tokio::signal::unix::SignalKind::user_defined1();
+            let mut listener = signal::Signal::new(kind).expect("listening failed");
+            let _ = tokio::time::timeout(Duration::from_millis(10), listener.recv()).await;
+        }
+    };
+
+    assert_resources(expected_resources, future);
+}
@@ -2,10 +2,12 @@ use std::{future::Future, task::Poll};
 
 use tokio::task::JoinHandle;
 
+mod resource;
 mod state;
 mod subscriber;
 mod task;
 
+pub(crate) use resource::ExpectedResource;
 use subscriber::run_test;
 pub(crate) use subscriber::MAIN_TASK_NAME;
 pub(crate) use task::ExpectedTask;
@@ -27,7 +29,7 @@ where
     Fut: Future + Send + 'static,
     Fut::Output: Send + 'static,
 {
-    run_test(vec![expected_task], future)
+    run_test(vec![expected_task], Vec::new(), future)
 }
 
 /// Assert that the `expected_tasks` are recorded by a console-subscriber
@@ -44,7 +46,25 @@ where
     Fut: Future + Send + 'static,
     Fut::Output: Send + 'static,
 {
-    run_test(expected_tasks, future)
+    run_test(expected_tasks, Vec::new(), future)
+}
+
+/// Assert that the `expected_resources` are recorded by a console-subscriber
+/// when driving the provided `future` to completion.
+///
+/// # Panics
+///
+/// This function will panic if the expectations on any of the expected
+/// resources are not met or if matching resources are not recorded for all
+/// expected resources.
+#[track_caller]
+#[allow(dead_code)]
+pub(crate) fn assert_resources<Fut>(expected_resources: Vec<ExpectedResource>, future: Fut)
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    run_test(Vec::new(), expected_resources, future)
 }
 
 /// Spawn a named task and unwrap.
@@ -0,0 +1,300 @@
+use std::{collections::HashMap, error, fmt};
+
+use console_api::{field::Value, resources};
+
+/// An actual resource
+///
+/// This struct contains the values recorded from the console subscriber
+/// client and represents what is known about an actual resource created
+/// during the test.
+#[derive(Clone, Debug)]
+pub(super) struct ActualResource {
+    pub(super) id: u64,
+    pub(super) concrete_type: String,
+    pub(super) kind: Option<String>,
+    /// The resource's attributes, formatted as strings.
+    pub(super) attributes: HashMap<String, String>,
+    pub(super) dropped: bool,
+}
+
+impl ActualResource {
+    pub(super) fn new(resource: &resources::Resource) -> Option<Self> {
+        use resources::resource::kind::Kind;
+
+        let kind = match resource.kind.as_ref()?.kind.as_ref()? {
+            Kind::Known(_) => None,
+            Kind::Other(kind) => Some(kind.clone()),
+        };
+        Some(Self {
+            id: resource.id?.id,
+            concrete_type: resource.concrete_type.clone(),
+            kind,
+            attributes: HashMap::new(),
+            dropped: false,
+        })
+    }
+
+    pub(super) fn update_from_stats(&mut self, stats: &resources::Stats) {
+        for attribute in &stats.attributes {
+            let Some(field) = &attribute.field else {
+                continue;
+            };
+            let Some(console_api::field::Name::StrName(name)) = &field.name else {
+                continue;
+            };
+            let value = match &field.value {
+                Some(Value::StrVal(value)) | Some(Value::DebugVal(value)) => value.clone(),
+                Some(Value::U64Val(value)) => value.to_string(),
+                Some(Value::I64Val(value)) => value.to_string(),
+                Some(Value::BoolVal(value)) => value.to_string(),
+                None => continue,
+            };
+            self.attributes.insert(name.clone(), value);
+        }
+        self.dropped = stats.dropped_at.is_some();
+    }
+}
+
+/// An error in resource validation.
+pub(super) struct ResourceValidationFailure {
+    /// The expected resource whose expectations were not met.
+    expected: ExpectedResource,
+    /// The actual resource which failed the validation
+    actual: Option<ActualResource>,
+    /// A textual description of the validation failure
+    failure: String,
+}
+
+impl error::Error for ResourceValidationFailure {}
+
+impl fmt::Display for ResourceValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.failure)
+    }
+}
+
+impl fmt::Debug for ResourceValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "Resource Validation Failed!\n  Expected Resource: {expected:?}\
+                \n  Actual Resource:   {actual:?}\
+                \n  Failure:           {failure}",
+                expected = self.expected,
+                failure = self.failure,
+            ),
+            None => write!(
+                f,
+                "Resource Validation Failed!\n  Expected Resource: {expected:?}\
+                \n  Actual Resource:   <not found>\
+                \n  Failure:           {failure}",
+                expected = self.expected,
+                failure = self.failure,
+            ),
+        }
+    }
+}
+
+/// An expected resource.
+///
+/// This struct contains the fields that an expected resource will attempt to
+/// match actual resources on, as well as the expectations that will be used
+/// to validate that the actual resource is as expected.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ExpectedResource {
+    match_concrete_type: Option<String>,
+    expect_present: Option<bool>,
+    expect_kind: Option<String>,
+    expect_attributes: Vec<(String, Option<String>)>,
+    expect_dropped: Option<bool>,
+    expect_count: Option<usize>,
+}
+
+#[allow(clippy::result_large_err)]
+impl ExpectedResource {
+    /// Returns whether or not an actual resource matches this expected
+    /// resource.
+    pub(super) fn matches_actual_resource(&self, actual_resource: &ActualResource) -> bool {
+        matches!(
+            &self.match_concrete_type,
+            Some(concrete_type) if *concrete_type == actual_resource.concrete_type
+        )
+    }
+
+    /// Returns an error specifying that no match was found for this expected
+    /// resource.
+    pub(super) fn no_match_error(&self) -> Result<(), ResourceValidationFailure> {
+        Err(ResourceValidationFailure {
+            expected: self.clone(),
+            actual: None,
+            failure: format!("{self}: no matching actual resource was found"),
+        })
+    }
+
+    /// Validates that `count` actual resources match this expected resource,
+    /// if a number of matches is expected.
+    pub(super) fn validate_match_count(
+        &self,
+        count: usize,
+    ) -> Result<(), ResourceValidationFailure> {
+        match self.expect_count {
+            Some(expected_count) if expected_count != count => Err(ResourceValidationFailure {
+                expected: self.clone(),
+                actual: None,
+                failure: format!(
+                    "{self}: expected {expected_count} matching actual resources, but \
+                    found {count}"
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates all expectations against the provided actual resource.
+    ///
+    /// No check that the actual resource matches is performed. That must have
+    /// been done prior.
+    ///
+    /// If all expectations are met, this method returns `Ok(())`. If any
+    /// expectations are not met, then the first incorrect expectation will
+    /// be returned as an `Err`.
+    pub(super) fn validate_actual_resource(
+        &self,
+        actual_resource: &ActualResource,
+    ) -> Result<(), ResourceValidationFailure> {
+        let fail = |failure: String| {
+            Err(ResourceValidationFailure {
+                expected: self.clone(),
+                actual: Some(actual_resource.clone()),
+                failure,
+            })
+        };
+        let mut no_expectations = self.expect_present.is_none() && self.expect_count.is_none();
+
+        if let Some(expected_kind) = &self.expect_kind {
+            no_expectations = false;
+            if Some(expected_kind) != actual_resource.kind.as_ref() {
+                return fail(format!(
+                    "{self}: expected `kind` to be {expected_kind}, but actual \
+                    was {actual_kind:?}",
+                    actual_kind = actual_resource.kind,
+                ));
+            }
+        }
+
+        for (name, expected_value) in &self.expect_attributes {
+            no_expectations = false;
+            let actual_value = actual_resource.attributes.get(name);
+            match (expected_value, actual_value) {
+                (_, None) => {
+                    return fail(format!(
+                        "{self}: expected an attribute named `{name}`, but \
+                        there was none"
+                    ))
+                }
+                (Some(expected_value), Some(actual_value)) if expected_value != actual_value => {
+                    return fail(format!(
+                        "{self}: expected attribute `{name}` to be \
+                        {expected_value}, but actual was {actual_value}"
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(expected_dropped) = self.expect_dropped {
+            no_expectations = false;
+            if expected_dropped != actual_resource.dropped {
+                return fail(format!(
+                    "{self}: expected `dropped` to be {expected_dropped}, but \
+                    actual was {actual_dropped}",
+                    actual_dropped = actual_resource.dropped,
+                ));
+            }
+        }
+
+        if no_expectations {
+            return fail(format!(
+                "{self}: no expectations set, if you want to just expect \
+                that a matching resource is present, use `expect_present()`",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Matches resources by their concrete type.
+    ///
+    /// To match this expected resource, an actual resource must have the
+    /// concrete type `concrete_type`.
+    #[allow(dead_code)]
+    pub(crate) fn match_concrete_type(mut self, concrete_type: &str) -> Self {
+        self.match_concrete_type = Some(concrete_type.to_owned());
+        self
+    }
+
+    /// Expects that a resource is present.
+    ///
+    /// To validate, an actual resource matching this expected resource must
+    /// be found.
+    #[allow(dead_code)]
+    pub(crate) fn expect_present(mut self) -> Self {
+        self.expect_present = Some(true);
+        self
+    }
+
+    /// Expects that a resource is of a specific kind.
+    ///
+    /// To validate, the actual resource must have been recorded with the
+    /// kind `kind`, which isn't one of the kinds known to the console.
+    #[allow(dead_code)]
+    pub(crate) fn expect_kind(mut self, kind: &str) -> Self {
+        self.expect_kind = Some(kind.to_owned());
+        self
+    }
+
+    /// Expects that a resource has an attribute named `name`, whatever its
+    /// value.
+    #[allow(dead_code)]
+    pub(crate) fn expect_attribute(mut self, name: &str) -> Self {
+        self.expect_attributes.push((name.to_owned(), None));
+        self
+    }
+
+    /// Expects that a resource has an attribute named `name`, whose latest
+    /// value, formatted as a string, is `value`.
+    #[allow(dead_code)]
+    pub(crate) fn expect_attribute_value(mut self, name: &str, value: &str) -> Self {
+        self.expect_attributes
+            .push((name.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// Expects that exactly `count` actual resources match this expected
+    /// resource.
+    ///
+    /// The other expectations are validated against the first of them.
+    #[allow(dead_code)]
+    pub(crate) fn expect_count(mut self, count: usize) -> Self {
+        self.expect_count = Some(count);
+        self
+    }
+
+    /// Expects that a resource has been dropped, or that it hasn't.
+    #[allow(dead_code)]
+    pub(crate) fn expect_dropped(mut self, dropped: bool) -> Self {
+        self.expect_dropped = Some(dropped);
+        self
+    }
+}
+
+impl fmt::Display for ExpectedResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = match &self.match_concrete_type {
+            Some(concrete_type) => format!("concrete_type={concrete_type}"),
+            None => "(no fields to match on)".into(),
+        };
+        write!(f, "Resource {{ {fields} }}")
+    }
+}
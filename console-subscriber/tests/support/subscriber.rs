@@ -11,6 +11,7 @@ use tokio::{io::DuplexStream, task};
 use tonic::transport::{Channel, Endpoint, Server, Uri};
 use tower::service_fn;
 
+use super::resource::{ActualResource, ExpectedResource, ResourceValidationFailure};
 use super::state::{TestState, TestStep};
 use super::task::{ActualTask, ExpectedTask, TaskValidationFailure};
 
@@ -19,14 +20,23 @@ const END_SIGNAL_TASK_NAME: &str = "console-test::signal";
 
 #[derive(Debug)]
 struct TestFailure {
-    failures: Vec<TaskValidationFailure>,
+    task_failures: Vec<TaskValidationFailure>,
+    resource_failures: Vec<ResourceValidationFailure>,
 }
 
 impl fmt::Display for TestFailure {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "Task validation failed:")?;
-        for failure in &self.failures {
-            writeln!(f, " - {failure}")?;
+        if !self.task_failures.is_empty() {
+            writeln!(f, "Task validation failed:")?;
+            for failure in &self.task_failures {
+                writeln!(f, " - {failure}")?;
+            }
+        }
+        if !self.resource_failures.is_empty() {
+            writeln!(f, "Resource validation failed:")?;
+            for failure in &self.resource_failures {
+                writeln!(f, " - {failure}")?;
+            }
         }
         Ok(())
     }
@@ -40,10 +50,14 @@ impl fmt::Display for TestFailure {
 /// completion on a current thread tokio runtime.
 ///
 /// This function will panic if the expectations on any of the expected tasks
-/// are not met or if matching tasks are not recorded for all expected tasks.
+/// or resources are not met or if matching tasks or resources are not
+/// recorded for all of them.
 #[track_caller]
-pub(super) fn run_test<Fut>(expected_tasks: Vec<ExpectedTask>, future: Fut)
-where
+pub(super) fn run_test<Fut>(
+    expected_tasks: Vec<ExpectedTask>,
+    expected_resources: Vec<ExpectedResource>,
+    future: Fut,
+) where
     Fut: Future + Send + 'static,
     Fut::Output: Send + 'static,
 {
@@ -51,7 +65,7 @@ where
 
     let (client_stream, server_stream) = tokio::io::duplex(1024);
     let (console_layer, server) = console_subscriber::ConsoleLayer::builder().build();
-    let registry = tracing_subscriber::registry().with(console_layer);
+    let dispatch = tracing::Dispatch::new(tracing_subscriber::registry().with(console_layer));
 
     let mut test_state = TestState::new();
     let mut test_state_test = test_state.clone();
@@ -84,7 +98,7 @@ where
                     .spawn(console_server(server, server_stream, test_state.clone()))
                     .expect("console-test error: could not spawn 'console-server' task");
 
-                let actual_tasks = task::Builder::new()
+                let actual = task::Builder::new()
                     .name("console::client")
                     .spawn(console_client(client_stream, test_state.clone()))
                     .expect("console-test error: could not spawn 'console-client' task")
@@ -92,14 +106,19 @@ where
                     .expect("console-test error: failed to await 'console-client' task");
 
                 test_state.advance_to_step(TestStep::UpdatesRecorded);
-                actual_tasks
+                actual
             })
         })
         .expect("console-test error: console subscriber could not spawn thread");
 
-    tracing::subscriber::with_default(registry, || {
+    tracing::dispatcher::with_default(&dispatch.clone(), || {
         let runtime = tokio::runtime::Builder::new_current_thread()
             .enable_all()
+            // A span which is entered on a thread where the subscriber isn't
+            // the default is never closed, so the subscriber must also be
+            // the default on the blocking pool's threads, where blocking
+            // tasks' spans are entered.
+            .on_thread_start(move || std::mem::forget(tracing::dispatcher::set_default(&dispatch)))
             .build()
             .unwrap();
 
@@ -127,11 +146,16 @@ where
         });
     });
 
-    let actual_tasks = join_handle
+    let (actual_tasks, actual_resources) = join_handle
         .join()
         .expect("console-test error: failed to join 'console-subscriber' thread");
 
-    if let Err(test_failure) = validate_expected_tasks(expected_tasks, actual_tasks) {
+    if let Err(test_failure) = validate_expected(
+        expected_tasks,
+        actual_tasks,
+        expected_resources,
+        actual_resources,
+    ) {
         panic!("Test failed: {test_failure}")
     }
 }
@@ -181,16 +205,19 @@ async fn console_server(
 ///
 /// First we wait until the server has started (test step `ServerStarted`), then
 /// the client is connected to its half of the duplex stream and we start recording
-/// the actual tasks.
+/// the actual tasks and resources.
 ///
-/// Once recording finishes (see [`record_actual_tasks()`] for details on the test
-/// state condition), the actual tasks returned.
+/// Once recording finishes (see [`record_actual()`] for details on the test
+/// state condition), the actual tasks and resources are returned.
 ///
 /// # Test State
 ///
 /// 1. Waits for: `ServerStarted`
 /// 2. Advances to: `ClientConnected`
-async fn console_client(client_stream: DuplexStream, mut test_state: TestState) -> Vec<ActualTask> {
+async fn console_client(
+    client_stream: DuplexStream,
+    mut test_state: TestState,
+) -> (Vec<ActualTask>, Vec<ActualResource>) {
     test_state.wait_for_step(TestStep::ServerStarted).await;
 
     let mut client_stream = Some(client_stream);
@@ -207,8 +234,7 @@ async fn console_client(client_stream: DuplexStream, mut test_state: TestState)
                 // We need to return a Result from this async block, which is
                 // why we don't unwrap the `client` here.
                 client.map(TokioIo::new).ok_or_else(|| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    std::io::Error::other(
                         "console-test error: client already taken. This shouldn't happen.",
                     )
                 })
@@ -218,22 +244,24 @@ async fn console_client(client_stream: DuplexStream, mut test_state: TestState)
         .expect("console-test client error: couldn't create client");
     test_state.advance_to_step(TestStep::ClientConnected);
 
-    record_actual_tasks(channel, test_state).await
+    record_actual(channel, test_state).await
 }
 
-/// Records the actual tasks which are received by the client channel.
+/// Records the actual tasks and resources which are received by the client
+/// channel.
 ///
 /// Updates will be received until the test state reaches the `TestFinished` step
 /// (indicating that the test itself has finished running), at which point we wait
-/// for a final update before returning all the actual tasks which were recorded.
+/// for a final update before returning all the actual tasks and resources which
+/// were recorded.
 ///
 /// # Test State
 ///
 /// 1. Waits for: `TestFinished`
-async fn record_actual_tasks(
+async fn record_actual(
     client_channel: Channel,
     mut test_state: TestState,
-) -> Vec<ActualTask> {
+) -> (Vec<ActualTask>, Vec<ActualResource>) {
     let mut client = InstrumentClient::new(client_channel);
 
     let mut stream = match client
@@ -245,6 +273,7 @@ async fn record_actual_tasks(
     };
 
     let mut tasks = HashMap::new();
+    let mut resources = HashMap::new();
 
     // The console-subscriber aggregator is a bit of an unknown entity for us,
     // especially with respect to its update loops. We can't guarantee that
@@ -289,37 +318,76 @@ async fn record_actual_tasks(
             }
         }
 
+        if let Some(resource_update) = &update.resource_update {
+            for new_resource in &resource_update.new_resources {
+                if let Some(actual_resource) = ActualResource::new(new_resource) {
+                    resources.insert(actual_resource.id, actual_resource);
+                }
+            }
+
+            for (id, stats) in &resource_update.stats_update {
+                if let Some(resource) = resources.get_mut(id) {
+                    resource.update_from_stats(stats);
+                }
+            }
+        }
+
         if test_state.is_step(TestStep::TestFinished) && signal_task_read {
             // Once the test finishes running and we've read the signal task, the test ends.
             break;
         }
     }
 
-    tasks.into_values().collect()
+    let names: HashMap<u64, Option<String>> = tasks
+        .iter()
+        .map(|(id, task)| (*id, task.name.clone()))
+        .collect();
+    for task in tasks.values_mut() {
+        let aborted_by = task.abort.as_ref().and_then(|abort| abort.aborted_by);
+        if let Some(aborted_by) = aborted_by {
+            task.aborted_by = names.get(&aborted_by.id).cloned().flatten();
+        }
+    }
+
+    (
+        tasks.into_values().collect(),
+        resources.into_values().collect(),
+    )
 }
 
-/// Validate the expected tasks against the actual tasks.
+/// Validate the expected tasks and resources against the actual ones.
 ///
-/// Each expected task is checked in turn.
+/// Each expected task and resource is checked in turn.
 ///
-/// A matching actual task is searched for. If one is found it, the
-/// expected task is validated against the actual task.
+/// A matching actual task or resource is searched for. If one is found it,
+/// the expectations are validated against it.
 ///
 /// Any validation errors result in failure. If no matches
-fn validate_expected_tasks(
+#[allow(clippy::result_large_err)]
+fn validate_expected(
     expected_tasks: Vec<ExpectedTask>,
     actual_tasks: Vec<ActualTask>,
+    expected_resources: Vec<ExpectedResource>,
+    actual_resources: Vec<ActualResource>,
 ) -> Result<(), TestFailure> {
-    let failures: Vec<_> = expected_tasks
+    let task_failures: Vec<_> = expected_tasks
         .iter()
         .map(|expected| validate_expected_task(expected, &actual_tasks))
         .filter_map(Result::err)
         .collect();
+    let resource_failures: Vec<_> = expected_resources
+        .iter()
+        .map(|expected| validate_expected_resource(expected, &actual_resources))
+        .filter_map(Result::err)
+        .collect();
 
-    if failures.is_empty() {
+    if task_failures.is_empty() && resource_failures.is_empty() {
         Ok(())
     } else {
-        Err(TestFailure { failures })
+        Err(TestFailure {
+            task_failures,
+            resource_failures,
+        })
     }
 }
 
@@ -338,3 +406,21 @@ fn validate_expected_task(
 
     expected.no_match_error()
 }
+
+#[allow(clippy::result_large_err)]
+fn validate_expected_resource(
+    expected: &ExpectedResource,
+    actual_resources: &[ActualResource],
+) -> Result<(), ResourceValidationFailure> {
+    let matching: Vec<_> = actual_resources
+        .iter()
+        .filter(|actual| expected.matches_actual_resource(actual))
+        .collect();
+    match matching.first() {
+        Some(actual) => {
+            expected.validate_match_count(matching.len())?;
+            expected.validate_actual_resource(actual)
+        }
+        None => expected.no_match_error(),
+    }
+}
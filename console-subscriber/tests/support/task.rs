@@ -16,6 +16,12 @@ pub(super) struct ActualTask {
     pub(super) wakes: u64,
     pub(super) self_wakes: u64,
     pub(super) polls: u64,
+    pub(super) panic: Option<tasks::Panic>,
+    pub(super) abort: Option<tasks::Abort>,
+    /// The name of the task which aborted this one, resolved from
+    /// `abort.aborted_by` once all the tasks have been read.
+    pub(super) aborted_by: Option<String>,
+    pub(super) end_reason: Option<tasks::EndReason>,
 }
 
 impl ActualTask {
@@ -26,6 +32,10 @@ impl ActualTask {
             wakes: 0,
             self_wakes: 0,
             polls: 0,
+            panic: None,
+            abort: None,
+            aborted_by: None,
+            end_reason: None,
         }
     }
 
@@ -35,6 +45,15 @@ impl ActualTask {
         if let Some(poll_stats) = &stats.poll_stats {
             self.polls = poll_stats.polls;
         }
+        if stats.panic.is_some() {
+            self.panic.clone_from(&stats.panic);
+        }
+        if stats.abort.is_some() {
+            self.abort.clone_from(&stats.abort);
+        }
+        if let Some(end_reason) = stats.end_reason {
+            self.end_reason = tasks::EndReason::try_from(end_reason).ok();
+        }
     }
 }
 
@@ -91,6 +110,9 @@ pub(crate) struct ExpectedTask {
     expect_wakes: Option<u64>,
     expect_self_wakes: Option<u64>,
     expect_polls: Option<u64>,
+    expect_panic: Option<(String, String)>,
+    expect_aborted_by: Option<(String, String)>,
+    expect_end_reason: Option<tasks::EndReason>,
 }
 
 #[allow(clippy::result_large_err)]
@@ -182,6 +204,81 @@ impl ExpectedTask {
             }
         }
 
+        if let Some((expected_message, expected_file)) = &self.expect_panic {
+            no_expectations = false;
+            let fail = |failure| {
+                Err(TaskValidationFailure {
+                    expected: self.clone(),
+                    actual: Some(actual_task.clone()),
+                    failure,
+                })
+            };
+            let Some(panic) = &actual_task.panic else {
+                return fail(format!("{self}: expected a panic, but there was none"));
+            };
+            if panic.message.as_ref() != Some(expected_message) {
+                return fail(format!(
+                    "{self}: expected the panic's message to be \
+                    {expected_message:?}, but actual was {actual_message:?}",
+                    actual_message = panic.message,
+                ));
+            }
+            let actual_file = panic.location.as_ref().and_then(|l| l.file.as_ref());
+            let has_line = panic.location.as_ref().is_some_and(|l| l.line.is_some());
+            if actual_file != Some(expected_file) || !has_line {
+                return fail(format!(
+                    "{self}: expected the panic's location to be a line in \
+                    {expected_file}, but actual was {actual_location:?}",
+                    actual_location = panic.location,
+                ));
+            }
+        }
+
+        if let Some((expected_name, expected_file)) = &self.expect_aborted_by {
+            no_expectations = false;
+            let fail = |failure| {
+                Err(TaskValidationFailure {
+                    expected: self.clone(),
+                    actual: Some(actual_task.clone()),
+                    failure,
+                })
+            };
+            let Some(abort) = &actual_task.abort else {
+                return fail(format!("{self}: expected an abort, but there was none"));
+            };
+            if actual_task.aborted_by.as_ref() != Some(expected_name) {
+                return fail(format!(
+                    "{self}: expected to be aborted by {expected_name}, \
+                    but actual was {actual_name:?}",
+                    actual_name = actual_task.aborted_by,
+                ));
+            }
+            let actual_file = abort.location.as_ref().and_then(|l| l.file.as_ref());
+            let has_line = abort.location.as_ref().is_some_and(|l| l.line.is_some());
+            if actual_file != Some(expected_file) || !has_line {
+                return fail(format!(
+                    "{self}: expected the abort's location to be a line in \
+                    {expected_file}, but actual was {actual_location:?}",
+                    actual_location = abort.location,
+                ));
+            }
+        }
+
+        if let Some(expected_end_reason) = self.expect_end_reason {
+            no_expectations = false;
+            if actual_task.end_reason != Some(expected_end_reason) {
+                return Err(TaskValidationFailure {
+                    expected: self.clone(),
+                    actual: Some(actual_task.clone()),
+                    failure: format!(
+                        "{self}: expected to end with {expected_end_reason:?}, \
+                        but actual was {actual_end_reason:?}",
+                        actual_end_reason = actual_task.end_reason,
+                    ),
+                });
+            }
+        }
+
         if no_expectations {
             return Err(TaskValidationFailure {
                 expected: self.clone(),
@@ -257,6 +354,38 @@ impl ExpectedTask {
         self.expect_polls = Some(polls);
         self
     }
+
+    /// Expects that a task panicked with the message `message`, somewhere in
+    /// the file `file`.
+    ///
+    /// To validate, the actual task's stats must have recorded a panic with
+    /// that message, at a location with a line in `file`.
+    #[allow(dead_code)]
+    pub(crate) fn expect_panic(mut self, message: &str, file: &str) -> Self {
+        self.expect_panic = Some((message.to_owned(), file.to_owned()));
+        self
+    }
+
+    /// Expects that a task was aborted by the task named `name`, somewhere in
+    /// the file `file`.
+    ///
+    /// To validate, the actual task's stats must have recorded an abort by a
+    /// task with that name, at a location with a line in `file`.
+    #[allow(dead_code)]
+    pub(crate) fn expect_aborted_by(mut self, name: &str, file: &str) -> Self {
+        self.expect_aborted_by = Some((name.to_owned(), file.to_owned()));
+        self
+    }
+
+    /// Expects that a task ended for the reason `end_reason`.
+    ///
+    /// To validate, the actual task must have ended, and its stats must have
+    /// recorded that reason.
+    #[allow(dead_code)]
+    pub(crate) fn expect_end_reason(mut self, end_reason: tasks::EndReason) -> Self {
+        self.expect_end_reason = Some(end_reason);
+        self
+    }
 }
 
 impl fmt::Display for ExpectedTask {
@@ -0,0 +1,233 @@
+#![cfg(tokio_unstable)]
+
+use std::{future, thread, time::Duration};
+
+use console_api::{
+    field::{Name, Value},
+    instrument::{
+        instrument_client::InstrumentClient, AbortTaskRequest, DumpTaskRequest, InstrumentRequest,
+        ServerInfoRequest, Update,
+    },
+    Id,
+};
+use console_subscriber::{ConsoleLayer, ServerParts};
+use hyper_util::rt::TokioIo;
+use tokio::task::JoinHandle;
+use tonic::{
+    transport::{Channel, Endpoint, Server, Uri},
+    Code, Streaming,
+};
+use tower::service_fn;
+use tracing_subscriber::prelude::*;
+
+/// Serves the console over a duplex stream, from a thread which isn't
+/// instrumented, and returns the dispatcher which records the tasks it
+/// serves, with a client connected to it.
+async fn serve() -> (tracing::Dispatch, InstrumentClient<Channel>) {
+    let (layer, server) = ConsoleLayer::builder()
+        .publish_interval(Duration::from_millis(10))
+        .build();
+    let dispatch = tracing::Dispatch::new(tracing_subscriber::registry().with(layer));
+    let (client_stream, server_stream) = tokio::io::duplex(1024);
+    thread::spawn(move || {
+        // Besides keeping the server's own tasks out of the updates, this
+        // registers a second dispatcher, so that callsites' interest isn't
+        // only decided by whichever thread first reaches them.
+        let _subscriber =
+            tracing::subscriber::set_default(tracing_core::subscriber::NoSubscriber::default());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let ServerParts {
+                instrument_server,
+                aggregator,
+                ..
+            } = server.into_parts();
+            tokio::spawn(aggregator.run());
+            Server::builder()
+                .add_service(instrument_server)
+                .serve_with_incoming(futures::stream::iter([Ok::<_, std::io::Error>(
+                    server_stream,
+                )]))
+                .await
+                .unwrap();
+            // The server returns once it's accepted the connection, which
+            // its runtime keeps serving.
+            future::pending::<()>().await
+        })
+    });
+
+    let mut client_stream = Some(client_stream);
+    let channel = Endpoint::try_from("http://[::]:6669")
+        .unwrap()
+        .connect_with_connector(service_fn(move |_: Uri| {
+            let client = client_stream.take();
+            async move {
+                client
+                    .map(TokioIo::new)
+                    .ok_or_else(|| std::io::Error::other("client already taken"))
+            }
+        }))
+        .await
+        .unwrap();
+    (dispatch, InstrumentClient::new(channel))
+}
+
+/// Returns the span IDs of the tasks spawned with `handles`, once updates
+/// have included all of them.
+async fn span_ids<const N: usize>(
+    updates: &mut Streaming<Update>,
+    handles: [&JoinHandle<()>; N],
+) -> [Id; N] {
+    let tokio_ids = handles.map(|handle| handle.id().to_string().parse::<u64>().unwrap());
+    let mut span_ids: [Option<Id>; N] = std::array::from_fn(|_| None);
+    while span_ids.iter().any(Option::is_none) {
+        let update = updates.message().await.unwrap().expect("updates ended");
+        for task in update.task_update.into_iter().flat_map(|u| u.new_tasks) {
+            let tokio_id = task
+                .fields
+                .iter()
+                .find_map(|field| match (&field.name, &field.value) {
+                    (Some(Name::StrName(name)), Some(Value::U64Val(id))) if name == "task.id" => {
+                        Some(*id)
+                    }
+                    _ => None,
+                });
+            if let Some(i) = tokio_id.and_then(|id| tokio_ids.iter().position(|&t| t == id)) {
+                span_ids[i] = task.id;
+            }
+        }
+    }
+    span_ids.map(Option::unwrap)
+}
+
+#[tokio::test]
+async fn clients_abort_allowed_tasks() {
+    let (dispatch, mut client) = serve().await;
+
+    let info = client
+        .get_server_info(ServerInfoRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(info.capabilities.iter().any(|c| c == "abort_task"));
+
+    let mut updates = client
+        .watch_updates(InstrumentRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    // Only the tasks under test are instrumented, rather than the client.
+    let (allowed, unallowed) = tracing::dispatcher::with_default(&dispatch, || {
+        let allowed = tokio::spawn(future::pending::<()>());
+        console_subscriber::allow_abort(&allowed);
+        (allowed, tokio::spawn(future::pending::<()>()))
+    });
+    let [allowed_id, unallowed_id] = span_ids(&mut updates, [&allowed, &unallowed]).await;
+
+    // Tasks which weren't allowed to be aborted can't be.
+    let error = client
+        .abort_task(AbortTaskRequest {
+            id: Some(unallowed_id),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::FailedPrecondition);
+    assert!(!unallowed.is_finished());
+
+    let error = client
+        .abort_task(AbortTaskRequest {
+            id: Some(Id { id: u64::MAX }),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+
+    client
+        .abort_task(AbortTaskRequest {
+            id: Some(allowed_id),
+        })
+        .await
+        .unwrap();
+    let error = tokio::time::timeout(Duration::from_secs(10), allowed)
+        .await
+        .expect("the task wasn't aborted")
+        .unwrap_err();
+    assert!(error.is_cancelled());
+
+    // The task's stats record that it was aborted.
+    loop {
+        let update = updates.message().await.unwrap().expect("updates ended");
+        let stats = update
+            .task_update
+            .and_then(|mut u| u.stats_update.remove(&allowed_id.id));
+        if stats.is_some_and(|stats| stats.abort.is_some()) {
+            break;
+        }
+    }
+
+    // A task can only be aborted once.
+    let error = client
+        .abort_task(AbortTaskRequest {
+            id: Some(allowed_id),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::FailedPrecondition);
+    unallowed.abort();
+}
+
+#[tokio::test]
+async fn clients_dump_tasks() {
+    let (dispatch, mut client) = serve().await;
+    let mut updates = client
+        .watch_updates(InstrumentRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    let task = tracing::dispatcher::with_default(&dispatch, || {
+        tokio::task::Builder::new()
+            .name("dumped")
+            .spawn(future::pending::<()>())
+            .unwrap()
+    });
+    let [id] = span_ids(&mut updates, [&task]).await;
+
+    let dump = client
+        .dump_task(DumpTaskRequest { id: Some(id) })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(dump.now.is_some());
+    let dumped = dump.task.expect("the dump should include the task");
+    assert_eq!(dumped.id, Some(id));
+    assert!(
+        dumped.fields.iter().any(|field| matches!(
+            (&field.name, &field.value),
+            (Some(Name::StrName(name)), Some(Value::DebugVal(value)))
+                if name == "task.name" && value.contains("dumped")
+        )),
+        "{dumped:?}"
+    );
+    let location = dumped
+        .location
+        .expect("the dump should include where the task was spawned");
+    assert_eq!(location.file.as_deref(), Some(file!()));
+    let stats = dump
+        .stats
+        .expect("the dump should include the task's stats");
+    assert!(stats.created_at.is_some());
+    // The subscriber can't capture where a task is suspended.
+    assert_eq!(dump.backtrace, None);
+
+    let error = client
+        .dump_task(DumpTaskRequest {
+            id: Some(Id { id: u64::MAX }),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+    task.abort();
+}
@@ -0,0 +1,98 @@
+//! Compares sorting every task in the task list again after an update, as
+//! the task list used to, with moving only the tasks whose sort keys the
+//! update changed.
+//!
+//! Run with `cargo bench -p tokio-console`.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+#[path = "../src/view/sort.rs"]
+#[allow(dead_code, unused_imports)]
+mod sort;
+
+const TASKS: usize = 10_000;
+
+struct Task {
+    busy: Duration,
+}
+
+/// The key which the task list sorts a task by, when it's sorted by busy
+/// time.
+fn busy(task: &Weak<RefCell<Task>>) -> Option<Duration> {
+    task.upgrade().map(|task| task.borrow().busy)
+}
+
+/// A xorshift generator, so that every run sorts the same tasks.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+fn refresh(c: &mut Criterion) {
+    let mut group = c.benchmark_group("refresh 10k tasks");
+    // How many of the tasks each update changes the busy time of.
+    for changed in [10, 100, 1_000, TASKS] {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        let tasks = (0..TASKS)
+            .map(|_| {
+                let busy = Duration::from_micros(random.below(1_000_000));
+                Rc::new(RefCell::new(Task { busy }))
+            })
+            .collect::<Vec<_>>();
+        let mut items = tasks.iter().map(Rc::downgrade).collect::<Vec<_>>();
+        let mut keys = Vec::new();
+        let mut update = move || {
+            for _ in 0..changed {
+                let task = &tasks[random.below(TASKS as u64) as usize];
+                task.borrow_mut().busy += Duration::from_micros(random.below(100_000));
+            }
+        };
+
+        // Each iteration starts from the tasks sorted as of the last update,
+        // and sorts them after the next one.
+        group.bench_with_input(
+            BenchmarkId::new("sort every task", changed),
+            &changed,
+            |b, _| {
+                b.iter_batched_ref(
+                    || {
+                        items.sort_by_cached_key(busy);
+                        update();
+                        items.clone()
+                    },
+                    |items| items.sort_by_cached_key(busy),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("reposition changed tasks", changed),
+            &changed,
+            |b, _| {
+                b.iter_batched_ref(
+                    || {
+                        sort::reposition(&mut items, &mut keys, busy, Ord::cmp);
+                        update();
+                        (items.clone(), keys.clone())
+                    },
+                    |(items, keys)| sort::reposition(items, keys, busy, Ord::cmp),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, refresh);
+criterion_main!(benches);
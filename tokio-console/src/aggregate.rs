@@ -0,0 +1,396 @@
+//! Merging many targets into one, which a console can connect to.
+//!
+//! The aggregator connects to each of its sources, such as every replica of
+//! a service, and serves their tasks, resources and async ops through a
+//! [relay](crate::relay), as though they were all in one program. Each
+//! source's IDs are remapped, so that they don't collide with each other's,
+//! and each entity is tagged with a `source` field naming where it came from,
+//! which the console can filter and group by.
+use crate::{
+    conn,
+    relay::{self, DetailsStream, Relay},
+};
+use color_eyre::eyre::eyre;
+use console_api::{
+    field,
+    instrument::{TaskDetailsRequest, TasksDetailsRequest, Update},
+    tasks, Attribute, Field, Id, MetaId, SpanId,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tonic::{transport::Uri, Status};
+
+/// The name of the field each entity is tagged with, naming its source.
+const SOURCE_FIELD: &str = "source";
+
+/// One of the targets being aggregated.
+struct Source {
+    /// The source's name, as it was given.
+    name: String,
+    target: relay::Target,
+}
+
+/// Maps each source's IDs to the aggregator's.
+#[derive(Default)]
+struct Ids {
+    tasks: IdMap,
+    resources: IdMap,
+    async_ops: IdMap,
+    metadata: IdMap,
+}
+
+/// A mapping from sources' IDs of one kind to the aggregator's, and back.
+#[derive(Default)]
+struct IdMap {
+    next: u64,
+    ids: HashMap<(usize, u64), u64>,
+    sources: HashMap<u64, (usize, u64)>,
+}
+
+/// The aggregator's side of the relay, which requests a task's details from
+/// the source it came from.
+struct Upstream {
+    sources: Arc<Vec<Source>>,
+    ids: Arc<Mutex<Ids>>,
+}
+
+/// Connects to each of `sources`, which are pairs of names and targets, and
+/// serves their merged state at `addr` until the process is stopped.
+pub(crate) async fn run(
+    addr: SocketAddr,
+    sources: Vec<(String, Uri)>,
+    retain_for: Option<Duration>,
+) -> color_eyre::Result<()> {
+    if sources.is_empty() {
+        return Err(eyre!("no targets to aggregate"));
+    }
+    let sources = Arc::new(
+        sources
+            .into_iter()
+            .map(|(name, target)| Source {
+                name,
+                target: relay::Target(target),
+            })
+            .collect::<Vec<_>>(),
+    );
+    let ids = Arc::new(Mutex::new(Ids::default()));
+    let upstream = Upstream {
+        sources: sources.clone(),
+        ids: ids.clone(),
+    };
+    let relay = Relay::serve(addr, upstream, retain_for).await?;
+    eprintln!("aggregating {} targets at {addr}", sources.len());
+
+    let mut messages = stream::select_all(sources.iter().enumerate().map(|(index, source)| {
+        let conn = conn::Connection::new(source.target.0.clone());
+        stream::unfold(conn, move |mut conn| async move {
+            let message = conn.next_message().await;
+            Some(((index, message), conn))
+        })
+        .boxed()
+    }));
+    while let Some((index, message)) = messages.next().await {
+        // The aggregate's state isn't any one source's, and it can't be
+        // paused, so only updates are passed on.
+        if let conn::Message::Update(mut update) = message {
+            ids.lock()
+                .unwrap()
+                .remap_update(index, &sources[index].name, &mut update);
+            relay.publish(&conn::Message::Update(update));
+        }
+    }
+    Ok(())
+}
+
+// === impl Ids ===
+
+impl Ids {
+    /// Rewrites every ID in `update`, from the source at `index`, to the
+    /// aggregator's, and tags its entities with the source's `name`.
+    fn remap_update(&mut self, index: usize, name: &str, update: &mut Update) {
+        if let Some(ref mut new_metadata) = update.new_metadata {
+            for metadata in &mut new_metadata.metadata {
+                self.metadata.remap(index, &mut metadata.id);
+            }
+        }
+
+        if let Some(ref mut task_update) = update.task_update {
+            for task in &mut task_update.new_tasks {
+                self.tasks.remap(index, &mut task.id);
+                self.metadata.remap(index, &mut task.metadata);
+                for field in &mut task.fields {
+                    self.remap_field(index, field);
+                }
+                for parent in &mut task.parents {
+                    parent.id = self.tasks.id_for(index, parent.id);
+                }
+                task.fields.push(source_field(name));
+            }
+            task_update.stats_update = std::mem::take(&mut task_update.stats_update)
+                .into_iter()
+                .map(|(id, mut stats)| {
+                    if let Some(ref mut abort) = stats.abort {
+                        self.tasks.remap(index, &mut abort.aborted_by);
+                    }
+                    (self.tasks.id_for(index, id), stats)
+                })
+                .collect();
+        }
+
+        if let Some(ref mut resource_update) = update.resource_update {
+            for resource in &mut resource_update.new_resources {
+                self.resources.remap(index, &mut resource.id);
+                self.resources
+                    .remap(index, &mut resource.parent_resource_id);
+                self.metadata.remap(index, &mut resource.metadata);
+            }
+            resource_update.stats_update = std::mem::take(&mut resource_update.stats_update)
+                .into_iter()
+                .map(|(id, mut stats)| {
+                    self.remap_attributes(index, name, &mut stats.attributes);
+                    (self.resources.id_for(index, id), stats)
+                })
+                .collect();
+            for poll_op in &mut resource_update.new_poll_ops {
+                self.metadata.remap(index, &mut poll_op.metadata);
+                self.resources.remap(index, &mut poll_op.resource_id);
+                self.tasks.remap(index, &mut poll_op.task_id);
+                self.async_ops.remap(index, &mut poll_op.async_op_id);
+            }
+        }
+
+        if let Some(ref mut async_op_update) = update.async_op_update {
+            for async_op in &mut async_op_update.new_async_ops {
+                self.async_ops.remap(index, &mut async_op.id);
+                self.async_ops
+                    .remap(index, &mut async_op.parent_async_op_id);
+                self.resources.remap(index, &mut async_op.resource_id);
+                self.metadata.remap(index, &mut async_op.metadata);
+            }
+            async_op_update.stats_update = std::mem::take(&mut async_op_update.stats_update)
+                .into_iter()
+                .map(|(id, mut stats)| {
+                    self.tasks.remap(index, &mut stats.task_id);
+                    self.remap_attributes(index, name, &mut stats.attributes);
+                    (self.async_ops.id_for(index, id), stats)
+                })
+                .collect();
+        }
+    }
+
+    fn remap_field(&mut self, index: usize, field: &mut Field) {
+        if let Some(ref mut metadata_id) = field.metadata_id {
+            metadata_id.id = self.metadata.id_for(index, metadata_id.id);
+        }
+    }
+
+    fn remap_attributes(&mut self, index: usize, name: &str, attributes: &mut Vec<Attribute>) {
+        for attribute in attributes.iter_mut() {
+            if let Some(ref mut field) = attribute.field {
+                self.remap_field(index, field);
+            }
+        }
+        attributes.push(Attribute {
+            field: Some(source_field(name)),
+            unit: None,
+        });
+    }
+}
+
+/// Returns the field tagging an entity with the source it came from.
+fn source_field(name: &str) -> Field {
+    Field {
+        name: Some(field::Name::StrName(SOURCE_FIELD.to_owned())),
+        value: Some(field::Value::StrVal(name.to_owned())),
+        metadata_id: None,
+    }
+}
+
+// === impl IdMap ===
+
+impl IdMap {
+    fn id_for(&mut self, index: usize, id: u64) -> u64 {
+        *self.ids.entry((index, id)).or_insert_with(|| {
+            self.next += 1;
+            self.sources.insert(self.next, (index, id));
+            self.next
+        })
+    }
+
+    fn remap<T: RawId>(&mut self, index: usize, id: &mut Option<T>) {
+        if let Some(id) = id {
+            let raw = self.id_for(index, id.raw());
+            id.set_raw(raw);
+        }
+    }
+
+    /// Returns the source an aggregated ID came from, and its ID there.
+    fn source_of(&self, id: u64) -> Option<(usize, u64)> {
+        self.sources.get(&id).copied()
+    }
+}
+
+/// The ID messages, which all wrap a `u64`.
+trait RawId {
+    fn raw(&self) -> u64;
+    fn set_raw(&mut self, id: u64);
+}
+
+macro_rules! impl_raw_id {
+    ($($ty:ty),+) => {
+        $(
+            impl RawId for $ty {
+                fn raw(&self) -> u64 {
+                    self.id
+                }
+
+                fn set_raw(&mut self, id: u64) {
+                    self.id = id;
+                }
+            }
+        )+
+    };
+}
+
+impl_raw_id!(Id, MetaId, SpanId);
+
+// === impl Upstream ===
+
+impl Upstream {
+    /// Returns the task with the aggregated ID `id`'s source, and its ID
+    /// there.
+    fn source_of(&self, id: Option<Id>) -> Option<(usize, u64)> {
+        let id = id?.id;
+        self.ids.lock().unwrap().tasks.source_of(id)
+    }
+
+    /// Maps the task IDs in details from the source at `index` back to the
+    /// aggregator's.
+    fn remap_details(&self, index: usize, details: DetailsStream) -> DetailsStream {
+        let ids = self.ids.clone();
+        details
+            .map_ok(move |mut details: tasks::TaskDetails| {
+                ids.lock().unwrap().tasks.remap(index, &mut details.task_id);
+                details
+            })
+            .boxed()
+    }
+}
+
+#[tonic::async_trait]
+impl relay::Upstream for Upstream {
+    async fn watch_task_details(
+        &self,
+        request: TaskDetailsRequest,
+    ) -> Result<DetailsStream, Status> {
+        let (index, id) = self
+            .source_of(request.id)
+            .ok_or_else(|| Status::not_found("no such task"))?;
+        let request = TaskDetailsRequest {
+            id: Some(Id { id }),
+        };
+        let details = self.sources[index]
+            .target
+            .watch_task_details(request)
+            .await?;
+        Ok(self.remap_details(index, details))
+    }
+
+    async fn watch_tasks_details(
+        &self,
+        request: TasksDetailsRequest,
+    ) -> Result<DetailsStream, Status> {
+        let mut by_source = HashMap::<usize, Vec<Id>>::new();
+        for id in &request.ids {
+            if let Some((index, id)) = self.source_of(Some(*id)) {
+                by_source.entry(index).or_default().push(Id { id });
+            }
+        }
+        if by_source.is_empty() {
+            return Err(Status::not_found("none of the tasks exist"));
+        }
+        let mut streams = Vec::with_capacity(by_source.len());
+        for (index, ids) in by_source {
+            let details = self.sources[index]
+                .target
+                .watch_tasks_details(TasksDetailsRequest { ids })
+                .await?;
+            streams.push(self.remap_details(index, details));
+        }
+        Ok(stream::select_all(streams).boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use console_api::{register_metadata::NewMetadata, RegisterMetadata};
+
+    fn update(task_ids: &[u64]) -> Update {
+        Update {
+            task_update: Some(tasks::TaskUpdate {
+                new_tasks: task_ids
+                    .iter()
+                    .map(|&id| tasks::Task {
+                        id: Some(Id { id }),
+                        metadata: Some(MetaId { id: 1 }),
+                        ..Default::default()
+                    })
+                    .collect(),
+                stats_update: task_ids
+                    .iter()
+                    .map(|&id| (id, tasks::Stats::default()))
+                    .collect(),
+                dropped_events: 0,
+            }),
+            new_metadata: Some(RegisterMetadata {
+                metadata: vec![NewMetadata {
+                    id: Some(MetaId { id: 1 }),
+                    metadata: None,
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sources_ids_dont_collide() {
+        let mut ids = Ids::default();
+        let mut a = update(&[1, 2]);
+        let mut b = update(&[1]);
+        ids.remap_update(0, "a", &mut a);
+        ids.remap_update(1, "b", &mut b);
+
+        let task_ids = |update: &Update| {
+            let tasks = update.task_update.as_ref().unwrap();
+            let mut stats = tasks.stats_update.keys().copied().collect::<Vec<_>>();
+            stats.sort_unstable();
+            let new = tasks
+                .new_tasks
+                .iter()
+                .map(|task| task.id.as_ref().unwrap().id)
+                .collect::<Vec<_>>();
+            (new, stats)
+        };
+        assert_eq!(task_ids(&a), (vec![1, 2], vec![1, 2]));
+        assert_eq!(task_ids(&b), (vec![3], vec![3]));
+        assert_eq!(ids.tasks.source_of(3), Some((1, 1)));
+
+        // Each source's metadata gets its own ID, which its tasks refer to.
+        let task = &b.task_update.as_ref().unwrap().new_tasks[0];
+        assert_eq!(task.metadata.as_ref().unwrap().id, 2);
+        assert_eq!(
+            b.new_metadata.as_ref().unwrap().metadata[0].id,
+            Some(MetaId { id: 2 })
+        );
+
+        // Tasks are tagged with their source.
+        let source = task.fields.last().unwrap();
+        assert_eq!(source.value, Some(field::Value::StrVal("b".to_owned())));
+    }
+}
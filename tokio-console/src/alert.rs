@@ -0,0 +1,386 @@
+//! Alerts, which watch the tasks for conditions such as a task being busy for
+//! too long, and make sure the user notices as soon as one is triggered, so
+//! that the console can be left watching a target during a load test.
+use crate::{
+    highlight::{self, Condition},
+    i18n::tr_fmt,
+    notify,
+    state::{store::SpanId, State},
+    util,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt, io,
+    process::Command,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// A rule which alerts the user when any task matches its condition.
+///
+/// Rules are written as `<condition>` or `<condition> in <window>`, such as
+/// `busy > 2s` or `self_wakes > 100 in 10s`. Conditions are written as in
+/// [highlight rules](crate::highlight::Highlight). With a window, each
+/// expression in the condition is how much it grew over the window, rather
+/// than its value, so `busy > 2s in 10s` matches tasks which were busy for
+/// more than two of the last ten seconds.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Alert {
+    /// The rule as it was written.
+    source: String,
+    condition: Condition,
+    window: Option<Duration>,
+}
+
+/// A command which is run whenever an alert is triggered, such as a script
+/// which posts to a webhook.
+///
+/// The command is split on whitespace, and is told about the alert by the
+/// `TOKIO_CONSOLE_ALERT` and `TOKIO_CONSOLE_ALERT_MESSAGE` environment
+/// variables, which hold the rule and a description of what triggered it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct AlertCommand {
+    /// The command as it was written.
+    source: String,
+    program: String,
+    args: Vec<String>,
+}
+
+/// Checks each update against the configured alerts, and tells the user of
+/// the ones which have been triggered.
+#[derive(Debug)]
+pub(crate) struct Alerts {
+    watches: Vec<Watch>,
+    /// Whether to ring the terminal bell when an alert is triggered.
+    bell: bool,
+    command: Option<AlertCommand>,
+}
+
+/// An alert, and what has been seen of the tasks it's checked against.
+#[derive(Debug)]
+struct Watch {
+    alert: Alert,
+    /// For alerts with a window, each task's history over the window.
+    ///
+    /// The oldest values are from the last update before the window, so that
+    /// how much they grew over the whole window is known.
+    samples: HashMap<SpanId, History>,
+    /// The tasks which matched the condition as of the last update, which
+    /// don't trigger the alert again until they stop matching it.
+    matching: HashSet<SpanId>,
+}
+
+/// The values of a condition's expressions for a task, as of each update in
+/// an alert's window, oldest first.
+type History = VecDeque<(SystemTime, Vec<Option<f64>>)>;
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+// === impl Alert ===
+
+impl Alert {
+    const ENV_RULE: &'static str = "TOKIO_CONSOLE_ALERT";
+    const ENV_MESSAGE: &'static str = "TOKIO_CONSOLE_ALERT_MESSAGE";
+}
+
+impl FromStr for Alert {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A window is only split off if it's a duration, so that a condition
+        // can't be mistaken for one.
+        let (condition, window) = match s.rsplit_once(" in ") {
+            Some((condition, window)) => match window.trim().parse::<humantime::Duration>() {
+                Ok(window) if window.is_zero() => {
+                    return Err(ParseError(format!(
+                        "the window of alert rule `{}` must be longer than zero",
+                        s.trim()
+                    )))
+                }
+                Ok(window) => (condition, Some(window.into())),
+                Err(_) => (s, None),
+            },
+            None => (s, None),
+        };
+        let condition = condition
+            .parse::<Condition>()
+            .map_err(|e: highlight::ParseError| {
+                ParseError(format!("invalid alert rule `{}`: {e}", s.trim()))
+            })?;
+        Ok(Self {
+            source: s.trim().to_owned(),
+            condition,
+            window,
+        })
+    }
+}
+
+// Rules are compared by how they're written, since the parsed rule contains
+// floating-point numbers.
+impl PartialEq for Alert {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Alert {}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Alert {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Alert> for String {
+    fn from(alert: Alert) -> Self {
+        alert.source
+    }
+}
+
+// === impl AlertCommand ===
+
+impl AlertCommand {
+    /// Runs the command for the triggered `alert`, without waiting for it to
+    /// exit.
+    fn run(&self, alert: &Alert, message: &str) -> io::Result<()> {
+        util::spawn_detached(
+            Command::new(&self.program)
+                .args(&self.args)
+                .env(Alert::ENV_RULE, &alert.source)
+                .env(Alert::ENV_MESSAGE, message),
+        )
+    }
+}
+
+impl FromStr for AlertCommand {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace().map(str::to_owned);
+        let program = words
+            .next()
+            .ok_or_else(|| ParseError("the alert command is empty".to_owned()))?;
+        Ok(Self {
+            source: s.trim().to_owned(),
+            program,
+            args: words.collect(),
+        })
+    }
+}
+
+impl fmt::Display for AlertCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for AlertCommand {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<AlertCommand> for String {
+    fn from(command: AlertCommand) -> Self {
+        command.source
+    }
+}
+
+// === impl Alerts ===
+
+impl Alerts {
+    pub(crate) fn new(alerts: &[Alert], bell: bool, command: Option<AlertCommand>) -> Self {
+        Self {
+            watches: alerts
+                .iter()
+                .cloned()
+                .map(|alert| Watch {
+                    alert,
+                    samples: HashMap::new(),
+                    matching: HashSet::new(),
+                })
+                .collect(),
+            bell,
+            command,
+        }
+    }
+
+    /// Checks every alert against the latest update, and returns a message
+    /// for each alert which a task has newly triggered, after ringing the
+    /// bell and running the alert command for them.
+    pub(crate) fn check(&mut self, state: &State) -> Vec<String> {
+        let Some(now) = state.last_updated_at() else {
+            return Vec::new();
+        };
+        let mut messages = Vec::new();
+        for watch in &mut self.watches {
+            let Some(message) = watch.check(state, now) else {
+                continue;
+            };
+            tracing::info!(alert = %watch.alert, message, "alert triggered");
+            if let Some(ref command) = self.command {
+                if let Err(error) = command.run(&watch.alert, &message) {
+                    tracing::warn!(%error, %command, "failed to run alert command");
+                }
+            }
+            messages.push(message);
+        }
+        if self.bell && !messages.is_empty() {
+            if let Err(error) = notify::ring_bell() {
+                tracing::warn!(%error, "failed to ring the bell for an alert");
+            }
+        }
+        messages
+    }
+
+    /// Forgets the tasks seen so far, such as when connecting to a new
+    /// target.
+    pub(crate) fn reset(&mut self) {
+        for watch in &mut self.watches {
+            watch.samples.clear();
+            watch.matching.clear();
+        }
+    }
+}
+
+// === impl Watch ===
+
+impl Watch {
+    /// Returns a message describing the tasks which have started matching
+    /// the alert's condition as of `now`, if any have.
+    fn check(&mut self, state: &State, now: SystemTime) -> Option<String> {
+        let mut matching = HashSet::with_capacity(self.matching.len());
+        let mut samples = HashMap::with_capacity(self.samples.len());
+        let mut triggered = Vec::new();
+        for task in state.tasks_state().tasks() {
+            let Some(task) = task.upgrade() else {
+                continue;
+            };
+            let task = task.borrow();
+            let matches = match self.alert.window {
+                None => self.alert.condition.holds(&task, now),
+                Some(window) => {
+                    let values = self
+                        .alert
+                        .condition
+                        .exprs()
+                        .map(|expr| expr.eval(&task, now))
+                        .collect::<Vec<_>>();
+                    let mut history = self.samples.remove(&task.span_id()).unwrap_or_default();
+                    history.push_back((now, values));
+                    let start = now.checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH);
+                    while history.get(1).is_some_and(|&(at, _)| at <= start) {
+                        history.pop_front();
+                    }
+                    let matches = grew_enough(&self.alert.condition, &history);
+                    samples.insert(task.span_id(), history);
+                    matches
+                }
+            };
+            if !matches {
+                continue;
+            }
+            matching.insert(task.span_id());
+            if !self.matching.contains(&task.span_id()) {
+                triggered.push(task.short_desc().to_string());
+            }
+        }
+        self.matching = matching;
+        self.samples = samples;
+
+        let first = triggered.first()?;
+        let message = match triggered.len() {
+            1 => tr_fmt("alert `{}` triggered by task {}", &[&self.alert, first]),
+            n => tr_fmt(
+                "alert `{}` triggered by task {} and {} other tasks",
+                &[&self.alert, first, &(n - 1)],
+            ),
+        };
+        Some(message)
+    }
+}
+
+/// Returns `true` if `condition` holds for how much each of its expressions
+/// grew from the oldest to the newest of `history`.
+fn grew_enough(condition: &Condition, history: &History) -> bool {
+    let (Some((_, oldest)), Some((_, newest))) = (history.front(), history.back()) else {
+        return false;
+    };
+    let grew = oldest
+        .iter()
+        .zip(newest)
+        .map(|(oldest, newest)| Some(newest.as_ref()? - oldest.as_ref()?))
+        .collect::<Vec<_>>();
+    condition.holds_for(&grew)
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let alert = "busy > 2s".parse::<Alert>().unwrap();
+        assert_eq!(alert.window, None);
+        assert_eq!(alert.to_string(), "busy > 2s");
+
+        let alert = " self_wakes > 100 in 10s ".parse::<Alert>().unwrap();
+        assert_eq!(alert.window, Some(Duration::from_secs(10)));
+        assert_eq!(alert.to_string(), "self_wakes > 100 in 10s");
+
+        assert!("busy".parse::<Alert>().is_err());
+        assert!("busy > 2s in 0s".parse::<Alert>().is_err());
+        assert!("busy > 2s in soon".parse::<Alert>().is_err());
+    }
+
+    #[test]
+    fn parse_commands() {
+        let command = "./alert.sh --channel load-tests"
+            .parse::<AlertCommand>()
+            .unwrap();
+        assert_eq!(command.program, "./alert.sh");
+        assert_eq!(command.args, ["--channel", "load-tests"]);
+        assert!("  ".parse::<AlertCommand>().is_err());
+    }
+
+    #[test]
+    fn windows_compare_growth() {
+        let condition = "self_wakes > 100 and busy < 1s"
+            .parse::<Condition>()
+            .unwrap();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut history = VecDeque::new();
+        history.push_back((at(0), vec![Some(1000.0), Some(5.0)]));
+        assert!(!grew_enough(&condition, &history));
+        history.push_back((at(5), vec![Some(1050.0), Some(5.2)]));
+        assert!(!grew_enough(&condition, &history));
+        history.push_back((at(10), vec![Some(1101.0), Some(5.5)]));
+        assert!(grew_enough(&condition, &history));
+        // A value which isn't known doesn't match.
+        history.push_back((at(15), vec![None, Some(5.5)]));
+        assert!(!grew_enough(&condition, &history));
+    }
+}
@@ -0,0 +1,204 @@
+//! Securing the console's connections to targets with TLS, and
+//! authenticating it with client certificates and bearer tokens.
+use crate::{config, conn::Transport};
+use console_api::instrument::instrument_client::InstrumentClient;
+use hyper_util::rt::TokioIo;
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+    ClientConfig, RootCertStore,
+};
+use std::{
+    error::Error,
+    sync::{Arc, OnceLock},
+};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Channel, Endpoint, Uri},
+    Request, Status,
+};
+
+/// A client for a target's instrument API, which authenticates each request.
+pub(crate) type Client = InstrumentClient<InterceptedService<Transport, Authorization>>;
+
+/// Adds the bearer token to each request, if there is one.
+#[derive(Clone, Debug)]
+pub(crate) struct Authorization(Option<MetadataValue<Ascii>>);
+
+/// The settings every connection is made with, which are set by [`init`].
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+struct Settings {
+    /// The TLS configuration of the framed transports.
+    tls: Arc<ClientConfig>,
+    /// The same configuration, negotiating HTTP/2 for gRPC.
+    grpc_tls: Arc<ClientConfig>,
+    token: Option<MetadataValue<Ascii>>,
+    /// Whether `http` and `https` targets are connected to with gRPC-web.
+    grpc_web: bool,
+}
+
+/// Reads the certificates and keys which connections are made with.
+///
+/// This must be called before connecting to any targets, or they're
+/// connected to without client certificates or a token.
+pub(crate) fn init(options: &config::Auth) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let settings = Settings::new(options)?;
+    // The console only reads its options once.
+    let _ = SETTINGS.set(settings);
+    Ok(())
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| {
+        Settings::new(&config::Auth::default()).expect("the default settings have no files to read")
+    })
+}
+
+/// Returns the TLS configuration of the framed transports.
+pub(crate) fn tls_config() -> Arc<ClientConfig> {
+    settings().tls.clone()
+}
+
+/// Returns `true` if `http` and `https` targets are connected to with
+/// gRPC-web.
+pub(crate) fn grpc_web() -> bool {
+    settings().grpc_web
+}
+
+/// Returns a client for the target on `transport`.
+pub(crate) fn client(transport: Transport) -> Client {
+    InstrumentClient::with_interceptor(transport, Authorization(settings().token.clone()))
+}
+
+/// Opens a channel to the console server at the `https` address `target`.
+pub(crate) async fn connect_https(target: &Uri) -> Result<Channel, Box<dyn Error + Send + Sync>> {
+    let host = target.host().ok_or("target address has no host")?;
+    // IPv6 hosts are written in brackets in URIs.
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_owned();
+    let port = target.port_u16().unwrap_or(443);
+    let server_name = ServerName::try_from(host.clone())?;
+    let connector = TlsConnector::from(settings().grpc_tls.clone());
+    // tonic refuses to connect to `https` endpoints unless it handles TLS
+    // itself, so the channel is opened to a dummy endpoint, which is ignored
+    // by the connector, and requests are sent as if to the target.
+    let endpoint = Endpoint::from_static("http://localhost").origin(target.clone());
+    let channel = endpoint
+        .connect_with_connector(tower::service_fn(move |_| {
+            let (host, server_name, connector) =
+                (host.clone(), server_name.clone(), connector.clone());
+            async move {
+                let tcp = TcpStream::connect((host.as_str(), port)).await?;
+                let tls = connector.connect(server_name, tcp).await?;
+                Ok::<_, std::io::Error>(TokioIo::new(tls))
+            }
+        }))
+        .await?;
+    Ok(channel)
+}
+
+// === impl Settings ===
+
+impl Settings {
+    fn new(options: &config::Auth) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let roots = match options.tls_ca {
+            Some(ref path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(path)
+                    .map_err(|error| format!("failed to read {}: {error}", path.display()))?
+                {
+                    roots.add(cert?)?;
+                }
+                if roots.is_empty() {
+                    return Err(format!("{} has no certificates", path.display()).into());
+                }
+                roots
+            }
+            None => RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            },
+        };
+        let tls = ClientConfig::builder().with_root_certificates(roots);
+        let tls = match (&options.tls_cert, &options.tls_key) {
+            (Some(cert), Some(key)) => {
+                let chain = CertificateDer::pem_file_iter(cert)
+                    .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                    .map_err(|error| format!("failed to read {}: {error}", cert.display()))?;
+                let key = PrivateKeyDer::from_pem_file(key)
+                    .map_err(|error| format!("failed to read {}: {error}", key.display()))?;
+                tls.with_client_auth_cert(chain, key)?
+            }
+            (None, None) => tls.with_no_client_auth(),
+            _ => return Err("`--tls-cert` and `--tls-key` must be used together".into()),
+        };
+        let mut grpc_tls = tls.clone();
+        grpc_tls.alpn_protocols = vec![b"h2".to_vec()];
+        let token = options
+            .token
+            .as_ref()
+            .map(|token| format!("Bearer {token}").parse())
+            .transpose()
+            .map_err(|_| "the token must be printable ASCII")?;
+        Ok(Self {
+            tls: Arc::new(tls),
+            grpc_tls: Arc::new(grpc_tls),
+            token,
+            grpc_web: options.grpc_web,
+        })
+    }
+}
+
+// === impl Authorization ===
+
+impl Interceptor for Authorization {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(ref token) = self.0 {
+            request
+                .metadata_mut()
+                .insert("authorization", token.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_bearer_token() {
+        let options = config::Auth {
+            token: Some("s3cret".to_owned()),
+            ..Default::default()
+        };
+        let settings = Settings::new(&options).unwrap();
+        let request = Authorization(settings.token)
+            .call(Request::new(()))
+            .unwrap();
+        assert_eq!(
+            request.metadata().get("authorization").unwrap(),
+            "Bearer s3cret"
+        );
+
+        let options = config::Auth {
+            token: Some("not\nascii".to_owned()),
+            ..Default::default()
+        };
+        assert!(Settings::new(&options).is_err());
+    }
+
+    #[test]
+    fn requires_cert_and_key_together() {
+        let options = config::Auth {
+            tls_cert: Some("cert.pem".into()),
+            ..Default::default()
+        };
+        let error = Settings::new(&options).err().unwrap();
+        assert!(error.to_string().contains("must be used together"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Comparing a run of the `report` or `lint` subcommands against a baseline
+//! saved by an earlier run.
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs, path::Path, time::Duration};
+
+/// How many standard errors a statistic must rise by to be a regression.
+///
+/// For normally distributed samples, a rise this large happens by chance
+/// less than 0.2% of the time.
+const SIGNIFICANCE: f64 = 3.0;
+
+/// What was seen while watching a target, as saved with `--save`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Session {
+    /// How long the target was watched for, in seconds.
+    pub(crate) duration: f64,
+    /// The tasks seen at each spawn location.
+    pub(crate) locations: BTreeMap<String, Location>,
+}
+
+/// The tasks seen at one spawn location.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Location {
+    /// How many tasks were spawned while the target was watched.
+    pub(crate) spawned: usize,
+    /// How long each task spent busy while the target was watched, in
+    /// seconds.
+    pub(crate) busy: Vec<f64>,
+    /// The p99 poll time of each task which completed a poll, in seconds.
+    pub(crate) poll_p99: Vec<f64>,
+}
+
+/// A statistic for a spawn location which rose significantly since the
+/// baseline.
+#[derive(Debug)]
+pub(crate) struct Regression {
+    location: String,
+    metric: &'static str,
+    baseline: String,
+    current: String,
+    /// How many standard errors the statistic rose by.
+    score: f64,
+}
+
+// === impl Session ===
+
+impl Session {
+    pub(crate) fn load(path: &Path) -> color_eyre::Result<Self> {
+        let raw = fs::read_to_string(path)
+            .wrap_err_with(|| format!("could not read the baseline {}", path.display()))?;
+        toml::from_str(&raw)
+            .wrap_err_with(|| format!("could not parse the baseline {}", path.display()))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> color_eyre::Result<()> {
+        let raw = toml::to_string(self)?;
+        fs::write(path, raw).wrap_err_with(|| format!("could not save to {}", path.display()))
+    }
+
+    /// Returns the statistics which rose significantly from `baseline` to
+    /// this session.
+    ///
+    /// Locations which only appear in one of the sessions aren't compared.
+    pub(crate) fn regressions(&self, baseline: &Session) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (name, current) in &self.locations {
+            let Some(base) = baseline.locations.get(name) else {
+                continue;
+            };
+            let mut regression = |metric, baseline, current, score| {
+                regressions.push(Regression {
+                    location: name.clone(),
+                    metric,
+                    baseline,
+                    current,
+                    score,
+                })
+            };
+
+            // Spawns are compared as rates, in case the runs were different
+            // lengths.
+            let expected = base.spawned as f64 * self.duration / baseline.duration;
+            let observed = current.spawned as f64;
+            if let Some(score) = count_increase(expected, observed) {
+                regression(
+                    "spawns",
+                    format!("{expected:.0}"),
+                    observed.to_string(),
+                    score,
+                );
+            }
+            if let Some(score) = mean_increase(&base.busy, &current.busy) {
+                regression(
+                    "mean busy time",
+                    duration(mean(&base.busy)),
+                    duration(mean(&current.busy)),
+                    score,
+                );
+            }
+            if let Some(score) = mean_increase(&base.poll_p99, &current.poll_p99) {
+                regression(
+                    "mean p99 poll time",
+                    duration(mean(&base.poll_p99)),
+                    duration(mean(&current.poll_p99)),
+                    score,
+                );
+            }
+        }
+        regressions
+    }
+}
+
+// === impl Regression ===
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} rose from {} to {}",
+            self.location, self.metric, self.baseline, self.current
+        )?;
+        if self.score.is_finite() {
+            write!(f, " ({:.1} standard errors)", self.score)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints `regressions`, or that there weren't any.
+pub(crate) fn print(regressions: &[Regression]) {
+    if regressions.is_empty() {
+        println!("regressions: none");
+    }
+    for regression in regressions {
+        println!("regression: {regression}");
+    }
+}
+
+/// Returns how many standard errors the mean of `current` is above the mean
+/// of `baseline`, using Welch's t-test, if that's significant.
+///
+/// Returns `None` if either has fewer than two samples, since their
+/// variance can't be estimated.
+fn mean_increase(baseline: &[f64], current: &[f64]) -> Option<f64> {
+    if baseline.len() < 2 || current.len() < 2 {
+        return None;
+    }
+    let diff = mean(current) - mean(baseline);
+    let error = (variance(baseline) / baseline.len() as f64
+        + variance(current) / current.len() as f64)
+        .sqrt();
+    let score = if error > 0.0 {
+        diff / error
+    } else if diff > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    (score > SIGNIFICANCE).then_some(score)
+}
+
+/// Returns how many standard errors `observed` is above `expected`, treating
+/// them as Poisson counts, if that's significant.
+fn count_increase(expected: f64, observed: f64) -> Option<f64> {
+    if expected.is_nan() || expected <= 0.0 {
+        return None;
+    }
+    let score = (observed - expected) / expected.sqrt();
+    (score > SIGNIFICANCE).then_some(score)
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn variance(samples: &[f64]) -> f64 {
+    let mean = mean(samples);
+    samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+fn duration(secs: f64) -> String {
+    format!("{:.2?}", Duration::from_secs_f64(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(duration: f64, busy: Vec<f64>) -> Session {
+        Session {
+            duration,
+            locations: [(
+                "src/main.rs:1:1".to_owned(),
+                Location {
+                    spawned: busy.len(),
+                    busy,
+                    poll_p99: Vec::new(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn busy_time_regression() {
+        let baseline = session(60.0, vec![1.0, 1.1, 0.9, 1.0]);
+        let same = session(60.0, vec![1.05, 0.95, 1.0, 1.1]);
+        assert!(same.regressions(&baseline).is_empty());
+
+        let slower = session(60.0, vec![2.0, 2.1, 1.9, 2.0]);
+        let regressions = slower.regressions(&baseline);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].metric, "mean busy time");
+    }
+
+    #[test]
+    fn spawn_count_regression() {
+        let baseline = session(60.0, vec![0.0; 100]);
+        // The same rate over half as long isn't a regression.
+        assert!(session(30.0, vec![0.0; 50])
+            .regressions(&baseline)
+            .is_empty());
+
+        let regressions = session(60.0, vec![0.0; 200]).regressions(&baseline);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].metric, "spawns");
+    }
+
+    #[test]
+    fn too_few_samples() {
+        assert_eq!(mean_increase(&[1.0], &[10.0, 10.0]), None);
+    }
+}
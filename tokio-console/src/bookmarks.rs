@@ -0,0 +1,106 @@
+//! Persists task bookmarks across sessions.
+//!
+//! Bookmarks are stored by task identity (see [`TaskKey`]) in a state file for
+//! each target address, so reconnecting to the same target restores the
+//! bookmarks and notes from earlier sessions.
+use crate::{persist, state::tasks::TaskKey};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tonic::transport::Uri;
+
+pub(crate) type Bookmarks = HashMap<TaskKey, Option<String>>;
+
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+struct BookmarksFile {
+    #[serde(default, rename = "bookmark")]
+    bookmarks: Vec<SavedBookmark>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+struct SavedBookmark {
+    location: String,
+    name: Option<String>,
+    /// The hash of the task's fields, in hex, since TOML integers are signed.
+    fields: String,
+    note: Option<String>,
+}
+
+/// Returns the path of the bookmarks file for `target`.
+pub(crate) fn path_for(target: &Uri) -> Option<PathBuf> {
+    persist::path_for("bookmarks", target)
+}
+
+/// Loads the bookmarks saved at `path`.
+pub(crate) fn load(path: &Path) -> Bookmarks {
+    persist::load(path, from_toml).unwrap_or_default()
+}
+
+/// Saves `bookmarks` to `path`, replacing any previously saved bookmarks.
+pub(crate) fn save(path: &Path, bookmarks: &Bookmarks) {
+    persist::save(path, to_toml(bookmarks))
+}
+
+fn from_toml(raw: &str) -> color_eyre::Result<Bookmarks> {
+    let file = toml::from_str::<BookmarksFile>(raw)?;
+    file.bookmarks
+        .into_iter()
+        .map(|saved| {
+            let key = TaskKey {
+                location: saved.location,
+                name: saved.name,
+                fields_hash: u64::from_str_radix(&saved.fields, 16)?,
+            };
+            Ok((key, saved.note))
+        })
+        .collect()
+}
+
+fn to_toml(bookmarks: &Bookmarks) -> color_eyre::Result<String> {
+    let mut bookmarks = bookmarks
+        .iter()
+        .map(|(key, note)| SavedBookmark {
+            location: key.location.clone(),
+            name: key.name.clone(),
+            fields: format!("{:016x}", key.fields_hash),
+            note: note.clone(),
+        })
+        .collect::<Vec<_>>();
+    // Sort the bookmarks so that the file doesn't change on every save.
+    bookmarks.sort_unstable_by(|a, b| {
+        (&a.location, &a.name, &a.fields).cmp(&(&b.location, &b.name, &b.fields))
+    });
+    Ok(toml::to_string(&BookmarksFile { bookmarks })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_roundtrips() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.insert(
+            TaskKey {
+                location: "src/main.rs:10:5".to_string(),
+                name: Some("accept".to_string()),
+                fields_hash: u64::MAX,
+            },
+            Some("suspect leak".to_string()),
+        );
+        bookmarks.insert(
+            TaskKey {
+                location: "src/lib.rs:1:1".to_string(),
+                name: None,
+                fields_hash: 0,
+            },
+            None,
+        );
+
+        let raw = to_toml(&bookmarks).unwrap();
+        assert_eq!(from_toml(&raw).unwrap(), bookmarks);
+    }
+}
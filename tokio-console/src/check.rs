@@ -0,0 +1,152 @@
+//! The `tokio-console check` subcommand.
+use crate::{auth, conn};
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Help,
+};
+use console_api::instrument::{InstrumentRequest, ServerInfo, ServerInfoRequest};
+use futures::StreamExt;
+use std::time::Duration;
+use tonic::transport::Uri;
+
+/// Connects to `target`, checks that it is compatible with this console, and
+/// prints what it reports about itself.
+///
+/// Returns an error if any step fails or does not complete within `timeout`.
+pub(crate) async fn run(target: Uri, timeout: Duration) -> color_eyre::Result<()> {
+    println!("target: {target}");
+    tokio::time::timeout(timeout, check(target))
+        .await
+        .map_err(|_| eyre!("timed out after {}", humantime::format_duration(timeout)))
+        .suggestion("is the application running, and is it instrumented with console-subscriber?")?
+}
+
+async fn check(target: Uri) -> color_eyre::Result<()> {
+    let channel = conn::connect_channel(&target)
+        .await
+        .map_err(|error| eyre!(error))
+        .wrap_err("could not connect to the target")?;
+    println!("connected: ok");
+    let mut client = auth::client(channel);
+
+    match client
+        .get_server_info(tonic::Request::new(ServerInfoRequest {}))
+        .await
+    {
+        Ok(info) => print_info(info.into_inner())?,
+        Err(status) if status.code() == tonic::Code::Unimplemented => {
+            // Servers from before `GetServerInfo` was added can still be
+            // used, if they support the rest of the protocol.
+            println!("server info: not reported (console-subscriber is older than this console)");
+        }
+        Err(status) => return Err(status).wrap_err("could not get server info"),
+    }
+
+    let mut updates = client
+        .watch_updates(tonic::Request::new(InstrumentRequest {}))
+        .await
+        .wrap_err("could not watch updates")?
+        .into_inner();
+    // The first update includes everything the server is currently tracking.
+    let update = match updates.next().await {
+        Some(update) => update.wrap_err("could not receive an update")?,
+        None => bail!("the target closed the update stream before sending an update"),
+    };
+    let tasks = update.task_update.unwrap_or_default();
+    let resources = update.resource_update.unwrap_or_default();
+    let async_ops = update.async_op_update.unwrap_or_default();
+    println!("tasks: {}", tasks.new_tasks.len());
+    println!("resources: {}", resources.new_resources.len());
+    println!("async ops: {}", async_ops.new_async_ops.len());
+    let dropped_events = tasks.dropped_events + resources.dropped_events + async_ops.dropped_events;
+    if dropped_events > 0 {
+        println!("dropped events: {dropped_events}");
+    }
+    Ok(())
+}
+
+fn print_info(info: ServerInfo) -> color_eyre::Result<()> {
+    println!(
+        "api version: {} (console: {})",
+        info.api_version,
+        console_api::VERSION
+    );
+    println!("capabilities: {}", info.capabilities.join(", "));
+    let duration = |duration: Option<prost_types::Duration>| {
+        duration
+            .and_then(|duration| Duration::try_from(duration).ok())
+            .map(|duration| humantime::format_duration(duration).to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    println!("retention: {}", duration(info.retention));
+    println!("publish interval: {}", duration(info.publish_interval));
+
+    if !is_compatible(&info.api_version, console_api::VERSION) {
+        bail!(
+            "the target's console-api version {} is not compatible with this console's version {}",
+            info.api_version,
+            console_api::VERSION
+        );
+    }
+    println!("compatible: ok");
+    Ok(())
+}
+
+/// Returns `true` if `server` and `client` versions are semver-compatible.
+///
+/// For versions before 1.0, the minor version is treated as the major
+/// version, as Cargo does.
+pub(crate) fn is_compatible(server: &str, client: &str) -> bool {
+    fn breaking(version: &str) -> Option<(&str, &str)> {
+        let mut parts = version.split('.');
+        let major = parts.next()?;
+        let minor = parts.next()?;
+        Some(if major == "0" {
+            (major, minor)
+        } else {
+            (major, "")
+        })
+    }
+    match (breaking(server), breaking(client)) {
+        (Some(server), Some(client)) => server == client,
+        _ => false,
+    }
+}
+
+/// Returns `true` if the `server` version is older than the `client`
+/// version, or can't be parsed.
+pub(crate) fn is_older(server: &str, client: &str) -> bool {
+    fn parse(version: &str) -> Option<Vec<u64>> {
+        // Ignore pre-release and build metadata, such as `-alpha.1`.
+        let version = version.split(['-', '+']).next()?;
+        version.split('.').map(|part| part.parse().ok()).collect()
+    }
+    match (parse(server), parse(client)) {
+        (Some(server), Some(client)) => server < client,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compatible_versions() {
+        assert!(is_compatible("0.8.1", "0.8.0"));
+        assert!(!is_compatible("0.7.0", "0.8.0"));
+        assert!(is_compatible("1.2.0", "1.0.3"));
+        assert!(!is_compatible("2.0.0", "1.0.0"));
+        assert!(!is_compatible("garbage", "0.8.0"));
+    }
+
+    #[test]
+    fn older_versions() {
+        assert!(is_older("0.7.9", "0.8.0"));
+        assert!(is_older("0.8.0", "0.8.1"));
+        assert!(!is_older("0.8.1", "0.8.1"));
+        assert!(!is_older("0.10.0", "0.8.1"));
+        assert!(!is_older("0.8.1-alpha.1", "0.8.1"));
+        assert!(is_older("garbage", "0.8.0"));
+    }
+}
@@ -0,0 +1,15 @@
+//! Copying text to the clipboard, so that what the console shows can be
+//! pasted into issues and chats.
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::io::{self, Write};
+
+/// Copies `text` to the clipboard with the OSC 52 escape sequence.
+///
+/// The terminal copies the text to the clipboard of the machine it's running
+/// on, so this works over SSH too. Terminals which don't support OSC 52 ignore
+/// it.
+pub(crate) fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -1,14 +1,28 @@
-use crate::state::tasks::Task;
-use crate::view::Palette;
+use crate::alert::{self, Alert, AlertCommand};
+use crate::conn;
+use crate::discover::Discover;
+use crate::editor::EditorCommand;
+use crate::graphics::{GraphicsMode, Protocol};
+use crate::highlight::Highlight;
+use crate::keymap::{Key, Keymap};
+use crate::notify::{NotifyEvent, NotifyMethod};
+use crate::pause::PauseCondition;
+use crate::slo::Slo;
+use crate::state::expr::Expr;
+use crate::state::tasks::{self, Task};
+use crate::view::{ColorVision, ColumnConfig, Palette, SortConfig, Theme};
 use crate::warnings;
 use clap::builder::{PossibleValuesParser, TypedValueParser};
 use clap::{ArgAction, ArgGroup, CommandFactory, Parser as Clap, Subcommand, ValueHint};
 use clap_complete::Shell;
 use color_eyre::eyre::WrapErr;
+use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::fs;
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
 use std::ops::Not;
 use std::path::PathBuf;
 use std::process::Command;
@@ -17,6 +31,9 @@ use std::time::Duration;
 use tonic::transport::Uri;
 use tracing_subscriber::filter;
 
+/// The most frames drawn each second as updates arrive, unless configured.
+const DEFAULT_FRAME_RATE: u32 = 30;
+
 #[derive(Clap, Debug)]
 #[clap(
     name = clap::crate_name!(),
@@ -29,15 +46,66 @@ use tracing_subscriber::filter;
 pub struct Config {
     /// The address of a console-enabled process to connect to.
     ///
-    /// This may be an IP address and port, or a DNS name.
+    /// This may be an IP address and port, or a DNS name. Addresses without
+    /// a scheme, such as `10.0.3.4:6669`, `[::1]:6669` or `api.local:6669`,
+    /// are connected to with `http`, and the port of an IP address without
+    /// one defaults to 6669.
     ///
     /// On Unix platforms, this may also be a URI with the `file` scheme that
     /// specifies the path to a Unix domain socket, as in
     /// `file://localhost/path/to/socket`.
     ///
+    /// A URI with the `framed` scheme, such as `framed://127.0.0.1:6669`,
+    /// connects to a target serving console-subscriber's framed transport
+    /// rather than gRPC, or `framed+file` for a Unix domain socket. The
+    /// framed transport only streams updates, so the target can't be paused
+    /// and tasks' histograms aren't shown. A URI with the `ws` or `wss`
+    /// scheme connects to the framed transport over a WebSocket, for targets
+    /// behind proxies which don't pass gRPC through, and one with the `quic`
+    /// scheme connects to it over QUIC, whose connections survive changes
+    /// to the console's network address. This isn't gRPC over HTTP/3, so
+    /// pausing and task details are unavailable over QUIC as well.
+    ///
+    /// A URI with the `snapshot` scheme, such as
+    /// `snapshot://localhost/path/to/snapshot`, opens a final snapshot which
+    /// console-subscriber wrote when the target panicked or shut down, and
+    /// one with the `replay` scheme, such as
+    /// `replay://localhost/path/to/recording`, replays a recording made with
+    /// `--record`.
+    ///
+    /// A URI with the `k8s` scheme, such as `k8s://namespace/pod:6669`,
+    /// connects to a Kubernetes pod through a port-forward, which is started
+    /// with `kubectl port-forward` and stopped when the console exits. The
+    /// port defaults to 6669.
+    ///
+    /// A URI with the `docker` scheme, such as `docker://container:6669`,
+    /// connects to a local Docker container, through the host port its
+    /// console port is published on, or its address on one of its networks
+    /// if the port isn't published. The port defaults to 6669.
+    ///
+    /// A URI with the `dns+srv` scheme, such as
+    /// `dns+srv://_console._tcp.example.com`, connects to one of the
+    /// instances which the name's DNS SRV records point to, picked by their
+    /// priorities and weights. The records are looked up again whenever the
+    /// console reconnects, from the first `nameserver` in `/etc/resolv.conf`.
+    /// Its `search` and `options` lines are ignored, so the name must be
+    /// fully qualified, and `dns+srv` targets aren't supported on Windows.
+    ///
+    /// This may also be the name of a target defined in the `[targets]`
+    /// table of the config file.
+    ///
     /// [default: http://127.0.0.1:6669]
     #[clap(value_hint = ValueHint::Url)]
-    pub(crate) target_addr: Option<Uri>,
+    pub(crate) target_addr: Option<String>,
+
+    /// Named target addresses, from the `[targets]` table of the config file.
+    #[clap(skip)]
+    targets: BTreeMap<String, Uri>,
+
+    /// Keys which act as other keys, from the `[keymap]` table of the config
+    /// file.
+    #[clap(skip)]
+    pub(crate) keymap: Keymap,
 
     /// Log level filter for the console's internal diagnostics.
     ///
@@ -58,17 +126,20 @@ pub struct Config {
     /// Each warning is specified by its name, which is one of:
     ///
     /// * `self-wakes` -- Warns when a task wakes itself more than a certain percentage of its total wakeups.
-    ///                   Default percentage is 50%.
+    ///   Default percentage is 50%.
     ///
     /// * `lost-waker` -- Warns when a task is dropped without being woken.
     ///
     /// * `never-yielded` -- Warns when a task has never yielded.
     ///
+    /// * `long-poll` -- Warns when a task which has yielded before is busy for
+    ///   a long time without yielding again. Default duration is 500ms.
+    ///
     /// * `auto-boxed-future` -- Warnings when the future driving a task was automatically boxed by
-    ///                          the runtime because it was large.
+    ///   the runtime because it was large.
     ///
     /// * `large-future` -- Warnings when the future driving a task occupies a large amount of
-    ///                     stack space.
+    ///   stack space.
     #[clap(long = "warn", short = 'W', value_delimiter = ',', num_args = 1..)]
     #[clap(default_values_t = KnownWarnings::default_enabled_warnings())]
     pub(crate) warnings: Vec<KnownWarnings>,
@@ -80,24 +151,240 @@ pub struct Config {
     /// Each warning is specified by its name, which is one of:
     ///
     /// * `self-wakes` -- Warns when a task wakes itself more than a certain percentage of its total wakeups.
-    ///                  Default percentage is 50%.
+    ///   Default percentage is 50%.
     ///
     /// * `lost-waker` -- Warns when a task is dropped without being woken.
     ///
     /// * `never-yielded` -- Warns when a task has never yielded.
     ///
+    /// * `long-poll` -- Warns when a task which has yielded before is busy for
+    ///   a long time without yielding again. Default duration is 500ms.
+    ///
     /// * `auto-boxed-future` -- Warnings when the future driving a task was automatically boxed by
-    ///                          the runtime because it was large.
+    ///   the runtime because it was large.
     ///
     /// * `large-future` -- Warnings when the future driving a task occupies a large amount of
-    ///                     stack space.
+    ///   stack space.
     ///
     /// If this is set to `all`, all warnings are allowed.
     ///
-    /// [possible values: all, self-wakes, lost-waker, never-yielded, large-future, auto-boxed-future]
+    /// [possible values: all, self-wakes, lost-waker, never-yielded, long-poll, large-future, auto-boxed-future]
     #[clap(long = "allow", short = 'A', num_args = 1..)]
     pub(crate) allow_warnings: Option<AllowedWarnings>,
 
+    /// Optional columns to display in the task list.
+    ///
+    /// This is a comma-separated list of columns, which may include:
+    ///
+    /// * `wakes` -- The total number of times the task has been woken.
+    ///
+    /// * `wakers` -- The number of wakers currently referencing the task.
+    ///
+    /// * `p99-poll` -- The task's 99th percentile poll time.
+    ///
+    /// * `max-poll` -- The task's longest poll time.
+    ///
+    /// * `polls-per-sec` -- How many times per second the task has been polled
+    ///   over the last 10 seconds.
+    ///
+    /// * `wakes-per-sec` -- How many times per second the task has been woken
+    ///   over the last 10 seconds.
+    ///
+    /// * `busy-percent` -- The percentage of time the task has recently been
+    ///   busy, smoothed so that momentary spikes don't reorder the task list.
+    ///
+    /// * `scheduled-percent` -- The percentage of time the task has recently
+    ///   spent scheduled but not yet polled, smoothed in the same way. Tasks
+    ///   which spend long scheduled are a sign that the runtime is starved.
+    ///
+    /// * `awaiting` -- The async op which the task's most recent poll
+    ///   returned `Pending` on, and the resource it's on.
+    ///
+    /// The poll time columns require console-subscriber to send summaries of
+    /// tasks' poll times, which older versions don't.
+    #[clap(long = "task-columns", value_delimiter = ',', num_args = 1..)]
+    pub(crate) task_columns: Vec<TaskColumn>,
+
+    /// Span fields to display as their own columns in the task list.
+    ///
+    /// This is a comma-separated list of field names, such as
+    /// `request_id,peer_addr`. Each field is shown in a separate, sortable
+    /// column, rather than with the rest of the task's fields.
+    #[clap(long = "field-columns", value_delimiter = ',', num_args = 1..)]
+    pub(crate) field_columns: Vec<String>,
+
+    /// Expressions over task statistics to display as their own columns in
+    /// the task list.
+    ///
+    /// This is a comma-separated list of expressions, such as
+    /// `busy / total,polls / total_secs`, which can be sorted by to order
+    /// tasks in ways the other columns can't. Expressions combine `total`,
+    /// `busy`, `scheduled`, `idle` (in seconds, optionally with a `_secs`
+    /// suffix), `busy_percent` and `scheduled_percent` (the `busy-percent` and
+    /// `scheduled-percent` columns), `polls`, `wakes`, `polls_per_sec` and
+    /// `wakes_per_sec` (the `polls-per-sec` and `wakes-per-sec` columns),
+    /// `wakers`, `self_wakes` and `warnings` with `+`, `-`, `*`, `/` and
+    /// parentheses.
+    #[clap(long = "sort-expressions", value_delimiter = ',', num_args = 1..)]
+    pub(crate) sort_expressions: Vec<Expr>,
+
+    /// The task list's columns, in the order they're displayed.
+    ///
+    /// This is a comma-separated list of the columns `mark`, `warn`, `id`,
+    /// `state`, `name`, `total`, `busy`, `sched`, `idle`, `polls`, `kind`,
+    /// `location` and `fields`, such as `id,state,name,total,busy,fields`.
+    /// A column may be given a fixed width, rather than fitting its contents,
+    /// such as `name:24`.
+    ///
+    /// Columns which aren't listed are hidden, except for the columns added
+    /// by `--task-columns`, `--field-columns` and `--sort-expressions`, which
+    /// are displayed after the listed columns unless they're listed
+    /// themselves. Columns can also be chosen by pressing `c` in the task
+    /// list.
+    #[clap(long = "show-columns", value_delimiter = ',', num_args = 1..)]
+    pub(crate) show_columns: Vec<ColumnConfig>,
+
+    /// The column to sort the task list by when the console starts.
+    ///
+    /// This is the name of any of the task list's columns, including those
+    /// added by `--task-columns`, `--field-columns` and `--sort-expressions`,
+    /// optionally followed by `:asc` to show the lowest values first, such as
+    /// `busy` or `polls:asc`. The highest values are shown first otherwise.
+    ///
+    /// The sorting chosen in the last session with the same target is
+    /// restored instead, if there is one.
+    ///
+    /// [default: total]
+    #[clap(long = "sort", value_name = "COLUMN")]
+    pub(crate) sort: Option<SortConfig>,
+
+    /// Latency objectives for tasks' poll and scheduled times.
+    ///
+    /// This is a comma-separated list of objectives, written as
+    /// `<histogram> p<percentile> < <threshold>`, where the histogram is
+    /// `poll` or `scheduled`, such as `poll p99 < 10ms`. Objectives are shown
+    /// on the histograms in a task's details, and tasks which don't meet them
+    /// are warned about.
+    #[clap(long = "slo", value_delimiter = ',', num_args = 1..)]
+    pub(crate) slos: Vec<Slo>,
+
+    /// Rules which style the rows of tasks matching a condition in the task
+    /// list.
+    ///
+    /// This is a comma-separated list of rules, written as
+    /// `<condition> => <style>`, such as `busy > 500ms => red` or
+    /// `idle > 1h and wakers == 0 => dim`. Conditions compare expressions
+    /// over task statistics, as in `--sort-expressions`, with `<`, `<=`,
+    /// `>`, `>=`, `==` or `!=` and a number, which may be a duration or have
+    /// a `k` or `M` suffix, and are combined with `and` and `or`. Styles are
+    /// a color and any of `bold`, `dim`, `italic`, `underlined` and
+    /// `reversed`. Later rules take precedence over earlier ones, and the
+    /// rules can be edited in the task list with `A`.
+    #[clap(long = "highlight", value_delimiter = ',', num_args = 1..)]
+    pub(crate) highlights: Vec<Highlight>,
+
+    /// A command which opens a location in the instrumented program's code.
+    ///
+    /// Pressing `o` on a task runs this command to open where the task was
+    /// spawned, and pressing `o` in a resource's details runs it to open where
+    /// the selected async op, or the resource itself, was created. In the
+    /// command, `{file}`, `{line}` and `{column}` are replaced with the
+    /// location, such as `code --goto {file}:{line}:{column}`. Since the
+    /// console is using the terminal, the command should open the editor in
+    /// its own window.
+    ///
+    /// If no command is configured, the editor named by `$VISUAL` or
+    /// `$EDITOR` is opened at the location's line, and is handed the terminal
+    /// until it exits.
+    ///
+    /// Async ops' locations are only known if the instrumented program
+    /// captures backtraces of async ops' creation.
+    #[clap(long = "editor-command")]
+    pub(crate) editor_command: Option<EditorCommand>,
+
+    /// How to notify you of events, such as a task triggering a warning,
+    /// while the console is in the background.
+    ///
+    /// This may be one of:
+    ///
+    /// * `bell` -- Ring the terminal bell.
+    ///
+    /// * `osc9` -- Send a desktop notification with an OSC 9 escape sequence,
+    ///   which terminals such as iTerm2, kitty and WezTerm support.
+    ///
+    /// * `desktop` -- Send a native desktop notification, with
+    ///   `notify-send` on Linux and the BSDs, or `osascript` on macOS.
+    ///
+    /// The console is in the background when its terminal reports that it has
+    /// lost focus. If the terminal doesn't report focus changes, you are
+    /// notified of every event.
+    #[clap(long = "notify")]
+    pub(crate) notify: Option<NotifyMethod>,
+
+    /// The events to notify you of, if notifications are enabled with
+    /// `--notify`.
+    ///
+    /// This is a comma-separated list of events, which may include:
+    ///
+    /// * `warning` -- A linter warns about a task which it hadn't warned about
+    ///   before.
+    ///
+    /// * `disconnect` -- The connection to the target is lost.
+    ///
+    /// * `bookmark-completed` -- A bookmarked task completes.
+    ///
+    /// [default: warning]
+    #[clap(long = "notify-on", value_delimiter = ',', num_args = 1..)]
+    pub(crate) notify_on: Vec<NotifyEvent>,
+
+    /// Serve the instrument API at this address, relaying the target to
+    /// other consoles.
+    ///
+    /// Consoles connected to the relay see everything this console receives
+    /// from its target, and can watch tasks' details, but can't pause the
+    /// target. This lets several people watch a target which accepts only a
+    /// single connection, or which is only reachable from this machine.
+    #[clap(long = "relay", value_name = "ADDR")]
+    pub(crate) relay: Option<SocketAddr>,
+
+    /// Record every update received from the target to this file, which
+    /// can be replayed later with `tokio-console replay`.
+    ///
+    /// The file is replaced if it already exists. Only the target the
+    /// console starts with is recorded, so recording stops if another
+    /// target is connected to. Recordings don't include tasks' details, so
+    /// tasks' histograms aren't shown when they're replayed.
+    #[clap(long = "record", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub(crate) record: Option<PathBuf>,
+
+    /// Where to look for targets to list in the connect dialog, besides the
+    /// named targets in the config file.
+    ///
+    /// This is a comma-separated list, which may include:
+    ///
+    /// * `kubernetes` -- Running pods with the `console.tokio.rs/port`
+    ///   annotation set to the port console-subscriber listens on, listed
+    ///   with `kubectl`.
+    ///
+    /// * `docker` -- Running local containers which publish port 6669,
+    ///   listed with `docker`.
+    ///
+    /// * `local` -- Local processes which serve on a Unix domain socket in
+    ///   `$XDG_RUNTIME_DIR/tokio-console`, such as by setting
+    ///   `TOKIO_CONSOLE_BIND=local`, listed by process name and ID.
+    #[clap(long = "discover", value_delimiter = ',', num_args = 1..)]
+    pub(crate) discover: Vec<Discover>,
+
+    /// Open another tab connected to this target, in addition to the tab
+    /// for the target the console starts with.
+    ///
+    /// This may be given more than once. Each tab has its own connection,
+    /// and is switched to with the number keys. A target may be the name
+    /// of a target in the config file, or any address the console can
+    /// connect to, including a recording to replay.
+    #[clap(long = "tab", value_name = "TARGET")]
+    pub(crate) tabs: Vec<String>,
+
     /// Path to a directory to write the console's internal logs to.
     ///
     /// [default: /tmp/tokio-console/logs]
@@ -107,12 +394,27 @@ pub struct Config {
     #[clap(flatten)]
     pub(crate) view_options: ViewOptions,
 
+    #[clap(flatten)]
+    pub(crate) auto_pause: AutoPause,
+
+    #[clap(flatten)]
+    pub(crate) auth: Auth,
+
+    #[clap(flatten)]
+    pub(crate) reconnect: Reconnect,
+
+    #[clap(flatten)]
+    pub(crate) alerts: Alerting,
+
     /// How long to continue displaying completed tasks and dropped resources
     /// after they have been closed.
     ///
     /// This accepts either a duration, parsed as a combination of time spans
     /// (such as `5days 2min 2s`), or `none` to disable removing completed tasks
-    /// and dropped resources.
+    /// and dropped resources. `0s` removes them as soon as they complete.
+    ///
+    /// While the console is running, `K` switches between this retention,
+    /// keeping them forever, and removing them as soon as they complete.
     ///
     /// Each time span is an integer number followed by a suffix. Supported suffixes are:
     ///
@@ -140,6 +442,33 @@ pub struct Config {
     #[clap(long = "retain-for")]
     retain_for: Option<RetainFor>,
 
+    /// How often to redraw the console while its terminal is unfocused.
+    ///
+    /// This accepts either a duration, parsed in the same way as
+    /// `--retain-for`, or `none` to keep redrawing as often as when it's
+    /// focused. Updates from the target are still received while the
+    /// terminal is unfocused, and the console is redrawn as soon as it's
+    /// focused again.
+    ///
+    /// Terminals which don't report focus changes are always redrawn as
+    /// often as usual.
+    ///
+    /// [default: 10s]
+    #[clap(long = "unfocused-redraw")]
+    unfocused_redraw: Option<UnfocusedRedraw>,
+
+    /// The most frames to draw each second as updates arrive from the
+    /// target.
+    ///
+    /// The updates received between frames are merged together and applied
+    /// at once, so that a target which sends very large updates, or sends
+    /// them faster than they can be drawn, doesn't stall the console. Keys
+    /// are always handled straight away.
+    ///
+    /// [default: 30]
+    #[clap(long = "frame-rate")]
+    frame_rate: Option<NonZeroU32>,
+
     /// An optional subcommand.
     ///
     /// If one of these is present, the console CLI will do something other than
@@ -148,6 +477,37 @@ pub struct Config {
     pub subcmd: Option<OptionalCmd>,
 }
 
+/// Optional columns in the task list.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum TaskColumn {
+    Wakes,
+    Wakers,
+    P99Poll,
+    MaxPoll,
+    PollsPerSec,
+    WakesPerSec,
+    BusyPercent,
+    ScheduledPercent,
+    Awaiting,
+}
+
+impl From<TaskColumn> for tasks::Column {
+    fn from(column: TaskColumn) -> Self {
+        match column {
+            TaskColumn::Wakes => tasks::Column::Wakes,
+            TaskColumn::Wakers => tasks::Column::Wakers,
+            TaskColumn::P99Poll => tasks::Column::P99Poll,
+            TaskColumn::MaxPoll => tasks::Column::MaxPoll,
+            TaskColumn::PollsPerSec => tasks::Column::PollsPerSec,
+            TaskColumn::WakesPerSec => tasks::Column::WakesPerSec,
+            TaskColumn::BusyPercent => tasks::Column::BusyPercent,
+            TaskColumn::ScheduledPercent => tasks::Column::ScheduledPercent,
+            TaskColumn::Awaiting => tasks::Column::Awaiting,
+        }
+    }
+}
+
 /// Known warnings that can be enabled or disabled.
 #[derive(clap::ValueEnum, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "kebab-case")]
@@ -155,6 +515,7 @@ pub(crate) enum KnownWarnings {
     SelfWakes,
     LostWaker,
     NeverYielded,
+    LongPoll,
     AutoBoxedFuture,
     LargeFuture,
 }
@@ -167,6 +528,7 @@ impl FromStr for KnownWarnings {
             "self-wakes" => Ok(KnownWarnings::SelfWakes),
             "lost-waker" => Ok(KnownWarnings::LostWaker),
             "never-yielded" => Ok(KnownWarnings::NeverYielded),
+            "long-poll" => Ok(KnownWarnings::LongPoll),
             "auto-boxed-future" => Ok(KnownWarnings::AutoBoxedFuture),
             "large-future" => Ok(KnownWarnings::LargeFuture),
             _ => Err(format!("unknown warning: {}", s)),
@@ -180,6 +542,7 @@ impl From<&KnownWarnings> for warnings::Linter<Task> {
             KnownWarnings::SelfWakes => warnings::Linter::new(warnings::SelfWakePercent::default()),
             KnownWarnings::LostWaker => warnings::Linter::new(warnings::LostWaker),
             KnownWarnings::NeverYielded => warnings::Linter::new(warnings::NeverYielded::default()),
+            KnownWarnings::LongPoll => warnings::Linter::new(warnings::LongPoll::default()),
             KnownWarnings::AutoBoxedFuture => warnings::Linter::new(warnings::AutoBoxedFuture),
             KnownWarnings::LargeFuture => warnings::Linter::new(warnings::LargeFuture::default()),
         }
@@ -192,6 +555,7 @@ impl fmt::Display for KnownWarnings {
             KnownWarnings::SelfWakes => write!(f, "self-wakes"),
             KnownWarnings::LostWaker => write!(f, "lost-waker"),
             KnownWarnings::NeverYielded => write!(f, "never-yielded"),
+            KnownWarnings::LongPoll => write!(f, "long-poll"),
             KnownWarnings::AutoBoxedFuture => write!(f, "auto-boxed-future"),
             KnownWarnings::LargeFuture => write!(f, "large-future"),
         }
@@ -204,6 +568,7 @@ impl KnownWarnings {
             KnownWarnings::SelfWakes,
             KnownWarnings::LostWaker,
             KnownWarnings::NeverYielded,
+            KnownWarnings::LongPoll,
             KnownWarnings::AutoBoxedFuture,
             KnownWarnings::LargeFuture,
         ]
@@ -277,6 +642,273 @@ pub enum OptionalCmd {
         #[clap(value_enum)]
         shell: Shell,
     },
+
+    /// Check whether the console can connect to a target, without starting
+    /// the console UI.
+    ///
+    /// This connects to the target, checks that it is compatible with this
+    /// version of the console, and prints information about how the target is
+    /// configured and how many tasks, resources, and async ops it is
+    /// tracking.
+    ///
+    /// Exits with a non-zero status if the target can't be reached or is not
+    /// compatible.
+    Check {
+        /// The address or target name of the instrumented application to
+        /// check.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+
+        /// How long to wait for the target to respond before failing.
+        #[clap(long = "timeout", default_value = "5s")]
+        timeout: humantime::Duration,
+    },
+
+    /// Watch a target for a while without starting the console UI, and report
+    /// any warnings that were triggered.
+    ///
+    /// Every warning enabled by `--warn` and not allowed by `--allow` is
+    /// checked for each update the target sends, and every task that
+    /// triggers one is reported, even if it has since stopped triggering it.
+    ///
+    /// Exits with a non-zero status if any warning was triggered, or if no
+    /// update was received from the target, so that it can be used to fail
+    /// CI builds. With `--baseline`, it also exits with a non-zero status if
+    /// the target regressed since the baseline was saved.
+    Lint {
+        #[clap(flatten)]
+        collect: CollectArgs,
+    },
+
+    /// Watch a target for a while without starting the console UI, and print
+    /// a summary of what its tasks did.
+    ///
+    /// The summary lists the tasks which were busy for longest, with their
+    /// 99th percentile poll times, how many tasks were spawned at each
+    /// location, and any warnings that were triggered. Unlike `lint`, this
+    /// exits successfully even if warnings were triggered.
+    Report {
+        #[clap(flatten)]
+        collect: CollectArgs,
+    },
+
+    /// Print the tasks in a target's current state which match a query,
+    /// without starting the console UI.
+    ///
+    /// Queries are written like SQL, selecting columns from the `tasks`
+    /// table, such as:
+    ///
+    ///
+    ///     $ tokio-console query 'select id, name, busy from tasks
+    ///         where state = "idle" and wakers = 0 order by idle desc limit 20'
+    ///
+    /// `select *` selects the columns shown in the task list. Conditions
+    /// compare columns and values with `==` (or `=`), `!=`, `<`, `<=`, `>`
+    /// and `>=`, match columns against regular expressions with `~` and
+    /// `!~`, and are combined with `&&` (or `and`), `||` (or `or`) and `!`
+    /// (or `not`). Durations can be written with units, such as `10ms`, and
+    /// are otherwise in seconds.
+    ///
+    /// A query may also be just a condition, which selects every task that
+    /// matches it, as the task list's filter does:
+    ///
+    ///
+    ///     $ tokio-console query 'state == idle && busy > 1s && target ~ "hyper"' --json
+    Query {
+        /// The query to run.
+        query: crate::query::Query,
+
+        /// The address or target name of the instrumented application to
+        /// query.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(long = "target", value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+
+        /// How to print the tasks which match the query.
+        #[clap(long = "format", value_enum, default_value_t)]
+        format: crate::query::Format,
+
+        /// Print the tasks as JSON, as `--format json` does.
+        #[clap(long = "json", conflicts_with = "format")]
+        json: bool,
+
+        /// How long to wait for the target's state before failing.
+        #[clap(long = "timeout", default_value = "5s")]
+        timeout: humantime::Duration,
+    },
+
+    /// Watch a target for a while without starting the console UI, and
+    /// write how long its tasks spent busy, by where they were spawned, as a
+    /// flamegraph's collapsed stacks.
+    ///
+    /// Each stack is the module path of the file the tasks were spawned in,
+    /// followed by their spawn location, with the time they spent busy while
+    /// the target was watched, in microseconds. The stacks can be drawn with
+    /// `inferno-flamegraph` or `flamegraph.pl`:
+    ///
+    ///
+    ///     $ tokio-console export-flamegraph --output busy.folded
+    ///     $ inferno-flamegraph busy.folded > busy.svg
+    ///
+    ExportFlamegraph {
+        /// The address or target name of the instrumented application to
+        /// watch.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+
+        /// How long to watch the target for.
+        #[clap(long = "duration", default_value = "60s")]
+        duration: humantime::Duration,
+
+        /// The file to write the stacks to.
+        ///
+        /// Defaults to a new file in the current directory.
+        #[clap(long = "output", short = 'o', value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the task and resource lists as the console would display them,
+    /// and exit, without starting the console UI.
+    ///
+    /// This waits for the target's current state, so that the lists include
+    /// everything it's currently tracking. The lists have the columns chosen
+    /// by `--show-columns`, `--task-columns`, `--field-columns` and
+    /// `--sort-expressions`, and are sorted as they are when the console
+    /// starts.
+    Dump {
+        /// The address or target name of the instrumented application to
+        /// dump.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+
+        /// How to print the lists.
+        #[clap(long = "format", value_enum, default_value_t)]
+        format: crate::query::Format,
+
+        /// How long to wait for the target's state before failing.
+        #[clap(long = "timeout", default_value = "5s")]
+        timeout: humantime::Duration,
+    },
+
+    /// Connect to many targets, such as every replica of a service, and
+    /// serve their merged state for a console to connect to, without
+    /// starting the console UI.
+    ///
+    /// Each target's tasks, resources and async ops are given new IDs, so
+    /// that they don't collide, and are tagged with a `source` field naming
+    /// the target they came from.
+    Aggregate {
+        /// The address to serve the merged state at.
+        #[clap(long = "listen", value_name = "ADDR")]
+        listen: SocketAddr,
+
+        /// The addresses or target names of the instrumented applications
+        /// to aggregate.
+        #[clap(required = true, value_hint = ValueHint::Url)]
+        targets: Vec<String>,
+    },
+
+    /// Watch a target and serve its runtime health as Prometheus metrics,
+    /// without starting the console UI.
+    ///
+    /// The metrics are served on `/metrics`, and include the number of tasks
+    /// in each state, and the total polls, busy time, wakes and self-wakes
+    /// of all of the target's tasks, including those which have completed.
+    Export {
+        /// The address to serve the metrics at.
+        #[clap(long = "listen", value_name = "ADDR")]
+        listen: SocketAddr,
+
+        /// The address or target name of the instrumented application to
+        /// export metrics for.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+    },
+
+    /// Watch a target and serve a live view of it to browsers, without
+    /// starting the console UI.
+    ///
+    /// The task and resource lists are served as they'd be displayed, as
+    /// JSON on `/api/tasks` and `/api/resources`, and each task's and
+    /// resource's details on `/api/tasks/<id>` and `/api/resources/<id>`,
+    /// along with a page showing them on `/`, so that many people can watch
+    /// a target through a single connection to it. `/api/tasks` takes a
+    /// `filter` parameter, written as the task list's filter is.
+    ///
+    /// Anyone who can reach the address can see the target's tasks, so it
+    /// should only be reachable from trusted networks.
+    Serve {
+        /// The address to serve the view at.
+        #[clap(long = "listen", value_name = "ADDR", default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+
+        /// The address or target name of the instrumented application to
+        /// serve a view of.
+        ///
+        /// Defaults to the address the console would connect to.
+        #[clap(value_hint = ValueHint::Url)]
+        target_addr: Option<String>,
+    },
+
+    /// Run the console against a made-up target, which spawns and completes
+    /// tasks and creates and drops resources, without connecting to an
+    /// instrumented application.
+    ///
+    /// This is useful for trying out the console, and for taking
+    /// screenshots. The made-up target can be paused, but doesn't record
+    /// task details, so the task details view doesn't show histograms.
+    Demo,
+
+    /// Replay a recording made with `--record`, without connecting to
+    /// an instrumented application.
+    ///
+    /// The recording is replayed at the rate it was recorded. It can be
+    /// paused and stepped through like a live target, and `<` and `>` seek
+    /// backwards and forwards through it by ten seconds.
+    Replay {
+        /// The recording to replay.
+        #[clap(value_hint = ValueHint::FilePath)]
+        recording: PathBuf,
+    },
+}
+
+/// Options for the subcommands which watch a target for a while.
+#[derive(Clap, Debug, PartialEq, Eq)]
+pub struct CollectArgs {
+    /// The address or target name of the instrumented application to
+    /// watch.
+    ///
+    /// Defaults to the address the console would connect to.
+    #[clap(value_hint = ValueHint::Url)]
+    pub(crate) target_addr: Option<String>,
+
+    /// How long to watch the target for.
+    #[clap(long = "duration", default_value = "60s")]
+    pub(crate) duration: humantime::Duration,
+
+    /// Save what was seen while watching the target to a file, to compare
+    /// later runs against with `--baseline`.
+    #[clap(long = "save", value_hint = ValueHint::FilePath)]
+    pub(crate) save: Option<PathBuf>,
+
+    /// Compare this run against one saved with `--save`, and report
+    /// statistically significant regressions.
+    ///
+    /// A location regresses if its tasks spend longer busy or have longer
+    /// p99 poll times, or if more tasks are spawned there, than in the
+    /// baseline. Both runs should watch the target for the same duration
+    /// under the same load.
+    #[clap(long = "baseline", value_hint = ValueHint::FilePath)]
+    pub(crate) baseline: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -306,10 +938,41 @@ impl Serialize for RetainFor {
     }
 }
 
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct UnfocusedRedraw(Option<Duration>);
+
+impl Default for UnfocusedRedraw {
+    fn default() -> Self {
+        Self(Some(Duration::from_secs(10)))
+    }
+}
+
+impl fmt::Display for UnfocusedRedraw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "none"),
+            Some(duration) => write!(f, "{}", humantime::format_duration(duration)),
+        }
+    }
+}
+
+impl Serialize for UnfocusedRedraw {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
 #[derive(Clap, Debug, Clone)]
 #[clap(group = ArgGroup::new("colors").conflicts_with("no-colors"))]
 pub struct ViewOptions {
     /// Overrides the terminal's default language.
+    ///
+    /// This also selects the language of the console's text. German (`de`)
+    /// is translated, and other languages are shown in English.
     #[clap(long = "lang", env = "LANG")]
     lang: Option<String>,
 
@@ -321,6 +984,26 @@ pub struct ViewOptions {
     #[clap(name = "no-colors", long = "no-colors",action = ArgAction::SetTrue)]
     no_colors: bool,
 
+    /// Render without colors, using only bold, reversed and underlined text.
+    ///
+    /// This is for monochrome terminals, and for anyone who needs as much
+    /// contrast as possible.
+    #[clap(long = "high-contrast", action = ArgAction::SetTrue)]
+    high_contrast: bool,
+
+    /// Which color theme to start with.
+    ///
+    /// `light` uses darker colors, for terminals with light backgrounds.
+    /// The theme can be switched while the console is running by pressing
+    /// `M`.
+    #[clap(long = "theme", value_enum)]
+    theme: Option<Theme>,
+
+    /// Colors which are replaced with colors of the user's own, from the
+    /// `[colors.custom]` table of the config file.
+    #[clap(skip)]
+    custom_colors: Vec<(Color, Color)>,
+
     /// Overrides the value of the `COLORTERM` environment variable.
     ///
     /// If this is set to `24bit` or `truecolor`, 24-bit RGB color support will be enabled.
@@ -341,6 +1024,23 @@ pub struct ViewOptions {
     )]
     palette: Option<Palette>,
 
+    /// Swap colors which are hard to tell apart with color blindness for
+    /// ones which aren't.
+    #[clap(
+        long = "color-vision",
+        value_parser = color_vision_parser(),
+        group = "colors"
+    )]
+    color_vision: Option<ColorVision>,
+
+    /// Which image protocol to draw charts with.
+    ///
+    /// By default, charts are drawn as images if the terminal supports the
+    /// kitty or iTerm2 image protocols, and colors are enabled, and as text
+    /// otherwise.
+    #[clap(long = "graphics", value_parser = graphics_parser())]
+    graphics: Option<GraphicsMode>,
+
     #[clap(flatten)]
     toggles: ColorToggles,
 }
@@ -360,6 +1060,46 @@ pub struct ColorToggles {
     color_terminated: Option<bool>,
 }
 
+/// Toggles on and off automatically pausing the target.
+#[derive(Clap, Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutoPause {
+    /// Automatically pause the target while a task's details are open.
+    ///
+    /// The target is paused when opening a task's details, and resumed when
+    /// leaving them, unless it was already paused.
+    #[clap(long = "auto-pause-details")]
+    #[serde(rename = "details")]
+    auto_pause_details: Option<bool>,
+
+    /// Automatically pause the target when a task first triggers one of
+    /// these warnings.
+    ///
+    /// This is a comma-separated list of warnings, using the same names as
+    /// `--warn`. Warnings which are not enabled will never pause the target.
+    #[clap(long = "auto-pause-on", value_delimiter = ',', num_args = 1..)]
+    #[serde(rename = "warnings", default)]
+    auto_pause_warnings: Vec<KnownWarnings>,
+
+    /// Automatically pause the target when a condition becomes true.
+    ///
+    /// Each condition is written as `<metric> > <threshold>`, where the metric
+    /// is one of:
+    ///
+    /// * `tasks` -- The number of tasks which have not completed.
+    ///
+    /// * `resources` -- The number of resources which have not been dropped.
+    ///
+    /// * `poll` -- The duration of the in-progress or most recent poll of
+    ///   any task, such as `poll > 5s`.
+    ///
+    /// This may be passed multiple times to pause on any of several
+    /// conditions.
+    #[clap(long = "pause-when")]
+    #[serde(rename = "conditions", default)]
+    pause_conditions: Vec<PauseCondition>,
+}
+
 #[derive(Clone, Debug)]
 struct LogFilter(filter::Targets);
 
@@ -397,10 +1137,44 @@ struct ConfigFile {
     log: Option<String>,
     warnings: Vec<KnownWarnings>,
     allow_warnings: Option<AllowedWarnings>,
+    #[serde(default)]
+    task_columns: Vec<TaskColumn>,
+    #[serde(default)]
+    field_columns: Vec<String>,
+    #[serde(default)]
+    sort_expressions: Vec<Expr>,
+    #[serde(default)]
+    show_columns: Vec<ColumnConfig>,
+    sort: Option<SortConfig>,
+    #[serde(default)]
+    slos: Vec<Slo>,
+    #[serde(default)]
+    highlights: Vec<Highlight>,
+    editor_command: Option<EditorCommand>,
+    notify: Option<NotifyMethod>,
+    #[serde(default)]
+    notify_on: Vec<NotifyEvent>,
+    relay: Option<SocketAddr>,
+    record: Option<PathBuf>,
+    #[serde(default)]
+    discover: Vec<Discover>,
+    #[serde(default)]
+    tabs: Vec<String>,
     log_directory: Option<PathBuf>,
     retention: Option<RetainFor>,
+    unfocused_redraw: Option<UnfocusedRedraw>,
+    frame_rate: Option<NonZeroU32>,
+    graphics: Option<GraphicsMode>,
+    #[serde(default)]
+    targets: BTreeMap<String, String>,
+    #[serde(default)]
+    keymap: BTreeMap<String, Key>,
     charset: Option<CharsetConfig>,
     colors: Option<ColorsConfig>,
+    auto_pause: Option<AutoPause>,
+    auth: Option<Auth>,
+    reconnect: Option<Reconnect>,
+    alerts: Option<Alerting>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -414,9 +1188,16 @@ struct CharsetConfig {
 #[serde(deny_unknown_fields)]
 struct ColorsConfig {
     enabled: Option<bool>,
+    high_contrast: Option<bool>,
+    theme: Option<Theme>,
     truecolor: Option<bool>,
     palette: Option<Palette>,
+    vision: Option<ColorVision>,
     enable: Option<ColorToggles>,
+    /// Colors, such as `yellow`, and the colors to draw them as instead,
+    /// such as `#d7af00`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    custom: BTreeMap<String, String>,
 }
 
 // === impl Config ===
@@ -520,17 +1301,110 @@ impl Config {
         self.retain_for.unwrap_or_default().0
     }
 
+    /// Returns how often to redraw while the terminal is unfocused, or `None`
+    /// if it's redrawn as often as usual.
+    pub(crate) fn unfocused_redraw(&self) -> Option<Duration> {
+        self.unfocused_redraw.unwrap_or_default().0
+    }
+
+    /// Returns how the console reconnects to targets after losing its
+    /// connection.
+    pub(crate) fn reconnect_policy(&self) -> conn::ReconnectPolicy {
+        let default = conn::ReconnectPolicy::default();
+        let reconnect = &self.reconnect;
+        conn::ReconnectPolicy {
+            initial: reconnect.initial.map_or(default.initial, |d| d.0),
+            multiplier: reconnect.multiplier.unwrap_or(default.multiplier),
+            max: reconnect.max.map_or(default.max, |d| d.0),
+            jitter: reconnect.jitter.unwrap_or(default.jitter),
+            give_up_after: reconnect
+                .give_up_after
+                .map(|d| d.0)
+                .or(default.give_up_after),
+        }
+    }
+
+    /// Returns the shortest time between frames drawn as updates arrive.
+    pub(crate) fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.frame_rate.map_or(DEFAULT_FRAME_RATE, NonZeroU32::get)
+    }
+
+    /// Returns the warnings which are enabled, and not allowed.
+    pub(crate) fn enabled_warnings(&self) -> Vec<&KnownWarnings> {
+        match &self.allow_warnings {
+            Some(AllowedWarnings::All) => vec![],
+            Some(AllowedWarnings::Explicit(allow_warnings)) => self
+                .warnings
+                .iter()
+                .filter(|lint| !allow_warnings.contains(lint))
+                .collect(),
+            None => self.warnings.iter().collect(),
+        }
+    }
+
+    /// Returns the columns which the task list displays in addition to its
+    /// own, from `--task-columns`, `--field-columns` and
+    /// `--sort-expressions`.
+    pub(crate) fn task_columns(&self) -> impl Iterator<Item = tasks::Column> + '_ {
+        self.task_columns
+            .iter()
+            .copied()
+            .map(Into::into)
+            .chain(self.field_columns.iter().cloned().map(tasks::Column::Field))
+            .chain(
+                self.sort_expressions
+                    .iter()
+                    .cloned()
+                    .map(tasks::Column::Expr),
+            )
+    }
+
     pub(crate) fn target_addr(&self) -> color_eyre::Result<Uri> {
-        let target_addr = self
-            .target_addr
-            .as_ref()
-            .unwrap_or(&default_target_addr())
-            .clone();
+        match self.target_addr {
+            Some(ref target) => self.resolve_target(target),
+            None => Ok(default_target_addr()),
+        }
+    }
+
+    /// Returns the name of the target being connected to, if the target
+    /// address was given as the name of a target in the config file.
+    pub(crate) fn target_name(&self) -> Option<&str> {
+        self.target_addr
+            .as_deref()
+            .filter(|target| self.is_target_name(target))
+    }
+
+    /// Returns the named targets from the config file, as `(name, address)`
+    /// pairs.
+    pub(crate) fn named_targets(&self) -> impl Iterator<Item = (&str, &Uri)> {
+        self.targets
+            .iter()
+            .map(|(name, addr)| (name.as_str(), addr))
+    }
+
+    /// Returns `true` if `target` is the name of a target in the config file.
+    pub(crate) fn is_target_name(&self, target: &str) -> bool {
+        self.targets.contains_key(target)
+    }
+
+    /// Returns the address of `target`, which is either the name of a target
+    /// in the config file or an address.
+    pub(crate) fn resolve_target(&self, target: &str) -> color_eyre::Result<Uri> {
+        let target_addr = match self.targets.get(target) {
+            Some(addr) => addr.clone(),
+            None => conn::parse_target(target).wrap_err_with(|| {
+                format!("{target:?} is not a URI or the name of a target in the config file")
+            })?,
+        };
         match target_addr.scheme_str() {
-            Some("file" | "http" | "https") => {}
+            Some(
+                "file" | "http" | "https" | conn::FRAMED | conn::FRAMED_UNIX | conn::WEBSOCKET
+                | conn::WEBSOCKET_TLS | conn::QUIC | conn::SNAPSHOT | conn::REPLAY | conn::DEMO
+                | conn::KUBERNETES | conn::DOCKER | conn::DNS_SRV,
+            ) => {}
             _ => {
                 return Err(color_eyre::eyre::eyre!(
-                "invalid scheme for target address {:?}, must be one of 'file', 'http', or 'https'",
+                "invalid scheme for target address {:?}, must be one of 'file', 'http', 'https', 'framed', 'framed+file', 'ws', 'wss', 'quic', 'snapshot', 'replay', 'k8s', 'docker', or 'dns+srv'",
                 target_addr
             ))
             }
@@ -561,12 +1435,28 @@ impl Config {
                 target_addr,
                 log_filter,
                 log_directory,
+                editor_command,
+                notify,
+                notify_on,
+                relay,
+                record,
+                discover,
+                tabs,
                 retain_for,
+                unfocused_redraw,
+                frame_rate,
+                auth.tls_ca,
+                auth.tls_cert,
+                auth.tls_key,
                 view_options.no_colors,
+                view_options.high_contrast,
+                view_options.theme,
                 view_options.lang,
                 view_options.ascii_only,
                 view_options.truecolor,
                 view_options.palette,
+                view_options.color_vision,
+                view_options.graphics,
                 view_options.toggles.color_durations,
                 view_options.toggles.color_terminated,
         }
@@ -583,7 +1473,46 @@ impl Config {
     fn merge_with(self, other: Self) -> Self {
         Self {
             log_directory: other.log_directory.or(self.log_directory),
+            editor_command: other.editor_command.or(self.editor_command),
+            notify: other.notify.or(self.notify),
+            notify_on: {
+                let mut events = self.notify_on;
+                for event in other.notify_on {
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                }
+                events
+            },
+            relay: other.relay.or(self.relay),
+            record: other.record.or(self.record),
+            discover: {
+                let mut sources = self.discover;
+                for source in other.discover {
+                    if !sources.contains(&source) {
+                        sources.push(source);
+                    }
+                }
+                sources
+            },
+            tabs: {
+                let mut tabs = self.tabs;
+                tabs.extend(other.tabs);
+                tabs
+            },
             target_addr: other.target_addr.or(self.target_addr),
+            targets: {
+                let mut targets = self.targets;
+                targets.extend(other.targets);
+                targets
+            },
+            keymap: {
+                let mut keymap = self.keymap;
+                for (key, to) in other.keymap.bindings() {
+                    keymap.bind(key, to);
+                }
+                keymap
+            },
             log_filter: other.log_filter.or(self.log_filter),
             warnings: {
                 let mut warns: Vec<KnownWarnings> = other.warnings;
@@ -598,8 +1527,66 @@ impl Config {
                     (a, b) => a.or(b),
                 }
             },
+            task_columns: {
+                let mut columns = self.task_columns;
+                for column in other.task_columns {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                columns
+            },
+            field_columns: {
+                let mut columns = self.field_columns;
+                for column in other.field_columns {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                columns
+            },
+            sort_expressions: {
+                let mut expressions = self.sort_expressions;
+                for expression in other.sort_expressions {
+                    if !expressions.contains(&expression) {
+                        expressions.push(expression);
+                    }
+                }
+                expressions
+            },
+            // The columns are listed in order, so they can't be combined.
+            show_columns: if other.show_columns.is_empty() {
+                self.show_columns
+            } else {
+                other.show_columns
+            },
+            sort: other.sort.or(self.sort),
+            slos: {
+                let mut slos = self.slos;
+                for slo in other.slos {
+                    if !slos.contains(&slo) {
+                        slos.push(slo);
+                    }
+                }
+                slos
+            },
+            highlights: {
+                let mut highlights = self.highlights;
+                for highlight in other.highlights {
+                    if !highlights.contains(&highlight) {
+                        highlights.push(highlight);
+                    }
+                }
+                highlights
+            },
             retain_for: other.retain_for.or(self.retain_for),
+            unfocused_redraw: other.unfocused_redraw.or(self.unfocused_redraw),
+            frame_rate: other.frame_rate.or(self.frame_rate),
             view_options: self.view_options.merge_with(other.view_options),
+            auto_pause: self.auto_pause.merge_with(other.auto_pause),
+            auth: self.auth.merge_with(other.auth),
+            reconnect: self.reconnect.merge_with(other.reconnect),
+            alerts: self.alerts.merge_with(other.alerts),
             subcmd: other.subcmd.or(self.subcmd),
         }
     }
@@ -608,15 +1595,37 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            target_addr: Some(default_target_addr()),
+            target_addr: Some(default_target_addr().to_string()),
+            targets: BTreeMap::new(),
+            keymap: Keymap::default(),
             log_filter: Some(LogFilter(
                 filter::Targets::new().with_default(filter::LevelFilter::OFF),
             )),
             warnings: KnownWarnings::default_enabled_warnings(),
             allow_warnings: None,
+            task_columns: Vec::new(),
+            field_columns: Vec::new(),
+            sort_expressions: Vec::new(),
+            show_columns: Vec::new(),
+            sort: None,
+            slos: Vec::new(),
+            highlights: Vec::new(),
+            editor_command: None,
+            notify: None,
+            notify_on: Vec::new(),
+            relay: None,
+            record: None,
+            discover: Vec::new(),
+            tabs: Vec::new(),
             log_directory: Some(default_log_directory()),
             retain_for: Some(RetainFor::default()),
+            unfocused_redraw: Some(UnfocusedRedraw::default()),
+            frame_rate: NonZeroU32::new(DEFAULT_FRAME_RATE),
             view_options: ViewOptions::default(),
+            auto_pause: AutoPause::default(),
+            auth: Auth::default(),
+            reconnect: Reconnect::default(),
+            alerts: Alerting::default(),
             subcmd: None,
         }
     }
@@ -635,6 +1644,11 @@ fn default_log_directory() -> PathBuf {
 // === impl ViewOptions ===
 
 impl ViewOptions {
+    /// The language to show the console's text in, if one was set.
+    pub(crate) fn lang(&self) -> Option<&str> {
+        self.lang.as_deref()
+    }
+
     pub fn is_utf8(&self) -> bool {
         if self.ascii_only.unwrap_or(false) {
             return false;
@@ -688,17 +1702,49 @@ impl ViewOptions {
         Palette::NoColors
     }
 
+    /// Returns the image protocol to draw charts with, if any.
+    pub(crate) fn graphics_protocol(&self) -> Option<Protocol> {
+        self.graphics.unwrap_or_default().protocol()
+    }
+
+    /// Returns the theme to start with, which is always the high contrast
+    /// theme with `--high-contrast`.
+    pub(crate) fn theme(&self) -> Theme {
+        if self.high_contrast {
+            return Theme::HighContrast;
+        }
+        self.theme.unwrap_or_default()
+    }
+
+    pub(crate) fn custom_colors(&self) -> &[(Color, Color)] {
+        &self.custom_colors
+    }
+
+    pub(crate) fn color_vision(&self) -> ColorVision {
+        self.color_vision.unwrap_or_default()
+    }
+
     pub(crate) fn toggles(&self) -> ColorToggles {
         self.toggles
     }
 
     fn merge_with(self, command_line: ViewOptions) -> Self {
+        let mut custom_colors = self.custom_colors;
+        for (color, replacement) in command_line.custom_colors {
+            custom_colors.retain(|(replaced, _)| *replaced != color);
+            custom_colors.push((color, replacement));
+        }
         Self {
             no_colors: command_line.no_colors || self.no_colors,
+            high_contrast: command_line.high_contrast || self.high_contrast,
+            theme: command_line.theme.or(self.theme),
+            custom_colors,
             lang: command_line.lang.or(self.lang),
             ascii_only: command_line.ascii_only.or(self.ascii_only),
             truecolor: command_line.truecolor.or(self.truecolor),
             palette: command_line.palette.or(self.palette),
+            color_vision: command_line.color_vision.or(self.color_vision),
+            graphics: command_line.graphics.or(self.graphics),
             toggles: ColorToggles {
                 color_durations: command_line
                     .toggles
@@ -717,10 +1763,15 @@ impl Default for ViewOptions {
     fn default() -> Self {
         Self {
             no_colors: false,
+            high_contrast: false,
+            theme: Some(Theme::Dark),
+            custom_colors: Vec::new(),
             lang: Some("en_us.UTF-8".to_string()),
             ascii_only: Some(false),
             truecolor: Some(true),
             palette: Some(Palette::All),
+            color_vision: Some(ColorVision::Normal),
+            graphics: Some(GraphicsMode::Auto),
             toggles: ColorToggles {
                 color_durations: Some(true),
                 color_terminated: Some(true),
@@ -740,6 +1791,20 @@ fn palette_parser() -> impl TypedValueParser<Value = Palette> {
     })
 }
 
+fn graphics_parser() -> impl TypedValueParser<Value = GraphicsMode> {
+    PossibleValuesParser::new(["auto", "kitty", "iterm2", "off"]).map(|s| {
+        s.parse::<GraphicsMode>()
+            .expect("possible values must have validated that this is a valid `GraphicsMode`")
+    })
+}
+
+fn color_vision_parser() -> impl TypedValueParser<Value = ColorVision> {
+    PossibleValuesParser::new(["normal", "deuteranopia", "protanopia"]).map(|s| {
+        s.parse::<ColorVision>()
+            .expect("possible values must have validated that this is a valid `ColorVision`")
+    })
+}
+
 fn parse_true_color(s: impl AsRef<str>) -> bool {
     let s = s.as_ref().trim();
     s.eq_ignore_ascii_case("truecolor") || s.eq_ignore_ascii_case("24bit")
@@ -758,6 +1823,27 @@ impl FromStr for RetainFor {
     }
 }
 
+impl FromStr for UnfocusedRedraw {
+    type Err = humantime::DurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            s if s.eq_ignore_ascii_case("none") => Ok(UnfocusedRedraw(None)),
+            _ => s
+                .parse::<humantime::Duration>()
+                .map(|duration| UnfocusedRedraw(Some(duration.into()))),
+        }
+    }
+}
+
+impl TryFrom<String> for UnfocusedRedraw {
+    type Error = humantime::DurationError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 // === impl ColorToggles ===
 
 impl ColorToggles {
@@ -772,7 +1858,281 @@ impl ColorToggles {
     }
 }
 
-// === impl ColorToggles ===
+/// How the console connects to targets, and secures and authenticates its
+/// connections.
+#[derive(Clap, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Auth {
+    /// A PEM file of the certificate authorities to verify the certificates
+    /// of `https`, `wss` and `quic` targets with.
+    ///
+    /// [default: the Mozilla root certificates]
+    #[clap(long = "tls-ca", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    #[serde(rename = "ca")]
+    pub(crate) tls_ca: Option<PathBuf>,
+
+    /// A PEM file of the certificate chain which the console presents to
+    /// targets which require client certificates.
+    ///
+    /// The certificate's private key is read from `--tls-key`.
+    #[clap(long = "tls-cert", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    #[serde(rename = "cert")]
+    pub(crate) tls_cert: Option<PathBuf>,
+
+    /// A PEM file of the private key of the certificate in `--tls-cert`.
+    #[clap(long = "tls-key", value_name = "FILE", value_hint = ValueHint::FilePath)]
+    #[serde(rename = "key")]
+    pub(crate) tls_key: Option<PathBuf>,
+
+    /// A bearer token to send in the `authorization` header of every gRPC
+    /// request to the target.
+    #[clap(long = "token", env = "TOKIO_CONSOLE_TOKEN", hide_env_values = true)]
+    pub(crate) token: Option<String>,
+
+    /// Connect to `http` and `https` targets with gRPC-web, rather than
+    /// gRPC.
+    ///
+    /// This is for targets which can only be reached through a gRPC-web
+    /// proxy, such as an ingress controller which doesn't pass HTTP/2 gRPC
+    /// through. Any path in the target address is prepended to the path of
+    /// each request, for proxies which serve the target under a path.
+    #[clap(long = "grpc-web", action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub(crate) grpc_web: bool,
+}
+
+/// How the console reconnects to a target after losing its connection.
+///
+/// After each failed attempt, the console waits longer before trying again,
+/// starting at `--reconnect-initial` and growing by `--reconnect-multiplier`
+/// up to `--reconnect-max`.
+#[derive(Clap, Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reconnect {
+    /// How long to wait before the first attempt to reconnect.
+    ///
+    /// [default: 500ms]
+    #[clap(long = "reconnect-initial", value_name = "DURATION")]
+    #[serde(rename = "initial")]
+    initial: Option<ReconnectDuration>,
+
+    /// How much longer to wait after each failed attempt to reconnect than
+    /// after the one before it.
+    ///
+    /// [default: 2]
+    #[clap(long = "reconnect-multiplier", value_name = "FACTOR")]
+    #[serde(rename = "multiplier")]
+    multiplier: Option<f64>,
+
+    /// The longest to wait between attempts to reconnect.
+    ///
+    /// [default: 5s]
+    #[clap(long = "reconnect-max", value_name = "DURATION")]
+    #[serde(rename = "max")]
+    max: Option<ReconnectDuration>,
+
+    /// How much each wait is randomly lengthened or shortened by, as a
+    /// fraction of it, so that many consoles don't reconnect in lockstep.
+    ///
+    /// [default: 0.1]
+    #[clap(long = "reconnect-jitter", value_name = "FRACTION")]
+    #[serde(rename = "jitter")]
+    jitter: Option<f64>,
+
+    /// Stop trying to reconnect after the target has been unreachable for
+    /// this long.
+    ///
+    /// [default: never give up]
+    #[clap(long = "reconnect-give-up-after", value_name = "DURATION")]
+    #[serde(rename = "give_up_after")]
+    give_up_after: Option<ReconnectDuration>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+struct ReconnectDuration(Duration);
+
+/// Alerts, which tell you as soon as any task matches a condition, such as
+/// being busy for too long, by showing a banner over the current view and
+/// logging the alert in the event log.
+#[derive(Clap, Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Alerting {
+    /// Alert when any task matches a condition.
+    ///
+    /// This is a comma-separated list of rules, written as `<condition>` or
+    /// `<condition> in <window>`, such as `busy > 2s` or
+    /// `self_wakes > 100 in 10s`. Conditions are written as in
+    /// `--highlight`. With a window, each expression in the condition is how
+    /// much it grew over the window, so `busy > 2s in 10s` matches tasks
+    /// which were busy for more than two of the last ten seconds. A task
+    /// only triggers an alert again once it has stopped matching it.
+    #[clap(long = "alert", value_delimiter = ',', num_args = 1..)]
+    #[serde(rename = "rules", default)]
+    alert_rules: Vec<Alert>,
+
+    /// Ring the terminal bell when an alert is triggered.
+    #[clap(long = "alert-bell")]
+    #[serde(rename = "bell", default)]
+    alert_bell: bool,
+
+    /// A command to run when an alert is triggered, such as a script which
+    /// posts to a webhook.
+    ///
+    /// The command is split on whitespace, and is not run in a shell. The
+    /// alert's rule and a description of the tasks which triggered it are
+    /// passed in the `TOKIO_CONSOLE_ALERT` and `TOKIO_CONSOLE_ALERT_MESSAGE`
+    /// environment variables.
+    #[clap(long = "alert-command", value_name = "COMMAND")]
+    #[serde(rename = "command")]
+    alert_command: Option<AlertCommand>,
+}
+
+// === impl AutoPause ===
+
+impl AutoPause {
+    /// Automatically pause the target while a task's details are open?
+    pub fn details(&self) -> bool {
+        self.auto_pause_details.unwrap_or(false)
+    }
+
+    /// The warnings which pause the target when they are triggered.
+    pub(crate) fn warnings(&self) -> &[KnownWarnings] {
+        &self.auto_pause_warnings
+    }
+
+    /// The conditions which pause the target when they become true.
+    pub(crate) fn conditions(&self) -> &[PauseCondition] {
+        &self.pause_conditions
+    }
+
+    fn merge_with(self, command_line: AutoPause) -> Self {
+        Self {
+            auto_pause_details: command_line.auto_pause_details.or(self.auto_pause_details),
+            auto_pause_warnings: {
+                let mut warns = command_line.auto_pause_warnings;
+                warns.extend(self.auto_pause_warnings);
+                warns.sort_unstable();
+                warns.dedup();
+                warns
+            },
+            pause_conditions: {
+                let mut conditions = self.pause_conditions;
+                for condition in command_line.pause_conditions {
+                    if !conditions.contains(&condition) {
+                        conditions.push(condition);
+                    }
+                }
+                conditions
+            },
+        }
+    }
+}
+
+// === impl Alerting ===
+
+impl Alerting {
+    /// Returns the monitor which checks each update against the rules.
+    pub(crate) fn monitor(&self) -> alert::Alerts {
+        alert::Alerts::new(
+            &self.alert_rules,
+            self.alert_bell,
+            self.alert_command.clone(),
+        )
+    }
+
+    fn merge_with(self, command_line: Alerting) -> Self {
+        Self {
+            alert_rules: {
+                let mut rules = self.alert_rules;
+                for rule in command_line.alert_rules {
+                    if !rules.contains(&rule) {
+                        rules.push(rule);
+                    }
+                }
+                rules
+            },
+            alert_bell: command_line.alert_bell || self.alert_bell,
+            alert_command: command_line.alert_command.or(self.alert_command),
+        }
+    }
+}
+
+// === impl Auth ===
+
+impl Auth {
+    fn merge_with(self, command_line: Auth) -> Self {
+        Self {
+            tls_ca: command_line.tls_ca.or(self.tls_ca),
+            tls_cert: command_line.tls_cert.or(self.tls_cert),
+            tls_key: command_line.tls_key.or(self.tls_key),
+            token: command_line.token.or(self.token),
+            grpc_web: command_line.grpc_web || self.grpc_web,
+        }
+    }
+}
+
+// === impl Reconnect ===
+
+impl Reconnect {
+    fn merge_with(self, command_line: Reconnect) -> Self {
+        Self {
+            initial: command_line.initial.or(self.initial),
+            multiplier: command_line.multiplier.or(self.multiplier),
+            max: command_line.max.or(self.max),
+            jitter: command_line.jitter.or(self.jitter),
+            give_up_after: command_line.give_up_after.or(self.give_up_after),
+        }
+    }
+}
+
+impl FromStr for ReconnectDuration {
+    type Err = humantime::DurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<humantime::Duration>()
+            .map(|duration| ReconnectDuration(duration.into()))
+    }
+}
+
+impl TryFrom<String> for ReconnectDuration {
+    type Error = humantime::DurationError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ReconnectDuration> for String {
+    fn from(duration: ReconnectDuration) -> Self {
+        humantime::format_duration(duration.0).to_string()
+    }
+}
+
+// The token is a secret, so it's never logged or included in issue reports.
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth")
+            .field("tls_ca", &self.tls_ca)
+            .field("tls_cert", &self.tls_cert)
+            .field("tls_key", &self.tls_key)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("grpc_web", &self.grpc_web)
+            .finish()
+    }
+}
+
+impl Default for AutoPause {
+    fn default() -> Self {
+        Self {
+            auto_pause_details: Some(false),
+            auto_pause_warnings: Vec::new(),
+            pause_conditions: Vec::new(),
+        }
+    }
+}
+
+// === impl ConfigFile ===
 
 impl ConfigFile {
     fn from_path(path: ConfigPath) -> color_eyre::Result<Option<Self>> {
@@ -790,19 +2150,27 @@ impl ConfigFile {
         Ok(config)
     }
 
-    fn target_addr(&self) -> color_eyre::Result<Option<Uri>> {
-        let uri = self
-            .default_target_addr
-            .as_ref()
-            .map(|addr| addr.parse::<Uri>())
-            .transpose()
-            .wrap_err_with(|| {
-                format!(
-                    "failed to parse target address {:?} as URI",
-                    self.default_target_addr
-                )
-            })?;
-        Ok(uri)
+    fn targets(&self) -> color_eyre::Result<BTreeMap<String, Uri>> {
+        self.targets
+            .iter()
+            .map(|(name, addr)| {
+                let addr = conn::parse_target(addr).wrap_err_with(|| {
+                    format!("failed to parse address {addr:?} of target {name:?} as URI")
+                })?;
+                Ok((name.clone(), addr))
+            })
+            .collect()
+    }
+
+    fn keymap(&self) -> color_eyre::Result<Keymap> {
+        let mut keymap = Keymap::default();
+        for (key, to) in &self.keymap {
+            let key = key
+                .parse::<Key>()
+                .wrap_err_with(|| format!("failed to parse key {key:?} in the keymap"))?;
+            keymap.bind(key, *to);
+        }
+        Ok(keymap)
     }
 
     fn log_filter(&self) -> color_eyre::Result<Option<LogFilter>> {
@@ -830,6 +2198,22 @@ impl ConfigFile {
             .and_then(|config| config.enabled.map(Not::not))
     }
 
+    fn custom_colors(&self) -> color_eyre::Result<Vec<(Color, Color)>> {
+        let Some(colors) = self.colors.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let parse = |color: &str| {
+            color.parse::<Color>().map_err(|_| {
+                color_eyre::eyre::eyre!("unknown color {color:?} in `[colors.custom]`")
+            })
+        };
+        colors
+            .custom
+            .iter()
+            .map(|(color, replacement)| Ok((parse(color)?, parse(replacement)?)))
+            .collect()
+    }
+
     fn color_durations(&self) -> Option<bool> {
         self.colors
             .as_ref()
@@ -846,22 +2230,62 @@ impl ConfigFile {
 impl From<Config> for ConfigFile {
     fn from(config: Config) -> Self {
         Self {
-            default_target_addr: config.target_addr.map(|addr| addr.to_string()),
+            default_target_addr: config.target_addr,
+            targets: config
+                .targets
+                .into_iter()
+                .map(|(name, addr)| (name, addr.to_string()))
+                .collect(),
+            keymap: config
+                .keymap
+                .bindings()
+                .map(|(key, to)| (key.to_string(), to))
+                .collect(),
             log: config.log_filter.map(|filter| filter.to_string()),
             log_directory: config.log_directory,
             warnings: config.warnings,
             allow_warnings: config.allow_warnings,
+            task_columns: config.task_columns,
+            field_columns: config.field_columns,
+            sort_expressions: config.sort_expressions,
+            show_columns: config.show_columns,
+            sort: config.sort,
+            slos: config.slos,
+            highlights: config.highlights,
+            editor_command: config.editor_command,
+            notify: config.notify,
+            notify_on: config.notify_on,
+            relay: config.relay,
+            record: config.record,
+            discover: config.discover,
+            tabs: config.tabs,
             retention: config.retain_for,
+            unfocused_redraw: config.unfocused_redraw,
+            frame_rate: config.frame_rate,
+            graphics: config.view_options.graphics,
             charset: Some(CharsetConfig {
                 lang: config.view_options.lang,
                 ascii_only: config.view_options.ascii_only,
             }),
             colors: Some(ColorsConfig {
                 enabled: Some(!config.view_options.no_colors),
+                high_contrast: Some(config.view_options.high_contrast),
+                theme: config.view_options.theme,
                 truecolor: config.view_options.truecolor,
                 palette: config.view_options.palette,
+                vision: config.view_options.color_vision,
                 enable: Some(config.view_options.toggles),
+                custom: config
+                    .view_options
+                    .custom_colors
+                    .iter()
+                    .map(|(color, replacement)| (color.to_string(), replacement.to_string()))
+                    .collect(),
             }),
+            auto_pause: Some(config.auto_pause),
+            auth: Some(config.auth),
+            reconnect: Some(config.reconnect),
+            alerts: Some(config.alerts),
         }
     }
 }
@@ -871,14 +2295,39 @@ impl TryFrom<ConfigFile> for Config {
 
     fn try_from(mut value: ConfigFile) -> Result<Self, Self::Error> {
         Ok(Config {
-            target_addr: value.target_addr()?,
+            target_addr: value.default_target_addr.clone(),
+            targets: value.targets()?,
+            keymap: value.keymap()?,
             log_filter: value.log_filter()?,
             warnings: value.warnings.clone(),
             allow_warnings: value.allow_warnings.clone(),
+            task_columns: value.task_columns.clone(),
+            field_columns: value.field_columns.clone(),
+            sort_expressions: value.sort_expressions.clone(),
+            show_columns: value.show_columns.clone(),
+            sort: value.sort.clone(),
+            slos: value.slos.clone(),
+            highlights: value.highlights.clone(),
+            editor_command: value.editor_command.take(),
+            notify: value.notify,
+            notify_on: value.notify_on.clone(),
+            relay: value.relay,
+            record: value.record.take(),
+            discover: value.discover.clone(),
+            tabs: value.tabs.clone(),
             log_directory: value.log_directory.take(),
             retain_for: value.retain_for(),
+            unfocused_redraw: value.unfocused_redraw,
+            frame_rate: value.frame_rate,
             view_options: ViewOptions {
                 no_colors: value.no_colors().unwrap_or(false),
+                high_contrast: value
+                    .colors
+                    .as_ref()
+                    .and_then(|config| config.high_contrast)
+                    .unwrap_or(false),
+                theme: value.colors.as_ref().and_then(|config| config.theme),
+                custom_colors: value.custom_colors()?,
                 lang: value
                     .charset
                     .as_ref()
@@ -886,11 +2335,21 @@ impl TryFrom<ConfigFile> for Config {
                 ascii_only: value.charset.as_ref().and_then(|config| config.ascii_only),
                 truecolor: value.colors.as_ref().and_then(|config| config.truecolor),
                 palette: value.colors.as_ref().and_then(|config| config.palette),
+                color_vision: value.colors.as_ref().and_then(|config| config.vision),
+                graphics: value.graphics,
                 toggles: ColorToggles {
                     color_durations: value.color_durations(),
                     color_terminated: value.color_terminated(),
                 },
             },
+            auto_pause: value.auto_pause.take().unwrap_or(AutoPause {
+                auto_pause_details: None,
+                auto_pause_warnings: Vec::new(),
+                pause_conditions: Vec::new(),
+            }),
+            auth: value.auth.take().unwrap_or_default(),
+            reconnect: value.reconnect.take().unwrap_or_default(),
+            alerts: value.alerts.take().unwrap_or_default(),
             subcmd: None,
         })
     }
@@ -983,6 +2442,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resolves_named_targets() {
+        let file = toml::from_str::<ConfigFile>(
+            r#"
+            default_target_addr = "prod-gateway"
+            warnings = []
+
+            [targets]
+            prod-gateway = "http://10.0.3.4:6669"
+            "#,
+        )
+        .expect("config file should parse");
+        let config = Config::try_from(file).expect("config file should be valid");
+
+        assert_eq!(config.target_name(), Some("prod-gateway"));
+        assert_eq!(
+            config.target_addr().unwrap(),
+            "http://10.0.3.4:6669".parse::<Uri>().unwrap()
+        );
+        assert_eq!(
+            config.resolve_target("http://127.0.0.1:1234").unwrap(),
+            "http://127.0.0.1:1234".parse::<Uri>().unwrap()
+        );
+        assert!(config.resolve_target("staging").is_err());
+    }
+
+    #[test]
+    fn parses_keymap() {
+        let file = toml::from_str::<ConfigFile>(
+            r#"
+            warnings = []
+
+            [keymap]
+            J = "down"
+            ctrl-d = "q"
+            "#,
+        )
+        .expect("config file should parse");
+        let config = Config::try_from(file).expect("config file should be valid");
+        let bindings = config
+            .keymap
+            .bindings()
+            .map(|(key, to)| (key.to_string(), to.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bindings,
+            [
+                ("J".to_owned(), "down".to_owned()),
+                ("ctrl-d".to_owned(), "q".to_owned())
+            ]
+        );
+
+        let file = toml::from_str::<ConfigFile>(
+            r#"
+            warnings = []
+
+            [keymap]
+            ctrl-nope = "q"
+            "#,
+        )
+        .expect("config file should parse");
+        assert!(Config::try_from(file).is_err());
+    }
+
+    #[test]
+    fn parses_custom_colors() {
+        let file = toml::from_str::<ConfigFile>(
+            r##"
+            warnings = []
+
+            [colors]
+            theme = "light"
+
+            [colors.custom]
+            yellow = "#d7af00"
+            "##,
+        )
+        .expect("config file should parse");
+        let config = Config::try_from(file).expect("config file should be valid");
+        assert_eq!(config.view_options.theme(), Theme::Light);
+        assert_eq!(
+            config.view_options.custom_colors(),
+            [(Color::Yellow, Color::Rgb(0xd7, 0xaf, 0x00))]
+        );
+
+        let file = toml::from_str::<ConfigFile>(
+            r#"
+            warnings = []
+
+            [colors.custom]
+            yellow = "not a color"
+            "#,
+        )
+        .expect("config file should parse");
+        assert!(Config::try_from(file).is_err());
+    }
+
     fn git_diff(path: impl AsRef<Path>) -> Result<(), String> {
         let output = process::Command::new("git")
             .arg("diff")
@@ -1,26 +1,111 @@
+use crate::auth;
+use crate::check;
+use crate::demo::{self, Demo};
+use crate::docker;
+use crate::k8s;
+use crate::replay::Replay;
+use crate::srv;
+use crate::state::clock::{ClockSkew, SkewEstimator};
 use console_api::instrument::StateRequest;
 use console_api::instrument::{
-    instrument_client::InstrumentClient, InstrumentRequest, PauseRequest, ResumeRequest,
-    State as InstrumentState, TaskDetailsRequest, Update,
+    frame, AbortTaskRequest, DumpTaskRequest, Frame, InstrumentRequest, PauseRequest,
+    ResumeRequest, ServerInfo, ServerInfoRequest, State as InstrumentState, StepRequest,
+    TaskDetailsRequest, TaskDump, Update,
 };
 use console_api::tasks::TaskDetails;
-use futures::stream::StreamExt;
+use futures::stream::{BoxStream, StreamExt};
 use futures::TryFutureExt;
 use hyper_util::rt::TokioIo;
-use std::{error::Error, time::Duration};
+use prost::{bytes::Bytes, Message as _};
+use rustls::pki_types::ServerName;
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 #[cfg(unix)]
 use tokio::net::UnixStream;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    time::Instant,
+};
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::Message as WebSocketMessage;
+use tokio_util::codec::{FramedRead, LengthDelimitedCodec};
 use tonic::{
-    transport::{Channel, Endpoint, Uri},
+    body::BoxBody,
+    transport::{Endpoint, Uri},
     Streaming,
 };
+use tonic_web::{GrpcWebCall, GrpcWebClientService};
+use tower::{util::BoxCloneService, ServiceExt};
+
+/// The service which gRPC requests are sent to a target on: a channel, or
+/// gRPC-web requests on HTTP/1.1 connections with `--grpc-web`.
+///
+/// Its errors are turned into statuses as tonic would turn the channel's
+/// errors into them, so that they can still be told apart by their source.
+pub(crate) type Transport =
+    BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, tonic::Status>;
 
 #[derive(Debug)]
 pub struct Connection {
     target: Uri,
+    /// The name of the target in the config file, if it was connected to by
+    /// name.
+    name: Option<String>,
+    /// How often the target publishes updates, if it reported it.
+    publish_interval: Option<Duration>,
+    /// The version of `console-api` which the target was built with, if it
+    /// reported it.
+    api_version: Option<String>,
+    /// The features which the target doesn't support, because they weren't
+    /// among the capabilities it reported, or because it said the RPCs they
+    /// need aren't implemented when they were used.
+    unsupported: Vec<Feature>,
+    /// How long to wait between attempts to reconnect.
+    reconnect: ReconnectPolicy,
+    /// How far the target's clock is from the console's, estimated from the
+    /// live updates received since connecting.
+    skew: SkewEstimator,
     state: State,
 }
 
+/// How long to wait between attempts to reconnect to a target, after losing
+/// the connection or failing to connect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ReconnectPolicy {
+    /// How long to wait before the first attempt.
+    pub(crate) initial: Duration,
+    /// How much longer to wait before each attempt than before the last.
+    pub(crate) multiplier: f64,
+    /// The longest to wait before any attempt.
+    pub(crate) max: Duration,
+    /// The fraction of each wait which is randomly added or taken away, so
+    /// that consoles which lost their connections at once don't all
+    /// reconnect at once.
+    pub(crate) jitter: f64,
+    /// How long to keep trying to reconnect for, after which the connection
+    /// is closed until the user reconnects, or `None` to never give up.
+    pub(crate) give_up_after: Option<Duration>,
+}
+
+/// The features of the console which need RPCs that a target may not
+/// support, because its console-subscriber is older than the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Feature {
+    Pause,
+    Step,
+    TaskDetails,
+    DumpTask,
+    AbortTask,
+}
+
 // clippy doesn't like that the "connected" case is much larger than the
 // disconnected case, and suggests boxing the connected side's stream.
 // however, this is rarely disconnected; it's normally connected. boxing the
@@ -30,14 +115,38 @@ pub struct Connection {
 #[derive(Debug)]
 enum State {
     Connected {
-        client: InstrumentClient<Channel>,
+        client: auth::Client,
         update_stream: Box<Streaming<Update>>,
         state_stream: Box<Streaming<InstrumentState>>,
     },
-    Disconnected(Duration),
+    /// Connected with one of the framed transports, which only stream updates
+    /// and state.
+    Framed(Frames),
+    /// Running the made-up target of `tokio-console demo`.
+    Demo(Box<Demo>),
+    /// Replaying a recording made with `--record`.
+    Replay(Box<Replay>),
+    /// Waiting to connect to the target, or to reconnect after losing the
+    /// connection or failing to connect.
+    Disconnected {
+        /// When to try connecting next.
+        retry_at: Instant,
+        /// How long was waited before this attempt, which the wait before
+        /// the next attempt grows from, or zero for the first attempt.
+        backoff: Duration,
+        /// When the connection was lost, or connecting was first tried.
+        since: Instant,
+    },
+    /// Disconnected by the user, or after giving up reconnecting, so the
+    /// user must choose to reconnect.
+    Closed,
 }
 
+/// The encoded frames received from one of the framed transports.
+struct Frames(BoxStream<'static, Result<Bytes, Box<dyn Error + Send + Sync>>>);
+
 #[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
 pub(crate) enum Message {
     Update(Update),
     State(InstrumentState),
@@ -59,7 +168,7 @@ macro_rules! with_client {
                                 error = %error,
                                 "connection error sending command"
                             );
-                            $me.state = State::Disconnected(Self::BACKOFF);
+                            $me.lose_connection();
                         }
                         // Otherwise, return the error.
                         Err(e) => {
@@ -67,57 +176,429 @@ macro_rules! with_client {
                         }
                     }
                 }
-                State::Disconnected(_) => $me.connect().await,
+                State::Framed(_) => {
+                    break Err(tonic::Status::unimplemented(
+                        "the framed transport only streams updates",
+                    ))
+                }
+                State::Demo(_) => {
+                    break Err(tonic::Status::unimplemented(
+                        "the demo doesn't record task details",
+                    ))
+                }
+                State::Replay(_) => {
+                    break Err(tonic::Status::unimplemented(
+                        "recordings don't include task details",
+                    ))
+                }
+                State::Disconnected { .. } => $me.connect().await,
+                State::Closed => break Err(tonic::Status::unavailable("disconnected")),
             }
         }
     })
 }
 
+/// Opens a channel to the console server at `target`.
+pub(crate) async fn connect_channel(
+    target: &Uri,
+) -> Result<Transport, Box<dyn Error + Send + Sync>> {
+    let channel = match target.scheme_str() {
+        Some(FRAMED | FRAMED_UNIX | WEBSOCKET | WEBSOCKET_TLS | QUIC) => {
+            return Err("the framed transports only stream updates, and don't support gRPC".into());
+        }
+        Some(SNAPSHOT) => return Err("a snapshot is a file, not a target to connect to".into()),
+        Some(DEMO) => return Err("the demo is run by the console, not connected to".into()),
+        Some(REPLAY) => return Err("a recording is a file, not a target to connect to".into()),
+        #[cfg(unix)]
+        Some("file") => {
+            if !matches!(target.host(), None | Some("localhost")) {
+                return Err("cannot connect to non-localhost unix domain socket".into());
+            }
+            let path = target.path().to_owned();
+            // Dummy endpoint is ignored by the connector.
+            let endpoint = Endpoint::from_static("http://localhost");
+            endpoint
+                .connect_with_connector(tower::service_fn(move |_| {
+                    UnixStream::connect(path.clone()).map_ok(TokioIo::new)
+                }))
+                .await?
+        }
+        #[cfg(not(unix))]
+        Some("file") => {
+            return Err("unix domain sockets are not supported on this platform".into());
+        }
+        Some(KUBERNETES) => {
+            let local = k8s::forward(target).await?;
+            Endpoint::from(local).connect().await?
+        }
+        Some(DOCKER) => {
+            let addr = docker::resolve(target).await?;
+            Endpoint::from(addr).connect().await?
+        }
+        // The records are resolved again on each reconnection, in case the
+        // instance which was connected to has gone away.
+        Some(DNS_SRV) => {
+            let addr = srv::resolve(target).await?;
+            Endpoint::from(addr).connect().await?
+        }
+        Some("http" | "https") if auth::grpc_web() => return grpc_web(target),
+        Some("https") => auth::connect_https(target).await?,
+        _ => {
+            let endpoint = Endpoint::from(target.clone());
+            endpoint.connect().await?
+        }
+    };
+    Ok(BoxCloneService::new(
+        channel.map_err(|error| tonic::Status::from_error(error.into())),
+    ))
+}
+
+/// Returns a transport which sends gRPC-web requests to the proxy at
+/// `target`, for targets which can't be reached with HTTP/2 gRPC.
+///
+/// Each request is sent on a connection of its own, since HTTP/1.1 can't
+/// send another request on a connection until the response to the last one
+/// has ended, and streaming responses don't end until the stream does.
+fn grpc_web(target: &Uri) -> Result<Transport, Box<dyn Error + Send + Sync>> {
+    let https = target.scheme_str() == Some("https");
+    let host = target
+        .host()
+        .ok_or("target address has no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_owned();
+    let port = target.port_u16().unwrap_or(if https { 443 } else { 80 });
+    let authority = http::HeaderValue::from_str(
+        target
+            .authority()
+            .ok_or("target address has no host")?
+            .as_str(),
+    )?;
+    // Requests are made to the path of the target, under which the proxy
+    // may serve it.
+    let prefix = target.path().trim_end_matches('/').to_owned();
+    let tls = https.then(|| TlsConnector::from(auth::tls_config()));
+
+    let send = tower::service_fn(move |mut request: http::Request<GrpcWebCall<BoxBody>>| {
+        let (host, authority, prefix, tls) =
+            (host.clone(), authority.clone(), prefix.clone(), tls.clone());
+        async move {
+            *request.uri_mut() = format!("{prefix}{}", request.uri().path()).parse()?;
+            request.headers_mut().insert(http::header::HOST, authority);
+            let tcp = TcpStream::connect((host.as_str(), port)).await?;
+            match tls {
+                Some(tls) => {
+                    let server_name = ServerName::try_from(host)?;
+                    send_http1(tls.connect(server_name, tcp).await?, request).await
+                }
+                None => send_http1(tcp, request).await,
+            }
+        }
+    });
+    let transport = GrpcWebClientService::new(send)
+        .map_response(|response| response.map(tonic::body::boxed))
+        .map_err(tonic::Status::from_error);
+    Ok(BoxCloneService::new(transport))
+}
+
+/// Sends `request` on a new HTTP/1.1 connection over `io`.
+async fn send_http1(
+    io: impl AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    request: http::Request<GrpcWebCall<BoxBody>>,
+) -> Result<http::Response<hyper::body::Incoming>, Box<dyn Error + Send + Sync>> {
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io)).await?;
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            tracing::debug!(%error, "gRPC-web connection failed");
+        }
+    });
+    Ok(sender.send_request(request).await?)
+}
+
+/// The scheme of target addresses which are connected to with the framed
+/// transport, over TCP.
+pub(crate) const FRAMED: &str = "framed";
+/// The scheme of target addresses which are connected to with the framed
+/// transport, over a Unix domain socket.
+pub(crate) const FRAMED_UNIX: &str = "framed+file";
+/// The scheme of target addresses which are connected to with the framed
+/// transport over a WebSocket.
+pub(crate) const WEBSOCKET: &str = "ws";
+/// The scheme of target addresses which are connected to with the framed
+/// transport over a WebSocket, with TLS.
+pub(crate) const WEBSOCKET_TLS: &str = "wss";
+/// The scheme of target addresses which are connected to with the framed
+/// transport over QUIC.
+pub(crate) const QUIC: &str = "quic";
+/// The scheme of target addresses which open a final snapshot written by
+/// console-subscriber, rather than connecting to a target.
+pub(crate) const SNAPSHOT: &str = "snapshot";
+/// The scheme of the target address which runs a made-up target, rather
+/// than connecting to one.
+pub(crate) const DEMO: &str = "demo";
+/// The scheme of target addresses which replay a recording made with
+/// `--record`, rather than connecting to a target.
+pub(crate) const REPLAY: &str = "replay";
+/// The scheme of target addresses which name a Kubernetes pod, which is
+/// connected to through a port-forward.
+pub(crate) const KUBERNETES: &str = "k8s";
+/// The scheme of target addresses which name a local Docker container.
+pub(crate) const DOCKER: &str = "docker";
+/// The scheme of target addresses which name a service whose instances are
+/// found with DNS SRV records.
+pub(crate) const DNS_SRV: &str = "dns+srv";
+
+/// The port console-subscriber listens on by default.
+pub(crate) const DEFAULT_PORT: u16 = 6669;
+
+/// Parses a target address.
+///
+/// Addresses are often copied from service discovery without a scheme, so
+/// an address such as `10.0.3.4:6669`, `[::1]:6669` or `api.local:6669` is
+/// taken to be an `http` address. An IP address without a port, such as
+/// `::1`, is on the port console-subscriber listens on by default, but a
+/// name needs a port, so that a misspelled target name isn't looked up.
+pub(crate) fn parse_target(addr: &str) -> Result<Uri, http::uri::InvalidUri> {
+    let addr = addr.trim();
+    if addr.contains("://") {
+        return addr.parse();
+    }
+    // IPv6 addresses have to be written in brackets in URIs.
+    let uri = match addr.parse::<Ipv6Addr>() {
+        Ok(ip) => format!("http://[{ip}]"),
+        Err(_) => format!("http://{addr}"),
+    }
+    .parse::<Uri>();
+    let Ok(uri) = uri else {
+        return addr.parse();
+    };
+    let is_ip = addr
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok();
+    match uri.port_u16() {
+        Some(_) => Ok(uri),
+        None if is_ip => {
+            let host = uri.host().unwrap_or_default();
+            format!("http://{host}:{DEFAULT_PORT}").parse()
+        }
+        // Not an address without a scheme after all, so it's left to be
+        // rejected as it is.
+        None => addr.parse(),
+    }
+}
+
+/// Returns the host and port of `target`, defaulting to the port which
+/// console-subscriber listens on by default.
+fn host_and_port(target: &Uri) -> Result<(&str, u16), Box<dyn Error + Send + Sync>> {
+    let host = target.host().ok_or("target address has no host")?;
+    // IPv6 hosts are written in brackets in URIs.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Ok((host, target.port_u16().unwrap_or(DEFAULT_PORT)))
+}
+
+/// Connects to the framed transport at `target`, which streams length-prefixed
+/// [`Frame`]s rather than serving gRPC.
+async fn connect_framed(target: &Uri) -> Result<Frames, Box<dyn Error + Send + Sync>> {
+    let io: Box<dyn AsyncRead + Send + Unpin> = match target.scheme_str() {
+        #[cfg(unix)]
+        Some(FRAMED_UNIX) => {
+            if !matches!(target.host(), None | Some("localhost")) {
+                return Err("cannot connect to non-localhost unix domain socket".into());
+            }
+            Box::new(UnixStream::connect(target.path()).await?)
+        }
+        #[cfg(not(unix))]
+        Some(FRAMED_UNIX) => {
+            return Err("unix domain sockets are not supported on this platform".into());
+        }
+        _ => Box::new(TcpStream::connect(host_and_port(target)?).await?),
+    };
+    Ok(Frames::length_delimited(io))
+}
+
+/// Connects to the framed transport over QUIC at `target`, which sends
+/// length-prefixed [`Frame`]s on a stream it opens to the console.
+///
+/// This is the same framed transport as the others, rather than gRPC over
+/// HTTP/3, so it only streams updates.
+///
+/// The server's certificate is verified with `tls`, which is normally
+/// configured with the certificate authorities passed with `--tls-ca`, or
+/// the Mozilla root certificates.
+async fn connect_quic(
+    target: &Uri,
+    tls: Arc<rustls::ClientConfig>,
+) -> Result<Frames, Box<dyn Error + Send + Sync>> {
+    let (host, port) = host_and_port(target)?;
+    let addr = tokio::net::lookup_host((host, port))
+        .await?
+        .next()
+        .ok_or("target address did not resolve")?;
+    let config = quinn::ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from((*tls).clone())?,
+    ));
+    let local_addr = match addr {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let mut endpoint = quinn::Endpoint::client(local_addr)?;
+    endpoint.set_default_client_config(config);
+    let connection = endpoint.connect(addr, host)?.await?;
+    let stream = connection.accept_uni().await?;
+    Ok(Frames::length_delimited(Box::new(stream)))
+}
+
+/// Connects to the framed transport over a WebSocket at `target`, which sends
+/// each [`Frame`] as a binary message.
+async fn connect_websocket(target: &Uri) -> Result<Frames, Box<dyn Error + Send + Sync>> {
+    let connector = tokio_tungstenite::Connector::Rustls(auth::tls_config());
+    let (websocket, _) = tokio_tungstenite::connect_async_tls_with_config(
+        target.to_string(),
+        None,
+        false,
+        Some(connector),
+    )
+    .await?;
+    let frames = websocket
+        .filter_map(|message| async {
+            match message {
+                Ok(WebSocketMessage::Binary(frame)) => Some(Ok(Bytes::from(frame))),
+                // Control messages are handled by the WebSocket itself.
+                Ok(_) => None,
+                Err(error) => Some(Err(error.into())),
+            }
+        })
+        .boxed();
+    Ok(Frames(frames))
+}
+
+impl Frames {
+    /// Reads frames from `io`, each prefixed by its length.
+    fn length_delimited(io: Box<dyn AsyncRead + Send + Unpin>) -> Self {
+        // The subscriber writes the length as a 4-byte integer, so any frame
+        // it can write is accepted, rather than only the codec's default of
+        // 8 MiB, which a large snapshot exceeds.
+        let codec = LengthDelimitedCodec::builder()
+            .length_field_length(4)
+            .max_frame_length(u32::MAX as usize)
+            .new_codec();
+        let frames = FramedRead::new(io, codec)
+            .map(|frame| Ok(frame?.freeze()))
+            .boxed();
+        Self(frames)
+    }
+}
+
+impl std::fmt::Debug for Frames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Frames").finish_non_exhaustive()
+    }
+}
+
 impl Connection {
-    const BACKOFF: Duration = Duration::from_millis(500);
     pub fn new(target: Uri) -> Self {
         Self {
             target,
-            state: State::Disconnected(Duration::from_secs(0)),
+            name: None,
+            publish_interval: None,
+            api_version: None,
+            unsupported: Vec::new(),
+            reconnect: ReconnectPolicy::default(),
+            skew: SkewEstimator::default(),
+            state: State::connecting(),
         }
     }
 
-    async fn connect(&mut self) {
-        const MAX_BACKOFF: Duration = Duration::from_secs(5);
+    pub fn with_name(self, name: Option<String>) -> Self {
+        Self { name, ..self }
+    }
+
+    pub(crate) fn with_reconnect(self, reconnect: ReconnectPolicy) -> Self {
+        Self { reconnect, ..self }
+    }
 
-        while let State::Disconnected(backoff) = self.state {
-            if backoff == Duration::from_secs(0) {
+    pub(crate) fn target(&self) -> &Uri {
+        &self.target
+    }
+
+    /// Returns the name of the target in the config file, if it was
+    /// connected to by name.
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    async fn connect(&mut self) {
+        while let State::Disconnected {
+            retry_at,
+            backoff,
+            since,
+        } = self.state
+        {
+            if backoff.is_zero() {
                 tracing::debug!(to = %self.target, "connecting");
             } else {
-                tracing::debug!(reconnect_in = ?backoff, "reconnecting");
-                tokio::time::sleep(backoff).await;
+                let reconnect_in = retry_at.saturating_duration_since(Instant::now());
+                tracing::debug!(?reconnect_in, "reconnecting");
             }
+            // The time to retry at is kept in the state, so that the wait
+            // isn't started over if this is cancelled and called again.
+            tokio::time::sleep_until(retry_at).await;
             let try_connect = async {
-                let channel = match self.target.scheme_str() {
-                    #[cfg(unix)]
-                    Some("file") => {
+                // The framed transports don't report the server's publish
+                // interval.
+                match self.target.scheme_str() {
+                    Some(FRAMED | FRAMED_UNIX) => {
+                        let frames = connect_framed(&self.target).await?;
+                        return Ok((State::Framed(frames), None, None));
+                    }
+                    Some(WEBSOCKET | WEBSOCKET_TLS) => {
+                        let frames = connect_websocket(&self.target).await?;
+                        return Ok((State::Framed(frames), None, None));
+                    }
+                    Some(QUIC) => {
+                        let frames = connect_quic(&self.target, auth::tls_config()).await?;
+                        return Ok((State::Framed(frames), None, None));
+                    }
+                    // A snapshot is written in the same format as the framed
+                    // transport, so it's read like a target which sends one
+                    // update and disconnects.
+                    Some(SNAPSHOT) => {
                         if !matches!(self.target.host(), None | Some("localhost")) {
-                            return Err("cannot connect to non-localhost unix domain socket".into());
+                            return Err("cannot open a snapshot on another host".into());
                         }
-                        let path = self.target.path().to_owned();
-                        // Dummy endpoint is ignored by the connector.
-                        let endpoint = Endpoint::from_static("http://localhost");
-                        endpoint
-                            .connect_with_connector(tower::service_fn(move |_| {
-                                UnixStream::connect(path.clone()).map_ok(TokioIo::new)
-                            }))
-                            .await?
+                        let file = tokio::fs::File::open(self.target.path()).await?;
+                        let frames = Frames::length_delimited(Box::new(file));
+                        return Ok((State::Framed(frames), None, None));
                     }
-                    #[cfg(not(unix))]
-                    Some("file") => {
-                        return Err("unix domain sockets are not supported on this platform".into());
+                    Some(DEMO) => {
+                        let demo = State::Demo(Box::new(Demo::new()));
+                        return Ok((demo, Some(demo::PUBLISH_INTERVAL), None));
                     }
-                    _ => {
-                        let endpoint = Endpoint::from(self.target.clone());
-                        endpoint.connect().await?
+                    Some(REPLAY) => {
+                        if !matches!(self.target.host(), None | Some("localhost")) {
+                            return Err("cannot replay a recording on another host".into());
+                        }
+                        let replay = Replay::open(self.target.path().as_ref()).await?;
+                        return Ok((State::Replay(Box::new(replay)), None, None));
                     }
-                };
-                let mut client = InstrumentClient::new(channel);
+                    _ => {}
+                }
+                let channel = connect_channel(&self.target).await?;
+                let mut client = auth::client(channel);
+                // Servers from before `GetServerInfo` was added don't report
+                // their publish interval or capabilities, but can still be
+                // used.
+                let info = client
+                    .get_server_info(tonic::Request::new(ServerInfoRequest {}))
+                    .await
+                    .ok()
+                    .map(tonic::Response::into_inner);
+                let publish_interval = info
+                    .as_ref()
+                    .and_then(|info| info.publish_interval)
+                    .and_then(|interval| interval.try_into().ok());
                 let update_request = tonic::Request::new(InstrumentRequest {});
                 let update_stream =
                     Box::new(client.watch_updates(update_request).await?.into_inner());
@@ -134,21 +615,38 @@ impl Connection {
                         return Err(e.into());
                     }
                 };
-                Ok::<State, Box<dyn Error + Send + Sync>>(State::Connected {
+                let connected = State::Connected {
                     client,
                     update_stream,
                     state_stream,
-                })
+                };
+                Ok::<_, Box<dyn Error + Send + Sync>>((connected, publish_interval, info))
             };
             self.state = match try_connect.await {
-                Ok(connected) => {
-                    tracing::debug!("connected successfully!");
+                Ok((connected, publish_interval, info)) => {
+                    tracing::debug!(?publish_interval, "connected successfully!");
+                    self.publish_interval = publish_interval;
+                    self.skew.reset();
+                    self.handshake(info);
                     connected
                 }
                 Err(error) => {
                     tracing::warn!(%error, "error connecting");
-                    let backoff = std::cmp::max(backoff + Self::BACKOFF, MAX_BACKOFF);
-                    State::Disconnected(backoff)
+                    let now = Instant::now();
+                    match self.reconnect.give_up_after {
+                        Some(after) if now.duration_since(since) >= after => {
+                            tracing::warn!(?after, "giving up reconnecting");
+                            State::Closed
+                        }
+                        _ => {
+                            let backoff = self.reconnect.next_backoff(backoff);
+                            State::Disconnected {
+                                retry_at: now + self.reconnect.jittered(backoff),
+                                backoff,
+                                since,
+                            }
+                        }
+                    }
                 }
             };
         }
@@ -164,62 +662,212 @@ impl Connection {
                 } => {
                     tokio::select! { biased; // Always biased to update stream.
                         update = update_stream.next() => match update {
-                            Some(Ok(update)) => return Message::Update(update),
+                            Some(Ok(update)) => {
+                                self.record_skew(&update);
+                                return Message::Update(update);
+                            }
                             Some(Err(status)) => {
                                 tracing::warn!(%status, "error from update stream");
-                                self.state = State::Disconnected(Self::BACKOFF);
+                                self.lose_connection();
                             }
                             None => {
                                 tracing::error!("update stream closed by server");
-                                self.state = State::Disconnected(Self::BACKOFF);
+                                self.lose_connection();
                             }
                         },
                         state = state_stream.next() => match state {
                             Some(Ok(state)) => return Message::State(state),
                             Some(Err(status)) => {
                                 tracing::warn!(%status, "error from state stream");
-                                self.state = State::Disconnected(Self::BACKOFF);
+                                self.lose_connection();
                             }
                             None => {
                                 tracing::error!("state stream closed by server");
-                                self.state = State::Disconnected(Self::BACKOFF);
+                                self.lose_connection();
                             }
                         },
                     }
                 }
-                State::Disconnected(_) => self.connect().await,
+                State::Framed(Frames(frames)) => match frames.next().await {
+                    Some(Ok(frame)) => match Frame::decode(frame) {
+                        Ok(Frame {
+                            message: Some(frame::Message::Update(update)),
+                        }) => {
+                            // A snapshot's updates were sent long ago.
+                            if !self.is_snapshot() {
+                                self.record_skew(&update);
+                            }
+                            return Message::Update(update);
+                        }
+                        Ok(Frame {
+                            message: Some(frame::Message::State(state)),
+                        }) => return Message::State(state),
+                        // A message from a newer server which this console
+                        // doesn't know about.
+                        Ok(Frame { message: None }) => {}
+                        Err(error) => {
+                            tracing::warn!(%error, "invalid frame");
+                            self.lose_connection();
+                        }
+                    },
+                    Some(Err(error)) => {
+                        tracing::warn!(%error, "error reading frames");
+                        self.lose_connection();
+                    }
+                    // Keep showing the snapshot, rather than reading it again.
+                    None if self.is_snapshot() => {
+                        tracing::debug!("read the whole snapshot");
+                        self.state = State::Closed;
+                    }
+                    None => {
+                        tracing::error!("connection closed by server");
+                        self.lose_connection();
+                    }
+                },
+                State::Demo(demo) => return demo.next_message().await,
+                State::Replay(replay) => return replay.next_message().await,
+                State::Disconnected { .. } => self.connect().await,
+                // Wait until the user reconnects.
+                State::Closed => futures::future::pending().await,
             }
         }
     }
 
+    /// Returns `true` if this is a snapshot written by console-subscriber,
+    /// rather than a connection to a live target.
+    pub fn is_snapshot(&self) -> bool {
+        self.target.scheme_str() == Some(SNAPSHOT)
+    }
+
+    /// Returns `true` if the target is running now, rather than being read
+    /// from a snapshot or a recording.
+    pub fn is_live(&self) -> bool {
+        !self.is_snapshot() && self.target.scheme_str() != Some(REPLAY)
+    }
+
+    /// Returns `true` if the console is connected to the target.
+    pub fn is_connected(&self) -> bool {
+        matches!(
+            self.state,
+            State::Connected { .. } | State::Framed(_) | State::Demo(_) | State::Replay(_)
+        )
+    }
+
+    /// Returns how often the target publishes updates, if it reported it.
+    pub fn publish_interval(&self) -> Option<Duration> {
+        self.publish_interval
+    }
+
+    /// Returns `true` if the target supports `feature`, as far as the
+    /// console knows.
+    ///
+    /// A target which didn't report its capabilities is assumed to support
+    /// everything, until it says an RPC isn't implemented.
+    pub(crate) fn supports(&self, feature: Feature) -> bool {
+        match self.target.scheme_str() {
+            Some(FRAMED | FRAMED_UNIX | WEBSOCKET | WEBSOCKET_TLS | QUIC | SNAPSHOT) => false,
+            Some(DEMO | REPLAY) => matches!(feature, Feature::Pause | Feature::Step),
+            _ => !self.unsupported.contains(&feature),
+        }
+    }
+
+    /// Records what the target reported about itself when connecting, or
+    /// that it didn't report anything.
+    fn handshake(&mut self, info: Option<ServerInfo>) {
+        self.unsupported.clear();
+        self.api_version = None;
+        if let Some(info) = info {
+            tracing::debug!(api_version = %info.api_version, capabilities = ?info.capabilities, "server info");
+            self.unsupported = Feature::ALL
+                .iter()
+                .copied()
+                .filter(|feature| !info.capabilities.iter().any(|c| c == feature.capability()))
+                .collect();
+            self.api_version = Some(info.api_version);
+        }
+    }
+
+    /// Records that the target doesn't support `feature`, if `error` says
+    /// the RPC it needs isn't implemented.
+    fn check_unsupported(&mut self, feature: Feature, error: &tonic::Status) {
+        if error.code() == tonic::Code::Unimplemented
+            && matches!(self.state, State::Connected { .. })
+            && !self.unsupported.contains(&feature)
+        {
+            tracing::info!(%feature, "target doesn't support a feature");
+            self.unsupported.push(feature);
+        }
+    }
+
+    /// Connects to the target again immediately, skipping any remaining
+    /// backoff, and replacing the current connection if there is one.
+    pub fn reconnect(&mut self) {
+        tracing::debug!(to = %self.target, "reconnecting at the user's request");
+        self.state = State::connecting();
+    }
+
+    /// Waits to reconnect after losing the connection to the target.
+    fn lose_connection(&mut self) {
+        let now = Instant::now();
+        let backoff = self.reconnect.next_backoff(Duration::ZERO);
+        self.state = State::Disconnected {
+            retry_at: now + self.reconnect.jittered(backoff),
+            backoff,
+            since: now,
+        };
+    }
+
+    /// Closes the connection to the target, without reconnecting until
+    /// [`Connection::reconnect`] is called.
+    pub fn disconnect(&mut self) {
+        tracing::debug!(from = %self.target, "disconnecting at the user's request");
+        self.state = State::Closed;
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn watch_details(
         &mut self,
         task_id: u64,
     ) -> Result<Streaming<TaskDetails>, tonic::Status> {
-        with_client!(self, client, {
+        let res = with_client!(self, client, {
             let request = tonic::Request::new(TaskDetailsRequest {
                 id: Some(task_id.into()),
             });
             client.watch_task_details(request).await
-        })
-        .map(|watch| watch.into_inner())
+        });
+        if let Err(ref e) = res {
+            self.check_unsupported(Feature::TaskDetails, e);
+        }
+        res.map(|watch| watch.into_inner())
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn pause(&mut self) {
+        match self.state {
+            State::Demo(ref mut demo) => return demo.pause(),
+            State::Replay(ref mut replay) => return replay.pause(),
+            _ => {}
+        }
         let res = with_client!(self, client, {
-            let request = tonic::Request::new(PauseRequest {});
+            let request = tonic::Request::new(PauseRequest {
+                client: Some(format!("tokio-console (pid {})", std::process::id())),
+            });
             client.pause(request).await
         });
 
         if let Err(e) = res {
             tracing::error!(error = %e, "rpc error sending pause command");
+            self.check_unsupported(Feature::Pause, &e);
         }
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn resume(&mut self) {
+        match self.state {
+            State::Demo(ref mut demo) => return demo.resume(),
+            State::Replay(ref mut replay) => return replay.resume(),
+            _ => {}
+        }
         let res = with_client!(self, client, {
             let request = tonic::Request::new(ResumeRequest {});
             client.resume(request).await
@@ -227,33 +875,572 @@ impl Connection {
 
         if let Err(e) = res {
             tracing::error!(error = %e, "rpc error sending resume command");
+            self.check_unsupported(Feature::Pause, &e);
         }
     }
 
-    pub fn render(&self, styles: &crate::view::Styles) -> ratatui::text::Line {
+    #[tracing::instrument(skip(self))]
+    pub async fn step(&mut self) {
+        match self.state {
+            State::Demo(ref mut demo) => return demo.step(),
+            State::Replay(ref mut replay) => return replay.step(),
+            _ => {}
+        }
+        let res = with_client!(self, client, {
+            let request = tonic::Request::new(StepRequest {});
+            client.step(request).await
+        });
+
+        if let Err(e) = res {
+            tracing::error!(error = %e, "rpc error sending step command");
+            self.check_unsupported(Feature::Step, &e);
+        }
+    }
+
+    /// Asks the target for a dump of everything it knows about a task.
+    #[tracing::instrument(skip(self))]
+    pub async fn dump_task(&mut self, task_id: u64) -> Result<TaskDump, tonic::Status> {
+        let res = with_client!(self, client, {
+            let request = tonic::Request::new(DumpTaskRequest {
+                id: Some(task_id.into()),
+            });
+            client.dump_task(request).await
+        });
+        if let Err(ref e) = res {
+            tracing::error!(error = %e, "rpc error dumping task");
+            self.check_unsupported(Feature::DumpTask, e);
+        }
+        res.map(tonic::Response::into_inner)
+    }
+
+    /// Asks the target to abort a task.
+    #[tracing::instrument(skip(self))]
+    pub async fn abort_task(&mut self, task_id: u64) -> Result<(), tonic::Status> {
+        let res = with_client!(self, client, {
+            let request = tonic::Request::new(AbortTaskRequest {
+                id: Some(task_id.into()),
+            });
+            client.abort_task(request).await
+        });
+        if let Err(ref e) = res {
+            tracing::error!(error = %e, "rpc error aborting task");
+            self.check_unsupported(Feature::AbortTask, e);
+        }
+        res.map(|_| ())
+    }
+
+    /// Seeks `by` forwards through a recording being replayed, or backwards
+    /// if `back` is `true`.
+    ///
+    /// Returns `true` if the replay starts again from the beginning, in
+    /// which case the console's state must be cleared. Does nothing unless a
+    /// recording is being replayed.
+    pub fn seek(&mut self, by: Duration, back: bool) -> bool {
+        match self.state {
+            State::Replay(ref mut replay) => replay.seek(by, back),
+            _ => false,
+        }
+    }
+
+    /// Renders the connection line, showing that the display is frozen if
+    /// `frozen_pending`, the number of updates it's holding, is `Some`.
+    pub fn render(
+        &self,
+        styles: &crate::view::Styles,
+        frozen_pending: Option<usize>,
+    ) -> ratatui::text::Line<'_> {
         use ratatui::{
-            style::{Color, Modifier},
+            style::{Color, Modifier, Style},
             text::{Line, Span},
         };
         let state = match self.state {
-            State::Connected { .. } => Span::styled(
+            State::Framed(_) | State::Closed if self.is_snapshot() => Span::styled(
+                "(SNAPSHOT)",
+                styles.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+            State::Demo(_) => Span::styled(
+                "(DEMO)",
+                styles.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+            State::Replay(ref replay) => {
+                let (position, length) = replay.position();
+                let ended = if replay.is_finished() { ", ENDED" } else { "" };
+                Span::styled(
+                    format!(
+                        "(REPLAY {} of {}{ended}, seek with < and >)",
+                        humantime::format_duration(Duration::from_secs(position.as_secs())),
+                        humantime::format_duration(Duration::from_secs(length.as_secs())),
+                    ),
+                    styles.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                )
+            }
+            State::Connected { .. } | State::Framed(_) => Span::styled(
                 "(CONNECTED)",
                 styles.fg(Color::Green).add_modifier(Modifier::BOLD),
             ),
-            State::Disconnected(d) if d == Duration::from_secs(0) => Span::styled(
+            State::Disconnected { backoff, .. } if backoff.is_zero() => Span::styled(
                 "(CONNECTING)",
                 styles.fg(Color::Yellow).add_modifier(Modifier::BOLD),
             ),
-            State::Disconnected(d) => Span::styled(
-                format!("(RECONNECTING IN {:?})", d),
+            State::Disconnected { retry_at, .. } => Span::styled(
+                format!(
+                    "(RECONNECTING IN {:.1}s)",
+                    retry_at
+                        .saturating_duration_since(Instant::now())
+                        .as_secs_f64()
+                ),
                 styles.fg(Color::Yellow).add_modifier(Modifier::BOLD),
             ),
+            State::Closed => Span::styled(
+                "(DISCONNECTED)",
+                styles.fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+        };
+        let mut line = Line::from(vec![Span::raw("connection: ")]);
+        if let Some(ref name) = self.name {
+            line.push_span(Span::styled(
+                name.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            line.push_span(Span::raw(format!(" ({}) ", self.target)));
+        } else {
+            line.push_span(Span::raw(format!("{} ", self.target)));
+        }
+        line.push_span(state);
+        if let Some(pending) = frozen_pending {
+            line.push_span(Span::styled(
+                format!(" FROZEN ({pending} updates pending)"),
+                styles.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(offline) = self.describe_offline() {
+            line.push_span(Span::styled(
+                format!(" {offline}"),
+                styles.fg(Color::Yellow),
+            ));
+        }
+        if let Some(skew) = self.describe_skew() {
+            line.push_span(Span::styled(format!(" {skew}"), styles.fg(Color::Yellow)));
+        }
+        if let Some(skew) = self.describe_clock_skew() {
+            line.push_span(Span::styled(format!(" {skew}"), styles.fg(Color::Yellow)));
+        }
+        line
+    }
+
+    /// Returns how far the target's clock is estimated to be from the
+    /// console's, if any live updates have been received.
+    pub(crate) fn clock_skew(&self) -> Option<ClockSkew> {
+        self.skew.estimate()
+    }
+
+    /// Records when a live update was sent by the target's clock and
+    /// received by the console's, to estimate how far apart they are.
+    fn record_skew(&mut self, update: &Update) {
+        if let Some(sent_at) = update.now.and_then(|now| now.try_into().ok()) {
+            self.skew.record(sent_at, SystemTime::now());
+        }
+    }
+
+    /// Describes how far the target's clock is from the console's, if it's
+    /// far enough that the times the target reports can't be taken at face
+    /// value.
+    fn describe_clock_skew(&self) -> Option<String> {
+        let skew = self.clock_skew().filter(|skew| skew.is_noticeable())?;
+        Some(format!("clock skew {skew}"))
+    }
+
+    /// Describes how long the connection has been lost for, and how many
+    /// updates the target would have published since, while reconnecting.
+    fn describe_offline(&self) -> Option<String> {
+        let State::Disconnected { backoff, since, .. } = self.state else {
+            return None;
+        };
+        if backoff.is_zero() {
+            return None;
+        }
+        let offline = Instant::now().saturating_duration_since(since);
+        let mut description = format!(
+            "disconnected for {}",
+            humantime::format_duration(Duration::from_secs(offline.as_secs()))
+        );
+        // The publish interval is only known once the target has been
+        // connected to.
+        if let Some(interval) = self.publish_interval.filter(|interval| !interval.is_zero()) {
+            let missed = (offline.as_secs_f64() / interval.as_secs_f64()) as u64;
+            description.push_str(&format!(", {missed} updates missed"));
+        }
+        Some(description)
+    }
+
+    /// Describes how the target's version differs from the console's, if it
+    /// matters: which features are unavailable, or that the versions aren't
+    /// compatible, so that some data may be missing.
+    fn describe_skew(&self) -> Option<String> {
+        // Whether a target can abort tasks depends on how it was built,
+        // rather than on its version, so it isn't skew.
+        let unsupported = self
+            .unsupported
+            .iter()
+            .filter(|&&feature| feature != Feature::AbortTask)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            let features = unsupported.join(", ");
+            // A relay doesn't support pausing or stepping, whatever its
+            // version.
+            let older = self.api_version.as_deref().map_or(true, |version| {
+                check::is_older(version, console_api::VERSION)
+            });
+            return Some(if older {
+                format!("server is older than console; {features} unavailable")
+            } else {
+                format!("server doesn't support {features}")
+            });
+        }
+        let version = self.api_version.as_deref()?;
+        (!check::is_compatible(version, console_api::VERSION)).then(|| {
+            format!(
+                "server's console-api {version} isn't compatible with console's {}; some data may be missing",
+                console_api::VERSION
+            )
+        })
+    }
+}
+
+impl Feature {
+    /// The features which targets list in their capabilities if they support
+    /// them.
+    const ALL: &'static [Feature] = &[
+        Feature::Pause,
+        Feature::Step,
+        Feature::TaskDetails,
+        Feature::DumpTask,
+        Feature::AbortTask,
+    ];
+
+    /// Returns the RPC which the feature needs, as listed in the target's
+    /// capabilities.
+    fn capability(self) -> &'static str {
+        match self {
+            Feature::Pause => "pause",
+            Feature::Step => "step",
+            Feature::TaskDetails => "watch_task_details",
+            Feature::DumpTask => "dump_task",
+            Feature::AbortTask => "abort_task",
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::Pause => "pausing",
+            Feature::Step => "stepping",
+            Feature::TaskDetails => "task details",
+            Feature::DumpTask => "task dumps",
+            Feature::AbortTask => "aborting tasks",
+        })
+    }
+}
+
+impl State {
+    /// Connecting for the first time, or at the user's request, which is
+    /// tried immediately.
+    fn connecting() -> Self {
+        let now = Instant::now();
+        State::Disconnected {
+            retry_at: now,
+            backoff: Duration::ZERO,
+            since: now,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns how long to wait before the attempt after one which was
+    /// waited for for `backoff`.
+    pub(crate) fn next_backoff(&self, backoff: Duration) -> Duration {
+        let next = if backoff.is_zero() {
+            self.initial
+        } else {
+            backoff.mul_f64(self.multiplier.max(1.0))
+        };
+        next.min(self.max)
+    }
+
+    /// Returns `backoff` with a random part of it, up to the jitter, added
+    /// or taken away.
+    fn jittered(&self, backoff: Duration) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return backoff;
+        }
+        // Each new `RandomState` is randomly keyed, which is random enough
+        // to spread out reconnections.
+        let random = RandomState::new().build_hasher().finish();
+        let factor = (random as f64 / u64::MAX as f64) * 2.0 - 1.0;
+        backoff.mul_f64(1.0 + jitter * factor)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            multiplier: 2.0,
+            max: Duration::from_secs(5),
+            jitter: 0.1,
+            give_up_after: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_without_schemes() {
+        let parse = |addr| parse_target(addr).unwrap().to_string();
+        assert_eq!(parse("10.0.3.4:7000"), "http://10.0.3.4:7000/");
+        assert_eq!(parse(" api.local:7000 "), "http://api.local:7000/");
+        assert_eq!(parse("10.0.3.4"), "http://10.0.3.4:6669/");
+        assert_eq!(parse("[::1]:7000"), "http://[::1]:7000/");
+        assert_eq!(parse("[fe80::1]"), "http://[fe80::1]:6669/");
+        assert_eq!(parse("::1"), "http://[::1]:6669/");
+        assert_eq!(parse("https://[::1]"), "https://[::1]/");
+        assert_eq!(
+            parse("dns+srv://_console._tcp.example.com"),
+            "dns+srv://_console._tcp.example.com/"
+        );
+        // Names need a port, and ports need to be numbers.
+        for addr in ["localhost", "host:port", "[::1]:port"] {
+            let uri = parse_target(addr).unwrap_or_default();
+            assert_eq!(uri.scheme(), None, "{addr:?} should not have a scheme");
+        }
+
+        let target = parse_target("[::1]").unwrap();
+        assert_eq!(host_and_port(&target).unwrap(), ("::1", DEFAULT_PORT));
+    }
+
+    #[tokio::test]
+    async fn reads_frames_larger_than_the_codec_default() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut writer, reader) = tokio::io::duplex(64 * 1024);
+        let frame = vec![7; 9 * 1024 * 1024];
+        let write = async {
+            writer
+                .write_all(&(frame.len() as u32).to_be_bytes())
+                .await
+                .unwrap();
+            writer.write_all(&frame).await.unwrap();
+            drop(writer);
+        };
+        let Frames(mut frames) = Frames::length_delimited(Box::new(reader));
+        let (read, ()) = tokio::join!(frames.next(), write);
+        assert_eq!(read.unwrap().unwrap(), frame);
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn reads_frames_over_quic() {
+        use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+
+        let tls = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+        let cert = CertificateDer::from_pem_file(tls.join("localhost.pem")).unwrap();
+        let key = PrivateKeyDer::from_pem_file(tls.join("localhost.key")).unwrap();
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert.clone()).unwrap();
+        let client_tls = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        let server_config = quinn::ServerConfig::with_single_cert(vec![cert], key).unwrap();
+        let server = quinn::Endpoint::server(server_config, ([127, 0, 0, 1], 0).into()).unwrap();
+        let target = format!("quic://127.0.0.1:{}", server.local_addr().unwrap().port());
+        let frame = b"an encoded frame".to_vec();
+        let serve = tokio::spawn({
+            let frame = frame.clone();
+            async move {
+                let connection = server.accept().await.unwrap().await.unwrap();
+                let mut stream = connection.open_uni().await.unwrap();
+                stream
+                    .write_all(&(frame.len() as u32).to_be_bytes())
+                    .await
+                    .unwrap();
+                stream.write_all(&frame).await.unwrap();
+                stream.finish().unwrap();
+                // The stream is only delivered while the connection is open.
+                connection.closed().await;
+            }
+        });
+
+        let Frames(mut frames) = connect_quic(&target.parse().unwrap(), Arc::new(client_tls))
+            .await
+            .unwrap();
+        assert_eq!(frames.next().await.unwrap().unwrap(), frame);
+        assert!(frames.next().await.is_none());
+        drop(frames);
+        serve.abort();
+    }
+
+    #[test]
+    fn frozen_display_is_shown_in_the_connection_line() {
+        use crate::config::ViewOptions;
+        use clap::Parser;
+
+        let styles = crate::view::Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let conn = Connection::new("http://127.0.0.1:6669".parse().unwrap());
+        let text = |line: ratatui::text::Line<'_>| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+
+        assert!(!text(conn.render(&styles, None)).contains("FROZEN"));
+        assert!(text(conn.render(&styles, Some(3))).contains(" FROZEN (3 updates pending)"));
+    }
+
+    #[test]
+    fn unsupported_features_are_reported() {
+        let mut conn = Connection::new("http://127.0.0.1:6669".parse().unwrap());
+        // A server which doesn't report anything is assumed to support
+        // everything.
+        conn.handshake(None);
+        assert!(conn.supports(Feature::Pause));
+        assert_eq!(conn.describe_skew(), None);
+
+        conn.handshake(Some(ServerInfo {
+            api_version: "0.0.1".to_owned(),
+            capabilities: vec!["watch_updates".to_owned(), "pause".to_owned()],
+            ..Default::default()
+        }));
+        assert!(conn.supports(Feature::Pause));
+        assert!(!conn.supports(Feature::Step));
+        assert!(!conn.supports(Feature::TaskDetails));
+        assert!(!conn.supports(Feature::AbortTask));
+        assert_eq!(
+            conn.describe_skew().as_deref(),
+            Some("server is older than console; stepping, task details, task dumps unavailable")
+        );
+
+        // A relay is as new as the console, but can't pause the target.
+        conn.handshake(Some(ServerInfo {
+            api_version: console_api::VERSION.to_owned(),
+            capabilities: vec!["watch_task_details".to_owned()],
+            ..Default::default()
+        }));
+        assert_eq!(
+            conn.describe_skew().as_deref(),
+            Some("server doesn't support pausing, stepping, task dumps")
+        );
+
+        // The framed transports never support any of them.
+        let conn = Connection::new("framed://127.0.0.1:6669".parse().unwrap());
+        assert!(!conn.supports(Feature::Pause));
+        assert_eq!(conn.describe_skew(), None);
+    }
+
+    #[tokio::test]
+    async fn grpc_web_requests() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let target = format!("http://{addr}/console/").parse().unwrap();
+        let mut client = auth::client(grpc_web(&target).unwrap());
+        let info = tokio::spawn(async move {
+            client
+                .get_server_info(tonic::Request::new(ServerInfoRequest {}))
+                .await
+        });
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.windows(4).any(|end| end == b"\r\n\r\n") {
+            let mut buf = [0; 1024];
+            let read = socket.read(&mut buf).await.unwrap();
+            assert_ne!(read, 0, "the request ended early");
+            request.extend_from_slice(&buf[..read]);
+        }
+        let request = String::from_utf8_lossy(&request);
+        assert!(
+            request.starts_with(
+                "POST /console/rs.tokio.console.instrument.Instrument/GetServerInfo HTTP/1.1\r\n"
+            ),
+            "{request}"
+        );
+        assert!(request.contains(&format!("host: {addr}\r\n")), "{request}");
+        assert!(
+            request.contains("content-type: application/grpc-web\r\n"),
+            "{request}"
+        );
+
+        // A gRPC-web response has its trailers in the body, after the
+        // message, each prefixed by a flag and its length.
+        let message = ServerInfo {
+            api_version: "1.2.3".to_owned(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let trailers = b"grpc-status:0\r\n";
+        let mut body = vec![0];
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(&message);
+        body.push(0x80);
+        body.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+        body.extend_from_slice(trailers);
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/grpc-web+proto\r\ncontent-length: {}\r\n\r\n",
+            body.len()
+        );
+        socket.write_all(head.as_bytes()).await.unwrap();
+        socket.write_all(&body).await.unwrap();
+
+        let info = info.await.unwrap().unwrap().into_inner();
+        assert_eq!(info.api_version, "1.2.3");
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_max() {
+        let policy = ReconnectPolicy {
+            jitter: 0.0,
+            ..ReconnectPolicy::default()
         };
-        Line::from(vec![
-            Span::raw("connection: "),
-            Span::raw(self.target.to_string()),
-            Span::raw(" "),
-            state,
-        ])
+        let mut backoff = Duration::ZERO;
+        let mut backoffs = Vec::new();
+        for _ in 0..6 {
+            backoff = policy.next_backoff(backoff);
+            backoffs.push(backoff.as_millis());
+        }
+        assert_eq!(backoffs, [500, 1000, 2000, 4000, 5000, 5000]);
+        assert_eq!(policy.jittered(backoff), backoff);
+
+        // A multiplier below one doesn't shrink the backoff.
+        let policy = ReconnectPolicy {
+            multiplier: 0.5,
+            ..policy
+        };
+        assert_eq!(
+            policy.next_backoff(Duration::from_secs(1)),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let policy = ReconnectPolicy {
+            jitter: 0.25,
+            ..ReconnectPolicy::default()
+        };
+        let backoff = Duration::from_secs(4);
+        for _ in 0..100 {
+            let jittered = policy.jittered(backoff);
+            assert!(jittered >= Duration::from_secs(3), "{jittered:?}");
+            assert!(jittered <= Duration::from_secs(5), "{jittered:?}");
+        }
     }
 }
@@ -0,0 +1,577 @@
+//! A made-up target for `tokio-console demo`, which spawns and completes
+//! tasks and creates and drops resources, so that the console can be tried
+//! out without an instrumented application.
+use crate::conn::Message;
+use console_api as proto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The address the console shows while running the demo.
+pub(crate) const TARGET: &str = "demo://localhost";
+
+/// How often the demo publishes updates.
+pub(crate) const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a task spawned at one of the demo's spawn locations behaves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Behavior {
+    /// Runs for the whole demo, polled a few times every update.
+    Server,
+    /// Handles a connection, polled often until it completes.
+    Connection,
+    /// Waits for a long time between polls.
+    Idle,
+    /// Runs on the blocking pool, polled once until it completes.
+    Blocking,
+    /// Mostly wakes itself.
+    SelfWaking,
+    /// Drops its waker after a while, so it's never woken again.
+    LosesWaker,
+    /// Completes quickly, sometimes by panicking.
+    Job,
+}
+
+/// Where the demo's tasks are spawned, and how they behave.
+const SPAWNS: &[(&str, u32, Behavior)] = &[
+    ("src/server.rs", 42, Behavior::Server),
+    ("src/server.rs", 58, Behavior::Connection),
+    ("src/cache.rs", 17, Behavior::Idle),
+    ("src/storage.rs", 88, Behavior::Blocking),
+    ("src/client.rs", 130, Behavior::SelfWaking),
+    ("src/client.rs", 77, Behavior::LosesWaker),
+    ("src/jobs.rs", 25, Behavior::Job),
+];
+
+/// The metadata IDs of the demo's resources, after those of its spawn
+/// locations.
+const MUTEX_META: u64 = SPAWNS.len() as u64 + 1;
+const SEMAPHORE_META: u64 = MUTEX_META + 1;
+const SLEEP_META: u64 = MUTEX_META + 2;
+
+/// A made-up target, which publishes an update every
+/// [`PUBLISH_INTERVAL`] unless it's paused.
+#[derive(Debug)]
+pub(crate) struct Demo {
+    rng: u64,
+    /// The demo's clock, which doesn't advance while it's paused.
+    now: SystemTime,
+    interval: tokio::time::Interval,
+    paused: bool,
+    /// Whether to publish one update while paused.
+    stepping: bool,
+    /// The demo's state, if it has changed and hasn't been sent yet.
+    state: Option<proto::instrument::State>,
+    /// Whether the first update, which registers the metadata, has been sent.
+    started: bool,
+    next_id: u64,
+    tasks: Vec<Task>,
+    resources: Vec<Resource>,
+}
+
+#[derive(Debug)]
+struct Task {
+    id: u64,
+    /// The index of where the task was spawned in [`SPAWNS`].
+    spawn: usize,
+    /// How many more updates the task lives for, if it completes.
+    remaining: Option<u32>,
+    /// The resource created by the task, which is dropped when it completes.
+    resource: Option<u64>,
+    stats: proto::tasks::Stats,
+    /// Whether the task hasn't been published yet.
+    new: bool,
+}
+
+#[derive(Debug)]
+struct Resource {
+    id: u64,
+    meta: u64,
+    stats: proto::resources::Stats,
+    new: bool,
+}
+
+// === impl Demo ===
+
+impl Demo {
+    pub(crate) fn new() -> Self {
+        let now = SystemTime::now();
+        let seed = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let mut demo = Self {
+            rng: seed | 1,
+            now,
+            interval: tokio::time::interval(PUBLISH_INTERVAL),
+            paused: false,
+            stepping: false,
+            state: None,
+            started: false,
+            next_id: 1,
+            tasks: Vec::new(),
+            resources: Vec::new(),
+        };
+        for (spawn, &(_, _, behavior)) in SPAWNS.iter().enumerate() {
+            let count = match behavior {
+                Behavior::Server | Behavior::SelfWaking | Behavior::LosesWaker => 1,
+                Behavior::Idle => 4,
+                Behavior::Connection => 6,
+                Behavior::Blocking | Behavior::Job => 0,
+            };
+            for _ in 0..count {
+                demo.spawn(spawn, now);
+            }
+        }
+        demo.create_resource(MUTEX_META, vec![bool_attribute("locked", false)], now);
+        demo.create_resource(
+            SEMAPHORE_META,
+            vec![u64_attribute("permits", 16, None)],
+            now,
+        );
+        demo
+    }
+
+    /// Returns the demo's next update, or its state if it has been paused or
+    /// resumed since the last message.
+    pub(crate) async fn next_message(&mut self) -> Message {
+        loop {
+            if let Some(state) = self.state.take() {
+                return Message::State(state);
+            }
+            self.interval.tick().await;
+            if self.paused && !std::mem::take(&mut self.stepping) {
+                continue;
+            }
+            return Message::Update(self.update());
+        }
+    }
+
+    pub(crate) fn pause(&mut self) {
+        self.paused = true;
+        self.state = Some(proto::instrument::State {
+            temporality: proto::instrument::Temporality::Paused as i32,
+            paused_at: Some(self.now.into()),
+            paused_by: Some(format!("tokio-console (pid {})", std::process::id())),
+        });
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.paused = false;
+        self.state = Some(proto::instrument::State {
+            temporality: proto::instrument::Temporality::Live as i32,
+            paused_at: None,
+            paused_by: None,
+        });
+    }
+
+    pub(crate) fn step(&mut self) {
+        if self.paused {
+            self.stepping = true;
+            self.interval.reset_immediately();
+        }
+    }
+
+    /// Advances the demo by one update, and returns what changed.
+    fn update(&mut self) -> proto::instrument::Update {
+        let start = self.now;
+        self.now += PUBLISH_INTERVAL;
+
+        // Spawn new connections and jobs at random.
+        for (spawn, &(_, _, behavior)) in SPAWNS.iter().enumerate() {
+            let chance = match behavior {
+                Behavior::Connection => 0.7,
+                Behavior::Blocking => 0.2,
+                Behavior::Job => 0.3,
+                _ => continue,
+            };
+            while self.chance(chance) {
+                let at = start + Duration::from_millis(self.between(0, 900));
+                self.spawn(spawn, at);
+            }
+        }
+
+        for idx in 0..self.tasks.len() {
+            self.poll(idx, start);
+        }
+
+        let now = Some(self.now.into());
+        let mut task_update = proto::tasks::TaskUpdate::default();
+        let mut resource_update = proto::resources::ResourceUpdate::default();
+        for resource in &mut self.resources {
+            if std::mem::take(&mut resource.new) {
+                resource_update
+                    .new_resources
+                    .push(proto::resources::Resource {
+                        id: Some(proto::Id { id: resource.id }),
+                        metadata: Some(proto::MetaId { id: resource.meta }),
+                        concrete_type: match resource.meta {
+                            MUTEX_META => "Mutex",
+                            SEMAPHORE_META => "Semaphore",
+                            _ => "Sleep",
+                        }
+                        .to_owned(),
+                        kind: Some(proto::resources::resource::Kind {
+                            kind: Some(match resource.meta {
+                                SLEEP_META => proto::resources::resource::kind::Kind::Known(
+                                    proto::resources::resource::kind::Known::Timer as i32,
+                                ),
+                                _ => {
+                                    proto::resources::resource::kind::Kind::Other("Sync".to_owned())
+                                }
+                            }),
+                        }),
+                        location: Some(resource_location(resource.meta)),
+                        ..Default::default()
+                    });
+            }
+            resource_update
+                .stats_update
+                .insert(resource.id, resource.stats.clone());
+        }
+        self.resources
+            .retain(|resource| resource.stats.dropped_at.is_none());
+
+        for task in &mut self.tasks {
+            if std::mem::take(&mut task.new) {
+                task_update.new_tasks.push(new_task(task));
+            }
+            task_update.stats_update.insert(task.id, task.stats.clone());
+        }
+        self.tasks.retain(|task| task.stats.dropped_at.is_none());
+
+        let new_metadata = (!std::mem::replace(&mut self.started, true)).then(metadata);
+        proto::instrument::Update {
+            now,
+            task_update: Some(task_update),
+            resource_update: Some(resource_update),
+            async_op_update: Some(Default::default()),
+            new_metadata,
+            ..Default::default()
+        }
+    }
+
+    /// Polls the task at `idx` as its behavior says, over the update starting
+    /// at `start`, completing it if its time is up.
+    fn poll(&mut self, idx: usize, start: SystemTime) {
+        let behavior = SPAWNS[self.tasks[idx].spawn].2;
+        let (polls, each) = match behavior {
+            Behavior::Server => (self.between(2, 8), self.between(20, 400)),
+            Behavior::Connection => (self.between(5, 40), self.between(50, 2_000)),
+            Behavior::Idle if self.chance(0.1) => (1, self.between(100, 900)),
+            Behavior::Blocking => (1, 0),
+            Behavior::SelfWaking => (self.between(20, 60), self.between(10, 80)),
+            Behavior::LosesWaker if self.tasks[idx].stats.wakes < 5 => (1, self.between(30, 90)),
+            Behavior::Job => (self.between(1, 5), self.between(200, 5_000)),
+            _ => (0, 0),
+        };
+        let panics = behavior == Behavior::Job && self.chance(0.2);
+        let running = behavior == Behavior::Connection && self.chance(0.15);
+        let scheduled = self.between(5, 200) * polls;
+        let end = self.now;
+
+        let task = &mut self.tasks[idx];
+        let stats = &mut task.stats;
+        let poll_stats = stats.poll_stats.get_or_insert_with(Default::default);
+        // A task spawned during this update can only have run since then.
+        let since = stats
+            .created_at
+            .and_then(|created| SystemTime::try_from(created).ok())
+            .map_or(start, |created| created.max(start));
+        let window = end.duration_since(since).unwrap_or_default();
+        let busy = match behavior {
+            // Blocking tasks are busy for as long as they run.
+            Behavior::Blocking => window,
+            _ => Duration::from_micros(polls * each).min(window / 2),
+        };
+        if polls > 0 {
+            // Each poll is woken by the one before it, or by the task itself.
+            stats.wakes += polls;
+            if behavior == Behavior::SelfWaking {
+                stats.self_wakes += polls - polls / 10;
+            }
+            add(&mut stats.scheduled_time, Duration::from_micros(scheduled));
+            poll_stats.polls += polls;
+            add(&mut poll_stats.busy_time, busy);
+            poll_stats.first_poll.get_or_insert_with(|| since.into());
+            let last_started = end - busy / 2;
+            stats.last_wake =
+                Some((last_started - Duration::from_micros(scheduled / polls)).into());
+            poll_stats.last_poll_started = Some(last_started.into());
+            poll_stats.last_poll_ended = (!running && behavior != Behavior::Blocking)
+                .then(|| (last_started + Duration::from_micros(each)).into());
+        }
+        // The task holds one waker while it waits, except once it has lost it.
+        let lost = behavior == Behavior::LosesWaker && stats.wakes >= 5;
+        stats.waker_clones = stats.wakes + 1;
+        stats.waker_drops = if lost {
+            stats.waker_clones
+        } else {
+            stats.wakes
+        };
+
+        let remaining = task.remaining.map(|remaining| remaining.saturating_sub(1));
+        task.remaining = remaining;
+        if remaining != Some(0) && !panics {
+            return;
+        }
+        stats.dropped_at = Some(end.into());
+        poll_stats.last_poll_ended = Some(end.into());
+        stats.waker_drops = stats.waker_clones;
+        if panics {
+            stats.panic = Some(proto::tasks::Panic {
+                message: Some("index out of bounds: the len is 3 but the index is 7".to_owned()),
+                location: Some(location("src/jobs.rs", 31)),
+            });
+            stats.end_reason = Some(proto::tasks::EndReason::Panicked as i32);
+        }
+        let resource_id = task.resource;
+        if let Some(resource) = self
+            .resources
+            .iter_mut()
+            .find(|resource| Some(resource.id) == resource_id)
+        {
+            resource.stats.dropped_at = Some(end.into());
+        }
+    }
+
+    /// Spawns a task at the spawn location at `spawn`, in [`SPAWNS`], at the
+    /// time `at`.
+    fn spawn(&mut self, spawn: usize, at: SystemTime) {
+        let id = self.next_id();
+        let remaining = match SPAWNS[spawn].2 {
+            Behavior::Connection => Some(self.between(3, 40) as u32),
+            Behavior::Blocking => Some(self.between(1, 4) as u32),
+            Behavior::Job => Some(self.between(1, 6) as u32),
+            _ => None,
+        };
+        // Connections time out if they're idle for too long.
+        let resource = (SPAWNS[spawn].2 == Behavior::Connection).then(|| {
+            let timeout = self.between(5, 30) * 1000;
+            self.create_resource(
+                SLEEP_META,
+                vec![u64_attribute("duration", timeout, Some("ms"))],
+                at,
+            )
+        });
+        self.tasks.push(Task {
+            id,
+            spawn,
+            remaining,
+            resource,
+            stats: proto::tasks::Stats {
+                created_at: Some(at.into()),
+                ..Default::default()
+            },
+            new: true,
+        });
+    }
+
+    fn create_resource(
+        &mut self,
+        meta: u64,
+        attributes: Vec<proto::Attribute>,
+        at: SystemTime,
+    ) -> u64 {
+        let id = self.next_id();
+        self.resources.push(Resource {
+            id,
+            meta,
+            stats: proto::resources::Stats {
+                created_at: Some(at.into()),
+                dropped_at: None,
+                attributes,
+            },
+            new: true,
+        });
+        id
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Returns a random number, with xorshift.
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// Returns a random number from `low` to `high`, inclusive.
+    fn between(&mut self, low: u64, high: u64) -> u64 {
+        low + self.random() % (high - low + 1)
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        (self.random() % 1_000) as f64 / 1_000.0 < probability
+    }
+}
+
+fn new_task(task: &Task) -> proto::tasks::Task {
+    let (file, line, behavior) = SPAWNS[task.spawn];
+    let meta = task.spawn as u64 + 1;
+    let field = |name: &str, value| proto::Field {
+        metadata_id: Some(proto::MetaId { id: meta }),
+        name: Some(proto::field::Name::StrName(name.to_owned())),
+        value: Some(value),
+    };
+    let blocking = behavior == Behavior::Blocking;
+    let mut fields = vec![
+        field("task.id", proto::field::Value::U64Val(task.id)),
+        field(
+            "kind",
+            proto::field::Value::StrVal(if blocking { "blocking" } else { "task" }.to_owned()),
+        ),
+    ];
+    let name = match behavior {
+        Behavior::Server => Some("accept".to_owned()),
+        Behavior::Connection => Some(format!("conn-{}", task.id)),
+        Behavior::Idle => Some("cache-refresh".to_owned()),
+        _ => None,
+    };
+    if let Some(name) = name {
+        fields.push(field("task.name", proto::field::Value::StrVal(name)));
+    }
+    if blocking {
+        fields.push(field(
+            "fn",
+            proto::field::Value::StrVal("storage::compress".to_owned()),
+        ));
+    }
+    proto::tasks::Task {
+        id: Some(proto::Id { id: task.id }),
+        metadata: Some(proto::MetaId { id: meta }),
+        kind: if blocking {
+            proto::tasks::task::Kind::Blocking
+        } else {
+            proto::tasks::task::Kind::Spawn
+        } as i32,
+        fields,
+        location: Some(location(file, line)),
+        ..Default::default()
+    }
+}
+
+/// Returns the metadata for the demo's spawn locations and resources.
+fn metadata() -> proto::RegisterMetadata {
+    let meta = |id, name: &str, target: &str, location| proto::register_metadata::NewMetadata {
+        id: Some(proto::MetaId { id }),
+        metadata: Some(proto::Metadata {
+            name: name.to_owned(),
+            target: target.to_owned(),
+            location: Some(location),
+            ..Default::default()
+        }),
+    };
+    let mut metadata = SPAWNS
+        .iter()
+        .enumerate()
+        .map(|(idx, &(file, line, _))| {
+            meta(
+                idx as u64 + 1,
+                "runtime.spawn",
+                "tokio::task",
+                location(file, line),
+            )
+        })
+        .collect::<Vec<_>>();
+    metadata.extend([
+        meta(
+            MUTEX_META,
+            "runtime.resource",
+            "tokio::sync::mutex",
+            resource_location(MUTEX_META),
+        ),
+        meta(
+            SEMAPHORE_META,
+            "runtime.resource",
+            "tokio::sync::semaphore",
+            resource_location(SEMAPHORE_META),
+        ),
+        meta(
+            SLEEP_META,
+            "runtime.resource",
+            "tokio::time::sleep",
+            resource_location(SLEEP_META),
+        ),
+    ]);
+    proto::RegisterMetadata { metadata }
+}
+
+fn location(file: &str, line: u32) -> proto::Location {
+    proto::Location {
+        file: Some(file.to_owned()),
+        module_path: None,
+        line: Some(line),
+        column: Some(9),
+    }
+}
+
+/// Returns where the resource with the metadata ID `meta` is created.
+fn resource_location(meta: u64) -> proto::Location {
+    location("src/server.rs", 12 + (meta - MUTEX_META) as u32)
+}
+
+fn bool_attribute(name: &str, value: bool) -> proto::Attribute {
+    attribute(name, proto::field::Value::BoolVal(value), None)
+}
+
+fn u64_attribute(name: &str, value: u64, unit: Option<&str>) -> proto::Attribute {
+    attribute(name, proto::field::Value::U64Val(value), unit)
+}
+
+fn attribute(name: &str, value: proto::field::Value, unit: Option<&str>) -> proto::Attribute {
+    proto::Attribute {
+        field: Some(proto::Field {
+            metadata_id: None,
+            name: Some(proto::field::Name::StrName(name.to_owned())),
+            value: Some(value),
+        }),
+        unit: unit.map(ToOwned::to_owned),
+    }
+}
+
+/// Adds `duration` to the protobuf duration `total`.
+fn add(total: &mut Option<prost_types::Duration>, duration: Duration) {
+    let sum = total
+        .and_then(|total| Duration::try_from(total).ok())
+        .unwrap_or_default()
+        + duration;
+    *total = prost_types::Duration::try_from(sum).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ViewOptions,
+        state::State,
+        view::{Styles, ViewState},
+    };
+    use clap::Parser;
+
+    // The demo's publishing interval needs a runtime to be created.
+    #[tokio::test]
+    async fn tasks_and_resources_churn() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = State::default();
+        let mut demo = Demo::new();
+        for _ in 0..30 {
+            state.update(&styles, &ViewState::TasksList, demo.update());
+        }
+
+        let tasks = state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+            .collect::<Vec<_>>();
+        // Every long-lived task is still there, and others have come and gone.
+        assert!(tasks.len() > 13, "only {} tasks", tasks.len());
+        assert!(tasks.iter().any(|task| task.borrow().is_completed()));
+        assert!(tasks.iter().any(|task| !task.borrow().is_completed()));
+        assert!(state.resources_state().resources().count() > 2);
+    }
+}
@@ -0,0 +1,101 @@
+//! Finding targets to list in the connect dialog, besides the named targets
+//! in the config file.
+use crate::{docker, k8s};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where to look for targets.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Discover {
+    Kubernetes,
+    Docker,
+    Local,
+}
+
+/// Returns the targets found in each of `sources`, as `(name, address)`
+/// pairs.
+///
+/// Sources which can't be searched, such as because a tool they use isn't
+/// installed, are skipped.
+pub(crate) fn targets(sources: &[Discover]) -> Vec<(String, String)> {
+    sources
+        .iter()
+        .flat_map(|source| match source {
+            Discover::Kubernetes => k8s::discover(),
+            Discover::Docker => docker::discover(),
+            Discover::Local => local(),
+        })
+        .collect()
+}
+
+/// Returns the sockets which local processes serve on with
+/// console-subscriber's `ServerAddr::local`, as `(name (pid PID), address)`
+/// pairs.
+///
+/// Sockets left by processes which have exited are skipped, where that can
+/// be told from `/proc`.
+fn local() -> Vec<(String, String)> {
+    let dir = local_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::debug!(%error, dir = %dir.display(), "could not list local sockets");
+            return Vec::new();
+        }
+    };
+    let proc = Path::new("/proc/self").exists();
+    let mut sockets = entries
+        .filter_map(|entry| local_socket(&entry.ok()?.path()))
+        .filter(|(_, pid, _)| !proc || Path::new("/proc").join(pid.to_string()).exists())
+        .map(|(name, pid, path)| (format!("{name} (pid {pid})"), format!("file://{path}")))
+        .collect::<Vec<_>>();
+    sockets.sort();
+    sockets
+}
+
+/// The directory console-subscriber's `ServerAddr::local` puts sockets in.
+fn local_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("tokio-console")
+}
+
+/// Parses the name and process ID of the process serving on the socket at
+/// `path`, which is named `NAME-PID.sock`.
+fn local_socket(path: &Path) -> Option<(String, u32, String)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (name, pid) = file_name.strip_suffix(".sock")?.rsplit_once('-')?;
+    Some((
+        name.to_owned(),
+        pid.parse().ok()?,
+        path.to_str()?.to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_socket_names() {
+        assert_eq!(
+            local_socket(Path::new("/run/user/1000/tokio-console/my-app-4321.sock")),
+            Some((
+                "my-app".to_owned(),
+                4321,
+                "/run/user/1000/tokio-console/my-app-4321.sock".to_owned()
+            ))
+        );
+        assert_eq!(local_socket(Path::new("/tmp/tokio-console/app.sock")), None);
+        assert_eq!(
+            local_socket(Path::new("/tmp/tokio-console/app-x.sock")),
+            None
+        );
+        assert_eq!(
+            local_socket(Path::new("/tmp/tokio-console/app-1.txt")),
+            None
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Connecting to targets running in local Docker containers.
+//!
+//! A target written as `docker://container[:port]` is connected to through
+//! the host port the container's console port is published on, or, if it
+//! isn't published, the container's address on one of its networks, which
+//! are looked up with `docker inspect` each time the console connects, since
+//! they change when the container is restarted.
+use crate::conn::DEFAULT_PORT;
+use serde::Deserialize;
+use std::{collections::HashMap, error::Error, net::IpAddr, process::Stdio};
+use tokio::process::Command;
+use tonic::transport::Uri;
+
+/// Returns the address to connect to `target`, which is a `docker://`
+/// address.
+pub(crate) async fn resolve(target: &Uri) -> Result<Uri, Box<dyn Error + Send + Sync>> {
+    let container = target
+        .host()
+        .filter(|container| !container.is_empty())
+        .ok_or("a Docker target must be written as docker://container[:port]")?;
+    let port = target.port_u16().unwrap_or(DEFAULT_PORT);
+    let output = Command::new("docker")
+        .args(["inspect", "--type=container", container])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|error| format!("could not run docker: {error}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("docker inspect failed: {}", stderr.trim()).into());
+    }
+    let containers: Vec<Container> = serde_json::from_slice(&output.stdout)?;
+    let container = containers
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("no such container: {container}"))?;
+    let addr = container
+        .addr(port)
+        .ok_or_else(|| format!("port {port} of the container isn't reachable from this host"))?;
+    tracing::debug!(%target, %addr, "resolved container");
+    Ok(format!("http://{addr}").parse()?)
+}
+
+/// Returns the running containers which publish console-subscriber's
+/// default port, as `(name, address)` pairs.
+///
+/// This returns nothing if `docker` isn't installed or its daemon isn't
+/// running, since listing containers is only a convenience.
+pub(crate) fn discover() -> Vec<(String, String)> {
+    let output = std::process::Command::new("docker")
+        .args([
+            "ps",
+            &format!("--filter=publish={DEFAULT_PORT}/tcp"),
+            "--format={{.Names}}",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            container_names(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            tracing::debug!(status = %output.status, "could not list containers");
+            Vec::new()
+        }
+        Err(error) => {
+            tracing::debug!(%error, "could not run docker");
+            Vec::new()
+        }
+    }
+}
+
+/// Parses `docker ps`'s list of containers' names, one container per line,
+/// with each container's names separated by commas.
+fn container_names(names: &str) -> Vec<(String, String)> {
+    let mut containers = names
+        .lines()
+        .filter_map(|names| names.split(',').next())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| (name.to_owned(), format!("docker://{name}")))
+        .collect::<Vec<_>>();
+    containers.sort();
+    containers
+}
+
+/// The parts of `docker inspect`'s description of a container which are
+/// used.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Container {
+    network_settings: NetworkSettings,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NetworkSettings {
+    /// The host ports each container port is published on, by port and
+    /// protocol, such as `6669/tcp`, or `null` for ports which are exposed
+    /// but not published.
+    #[serde(default)]
+    ports: HashMap<String, Option<Vec<Binding>>>,
+    #[serde(default)]
+    networks: HashMap<String, Network>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Binding {
+    host_ip: String,
+    host_port: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Network {
+    #[serde(rename = "IPAddress")]
+    ip_address: String,
+}
+
+// === impl Container ===
+
+impl Container {
+    /// Returns the address to connect to the container's `port` at,
+    /// preferring the host port it's published on.
+    fn addr(&self, port: u16) -> Option<String> {
+        let settings = &self.network_settings;
+        let bindings = settings.ports.get(&format!("{port}/tcp"));
+        let published = bindings
+            .into_iter()
+            .flatten()
+            .flatten()
+            .find_map(|binding| {
+                let host_port = binding.host_port.parse::<u16>().ok()?;
+                // A port published on every interface is reachable locally.
+                let host = match binding.host_ip.parse::<IpAddr>() {
+                    Ok(ip) if ip.is_unspecified() => "127.0.0.1".to_owned(),
+                    Ok(IpAddr::V6(ip)) => format!("[{ip}]"),
+                    Ok(IpAddr::V4(ip)) => ip.to_string(),
+                    Err(_) if binding.host_ip.is_empty() => "127.0.0.1".to_owned(),
+                    Err(_) => return None,
+                };
+                Some(format!("{host}:{host_port}"))
+            });
+        if published.is_some() {
+            return published;
+        }
+
+        // Otherwise, connect through the container's network, which is
+        // only reachable from the host on Linux.
+        let mut networks = settings.networks.iter().collect::<Vec<_>>();
+        networks.sort_by_key(|(name, _)| *name);
+        networks
+            .into_iter()
+            .map(|(_, network)| network.ip_address.as_str())
+            .find(|ip| !ip.is_empty())
+            .map(|ip| format!("{ip}:{port}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Container {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn prefers_published_port() {
+        let container = parse(
+            r#"{"NetworkSettings": {
+                "Ports": {
+                    "6669/tcp": [
+                        {"HostIp": "0.0.0.0", "HostPort": "32768"},
+                        {"HostIp": "::", "HostPort": "32768"}
+                    ],
+                    "8080/tcp": null
+                },
+                "Networks": {"bridge": {"IPAddress": "172.17.0.2"}}
+            }}"#,
+        );
+        assert_eq!(container.addr(6669).as_deref(), Some("127.0.0.1:32768"));
+        assert_eq!(container.addr(8080).as_deref(), Some("172.17.0.2:8080"));
+    }
+
+    #[test]
+    fn falls_back_to_container_network() {
+        let container = parse(
+            r#"{"NetworkSettings": {
+                "Ports": {},
+                "Networks": {
+                    "none": {"IPAddress": ""},
+                    "app": {"IPAddress": "10.0.5.3"}
+                }
+            }}"#,
+        );
+        assert_eq!(container.addr(6669).as_deref(), Some("10.0.5.3:6669"));
+
+        let container = parse(r#"{"NetworkSettings": {"Networks": {}}}"#);
+        assert_eq!(container.addr(6669), None);
+    }
+
+    #[test]
+    fn parses_container_names() {
+        assert_eq!(
+            container_names("web\napi,api-alias\n\n"),
+            [
+                ("api".to_owned(), "docker://api".to_owned()),
+                ("web".to_owned(), "docker://web".to_owned()),
+            ]
+        );
+    }
+}
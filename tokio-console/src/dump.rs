@@ -0,0 +1,98 @@
+//! The `tokio-console dump` subcommand, which prints the task and resource
+//! lists as the console would display them, without starting the console UI.
+use crate::{
+    export::{self, Table},
+    query::{self, Format},
+    state::State,
+    view,
+};
+use std::{fmt::Write as _, time::Duration};
+use tonic::transport::Uri;
+
+/// Connects to `target`, waits for its current state, and prints the task
+/// and resource lists that `view` would display in `format`.
+///
+/// Returns an error if the target doesn't send its state within `timeout`.
+pub(crate) async fn run(
+    target: Uri,
+    format: Format,
+    timeout: Duration,
+    styles: &view::Styles,
+    mut state: State,
+    mut view: view::View,
+) -> color_eyre::Result<()> {
+    let update = query::first_update(target, timeout).await?;
+    state.update(styles, &view::ViewState::TasksList, update);
+    print!("{}", print(&view.tables(&mut state), format));
+    Ok(())
+}
+
+/// Returns `tables` as aligned tables, each below its name and number of
+/// rows, or as a JSON object with an array of rows for each table.
+fn print(tables: &[Table], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Table => {
+            for (i, table) in tables.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "{} ({})", table.name, table.rows.len());
+                let header = table.header.iter().map(String::as_str);
+                out.push_str(&export::text(header, &table.rows));
+            }
+        }
+        Format::Json => {
+            out.push('{');
+            for (i, table) in tables.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                let header = table.header.iter().map(String::as_str);
+                let rows = export::json(header, &table.rows);
+                let name = serde_json::Value::from(table.name);
+                let _ = write!(out, "{name}: {}", rows.trim_end());
+            }
+            out.push_str("}\n");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Value;
+
+    #[test]
+    fn print_tables() {
+        let tables = [
+            Table {
+                name: "tasks",
+                header: vec!["ID".to_owned(), "Name".to_owned()],
+                rows: vec![vec![Value::Number(1.0), Value::Text("main".to_owned())]],
+            },
+            Table {
+                name: "resources",
+                header: vec!["ID".to_owned()],
+                rows: Vec::new(),
+            },
+        ];
+        assert_eq!(
+            print(&tables, Format::Table),
+            "tasks (1)\n\
+             ID  Name\n\
+             1   main\n\
+             \n\
+             resources (0)\n\
+             ID\n"
+        );
+        assert_eq!(
+            print(&tables, Format::Json),
+            "{\"tasks\": [\n  \
+             {\"ID\": 1, \"Name\": \"main\"}\n\
+             ],\n\
+             \"resources\": []}\n"
+        );
+    }
+}
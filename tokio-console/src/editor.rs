@@ -0,0 +1,275 @@
+use crate::util;
+use console_api as proto;
+use std::{fmt, io, process::Command, str::FromStr};
+
+/// A command which opens a location in the instrumented program's code in an
+/// editor, such as `code --goto {file}:{line}:{column}`.
+///
+/// The command is split on whitespace, and `{file}`, `{line}` and `{column}`
+/// in its arguments are replaced with the location being opened.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct EditorCommand {
+    /// The command as it was written.
+    template: String,
+    program: String,
+    args: Vec<String>,
+    /// Whether the editor runs in the terminal, so that the console has to
+    /// hand the terminal over to it until it exits.
+    in_terminal: bool,
+}
+
+/// An editor which runs in the terminal, waiting for the console to hand the
+/// terminal over to it to open a location.
+#[derive(Debug)]
+pub(crate) struct Edit {
+    command: EditorCommand,
+    location: SourceLocation,
+}
+
+/// A location in the instrumented program's code, as recorded by its
+/// instrumentation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SourceLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+/// Opens `location` with `command`, returning the edit waiting to be handed
+/// the terminal if the editor runs in it.
+pub(crate) fn open(
+    command: Option<&EditorCommand>,
+    location: &SourceLocation,
+) -> Result<Option<Edit>, String> {
+    let command = command.ok_or_else(|| {
+        format!(
+            "no editor command is configured to open {location}, see --editor-command or set $EDITOR"
+        )
+    })?;
+    command
+        .open(location)
+        .map_err(|error| format!("couldn't run `{command}` to open {location}: {error}"))
+}
+
+// === impl EditorCommand ===
+
+impl EditorCommand {
+    const FILE: &'static str = "{file}";
+    const LINE: &'static str = "{line}";
+    const COLUMN: &'static str = "{column}";
+
+    /// Returns a command which opens locations in the editor named by the
+    /// `VISUAL` or `EDITOR` environment variables, if either is set.
+    ///
+    /// These editors usually run in the terminal, so the console hands the
+    /// terminal over to them until they exit.
+    pub(crate) fn from_env() -> Option<Self> {
+        ["VISUAL", "EDITOR"]
+            .into_iter()
+            .find_map(|var| Self::in_terminal(&std::env::var(var).ok()?))
+    }
+
+    /// Returns a command which runs `editor` in the terminal, passing it the
+    /// line to open as `+{line}`, which most terminal editors understand.
+    fn in_terminal(editor: &str) -> Option<Self> {
+        if editor.trim().is_empty() {
+            return None;
+        }
+        let mut command = format!("{} +{} {}", editor.trim(), Self::LINE, Self::FILE)
+            .parse::<Self>()
+            .ok()?;
+        command.in_terminal = true;
+        Some(command)
+    }
+
+    /// Runs the command to open `location`, or, if the editor runs in the
+    /// terminal, returns it to be run once the console has handed the
+    /// terminal over to it.
+    ///
+    /// Otherwise, the editor is not waited for, so that the console keeps
+    /// updating while it runs. Since the console is using the terminal, the
+    /// editor should open in its own window.
+    pub(crate) fn open(&self, location: &SourceLocation) -> io::Result<Option<Edit>> {
+        if self.in_terminal {
+            return Ok(Some(Edit {
+                command: self.clone(),
+                location: location.clone(),
+            }));
+        }
+        util::spawn_detached(Command::new(&self.program).args(self.args_for(location)))?;
+        Ok(None)
+    }
+
+    fn args_for(&self, location: &SourceLocation) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                arg.replace(Self::FILE, &location.file)
+                    .replace(Self::LINE, &location.line.to_string())
+                    .replace(Self::COLUMN, &location.column.to_string())
+            })
+            .collect()
+    }
+}
+
+impl FromStr for EditorCommand {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace().map(str::to_owned);
+        let program = words
+            .next()
+            .ok_or_else(|| ParseError("editor command is empty".to_owned()))?;
+        let args = words.collect::<Vec<_>>();
+        if !args.iter().any(|arg| arg.contains(Self::FILE)) {
+            return Err(ParseError(format!(
+                "editor command `{s}` has no `{}` argument to replace with the file to open",
+                Self::FILE
+            )));
+        }
+        Ok(Self {
+            template: s.trim().to_owned(),
+            program,
+            args,
+            in_terminal: false,
+        })
+    }
+}
+
+impl fmt::Display for EditorCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl TryFrom<String> for EditorCommand {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<EditorCommand> for String {
+    fn from(command: EditorCommand) -> Self {
+        command.template
+    }
+}
+
+// === impl Edit ===
+
+impl Edit {
+    /// Runs the editor, waiting for it to exit.
+    ///
+    /// The console must have given up the terminal first, since the editor
+    /// uses it until it exits.
+    pub(crate) fn run(self) -> io::Result<()> {
+        let status = Command::new(&self.command.program)
+            .args(self.command.args_for(&self.location))
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "`{}` {status}",
+                self.command.program
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.location, f)
+    }
+}
+
+// === impl SourceLocation ===
+
+impl SourceLocation {
+    /// Returns the location recorded in `location`, if it records a file.
+    ///
+    /// A missing line or column is replaced with 1, so that the file can still
+    /// be opened.
+    pub(crate) fn from_proto(location: &proto::Location) -> Option<Self> {
+        Some(Self {
+            file: location.file.clone()?,
+            line: location.line.unwrap_or(1),
+            column: location.column.unwrap_or(1),
+        })
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        let command = "code  --goto {file}:{line}:{column}"
+            .parse::<EditorCommand>()
+            .unwrap();
+        assert_eq!(command.program, "code");
+        assert_eq!(command.args, ["--goto", "{file}:{line}:{column}"]);
+        assert_eq!(command.to_string(), "code  --goto {file}:{line}:{column}");
+
+        assert!("".parse::<EditorCommand>().is_err());
+        assert!("code --goto".parse::<EditorCommand>().is_err());
+    }
+
+    #[test]
+    fn replace_placeholders() {
+        let command = "emacsclient -n +{line}:{column} {file}"
+            .parse::<EditorCommand>()
+            .unwrap();
+        let location = SourceLocation::from_proto(&proto::Location {
+            file: Some("src/main.rs".to_owned()),
+            module_path: None,
+            line: Some(10),
+            column: None,
+        })
+        .unwrap();
+        assert_eq!(location.to_string(), "src/main.rs:10:1");
+        assert_eq!(command.args_for(&location), ["-n", "+10:1", "src/main.rs"]);
+    }
+
+    #[test]
+    fn terminal_editors_are_handed_the_terminal() {
+        let command = EditorCommand::in_terminal("nvim --clean").unwrap();
+        assert_eq!(command.program, "nvim");
+        let location = SourceLocation::from_proto(&proto::Location {
+            file: Some("src/lib.rs".to_owned()),
+            module_path: None,
+            line: Some(3),
+            column: Some(7),
+        })
+        .unwrap();
+        assert_eq!(command.args_for(&location), ["--clean", "+3", "src/lib.rs"]);
+        let edit = command
+            .open(&location)
+            .unwrap()
+            .expect("nvim runs in the terminal");
+        assert_eq!(edit.to_string(), "src/lib.rs:3:7");
+
+        assert!(EditorCommand::in_terminal(" ").is_none());
+    }
+}
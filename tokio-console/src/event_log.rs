@@ -0,0 +1,261 @@
+//! A rolling log of the changes between consecutive updates, such as tasks
+//! being spawned and completing, which otherwise vanish as soon as the next
+//! update is drawn.
+use crate::{
+    i18n::{tr, tr_fmt},
+    state::{
+        resources::ResourcesState,
+        store::SpanId,
+        tasks::{TaskState, TasksState},
+    },
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::SystemTime,
+};
+
+/// How many entries are kept, after which the oldest are dropped.
+const CAPACITY: usize = 1000;
+
+/// How much an entry matters, which the log can be filtered by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    /// Changes which happen all the time in a busy target, such as tasks
+    /// waking themselves.
+    Debug,
+    /// Tasks and resources coming and going.
+    Info,
+    /// Tasks panicking, alerts being triggered, and the connection being
+    /// lost.
+    Warn,
+}
+
+/// Something which changed between two updates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// When the change was seen, by the target's clock if it was seen in an
+    /// update.
+    pub(crate) at: SystemTime,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+/// The log, and what was seen in the last update, to find what changed in
+/// the next one.
+#[derive(Debug, Default)]
+pub(crate) struct EventLog {
+    /// The newest entries, oldest first.
+    entries: VecDeque<Entry>,
+    /// Each task in the last update, and what was seen of it.
+    tasks: HashMap<SpanId, SeenTask>,
+    /// Each resource in the last update, and whether it had been dropped.
+    resources: HashMap<SpanId, bool>,
+    /// Whether an update has been recorded yet.
+    recorded: bool,
+    /// Whether the console was connected to the target when last checked,
+    /// or `None` if it hasn't connected yet.
+    connected: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SeenTask {
+    completed: bool,
+    self_wakes: u64,
+}
+
+// === impl Severity ===
+
+impl Severity {
+    /// Returns the next severity to filter the log by, from showing every
+    /// entry to showing only warnings.
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Debug => Self::Info,
+            Self::Info => Self::Warn,
+            Self::Warn => Self::Debug,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+        }
+    }
+}
+
+// === impl EventLog ===
+
+impl EventLog {
+    /// Logs what changed about the tasks and resources since the last
+    /// update, as of `now`.
+    ///
+    /// The first update only records the tasks and resources which exist,
+    /// since they didn't change when the console connected.
+    pub(crate) fn record(
+        &mut self,
+        now: SystemTime,
+        tasks: &TasksState,
+        resources: &ResourcesState,
+    ) {
+        let log = self.recorded;
+        self.recorded = true;
+
+        let mut seen_tasks = HashMap::with_capacity(self.tasks.len());
+        for task in tasks.tasks() {
+            let Some(task) = task.upgrade() else {
+                continue;
+            };
+            let task = task.borrow();
+            let seen = SeenTask {
+                completed: task.is_completed(),
+                self_wakes: task.self_wakes(),
+            };
+            let previous = self.tasks.get(&task.span_id()).copied();
+            seen_tasks.insert(task.span_id(), seen);
+            if !log {
+                continue;
+            }
+            let desc = task.short_desc();
+            if previous.is_none() {
+                self.push(now, Severity::Info, tr_fmt("task {} spawned", &[&desc]));
+            }
+            let self_wakes = seen
+                .self_wakes
+                .saturating_sub(previous.map_or(0, |seen| seen.self_wakes));
+            if self_wakes > 0 {
+                self.push(
+                    now,
+                    Severity::Debug,
+                    tr_fmt("task {} woke itself {} times", &[&desc, &self_wakes]),
+                );
+            }
+            if seen.completed && !previous.is_some_and(|seen| seen.completed) {
+                let state = task.state();
+                let severity = if state == TaskState::Panicked {
+                    Severity::Warn
+                } else {
+                    Severity::Info
+                };
+                self.push(
+                    now,
+                    severity,
+                    tr_fmt("task {} {}", &[&desc, &tr(state.description())]),
+                );
+            }
+        }
+        self.tasks = seen_tasks;
+
+        let mut seen_resources = HashMap::with_capacity(self.resources.len());
+        for resource in resources.resources() {
+            let Some(resource) = resource.upgrade() else {
+                continue;
+            };
+            let resource = resource.borrow();
+            let dropped = resource.dropped();
+            let previous = self.resources.get(&resource.span_id()).copied();
+            seen_resources.insert(resource.span_id(), dropped);
+            if !log {
+                continue;
+            }
+            let desc = format!("{} ({})", resource.id_str(), resource.concrete_type());
+            if previous.is_none() {
+                self.push(now, Severity::Info, tr_fmt("resource {} created", &[&desc]));
+            }
+            if dropped && previous != Some(true) {
+                self.push(now, Severity::Info, tr_fmt("resource {} dropped", &[&desc]));
+            }
+        }
+        self.resources = seen_resources;
+    }
+
+    /// Logs the connection to the target being lost or restored, if it was
+    /// connected to or disconnected from since it was last checked, at `now`
+    /// by the target's clock.
+    pub(crate) fn check_connection(&mut self, now: SystemTime, connected: bool) {
+        match (self.connected, connected) {
+            (Some(true), false) => self.push(
+                now,
+                Severity::Warn,
+                tr("lost the connection to the target").to_owned(),
+            ),
+            (Some(false), true) => self.push(
+                now,
+                Severity::Info,
+                tr("restored the connection to the target").to_owned(),
+            ),
+            _ => {}
+        }
+        // Connecting for the first time isn't a change worth logging.
+        if connected || self.connected.is_some() {
+            self.connected = Some(connected);
+        }
+    }
+
+    /// Logs an alert being triggered by the update at `now`.
+    pub(crate) fn alert(&mut self, now: SystemTime, message: String) {
+        self.push(now, Severity::Warn, message);
+    }
+
+    /// Returns the entries which are at least as severe as `severity`,
+    /// oldest first.
+    pub(crate) fn entries(&self, severity: Severity) -> impl DoubleEndedIterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.severity >= severity)
+    }
+
+    fn push(&mut self, at: SystemTime, severity: Severity, message: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry {
+            at,
+            severity,
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_changes() {
+        let mut log = EventLog::default();
+        let now = SystemTime::UNIX_EPOCH;
+        // Connecting for the first time isn't logged.
+        log.check_connection(now, false);
+        log.check_connection(now, true);
+        assert_eq!(log.entries(Severity::Debug).count(), 0);
+
+        log.check_connection(now, false);
+        log.check_connection(now, false);
+        log.check_connection(now, true);
+        let messages = log
+            .entries(Severity::Debug)
+            .map(|entry| (entry.severity, entry.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                (Severity::Warn, "lost the connection to the target"),
+                (Severity::Info, "restored the connection to the target"),
+            ]
+        );
+        assert_eq!(log.entries(Severity::Warn).count(), 1);
+    }
+
+    #[test]
+    fn drops_oldest_entries() {
+        let mut log = EventLog::default();
+        for idx in 0..CAPACITY + 1 {
+            log.push(SystemTime::UNIX_EPOCH, Severity::Info, idx.to_string());
+        }
+        let entries = log.entries(Severity::Debug).collect::<Vec<_>>();
+        assert_eq!(entries.len(), CAPACITY);
+        assert_eq!(entries[0].message, "1");
+    }
+}
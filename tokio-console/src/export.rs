@@ -0,0 +1,202 @@
+//! Writing the rows of a table to a CSV or JSON file, so that they can be
+//! shared or analyzed elsewhere.
+use crate::query::Value;
+use std::{
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The kind of file which a table is exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Comma-separated values, with a header. Durations are in seconds.
+    Csv,
+    /// A JSON array with an object for each row. Durations are in seconds.
+    Json,
+}
+
+/// The displayed columns and rows of one of the console's tables.
+#[derive(Debug)]
+pub(crate) struct Table {
+    /// The table's name, such as `tasks`.
+    pub(crate) name: &'static str,
+    pub(crate) header: Vec<String>,
+    pub(crate) rows: Vec<Vec<Value>>,
+}
+
+/// Writes `rows`, whose columns are named `header`, to a file named after
+/// `table` in the current directory, returning its path.
+pub(crate) fn save(
+    table: &str,
+    format: Format,
+    header: &[String],
+    rows: &[Vec<Value>],
+) -> color_eyre::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let header = header.iter().map(String::as_str);
+    let (contents, extension) = match format {
+        Format::Csv => (csv(header, rows), "csv"),
+        Format::Json => (json(header, rows), "json"),
+    };
+    let path = PathBuf::from(format!("tokio-console-{table}-{secs}.{extension}"));
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Returns `rows` as CSV, with a header row of `names`.
+pub(crate) fn csv<'a>(names: impl IntoIterator<Item = &'a str>, rows: &[Vec<Value>]) -> String {
+    let mut out = String::new();
+    let names = names.into_iter().map(csv_field).collect::<Vec<_>>();
+    out.push_str(&names.join(","));
+    out.push_str("\r\n");
+    for row in rows {
+        let row = row
+            .iter()
+            .map(|value| match value {
+                Value::Null => String::new(),
+                Value::Number(value) => value.to_string(),
+                Value::Duration(value) => value.as_secs_f64().to_string(),
+                Value::Text(value) => csv_field(value),
+            })
+            .collect::<Vec<_>>();
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Returns `rows` as an aligned table, with a header row of `names`.
+pub(crate) fn text<'a>(names: impl IntoIterator<Item = &'a str>, rows: &[Vec<Value>]) -> String {
+    let mut cells = vec![names.into_iter().map(str::to_owned).collect::<Vec<_>>()];
+    cells.extend(
+        rows.iter()
+            .map(|row| row.iter().map(Value::to_string).collect()),
+    );
+    let widths = (0..cells[0].len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row.get(i).map_or(0, |cell| cell.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    for row in cells {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            let _ = write!(line, "{cell:width$}");
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns `rows` as a Markdown table, with a header row of `names`.
+pub(crate) fn markdown<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    rows: &[Vec<Value>],
+) -> String {
+    // A pipe would end the cell early.
+    let cell = |text: &str| text.replace('|', "\\|");
+    let names = names.into_iter().map(cell).collect::<Vec<_>>();
+    let mut out = format!("| {} |\n", names.join(" | "));
+    let _ = writeln!(out, "|{}", " --- |".repeat(names.len()));
+    for row in rows {
+        let row = row
+            .iter()
+            .map(|value| cell(&value.to_string()))
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "| {} |", row.join(" | "));
+    }
+    out
+}
+
+/// Returns `rows` as a JSON array, with an object on each line whose keys
+/// are `names`, in order.
+pub(crate) fn json<'a>(names: impl IntoIterator<Item = &'a str>, rows: &[Vec<Value>]) -> String {
+    let names = names
+        .into_iter()
+        .map(|name| serde_json::Value::from(name).to_string())
+        .collect::<Vec<_>>();
+    let mut out = String::from("[");
+    for (i, row) in rows.iter().enumerate() {
+        out.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+        for (j, (name, value)) in names.iter().zip(row).enumerate() {
+            if j > 0 {
+                out.push_str(", ");
+            }
+            let _ = write!(out, "{name}: {}", value.to_json());
+        }
+        out.push('}');
+    }
+    out.push_str(if rows.is_empty() { "]\n" } else { "\n]\n" });
+    out
+}
+
+/// Quotes `field` if it contains a comma, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn csv_quotes_fields() {
+        let rows = vec![
+            vec![
+                Value::Number(1.0),
+                Value::Text("worker, \"main\"".to_owned()),
+                Value::Duration(Duration::from_millis(1500)),
+            ],
+            vec![
+                Value::Number(2.0),
+                Value::Null,
+                Value::Duration(Duration::ZERO),
+            ],
+        ];
+        assert_eq!(
+            csv(["ID", "Name", "Total"], &rows),
+            "ID,Name,Total\r\n1,\"worker, \"\"main\"\"\",1.5\r\n2,,0\r\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let rows = vec![
+            vec![Value::Number(1.0), Value::Text("a | b".to_owned())],
+            vec![Value::Number(2.0), Value::Null],
+        ];
+        assert_eq!(
+            markdown(["ID", "Name"], &rows),
+            "| ID | Name |\n| --- | --- |\n| 1 | a \\| b |\n| 2 | - |\n"
+        );
+    }
+
+    #[test]
+    fn json_escapes_names() {
+        let rows = vec![vec![Value::Text("a".to_owned()), Value::Number(0.5)]];
+        assert_eq!(
+            json(["say \"hi\"", "Busy"], &rows),
+            "[\n  {\"say \\\"hi\\\"\": \"a\", \"Busy\": 0.5}\n]\n"
+        );
+        assert_eq!(json(["ID"], &[]), "[]\n");
+    }
+}
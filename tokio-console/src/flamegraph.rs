@@ -0,0 +1,166 @@
+//! Writing how long tasks spent busy, by where they were spawned, as the
+//! collapsed stacks which `inferno-flamegraph` and `flamegraph.pl` draw
+//! flamegraphs from, for the `tokio-console export-flamegraph` subcommand
+//! and the task list.
+use crate::{config::CollectArgs, report, state::State, view};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tonic::transport::Uri;
+
+/// Watches `target` for `duration`, and writes how long its tasks spent busy
+/// while it was watched to `output`, or to a new file in the current
+/// directory.
+pub(crate) async fn run(
+    target: Uri,
+    duration: humantime::Duration,
+    output: Option<&Path>,
+    styles: &view::Styles,
+) -> color_eyre::Result<()> {
+    let args = CollectArgs {
+        target_addr: None,
+        duration,
+        save: None,
+        baseline: None,
+    };
+    let report = report::collect(target, &args, &[], styles).await?;
+    let stacks = collapse(report.busy_by_location());
+    let path = match output {
+        Some(path) => {
+            fs::write(path, stacks)?;
+            path.to_owned()
+        }
+        None => save(&stacks)?,
+    };
+    println!("flamegraph: {}", path.display());
+    Ok(())
+}
+
+/// Returns the collapsed stacks for how long the tasks in `state` have spent
+/// busy, in total.
+pub(crate) fn from_state(state: &State) -> String {
+    let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+    let tasks = state
+        .tasks_state()
+        .tasks()
+        .filter_map(|task| task.upgrade())
+        .collect::<Vec<_>>();
+    let tasks = tasks.iter().map(|task| task.borrow()).collect::<Vec<_>>();
+    collapse(tasks.iter().map(|task| (task.location(), task.busy(now))))
+}
+
+/// Writes `stacks` to a new file in the current directory, returning its
+/// path.
+pub(crate) fn save(stacks: &str) -> color_eyre::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = PathBuf::from(format!("tokio-console-flamegraph-{secs}.folded"));
+    fs::write(&path, stacks)?;
+    Ok(path)
+}
+
+/// Returns a collapsed stack for each spawn location in `busy`, with the
+/// total busy time of the tasks spawned there, in microseconds.
+///
+/// Each stack is the module path of the file the tasks were spawned in,
+/// followed by the location itself, so that the busy time of every task
+/// spawned in a module, or in a crate, is added up in the flamegraph.
+pub(crate) fn collapse<'a>(busy: impl IntoIterator<Item = (&'a str, Duration)>) -> String {
+    let mut stacks = BTreeMap::<String, u128>::new();
+    for (location, busy) in busy {
+        *stacks.entry(stack(location)).or_default() += busy.as_micros();
+    }
+    let mut out = String::new();
+    for (stack, micros) in stacks {
+        // Locations which were never busy would be drawn with no width.
+        if micros > 0 {
+            let _ = writeln!(out, "{stack} {micros}");
+        }
+    }
+    out
+}
+
+/// Returns the frames for tasks spawned at `location`, such as
+/// `tokio-1.0.1;runtime;task;<cargo>/tokio-1.0.1/src/runtime/task.rs:12:5`,
+/// outermost first and separated by `;`.
+fn stack(location: &str) -> String {
+    // The line and column are only split off if they're numbers, since
+    // Windows paths have a colon after the drive letter.
+    let mut parts = location.rsplitn(3, ':');
+    let file = match (parts.next(), parts.next(), parts.next()) {
+        (Some(column), Some(line), Some(file))
+            if column.parse::<u32>().is_ok() && line.parse::<u32>().is_ok() =>
+        {
+            file
+        }
+        // Not a location in a file, such as an unknown location.
+        _ => return frame(location),
+    };
+    let components = file.split(['/', '\\']).collect::<Vec<_>>();
+    // The module path starts after the last `src` directory, under the
+    // crate's own directory.
+    let mut frames = match components.iter().rposition(|&dir| dir == "src") {
+        Some(src) => src
+            .checked_sub(1)
+            .map(|krate| components[krate])
+            .into_iter()
+            .chain(components[src + 1..].iter().copied())
+            .collect::<Vec<_>>(),
+        None => components,
+    };
+    if let Some(last) = frames.last_mut() {
+        *last = last.strip_suffix(".rs").unwrap_or(last);
+    }
+    // A `mod.rs`, `lib.rs` or `main.rs` is its parent module.
+    if frames.len() > 1 && matches!(frames.last(), Some(&("mod" | "lib" | "main"))) {
+        frames.pop();
+    }
+    frames.retain(|frame| !frame.is_empty());
+    frames.push(location);
+    frames.into_iter().map(frame).collect::<Vec<_>>().join(";")
+}
+
+/// Returns `name` as a frame. Frames are separated by `;`, so it can't appear
+/// in one.
+fn frame(name: &str) -> String {
+    name.replace(';', ",").trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_follow_module_paths() {
+        assert_eq!(
+            stack("<cargo>/tokio-1.0.1/src/runtime/task.rs:12:5"),
+            "tokio-1.0.1;runtime;task;<cargo>/tokio-1.0.1/src/runtime/task.rs:12:5"
+        );
+        assert_eq!(
+            stack("/home/user/app/src/server/mod.rs:40:9"),
+            "app;server;/home/user/app/src/server/mod.rs:40:9"
+        );
+        assert_eq!(stack("src/main.rs:3:1"), "main;src/main.rs:3:1");
+        assert_eq!(
+            stack(r"C:\app\src\lib.rs:7:2"),
+            r"app;C:\app\src\lib.rs:7:2"
+        );
+        assert_eq!(stack("<unknown location>"), "<unknown location>");
+    }
+
+    #[test]
+    fn collapse_adds_up_locations() {
+        let stacks = collapse([
+            ("src/a.rs:1:1", Duration::from_millis(2)),
+            ("src/b.rs:1:1", Duration::ZERO),
+            ("src/a.rs:1:1", Duration::from_micros(500)),
+        ]);
+        assert_eq!(stacks, "a;src/a.rs:1:1 2500\n");
+    }
+}
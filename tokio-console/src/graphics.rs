@@ -0,0 +1,422 @@
+//! Drawing charts as images, in terminals which support the kitty or iTerm2
+//! image protocols.
+//!
+//! Widgets [place](Graphics::place) images while a frame is rendered, leaving
+//! the cells beneath them blank, and the images are written to the terminal
+//! once the frame has been drawn.
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// How many pixels wide images are drawn per cell.
+///
+/// The terminal scales images to fit the cells they're placed in, so this
+/// only sets how fine the image's detail is.
+pub(crate) const CELL_WIDTH: u32 = 4;
+/// How many pixels tall images are drawn per cell.
+pub(crate) const CELL_HEIGHT: u32 = 8;
+
+/// How many base64 characters are sent in each kitty graphics command.
+const KITTY_CHUNK: usize = 4096;
+
+/// Which image protocol to draw charts with, as configured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsMode {
+    /// Use whichever protocol the terminal supports, if any.
+    #[default]
+    Auto,
+    Kitty,
+    Iterm2,
+    /// Always draw charts as text.
+    Off,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    Kitty,
+    Iterm2,
+}
+
+/// An RGBA image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+/// The images placed in the frame being rendered, and those which are on
+/// the screen.
+#[derive(Debug)]
+pub(crate) struct Graphics {
+    protocol: Protocol,
+    placed: RefCell<Vec<Placement>>,
+    shown: RefCell<Vec<Placement>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Placement {
+    area: Rect,
+    image: Image,
+}
+
+// === impl GraphicsMode ===
+
+impl GraphicsMode {
+    /// Returns the protocol to draw charts with, if any.
+    pub(crate) fn protocol(self) -> Option<Protocol> {
+        match self {
+            GraphicsMode::Auto => Protocol::detect(|name| std::env::var(name).ok()),
+            GraphicsMode::Kitty => Some(Protocol::Kitty),
+            GraphicsMode::Iterm2 => Some(Protocol::Iterm2),
+            GraphicsMode::Off => None,
+        }
+    }
+}
+
+impl FromStr for GraphicsMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("auto") => Ok(GraphicsMode::Auto),
+            s if s.eq_ignore_ascii_case("kitty") => Ok(GraphicsMode::Kitty),
+            s if s.eq_ignore_ascii_case("iterm2") => Ok(GraphicsMode::Iterm2),
+            s if s.eq_ignore_ascii_case("off") => Ok(GraphicsMode::Off),
+            _ => Err("invalid graphics mode"),
+        }
+    }
+}
+
+// === impl Protocol ===
+
+impl Protocol {
+    /// Detects which protocol the terminal supports from the environment.
+    ///
+    /// Terminal multiplexers don't pass images through, so no protocol is
+    /// used inside one.
+    fn detect(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if var("TMUX").is_some() || var("STY").is_some() {
+            return None;
+        }
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        if term.contains("kitty") || var("KITTY_WINDOW_ID").is_some() || program == "ghostty" {
+            return Some(Protocol::Kitty);
+        }
+        if program == "iTerm.app"
+            || program == "WezTerm"
+            || var("LC_TERMINAL").as_deref() == Some("iTerm2")
+        {
+            return Some(Protocol::Iterm2);
+        }
+        None
+    }
+
+    /// Writes `image`, scaled to fill `area`.
+    ///
+    /// `id` identifies the image's slot, so that a kitty terminal replaces
+    /// the image previously written to it.
+    fn write(self, out: &mut impl Write, id: usize, area: Rect, image: &Image) -> io::Result<()> {
+        // Save the cursor, and move it to the top left of the area.
+        write!(out, "\x1b7\x1b[{};{}H", area.y + 1, area.x + 1)?;
+        match self {
+            Protocol::Kitty => {
+                let data = base64(&image.rgba);
+                let mut chunks = data.as_bytes().chunks(KITTY_CHUNK).peekable();
+                let mut first = true;
+                while let Some(chunk) = chunks.next() {
+                    let more = u8::from(chunks.peek().is_some());
+                    if first {
+                        // Draw the image beneath text, so that popups drawn
+                        // over it are still visible, and don't move the
+                        // cursor.
+                        write!(
+                            out,
+                            "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={id},p=1,z=-1,C=1,q=2,m={more};",
+                            image.width, image.height, area.width, area.height,
+                        )?;
+                        first = false;
+                    } else {
+                        write!(out, "\x1b_Gm={more};")?;
+                    }
+                    out.write_all(chunk)?;
+                    out.write_all(b"\x1b\\")?;
+                }
+            }
+            Protocol::Iterm2 => {
+                let png = image.png();
+                write!(
+                    out,
+                    "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+                    png.len(),
+                    area.width,
+                    area.height,
+                    base64(&png),
+                )?;
+            }
+        }
+        out.write_all(b"\x1b8")
+    }
+}
+
+// === impl Image ===
+
+impl Image {
+    /// Returns a transparent image large enough to fill `area`.
+    pub(crate) fn for_area(area: Rect) -> Self {
+        let width = u32::from(area.width) * CELL_WIDTH;
+        let height = u32::from(area.height) * CELL_HEIGHT;
+        Self {
+            width,
+            height,
+            rgba: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub(crate) fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Fills the rectangle at `x` and `y` with `color`, clipped to the image.
+    pub(crate) fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let idx = ((row * self.width + column) * 4) as usize;
+                self.rgba[idx..idx + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Encodes the image as a PNG.
+    ///
+    /// The image data is stored without compression, since these images are
+    /// small, and are only sent to the terminal.
+    fn png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.rgba.len() + self.height as usize);
+        for row in self.rgba.chunks((self.width * 4).max(1) as usize) {
+            // No filter.
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            let last = u8::from(blocks.peek().is_none());
+            let len = block.len() as u16;
+            zlib.push(last);
+            zlib.extend_from_slice(&len.to_le_bytes());
+            zlib.extend_from_slice(&(!len).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bits per channel, RGBA, and the only compression, filtering and
+        // interlacing methods.
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, data) in [(b"IHDR", &header), (b"IDAT", &zlib), (b"IEND", &Vec::new())] {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            let crc = crc32(&png[start..]);
+            png.extend_from_slice(&crc.to_be_bytes());
+        }
+        png
+    }
+}
+
+// === impl Graphics ===
+
+impl Graphics {
+    pub(crate) fn new(protocol: Protocol) -> Self {
+        Self {
+            protocol,
+            placed: RefCell::new(Vec::new()),
+            shown: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Places `image` over `area` in the frame being rendered.
+    pub(crate) fn place(&self, area: Rect, image: Image) {
+        if area.is_empty() {
+            return;
+        }
+        self.placed.borrow_mut().push(Placement { area, image });
+    }
+
+    /// Writes the images placed since the last flush to the terminal, if
+    /// they changed.
+    ///
+    /// Returns `true` if the screen must be cleared and redrawn, to remove
+    /// images which the terminal can't delete, in which case nothing is
+    /// written and the images will be written after the next frame.
+    pub(crate) fn flush(&self, out: &mut impl Write) -> io::Result<bool> {
+        let placed = self.placed.take();
+        let mut shown = self.shown.borrow_mut();
+        if placed == *shown {
+            return Ok(false);
+        }
+
+        let moved = !shown.is_empty()
+            && (placed.len() != shown.len()
+                || placed
+                    .iter()
+                    .zip(shown.iter())
+                    .any(|(new, old)| new.area != old.area));
+        if moved {
+            match self.protocol {
+                Protocol::Kitty => out.write_all(b"\x1b_Ga=d,d=A,q=2\x1b\\")?,
+                // iTerm2 draws images into the cells beneath them, so they
+                // can only be removed by drawing over those cells.
+                Protocol::Iterm2 => {
+                    shown.clear();
+                    return Ok(true);
+                }
+            }
+        }
+        for (idx, placement) in placed.iter().enumerate() {
+            if !moved && shown.get(idx) == Some(placement) {
+                continue;
+            }
+            self.protocol
+                .write(out, idx + 1, placement.area, &placement.image)?;
+        }
+        out.flush()?;
+        *shown = placed;
+        Ok(false)
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn encodes_png() {
+        let mut image = Image::for_area(Rect::new(0, 0, 2, 1));
+        image.fill(0, 0, 1, 1, [255, 0, 0, 255]);
+        let png = image.png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn detects_protocol() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(
+            Protocol::detect(env(&[("TERM", "xterm-kitty")])),
+            Some(Protocol::Kitty)
+        );
+        assert_eq!(
+            Protocol::detect(env(&[("TERM_PROGRAM", "iTerm.app")])),
+            Some(Protocol::Iterm2)
+        );
+        assert_eq!(
+            Protocol::detect(env(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")])),
+            None
+        );
+        assert_eq!(Protocol::detect(env(&[("TERM", "xterm-256color")])), None);
+    }
+
+    #[test]
+    fn only_writes_changed_images() {
+        let graphics = Graphics::new(Protocol::Kitty);
+        let area = Rect::new(1, 1, 2, 1);
+        let mut out = Vec::new();
+        graphics.place(area, Image::for_area(area));
+        assert!(!graphics.flush(&mut out).unwrap());
+        assert!(!out.is_empty());
+
+        out.clear();
+        graphics.place(area, Image::for_area(area));
+        graphics.flush(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        // An iTerm2 image can't be removed without redrawing.
+        let graphics = Graphics::new(Protocol::Iterm2);
+        graphics.place(area, Image::for_area(area));
+        graphics.flush(&mut out).unwrap();
+        assert!(graphics.flush(&mut out).unwrap());
+    }
+}
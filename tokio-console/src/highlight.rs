@@ -0,0 +1,396 @@
+use crate::{
+    state::{expr::Expr, tasks::Task},
+    view::Styles,
+};
+use ratatui::style::{Color, Modifier, Style};
+use std::{fmt, str::FromStr, time::SystemTime};
+
+/// A rule which styles the rows of the tasks which match its condition in the
+/// task list.
+///
+/// Rules are written as `<condition> => <style>`, such as
+/// `busy > 500ms => red`, `idle > 1h and wakers == 0 => dim` or
+/// `polls_per_sec > 10k => bold yellow`. A condition compares [sort
+/// expressions](Expr) with numbers, and comparisons are combined with `and`
+/// (or `&&`) and `or` (or `||`), as in [task filters](crate::query::Filter),
+/// where `and` binds more tightly. Numbers may be durations, which
+/// are compared in seconds, or have a `k` or `M` suffix. A style is a list of
+/// colors and the modifiers `bold`, `dim`, `italic`, `underlined` and
+/// `reversed`.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Highlight {
+    /// The rule as it was written.
+    source: String,
+    condition: Condition,
+    color: Option<Color>,
+    modifiers: Modifier,
+}
+
+/// A condition over a task's statistics, such as `idle > 1h and wakers == 0`,
+/// which compares [sort expressions](Expr) with numbers.
+#[derive(Clone, Debug)]
+pub(crate) struct Condition {
+    /// The comparisons which must all hold for any one of the alternatives
+    /// for the condition to hold.
+    any: Vec<Vec<Comparison>>,
+}
+
+#[derive(Clone, Debug)]
+struct Comparison {
+    lhs: Expr,
+    op: CompareOp,
+    rhs: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+const MODIFIERS: &[(&str, Modifier)] = &[
+    ("bold", Modifier::BOLD),
+    ("dim", Modifier::DIM),
+    ("italic", Modifier::ITALIC),
+    ("underlined", Modifier::UNDERLINED),
+    ("reversed", Modifier::REVERSED),
+];
+
+// === impl Highlight ===
+
+impl Highlight {
+    /// Returns `true` if `task` matches the rule's condition at `now`.
+    pub(crate) fn matches(&self, task: &Task, now: SystemTime) -> bool {
+        self.condition.holds(task, now)
+    }
+
+    /// Returns the style of the rows the rule matches, with its color
+    /// adjusted to what the terminal supports.
+    pub(crate) fn style(&self, styles: &Styles) -> Style {
+        let style = match self.color {
+            Some(color) => styles.fg(color),
+            None => Style::default(),
+        };
+        style.add_modifier(self.modifiers)
+    }
+
+    /// Returns the combined style of each of `highlights` which matches
+    /// `task`, with later rules taking precedence over earlier ones.
+    pub(crate) fn style_task(
+        highlights: &[Highlight],
+        styles: &Styles,
+        task: &Task,
+        now: SystemTime,
+    ) -> Option<Style> {
+        highlights
+            .iter()
+            .filter(|highlight| highlight.matches(task, now))
+            .map(|highlight| highlight.style(styles))
+            .reduce(Style::patch)
+    }
+
+    /// Parses a list of rules separated by `;`, as they're typed into the
+    /// task list's highlight editor.
+    pub(crate) fn parse_list(s: &str) -> Result<Vec<Self>, ParseError> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for Highlight {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, style) = s.split_once("=>").ok_or_else(|| {
+            ParseError(format!(
+                "invalid highlight rule `{s}`, expected `<condition> => <style>`"
+            ))
+        })?;
+        let condition = condition.parse::<Condition>()?;
+
+        let mut color = None;
+        let mut modifiers = Modifier::empty();
+        for word in style.split_whitespace() {
+            if let Some(&(_, modifier)) = MODIFIERS.iter().find(|(name, _)| *name == word) {
+                modifiers |= modifier;
+            } else {
+                color = Some(word.parse::<Color>().map_err(|_| {
+                    let modifiers = MODIFIERS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ParseError(format!(
+                        "unknown style `{word}` in highlight rule, expected a color or one of: {modifiers}"
+                    ))
+                })?);
+            }
+        }
+        if color.is_none() && modifiers.is_empty() {
+            return Err(ParseError(format!(
+                "highlight rule `{s}` has no style after `=>`"
+            )));
+        }
+
+        Ok(Self {
+            source: s.trim().to_owned(),
+            condition,
+            color,
+            modifiers,
+        })
+    }
+}
+
+// Rules are compared by how they're written, since the parsed rule contains
+// floating-point numbers.
+impl PartialEq for Highlight {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Highlight {}
+
+impl fmt::Display for Highlight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Highlight {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Highlight> for String {
+    fn from(highlight: Highlight) -> Self {
+        highlight.source
+    }
+}
+
+// === impl Condition ===
+
+impl Condition {
+    /// Returns `true` if the condition holds for `task` at `now`.
+    pub(crate) fn holds(&self, task: &Task, now: SystemTime) -> bool {
+        self.any.iter().any(|all| {
+            all.iter()
+                .all(|comparison| comparison.holds(comparison.lhs.eval(task, now)))
+        })
+    }
+
+    /// Returns `true` if the condition holds when its expressions have
+    /// `values`, in the order [`Condition::exprs`] returns them, rather than
+    /// their values for a task.
+    pub(crate) fn holds_for(&self, values: &[Option<f64>]) -> bool {
+        let mut start = 0;
+        self.any.iter().any(|all| {
+            let end = start + all.len();
+            let Some(values) = values.get(start..end) else {
+                return false;
+            };
+            start = end;
+            all.iter()
+                .zip(values)
+                .all(|(comparison, &value)| comparison.holds(value))
+        })
+    }
+
+    /// Returns the expressions the condition compares, in the order they're
+    /// written.
+    pub(crate) fn exprs(&self) -> impl Iterator<Item = &Expr> {
+        self.any.iter().flatten().map(|comparison| &comparison.lhs)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let any = split_words(s, &["or", "||"])
+            .into_iter()
+            .map(|all| {
+                split_words(all, &["and", "&&"])
+                    .into_iter()
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { any })
+    }
+}
+
+// === impl Comparison ===
+
+impl Comparison {
+    /// Returns `true` if the comparison holds when its expression's value is
+    /// `lhs`. It doesn't if the value is unknown, such as a rate which hasn't
+    /// been measured yet.
+    fn holds(&self, lhs: Option<f64>) -> bool {
+        let Some(lhs) = lhs else {
+            return false;
+        };
+        match self.op {
+            CompareOp::Lt => lhs < self.rhs,
+            CompareOp::Le => lhs <= self.rhs,
+            CompareOp::Gt => lhs > self.rhs,
+            CompareOp::Ge => lhs >= self.rhs,
+            CompareOp::Eq => lhs == self.rhs,
+            CompareOp::Ne => lhs != self.rhs,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Two-character operators are tried first, so that `>=` isn't taken
+        // for `>`.
+        const OPS: &[(&str, CompareOp)] = &[
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+            ("=", CompareOp::Eq),
+        ];
+        let (lhs, op, rhs) = OPS
+            .iter()
+            .find_map(|&(token, op)| {
+                let (lhs, rhs) = s.split_once(token)?;
+                Some((lhs, op, rhs))
+            })
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "invalid comparison `{}`, expected `<expression> <op> <number>`",
+                    s.trim()
+                ))
+            })?;
+        let lhs = lhs.parse::<Expr>().map_err(|e| ParseError(e.to_string()))?;
+        Ok(Self {
+            lhs,
+            op,
+            rhs: parse_number(rhs.trim())?,
+        })
+    }
+}
+
+/// Parses a number to compare an expression with, which may be a duration in
+/// seconds, or have a `k` or `M` suffix.
+fn parse_number(s: &str) -> Result<f64, ParseError> {
+    let s = s.replace('_', "");
+    if let Ok(number) = s.parse::<f64>() {
+        return Ok(number);
+    }
+    for (suffix, scale) in [("k", 1e3), ("M", 1e6)] {
+        if let Some(number) = s.strip_suffix(suffix).and_then(|n| n.parse::<f64>().ok()) {
+            return Ok(number * scale);
+        }
+    }
+    s.parse::<humantime::Duration>()
+        .map(|duration| duration.as_secs_f64())
+        .map_err(|_| {
+            ParseError(format!(
+                "invalid number `{s}`, expected a number, such as `10k`, or a duration, such as `500ms`"
+            ))
+        })
+}
+
+/// Splits `s` at each occurrence of any of `words`.
+fn split_words<'a>(s: &'a str, words: &[&str]) -> Vec<&'a str> {
+    let separators = words
+        .iter()
+        .map(|word| format!(" {word} "))
+        .collect::<Vec<_>>();
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some((at, separator)) = separators
+        .iter()
+        .filter_map(|separator| Some((rest.find(separator.as_str())?, separator)))
+        .min()
+    {
+        parts.push(&rest[..at]);
+        rest = &rest[at + separator.len()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let rule = "idle > 1h and wakers == 0 or busy >= 500ms => bold yellow"
+            .parse::<Highlight>()
+            .unwrap();
+        assert_eq!(rule.condition.any.len(), 2);
+        assert_eq!(rule.condition.any[0].len(), 2);
+        assert_eq!(rule.condition.any[0][0].op, CompareOp::Gt);
+        assert_eq!(rule.condition.any[0][0].rhs, 3600.0);
+        assert_eq!(rule.condition.any[0][1].op, CompareOp::Eq);
+        assert_eq!(rule.condition.any[1][0].op, CompareOp::Ge);
+        assert_eq!(rule.condition.any[1][0].rhs, 0.5);
+        assert_eq!(rule.color, Some(Color::Yellow));
+        assert_eq!(rule.modifiers, Modifier::BOLD);
+
+        let rule = "busy > 1s && polls < 2 || wakes == 0 => red"
+            .parse::<Highlight>()
+            .unwrap();
+        assert_eq!(rule.condition.any.len(), 2);
+        assert_eq!(rule.condition.any[0].len(), 2);
+
+        let rule = "polls_per_sec > 10k => dim".parse::<Highlight>().unwrap();
+        assert_eq!(rule.condition.any[0][0].rhs, 10_000.0);
+        assert_eq!(rule.color, None);
+        assert_eq!(rule.to_string(), "polls_per_sec > 10k => dim");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("busy > 1s".parse::<Highlight>().is_err());
+        assert!("busy > 1s =>".parse::<Highlight>().is_err());
+        assert!("busy > 1s => sparkly".parse::<Highlight>().is_err());
+        assert!("busy => red".parse::<Highlight>().is_err());
+        assert!("latency > 1s => red".parse::<Highlight>().is_err());
+        assert!("busy > soon => red".parse::<Highlight>().is_err());
+    }
+
+    #[test]
+    fn parse_list() {
+        let rules = Highlight::parse_list("busy > 1s => red; ; polls < 2 => dim").unwrap();
+        assert_eq!(
+            rules.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["busy > 1s => red", "polls < 2 => dim"]
+        );
+        assert!(Highlight::parse_list("  ").unwrap().is_empty());
+        assert!(Highlight::parse_list("busy > 1s => red; nope").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! A minimal HTTP/1.1 server, for the subcommands which serve what they're
+//! watching over HTTP, such as `tokio-console export` and `tokio-console
+//! serve`.
+//!
+//! Each connection is answered with one response and closed, which is all
+//! that a metrics scraper, or a page polling for updates, needs.
+use std::{future::Future, io, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// How much of a request is read before it's answered.
+const MAX_REQUEST: u64 = 8 * 1024;
+
+/// How long a client has to send its request, and the server has to answer
+/// it.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A `GET` request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Request {
+    /// The requested path, without its query string.
+    pub(crate) path: String,
+    /// The query string, if there was one.
+    query: Option<String>,
+}
+
+/// A response to a request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Response {
+    pub(crate) status: &'static str,
+    content_type: &'static str,
+    pub(crate) body: String,
+}
+
+/// Answers each `GET` request to `listener` with `handle`, and every other
+/// request with an error. What's served, such as `metrics`, is named by
+/// `what` in logs.
+pub(crate) async fn serve<F, R>(listener: TcpListener, what: &'static str, handle: F)
+where
+    F: Fn(Request) -> R + Clone + Send + 'static,
+    R: Future<Output = Response> + Send,
+{
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                tracing::warn!(%error, "failed to accept a {what} connection");
+                continue;
+            }
+        };
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            let responded = tokio::time::timeout(REQUEST_TIMEOUT, respond(stream, handle)).await;
+            match responded {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => tracing::debug!(%error, %peer, "failed to serve {what}"),
+                Err(_) => tracing::debug!(%peer, "{what} request timed out"),
+            }
+        });
+    }
+}
+
+/// Reads an HTTP request from `stream`, and answers it.
+async fn respond<F, R>(mut stream: TcpStream, handle: F) -> io::Result<()>
+where
+    F: Fn(Request) -> R,
+    R: Future<Output = Response>,
+{
+    let mut request = BufReader::new((&mut stream).take(MAX_REQUEST));
+    let mut line = String::new();
+    request.read_line(&mut line).await?;
+    // The headers don't matter, but are read so that the client has finished
+    // sending the request when it's answered.
+    let mut header = String::new();
+    while request.read_line(&mut header).await? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let response = match Request::parse(&line) {
+        Some(request) => handle(request).await,
+        None => Response::new(
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n",
+        ),
+    };
+    stream.write_all(response.to_http().as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// === impl Request ===
+
+impl Request {
+    /// Parses the request line of a `GET` request, returning `None` for any
+    /// other method.
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != "GET" {
+            return None;
+        }
+        let target = parts.next().unwrap_or("/");
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_owned())),
+            None => (target, None),
+        };
+        Some(Self {
+            path: decode(path),
+            query,
+        })
+    }
+
+    /// Returns the value of the query parameter `name`, if it was given.
+    pub(crate) fn param(&self, name: &str) -> Option<String> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .filter_map(|param| param.split_once('=').or(Some((param, ""))))
+            .find(|&(param, _)| decode(param) == name)
+            .map(|(_, value)| decode(&value.replace('+', " ")))
+    }
+}
+
+/// Decodes the `%`-escapes in a part of a URL.
+fn decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(escaped) if byte == b'%' => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// === impl Response ===
+
+impl Response {
+    pub(crate) fn new(
+        status: &'static str,
+        content_type: &'static str,
+        body: impl Into<String>,
+    ) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub(crate) fn ok(content_type: &'static str, body: impl Into<String>) -> Self {
+        Self::new("200 OK", content_type, body)
+    }
+
+    pub(crate) fn not_found() -> Self {
+        Self::new("404 Not Found", "text/plain", "not found\n")
+    }
+
+    fn to_http(&self) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_requests() {
+        let request = Request::parse("GET /api/tasks?filter=busy+%3E+1s&all HTTP/1.1\r\n").unwrap();
+        assert_eq!(request.path, "/api/tasks");
+        assert_eq!(request.param("filter").as_deref(), Some("busy > 1s"));
+        assert_eq!(request.param("all").as_deref(), Some(""));
+        assert_eq!(request.param("limit"), None);
+
+        let request = Request::parse("GET /a%20b HTTP/1.1").unwrap();
+        assert_eq!(request.path, "/a b");
+        assert_eq!(request.param("filter"), None);
+
+        assert_eq!(Request::parse("POST /api/tasks HTTP/1.1"), None);
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%e2%9c%93"), "\u{2713}");
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(
+            Response::not_found().to_http(),
+            "HTTP/1.1 404 Not Found\r\n\
+             Content-Type: text/plain\r\n\
+             Content-Length: 10\r\n\
+             Connection: close\r\n\
+             \r\n\
+             not found\n"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Translating the console's text into the user's language.
+//!
+//! Text is looked up by its English form, so anything which hasn't been
+//! translated is shown in English.
+use std::{fmt, sync::OnceLock};
+
+/// A language which the console's text can be shown in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Lang {
+    En,
+    De,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Sets the language to show text in, from a locale such as `de_DE.UTF-8`.
+///
+/// Locales for languages without a translation, and the `C` and `POSIX`
+/// locales, are shown in English.
+pub(crate) fn init(locale: Option<&str>) {
+    let lang = locale.map_or(Lang::En, Lang::from_locale);
+    if LANG.set(lang).is_err() {
+        tracing::warn!(?lang, "the language was already set");
+    }
+}
+
+/// Returns `text` in the user's language.
+pub(crate) fn tr(text: &str) -> &str {
+    translate(current(), text).unwrap_or(text)
+}
+
+/// Returns `template` in the user's language, with each `{}` replaced by the
+/// next of `args`.
+pub(crate) fn tr_fmt(template: &str, args: &[&dyn fmt::Display]) -> String {
+    substitute(tr(template), args)
+}
+
+fn current() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+fn substitute(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+fn translate(lang: Lang, text: &str) -> Option<&'static str> {
+    let table = match lang {
+        Lang::En => return None,
+        Lang::De => DE,
+    };
+    table
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map(|&(_, translated)| translated)
+}
+
+// === impl Lang ===
+
+impl Lang {
+    fn from_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "de" => Lang::De,
+            _ => Lang::En,
+        }
+    }
+}
+
+const DE: &[(&str, &str)] = &[
+    // Controls.
+    ("controls", "Steuerung"),
+    ("or", "oder"),
+    ("keymap", "Tastenbelegung"),
+    ("acts as", "wirkt als"),
+    (
+        "abort task (press twice)",
+        "Task abbrechen (zweimal drücken)",
+    ),
+    ("annotate bookmark", "Lesezeichen kommentieren"),
+    ("clear completed tasks", "beendete Tasks entfernen"),
+    ("close tab", "Tab schließen"),
+    ("collapse/expand subtree", "Teilbaum ein-/ausklappen"),
+    ("connect to target", "mit Ziel verbinden"),
+    ("copy task details", "Taskdetails kopieren"),
+    ("cycle color theme", "Farbschema wechseln"),
+    ("diff with snapshot", "mit Schnappschuss vergleichen"),
+    ("disconnect", "trennen"),
+    ("dump task", "Task-Dump abrufen"),
+    ("edit highlight rules", "Hervorhebungsregeln bearbeiten"),
+    ("expand/collapse group", "Gruppe auf-/zuklappen"),
+    (
+        "export busy time as flamegraph",
+        "Busy-Zeit als Flamegraph exportieren",
+    ),
+    ("export histograms", "Histogramme exportieren"),
+    (
+        "filter event log by severity",
+        "Ereignisprotokoll nach Schwere filtern",
+    ),
+    ("filter tasks", "Tasks filtern"),
+    ("follow newest/top task", "neuestem/oberstem Task folgen"),
+    ("freeze display", "Anzeige einfrieren"),
+    (
+        "group tasks (name/target/location)",
+        "Tasks gruppieren (Name/Ziel/Ort)",
+    ),
+    (
+        "invert sort (highest/lowest)",
+        "Sortierung umkehren (höchste/niedrigste)",
+    ),
+    (
+        "keep completed tasks (configured/forever/never)",
+        "beendete Tasks behalten (konfiguriert/immer/nie)",
+    ),
+    ("move cursor", "Cursor bewegen"),
+    ("open in editor", "im Editor öffnen"),
+    ("open tab", "Tab öffnen"),
+    ("pin selected task", "ausgewählten Task anheften"),
+    ("quit", "beenden"),
+    ("reconnect now", "jetzt neu verbinden"),
+    ("remove bookmark", "Lesezeichen entfernen"),
+    ("reset stats", "Statistiken zurücksetzen"),
+    ("return to task list", "zurück zur Taskliste"),
+    ("save", "speichern"),
+    ("save screen", "Bildschirm speichern"),
+    ("scroll", "scrollen"),
+    (
+        "scroll fields left/right",
+        "Felder nach links/rechts scrollen",
+    ),
+    ("scroll half a page", "eine halbe Seite scrollen"),
+    ("scroll to bottom", "ans Ende scrollen"),
+    ("scroll to top", "an den Anfang scrollen"),
+    (
+        "select column (keep sort)",
+        "Spalte wählen (Sortierung behalten)",
+    ),
+    ("select column (sort)", "Spalte wählen (sortieren)"),
+    ("show all fields", "alle Felder anzeigen"),
+    ("show task tree", "Taskbaum anzeigen"),
+    (
+        "sort by busy/polls/wakers",
+        "nach Aktiv/Polls/Wakern sortieren",
+    ),
+    ("step (when paused)", "Schritt (wenn pausiert)"),
+    ("stop sorting by column", "nicht mehr nach Spalte sortieren"),
+    ("switch tab", "Tab wechseln"),
+    ("take snapshot", "Schnappschuss aufnehmen"),
+    ("then sort by column", "danach nach Spalte sortieren"),
+    ("toggle bookmark", "Lesezeichen umschalten"),
+    ("toggle event log", "Ereignisprotokoll umschalten"),
+    ("toggle pause", "Pause umschalten"),
+    ("toggle recent stats", "aktuelle Statistiken umschalten"),
+    ("view details", "Details anzeigen"),
+    ("views", "Ansichten"),
+    ("zoom in/out", "vergrößern/verkleinern"),
+    ("tasks", "Tasks"),
+    ("resources", "Ressourcen"),
+    ("network", "Netzwerk"),
+    ("bookmarks", "Lesezeichen"),
+    ("crashed", "abgestürzt"),
+    ("timeline", "Zeitleiste"),
+    ("diff", "Vergleich"),
+    // Table headers. Some columns are only as wide as their English header,
+    // or their values, so these must fit in the same width.
+    ("Mark", "Lz"),
+    ("State", "Status"),
+    ("Total", "Ges."),
+    ("Busy", "Aktiv"),
+    ("Sched", "Gepl."),
+    ("Idle", "Ruht"),
+    ("Kind", "Art"),
+    ("Location", "Ort"),
+    ("Fields", "Felder"),
+    ("Parent", "Eltern"),
+    ("Target", "Ziel"),
+    ("Type", "Typ"),
+    ("Attributes", "Attribute"),
+    ("Source", "Quelle"),
+    ("Note", "Notiz"),
+    ("Panicked At", "Panik bei"),
+    ("Message", "Meldung"),
+    ("Local", "Lokal"),
+    ("Oldest", "Erste"),
+    ("Newest", "Letzte"),
+    ("Change", "Änd."),
+    ("Group", "Gruppe"),
+    ("Mean Poll", "Ø Poll"),
+    ("Max Idle", "Max. Ruht"),
+    ("new", "neu"),
+    ("ended", "beendet"),
+    ("gone", "weg"),
+    // Titles.
+    ("Warnings", "Warnungen"),
+    (
+        "Changes since {} ({}s ago) ({}) ",
+        "Änderungen seit {} (vor {}s) ({}) ",
+    ),
+    (
+        "No snapshot taken, press m to take one ",
+        "Kein Schnappschuss, m drücken, um einen aufzunehmen ",
+    ),
+    // The summary above the task list.
+    (
+        "{} running, {} idle, {} completed",
+        "{} laufend, {} ruhend, {} beendet",
+    ),
+    ("Busy: ", "Aktiv: "),
+    ("Resources: ", "Ressourcen: "),
+    ("Dropped Events: ", "Verworfene Ereignisse: "),
+    ("Last Update: ", "Letztes Update: "),
+    ("{}s ago", "vor {}s"),
+    ("Tasks ({}) ", "Tasks ({}) "),
+    (" Running ({}) ", " Laufend ({}) "),
+    (" Idle ({})", " Ruhend ({})"),
+    // Warnings.
+    (
+        "tasks have woken themselves over {}% of the time",
+        "Tasks haben sich in über {}% der Fälle selbst geweckt",
+    ),
+    (
+        "This task has woken itself for more than {}% of its total wakeups ({}%)",
+        "Dieser Task hat sich bei mehr als {}% seiner Weckrufe selbst geweckt ({}%)",
+    ),
+    (
+        "tasks have lost their wakers",
+        "Tasks haben ihre Waker verloren",
+    ),
+    (
+        "This task has lost its waker, and will never be woken again.",
+        "Dieser Task hat seinen Waker verloren und wird nie wieder geweckt.",
+    ),
+    (
+        "tasks have never yielded (threshold {}ms)",
+        "Tasks haben nie die Kontrolle abgegeben (Schwelle {}ms)",
+    ),
+    (
+        "This task has never yielded ({})",
+        "Dieser Task hat nie die Kontrolle abgegeben ({})",
+    ),
+    (
+        "tasks have been busy without yielding (threshold {}ms)",
+        "Tasks waren beschäftigt, ohne die Kontrolle abzugeben (Schwelle {}ms)",
+    ),
+    (
+        "This task was busy for {} without yielding",
+        "Dieser Task war {} lang beschäftigt, ohne die Kontrolle abzugeben",
+    ),
+    (
+        "tasks have been boxed by the runtime due to their size",
+        "Tasks wurden wegen ihrer Größe von der Runtime geboxt",
+    ),
+    (
+        "This task's future was auto-boxed by the runtime when spawning, due to its size \
+        (originally {} bytes, boxed size {} bytes)",
+        "Das Future dieses Tasks wurde beim Starten wegen seiner Größe von der Runtime \
+        automatisch geboxt (ursprünglich {} Bytes, geboxt {} Bytes)",
+    ),
+    (
+        "tasks are {} bytes or larger",
+        "Tasks sind {} Bytes oder größer",
+    ),
+    (
+        "This task occupies a large amount of stack space ({} bytes)",
+        "Dieser Task belegt viel Stack-Speicher ({} Bytes)",
+    ),
+    (
+        "{} have risen steadily for {}, from {} to {}",
+        "{} sind seit {} stetig gestiegen, von {} auf {}",
+    ),
+    ("live tasks", "Aktive Tasks"),
+    ("idle tasks with no wakers", "Ruhende Tasks ohne Waker"),
+    ("live resources", "Aktive Ressourcen"),
+    // The event log.
+    (
+        "Events ({} and above, ! to filter)",
+        "Ereignisse ({} und höher, ! zum Filtern)",
+    ),
+    ("task {} spawned", "Task {} gestartet"),
+    (
+        "task {} woke itself {} times",
+        "Task {} hat sich {}-mal selbst geweckt",
+    ),
+    ("task {} {}", "Task {} {}"),
+    ("completed", "beendet"),
+    ("cancelled", "abgebrochen"),
+    ("panicked", "abgestürzt"),
+    ("resource {} created", "Ressource {} erstellt"),
+    ("resource {} dropped", "Ressource {} freigegeben"),
+    (
+        "lost the connection to the target",
+        "Verbindung zum Ziel verloren",
+    ),
+    (
+        "restored the connection to the target",
+        "Verbindung zum Ziel wiederhergestellt",
+    ),
+    // Alerts.
+    ("ALERT", "ALARM"),
+    ("and {} more", "und {} weitere"),
+    ("press any key to dismiss", "beliebige Taste zum Schließen"),
+    (
+        "alert `{}` triggered by task {}",
+        "Alarm `{}` ausgelöst von Task {}",
+    ),
+    (
+        "alert `{}` triggered by task {} and {} other tasks",
+        "Alarm `{}` ausgelöst von Task {} und {} weiteren Tasks",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lang_from_locale() {
+        assert_eq!(Lang::from_locale("de_DE.UTF-8"), Lang::De);
+        assert_eq!(Lang::from_locale("de"), Lang::De);
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("C"), Lang::En);
+        assert_eq!(Lang::from_locale(""), Lang::En);
+    }
+
+    #[test]
+    fn translations_keep_placeholders() {
+        for (english, translated) in DE {
+            assert_eq!(
+                english.matches("{}").count(),
+                translated.matches("{}").count(),
+                "translation of {english:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn untranslated_text_is_english() {
+        assert_eq!(translate(Lang::De, "quit"), Some("beenden"));
+        assert_eq!(translate(Lang::De, "not translated"), None);
+        assert_eq!(translate(Lang::En, "quit"), None);
+    }
+
+    #[test]
+    fn substitutes_args() {
+        assert_eq!(
+            substitute("{} sind {} Bytes", &[&"Tasks", &1024]),
+            "Tasks sind 1024 Bytes"
+        );
+    }
+}
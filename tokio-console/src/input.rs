@@ -15,6 +15,19 @@ pub fn should_ignore_key_event(input: &Event) -> bool {
     )
 }
 
+/// The mouse moving, or being dragged, is reported continually, but the
+/// console only handles clicks and scrolling, so these events are ignored
+/// rather than redrawing for each of them.
+pub(crate) fn should_ignore_mouse_event(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved | MouseEventKind::Drag(_) | MouseEventKind::Up(_),
+            ..
+        })
+    )
+}
+
 pub fn should_quit(input: &Event) -> bool {
     use Event::*;
     use KeyCode::*;
@@ -41,11 +54,146 @@ pub(crate) fn is_space(input: &Event) -> bool {
         input,
         Event::Key(KeyEvent {
             code: KeyCode::Char(' '),
+            modifiers,
+            ..
+        }) if !modifiers.contains(KeyModifiers::CONTROL)
+    )
+}
+
+pub(crate) fn is_step(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
             ..
         })
     )
 }
 
+pub(crate) fn is_reconnect(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('R'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_disconnect(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('D'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_clear_completed(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('X'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_cycle_theme(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('M'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_cycle_retention(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('K'),
+            ..
+        })
+    )
+}
+
+/// Freezing is also bound to `ctrl-space`, next to space pausing the target,
+/// since both stop the display from changing.
+pub(crate) fn is_freeze(input: &Event) -> bool {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('f'),
+            ..
+        }) => true,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(' '),
+            modifiers,
+            ..
+        }) => modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+pub(crate) fn is_save_screen(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('S'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_new_tab(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('N'),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_close_tab(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('W'),
+            ..
+        })
+    )
+}
+
+/// Returns the index of the tab to switch to, if a number key was pressed.
+pub(crate) fn tab_index(input: &Event) -> Option<usize> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c @ '1'..='9'),
+            ..
+        }) => c.to_digit(10).map(|digit| digit as usize - 1),
+        _ => None,
+    }
+}
+
+/// Returns whether to seek through a recording being replayed, and whether
+/// to seek backwards if so.
+pub(crate) fn seek_back(input: &Event) -> Option<bool> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('<'),
+            ..
+        }) => Some(true),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('>'),
+            ..
+        }) => Some(false),
+        _ => None,
+    }
+}
+
 pub(crate) fn is_help_toggle(event: &Event) -> bool {
     matches!(
         event,
@@ -70,6 +218,23 @@ pub(crate) fn is_esc(event: &Event) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn ctrl_space_freezes_rather_than_pausing() {
+        let space = |modifiers| {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(' '),
+                modifiers,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::empty(),
+            })
+        };
+
+        assert!(is_space(&space(KeyModifiers::empty())));
+        assert!(!is_freeze(&space(KeyModifiers::empty())));
+        assert!(is_freeze(&space(KeyModifiers::CONTROL)));
+        assert!(!is_space(&space(KeyModifiers::CONTROL)));
+    }
+
     #[test]
     fn ignore_key_repeat_and_release_events() {
         let event = Event::Key(KeyEvent {
@@ -99,4 +264,14 @@ mod tests {
 
         assert!(should_ignore_key_event(&event));
     }
+
+    #[test]
+    fn number_keys_switch_tabs() {
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+
+        assert_eq!(tab_index(&key(KeyCode::Char('1'))), Some(0));
+        assert_eq!(tab_index(&key(KeyCode::Char('9'))), Some(8));
+        assert_eq!(tab_index(&key(KeyCode::Char('0'))), None);
+        assert_eq!(tab_index(&key(KeyCode::Char('a'))), None);
+    }
 }
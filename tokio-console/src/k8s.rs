@@ -0,0 +1,323 @@
+//! Connecting to targets running in Kubernetes pods.
+//!
+//! A target written as `k8s://namespace/pod[:port]` is connected to through
+//! a `kubectl port-forward` to the pod, which the console starts, and stops
+//! when it exits, so that it uses the same cluster and credentials as
+//! `kubectl` does.
+use crate::conn::DEFAULT_PORT;
+use serde::Deserialize;
+use std::{collections::HashMap, error::Error, process::Stdio, sync::Mutex, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    process::{Child, Command},
+};
+use tonic::transport::Uri;
+
+/// The annotation which pods set to the port console-subscriber listens on,
+/// so that they're listed in the connect dialog.
+pub(crate) const PORT_ANNOTATION: &str = "console.tokio.rs/port";
+
+/// How long to wait for `kubectl` to start forwarding a port.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The port-forwards which are running, by the target they forward to, so
+/// that reconnecting to a target reuses its port-forward.
+static PORT_FORWARDS: Mutex<Option<HashMap<Uri, PortForward>>> = Mutex::new(None);
+
+/// A running `kubectl port-forward`.
+struct PortForward {
+    child: Child,
+    /// The local address the pod's port is forwarded to.
+    local: Uri,
+}
+
+/// A pod's console port, parsed from a target's address.
+#[derive(Debug, PartialEq, Eq)]
+struct Pod<'a> {
+    namespace: &'a str,
+    name: &'a str,
+    port: u16,
+}
+
+/// Stops every port-forward when it's dropped.
+///
+/// This is held by `main`, so that port-forwards don't outlive the console.
+pub(crate) struct StopPortForwards;
+
+/// Returns the local address to connect to `target`, which is a `k8s://`
+/// address, starting a port-forward to it if one isn't already running.
+pub(crate) async fn forward(target: &Uri) -> Result<Uri, Box<dyn Error + Send + Sync>> {
+    {
+        let mut forwards = PORT_FORWARDS.lock().unwrap();
+        let forwards = forwards.get_or_insert_with(HashMap::new);
+        if let Some(forward) = forwards.get_mut(target) {
+            // If `kubectl` exited, such as because the pod was restarted,
+            // forward to the pod again.
+            if matches!(forward.child.try_wait(), Ok(None)) {
+                return Ok(forward.local.clone());
+            }
+            forwards.remove(target);
+        }
+    }
+
+    let pod = Pod::parse(target)?;
+    let forward = tokio::time::timeout(FORWARD_TIMEOUT, pod.forward())
+        .await
+        .map_err(|_| format!("timed out waiting for kubectl to forward to {target}"))??;
+    let local = forward.local.clone();
+    tracing::info!(%target, %local, "forwarding to pod");
+    PORT_FORWARDS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(target.clone(), forward);
+    Ok(local)
+}
+
+/// Returns the pods in every namespace which have the console port
+/// annotation, as `(namespace/pod, address)` pairs.
+///
+/// This returns nothing if `kubectl` isn't installed or can't reach a
+/// cluster, since listing pods is only a convenience.
+pub(crate) fn discover() -> Vec<(String, String)> {
+    let output = std::process::Command::new("kubectl")
+        .args([
+            "get",
+            "pods",
+            "--all-namespaces",
+            "--field-selector=status.phase=Running",
+            "--request-timeout=2s",
+            "--output=json",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let pods = match output {
+        Ok(output) if output.status.success() => output.stdout,
+        Ok(output) => {
+            tracing::debug!(status = %output.status, "could not list pods");
+            return Vec::new();
+        }
+        Err(error) => {
+            tracing::debug!(%error, "could not run kubectl");
+            return Vec::new();
+        }
+    };
+    match serde_json::from_slice::<PodList>(&pods) {
+        Ok(pods) => annotated_pods(pods),
+        Err(error) => {
+            tracing::debug!(%error, "could not parse kubectl's list of pods");
+            Vec::new()
+        }
+    }
+}
+
+/// The parts of `kubectl get pods --output=json` which are used.
+#[derive(Deserialize)]
+struct PodList {
+    items: Vec<PodItem>,
+}
+
+#[derive(Deserialize)]
+struct PodItem {
+    metadata: PodMetadata,
+}
+
+#[derive(Deserialize)]
+struct PodMetadata {
+    name: String,
+    namespace: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+}
+
+fn annotated_pods(pods: PodList) -> Vec<(String, String)> {
+    let mut pods = pods
+        .items
+        .into_iter()
+        .filter_map(|pod| {
+            let PodMetadata {
+                name,
+                namespace,
+                annotations,
+            } = pod.metadata;
+            let port = annotations
+                .get(PORT_ANNOTATION)?
+                .trim()
+                .parse::<u16>()
+                .ok()?;
+            Some((
+                format!("{namespace}/{name}"),
+                format!("k8s://{namespace}/{name}:{port}"),
+            ))
+        })
+        .collect::<Vec<_>>();
+    pods.sort();
+    pods
+}
+
+// === impl Pod ===
+
+impl<'a> Pod<'a> {
+    fn parse(target: &'a Uri) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let namespace = target
+            .host()
+            .filter(|namespace| !namespace.is_empty())
+            .ok_or("a Kubernetes target must be written as k8s://namespace/pod[:port]")?;
+        let pod = target.path().trim_start_matches('/');
+        let (name, port) = match pod.rsplit_once(':') {
+            Some((name, port)) => (
+                name,
+                port.parse()
+                    .map_err(|_| format!("invalid port {port:?} for pod {name}"))?,
+            ),
+            None => (pod, DEFAULT_PORT),
+        };
+        if name.is_empty() || name.contains('/') {
+            return Err("a Kubernetes target must be written as k8s://namespace/pod[:port]".into());
+        }
+        Ok(Self {
+            namespace,
+            name,
+            port,
+        })
+    }
+
+    /// Starts `kubectl port-forward` to the pod, on a port it picks, and
+    /// waits for it to report the port.
+    async fn forward(&self) -> Result<PortForward, Box<dyn Error + Send + Sync>> {
+        let mut child = Command::new("kubectl")
+            .arg("port-forward")
+            .arg(format!("--namespace={}", self.namespace))
+            .arg(format!("pod/{}", self.name))
+            .arg(format!(":{}", self.port))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|error| format!("could not run kubectl: {error}"))?;
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        while let Some(line) = stdout.next_line().await? {
+            let Some(port) = forwarded_port(&line) else {
+                continue;
+            };
+            // `kubectl` prints a line for each connection it forwards, and
+            // for each error forwarding one, which must be read so that it
+            // doesn't block writing them.
+            tokio::spawn(async move {
+                while let Ok(Some(line)) = stdout.next_line().await {
+                    tracing::trace!(line, "kubectl port-forward");
+                }
+            });
+            if let Some(stderr) = child.stderr.take() {
+                let mut stderr = BufReader::new(stderr).lines();
+                tokio::spawn(async move {
+                    while let Ok(Some(line)) = stderr.next_line().await {
+                        tracing::warn!(line, "kubectl port-forward");
+                    }
+                });
+            }
+            return Ok(PortForward {
+                child,
+                local: format!("http://127.0.0.1:{port}").parse()?,
+            });
+        }
+
+        // `kubectl` exited without forwarding, so report why.
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            pipe.read_to_string(&mut stderr).await?;
+        }
+        let status = child.wait().await?;
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            Err(format!("kubectl port-forward exited with {status}").into())
+        } else {
+            Err(format!("kubectl port-forward failed: {stderr}").into())
+        }
+    }
+}
+
+/// Returns the local port from a line of `kubectl port-forward`'s output
+/// such as `Forwarding from 127.0.0.1:41235 -> 6669`.
+fn forwarded_port(line: &str) -> Option<u16> {
+    let addr = line
+        .strip_prefix("Forwarding from 127.0.0.1:")?
+        .split_whitespace()
+        .next()?;
+    addr.parse().ok()
+}
+
+// === impl StopPortForwards ===
+
+impl Drop for StopPortForwards {
+    fn drop(&mut self) {
+        let forwards = PORT_FORWARDS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        for (_, mut forward) in forwards.into_iter().flatten() {
+            let _ = forward.child.start_kill();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pods() {
+        let target = "k8s://prod/api-7d9f:7000".parse::<Uri>().unwrap();
+        assert_eq!(
+            Pod::parse(&target).unwrap(),
+            Pod {
+                namespace: "prod",
+                name: "api-7d9f",
+                port: 7000,
+            }
+        );
+        let target = "k8s://prod/api-7d9f".parse::<Uri>().unwrap();
+        assert_eq!(Pod::parse(&target).unwrap().port, DEFAULT_PORT);
+        let target = "k8s://prod".parse::<Uri>().unwrap();
+        assert!(Pod::parse(&target).is_err());
+    }
+
+    #[test]
+    fn parses_forwarded_port() {
+        assert_eq!(
+            forwarded_port("Forwarding from 127.0.0.1:41235 -> 6669"),
+            Some(41235)
+        );
+        assert_eq!(forwarded_port("Forwarding from [::1]:41235 -> 6669"), None);
+        assert_eq!(forwarded_port("Handling connection for 41235"), None);
+    }
+
+    #[test]
+    fn lists_annotated_pods() {
+        let pods = serde_json::from_str(
+            r#"{"items": [
+                {"metadata": {"name": "web-1", "namespace": "prod"}},
+                {"metadata": {
+                    "name": "api-2",
+                    "namespace": "prod",
+                    "annotations": {"console.tokio.rs/port": "6669"}
+                }},
+                {"metadata": {
+                    "name": "api-1",
+                    "namespace": "prod",
+                    "annotations": {"console.tokio.rs/port": "7000"}
+                }}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            annotated_pods(pods),
+            [
+                ("prod/api-1".to_owned(), "k8s://prod/api-1:7000".to_owned()),
+                ("prod/api-2".to_owned(), "k8s://prod/api-2:6669".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::input::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::{fmt, str::FromStr};
+
+/// A key, and the modifiers held while pressing it, such as `ctrl-d`, `J` or
+/// `pagedown`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+/// Which keys act as other keys, from the `[keymap]` table of the config
+/// file, in addition to the built-in vi-style bindings.
+///
+/// Keys are translated before anything else handles them, so a key can be
+/// bound to any key which the console handles, in every view.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Keymap {
+    /// Each key, and the key it acts as, in the order they were bound.
+    bindings: Vec<(Key, Key)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+const MODIFIERS: &[(&str, KeyModifiers)] = &[
+    ("ctrl-", KeyModifiers::CONTROL),
+    ("alt-", KeyModifiers::ALT),
+    ("shift-", KeyModifiers::SHIFT),
+];
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("space", KeyCode::Char(' ')),
+];
+
+// === impl Key ===
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Returns `true` if `event` is this key being pressed.
+    ///
+    /// Whether shift is held is ignored for characters, since it's already
+    /// reflected in the character's case.
+    fn matches(&self, event: &KeyEvent) -> bool {
+        let ignored = match self.code {
+            KeyCode::Char(_) => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        event.code == self.code
+            && event.modifiers.difference(ignored) == self.modifiers.difference(ignored)
+    }
+}
+
+impl FromStr for Key {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // A key which is itself `-` follows its modifiers, as in `ctrl--`.
+        while let Some(&(prefix, modifier)) = MODIFIERS
+            .iter()
+            .find(|(prefix, _)| rest.len() > prefix.len() && rest.starts_with(prefix))
+        {
+            modifiers |= modifier;
+            rest = &rest[prefix.len()..];
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+                .map(|&(_, code)| code)
+                .ok_or_else(|| ParseError(format!("unknown key `{s}`")))?,
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (prefix, modifier) in MODIFIERS {
+            if self.modifiers.contains(*modifier) {
+                f.write_str(prefix)?;
+            }
+        }
+        match NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => f.write_str(name),
+            None => match self.code {
+                KeyCode::Char(c) => write!(f, "{c}"),
+                code => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+// === impl Keymap ===
+
+impl Keymap {
+    /// The vi-style bindings, which apply unless the same keys are bound in
+    /// the keymap.
+    const DEFAULT_BINDINGS: &'static [(Key, Key)] = &[
+        (
+            Key::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            Key::new(KeyCode::PageDown, KeyModifiers::NONE),
+        ),
+        (
+            Key::new(KeyCode::Char('u'), KeyModifiers::CONTROL),
+            Key::new(KeyCode::PageUp, KeyModifiers::NONE),
+        ),
+    ];
+
+    /// Makes `key` act as `to`, replacing any previous binding of `key`.
+    pub(crate) fn bind(&mut self, key: Key, to: Key) {
+        self.bindings.retain(|(bound, _)| *bound != key);
+        self.bindings.push((key, to));
+    }
+
+    /// Returns each key in the keymap, and the key it acts as.
+    pub(crate) fn bindings(&self) -> impl Iterator<Item = (Key, Key)> + '_ {
+        self.bindings.iter().copied()
+    }
+
+    /// Returns each key which acts as `to`, including the vi-style bindings
+    /// which aren't overridden by the keymap.
+    pub(crate) fn keys_acting_as(&self, to: Key) -> impl Iterator<Item = Key> + '_ {
+        let defaults = Self::DEFAULT_BINDINGS
+            .iter()
+            .filter(|(key, _)| !self.bindings.iter().any(|(bound, _)| bound == key));
+        self.bindings
+            .iter()
+            .chain(defaults)
+            .filter(move |(_, bound_to)| *bound_to == to)
+            .map(|&(key, _)| key)
+    }
+
+    /// Returns the event of pressing the key which `event`'s key is bound to,
+    /// or `event` if it isn't bound to another key.
+    pub(crate) fn translate(&self, event: Event) -> Event {
+        let Event::Key(key) = event else {
+            return event;
+        };
+        let bound = self
+            .bindings
+            .iter()
+            .chain(Self::DEFAULT_BINDINGS)
+            .find(|(bound, _)| bound.matches(&key));
+        match bound {
+            Some((_, to)) => Event::Key(KeyEvent {
+                code: to.code,
+                modifiers: to.modifiers,
+                ..key
+            }),
+            None => event,
+        }
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn parse_keys() {
+        for s in [
+            "j",
+            "G",
+            "ctrl-d",
+            "ctrl-alt-x",
+            "shift-tab",
+            "pagedown",
+            "space",
+            "ctrl--",
+        ] {
+            let key = s.parse::<Key>().unwrap();
+            assert_eq!(key.to_string(), s);
+        }
+        assert_eq!(
+            "ctrl-u".parse::<Key>().unwrap(),
+            Key::new(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        );
+        assert!("".parse::<Key>().is_err());
+        assert!("ctrl-nope".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn translate_bound_keys() {
+        let mut keymap = Keymap::default();
+        keymap.bind("J".parse().unwrap(), "down".parse().unwrap());
+        keymap.bind("ctrl-d".parse().unwrap(), "q".parse().unwrap());
+
+        // Shift is implied by an uppercase character.
+        assert_eq!(
+            keymap.translate(key(KeyCode::Char('J'), KeyModifiers::SHIFT)),
+            key(KeyCode::Down, KeyModifiers::NONE)
+        );
+        // Binding a key overrides its default binding.
+        assert_eq!(
+            keymap.translate(key(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            key(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            keymap.translate(key(KeyCode::Char('u'), KeyModifiers::CONTROL)),
+            key(KeyCode::PageUp, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            keymap.translate(key(KeyCode::Char('u'), KeyModifiers::NONE)),
+            key(KeyCode::Char('u'), KeyModifiers::NONE)
+        );
+    }
+
+    #[test]
+    fn keys_acting_as_bound_keys() {
+        let mut keymap = Keymap::default();
+        keymap.bind("J".parse().unwrap(), "pagedown".parse().unwrap());
+        keymap.bind("ctrl-u".parse().unwrap(), "q".parse().unwrap());
+
+        let keys = |to: &str| {
+            keymap
+                .keys_acting_as(to.parse().unwrap())
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys("pagedown"), ["J", "ctrl-d"]);
+        // `ctrl-u` no longer acts as `pageup` once it's bound to another key.
+        assert!(keys("pageup").is_empty());
+        assert_eq!(keys("q"), ["ctrl-u"]);
+    }
+}
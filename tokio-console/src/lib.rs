@@ -37,6 +37,57 @@
 #[doc = include_str!("../console.example.toml")]
 /// ```
 ///
+/// ### Named Targets
+///
+/// The `[targets]` table gives names to target addresses, so that a target
+/// can be connected to by name rather than by its address:
+///
+/// ```toml
+/// [targets]
+/// prod-gateway = "http://10.0.3.4:6669"
+/// ```
+///
+/// With this configuration, `tokio-console prod-gateway` connects to
+/// `http://10.0.3.4:6669`, and the console's header shows the target's name.
+///
+/// ### Key Bindings
+///
+/// The `[keymap]` table makes keys act as other keys, in every view. Keys are
+/// written as a character, or as `up`, `down`, `left`, `right`, `pageup`,
+/// `pagedown`, `home`, `end`, `enter`, `esc`, `tab`, `backspace`, `delete` or
+/// `space`, optionally after any of `ctrl-`, `alt-` and `shift-`:
+///
+/// ```toml
+/// [keymap]
+/// ctrl-n = "down"
+/// ctrl-p = "up"
+/// ```
+///
+/// `ctrl-d` and `ctrl-u` scroll by half a page, like in vi, unless they're
+/// bound to other keys. Keys aren't translated while text is being typed,
+/// such as a search. The help popup, shown with `?`, lists each bound key
+/// alongside the controls of the key it acts as.
+///
+/// ### Color Themes
+///
+/// The console is drawn with the `dark` theme by default. The `light` theme
+/// uses darker colors, for terminals with light backgrounds, and the
+/// `high-contrast` and `monochrome` themes don't use colors at all. `M`
+/// switches between them while the console is running.
+///
+/// The `[colors.custom]` table replaces any of the colors the console uses
+/// with colors of your own, written as names such as `yellow`, 256-color
+/// indexes such as `136`, or RGB colors such as `#d7af00`:
+///
+/// ```toml
+/// [colors]
+/// theme = "light"
+///
+/// [colors.custom]
+/// yellow = "#d7af00"
+/// cyan = "blue"
+/// ```
+///
 /// ### Config File Locations
 ///
 /// Configuration files are read from two locations:
@@ -0,0 +1,50 @@
+//! The `tokio-console lint` subcommand.
+use crate::{
+    baseline,
+    config::{CollectArgs, KnownWarnings},
+    report, view,
+};
+use color_eyre::eyre::bail;
+use tonic::transport::Uri;
+
+/// Watches `target` as configured by `args`, checking every update it sends
+/// against `warnings`, and prints each task which triggered one.
+///
+/// Returns an error if any warning was triggered, if the target regressed
+/// since the baseline in `args`, or if the target didn't send any updates.
+pub(crate) async fn run(
+    target: Uri,
+    args: &CollectArgs,
+    warnings: &[&KnownWarnings],
+    styles: &view::Styles,
+) -> color_eyre::Result<()> {
+    let report = report::collect(target, args, warnings, styles).await?;
+    report.print_warnings();
+    let regressions = match args.baseline {
+        Some(ref path) => {
+            let regressions = report.regressions(path)?;
+            baseline::print(&regressions);
+            regressions.len()
+        }
+        None => 0,
+    };
+
+    let mut failures = Vec::new();
+    let triggered = report.warnings_triggered();
+    if triggered > 0 {
+        failures.push(format!(
+            "triggered {} of the {} enabled warnings",
+            triggered,
+            warnings.len()
+        ));
+    }
+    if regressions > 0 {
+        failures.push(format!(
+            "found {regressions} regressions since the baseline"
+        ));
+    }
+    if !failures.is_empty() {
+        bail!("{}", failures.join(", and "));
+    }
+    Ok(())
+}
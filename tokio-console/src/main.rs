@@ -1,40 +1,96 @@
-use color_eyre::{eyre::eyre, Help, SectionExt};
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Help, SectionExt,
+};
 use console_api::tasks::TaskDetails;
-use state::{State, Temporality};
+use state::{Retention, State, Temporality};
 
-use futures::stream::StreamExt;
+use futures::{future::FutureExt, stream::StreamExt};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::Color,
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Paragraph, Wrap},
+    widgets::{Paragraph, Tabs, Wrap},
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
 };
-use tokio::sync::{mpsc, watch};
+use tokio::sync::mpsc;
 
 use crate::{
-    config::AllowedWarnings,
+    i18n::tr,
     view::{bold, UpdateKind},
 };
 
+mod aggregate;
+mod alert;
+mod auth;
+mod baseline;
+mod bookmarks;
+mod check;
+mod clipboard;
 mod config;
 mod conn;
+mod demo;
+mod discover;
+mod docker;
+mod dump;
+mod editor;
+mod event_log;
+mod export;
+mod flamegraph;
+mod graphics;
+mod highlight;
+mod httpd;
+mod i18n;
 mod input;
 mod intern;
+mod k8s;
+mod keymap;
+mod lint;
+mod metrics;
+mod notify;
+mod pause;
+mod persist;
+mod plugins;
+mod query;
+mod relay;
+mod replay;
+mod report;
+mod screenshot;
+mod slo;
+mod srv;
 mod state;
+mod tab;
 mod term;
+mod trends;
 mod util;
 mod view;
 mod warnings;
+mod web;
+
+/// How often console-subscriber publishes updates by default, for targets
+/// which don't report their publish interval.
+const DEFAULT_PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The most messages from targets taken at once, before keys are handled
+/// again.
+const MAX_MESSAGES_TAKEN: usize = 256;
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
     let args = config::Config::parse()?;
+    // Stop any port-forwards to Kubernetes pods when the console exits.
+    let _port_forwards = k8s::StopPortForwards;
     // initialize error handling first, in case panics occur while setting up
     // other stuff.
     let styles = view::Styles::from_config(args.view_options.clone());
     styles.error_init(&args)?;
 
     args.trace_init()?;
+    i18n::init(args.view_options.lang());
+    auth::init(&args.auth).map_err(|error| eyre!(error))?;
     tracing::debug!(?args.target_addr, ?args.view_options);
 
     match args.subcmd {
@@ -47,105 +103,449 @@ async fn main() -> color_eyre::Result<()> {
         Some(config::OptionalCmd::GenCompletion { install, shell }) => {
             return config::gen_completion(install, shell);
         }
-        None => {}
+        Some(config::OptionalCmd::Check {
+            ref target_addr,
+            timeout,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            return check::run(target, timeout.into()).await;
+        }
+        Some(config::OptionalCmd::Lint { ref collect }) => {
+            let target = match collect.target_addr {
+                Some(ref target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            let warnings = args.enabled_warnings();
+            return lint::run(target, collect, &warnings, &styles).await;
+        }
+        Some(config::OptionalCmd::Report { ref collect }) => {
+            let target = match collect.target_addr {
+                Some(ref target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            let warnings = args.enabled_warnings();
+            return report::run(target, collect, &warnings, &styles).await;
+        }
+        Some(config::OptionalCmd::ExportFlamegraph {
+            ref target_addr,
+            duration,
+            ref output,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            return flamegraph::run(target, duration, output.as_deref(), &styles).await;
+        }
+        Some(config::OptionalCmd::Query {
+            ref query,
+            ref target_addr,
+            format,
+            json,
+            timeout,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            let format = if json { query::Format::Json } else { format };
+            return query::run(target, query, format, timeout.into(), &styles).await;
+        }
+        Some(config::OptionalCmd::Dump {
+            ref target_addr,
+            format,
+            timeout,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            let warnings = args.enabled_warnings();
+            let state = State::default()
+                .with_task_linters(warnings.iter().copied().map(|lint| lint.into()))
+                .with_task_columns(args.task_columns());
+            let view = view::View::new(styles.clone())
+                .with_task_list_columns(args.show_columns.clone())
+                .with_task_list_sort(args.sort.as_ref(), &state);
+            return dump::run(target, format, timeout.into(), &styles, state, view).await;
+        }
+        Some(config::OptionalCmd::Aggregate {
+            listen,
+            ref targets,
+        }) => {
+            let sources = targets
+                .iter()
+                .map(|target| Ok((target.clone(), args.resolve_target(target)?)))
+                .collect::<color_eyre::Result<Vec<_>>>()?;
+            return aggregate::run(listen, sources, args.retain_for()).await;
+        }
+        Some(config::OptionalCmd::Export {
+            listen,
+            ref target_addr,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            return metrics::run(listen, target, args.retain_for(), &styles).await;
+        }
+        Some(config::OptionalCmd::Serve {
+            listen,
+            ref target_addr,
+        }) => {
+            let target = match target_addr {
+                Some(target) => args.resolve_target(target)?,
+                None => args.target_addr()?,
+            };
+            let warnings = args.enabled_warnings();
+            let state = State::default()
+                .with_retain_for(args.retain_for())
+                .with_task_linters(warnings.iter().copied().map(|lint| lint.into()))
+                .with_task_columns(args.task_columns());
+            let view = view::View::new(styles.clone())
+                .with_task_list_columns(args.show_columns.clone())
+                .with_task_list_sort(args.sort.as_ref(), &state);
+            return web::run(listen, target, &styles, state, view).await;
+        }
+        Some(config::OptionalCmd::Demo | config::OptionalCmd::Replay { .. }) | None => {}
     }
 
-    let target = args.target_addr()?;
+    let target = match args.subcmd {
+        Some(config::OptionalCmd::Demo) => demo::TARGET.parse()?,
+        Some(config::OptionalCmd::Replay { ref recording }) => {
+            let path = recording
+                .canonicalize()
+                .wrap_err_with(|| format!("failed to open {}", recording.display()))?;
+            format!("{}://localhost{}", conn::REPLAY, path.display())
+                .parse()
+                .wrap_err_with(|| {
+                    format!(
+                        "cannot replay {}, since its path isn't a valid URI",
+                        path.display()
+                    )
+                })?
+        }
+        _ => args.target_addr()?,
+    };
     tracing::info!(?target, "using target addr");
 
     let retain_for = args.retain_for();
-    let (mut terminal, _cleanup) = term::init_crossterm()?;
-    terminal.clear()?;
-    let mut conn = conn::Connection::new(target);
-    // A channel to send the outcome of `View::update_input` to the watch_details_stream task.
-    let (update_tx, update_rx) = watch::channel(UpdateKind::Other);
-    // A channel to send the task details update stream (no need to keep outdated details in the memory)
-    let (details_tx, mut details_rx) = mpsc::channel::<TaskDetails>(2);
-    let warnings = match args.allow_warnings {
-        Some(AllowedWarnings::All) => vec![],
-        Some(AllowedWarnings::Explicit(allow_warnings)) => args
-            .warnings
-            .iter()
-            .filter(|lint| !allow_warnings.contains(lint))
-            .collect::<Vec<_>>(),
-        None => args.warnings.iter().collect::<Vec<_>>(),
+    let unfocused_redraw = args.unfocused_redraw();
+    let relay = match args.relay {
+        Some(addr) => {
+            Some(relay::Relay::serve(addr, relay::Target(target.clone()), retain_for).await?)
+        }
+        None => None,
     };
-
-    let mut state = State::default()
-        .with_task_linters(warnings.into_iter().map(|lint| lint.into()))
-        .with_retain_for(retain_for);
-    let mut input = Box::pin(input::EventStream::new());
-    let mut view = view::View::new(styles);
+    // Only the target the console starts with is recorded.
+    let mut recorder = match args.record {
+        Some(ref path) => Some(
+            replay::Recorder::create(path)
+                .wrap_err_with(|| format!("failed to create {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let mut tabs = vec![tab::Tab::new(
+        tab::FIRST_TAB,
+        &args,
+        styles.clone(),
+        target,
+        args.target_name().map(ToOwned::to_owned),
+    )];
+    for target in args.tabs.iter().take(tab::MAX_TABS - 1) {
+        let name = args.is_target_name(target).then(|| target.clone());
+        let target = args.resolve_target(target)?;
+        tabs.push(tab::Tab::new(
+            tabs.len(),
+            &args,
+            styles.clone(),
+            target,
+            name,
+        ));
+    }
+    let mut next_tab_id = tabs.len();
+    let mut current = 0;
+    let report_focus = tabs[0].notifier.is_enabled() || unfocused_redraw.is_some();
+    let (mut terminal, _cleanup) = term::init_crossterm(report_focus)?;
+    terminal.clear()?;
+    // A channel to send each tab's task details updates (no need to keep
+    // outdated details in the memory)
+    let (details_tx, mut details_rx) = mpsc::channel::<(tab::TabId, TaskDetails)>(2);
+    let mut events = Box::pin(input::EventStream::new());
+    // Redraw periodically even when nothing happens, so that the age of the
+    // data keeps up to date if updates stop arriving.
+    let mut redraw = tokio::time::interval(Duration::from_secs(1));
+    redraw.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Whether anything has happened since the last frame which could change
+    // what's drawn. Otherwise, the frame is only drawn again if it shows a
+    // time which counts up or down on its own.
+    let mut dirty = true;
+    // Whether the terminal has focus, and when the last frame was drawn, so
+    // that the console can draw less often while it's in the background.
+    let mut focused = true;
+    let mut last_drawn = Instant::now();
+    // Updates are applied at most once a frame, so that a target which sends
+    // them faster than they can be drawn doesn't stall the console. The
+    // updates received in the meantime are merged, and applied at once.
+    let frame_interval = args.frame_interval();
+    let mut next_frame = Instant::now();
+    // Whether to save the next frame, and the outcome of the last save, which
+    // is shown until the next key is pressed.
+    let mut save_screen = false;
+    let mut saved_screen: Option<Result<[PathBuf; 2], String>> = None;
 
     loop {
+        let pending = tabs.iter().any(tab::Tab::has_pending);
         tokio::select! { biased;
-            input = input.next() => {
+            input = events.next() => {
                 let input = input
                     .ok_or_else(|| eyre!("keyboard input stream ended early"))
                     .with_section(|| "this is probably a bug".header("Note:"))??;
 
-                if input::should_ignore_key_event(&input) {
+                if input::should_ignore_key_event(&input) || input::should_ignore_mouse_event(&input) {
                     continue;
                 }
 
-                if input::should_quit(&input) {
+                for tab in &mut tabs {
+                    tab.notifier.update_input(&input);
+                }
+                if matches!(input, input::Event::FocusGained | input::Event::FocusLost) {
+                    focused = matches!(input, input::Event::FocusGained);
+                    if focused {
+                        // Catch up with everything received in the background
+                        // straight away.
+                        redraw.reset_immediately();
+                    }
+                    continue;
+                }
+                dirty = true;
+                saved_screen = None;
+
+                // While text is being typed, every key is part of the text.
+                let editing = tabs[current].view.is_editing_text();
+                let input = if editing {
+                    input
+                } else {
+                    args.keymap.translate(input)
+                };
+
+                if !editing && input::should_quit(&input) {
                     return Ok(());
                 }
 
-                if input::is_space(&input) {
-                    if state.is_paused() {
-                        conn.resume().await;
-                        state.start_unpausing();
+                if let Some(idx) = input::tab_index(&input).filter(|_| !editing) {
+                    if idx < tabs.len() {
+                        current = idx;
+                    }
+                    continue;
+                }
+
+                if !editing && input::is_new_tab(&input) && tabs.len() < tab::MAX_TABS {
+                    // A new tab starts out connected to the same target, so
+                    // that it can show another view of it.
+                    let conn = &tabs[current].conn;
+                    let (target, name) = (conn.target().clone(), conn.name().map(ToOwned::to_owned));
+                    tabs.push(tab::Tab::new(next_tab_id, &args, styles.clone(), target, name));
+                    next_tab_id += 1;
+                    current = tabs.len() - 1;
+                    continue;
+                }
+
+                if !editing && input::is_close_tab(&input) && tabs.len() > 1 {
+                    let closed = tabs.remove(current);
+                    if closed.id() == tab::FIRST_TAB && recorder.take().is_some() {
+                        tracing::info!("stopped recording, since its tab was closed");
+                    }
+                    current = current.min(tabs.len() - 1);
+                    continue;
+                }
+
+                if !editing && input::is_cycle_theme(&input) {
+                    for tab in &mut tabs {
+                        tab.view.styles.cycle_theme();
+                    }
+                }
+
+                if !editing && input::is_save_screen(&input) {
+                    save_screen = true;
+                }
+
+                let tab = &mut tabs[current];
+
+                if !editing && input::is_space(&input) {
+                    // The user has taken control of pausing, so don't resume
+                    // automatically when leaving the task's details.
+                    tab.auto_paused = false;
+                    if tab.state.is_paused() {
+                        tab.resume().await;
                     } else {
-                        conn.pause().await;
-                        state.start_pausing();
+                        tab.pause().await;
                     }
                 }
 
-                let update_kind = view.update_input(input, &state);
-                // Using the result of update_input to manage the details watcher task
-                let _ = update_tx.send(update_kind);
-                match update_kind {
-                    UpdateKind::SelectTask(task_id) => {
-                        tracing::info!(task_id, "starting details watch");
-                        match conn.watch_details(task_id).await {
-                            Ok(stream) => {
-                                tokio::spawn(watch_details_stream(task_id, stream, update_rx.clone(), details_tx.clone()));
-                            },
-                            Err(error) => {
-                                tracing::warn!(%error, "error watching task details");
-                                state.unset_task_details();
-                        }
+                if !editing && input::is_step(&input) {
+                    tab.step().await;
+                }
+
+                if !editing && input::is_reconnect(&input) {
+                    tab.conn.reconnect();
+                }
+
+                if !editing && input::is_disconnect(&input) {
+                    tab.conn.disconnect();
+                }
+
+                if !editing && input::is_clear_completed(&input) {
+                    tab.state.clear_completed();
+                }
+
+                if !editing && input::is_cycle_retention(&input) {
+                    tab.state.cycle_retention();
+                }
+
+                if !editing && input::is_freeze(&input) {
+                    tab.frozen = !tab.frozen;
+                }
+
+                if let Some(back) = input::seek_back(&input).filter(|_| !editing) {
+                    tab.seek(&args, back);
+                }
+
+                let mut update_kind = tab.view.update_input(input, &tab.state);
+                if let Some(new_target) = tab.view.take_new_target() {
+                    match args.resolve_target(&new_target) {
+                        Ok(target) => {
+                            tracing::info!(?target, "changing target");
+                            let name = args.is_target_name(&new_target).then_some(new_target);
+                            if tab.id() == tab::FIRST_TAB {
+                                if let Some(ref relay) = relay {
+                                    relay.reset(target.clone());
+                                }
+                                if recorder.take().is_some() {
+                                    tracing::info!("stopped recording, since the target changed");
+                                }
+                            }
+                            tab.change_target(&args, target, name);
+                            // Stop watching the old target's task details.
+                            update_kind = UpdateKind::ExitTaskView;
                         }
-                    },
-                    UpdateKind::ExitTaskView => {
-                        state.unset_task_details();
+                        Err(error) => tab.view.reject_target(new_target, error.to_string()),
+                    }
+                }
+                if let Some(edit) = tab.view.take_edit() {
+                    // The editor runs in the terminal, so it has to be handed
+                    // the terminal, and the keys typed in it mustn't be read
+                    // as input, until it exits.
+                    tracing::info!(location = %edit, "handing the terminal to the editor");
+                    drop(events);
+                    term::exit_crossterm()?;
+                    let result = edit.run();
+                    term::enter_crossterm(report_focus)?;
+                    terminal.clear()?;
+                    events = Box::pin(input::EventStream::new());
+                    if let Err(error) = result {
+                        tab.view.edit_failed(error.to_string());
                     }
-                    _ => {}
                 }
+                tab.send_task_request().await;
+                tab.save();
+                tab.handle_update(update_kind, &details_tx).await;
             },
-            instrument_message = conn.next_message() => {
-                match instrument_message {
-                    conn::Message::Update(update) => {
-                        state.update(&view.styles, view.current_view(), update);
-                    },
-                    conn::Message::State(state_update) => {
-                        state.update_state(state_update);
+            (message, idx) = tab::next_message(&mut tabs) => {
+                // Take every message which has already arrived, rather than
+                // going around the loop for each of them.
+                let mut next = Some((message, idx));
+                let mut taken = 0;
+                while let Some((message, idx)) = next {
+                    if tabs[idx].id() == tab::FIRST_TAB {
+                        if let Some(ref relay) = relay {
+                            relay.publish(&message);
+                        }
+                        if let Some(ref mut recording) = recorder {
+                            if let Err(error) = recording.record(&message) {
+                                tracing::error!(%error, "failed to record message, no longer recording");
+                                recorder = None;
+                            }
+                        }
                     }
+                    tabs[idx].received.push_back(message);
+                    taken += 1;
+                    next = if taken < MAX_MESSAGES_TAKEN {
+                        tab::next_message(&mut tabs).now_or_never()
+                    } else {
+                        None
+                    };
                 }
             }
             details_update = details_rx.recv() => {
-                if let Some(details_update) = details_update {
-                    state.update_task_details(details_update);
+                if let Some((id, details_update)) = details_update {
+                    if let Some(idx) = tabs.iter().position(|tab| tab.id() == id) {
+                        tabs[idx].receive_details(details_update);
+                    }
                 }
             },
+            _ = tokio::time::sleep_until(next_frame.into()), if pending => {}
+            _ = redraw.tick() => {}
         }
-        terminal.draw(|f| {
+
+        let frame = Instant::now() >= next_frame;
+        let mut applied = false;
+        for (idx, tab) in tabs.iter_mut().enumerate() {
+            if tab.apply_received(frame).await {
+                applied = true;
+                // Tabs in the background are kept up to date, but aren't
+                // drawn.
+                dirty |= idx == current;
+            }
+        }
+        if applied {
+            next_frame = Instant::now() + frame_interval;
+        }
+
+        let titles = tabs.iter().map(tab::Tab::title).collect::<Vec<_>>();
+        let tab = &mut tabs[current];
+        // The target doesn't publish updates while it's paused, so the data
+        // is only stale if it's live. Allow half an interval of slack, so that
+        // an update arriving slightly late doesn't make the data flicker. A
+        // snapshot is never updated, and a recording is replayed as it was
+        // recorded, so neither is ever stale.
+        let publish_interval = tab
+            .conn
+            .publish_interval()
+            .unwrap_or(DEFAULT_PUBLISH_INTERVAL);
+        let stale_age = tab.state.data_age().filter(|age| {
+            !tab.frozen
+                && tab.conn.is_live()
+                && matches!(tab.state.temporality(), Temporality::Live)
+                && *age > publish_interval + publish_interval / 2
+        });
+        // The header counts how long the data has been stale for, how long
+        // the target has been paused for, and down to the next reconnection
+        // attempt, and alerts' banners flash.
+        let counting = stale_age.is_some()
+            || tab.view.is_alerting()
+            || tab.state.is_paused()
+            || (!tab.conn.is_connected() && tab.conn.is_live());
+        if !dirty && !counting {
+            continue;
+        }
+        if !focused && unfocused_redraw.is_some_and(|interval| last_drawn.elapsed() < interval) {
+            continue;
+        }
+        dirty = false;
+        last_drawn = Instant::now();
+        let frame = terminal.draw(|f| {
+            // The tab bar is only shown once there's more than one tab.
+            let tab_bar_height = if titles.len() > 1 { 1 } else { 0 };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(0)
                 .constraints(
                     [
+                        Constraint::Length(tab_bar_height),
                         Constraint::Length(1),
                         Constraint::Length(1),
                         Constraint::Percentage(95),
@@ -154,10 +554,28 @@ async fn main() -> color_eyre::Result<()> {
                 )
                 .split(f.size());
 
-            let mut header_text = conn.render(&view.styles);
+            let frozen_pending = tab.frozen_pending();
+            let (state, view, conn) = (&mut tab.state, &mut tab.view, &tab.conn);
+            let mut header_text = conn.render(&view.styles, frozen_pending);
             match state.temporality() {
                 Temporality::Paused => {
-                    header_text.push_span(Span::styled(" PAUSED", view.styles.fg(Color::Red)));
+                    let paused_style = view.styles.fg(Color::Red).add_modifier(Modifier::BOLD);
+                    header_text.push_span(Span::styled(" PAUSED", paused_style));
+                    if let Some(paused_for) = state.paused_for() {
+                        // Round to whole seconds, since the header is only redrawn
+                        // when the target publishes its state.
+                        let paused_for = Duration::from_secs(paused_for.as_secs());
+                        header_text.push_span(Span::styled(
+                            format!(" for {}", humantime::format_duration(paused_for)),
+                            paused_style,
+                        ));
+                    }
+                    if let Some(paused_by) = state.paused_by() {
+                        header_text.push_span(Span::styled(
+                            format!(" by {paused_by}"),
+                            view.styles.fg(Color::Red),
+                        ));
+                    }
                 }
                 Temporality::Pausing => {
                     header_text.push_span(Span::styled(" PAUSING", view.styles.fg(Color::Yellow)));
@@ -186,64 +604,133 @@ async fn main() -> color_eyre::Result<()> {
                     view.styles.fg(Color::Red),
                 ));
             }
+            let coalesced_updates = state.coalesced_updates();
+            if coalesced_updates > 0 {
+                header_text.push_span(Span::styled(
+                    format!(" behind: {coalesced_updates} updates coalesced"),
+                    view.styles.fg(Color::Yellow),
+                ));
+            }
+            match state.retention() {
+                Retention::Configured => {}
+                Retention::Forever => header_text.push_span(Span::styled(
+                    " keeping completed tasks",
+                    view.styles.fg(Color::Cyan),
+                )),
+                Retention::Immediately => header_text.push_span(Span::styled(
+                    " dropping completed tasks",
+                    view.styles.fg(Color::Cyan),
+                )),
+            }
+            if let Some(age) = stale_age {
+                header_text.push_span(Span::styled(
+                    format!(" data is {}s old", age.as_secs()),
+                    view.styles.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ));
+            }
+            match saved_screen {
+                Some(Ok([ref ansi, ref html])) => header_text.push_span(Span::styled(
+                    format!(" saved screen to {} and {}", ansi.display(), html.display()),
+                    view.styles.fg(Color::Green),
+                )),
+                Some(Err(ref error)) => header_text.push_span(Span::styled(
+                    format!(" failed to save screen: {error}"),
+                    view.styles.fg(Color::Red),
+                )),
+                None => {}
+            }
+            match view.exported() {
+                Some(Ok(path)) => header_text.push_span(Span::styled(
+                    format!(" exported to {}", path.display()),
+                    view.styles.fg(Color::Green),
+                )),
+                Some(Err(error)) => header_text.push_span(Span::styled(
+                    format!(" failed to export: {error}"),
+                    view.styles.fg(Color::Red),
+                )),
+                None => {}
+            }
+            match view.opened() {
+                Some(Ok(location)) => header_text.push_span(Span::styled(
+                    format!(" opened {location}"),
+                    view.styles.fg(Color::Green),
+                )),
+                Some(Err(error)) => header_text.push_span(Span::styled(
+                    format!(" failed to open in editor: {error}"),
+                    view.styles.fg(Color::Red),
+                )),
+                None => {}
+            }
+            match view.copied() {
+                Some(Ok(what)) => header_text.push_span(Span::styled(
+                    format!(" copied {what} to clipboard"),
+                    view.styles.fg(Color::Green),
+                )),
+                Some(Err(error)) => header_text.push_span(Span::styled(
+                    format!(" failed to copy: {error}"),
+                    view.styles.fg(Color::Red),
+                )),
+                None => {}
+            }
             let header = Paragraph::new(header_text).wrap(Wrap { trim: true });
-            let view_controls = Paragraph::new(Line::from(vec![
-                Span::raw("views: "),
+            let mut views = vec![
+                Span::raw(format!("{}: ", tr("views"))),
                 bold("t"),
-                Span::raw(" = tasks, "),
+                Span::raw(format!(" = {}, ", tr("tasks"))),
                 bold("r"),
-                Span::raw(" = resources"),
-            ]))
-            .wrap(Wrap { trim: true });
+                Span::raw(format!(" = {}, ", tr("resources"))),
+                bold("n"),
+                Span::raw(format!(" = {}, ", tr("network"))),
+                bold("B"),
+                Span::raw(format!(" = {}, ", tr("bookmarks"))),
+                bold("p"),
+                Span::raw(format!(" = {}, ", tr("crashed"))),
+                bold("T"),
+                Span::raw(format!(" = {}, ", tr("timeline"))),
+                bold("v"),
+                Span::raw(format!(" = {}", tr("diff"))),
+            ];
+            for (key, name) in view.plugins() {
+                views.push(Span::raw(", "));
+                views.push(bold(key.to_string()));
+                views.push(Span::raw(format!(" = {}", name.to_lowercase())));
+            }
+            let view_controls = Paragraph::new(Line::from(views)).wrap(Wrap { trim: true });
 
-            f.render_widget(header, chunks[0]);
-            f.render_widget(view_controls, chunks[1]);
-            view.render(f, chunks[2], &mut state);
+            if titles.len() > 1 {
+                let titles = titles
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, title)| format!("{} {title}", idx + 1));
+                let tab_bar = Tabs::new(titles).select(current).highlight_style(
+                    Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                );
+                f.render_widget(tab_bar, chunks[0]);
+            }
+            f.render_widget(header, chunks[1]);
+            f.render_widget(view_controls, chunks[2]);
+            view.render(f, chunks[3], state);
+            if stale_age.is_some() {
+                // Dim the data, so that it's clear it can't be trusted.
+                f.buffer_mut()
+                    .set_style(chunks[3], Style::default().add_modifier(view.styles.dim()));
+            }
         })?;
-    }
-}
-
-/// Given the task details stream for the given task id, sends the updates
-/// to the `details_tx` channel until the currently-viewed task changes.
-///
-/// This is a separate task from the main program loop mainly because there isn't
-/// always a details stream to poll and we need to react to user inputs to
-/// replace the details stream with another one.
-async fn watch_details_stream(
-    task_id: u64,
-    mut details_stream: tonic::Streaming<TaskDetails>,
-    mut watch_rx: watch::Receiver<UpdateKind>,
-    details_tx: mpsc::Sender<TaskDetails>,
-) {
-    loop {
-        tokio::select! { biased;
-            details = details_stream.next() => {
-                match details {
-                    Some(Ok(details)) => {
-                        if details_tx.send(details).await.is_err() {
-                            break;
-                        }
-                    },
-                    _ => {
-                        break;
-                    }
-                }
-            },
-            update = watch_rx.changed() => {
-                if update.is_ok() {
-                    match *watch_rx.borrow() {
-                        UpdateKind::ExitTaskView => {
-                            break;
-                        },
-                        UpdateKind::SelectTask(new_id) if new_id != task_id => {
-                            break;
-                        },
-                        _ => {}
-                    }
-                } else {
-                    break;
-                }
-            },
+        if save_screen {
+            save_screen = false;
+            saved_screen = Some(screenshot::save(frame.buffer).map_err(|error| error.to_string()));
+            // Show where the screen was saved.
+            dirty = true;
+            redraw.reset_immediately();
+        }
+        if let Some(graphics) = tab.view.styles.graphics() {
+            if graphics.flush(terminal.backend_mut())? {
+                // The images have moved, and the old ones can only be removed
+                // by drawing over them, so draw everything again.
+                terminal.clear()?;
+                redraw.reset_immediately();
+                dirty = true;
+            }
         }
     }
 }
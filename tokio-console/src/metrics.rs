@@ -0,0 +1,281 @@
+//! Serving a target's runtime health as Prometheus metrics, for the
+//! `tokio-console export` subcommand.
+//!
+//! The exporter watches the target as the console would, and serves totals
+//! over all of its tasks on `/metrics`, in the Prometheus text format, so
+//! that existing dashboards and alerts can watch it continuously.
+use crate::{
+    conn,
+    httpd::{self, Response},
+    state::{
+        tasks::{Task, TaskState},
+        Id, State,
+    },
+    view,
+};
+use color_eyre::eyre::WrapErr;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+use tokio::{net::TcpListener, sync::watch};
+use tonic::transport::Uri;
+
+/// The content type of the Prometheus text format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Each state a task can be in, and the `state` label it's counted under.
+const TASK_STATES: &[(TaskState, &str)] = &[
+    (TaskState::Running, "running"),
+    (TaskState::Scheduled, "scheduled"),
+    (TaskState::Idle, "idle"),
+    (TaskState::Completed, "completed"),
+    (TaskState::Cancelled, "cancelled"),
+    (TaskState::Panicked, "panicked"),
+];
+
+/// The metrics served for a target.
+#[derive(Debug, Default)]
+struct Exporter {
+    /// The totals of each task in the state, as of the last update.
+    tasks: HashMap<Id<Task>, Totals>,
+    /// The totals of the tasks which have since been dropped from the state,
+    /// so that the counters don't go down when completed tasks are.
+    retired: Totals,
+}
+
+/// Counters which are summed over tasks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Totals {
+    polls: u64,
+    busy: Duration,
+    wakes: u64,
+    self_wakes: u64,
+}
+
+/// Watches `target`, serving its metrics at `addr` until the process is
+/// stopped.
+///
+/// Completed tasks are dropped from the exporter's state after `retain_for`,
+/// or never if it's `None`, but still count towards the counters.
+pub(crate) async fn run(
+    addr: SocketAddr,
+    target: Uri,
+    retain_for: Option<Duration>,
+    styles: &view::Styles,
+) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .wrap_err_with(|| format!("could not listen for metrics requests on {addr}"))?;
+    let mut state = State::default().with_retain_for(retain_for);
+    let mut exporter = Exporter::default();
+    let (metrics, rx) = watch::channel(exporter.render(&state));
+    tokio::spawn(httpd::serve(listener, "metrics", move |request| {
+        let response = match request.path.as_str() {
+            "/metrics" => Response::ok(CONTENT_TYPE, rx.borrow().clone()),
+            _ => Response::not_found(),
+        };
+        async move { response }
+    }));
+    tracing::info!(%addr, %target, "serving metrics");
+
+    let mut conn = conn::Connection::new(target);
+    loop {
+        match conn.next_message().await {
+            conn::Message::Update(update) => {
+                // The task list is the only view whose data is kept without
+                // being displayed.
+                state.update(styles, &view::ViewState::TasksList, update);
+                state.retain_active();
+                exporter.record(&state);
+                metrics.send_replace(exporter.render(&state));
+            }
+            conn::Message::State(state_update) => state.update_state(state_update),
+        }
+    }
+}
+
+// === impl Exporter ===
+
+impl Exporter {
+    /// Records the totals of the tasks in `state`, retiring those of the
+    /// tasks which are no longer in it.
+    fn record(&mut self, state: &State) {
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let mut tasks = HashMap::with_capacity(self.tasks.len());
+        for task in state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+        {
+            let task = task.borrow();
+            tasks.insert(task.id(), Totals::of(&task, now));
+        }
+        for (id, totals) in self.tasks.drain() {
+            if !tasks.contains_key(&id) {
+                self.retired += totals;
+            }
+        }
+        self.tasks = tasks;
+    }
+
+    /// Returns the metrics in the Prometheus text format.
+    fn render(&self, state: &State) -> String {
+        let mut counts = BTreeMap::<TaskState, usize>::new();
+        for task in state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+        {
+            *counts.entry(task.borrow().state()).or_default() += 1;
+        }
+        let totals = self.tasks.values().fold(self.retired, |mut sum, &totals| {
+            sum += totals;
+            sum
+        });
+        let mean_poll = match totals.polls {
+            0 => 0.0,
+            polls => totals.busy.as_secs_f64() / polls as f64,
+        };
+
+        let mut out = String::new();
+        header(
+            &mut out,
+            "tokio_console_tasks",
+            "gauge",
+            "The number of tasks in each state.",
+        );
+        for (state, label) in TASK_STATES {
+            let count = counts.get(state).copied().unwrap_or(0);
+            let _ = writeln!(out, "tokio_console_tasks{{state=\"{label}\"}} {count}");
+        }
+        let metrics: [(&str, &str, &str, &dyn std::fmt::Display); 5] = [
+            (
+                "tokio_console_task_polls_total",
+                "counter",
+                "The number of times tasks have been polled.",
+                &totals.polls,
+            ),
+            (
+                "tokio_console_task_busy_seconds_total",
+                "counter",
+                "The time tasks have spent being polled.",
+                &totals.busy.as_secs_f64(),
+            ),
+            (
+                "tokio_console_task_poll_duration_mean_seconds",
+                "gauge",
+                "The mean time a poll of a task has taken.",
+                &mean_poll,
+            ),
+            (
+                "tokio_console_task_wakes_total",
+                "counter",
+                "The number of times tasks have been woken.",
+                &totals.wakes,
+            ),
+            (
+                "tokio_console_task_self_wakes_total",
+                "counter",
+                "The number of times tasks have woken themselves.",
+                &totals.self_wakes,
+            ),
+        ];
+        for (name, kind, help, value) in metrics {
+            header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+// === impl Totals ===
+
+impl Totals {
+    fn of(task: &Task, now: SystemTime) -> Self {
+        Self {
+            polls: task.total_polls(),
+            busy: task.busy(now),
+            wakes: task.wakes(),
+            self_wakes: task.self_wakes(),
+        }
+    }
+}
+
+impl std::ops::AddAssign for Totals {
+    fn add_assign(&mut self, other: Self) {
+        self.polls += other.polls;
+        self.busy += other.busy;
+        self.wakes += other.wakes;
+        self.self_wakes += other.self_wakes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ViewOptions,
+        state::fixtures::{self, timestamp},
+    };
+    use clap::Parser;
+    use console_api as proto;
+
+    fn task(
+        id: u64,
+        polls: u64,
+        dropped_at: Option<i64>,
+    ) -> (proto::tasks::Task, proto::tasks::Stats) {
+        let stats = proto::tasks::Stats {
+            created_at: Some(timestamp(0)),
+            dropped_at: dropped_at.map(timestamp),
+            wakes: polls,
+            self_wakes: 1,
+            poll_stats: Some(proto::PollStats {
+                polls,
+                first_poll: Some(timestamp(1)),
+                last_poll_started: Some(timestamp(8)),
+                last_poll_ended: Some(timestamp(9)),
+                busy_time: Some(prost_types::Duration {
+                    seconds: polls as i64,
+                    nanos: 0,
+                }),
+            }),
+            ..Default::default()
+        };
+        (fixtures::task(id), stats)
+    }
+
+    #[test]
+    fn counters_include_dropped_tasks() {
+        let styles = view::Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let update = fixtures::update(60, [task(1, 4, None), task(2, 2, Some(10))]);
+        let mut state = State::default().with_retain_for(Some(Duration::from_secs(5)));
+        state.update(&styles, &view::ViewState::TasksList, update);
+        let mut exporter = Exporter::default();
+        exporter.record(&state);
+        let metrics = exporter.render(&state);
+        assert!(metrics.contains("tokio_console_tasks{state=\"idle\"} 1\n"));
+        assert!(metrics.contains("tokio_console_tasks{state=\"completed\"} 1\n"));
+        assert!(metrics.contains("tokio_console_tasks{state=\"running\"} 0\n"));
+        assert!(metrics.contains("# TYPE tokio_console_task_polls_total counter\n"));
+        assert!(metrics.contains("tokio_console_task_polls_total 6\n"));
+        assert!(metrics.contains("tokio_console_task_poll_duration_mean_seconds 1\n"));
+        assert!(metrics.contains("tokio_console_task_self_wakes_total 2\n"));
+
+        // The completed task is dropped, but still counts towards the totals.
+        state.retain_active();
+        exporter.record(&state);
+        let metrics = exporter.render(&state);
+        assert!(metrics.contains("tokio_console_tasks{state=\"completed\"} 0\n"));
+        assert!(metrics.contains("tokio_console_task_polls_total 6\n"));
+        assert!(metrics.contains("tokio_console_task_wakes_total 6\n"));
+    }
+}
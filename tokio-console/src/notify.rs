@@ -0,0 +1,203 @@
+//! Notifying the user of events, such as new warnings, while the console is
+//! in the background.
+use crate::{
+    input,
+    state::{store::SpanId, State},
+    util,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    process::Command,
+};
+
+/// How to notify the user of an event.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum NotifyMethod {
+    Bell,
+    Osc9,
+    Desktop,
+}
+
+/// The events which the user can be notified of.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum NotifyEvent {
+    Warning,
+    Disconnect,
+    BookmarkCompleted,
+}
+
+/// Watches for events, and notifies the user of them while the terminal is
+/// in the background.
+#[derive(Debug)]
+pub(crate) struct Notifier {
+    method: Option<NotifyMethod>,
+    events: Vec<NotifyEvent>,
+    /// Whether the terminal has focus, or `None` if it hasn't reported a
+    /// focus change yet.
+    ///
+    /// Terminals which don't report focus changes are treated as being in the
+    /// background, so that the user is still notified.
+    focused: Option<bool>,
+    /// How many tasks each linter had warned about as of the last update.
+    counts: Vec<usize>,
+    /// Whether the console was connected to the target when last checked.
+    connected: bool,
+    /// The bookmarked tasks which have already been seen to complete.
+    completed: HashSet<SpanId>,
+}
+
+// === impl Notifier ===
+
+impl Notifier {
+    /// Returns a notifier which notifies the user of `events` with `method`.
+    ///
+    /// If no events are given, the user is only notified of warnings.
+    pub(crate) fn new(method: Option<NotifyMethod>, events: &[NotifyEvent]) -> Self {
+        let events = if events.is_empty() {
+            vec![NotifyEvent::Warning]
+        } else {
+            events.to_vec()
+        };
+        Self {
+            method,
+            events,
+            focused: None,
+            counts: Vec::new(),
+            connected: false,
+            completed: HashSet::new(),
+        }
+    }
+
+    /// Returns `true` if notifications are enabled, so that the terminal
+    /// should report focus changes.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.method.is_some()
+    }
+
+    /// Tracks whether the terminal has focus, from a terminal input event.
+    pub(crate) fn update_input(&mut self, event: &input::Event) {
+        match event {
+            input::Event::FocusGained => self.focused = Some(true),
+            input::Event::FocusLost => self.focused = Some(false),
+            _ => {}
+        }
+    }
+
+    /// Notifies the user if any linter has warned about more tasks, or any
+    /// bookmarked task has completed, since the last update.
+    pub(crate) fn check(&mut self, state: &State) {
+        let linters = &state.tasks_state().linters;
+        let new_warning = linters.iter().enumerate().find_map(|(idx, linter)| {
+            let previous = self.counts.get(idx).copied().unwrap_or(0);
+            (linter.count() > previous).then(|| linter.summary().to_owned())
+        });
+        self.counts = linters.iter().map(|linter| linter.count()).collect();
+        if let Some(summary) = new_warning {
+            self.notify(NotifyEvent::Warning, &summary);
+        }
+
+        let mut newly_completed = Vec::new();
+        for task in state.tasks_state().bookmarked() {
+            let Some(task) = task.upgrade() else {
+                continue;
+            };
+            let task = task.borrow();
+            if task.is_completed() && self.completed.insert(task.span_id()) {
+                newly_completed.push(format!(
+                    "bookmarked task {} {}",
+                    task.short_desc(),
+                    task.state().description()
+                ));
+            }
+        }
+        for message in newly_completed {
+            self.notify(NotifyEvent::BookmarkCompleted, &message);
+        }
+    }
+
+    /// Notifies the user if the console was connected to the target, but no
+    /// longer is.
+    pub(crate) fn check_connection(&mut self, connected: bool) {
+        if self.connected && !connected {
+            self.notify(NotifyEvent::Disconnect, "lost the connection to the target");
+        }
+        self.connected = connected;
+    }
+
+    /// Forgets the events seen so far, such as when connecting to a new
+    /// target.
+    pub(crate) fn reset(&mut self) {
+        self.counts.clear();
+        self.completed.clear();
+        self.connected = false;
+    }
+
+    fn notify(&self, event: NotifyEvent, message: &str) {
+        let Some(method) = self.method else {
+            return;
+        };
+        if !self.events.contains(&event) || self.focused == Some(true) {
+            return;
+        }
+        tracing::debug!(?method, ?event, message, "notifying");
+        if let Err(error) = notify(method, message) {
+            tracing::warn!(%error, ?method, "failed to send notification");
+        }
+    }
+}
+
+fn notify(method: NotifyMethod, message: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    match method {
+        NotifyMethod::Bell => return ring_bell(),
+        NotifyMethod::Osc9 => {
+            // The notification ends at the first BEL or ESC, so neither can
+            // appear in its text.
+            let message = message.replace(['\x07', '\x1b'], "");
+            write!(stdout, "\x1b]9;tokio-console: {message}\x07")?
+        }
+        NotifyMethod::Desktop => return notify_desktop(message),
+    }
+    stdout.flush()
+}
+
+/// Rings the terminal bell.
+pub(crate) fn ring_bell() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(b"\x07")?;
+    stdout.flush()
+}
+
+/// Sends a native desktop notification, using the platform's own tool for
+/// sending them.
+fn notify_desktop(message: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        // Passing the message as an argument means it doesn't have to be
+        // escaped as AppleScript.
+        let mut command = Command::new("osascript");
+        command.args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 1 of argv) with title \"tokio-console\"",
+            "-e",
+            "end run",
+            message,
+        ]);
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=tokio-console", "tokio-console", message]);
+        command
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "desktop notifications are not supported on this platform",
+        ));
+    };
+    util::spawn_detached(&mut command)
+}
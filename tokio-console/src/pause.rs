@@ -0,0 +1,204 @@
+use crate::state::State;
+use std::{fmt, str::FromStr, time::Duration};
+
+/// A condition which automatically pauses the target when it becomes true.
+///
+/// Conditions are written as `<metric> > <threshold>`, such as `tasks > 10000`
+/// or `poll > 5s`. They are evaluated by the console against each update from
+/// the target, so a condition which becomes true and false again between two
+/// updates will not pause the target.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) enum PauseCondition {
+    /// The number of tasks which have not completed exceeds the threshold.
+    Tasks(usize),
+    /// The number of resources which have not been dropped exceeds the
+    /// threshold.
+    Resources(usize),
+    /// A single poll of any task has taken longer than the threshold.
+    ///
+    /// Only each task's in-progress or most recent poll can be checked, since
+    /// the target does not report every poll.
+    Poll(Duration),
+}
+
+/// Tracks whether a [`PauseCondition`] was true for the previous update, so
+/// that the target is only paused when the condition *becomes* true.
+#[derive(Debug)]
+pub(crate) struct PauseTrigger {
+    condition: PauseCondition,
+    was_true: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+// === impl PauseCondition ===
+
+impl PauseCondition {
+    const TASKS: &'static str = "tasks";
+    const RESOURCES: &'static str = "resources";
+    const POLL: &'static str = "poll";
+
+    /// Returns `true` if the condition holds for the current `state`.
+    pub(crate) fn check(&self, state: &mut State) -> bool {
+        match *self {
+            Self::Tasks(max) => {
+                let tasks = state.tasks_state().tasks();
+                tasks
+                    .filter_map(|task| task.upgrade())
+                    .filter(|task| !task.borrow().is_completed())
+                    .count()
+                    > max
+            }
+            Self::Resources(max) => {
+                let resources = state.resources_state().resources();
+                resources
+                    .filter_map(|resource| resource.upgrade())
+                    .filter(|resource| !resource.borrow().dropped())
+                    .count()
+                    > max
+            }
+            Self::Poll(max) => {
+                let Some(now) = state.last_updated_at() else {
+                    return false;
+                };
+                let mut tasks = state.tasks_state().tasks();
+                tasks.any(|task| {
+                    task.upgrade()
+                        .and_then(|task| task.borrow().last_poll_duration(now))
+                        .is_some_and(|poll| poll > max)
+                })
+            }
+        }
+    }
+}
+
+impl FromStr for PauseCondition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (metric, threshold) = s.split_once('>').ok_or_else(|| {
+            ParseError(format!(
+                "invalid pause condition `{s}`, expected `<metric> > <threshold>`"
+            ))
+        })?;
+        let threshold = threshold.trim();
+        let count = || {
+            threshold
+                .replace('_', "")
+                .parse::<usize>()
+                .map_err(|e| ParseError(format!("invalid count `{threshold}`: {e}")))
+        };
+        match metric.trim() {
+            Self::TASKS => count().map(Self::Tasks),
+            Self::RESOURCES => count().map(Self::Resources),
+            Self::POLL => threshold
+                .parse::<humantime::Duration>()
+                .map(|duration| Self::Poll(duration.into()))
+                .map_err(|e| ParseError(format!("invalid duration `{threshold}`: {e}"))),
+            metric => Err(ParseError(format!(
+                "unknown pause condition metric `{metric}`, expected one of: {}, {}, {}",
+                Self::TASKS,
+                Self::RESOURCES,
+                Self::POLL,
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PauseCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tasks(max) => write!(f, "{} > {max}", Self::TASKS),
+            Self::Resources(max) => write!(f, "{} > {max}", Self::RESOURCES),
+            Self::Poll(max) => write!(f, "{} > {}", Self::POLL, humantime::format_duration(*max)),
+        }
+    }
+}
+
+impl TryFrom<String> for PauseCondition {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PauseCondition> for String {
+    fn from(condition: PauseCondition) -> Self {
+        condition.to_string()
+    }
+}
+
+// === impl PauseTrigger ===
+
+impl PauseTrigger {
+    pub(crate) fn new(condition: PauseCondition) -> Self {
+        Self {
+            condition,
+            was_true: false,
+        }
+    }
+
+    /// Checks the condition against the current `state`, returning `true` if
+    /// it has become true since the last check.
+    pub(crate) fn check(&mut self, state: &mut State) -> bool {
+        let is_true = self.condition.check(state);
+        let triggered = is_true && !self.was_true;
+        self.was_true = is_true;
+        triggered
+    }
+
+    pub(crate) fn condition(&self) -> &PauseCondition {
+        &self.condition
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_conditions() {
+        assert_eq!(
+            "tasks > 10_000".parse::<PauseCondition>().unwrap(),
+            PauseCondition::Tasks(10_000)
+        );
+        assert_eq!(
+            "resources>5".parse::<PauseCondition>().unwrap(),
+            PauseCondition::Resources(5)
+        );
+        assert_eq!(
+            "poll > 5s".parse::<PauseCondition>().unwrap(),
+            PauseCondition::Poll(Duration::from_secs(5))
+        );
+        assert!("tasks".parse::<PauseCondition>().is_err());
+        assert!("wakers > 5".parse::<PauseCondition>().is_err());
+        assert!("poll > 5".parse::<PauseCondition>().is_err());
+    }
+
+    #[test]
+    fn display_roundtrips() {
+        for condition in [
+            PauseCondition::Tasks(10_000),
+            PauseCondition::Resources(5),
+            PauseCondition::Poll(Duration::from_millis(1500)),
+        ] {
+            assert_eq!(
+                condition.to_string().parse::<PauseCondition>().unwrap(),
+                condition
+            );
+        }
+    }
+}
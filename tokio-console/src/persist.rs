@@ -0,0 +1,80 @@
+//! State which the console saves between sessions, separately for each
+//! target address.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tonic::transport::Uri;
+
+/// Returns the path of the `kind` state file for `target`, if the platform
+/// has a local data directory.
+///
+/// Every connection to the same target address uses the same file.
+pub(crate) fn path_for(kind: &str, target: &Uri) -> Option<PathBuf> {
+    let mut path = dirs::data_local_dir()?;
+    path.push("tokio-console");
+    path.push(kind);
+    path.push(format!("{}.toml", file_stem(target)));
+    Some(path)
+}
+
+/// Loads the state saved at `path`, parsing it with `parse`.
+///
+/// Returns `None` if nothing has been saved yet, or if the saved state can't
+/// be read, since a problem with saved state shouldn't prevent the console
+/// from starting.
+pub(crate) fn load<T>(path: &Path, parse: impl FnOnce(&str) -> color_eyre::Result<T>) -> Option<T> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            tracing::warn!(%error, path = %path.display(), "failed to read saved state");
+            return None;
+        }
+    };
+    match parse(&raw) {
+        Ok(state) => Some(state),
+        Err(error) => {
+            tracing::warn!(%error, path = %path.display(), "failed to parse saved state");
+            None
+        }
+    }
+}
+
+/// Saves `raw` to `path`, replacing any previously saved state.
+///
+/// Errors are logged rather than returned, since they shouldn't interrupt the
+/// console.
+pub(crate) fn save(path: &Path, raw: color_eyre::Result<String>) {
+    let res = raw.and_then(|raw| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, raw)?;
+        Ok(())
+    });
+    if let Err(error) = res {
+        tracing::warn!(%error, path = %path.display(), "failed to save state");
+    }
+}
+
+/// Returns a file name for `target`'s state.
+fn file_stem(target: &Uri) -> String {
+    let target = target.to_string();
+    target
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_stem_is_path_safe() {
+        let target = "http://127.0.0.1:6669".parse::<Uri>().unwrap();
+        assert_eq!(file_stem(&target), "http___127_0_0_1_6669");
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    input,
+    state::State,
+    view::{self, ControlDisplay, KeyDisplay, Plugin},
+};
+use ratatui::{
+    layout,
+    style::{self, Color, Style},
+    widgets::{Cell, Row, Table},
+};
+use std::collections::HashMap;
+
+/// The tasks spawned at each location, with the most which were ever alive
+/// at once, which shows where tasks pile up.
+#[derive(Debug, Default)]
+pub(super) struct LocationsView {
+    /// The most tasks alive at once, by spawn location, over every update.
+    peaks: HashMap<String, usize>,
+    sort: Sort,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    #[default]
+    Live,
+    Peak,
+    Total,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Location {
+    location: String,
+    live: usize,
+    peak: usize,
+    total: usize,
+}
+
+impl LocationsView {
+    const HEADER: &'static [&'static str] = &["Location", "Live", "Peak", "Total"];
+
+    /// Tallies `tasks`, as `(location, is_completed)` pairs, and records new
+    /// peaks.
+    fn tally<'a>(&mut self, tasks: impl IntoIterator<Item = (&'a str, bool)>) -> Vec<Location> {
+        let mut locations = HashMap::<&str, Location>::new();
+        for (location, completed) in tasks {
+            let entry = locations.entry(location).or_insert_with(|| Location {
+                location: location.to_owned(),
+                ..Location::default()
+            });
+            entry.total += 1;
+            if !completed {
+                entry.live += 1;
+            }
+        }
+        let mut locations = locations
+            .into_values()
+            .map(|mut location| {
+                let peak = self.peaks.entry(location.location.clone()).or_default();
+                *peak = (*peak).max(location.live);
+                location.peak = *peak;
+                location
+            })
+            .collect::<Vec<_>>();
+        locations.sort_by(|a, b| {
+            let key = |location: &Location| match self.sort {
+                Sort::Live => location.live,
+                Sort::Peak => location.peak,
+                Sort::Total => location.total,
+            };
+            key(b)
+                .cmp(&key(a))
+                .then_with(|| a.location.cmp(&b.location))
+        });
+        locations
+    }
+
+    fn locations(&mut self, state: &State) -> Vec<Location> {
+        let tasks = state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+            .collect::<Vec<_>>();
+        let tasks = tasks.iter().map(|task| task.borrow()).collect::<Vec<_>>();
+        self.tally(
+            tasks
+                .iter()
+                .map(|task| (task.location(), task.is_completed())),
+        )
+    }
+}
+
+impl Plugin for LocationsView {
+    fn name(&self) -> &'static str {
+        "Spawn Locations"
+    }
+
+    fn key(&self) -> char {
+        'O'
+    }
+
+    fn controls(&self) -> &'static [ControlDisplay] {
+        &[ControlDisplay {
+            action: "sort by live, peak, or total tasks",
+            keys: &[KeyDisplay {
+                base: "o",
+                utf8: None,
+            }],
+        }]
+    }
+
+    fn update(&mut self, state: &State) {
+        // Peaks are tracked even while the view isn't shown, so that they
+        // include every update.
+        self.locations(state);
+    }
+
+    fn reset(&mut self) {
+        self.peaks.clear();
+    }
+
+    fn update_input(&mut self, event: input::Event, _state: &State) {
+        if let input::Event::Key(input::KeyEvent {
+            code: input::KeyCode::Char('o'),
+            ..
+        }) = event
+        {
+            self.sort = match self.sort {
+                Sort::Live => Sort::Peak,
+                Sort::Peak => Sort::Total,
+                Sort::Total => Sort::Live,
+            };
+        }
+    }
+
+    fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &State,
+    ) {
+        let locations = self.locations(state);
+        let header_style = if styles.color(Color::Cyan).is_some() {
+            Style::default()
+        } else {
+            Style::default().add_modifier(style::Modifier::REVERSED)
+        };
+        let header_style = header_style.add_modifier(style::Modifier::BOLD);
+        let rows = locations.iter().map(|location| {
+            let row = Row::new(vec![
+                Cell::from(location.location.clone()),
+                Cell::from(location.live.to_string()),
+                Cell::from(location.peak.to_string()),
+                Cell::from(location.total.to_string()),
+            ]);
+            if location.live == 0 {
+                row.style(styles.terminated())
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                layout::Constraint::Min(20),
+                layout::Constraint::Length(6),
+                layout::Constraint::Length(6),
+                layout::Constraint::Length(6),
+            ],
+        )
+        .header(Row::new(Self::HEADER.iter().copied()).style(header_style))
+        .column_spacing(1);
+        frame.render_widget(table, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_peaks() {
+        let mut view = LocationsView::default();
+        let locations = view.tally([("a.rs:1", false), ("a.rs:1", false), ("b.rs:2", true)]);
+        assert_eq!(
+            locations,
+            [
+                Location {
+                    location: "a.rs:1".to_owned(),
+                    live: 2,
+                    peak: 2,
+                    total: 2,
+                },
+                Location {
+                    location: "b.rs:2".to_owned(),
+                    live: 0,
+                    peak: 0,
+                    total: 1,
+                },
+            ]
+        );
+
+        // One task completes, and another is spawned elsewhere.
+        let locations = view.tally([("a.rs:1", true), ("a.rs:1", false), ("b.rs:2", false)]);
+        assert_eq!(locations[0].location, "a.rs:1");
+        assert_eq!((locations[0].live, locations[0].peak), (1, 2));
+        assert_eq!((locations[1].live, locations[1].peak), (1, 1));
+
+        view.update_input(
+            input::Event::Key(input::KeyEvent::new(
+                input::KeyCode::Char('o'),
+                input::KeyModifiers::NONE,
+            )),
+            &State::default(),
+        );
+        assert_eq!(view.sort, Sort::Peak);
+        view.reset();
+        assert!(view.peaks.is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! The custom views compiled into the console.
+//!
+//! To add a view, implement [`Plugin`] in a module here, gate the module on
+//! a `plugin-*` feature in `Cargo.toml`, and add the view to
+//! [`registered`]. Building the console with the feature enabled, such as
+//! with `cargo install tokio-console --features plugin-locations`, then adds
+//! the view to it.
+use crate::view::Plugin;
+
+#[cfg(any(test, feature = "plugin-locations"))]
+mod locations;
+
+/// Every key which has a built-in binding in some view.
+///
+/// A plugin shown by one of these keys would either never be shown, or would
+/// take the key from the built-in binding in the views where the plugin's key
+/// is checked first.
+const BUILT_IN_KEYS: &str =
+    " !+-/0123456789<=>?ABCDEFGHIJKLMNPRSTVWXYZ[]abcdefghijklmnopqrstuvwxyz|";
+
+/// Returns the views enabled by this build's features, in the order their
+/// keys are checked.
+///
+/// Views whose key has a built-in binding are left out.
+#[allow(unused_mut, clippy::vec_init_then_push)]
+pub(crate) fn registered() -> Vec<Box<dyn Plugin>> {
+    let mut plugins: Vec<Box<dyn Plugin>> = Vec::new();
+    #[cfg(feature = "plugin-locations")]
+    plugins.push(Box::new(locations::LocationsView::default()));
+    without_built_in_keys(plugins)
+}
+
+fn without_built_in_keys(mut plugins: Vec<Box<dyn Plugin>>) -> Vec<Box<dyn Plugin>> {
+    plugins.retain(|plugin| {
+        let key = plugin.key();
+        let built_in = BUILT_IN_KEYS.contains(key);
+        if built_in {
+            tracing::warn!(
+                plugin = plugin.name(),
+                %key,
+                "ignoring a plugin whose key has a built-in binding"
+            );
+        }
+        !built_in
+    });
+    plugins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::State;
+
+    struct KeyOnly(char);
+
+    impl Plugin for KeyOnly {
+        fn name(&self) -> &'static str {
+            "Key Only"
+        }
+
+        fn key(&self) -> char {
+            self.0
+        }
+
+        fn render(
+            &mut self,
+            _: &crate::view::Styles,
+            _: &mut ratatui::terminal::Frame,
+            _: ratatui::layout::Rect,
+            _: &State,
+        ) {
+        }
+    }
+
+    #[test]
+    fn plugins_with_built_in_keys_are_rejected() {
+        let plugins = without_built_in_keys(vec![
+            Box::new(KeyOnly('L')),
+            Box::new(KeyOnly('U')),
+            Box::new(KeyOnly('q')),
+        ]);
+        let keys = plugins
+            .iter()
+            .map(|plugin| plugin.key())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ['U']);
+    }
+
+    #[test]
+    fn plugin_keys_are_not_built_in() {
+        let locations = locations::LocationsView::default();
+        assert!(!BUILT_IN_KEYS.contains(locations.key()));
+    }
+}
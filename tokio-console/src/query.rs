@@ -0,0 +1,1050 @@
+//! The task query language, which filters tasks in the task list, and
+//! selects them for the `tokio-console query` subcommand, which prints the
+//! tasks in a target's current state that match a small SQL-like query.
+//!
+//! Queries look like
+//! `select id, name, busy from tasks where state = "idle" and wakers = 0 order by idle desc limit 20`.
+//! Every clause but `select` and `from` is optional, and `select *` selects
+//! the columns shown in the task list. A query may also be just a [`Filter`],
+//! such as `state == idle && busy > 1s && target ~ "hyper"`, which selects
+//! every task that matches it.
+use crate::{
+    conn, export,
+    state::{tasks::Task, State},
+    view,
+};
+use color_eyre::{eyre::eyre, Help};
+use console_api::instrument::Update;
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Peekable,
+    str::{Chars, FromStr},
+    time::{Duration, SystemTime},
+};
+use tonic::transport::Uri;
+
+/// How a query's results are printed.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) enum Format {
+    /// An aligned table, with a header.
+    #[default]
+    Table,
+    /// A JSON array with an object for each row. Durations are in seconds.
+    Json,
+}
+
+/// A parsed query.
+#[derive(Clone, Debug)]
+pub(crate) struct Query {
+    /// The query as it was written.
+    source: String,
+    columns: Vec<Column>,
+    filter: Option<Cond>,
+    order_by: Vec<(Column, Order)>,
+    limit: Option<usize>,
+}
+
+/// A condition which tasks are filtered by, such as
+/// `state == idle && busy > 1s && target ~ "hyper"`.
+///
+/// Columns are compared with values with `==` (or `=`), `!=`, `<`, `<=`, `>`
+/// and `>=`, and are matched against regular expressions with `~` and `!~`.
+/// Conditions are combined with `&&` (or `and`), `||` (or `or`) and `!` (or
+/// `not`), and parentheses. Words compared with the text columns `name`,
+/// `state`, `target`, `location` and `kind` are text, as though they were
+/// quoted.
+#[derive(Clone, Debug)]
+pub(crate) struct Filter {
+    /// The filter as it was written.
+    source: String,
+    cond: Cond,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Column {
+    Id,
+    Name,
+    State,
+    Target,
+    Location,
+    Kind,
+    Total,
+    Busy,
+    Scheduled,
+    Idle,
+    BusyPercent,
+    ScheduledPercent,
+    Polls,
+    Wakes,
+    Wakers,
+    SelfWakes,
+    Warnings,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Order {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cond {
+    Compare(Operand, CompareOp, Operand),
+    /// Whether a column's value matches a pattern, or doesn't if the flag
+    /// is `false`.
+    Match(Column, Pattern, bool),
+    Not(Box<Cond>),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A regular expression which a column's value is matched against.
+#[derive(Clone, Debug)]
+struct Pattern(regex::Regex);
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Column(Column),
+    Value(Value),
+}
+
+/// A column's value for a task, or a literal in a query.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    /// The task doesn't have a value for the column, such as a task without a
+    /// name. Null is not equal to anything, and sorts first.
+    Null,
+    Number(f64),
+    Duration(Duration),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Duration(Duration),
+    Text(String),
+    Symbol(&'static str),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+/// Connects to `target`, waits for its current state, and prints the tasks
+/// which match `query` in `format`.
+///
+/// Returns an error if the target doesn't send its state within `timeout`.
+pub(crate) async fn run(
+    target: Uri,
+    query: &Query,
+    format: Format,
+    timeout: Duration,
+    styles: &view::Styles,
+) -> color_eyre::Result<()> {
+    let update = first_update(target, timeout).await?;
+    let mut state = State::default();
+    // The task list is the only view whose data is kept without being
+    // displayed.
+    state.update(styles, &view::ViewState::TasksList, update);
+    let rows = query.rows(&state);
+    let output = match format {
+        Format::Table => query.table(&rows),
+        Format::Json => query.json(&rows),
+    };
+    print!("{output}");
+    Ok(())
+}
+
+/// Connects to `target` and returns the first update it sends, which
+/// includes everything it's currently tracking.
+///
+/// Returns an error if the target doesn't send an update within `timeout`.
+pub(crate) async fn first_update(target: Uri, timeout: Duration) -> color_eyre::Result<Update> {
+    let mut conn = conn::Connection::new(target);
+    tokio::time::timeout(timeout, async {
+        loop {
+            if let conn::Message::Update(update) = conn.next_message().await {
+                return update;
+            }
+        }
+    })
+    .await
+    .map_err(|_| eyre!("timed out after {}", humantime::format_duration(timeout)))
+    .suggestion("is the application running, and is it instrumented with console-subscriber?")
+}
+
+/// Returns the value of every column for `task`, by the column's name, as
+/// JSON.
+pub(crate) fn columns_json(
+    task: &Task,
+    now: SystemTime,
+) -> serde_json::Map<String, serde_json::Value> {
+    Column::ALL
+        .iter()
+        .map(|&(name, column)| (name.to_owned(), column.value(task, now).to_json()))
+        .collect()
+}
+
+// === impl Query ===
+
+impl Query {
+    /// The columns which `select *` selects.
+    const ALL: &'static [Column] = &[
+        Column::Id,
+        Column::Name,
+        Column::State,
+        Column::Total,
+        Column::Busy,
+        Column::Scheduled,
+        Column::Idle,
+        Column::Polls,
+        Column::Kind,
+        Column::Location,
+    ];
+
+    /// Returns a query selecting every task which matches `filter`.
+    fn filtered(source: &str, filter: Cond) -> Self {
+        Self {
+            source: source.to_owned(),
+            columns: Self::ALL.to_vec(),
+            filter: Some(filter),
+            order_by: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Returns the selected columns of the tasks in `state` which match the
+    /// query, in order.
+    fn rows(&self, state: &State) -> Vec<Vec<Value>> {
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let tasks = state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+            .collect::<Vec<_>>();
+        let mut tasks = tasks
+            .iter()
+            .map(|task| task.borrow())
+            .filter(|task| {
+                self.filter
+                    .as_ref()
+                    .map_or(true, |filter| filter.matches(task, now))
+            })
+            .collect::<Vec<_>>();
+        // Order by the task's ID last, so that the output doesn't depend on
+        // the order the tasks are stored in.
+        tasks.sort_by_key(|task| task.id());
+        tasks.sort_by(|a, b| {
+            self.order_by
+                .iter()
+                .map(|&(column, order)| {
+                    let ordering = column.value(a, now).order(&column.value(b, now));
+                    match order {
+                        Order::Asc => ordering,
+                        Order::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        tasks
+            .into_iter()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|task| {
+                self.columns
+                    .iter()
+                    .map(|column| column.value(&task, now))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn table(&self, rows: &[Vec<Value>]) -> String {
+        let names = self.columns.iter().map(|column| column.name());
+        export::text(names, rows)
+    }
+
+    /// Returns `rows` as a JSON array, with an object on each line whose
+    /// keys are in the order they were selected.
+    fn json(&self, rows: &[Vec<Value>]) -> String {
+        let names = self.columns.iter().map(|column| column.name());
+        export::json(names, rows)
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s)?;
+        // A query which doesn't start with `select` is just a filter.
+        if !matches!(parser.tokens.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case("select"))
+        {
+            return Ok(Self::filtered(s.trim(), parser.filter()?));
+        }
+        parser.query(s.trim())
+    }
+}
+
+// Queries are compared by how they're written, since the parsed query
+// contains floating-point numbers.
+impl PartialEq for Query {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Query {}
+
+// === impl Filter ===
+
+impl Filter {
+    /// Returns `true` if `task` matches the filter at `now`.
+    pub(crate) fn matches(&self, task: &Task, now: SystemTime) -> bool {
+        self.cond.matches(task, now)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            source: s.trim().to_owned(),
+            cond: Parser::new(s)?.filter()?,
+        })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// === impl Column ===
+
+impl Column {
+    const ALL: &'static [(&'static str, Column)] = &[
+        ("id", Self::Id),
+        ("name", Self::Name),
+        ("state", Self::State),
+        ("target", Self::Target),
+        ("location", Self::Location),
+        ("kind", Self::Kind),
+        ("total", Self::Total),
+        ("busy", Self::Busy),
+        ("scheduled", Self::Scheduled),
+        ("idle", Self::Idle),
+        ("busy_percent", Self::BusyPercent),
+        ("scheduled_percent", Self::ScheduledPercent),
+        ("polls", Self::Polls),
+        ("wakes", Self::Wakes),
+        ("wakers", Self::Wakers),
+        ("self_wakes", Self::SelfWakes),
+        ("warnings", Self::Warnings),
+    ];
+
+    fn from_name(name: &str) -> Result<Self, ParseError> {
+        Self::ALL
+            .iter()
+            .find(|(column_name, _)| column_name.eq_ignore_ascii_case(name))
+            .map(|&(_, column)| column)
+            .ok_or_else(|| {
+                let columns = Self::ALL
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                ParseError(format!(
+                    "unknown column `{name}`, expected one of: {columns}"
+                ))
+            })
+    }
+
+    /// Returns `true` if the column's values are text, so that words
+    /// compared with it are too.
+    fn is_text(self) -> bool {
+        matches!(
+            self,
+            Self::Name | Self::State | Self::Target | Self::Location | Self::Kind
+        )
+    }
+
+    fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|&&(_, column)| column == self)
+            .map(|&(name, _)| name)
+            .expect("every column has a name")
+    }
+
+    fn value(self, task: &Task, now: SystemTime) -> Value {
+        match self {
+            Self::Id => Value::id(task.id_str()),
+            Self::Name => task
+                .name()
+                .map_or(Value::Null, |name| Value::Text(name.to_owned())),
+            Self::State => Value::Text(task.state().description().to_owned()),
+            Self::Target => Value::Text(task.target().to_owned()),
+            Self::Location => Value::Text(task.location().to_owned()),
+            Self::Kind => Value::Text(task.kind().to_owned()),
+            Self::Total => Value::Duration(task.total(now)),
+            Self::Busy => Value::Duration(task.busy(now)),
+            Self::Scheduled => Value::Duration(task.scheduled(now)),
+            Self::Idle => Value::Duration(task.idle(now)),
+            Self::BusyPercent => Value::Number(task.busy_percent(now)),
+            Self::ScheduledPercent => Value::Number(task.scheduled_percent(now)),
+            Self::Polls => Value::Number(task.total_polls() as f64),
+            Self::Wakes => Value::Number(task.wakes() as f64),
+            Self::Wakers => Value::Number(task.waker_count() as f64),
+            Self::SelfWakes => Value::Number(task.self_wakes() as f64),
+            Self::Warnings => Value::Number(task.warnings().len() as f64),
+        }
+    }
+}
+
+// === impl Cond ===
+
+impl Cond {
+    fn matches(&self, task: &Task, now: SystemTime) -> bool {
+        match self {
+            Self::Compare(lhs, op, rhs) => {
+                let Some(ordering) = lhs.value(task, now).compare(&rhs.value(task, now)) else {
+                    return false;
+                };
+                match op {
+                    CompareOp::Eq => ordering.is_eq(),
+                    CompareOp::Ne => ordering.is_ne(),
+                    CompareOp::Lt => ordering.is_lt(),
+                    CompareOp::Le => ordering.is_le(),
+                    CompareOp::Gt => ordering.is_gt(),
+                    CompareOp::Ge => ordering.is_ge(),
+                }
+            }
+            Self::Match(column, pattern, matches) => {
+                let value = match column.value(task, now) {
+                    Value::Null => return false,
+                    value => value.to_string(),
+                };
+                pattern.0.is_match(&value) == *matches
+            }
+            Self::Not(cond) => !cond.matches(task, now),
+            Self::And(lhs, rhs) => lhs.matches(task, now) && rhs.matches(task, now),
+            Self::Or(lhs, rhs) => lhs.matches(task, now) || rhs.matches(task, now),
+        }
+    }
+}
+
+impl Pattern {
+    /// Compiles `pattern`, which is case-insensitive unless it contains an
+    /// uppercase letter, as the task list's search is.
+    fn new(pattern: &str) -> Result<Self, ParseError> {
+        let ignore_case = !pattern.chars().any(char::is_uppercase);
+        regex::RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(Self)
+            .map_err(|e| ParseError(format!("invalid pattern `{pattern}` in query: {e}")))
+    }
+}
+
+// Patterns are compared by how they're written.
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Operand {
+    fn value(&self, task: &Task, now: SystemTime) -> Value {
+        match self {
+            Self::Column(column) => column.value(task, now),
+            Self::Value(value) => value.clone(),
+        }
+    }
+}
+
+// === impl Value ===
+
+impl Value {
+    /// Returns an ID's value, which is a number unless the ID isn't one.
+    pub(crate) fn id(id: &str) -> Self {
+        id.parse()
+            .map_or_else(|_| Self::Text(id.to_owned()), Self::Number)
+    }
+
+    /// Compares two values, or returns `None` if they can't be compared.
+    ///
+    /// Durations are compared with numbers as seconds, and text is only
+    /// compared with text.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (a, b) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        }
+    }
+
+    /// Orders values for sorting, with values which can't be compared, such
+    /// as nulls, first.
+    fn order(&self, other: &Self) -> Ordering {
+        self.compare(other).unwrap_or_else(|| {
+            let comparable = |value: &Self| value.compare(value).is_some();
+            comparable(self).cmp(&comparable(other))
+        })
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(value) => Some(*value),
+            Self::Duration(value) => Some(value.as_secs_f64()),
+            Self::Null | Self::Text(_) => None,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Null => serde_json::Value::Null,
+            // Whole numbers, such as IDs and counts, are written as integers.
+            Self::Number(value) if value.fract() == 0.0 && value.abs() < 2f64.powi(53) => {
+                (*value as i64).into()
+            }
+            Self::Number(value) => (*value).into(),
+            Self::Duration(value) => value.as_secs_f64().into(),
+            Self::Text(value) => value.clone().into(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("-"),
+            Self::Number(value) if value.fract() == 0.0 => write!(f, "{value}"),
+            Self::Number(value) => write!(f, "{value:.2}"),
+            Self::Duration(value) => write!(f, "{value:.2?}"),
+            Self::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+// === impl Lexer ===
+
+impl Lexer<'_> {
+    fn tokens(mut self) -> Result<Vec<Token>, ParseError> {
+        // Two-character symbols are tried first, so that `!=` isn't taken for
+        // `!`.
+        const SYMBOLS: &[&str] = &[
+            "==", "!=", "!~", "<=", ">=", "&&", "||", "=", "<", ">", "~", "!", ",", "(", ")", "*",
+        ];
+        let mut tokens = Vec::new();
+        loop {
+            while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&c) = self.chars.peek() else {
+                return Ok(tokens);
+            };
+            let token = if c.is_ascii_alphabetic() || c == '_' {
+                Token::Word(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'))
+            } else if c.is_ascii_digit() || c == '.' {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                let unit = self.take_while(|c| c.is_ascii_alphabetic());
+                if unit.is_empty() {
+                    Token::Number(number.parse().map_err(|e| {
+                        ParseError(format!("invalid number `{number}` in query: {e}"))
+                    })?)
+                } else {
+                    let duration = format!("{number}{unit}");
+                    Token::Duration(humantime::parse_duration(&duration).map_err(|e| {
+                        ParseError(format!("invalid duration `{duration}` in query: {e}"))
+                    })?)
+                }
+            } else if c == '"' || c == '\'' {
+                self.chars.next();
+                let text = self.take_while(|next| next != c);
+                if self.chars.next() != Some(c) {
+                    return Err(ParseError(format!("unclosed string `{c}{text}` in query")));
+                }
+                Token::Text(text)
+            } else {
+                let rest = self.chars.clone().collect::<String>();
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|symbol| rest.starts_with(**symbol))
+                    .ok_or_else(|| ParseError(format!("unexpected `{c}` in query")))?;
+                for _ in symbol.chars() {
+                    self.chars.next();
+                }
+                Token::Symbol(symbol)
+            };
+            tokens.push(token);
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.chars.next_if(|&c| f(c)) {
+            taken.push(c);
+        }
+        taken
+    }
+}
+
+// === impl Parser ===
+
+impl Parser {
+    fn new(s: &str) -> Result<Self, ParseError> {
+        let tokens = Lexer {
+            chars: s.chars().peekable(),
+        }
+        .tokens()?;
+        Ok(Self {
+            tokens: tokens.into_iter().peekable(),
+        })
+    }
+
+    /// Parses a whole filter, which must be all that's left.
+    fn filter(&mut self) -> Result<Cond, ParseError> {
+        if self.tokens.peek().is_none() {
+            return Err(ParseError("the filter is empty".to_owned()));
+        }
+        let cond = self.or()?;
+        match self.tokens.next() {
+            None => Ok(cond),
+            Some(token) => Err(ParseError(format!("unexpected {token} in query"))),
+        }
+    }
+
+    fn query(&mut self, source: &str) -> Result<Query, ParseError> {
+        self.expect_keyword("select")?;
+        let columns = if self.eat_symbol("*") {
+            Query::ALL.to_vec()
+        } else {
+            let mut columns = vec![self.column()?];
+            while self.eat_symbol(",") {
+                columns.push(self.column()?);
+            }
+            columns
+        };
+
+        self.expect_keyword("from")?;
+        match self.tokens.next() {
+            Some(Token::Word(table)) if table.eq_ignore_ascii_case("tasks") => {}
+            Some(Token::Word(table)) => {
+                return Err(ParseError(format!(
+                    "unknown table `{table}`, expected `tasks`"
+                )))
+            }
+            _ => return Err(ParseError("expected a table after `from`".to_owned())),
+        }
+
+        let filter = if self.eat_keyword("where") {
+            Some(self.or()?)
+        } else {
+            None
+        };
+
+        let mut order_by = Vec::new();
+        if self.eat_keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let column = self.column()?;
+                let order = if self.eat_keyword("desc") {
+                    Order::Desc
+                } else {
+                    self.eat_keyword("asc");
+                    Order::Asc
+                };
+                order_by.push((column, order));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = if self.eat_keyword("limit") {
+            match self.tokens.next() {
+                Some(Token::Number(limit)) if limit.fract() == 0.0 && limit >= 0.0 => {
+                    Some(limit as usize)
+                }
+                _ => return Err(ParseError("expected a number after `limit`".to_owned())),
+            }
+        } else {
+            None
+        };
+
+        match self.tokens.next() {
+            None => Ok(Query {
+                source: source.to_owned(),
+                columns,
+                filter,
+                order_by,
+                limit,
+            }),
+            Some(token) => Err(ParseError(format!("unexpected {token} in query"))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Cond, ParseError> {
+        let mut lhs = self.and()?;
+        while self.eat_keyword("or") || self.eat_symbol("||") {
+            lhs = Cond::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Cond, ParseError> {
+        let mut lhs = self.not()?;
+        while self.eat_keyword("and") || self.eat_symbol("&&") {
+            lhs = Cond::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Cond, ParseError> {
+        if self.eat_keyword("not") || self.eat_symbol("!") {
+            return Ok(Cond::Not(Box::new(self.not()?)));
+        }
+        if self.eat_symbol("(") {
+            let cond = self.or()?;
+            if !self.eat_symbol(")") {
+                return Err(ParseError("expected `)` in query".to_owned()));
+            }
+            return Ok(cond);
+        }
+        let lhs = self.operand_token()?;
+        let op = match self.tokens.next() {
+            Some(Token::Symbol("=" | "==")) => CompareOp::Eq,
+            Some(Token::Symbol("!=")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            Some(Token::Symbol(op @ ("~" | "!~"))) => {
+                let column = match lhs {
+                    Token::Word(name) => Column::from_name(&name)?,
+                    token => {
+                        return Err(ParseError(format!(
+                            "expected a column before `{op}` in query, found {token}"
+                        )))
+                    }
+                };
+                let pattern = match self.tokens.next() {
+                    Some(Token::Text(pattern) | Token::Word(pattern)) => Pattern::new(&pattern)?,
+                    _ => {
+                        return Err(ParseError(format!(
+                            "expected a pattern after `{op}` in query"
+                        )))
+                    }
+                };
+                return Ok(Cond::Match(column, pattern, op == "~"));
+            }
+            _ => {
+                return Err(ParseError(
+                    "expected one of `==`, `!=`, `<`, `<=`, `>`, `>=`, `~` or `!~` in query"
+                        .to_owned(),
+                ))
+            }
+        };
+        let rhs = self.operand_token()?;
+        // A word compared with a text column is text, such as the `idle` in
+        // `state == idle`, even if it's also a column's name.
+        let is_text = |token: &Token| match token {
+            Token::Word(name) => Column::from_name(name).is_ok_and(Column::is_text),
+            _ => false,
+        };
+        let (lhs_text, rhs_text) = (is_text(&lhs), is_text(&rhs));
+        Ok(Cond::Compare(
+            operand(lhs, rhs_text)?,
+            op,
+            operand(rhs, lhs_text)?,
+        ))
+    }
+
+    /// Returns the next token, which is turned into an operand once what
+    /// it's compared with is known.
+    fn operand_token(&mut self) -> Result<Token, ParseError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| ParseError("query ended early".to_owned()))
+    }
+
+    fn column(&mut self) -> Result<Column, ParseError> {
+        match self.tokens.next() {
+            Some(Token::Word(name)) => Column::from_name(&name),
+            Some(token) => Err(ParseError(format!(
+                "expected a column in query, found {token}"
+            ))),
+            None => Err(ParseError("query ended early".to_owned())),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(
+                |token| matches!(token, Token::Word(word) if word.eq_ignore_ascii_case(keyword)),
+            )
+            .is_some()
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            return Ok(());
+        }
+        match self.tokens.peek() {
+            Some(token) => Err(ParseError(format!(
+                "expected `{keyword}` in query, found {token}"
+            ))),
+            None => Err(ParseError(format!("expected `{keyword}` in query"))),
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &'static str) -> bool {
+        self.tokens
+            .next_if(|token| *token == Token::Symbol(symbol))
+            .is_some()
+    }
+}
+
+/// Returns the operand which `token` is, where words are text rather than
+/// columns if they're compared with a text column.
+fn operand(token: Token, compared_with_text: bool) -> Result<Operand, ParseError> {
+    match token {
+        Token::Word(word) if compared_with_text => Ok(Operand::Value(Value::Text(word))),
+        Token::Word(name) => Column::from_name(&name).map(Operand::Column),
+        Token::Number(value) => Ok(Operand::Value(Value::Number(value))),
+        Token::Duration(value) => Ok(Operand::Value(Value::Duration(value))),
+        Token::Text(value) => Ok(Operand::Value(Value::Text(value))),
+        Token::Symbol(symbol) => Err(ParseError(format!(
+            "expected a column or value in query, found `{symbol}`"
+        ))),
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "`{word}`"),
+            Self::Number(value) => write!(f, "`{value}`"),
+            Self::Duration(value) => write!(f, "`{}`", humantime::format_duration(*value)),
+            Self::Text(value) => write!(f, "{value:?}"),
+            Self::Symbol(symbol) => write!(f, "`{symbol}`"),
+        }
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Query {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_full_query() {
+        let query = parse(
+            r#"select id, name, busy from tasks where state = "idle" and wakers = 0 order by idle desc limit 20"#,
+        );
+        assert_eq!(query.columns, [Column::Id, Column::Name, Column::Busy]);
+        assert_eq!(
+            query.filter,
+            Some(Cond::And(
+                Box::new(Cond::Compare(
+                    Operand::Column(Column::State),
+                    CompareOp::Eq,
+                    Operand::Value(Value::Text("idle".to_owned())),
+                )),
+                Box::new(Cond::Compare(
+                    Operand::Column(Column::Wakers),
+                    CompareOp::Eq,
+                    Operand::Value(Value::Number(0.0)),
+                )),
+            ))
+        );
+        assert_eq!(query.order_by, [(Column::Idle, Order::Desc)]);
+        assert_eq!(query.limit, Some(20));
+    }
+
+    #[test]
+    fn parse_precedence_and_durations() {
+        let query = parse("SELECT * FROM tasks WHERE not polls > 1 or busy >= 10ms and idle < 1s");
+        assert_eq!(query.columns, Query::ALL);
+        let compare = |column, op, value| {
+            Box::new(Cond::Compare(
+                Operand::Column(column),
+                op,
+                Operand::Value(value),
+            ))
+        };
+        assert_eq!(
+            query.filter,
+            Some(Cond::Or(
+                Box::new(Cond::Not(compare(
+                    Column::Polls,
+                    CompareOp::Gt,
+                    Value::Number(1.0)
+                ))),
+                Box::new(Cond::And(
+                    compare(
+                        Column::Busy,
+                        CompareOp::Ge,
+                        Value::Duration(Duration::from_millis(10))
+                    ),
+                    compare(
+                        Column::Idle,
+                        CompareOp::Lt,
+                        Value::Duration(Duration::from_secs(1))
+                    ),
+                )),
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for query in [
+            "",
+            "select",
+            "select id",
+            "select id from resources",
+            "select latency from tasks",
+            "select id from tasks where",
+            "select id from tasks where state",
+            "select id from tasks where state = \"idle",
+            "select id from tasks limit -1",
+            "select id from tasks limit 1 extra",
+        ] {
+            assert!(
+                query.parse::<Query>().is_err(),
+                "{query:?} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_filters() {
+        let source = r#"state == idle && busy > 1s && target ~ "hyper""#;
+        let filter = source.parse::<Filter>().unwrap();
+        assert_eq!(
+            filter.cond,
+            Cond::And(
+                Box::new(Cond::And(
+                    Box::new(Cond::Compare(
+                        Operand::Column(Column::State),
+                        CompareOp::Eq,
+                        Operand::Value(Value::Text("idle".to_owned())),
+                    )),
+                    Box::new(Cond::Compare(
+                        Operand::Column(Column::Busy),
+                        CompareOp::Gt,
+                        Operand::Value(Value::Duration(Duration::from_secs(1))),
+                    )),
+                )),
+                Box::new(Cond::Match(
+                    Column::Target,
+                    Pattern::new("hyper").unwrap(),
+                    true
+                )),
+            )
+        );
+        assert_eq!(filter.to_string(), source);
+
+        let filter = "!(name !~ worker || polls < 2)".parse::<Filter>().unwrap();
+        assert!(matches!(filter.cond, Cond::Not(_)));
+
+        for filter in [
+            "",
+            "busy >",
+            "busy ~ 1s",
+            "1 ~ name",
+            r#"name ~ "(""#,
+            "busy > 1s )",
+        ] {
+            assert!(
+                filter.parse::<Filter>().is_err(),
+                "{filter:?} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn queries_can_be_filters() {
+        let query = parse("state != idle || wakers == 0");
+        assert_eq!(query.columns, Query::ALL);
+        assert!(matches!(query.filter, Some(Cond::Or(..))));
+        assert_eq!(query.source, "state != idle || wakers == 0");
+    }
+
+    #[test]
+    fn compare_values() {
+        let secs = Value::Duration(Duration::from_millis(1500));
+        assert_eq!(secs.compare(&Value::Number(1.0)), Some(Ordering::Greater));
+        assert_eq!(
+            Value::Text("idle".to_owned()).compare(&Value::Number(1.0)),
+            None
+        );
+        assert_eq!(Value::Null.compare(&Value::Null), None);
+        assert_eq!(Value::Null.order(&Value::Number(1.0)), Ordering::Less);
+    }
+
+    #[test]
+    fn print_rows() {
+        let query = parse("select id, name, busy from tasks");
+        let rows = [
+            vec![
+                Value::Number(1.0),
+                Value::Text("worker".to_owned()),
+                Value::Duration(Duration::from_millis(1500)),
+            ],
+            vec![
+                Value::Number(12.0),
+                Value::Null,
+                Value::Duration(Duration::from_micros(3)),
+            ],
+        ];
+        assert_eq!(
+            query.table(&rows),
+            "id  name    busy\n\
+             1   worker  1.50s\n\
+             12  -       3.00µs\n"
+        );
+        assert_eq!(
+            query.json(&rows),
+            "[\n  \
+             {\"id\": 1, \"name\": \"worker\", \"busy\": 1.5},\n  \
+             {\"id\": 12, \"name\": null, \"busy\": 3e-6}\n\
+             ]\n"
+        );
+        assert_eq!(query.json(&[]), "[]\n");
+    }
+}
@@ -0,0 +1,527 @@
+//! Serving the instrument API from the console, so that other consoles can
+//! watch its target through it.
+//!
+//! The relay keeps the latest state of every task, resource and async op it
+//! has been sent, so that a console which connects to it gets everything in
+//! its first update, as it would from the target itself, and is then sent
+//! each update as the relay receives it. Consoles watching a relay can watch
+//! tasks' details, which are requested from the target, but can't pause the
+//! target, since the relay shares the connection, not control of the target.
+use crate::{auth, conn};
+use color_eyre::eyre::{eyre, WrapErr};
+use console_api::{
+    async_ops,
+    instrument::{
+        instrument_server::{Instrument, InstrumentServer},
+        AbortTaskRequest, AbortTaskResponse, DumpTaskRequest, InstrumentRequest, PauseRequest,
+        PauseResponse, ResumeRequest, ResumeResponse, ServerInfo, ServerInfoRequest,
+        State as InstrumentState, StateRequest, StepRequest, StepResponse, TaskDetailsRequest,
+        TaskDump, TasksDetailsRequest, Update,
+    },
+    register_metadata::NewMetadata,
+    resources, tasks, RegisterMetadata,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, watch},
+};
+use tonic::{
+    transport::{server::TcpIncoming, Uri},
+    Request, Response, Status,
+};
+
+/// How many updates can be waiting to be sent to a console watching the
+/// relay before it is disconnected, so that it catches up by reconnecting.
+const BUFFER: usize = 64;
+
+/// A stream of tasks' details, as a relay serves it.
+pub(crate) type DetailsStream = BoxStream<'static, Result<tasks::TaskDetails, Status>>;
+
+/// The relay's side of the console, which is sent everything the console
+/// receives from its target.
+#[derive(Clone)]
+pub(crate) struct Relay {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// Where a relay requests tasks' details from.
+#[tonic::async_trait]
+pub(crate) trait Upstream: Send + Sync + 'static {
+    async fn watch_task_details(
+        &self,
+        request: TaskDetailsRequest,
+    ) -> Result<DetailsStream, Status>;
+
+    async fn watch_tasks_details(
+        &self,
+        request: TasksDetailsRequest,
+    ) -> Result<DetailsStream, Status>;
+}
+
+/// A single target, whose tasks' details are requested from it directly.
+pub(crate) struct Target(pub(crate) Uri);
+
+struct Inner {
+    /// Where tasks' details are requested from.
+    upstream: Arc<dyn Upstream>,
+    retain_for: Option<Duration>,
+    snapshot: Snapshot,
+    /// Sends each update to the consoles watching the relay.
+    ///
+    /// This is replaced when the console connects to another target, which
+    /// disconnects them, so that they get the new target's state when they
+    /// reconnect.
+    updates: broadcast::Sender<Update>,
+    state: watch::Sender<InstrumentState>,
+}
+
+/// The latest state of everything the target has reported.
+#[derive(Default)]
+struct Snapshot {
+    now: Option<prost_types::Timestamp>,
+    metadata: HashMap<u64, NewMetadata>,
+    tasks: Entities<tasks::Task, tasks::Stats>,
+    resources: Entities<resources::Resource, resources::Stats>,
+    async_ops: Entities<async_ops::AsyncOp, async_ops::Stats>,
+}
+
+/// One kind of entity, and the latest stats of each of them, by ID.
+struct Entities<T, S> {
+    new: HashMap<u64, T>,
+    stats: HashMap<u64, S>,
+    dropped_events: u64,
+}
+
+impl Relay {
+    /// Listens for consoles on `addr`, relaying the updates it's sent, and
+    /// the task details from `upstream`, to them.
+    ///
+    /// The relay remembers completed tasks, dropped resources and dropped
+    /// async ops for `retain_for`, or forever if it's `None`.
+    pub(crate) async fn serve(
+        addr: SocketAddr,
+        upstream: impl Upstream,
+        retain_for: Option<Duration>,
+    ) -> color_eyre::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .wrap_err_with(|| format!("could not listen for relay connections on {addr}"))?;
+        let incoming = TcpIncoming::from_listener(listener, true, None).map_err(|e| eyre!(e))?;
+        let relay = Self {
+            inner: Arc::new(Mutex::new(Inner {
+                upstream: Arc::new(upstream),
+                retain_for,
+                snapshot: Snapshot::default(),
+                updates: broadcast::channel(BUFFER).0,
+                state: watch::channel(InstrumentState::default()).0,
+            })),
+        };
+        let service = InstrumentServer::new(relay.clone());
+        tokio::spawn(async move {
+            let served = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming)
+                .await;
+            if let Err(error) = served {
+                tracing::error!(%error, "relay server failed");
+            }
+        });
+        tracing::info!(%addr, "relaying the target");
+        Ok(relay)
+    }
+
+    /// Passes on a message from the target to the consoles watching the relay.
+    pub(crate) fn publish(&self, message: &conn::Message) {
+        let mut inner = self.inner.lock().unwrap();
+        match message {
+            conn::Message::Update(update) => {
+                let retain_for = inner.retain_for;
+                inner.snapshot.merge(update, retain_for);
+                let update = Update {
+                    // The lag is how far the relay has fallen behind, which
+                    // the consoles watching it don't need to know.
+                    lag: None,
+                    ..update.clone()
+                };
+                // It's fine if no consoles are watching.
+                let _ = inner.updates.send(update);
+            }
+            conn::Message::State(state) => {
+                inner.state.send_replace(state.clone());
+            }
+        }
+    }
+
+    /// Starts relaying a different target, disconnecting every console
+    /// watching the relay.
+    pub(crate) fn reset(&self, target: Uri) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.upstream = Arc::new(Target(target));
+        inner.snapshot = Snapshot::default();
+        inner.updates = broadcast::channel(BUFFER).0;
+        inner.state.send_replace(InstrumentState::default());
+    }
+
+    fn upstream(&self) -> Arc<dyn Upstream> {
+        self.inner.lock().unwrap().upstream.clone()
+    }
+}
+
+// === impl Target ===
+
+impl Target {
+    pub(crate) async fn client(&self) -> Result<auth::Client, Status> {
+        let channel = conn::connect_channel(&self.0).await.map_err(|error| {
+            Status::unavailable(format!("could not connect to {}: {error}", self.0))
+        })?;
+        Ok(auth::client(channel))
+    }
+}
+
+#[tonic::async_trait]
+impl Upstream for Target {
+    async fn watch_task_details(
+        &self,
+        request: TaskDetailsRequest,
+    ) -> Result<DetailsStream, Status> {
+        let details = self.client().await?.watch_task_details(request).await?;
+        Ok(details.into_inner().boxed())
+    }
+
+    async fn watch_tasks_details(
+        &self,
+        request: TasksDetailsRequest,
+    ) -> Result<DetailsStream, Status> {
+        let details = self.client().await?.watch_tasks_details(request).await?;
+        Ok(details.into_inner().boxed())
+    }
+}
+
+#[tonic::async_trait]
+impl Instrument for Relay {
+    type WatchUpdatesStream = BoxStream<'static, Result<Update, Status>>;
+    type WatchTaskDetailsStream = DetailsStream;
+    type WatchTasksDetailsStream = DetailsStream;
+    type WatchStateStream = BoxStream<'static, Result<InstrumentState, Status>>;
+
+    async fn watch_updates(
+        &self,
+        _: Request<InstrumentRequest>,
+    ) -> Result<Response<Self::WatchUpdatesStream>, Status> {
+        // Subscribe while the snapshot is locked, so that no update is missed
+        // or sent twice.
+        let (snapshot, updates) = {
+            let inner = self.inner.lock().unwrap();
+            (inner.snapshot.to_update(), inner.updates.subscribe())
+        };
+        let updates = stream::unfold(updates, |mut updates| async move {
+            match updates.recv().await {
+                Ok(update) => Some((Ok(update), updates)),
+                Err(broadcast::error::RecvError::Lagged(_)) => Some((
+                    Err(Status::resource_exhausted(
+                        "fell behind the relay's updates, reconnect to catch up",
+                    )),
+                    updates,
+                )),
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        })
+        // Stop after an error, since the updates after it can't be applied.
+        .scan(false, |failed, update| {
+            let update = (!*failed).then_some(update);
+            *failed = matches!(update, Some(Err(_)));
+            async move { update }
+        });
+        Ok(Response::new(
+            stream::once(async move { Ok(snapshot) })
+                .chain(updates)
+                .boxed(),
+        ))
+    }
+
+    async fn watch_task_details(
+        &self,
+        request: Request<TaskDetailsRequest>,
+    ) -> Result<Response<Self::WatchTaskDetailsStream>, Status> {
+        let details = self
+            .upstream()
+            .watch_task_details(request.into_inner())
+            .await?;
+        Ok(Response::new(details))
+    }
+
+    async fn watch_tasks_details(
+        &self,
+        request: Request<TasksDetailsRequest>,
+    ) -> Result<Response<Self::WatchTasksDetailsStream>, Status> {
+        let details = self
+            .upstream()
+            .watch_tasks_details(request.into_inner())
+            .await?;
+        Ok(Response::new(details))
+    }
+
+    async fn watch_state(
+        &self,
+        _: Request<StateRequest>,
+    ) -> Result<Response<Self::WatchStateStream>, Status> {
+        let mut state = self.inner.lock().unwrap().state.subscribe();
+        state.mark_changed();
+        let states = stream::unfold(state, |mut state| async move {
+            state.changed().await.ok()?;
+            let current = state.borrow_and_update().clone();
+            Some((Ok(current), state))
+        });
+        Ok(Response::new(states.boxed()))
+    }
+
+    async fn pause(&self, _: Request<PauseRequest>) -> Result<Response<PauseResponse>, Status> {
+        Err(Status::permission_denied(
+            "the target can't be paused through a relay",
+        ))
+    }
+
+    async fn resume(&self, _: Request<ResumeRequest>) -> Result<Response<ResumeResponse>, Status> {
+        Err(Status::permission_denied(
+            "the target can't be resumed through a relay",
+        ))
+    }
+
+    async fn step(&self, _: Request<StepRequest>) -> Result<Response<StepResponse>, Status> {
+        Err(Status::permission_denied(
+            "the target can't be stepped through a relay",
+        ))
+    }
+
+    async fn dump_task(&self, _: Request<DumpTaskRequest>) -> Result<Response<TaskDump>, Status> {
+        Err(Status::unimplemented(
+            "tasks can't be dumped through a relay",
+        ))
+    }
+
+    async fn abort_task(
+        &self,
+        _: Request<AbortTaskRequest>,
+    ) -> Result<Response<AbortTaskResponse>, Status> {
+        Err(Status::permission_denied(
+            "the target's tasks can't be aborted through a relay",
+        ))
+    }
+
+    async fn get_server_info(
+        &self,
+        _: Request<ServerInfoRequest>,
+    ) -> Result<Response<ServerInfo>, Status> {
+        const CAPABILITIES: &[&str] = &[
+            "watch_updates",
+            "watch_task_details",
+            "watch_tasks_details",
+            "watch_state",
+            "get_server_info",
+        ];
+        let retain_for = self.inner.lock().unwrap().retain_for;
+        Ok(Response::new(ServerInfo {
+            api_version: console_api::VERSION.to_owned(),
+            capabilities: CAPABILITIES.iter().map(|&c| c.to_owned()).collect(),
+            retention: retain_for.and_then(|retain_for| retain_for.try_into().ok()),
+            publish_interval: None,
+        }))
+    }
+}
+
+// === impl Snapshot ===
+
+impl Snapshot {
+    fn merge(&mut self, update: &Update, retain_for: Option<Duration>) {
+        self.now = update.now;
+        if let Some(ref new_metadata) = update.new_metadata {
+            for metadata in &new_metadata.metadata {
+                if let Some(ref id) = metadata.id {
+                    self.metadata.insert(id.id, metadata.clone());
+                }
+            }
+        }
+        if let Some(ref update) = update.task_update {
+            self.tasks.merge(
+                &update.new_tasks,
+                |task| task.id.as_ref().map(|id| id.id),
+                &update.stats_update,
+                update.dropped_events,
+            );
+        }
+        if let Some(ref update) = update.resource_update {
+            self.resources.merge(
+                &update.new_resources,
+                |resource| resource.id.as_ref().map(|id| id.id),
+                &update.stats_update,
+                update.dropped_events,
+            );
+        }
+        if let Some(ref update) = update.async_op_update {
+            self.async_ops.merge(
+                &update.new_async_ops,
+                |async_op| async_op.id.as_ref().map(|id| id.id),
+                &update.stats_update,
+                update.dropped_events,
+            );
+        }
+
+        let now = self.now.and_then(|now| SystemTime::try_from(now).ok());
+        if let Some(cutoff) = now
+            .zip(retain_for)
+            .and_then(|(now, for_)| now.checked_sub(for_))
+        {
+            self.tasks
+                .retain_dropped_since(cutoff, |stats| stats.dropped_at.as_ref());
+            self.resources
+                .retain_dropped_since(cutoff, |stats| stats.dropped_at.as_ref());
+            self.async_ops
+                .retain_dropped_since(cutoff, |stats| stats.dropped_at.as_ref());
+        }
+    }
+
+    /// Returns an update with everything in the snapshot.
+    fn to_update(&self) -> Update {
+        Update {
+            now: self.now,
+            task_update: Some(tasks::TaskUpdate {
+                new_tasks: self.tasks.new.values().cloned().collect(),
+                stats_update: self.tasks.stats.clone(),
+                dropped_events: self.tasks.dropped_events,
+            }),
+            resource_update: Some(resources::ResourceUpdate {
+                new_resources: self.resources.new.values().cloned().collect(),
+                stats_update: self.resources.stats.clone(),
+                // Poll ops are events, rather than state, so only new ones
+                // are passed on.
+                new_poll_ops: Vec::new(),
+                dropped_events: self.resources.dropped_events,
+            }),
+            async_op_update: Some(async_ops::AsyncOpUpdate {
+                new_async_ops: self.async_ops.new.values().cloned().collect(),
+                stats_update: self.async_ops.stats.clone(),
+                dropped_events: self.async_ops.dropped_events,
+            }),
+            new_metadata: Some(RegisterMetadata {
+                metadata: self.metadata.values().cloned().collect(),
+            }),
+            lag: None,
+        }
+    }
+}
+
+// === impl Entities ===
+
+impl<T, S> Default for Entities<T, S> {
+    fn default() -> Self {
+        Self {
+            new: HashMap::new(),
+            stats: HashMap::new(),
+            dropped_events: 0,
+        }
+    }
+}
+
+impl<T: Clone, S: Clone> Entities<T, S> {
+    fn merge(
+        &mut self,
+        new: &[T],
+        id: impl Fn(&T) -> Option<u64>,
+        stats: &HashMap<u64, S>,
+        dropped_events: u64,
+    ) {
+        for entity in new {
+            if let Some(id) = id(entity) {
+                self.new.insert(id, entity.clone());
+            }
+        }
+        self.stats
+            .extend(stats.iter().map(|(&id, stats)| (id, stats.clone())));
+        self.dropped_events += dropped_events;
+    }
+
+    /// Forgets the entities which were dropped before `cutoff`.
+    fn retain_dropped_since(
+        &mut self,
+        cutoff: SystemTime,
+        dropped_at: impl Fn(&S) -> Option<&prost_types::Timestamp>,
+    ) {
+        let new = &mut self.new;
+        self.stats.retain(|id, stats| {
+            let dropped_at = dropped_at(stats)
+                .copied()
+                .and_then(|dropped_at| SystemTime::try_from(dropped_at).ok());
+            let retain = dropped_at.map_or(true, |dropped_at| dropped_at >= cutoff);
+            if !retain {
+                new.remove(id);
+            }
+            retain
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::fixtures::{task, timestamp};
+
+    fn task_update(now: i64, new: &[u64], stats: &[(u64, Option<i64>)]) -> Update {
+        Update {
+            now: Some(timestamp(now)),
+            task_update: Some(tasks::TaskUpdate {
+                new_tasks: new.iter().map(|&id| task(id)).collect(),
+                stats_update: stats
+                    .iter()
+                    .map(|&(id, dropped_at)| {
+                        let stats = tasks::Stats {
+                            dropped_at: dropped_at.map(timestamp),
+                            ..Default::default()
+                        };
+                        (id, stats)
+                    })
+                    .collect(),
+                dropped_events: 1,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_keeps_latest_state() {
+        let retain_for = Some(Duration::from_secs(5));
+        let mut snapshot = Snapshot::default();
+        snapshot.merge(
+            &task_update(0, &[1, 2], &[(1, None), (2, None)]),
+            retain_for,
+        );
+        // Task 1 completes, and is forgotten once it's been completed for
+        // longer than it's retained.
+        snapshot.merge(
+            &task_update(1, &[3], &[(1, Some(1)), (3, None)]),
+            retain_for,
+        );
+        snapshot.merge(&task_update(10, &[], &[(2, None)]), retain_for);
+
+        let update = snapshot.to_update();
+        assert_eq!(update.now, Some(timestamp(10)));
+        let tasks = update.task_update.unwrap();
+        let mut ids = tasks
+            .new_tasks
+            .iter()
+            .map(|task| task.id.as_ref().unwrap().id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, [2, 3]);
+        let mut stats = tasks.stats_update.keys().copied().collect::<Vec<_>>();
+        stats.sort_unstable();
+        assert_eq!(stats, [2, 3]);
+        assert_eq!(tasks.dropped_events, 3);
+    }
+}
@@ -0,0 +1,338 @@
+//! Recording the messages received from a target with `--record`, and
+//! replaying a recording with `tokio-console replay`.
+//!
+//! A recording is written in the same format as the framed transport: each
+//! message is a [`Frame`], prefixed by its length as a big-endian `u32`. So
+//! a recording can also be opened as a snapshot, which shows the target's
+//! state at the end of the recording.
+use crate::conn::Message;
+use console_api::instrument::{frame, Frame, State, Temporality};
+use prost::Message as _;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use tokio::time::Instant;
+
+/// How far `<` and `>` seek through a recording.
+pub(crate) const SEEK_STEP: Duration = Duration::from_secs(10);
+
+/// Writes every message received from a target to a file.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    file: BufWriter<File>,
+}
+
+/// Replays a recording at the rate it was recorded, unless it's paused or
+/// being seeked through.
+#[derive(Debug)]
+pub(crate) struct Replay {
+    messages: Vec<Message>,
+    /// When each message was recorded, which is the time of the latest
+    /// update at or before it.
+    times: Vec<SystemTime>,
+    /// The index of the next message to replay.
+    next: usize,
+    /// When the last message was replayed, which the time until the next one
+    /// is counted from.
+    last_at: Instant,
+    paused: bool,
+    /// Messages recorded up to this time are replayed without waiting, after
+    /// seeking or stepping.
+    seek_to: Option<SystemTime>,
+    /// The replay's state, if it has been paused or resumed since the last
+    /// message.
+    state: Option<State>,
+}
+
+// === impl Recorder ===
+
+impl Recorder {
+    /// Creates a recording at `path`, replacing any file which is already
+    /// there.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Appends `message` to the recording.
+    ///
+    /// Each message is flushed as it's written, so that the recording is
+    /// complete even if the console is killed.
+    pub(crate) fn record(&mut self, message: &Message) -> io::Result<()> {
+        let frame = encode(message);
+        let len = u32::try_from(frame.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "frame is too large"))?;
+        self.file.write_all(&len.to_be_bytes())?;
+        self.file.write_all(&frame)?;
+        self.file.flush()
+    }
+}
+
+fn encode(message: &Message) -> Vec<u8> {
+    let message = match message {
+        Message::Update(update) => frame::Message::Update(update.clone()),
+        Message::State(state) => frame::Message::State(state.clone()),
+    };
+    Frame {
+        message: Some(message),
+    }
+    .encode_to_vec()
+}
+
+// === impl Replay ===
+
+impl Replay {
+    /// Reads the recording at `path`.
+    pub(crate) async fn open(path: &Path) -> io::Result<Self> {
+        let recording = tokio::fs::read(path).await?;
+        Self::decode(&recording)
+    }
+
+    fn decode(mut recording: &[u8]) -> io::Result<Self> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+        let mut messages = Vec::new();
+        while recording.len() >= 4 {
+            let (len, rest) = recording.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+            if rest.len() < len {
+                break;
+            }
+            let (frame, rest) = rest.split_at(len);
+            recording = rest;
+            match Frame::decode(frame).map_err(invalid)?.message {
+                Some(frame::Message::Update(update)) => messages.push(Message::Update(update)),
+                Some(frame::Message::State(state)) => messages.push(Message::State(state)),
+                // A message from a newer console which this one doesn't know
+                // about.
+                None => {}
+            }
+        }
+        // The console may have been killed while writing the last frame.
+        if !recording.is_empty() {
+            tracing::warn!(
+                bytes = recording.len(),
+                "ignoring an incomplete message at the end of the recording"
+            );
+        }
+
+        let mut time = messages
+            .iter()
+            .find_map(|message| match message {
+                Message::Update(update) => update.now,
+                Message::State(_) => None,
+            })
+            .and_then(|now| SystemTime::try_from(now).ok())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "the recording has no updates")
+            })?;
+        let times = messages
+            .iter()
+            .map(|message| {
+                if let Message::Update(update) = message {
+                    if let Some(now) = update.now.and_then(|now| now.try_into().ok()) {
+                        time = now;
+                    }
+                }
+                time
+            })
+            .collect();
+        Ok(Self {
+            messages,
+            times,
+            next: 0,
+            last_at: Instant::now(),
+            paused: false,
+            seek_to: None,
+            state: None,
+        })
+    }
+
+    /// Returns the next message in the recording, once it's time to replay
+    /// it, or the replay's state if it has been paused or resumed since the
+    /// last message.
+    pub(crate) async fn next_message(&mut self) -> Message {
+        if let Some(state) = self.state.take() {
+            return Message::State(state);
+        }
+        let Some(&at) = self.times.get(self.next) else {
+            // Keep showing the end of the recording, until it's seeked back
+            // through.
+            return futures::future::pending().await;
+        };
+        match self.seek_to {
+            Some(to) if at <= to => {}
+            _ => {
+                if self.seek_to.take().is_some() {
+                    self.last_at = Instant::now();
+                }
+                if self.paused {
+                    return futures::future::pending().await;
+                }
+                let since = self.next.checked_sub(1).map_or(at, |last| self.times[last]);
+                let wait = at.duration_since(since).unwrap_or_default();
+                tokio::time::sleep_until(self.last_at + wait).await;
+                self.last_at += wait;
+            }
+        }
+        self.next += 1;
+        self.messages[self.next - 1].clone()
+    }
+
+    pub(crate) fn pause(&mut self) {
+        self.paused = true;
+        self.state = Some(State {
+            temporality: Temporality::Paused as i32,
+            paused_at: Some(self.now().into()),
+            paused_by: Some(format!("tokio-console (pid {})", std::process::id())),
+        });
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.paused = false;
+        self.last_at = Instant::now();
+        self.state = Some(State {
+            temporality: Temporality::Live as i32,
+            paused_at: None,
+            paused_by: None,
+        });
+    }
+
+    /// Replays everything up to and including the next update, while paused.
+    pub(crate) fn step(&mut self) {
+        let next = self.messages[self.next.min(self.messages.len())..]
+            .iter()
+            .position(|message| matches!(message, Message::Update(_)));
+        if let Some(next) = next {
+            self.seek_to = Some(self.times[self.next + next]);
+        }
+    }
+
+    /// Seeks `by` forwards through the recording, or backwards if `back` is
+    /// `true`.
+    ///
+    /// Returns `true` if the replay starts again from the beginning, since
+    /// its state can only be rebuilt by replaying every message before the
+    /// time it was seeked to. The console's state must be cleared before
+    /// the next message is applied.
+    pub(crate) fn seek(&mut self, by: Duration, back: bool) -> bool {
+        let now = self.now();
+        if back {
+            self.next = 0;
+            self.seek_to = Some(now.checked_sub(by).unwrap_or(now));
+            // The console's state starts again as live.
+            if self.paused {
+                self.pause();
+            }
+        } else {
+            self.seek_to = Some(now + by);
+        }
+        back
+    }
+
+    /// Returns how far through the recording the replay is, and how long
+    /// the recording is.
+    pub(crate) fn position(&self) -> (Duration, Duration) {
+        let start = self
+            .times
+            .first()
+            .copied()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let end = self.times.last().copied().unwrap_or(start);
+        let elapsed = |time: SystemTime| time.duration_since(start).unwrap_or_default();
+        (elapsed(self.now()), elapsed(end))
+    }
+
+    /// Returns `true` once every message in the recording has been replayed.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.next >= self.messages.len()
+    }
+
+    /// Returns the time the last replayed message was recorded at.
+    fn now(&self) -> SystemTime {
+        let last = self.next.saturating_sub(1);
+        self.times
+            .get(last)
+            .copied()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use console_api::instrument::Update;
+
+    fn update(secs: u64) -> Message {
+        Message::Update(Update {
+            now: Some((SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).into()),
+            ..Default::default()
+        })
+    }
+
+    fn recording(messages: &[Message]) -> Vec<u8> {
+        let mut recording = Vec::new();
+        for message in messages {
+            let frame = encode(message);
+            recording.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            recording.extend_from_slice(&frame);
+        }
+        recording
+    }
+
+    fn secs(message: &Message) -> u64 {
+        match message {
+            Message::Update(update) => SystemTime::try_from(update.now.unwrap())
+                .unwrap()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            Message::State(_) => panic!("expected an update"),
+        }
+    }
+
+    #[test]
+    fn ignores_incomplete_last_frame() {
+        let mut bytes = recording(&[update(100), update(101)]);
+        bytes.truncate(bytes.len() - 1);
+        let replay = Replay::decode(&bytes).unwrap();
+        assert_eq!(replay.messages.len(), 1);
+        assert!(Replay::decode(&[]).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replays_at_recorded_rate_and_seeks() {
+        let bytes = recording(&[update(100), update(101), update(130), update(131)]);
+        let mut replay = Replay::decode(&bytes).unwrap();
+        assert_eq!(replay.position(), (Duration::ZERO, Duration::from_secs(31)));
+
+        let start = Instant::now();
+        assert_eq!(secs(&replay.next_message().await), 100);
+        assert_eq!(secs(&replay.next_message().await), 101);
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        // Seeking forwards replays everything it skips over straight away.
+        assert!(!replay.seek(SEEK_STEP * 3, false));
+        assert_eq!(secs(&replay.next_message().await), 130);
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        // Seeking backwards starts again from the beginning.
+        assert!(replay.seek(SEEK_STEP, true));
+        assert_eq!(secs(&replay.next_message().await), 100);
+        assert_eq!(secs(&replay.next_message().await), 101);
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        replay.pause();
+        assert!(matches!(replay.next_message().await, Message::State(_)));
+        replay.step();
+        assert_eq!(secs(&replay.next_message().await), 130);
+        assert_eq!(replay.position().0, Duration::from_secs(30));
+        replay.resume();
+        assert!(matches!(replay.next_message().await, Message::State(_)));
+        assert_eq!(secs(&replay.next_message().await), 131);
+        assert!(replay.is_finished());
+    }
+}
@@ -0,0 +1,318 @@
+//! Watching a target for a fixed period without the console UI, for the
+//! `tokio-console report` and `tokio-console lint` subcommands.
+use crate::{
+    baseline::{self, Session},
+    config::{CollectArgs, KnownWarnings},
+    conn,
+    state::{store::SpanId, tasks::Task, State},
+    view,
+};
+use color_eyre::{eyre::eyre, Help};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    time::Duration,
+};
+use tonic::transport::Uri;
+
+/// How many tasks are listed in the report's busiest tasks.
+const TOP_TASKS: usize = 10;
+
+/// What was seen while watching a target.
+#[derive(Debug)]
+pub(crate) struct Report {
+    duration: Duration,
+    updates: usize,
+    /// Every task seen while watching, as of the last update it was seen in.
+    tasks: HashMap<SpanId, TaskSummary>,
+    /// The tasks which had already completed when the target started being
+    /// watched.
+    completed_before: HashSet<SpanId>,
+    /// The tasks which triggered each warning, in the order of the state's
+    /// task linters.
+    warnings: Vec<Triggered>,
+}
+
+#[derive(Debug)]
+struct TaskSummary {
+    desc: String,
+    location: String,
+    busy: Duration,
+    /// How long the task had been busy for when it was first seen.
+    busy_at_start: Duration,
+    /// Whether the task was spawned while the target was being watched.
+    spawned: bool,
+    polls: u64,
+    poll_p99: Option<Duration>,
+}
+
+/// The tasks which triggered a warning at some point while the target was
+/// being watched, by their span IDs.
+#[derive(Debug)]
+struct Triggered {
+    summary: String,
+    tasks: BTreeMap<SpanId, String>,
+}
+
+/// Watches `target` as configured by `args`, checking every update it sends
+/// against `warnings`, and saves what was seen if `args` asks for it.
+///
+/// Returns an error if the target didn't send any updates.
+pub(crate) async fn collect(
+    target: Uri,
+    args: &CollectArgs,
+    warnings: &[&KnownWarnings],
+    styles: &view::Styles,
+) -> color_eyre::Result<Report> {
+    let duration = args.duration.into();
+    println!("target: {target}");
+    println!("duration: {}", humantime::format_duration(duration));
+    let mut state = State::default().with_task_linters(warnings.iter().copied().map(Into::into));
+    let mut report = Report {
+        duration,
+        updates: 0,
+        tasks: HashMap::new(),
+        completed_before: HashSet::new(),
+        warnings: state
+            .tasks_state()
+            .linters
+            .iter()
+            .map(|linter| Triggered {
+                summary: linter.summary().to_owned(),
+                tasks: BTreeMap::new(),
+            })
+            .collect(),
+    };
+    let mut conn = conn::Connection::new(target);
+
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            message = conn.next_message() => match message {
+                conn::Message::Update(update) => {
+                    // The task list is the only view whose data is kept
+                    // without being displayed.
+                    state.update(styles, &view::ViewState::TasksList, update);
+                    report.record(&state);
+                }
+                conn::Message::State(state_update) => state.update_state(state_update),
+            }
+        }
+    }
+
+    if report.updates == 0 {
+        return Err(eyre!("no updates were received from the target")).suggestion(
+            "is the application running, and is it instrumented with console-subscriber?",
+        );
+    }
+    println!("updates: {}", report.updates);
+    println!("tasks: {}", report.tasks.len());
+    if let Some(ref path) = args.save {
+        report.session().save(path)?;
+        println!("saved: {}", path.display());
+    }
+    Ok(report)
+}
+
+/// Watches `target` as configured by `args`, and prints a summary of the
+/// tasks it spawned and what they did.
+pub(crate) async fn run(
+    target: Uri,
+    args: &CollectArgs,
+    warnings: &[&KnownWarnings],
+    styles: &view::Styles,
+) -> color_eyre::Result<()> {
+    let report = collect(target, args, warnings, styles).await?;
+    report.print_busiest();
+    report.print_spawns();
+    report.print_warnings();
+    if let Some(ref path) = args.baseline {
+        baseline::print(&report.regressions(path)?);
+    }
+    Ok(())
+}
+
+// === impl Report ===
+
+impl Report {
+    /// Returns the statistics which rose significantly since the baseline
+    /// saved at `path`.
+    pub(crate) fn regressions(&self, path: &Path) -> color_eyre::Result<Vec<baseline::Regression>> {
+        Ok(self.session().regressions(&Session::load(path)?))
+    }
+
+    fn session(&self) -> Session {
+        let mut session = Session {
+            duration: self.duration.as_secs_f64(),
+            ..Session::default()
+        };
+        for task in self.tasks.values() {
+            let location = session.locations.entry(task.location.clone()).or_default();
+            if task.spawned {
+                location.spawned += 1;
+            }
+            location
+                .busy
+                .push(task.busy.saturating_sub(task.busy_at_start).as_secs_f64());
+            location
+                .poll_p99
+                .extend(task.poll_p99.map(|p99| p99.as_secs_f64()));
+        }
+        session
+    }
+
+    /// Returns each task's spawn location, and how long it was busy for
+    /// while the target was watched.
+    pub(crate) fn busy_by_location(&self) -> impl Iterator<Item = (&str, Duration)> {
+        self.tasks.values().map(|task| {
+            (
+                task.location.as_str(),
+                task.busy.saturating_sub(task.busy_at_start),
+            )
+        })
+    }
+
+    /// Returns how many of the warnings were triggered.
+    pub(crate) fn warnings_triggered(&self) -> usize {
+        self.warnings
+            .iter()
+            .filter(|triggered| !triggered.tasks.is_empty())
+            .count()
+    }
+
+    /// Prints the warnings which were triggered, and the tasks which
+    /// triggered them.
+    pub(crate) fn print_warnings(&self) {
+        if self.warnings_triggered() == 0 {
+            println!("warnings: none");
+            return;
+        }
+        for Triggered { summary, tasks } in &self.warnings {
+            if tasks.is_empty() {
+                continue;
+            }
+            println!("warning: {} {summary}", tasks.len());
+            for task in tasks.values() {
+                println!("    {task}");
+            }
+        }
+    }
+
+    fn print_busiest(&self) {
+        let mut tasks = self.tasks.values().collect::<Vec<_>>();
+        tasks.sort_by_key(|task| std::cmp::Reverse(task.busy));
+        println!("busiest tasks:");
+        println!(
+            "    {:>10} {:>8} {:>10}  {:<30} Location",
+            "Busy", "Polls", "P99 Poll", "Task"
+        );
+        for task in tasks.into_iter().take(TOP_TASKS) {
+            let p99 = task
+                .poll_p99
+                .map(|p99| format!("{p99:.2?}"))
+                .unwrap_or_else(|| "-".to_owned());
+            println!(
+                "    {:>10} {:>8} {:>10}  {:<30} {}",
+                format!("{:.2?}", task.busy),
+                task.polls,
+                p99,
+                task.desc,
+                task.location
+            );
+        }
+        let worst = self
+            .tasks
+            .values()
+            .filter_map(|task| Some((task.poll_p99?, task)))
+            .max_by_key(|(p99, _)| *p99);
+        if let Some((p99, task)) = worst {
+            println!("highest p99 poll: {p99:.2?}, task {}", task.desc);
+        }
+    }
+
+    fn print_spawns(&self) {
+        let mut spawns = BTreeMap::<&str, usize>::new();
+        for task in self.tasks.values().filter(|task| task.spawned) {
+            *spawns.entry(&task.location).or_default() += 1;
+        }
+        let mut spawns = spawns.into_iter().collect::<Vec<_>>();
+        spawns.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        println!("spawns by location:");
+        for (location, count) in spawns {
+            println!("    {count:>8}  {location}");
+        }
+    }
+
+    /// Records the state of every task as of the latest update, and each
+    /// task which triggers a warning, along with why, the first time it is
+    /// seen to trigger it.
+    fn record(&mut self, state: &State) {
+        self.updates += 1;
+        let now = state
+            .last_updated_at()
+            .unwrap_or_else(std::time::SystemTime::now);
+        let linters = &state.tasks_state().linters;
+        for task in state.tasks_state().tasks() {
+            let Some(task) = task.upgrade() else {
+                continue;
+            };
+            let task = task.borrow();
+            let busy = task.busy(now);
+            // Tasks in the first update were spawned before the target was
+            // being watched, and any later tasks were spawned while it was.
+            // Tasks which had already completed by then didn't run while it
+            // was being watched, so they're not included.
+            let (busy_at_start, spawned) = match self.tasks.get(&task.span_id()) {
+                Some(summary) => (summary.busy_at_start, summary.spawned),
+                None if self.updates == 1 => (busy, false),
+                None => (Duration::ZERO, true),
+            };
+            if self.updates == 1 && task.is_completed() {
+                self.completed_before.insert(task.span_id());
+            }
+            if !self.completed_before.contains(&task.span_id()) {
+                self.tasks.insert(
+                    task.span_id(),
+                    TaskSummary {
+                        desc: describe(&task),
+                        location: task.location().to_owned(),
+                        busy,
+                        busy_at_start,
+                        spawned,
+                        polls: task.total_polls(),
+                        poll_p99: task.poll_p99(),
+                    },
+                );
+            }
+            for warning in task.warnings() {
+                let Some(idx) = linters
+                    .iter()
+                    .position(|linter| linter.summary() == warning.summary())
+                else {
+                    continue;
+                };
+                self.warnings[idx]
+                    .tasks
+                    .entry(task.span_id())
+                    .or_insert_with(|| {
+                        format!(
+                            "task {} at {}: {}",
+                            describe(&task),
+                            task.location(),
+                            warning.format(&task, now)
+                        )
+                    });
+            }
+        }
+    }
+}
+
+/// Returns the task's ID, and its name if it has one.
+fn describe(task: &Task) -> String {
+    match task.name() {
+        Some(name) => format!("{} ({name})", task.id_str()),
+        None => task.id_str().to_owned(),
+    }
+}
@@ -0,0 +1,299 @@
+//! Saving the console's screen, with its colors, so that it can be pasted
+//! into a document or shared.
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+    text::Span,
+};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The colors which the screen is drawn in where it uses the terminal's
+/// default colors, since a saved screen can't know them.
+const DEFAULT_FG: (u8, u8, u8) = (0xd0, 0xd0, 0xd0);
+const DEFAULT_BG: (u8, u8, u8) = (0x00, 0x00, 0x00);
+
+/// Writes `screen` to an ANSI text file and a standalone HTML file in the
+/// current directory, returning their paths.
+pub(crate) fn save(screen: &Buffer) -> color_eyre::Result<[PathBuf; 2]> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let ansi = PathBuf::from(format!("tokio-console-{secs}.ans"));
+    fs::write(&ansi, to_ansi(screen))?;
+    let html = PathBuf::from(format!("tokio-console-{secs}.html"));
+    fs::write(&html, to_html(screen))?;
+    Ok([ansi, html])
+}
+
+/// Returns `screen` as text, with SGR escape sequences for its styles.
+fn to_ansi(screen: &Buffer) -> String {
+    let mut out = String::new();
+    for row in rows(screen) {
+        let mut current: Option<&Cell> = None;
+        for cell in row {
+            if current.map_or(true, |current| !same_style(current, cell)) {
+                out.push_str("\x1b[0");
+                push_modifiers(&mut out, cell.modifier);
+                push_color(&mut out, cell.fg, 30);
+                push_color(&mut out, cell.bg, 40);
+                out.push('m');
+                current = Some(cell);
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// Returns `screen` as a standalone HTML page, with each run of cells in the
+/// same style in its own `<span>`.
+fn to_html(screen: &Buffer) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <title>tokio-console</title>\n</head>\n<body>\n\
+        <pre style=\"background: {}; color: {}; font-family: monospace; \
+        display: inline-block; padding: 0.5em;\">\n",
+        hex(DEFAULT_BG),
+        hex(DEFAULT_FG),
+    );
+    for row in rows(screen) {
+        let mut current: Option<&Cell> = None;
+        for cell in row {
+            if current.map_or(true, |current| !same_style(current, cell)) {
+                if current.is_some() {
+                    out.push_str("</span>");
+                }
+                out.push_str("<span style=\"");
+                push_css(&mut out, cell);
+                out.push_str("\">");
+                current = Some(cell);
+            }
+            for c in cell.symbol().chars() {
+                match c {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    c => out.push(c),
+                }
+            }
+        }
+        if current.is_some() {
+            out.push_str("</span>");
+        }
+        out.push('\n');
+    }
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+/// Returns each row of `screen`'s cells, skipping the cells which are covered
+/// by a wide character before them.
+fn rows(screen: &Buffer) -> impl Iterator<Item = Vec<&Cell>> + '_ {
+    let width = screen.area.width as usize;
+    screen.content.chunks(width.max(1)).map(|row| {
+        let mut covered = 0;
+        row.iter()
+            .filter(|cell| {
+                if covered > 0 {
+                    covered -= 1;
+                    return false;
+                }
+                covered = Span::raw(cell.symbol()).width().saturating_sub(1);
+                true
+            })
+            .collect()
+    })
+}
+
+fn same_style(a: &Cell, b: &Cell) -> bool {
+    a.fg == b.fg && a.bg == b.bg && a.modifier == b.modifier
+}
+
+fn push_modifiers(out: &mut String, modifier: Modifier) {
+    const CODES: [(Modifier, u8); 9] = [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ];
+    for (flag, code) in CODES {
+        if modifier.contains(flag) {
+            let _ = write!(out, ";{code}");
+        }
+    }
+}
+
+/// Pushes the SGR parameters for `color`, where `base` is 30 for the
+/// foreground and 40 for the background.
+fn push_color(out: &mut String, color: Color, base: u8) {
+    let _ = match color {
+        Color::Reset => Ok(()),
+        Color::Indexed(idx) => write!(out, ";{};5;{idx}", base + 8),
+        Color::Rgb(r, g, b) => write!(out, ";{};2;{r};{g};{b}", base + 8),
+        color => {
+            let idx = ansi_index(color).unwrap_or_default();
+            match idx {
+                0..=7 => write!(out, ";{}", base + idx),
+                _ => write!(out, ";{}", base + 60 + idx - 8),
+            }
+        }
+    };
+}
+
+fn push_css(out: &mut String, cell: &Cell) {
+    let mut fg = rgb(cell.fg).unwrap_or(DEFAULT_FG);
+    let mut bg = rgb(cell.bg);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        (fg, bg) = (bg.unwrap_or(DEFAULT_BG), Some(fg));
+    }
+    let _ = write!(out, "color: {};", hex(fg));
+    if let Some(bg) = bg {
+        let _ = write!(out, " background: {};", hex(bg));
+    }
+    if cell.modifier.contains(Modifier::BOLD) {
+        out.push_str(" font-weight: bold;");
+    }
+    if cell.modifier.contains(Modifier::DIM) {
+        out.push_str(" opacity: 0.6;");
+    }
+    if cell.modifier.contains(Modifier::ITALIC) {
+        out.push_str(" font-style: italic;");
+    }
+    if cell.modifier.contains(Modifier::UNDERLINED) {
+        out.push_str(" text-decoration: underline;");
+    }
+    if cell.modifier.contains(Modifier::CROSSED_OUT) {
+        out.push_str(" text-decoration: line-through;");
+    }
+    if cell.modifier.contains(Modifier::HIDDEN) {
+        out.push_str(" visibility: hidden;");
+    }
+}
+
+/// Returns the index of one of the 16 ANSI colors in the 256-color palette.
+fn ansi_index(color: Color) -> Option<u8> {
+    Some(match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        _ => return None,
+    })
+}
+
+/// Returns the RGB value of `color`, using xterm's default palette, or `None`
+/// if it's the terminal's default color.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let idx = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some((r, g, b)),
+        Color::Indexed(idx) => idx,
+        color => ansi_index(color)?,
+    };
+    Some(match idx {
+        0..=15 => ANSI[idx as usize],
+        // The 6x6x6 color cube.
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = idx - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        // The grayscale ramp.
+        _ => {
+            let level = 8 + (idx - 232) * 10;
+            (level, level, level)
+        }
+    })
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, style::Style};
+
+    fn screen() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buffer.set_string(
+            0,
+            1,
+            "日x",
+            Style::default()
+                .bg(Color::Indexed(196))
+                .add_modifier(Modifier::BOLD),
+        );
+        buffer
+    }
+
+    #[test]
+    fn ansi_keeps_styles() {
+        assert_eq!(
+            to_ansi(&screen()),
+            "\x1b[0;31ma<b\x1b[0m   \x1b[0m\n\
+            \x1b[0;1;48;5;196m日x\x1b[0m   \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_text() {
+        let html = to_html(&screen());
+        assert!(html.contains("<span style=\"color: #cd0000;\">a&lt;b</span>"));
+        assert!(html.contains(
+            "<span style=\"color: #d0d0d0; background: #ff0000; font-weight: bold;\">日x</span>"
+        ));
+    }
+
+    #[test]
+    fn palette_colors() {
+        assert_eq!(rgb(Color::Indexed(16)), Some((0, 0, 0)));
+        assert_eq!(rgb(Color::Indexed(231)), Some((255, 255, 255)));
+        assert_eq!(rgb(Color::Indexed(244)), Some((128, 128, 128)));
+        assert_eq!(rgb(Color::Reset), None);
+    }
+}
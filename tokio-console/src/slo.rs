@@ -0,0 +1,232 @@
+use crate::state::histogram::DurationHistogram;
+use std::{fmt, str::FromStr, time::Duration};
+
+/// A latency objective for tasks' poll or scheduled times, which is shown on
+/// the task's histograms and as a warning when the task doesn't meet it.
+///
+/// Objectives are written as `<histogram> p<percentile> < <threshold>`, such
+/// as `poll p99 < 10ms` or `scheduled p50 < 1ms`.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Slo {
+    pub(crate) histogram: SloHistogram,
+    /// The percentile, between 0 and 100.
+    percentile: f64,
+    threshold: Duration,
+}
+
+/// The histogram which an [`Slo`] applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SloHistogram {
+    Poll,
+    Scheduled,
+}
+
+/// How a histogram measures up to an [`Slo`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SloStatus<'a> {
+    pub(crate) slo: &'a Slo,
+    /// The value at the objective's percentile.
+    pub(crate) value: Duration,
+    /// The percentage of values within the objective's threshold.
+    pub(crate) within: f64,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+// === impl Slo ===
+
+impl Slo {
+    pub(crate) fn percentile(&self) -> f64 {
+        self.percentile
+    }
+
+    pub(crate) fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Checks `histogram` against this objective.
+    ///
+    /// Returns `None` if the histogram is empty.
+    pub(crate) fn check(&self, histogram: &DurationHistogram) -> Option<SloStatus<'_>> {
+        let histogram = &histogram.histogram;
+        if histogram.is_empty() {
+            return None;
+        }
+        let threshold = u64::try_from(self.threshold.as_nanos()).unwrap_or(u64::MAX);
+        Some(SloStatus {
+            slo: self,
+            value: Duration::from_nanos(histogram.value_at_percentile(self.percentile)),
+            within: histogram.percentile_below(threshold),
+        })
+    }
+}
+
+impl FromStr for Slo {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            ParseError(format!(
+                "invalid latency objective `{s}`, expected `<histogram> p<percentile> < <threshold>`"
+            ))
+        };
+        let (lhs, threshold) = s.split_once('<').ok_or_else(invalid)?;
+        let (histogram, percentile) = lhs.trim().split_once(' ').ok_or_else(invalid)?;
+        let histogram = histogram.parse()?;
+        let percentile = percentile.trim();
+        let percentile = percentile
+            .strip_prefix('p')
+            .and_then(|p| p.parse::<f64>().ok())
+            .filter(|p| (0.0..=100.0).contains(p))
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "invalid percentile `{percentile}`, expected `p` and a number from 0 to 100"
+                ))
+            })?;
+        let threshold = threshold.trim();
+        let threshold = threshold
+            .parse::<humantime::Duration>()
+            .map_err(|e| ParseError(format!("invalid duration `{threshold}`: {e}")))?;
+        Ok(Self {
+            histogram,
+            percentile,
+            threshold: threshold.into(),
+        })
+    }
+}
+
+impl fmt::Display for Slo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} p{} < {}",
+            self.histogram,
+            self.percentile,
+            humantime::format_duration(self.threshold)
+        )
+    }
+}
+
+impl TryFrom<String> for Slo {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Slo> for String {
+    fn from(slo: Slo) -> Self {
+        slo.to_string()
+    }
+}
+
+// === impl SloHistogram ===
+
+impl SloHistogram {
+    const POLL: &'static str = "poll";
+    const SCHEDULED: &'static str = "scheduled";
+}
+
+impl FromStr for SloHistogram {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Self::POLL => Ok(Self::Poll),
+            Self::SCHEDULED => Ok(Self::Scheduled),
+            histogram => Err(ParseError(format!(
+                "unknown histogram `{histogram}`, expected one of: {}, {}",
+                Self::POLL,
+                Self::SCHEDULED,
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SloHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Poll => f.write_str(Self::POLL),
+            Self::Scheduled => f.write_str(Self::SCHEDULED),
+        }
+    }
+}
+
+// === impl SloStatus ===
+
+impl SloStatus<'_> {
+    pub(crate) fn is_met(&self) -> bool {
+        self.value < self.slo.threshold
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hdrhistogram::Histogram;
+
+    #[test]
+    fn parse_slos() {
+        assert_eq!(
+            "poll p99 < 10ms".parse::<Slo>().unwrap(),
+            Slo {
+                histogram: SloHistogram::Poll,
+                percentile: 99.0,
+                threshold: Duration::from_millis(10),
+            }
+        );
+        assert_eq!(
+            "scheduled p99.9<1ms".parse::<Slo>().unwrap(),
+            Slo {
+                histogram: SloHistogram::Scheduled,
+                percentile: 99.9,
+                threshold: Duration::from_millis(1),
+            }
+        );
+        assert!("poll < 10ms".parse::<Slo>().is_err());
+        assert!("poll p101 < 10ms".parse::<Slo>().is_err());
+        assert!("wake p99 < 10ms".parse::<Slo>().is_err());
+        assert!("poll p99 < 10".parse::<Slo>().is_err());
+    }
+
+    #[test]
+    fn display_roundtrips() {
+        let slo = "poll p99.5 < 1500us".parse::<Slo>().unwrap();
+        assert_eq!(slo.to_string().parse::<Slo>().unwrap(), slo);
+    }
+
+    #[test]
+    fn check_histogram() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        for _ in 0..99 {
+            histogram.record(1_000_000).unwrap();
+        }
+        histogram.record(50_000_000).unwrap();
+        let histogram = DurationHistogram {
+            histogram,
+            high_outliers: 0,
+            highest_outlier: None,
+        };
+
+        let met = "poll p99 < 10ms".parse::<Slo>().unwrap();
+        let status = met.check(&histogram).unwrap();
+        assert!(status.is_met());
+        assert_eq!(status.within, 99.0);
+
+        let violated = "poll p99.9 < 10ms".parse::<Slo>().unwrap();
+        assert!(!violated.check(&histogram).unwrap().is_met());
+    }
+}
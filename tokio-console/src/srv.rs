@@ -0,0 +1,405 @@
+//! Connecting to targets found with DNS SRV records.
+//!
+//! A target written as `dns+srv://_console._tcp.example.com` is connected to
+//! at one of the instances which the name's SRV records point to. They're
+//! looked up each time the console connects, so that reconnecting after an
+//! instance goes away picks one of the instances which are left.
+//!
+//! The records are looked up from the first `nameserver` in
+//! `/etc/resolv.conf`, rather than with the system's resolver, which can
+//! only look up addresses. Its `search` and `options` lines are ignored, and
+//! there's no `/etc/resolv.conf` to read on Windows.
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fs,
+    hash::{BuildHasher, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+};
+use tonic::transport::Uri;
+
+/// How long to wait for the DNS server to answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The record type of SRV records.
+const SRV: u16 = 33;
+/// The `IN` (Internet) record class.
+const IN: u16 = 1;
+
+/// A SRV record: an instance of the service, and which instances to prefer.
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    /// Instances with lower priorities are picked first.
+    priority: u16,
+    /// How often to pick this instance relative to others of the same
+    /// priority.
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+/// Returns the address to connect to `target`, which is a `dns+srv://`
+/// address.
+pub(crate) async fn resolve(target: &Uri) -> Result<Uri, Box<dyn Error + Send + Sync>> {
+    let name = target
+        .host()
+        .filter(|name| !name.is_empty())
+        .ok_or("a DNS SRV target must be written as dns+srv://service.name")?;
+    let records = lookup(name).await?;
+    let record =
+        pick(&records, random()).ok_or_else(|| format!("no instances of {name} are available"))?;
+    let host = record.target.trim_end_matches('.');
+    let addr = match host.parse::<Ipv6Addr>() {
+        Ok(ip) => format!("http://[{ip}]:{}", record.port),
+        Err(_) => format!("http://{host}:{}", record.port),
+    };
+    tracing::debug!(%target, %addr, "resolved SRV records");
+    Ok(addr.parse()?)
+}
+
+/// Looks up the SRV records of `name`.
+async fn lookup(name: &str) -> Result<Vec<Record>, Box<dyn Error + Send + Sync>> {
+    let server = nameserver()?;
+    let id = random() as u16;
+    let query = query(id, name)?;
+    let local = match server {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(server).await?;
+    socket.send(&query).await?;
+    let mut response = vec![0; 4096];
+    let len = tokio::time::timeout(TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| format!("timed out looking up {name}"))??;
+    if let Some(records) = parse_response(id, &response[..len])? {
+        return Ok(records);
+    }
+
+    // The answer didn't fit in a datagram, so ask again over TCP, where
+    // each message is prefixed with its length.
+    tracing::debug!(name, "SRV records were truncated, looking up over TCP");
+    let lookup = async {
+        let mut stream = TcpStream::connect(server).await?;
+        stream.write_u16(query.len() as u16).await?;
+        stream.write_all(&query).await?;
+        let mut response = vec![0; usize::from(stream.read_u16().await?)];
+        stream.read_exact(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(TIMEOUT, lookup)
+        .await
+        .map_err(|_| format!("timed out looking up {name}"))??;
+    Ok(parse_response(id, &response)?.ok_or("the DNS server's answer was truncated")?)
+}
+
+/// Returns the address of the first DNS server in `/etc/resolv.conf`.
+fn nameserver() -> Result<SocketAddr, Box<dyn Error + Send + Sync>> {
+    let conf = fs::read_to_string("/etc/resolv.conf")
+        .map_err(|error| format!("could not read /etc/resolv.conf: {error}"))?;
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        // Link-local servers may have a zone, which addresses can't hold.
+        .filter_map(|server| server.trim().split('%').next()?.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .next()
+        .ok_or_else(|| "no DNS server is configured in /etc/resolv.conf".into())
+}
+
+/// Returns a query for the SRV records of `name`, with the message ID `id`.
+fn query(id: u16, name: &str) -> Result<Vec<u8>, String> {
+    let mut query = Vec::with_capacity(name.len() + 18);
+    query.extend(id.to_be_bytes());
+    // A standard query, which the server should answer recursively.
+    query.extend(0x0100u16.to_be_bytes());
+    // One question, and no answers or other records.
+    query.extend([0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("{name:?} is not a valid DNS name"));
+        }
+        query.push(label.len() as u8);
+        query.extend(label.as_bytes());
+    }
+    query.push(0);
+    query.extend(SRV.to_be_bytes());
+    query.extend(IN.to_be_bytes());
+    Ok(query)
+}
+
+/// Parses the answer to the query with the message ID `id`, returning the
+/// SRV records in it, or `None` if it was truncated.
+fn parse_response(id: u16, response: &[u8]) -> Result<Option<Vec<Record>>, String> {
+    let mut reader = Reader {
+        message: response,
+        pos: 0,
+    };
+    if reader.u16()? != id {
+        return Err("the DNS server answered a different query".to_owned());
+    }
+    let flags = reader.u16()?;
+    if flags & 0x0200 != 0 {
+        return Ok(None);
+    }
+    match flags & 0x000f {
+        0 => {}
+        3 => return Err("no such service name".to_owned()),
+        code => return Err(format!("the DNS server failed with response code {code}")),
+    }
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.skip(4)?;
+    for _ in 0..questions {
+        reader.name()?;
+        reader.skip(4)?;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        reader.name()?;
+        let (kind, class) = (reader.u16()?, reader.u16()?);
+        // The record's time to live doesn't matter, since the records are
+        // looked up again on each connection.
+        reader.skip(4)?;
+        let len = usize::from(reader.u16()?);
+        let end = reader.pos + len;
+        // Answers may also include the records of aliases.
+        if kind == SRV && class == IN {
+            records.push(Record {
+                priority: reader.u16()?,
+                weight: reader.u16()?,
+                port: reader.u16()?,
+                target: reader.name()?,
+            });
+        }
+        reader.pos = end;
+    }
+    Ok(Some(records))
+}
+
+/// Picks the instance to connect to from `records`, as RFC 2782 says to:
+/// one with the lowest priority, picked at random in proportion to their
+/// weights.
+fn pick(records: &[Record], random: u64) -> Option<&Record> {
+    // A target of `.` says that the service isn't available at this name.
+    let available = records.iter().filter(|record| record.target != ".");
+    let priority = available.clone().map(|record| record.priority).min()?;
+    let candidates = available
+        .filter(|record| record.priority == priority)
+        .collect::<Vec<_>>();
+    let total = candidates
+        .iter()
+        .map(|record| u64::from(record.weight))
+        .sum::<u64>();
+    if total == 0 {
+        return candidates
+            .get((random % candidates.len() as u64) as usize)
+            .copied();
+    }
+    let mut at = random % total;
+    candidates.into_iter().find(|record| {
+        let weight = u64::from(record.weight);
+        if at < weight {
+            return true;
+        }
+        at -= weight;
+        false
+    })
+}
+
+fn random() -> u64 {
+    // Each new `RandomState` is randomly keyed, which is random enough to
+    // spread out connections over the instances.
+    RandomState::new().build_hasher().finish()
+}
+
+/// Reads the fields of a DNS message.
+struct Reader<'a> {
+    message: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    const TRUNCATED: &'static str = "the DNS server's answer ended early";
+
+    fn u8(&mut self) -> Result<u8, String> {
+        let byte = *self.message.get(self.pos).ok_or(Self::TRUNCATED)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        if self.pos + len > self.message.len() {
+            return Err(Self::TRUNCATED.to_owned());
+        }
+        self.pos += len;
+        Ok(())
+    }
+
+    /// Reads a name, following the pointers which compress names to an
+    /// earlier copy of their remaining labels.
+    fn name(&mut self) -> Result<String, String> {
+        let mut labels = Vec::new();
+        let mut pos = self.pos;
+        // Where the name ends in the message, once a pointer is followed.
+        let mut end = None;
+        // Each pointer has to point back, or names could loop.
+        let mut limit = pos;
+        loop {
+            let len = *self.message.get(pos).ok_or(Self::TRUNCATED)?;
+            match len {
+                0 => {
+                    pos += 1;
+                    break;
+                }
+                len if len & 0xc0 == 0xc0 => {
+                    let low = *self.message.get(pos + 1).ok_or(Self::TRUNCATED)?;
+                    let to = usize::from(len & 0x3f) << 8 | usize::from(low);
+                    if to >= limit {
+                        return Err("the DNS server's answer has a looping name".to_owned());
+                    }
+                    end.get_or_insert(pos + 2);
+                    limit = to;
+                    pos = to;
+                }
+                len => {
+                    let label = self
+                        .message
+                        .get(pos + 1..pos + 1 + usize::from(len))
+                        .ok_or(Self::TRUNCATED)?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    pos += 1 + usize::from(len);
+                }
+            }
+        }
+        self.pos = end.unwrap_or(pos);
+        if labels.is_empty() {
+            return Ok(".".to_owned());
+        }
+        Ok(labels.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(priority: u16, weight: u16, target: &str) -> Record {
+        Record {
+            priority,
+            weight,
+            port: 6669,
+            target: target.to_owned(),
+        }
+    }
+
+    /// Returns an answer to the query with the message ID `0x1234`, with one
+    /// SRV record.
+    fn answer() -> Vec<u8> {
+        let mut response = query(0x1234, "_console._tcp.example.com").unwrap();
+        // A response with one answer, which is recursive.
+        response[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 1]);
+        // The answer's name points to the question's.
+        response.extend([0xc0, 12]);
+        response.extend(SRV.to_be_bytes());
+        response.extend(IN.to_be_bytes());
+        response.extend(300u32.to_be_bytes());
+        let rdata = [&[0, 10, 0, 5, 0x1a, 0x0d][..], b"\x04api1", &[0xc0, 26]].concat();
+        response.extend((rdata.len() as u16).to_be_bytes());
+        response.extend(rdata);
+        response
+    }
+
+    #[test]
+    fn parses_answers() {
+        let mut response = answer();
+        assert_eq!(
+            parse_response(0x1234, &response).unwrap(),
+            Some(vec![Record {
+                priority: 10,
+                weight: 5,
+                port: 6669,
+                target: "api1.example.com".to_owned(),
+            }])
+        );
+        assert!(parse_response(0x4321, &response).is_err());
+        assert!(parse_response(0x1234, &response[..response.len() - 3]).is_err());
+
+        // A truncated answer.
+        response[2] |= 0x02;
+        assert_eq!(parse_response(0x1234, &response).unwrap(), None);
+
+        // No such name.
+        let mut response = query(1, "_console._tcp.example.com").unwrap();
+        response[2..4].copy_from_slice(&[0x81, 0x83]);
+        assert!(parse_response(1, &response).is_err());
+    }
+
+    #[test]
+    fn parses_arbitrary_answers_without_panicking() {
+        // A xorshift generator with a fixed seed, so that failures can be
+        // reproduced.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let answer = answer();
+        for _ in 0..10_000 {
+            // Random bytes, with the ID of the query so that they're parsed
+            // past it.
+            let len = 2 + (next() % 128) as usize;
+            let mut response = (0..len).map(|_| next() as u8).collect::<Vec<_>>();
+            response[..2].copy_from_slice(&0x1234u16.to_be_bytes());
+            let _ = parse_response(0x1234, &response);
+
+            // A valid answer with some of its bytes changed, and cut short.
+            let mut response = answer.clone();
+            for _ in 0..1 + next() % 4 {
+                let at = (next() % response.len() as u64) as usize;
+                response[at] = next() as u8;
+            }
+            response.truncate((next() % (response.len() as u64 + 1)) as usize);
+            let _ = parse_response(0x1234, &response);
+        }
+    }
+
+    #[test]
+    fn rejects_looping_names() {
+        let mut response = query(1, "example.com").unwrap();
+        response[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 1]);
+        let at = response.len() as u8;
+        response.extend([0xc0, at]);
+        assert!(parse_response(1, &response).is_err());
+    }
+
+    #[test]
+    fn picks_by_priority_and_weight() {
+        let records = [
+            record(20, 100, "backup"),
+            record(10, 1, "small"),
+            record(10, 3, "large"),
+            record(0, 0, "."),
+        ];
+        let picked = (0..4)
+            .map(|random| pick(&records, random).unwrap().target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(picked, ["small", "large", "large", "large"]);
+
+        let records = [record(0, 0, "a"), record(0, 0, "b")];
+        assert_eq!(pick(&records, 3).unwrap().target, "b");
+        assert_eq!(pick(&[record(0, 0, ".")], 0), None);
+        assert!(query(1, "bad..name").is_err());
+    }
+}
@@ -1,33 +1,36 @@
 use crate::{
+    editor::SourceLocation,
     intern::{self, InternedStr},
     state::{
-        pb_duration,
+        format_location, pb_duration,
         resources::Resource,
         store::{self, Id, Store},
         tasks::Task,
-        Attribute, Field, Metadata, Visibility,
+        Attribute, Field, FieldValue, Metadata, Visibility,
     },
     view,
 };
 use console_api as proto;
 use ratatui::text::Span;
 use std::{
-    cell::RefCell,
     collections::HashMap,
     convert::{TryFrom, TryInto},
-    rc::{Rc, Weak},
+    rc::Rc,
     time::{Duration, SystemTime},
 };
 
 #[derive(Default, Debug)]
 pub(crate) struct AsyncOpsState {
     async_ops: Store<AsyncOp>,
+    /// The filesystem operations issued by each task, from oldest to newest.
+    fs_ops: HashMap<Id<Task>, Vec<AsyncOpRef>>,
     dropped_events: u64,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 #[repr(usize)]
 pub(crate) enum SortBy {
+    #[default]
     Aid = 0,
     Task = 1,
     Source = 2,
@@ -37,6 +40,16 @@ pub(crate) enum SortBy {
     Polls = 6,
 }
 
+/// An async op's value for one of the keys it can be sorted by.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SortKey {
+    Id(Id<AsyncOp>),
+    TaskId(Option<Id<Task>>),
+    Text(InternedStr),
+    Duration(Duration),
+    Count(u64),
+}
+
 #[derive(Debug)]
 pub(crate) struct AsyncOp {
     id: Id<AsyncOp>,
@@ -44,7 +57,23 @@ pub(crate) struct AsyncOp {
     resource_id: Id<Resource>,
     meta_id: u64,
     source: InternedStr,
+    /// Where the async op was created, if the instrumented program captures
+    /// backtraces of async ops' creation.
+    location: String,
+    source_location: Option<SourceLocation>,
+    backtrace: Option<String>,
     stats: AsyncOpStats,
+    /// The most recent time the async op polled its resource, if it has.
+    last_poll_op: Option<PollOp>,
+}
+
+/// A poll of a resource from within an async op, such as a semaphore's
+/// `poll_acquire`.
+#[derive(Debug)]
+pub(crate) struct PollOp {
+    pub(crate) name: InternedStr,
+    /// Whether the poll returned `Ready`, rather than `Pending`.
+    pub(crate) is_ready: bool,
 }
 
 pub(crate) type AsyncOpRef = store::Ref<AsyncOp>;
@@ -62,31 +91,23 @@ struct AsyncOpStats {
     total: Option<Duration>,
     task_id: Option<Id<Task>>,
     task_id_str: InternedStr,
+    /// The path which the async op operates on, if it's a filesystem
+    /// operation.
+    path: Option<InternedStr>,
     formatted_attributes: Vec<Vec<Span<'static>>>,
 }
 
-impl Default for SortBy {
-    fn default() -> Self {
-        Self::Aid
-    }
-}
-
 impl SortBy {
-    pub fn sort(&self, now: SystemTime, ops: &mut [Weak<RefCell<AsyncOp>>]) {
+    /// Returns `op`'s value for this key as of `now`.
+    pub fn key(&self, now: SystemTime, op: &AsyncOp) -> SortKey {
         match self {
-            Self::Aid => ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().id)),
-            Self::Task => ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().task_id())),
-            Self::Source => {
-                ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().source.clone()))
-            }
-            Self::Total => {
-                ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().total(now)))
-            }
-            Self::Busy => ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().busy(now))),
-            Self::Idle => ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().idle(now))),
-            Self::Polls => {
-                ops.sort_unstable_by_key(|ao| ao.upgrade().map(|a| a.borrow().stats.polls))
-            }
+            Self::Aid => SortKey::Id(op.id),
+            Self::Task => SortKey::TaskId(op.task_id()),
+            Self::Source => SortKey::Text(op.source.clone()),
+            Self::Total => SortKey::Duration(op.total(now)),
+            Self::Busy => SortKey::Duration(op.busy(now)),
+            Self::Idle => SortKey::Duration(op.idle(now)),
+            Self::Polls => SortKey::Count(op.stats.polls),
         }
     }
 }
@@ -108,6 +129,8 @@ impl TryFrom<usize> for SortBy {
 }
 
 impl view::SortBy for SortBy {
+    type Key = SortKey;
+
     fn as_column(&self) -> usize {
         *self as usize
     }
@@ -179,6 +202,11 @@ impl AsyncOpsState {
                 };
 
                 let source = strings.string(async_op.source);
+                let source_location = async_op
+                    .location
+                    .as_ref()
+                    .and_then(SourceLocation::from_proto);
+                let location = format_location(async_op.location);
 
                 let async_op = AsyncOp {
                     id,
@@ -186,7 +214,11 @@ impl AsyncOpsState {
                     resource_id,
                     meta_id,
                     source,
+                    location,
+                    source_location,
+                    backtrace: async_op.backtrace,
                     stats,
+                    last_poll_op: None,
                 };
                 Some((id, async_op))
             });
@@ -199,6 +231,56 @@ impl AsyncOpsState {
         }
 
         self.dropped_events += update.dropped_events;
+        self.index_fs_ops();
+    }
+
+    /// Returns the filesystem operations issued by the task `task_id`, from
+    /// oldest to newest.
+    pub(crate) fn fs_ops_issued_by(&self, task_id: Id<Task>) -> &[AsyncOpRef] {
+        self.fs_ops
+            .get(&task_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Indexes the filesystem operations by the task which issued them.
+    ///
+    /// An async op's task is only known once it's been polled, so this is
+    /// rebuilt after each update, rather than when async ops are added.
+    fn index_fs_ops(&mut self) {
+        self.fs_ops.clear();
+        for async_op in self.async_ops.values() {
+            let op = async_op.borrow();
+            if let (Some(task_id), true) = (op.task_id(), op.is_fs_op()) {
+                self.fs_ops
+                    .entry(task_id)
+                    .or_default()
+                    .push(Rc::downgrade(async_op));
+            }
+        }
+        for ops in self.fs_ops.values_mut() {
+            ops.sort_by_key(|op| op.upgrade().map(|op| op.borrow().stats.created_at));
+        }
+    }
+
+    /// Records the polls of resources from within async ops, in the order
+    /// they happened.
+    pub(crate) fn update_poll_ops(
+        &mut self,
+        strings: &mut intern::Strings,
+        poll_ops: Vec<proto::resources::PollOp>,
+    ) {
+        for poll_op in poll_ops {
+            let Some(span_id) = poll_op.async_op_id.map(|id| id.id) else {
+                continue;
+            };
+            if let Some(async_op) = self.async_ops.get_by_span(span_id) {
+                async_op.borrow_mut().last_poll_op = Some(PollOp {
+                    name: strings.string(poll_op.name),
+                    is_ready: poll_op.is_ready,
+                });
+            }
+        }
     }
 
     pub(crate) fn retain_active(&mut self, now: SystemTime, retain_for: Duration) {
@@ -213,7 +295,8 @@ impl AsyncOpsState {
                     retain_for > dropped_for
                 })
                 .unwrap_or(true)
-        })
+        });
+        self.index_fs_ops();
     }
 
     pub(crate) fn dropped_events(&self) -> u64 {
@@ -246,6 +329,36 @@ impl AsyncOp {
         &self.source
     }
 
+    /// Returns whether this is a filesystem operation recorded by
+    /// `console_subscriber::fs`.
+    pub(crate) fn is_fs_op(&self) -> bool {
+        self.source.starts_with("fs::")
+    }
+
+    /// Returns the path which this async op operates on, if it's a
+    /// filesystem operation.
+    pub(crate) fn path(&self) -> Option<&str> {
+        self.stats.path.as_deref()
+    }
+
+    pub(crate) fn last_poll_op(&self) -> Option<&PollOp> {
+        self.last_poll_op.as_ref()
+    }
+
+    pub(crate) fn location(&self) -> &str {
+        &self.location
+    }
+
+    pub(crate) fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
+
+    /// Returns the backtrace captured when the async op was created, if the
+    /// instrumented program captures them.
+    pub(crate) fn backtrace(&self) -> Option<&str> {
+        self.backtrace.as_deref()
+    }
+
     pub(crate) fn total(&self, since: SystemTime) -> Duration {
         self.stats
             .total
@@ -274,6 +387,16 @@ impl AsyncOp {
         self.stats.polls
     }
 
+    /// Returns when the async op's most recent poll ended, unless it hasn't
+    /// been polled or is being polled right now.
+    pub(crate) fn last_poll_ended(&self) -> Option<SystemTime> {
+        let ended = self.stats.last_poll_ended?;
+        match self.stats.last_poll_started {
+            Some(started) if started > ended => None,
+            _ => Some(ended),
+        }
+    }
+
     pub(crate) fn dropped(&self) -> bool {
         self.stats.total.is_some()
     }
@@ -318,6 +441,15 @@ impl AsyncOpStats {
         let poll_stats = pb.poll_stats.expect("task should have poll stats");
         let busy = poll_stats.busy_time.map(pb_duration).unwrap_or_default();
         let idle = total.map(|total| total.checked_sub(busy).unwrap_or_default());
+        let path = attributes
+            .iter()
+            .find_map(|attribute| match attribute.field {
+                Field {
+                    ref name,
+                    value: FieldValue::Str(ref path),
+                } if &**name == "path" => Some(strings.string(path.clone())),
+                _ => None,
+            });
         let formatted_attributes = Attribute::make_formatted(styles, &mut attributes);
         let task_id = pb.task_id.map(|id| task_ids.id_for(id.id));
         let task_id_str = strings.string(
@@ -331,6 +463,7 @@ impl AsyncOpStats {
             idle,
             task_id,
             task_id_str,
+            path,
             busy,
             last_poll_started: poll_stats.last_poll_started.map(|v| v.try_into().unwrap()),
             last_poll_ended: poll_stats.last_poll_ended.map(|v| v.try_into().unwrap()),
@@ -0,0 +1,136 @@
+//! Estimating how far the target's clock is from the console's.
+//!
+//! Tasks' timestamps are taken by the target's clock, so durations up to
+//! "now" have to be measured by the target's clock too, or they come out
+//! wrong, or even negative, when the two clocks disagree.
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// How far the target's clock is ahead of the console's, or behind it if
+/// negative.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ClockSkew {
+    secs: f64,
+}
+
+/// Estimates the skew between the target's clock and the console's, from
+/// when updates say they were sent and when they were received.
+///
+/// An update can only be received after it was sent, so each update gives
+/// the skew less however long it took to arrive. The least delayed of the
+/// recent updates gives the closest estimate.
+#[derive(Debug, Default)]
+pub(crate) struct SkewEstimator {
+    /// How far ahead of the console's clock each recent update was sent, in
+    /// seconds, oldest first.
+    samples: VecDeque<f64>,
+}
+
+// === impl ClockSkew ===
+
+impl ClockSkew {
+    /// Skews smaller than this are within what an update usually takes to
+    /// arrive, so they aren't worth pointing out.
+    const NOTICEABLE: f64 = 0.1;
+
+    fn from_secs(secs: f64) -> Self {
+        Self { secs }
+    }
+
+    /// Returns the time by the target's clock at `local` by the console's.
+    pub(crate) fn to_target(self, local: SystemTime) -> SystemTime {
+        let skew = Duration::from_secs_f64(self.secs.abs());
+        let target = if self.secs >= 0.0 {
+            local.checked_add(skew)
+        } else {
+            local.checked_sub(skew)
+        };
+        target.unwrap_or(local)
+    }
+
+    /// Returns `true` if the skew is large enough to tell the user about.
+    pub(crate) fn is_noticeable(self) -> bool {
+        self.secs.abs() >= Self::NOTICEABLE
+    }
+}
+
+impl fmt::Display for ClockSkew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.1}s", self.secs)
+    }
+}
+
+// === impl SkewEstimator ===
+
+impl SkewEstimator {
+    /// How many of the most recent updates the skew is estimated from.
+    const SAMPLES: usize = 16;
+
+    /// Records an update which the target sent at `sent_at` by its clock,
+    /// and which was received at `received_at` by the console's.
+    pub(crate) fn record(&mut self, sent_at: SystemTime, received_at: SystemTime) {
+        let ahead = match sent_at.duration_since(received_at) {
+            Ok(ahead) => ahead.as_secs_f64(),
+            Err(behind) => -behind.duration().as_secs_f64(),
+        };
+        if self.samples.len() == Self::SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ahead);
+    }
+
+    /// Returns the estimated skew, if any updates have been recorded.
+    pub(crate) fn estimate(&self) -> Option<ClockSkew> {
+        self.samples
+            .iter()
+            .copied()
+            .reduce(f64::max)
+            .map(ClockSkew::from_secs)
+    }
+
+    /// Forgets the recorded updates, such as when reconnecting to a target
+    /// which may have restarted on another host.
+    pub(crate) fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn estimates_from_least_delayed_update() {
+        let mut skew = SkewEstimator::default();
+        assert_eq!(skew.estimate(), None);
+        // The target's clock is 2s behind, and updates take 50-300ms.
+        skew.record(at(10_000), at(12_300));
+        skew.record(at(11_000), at(13_050));
+        skew.record(at(12_000), at(14_200));
+        let estimate = skew.estimate().unwrap();
+        assert_eq!(estimate.to_string(), "-2.0s");
+        assert_eq!(estimate.to_target(at(13_050)), at(11_000));
+        assert!(estimate.is_noticeable());
+    }
+
+    #[test]
+    fn forgets_old_updates() {
+        let mut skew = SkewEstimator::default();
+        skew.record(at(5_000), at(1_000));
+        for i in 0..SkewEstimator::SAMPLES as u64 {
+            skew.record(at(i * 1000), at(i * 1000 + 20));
+        }
+        let estimate = skew.estimate().unwrap();
+        assert_eq!(estimate.to_target(at(1_020)), at(1_000));
+        assert!(!estimate.is_noticeable());
+        skew.reset();
+        assert_eq!(skew.estimate(), None);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::state::tasks::Task;
+use std::{fmt, iter::Peekable, str::Chars, str::FromStr, time::SystemTime};
+
+/// An arithmetic expression over a task's statistics, which is displayed as a
+/// sortable column in the task list.
+///
+/// Expressions combine the task's statistics with `+`, `-`, `*`, `/` and
+/// parentheses, such as `busy / total` or `polls / total_secs`. Durations are
+/// in seconds, and may be written with a `_secs` suffix to make that clear.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Expr {
+    /// The expression as it was written, which is the column's header.
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseError(String);
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(f64),
+    Stat(Stat),
+    Neg(Box<Node>),
+    Binary(Box<Node>, Op, Box<Node>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stat {
+    Total,
+    Busy,
+    Scheduled,
+    Idle,
+    BusyPercent,
+    ScheduledPercent,
+    Polls,
+    Wakes,
+    PollsPerSec,
+    WakesPerSec,
+    Wakers,
+    SelfWakes,
+    Warnings,
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+// === impl Expr ===
+
+impl Expr {
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression for `task` at `now`.
+    ///
+    /// Returns `None` if the result is not a finite number, such as when
+    /// dividing by zero.
+    pub(crate) fn eval(&self, task: &Task, now: SystemTime) -> Option<f64> {
+        Some(self.node.eval(task, now)).filter(|value| value.is_finite())
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+        };
+        let node = parser.expr()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.chars.next() {
+            return Err(ParseError(format!(
+                "unexpected `{c}` in sort expression `{s}`"
+            )));
+        }
+        Ok(Self {
+            source: s.trim().to_owned(),
+            node,
+        })
+    }
+}
+
+// Expressions are compared by how they're written, since the parsed
+// expression contains floating-point numbers.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Expr {}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Expr {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Expr> for String {
+    fn from(expr: Expr) -> Self {
+        expr.source
+    }
+}
+
+// === impl Node ===
+
+impl Node {
+    fn eval(&self, task: &Task, now: SystemTime) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Stat(stat) => stat.eval(task, now),
+            Self::Neg(node) => -node.eval(task, now),
+            Self::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(task, now), rhs.eval(task, now));
+                match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                }
+            }
+        }
+    }
+}
+
+// === impl Stat ===
+
+impl Stat {
+    const ALL: &'static [(&'static str, Stat)] = &[
+        ("total", Self::Total),
+        ("busy", Self::Busy),
+        ("scheduled", Self::Scheduled),
+        ("idle", Self::Idle),
+        ("busy_percent", Self::BusyPercent),
+        ("scheduled_percent", Self::ScheduledPercent),
+        ("polls", Self::Polls),
+        ("wakes", Self::Wakes),
+        ("polls_per_sec", Self::PollsPerSec),
+        ("wakes_per_sec", Self::WakesPerSec),
+        ("wakers", Self::Wakers),
+        ("self_wakes", Self::SelfWakes),
+        ("warnings", Self::Warnings),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        let stat = Self::ALL
+            .iter()
+            .find(|(stat_name, _)| *stat_name == name.strip_suffix("_secs").unwrap_or(name))
+            .map(|&(_, stat)| stat)?;
+        // Only durations can be written in seconds.
+        if name.ends_with("_secs") && !stat.is_duration() {
+            return None;
+        }
+        Some(stat)
+    }
+
+    fn is_duration(self) -> bool {
+        matches!(
+            self,
+            Self::Total | Self::Busy | Self::Scheduled | Self::Idle
+        )
+    }
+
+    fn eval(self, task: &Task, now: SystemTime) -> f64 {
+        match self {
+            Self::Total => task.total(now).as_secs_f64(),
+            Self::Busy => task.busy(now).as_secs_f64(),
+            Self::Scheduled => task.scheduled(now).as_secs_f64(),
+            Self::Idle => task.idle(now).as_secs_f64(),
+            Self::BusyPercent => task.busy_percent(now),
+            Self::ScheduledPercent => task.scheduled_percent(now),
+            Self::Polls => task.total_polls() as f64,
+            Self::Wakes => task.wakes() as f64,
+            // A rate which isn't known yet makes the expression unknown too.
+            Self::PollsPerSec => task.polls_per_sec(now).unwrap_or(f64::NAN),
+            Self::WakesPerSec => task.wakes_per_sec(now).unwrap_or(f64::NAN),
+            Self::Wakers => task.waker_count() as f64,
+            Self::SelfWakes => task.self_wakes() as f64,
+            Self::Warnings => task.warnings().len() as f64,
+        }
+    }
+}
+
+// === impl Parser ===
+
+impl Parser<'_> {
+    fn expr(&mut self) -> Result<Node, ParseError> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.op(&[('+', Op::Add), ('-', Op::Sub)]) {
+            lhs = Node::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Node, ParseError> {
+        let mut lhs = self.factor()?;
+        while let Some(op) = self.op(&[('*', Op::Mul), ('/', Op::Div)]) {
+            lhs = Node::Binary(Box::new(lhs), op, Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Node, ParseError> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('-') => {
+                self.chars.next();
+                Ok(Node::Neg(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.chars.next();
+                let node = self.expr()?;
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some(')') => Ok(node),
+                    _ => Err(ParseError("expected `)` in sort expression".to_owned())),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                number
+                    .parse()
+                    .map(Node::Number)
+                    .map_err(|e| ParseError(format!("invalid number `{number}`: {e}")))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                Stat::from_name(&name).map(Node::Stat).ok_or_else(|| {
+                    let stats = Stat::ALL
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ParseError(format!(
+                        "unknown statistic `{name}` in sort expression, expected one of: {stats}"
+                    ))
+                })
+            }
+            Some(c) => Err(ParseError(format!("unexpected `{c}` in sort expression"))),
+            None => Err(ParseError("sort expression ended early".to_owned())),
+        }
+    }
+
+    fn op(&mut self, ops: &[(char, Op)]) -> Option<Op> {
+        self.skip_whitespace();
+        let c = self.chars.peek()?;
+        let &(_, op) = ops.iter().find(|(op_char, _)| op_char == c)?;
+        self.chars.next();
+        Some(op)
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.chars.next_if(|&c| f(c)) {
+            taken.push(c);
+        }
+        taken
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+// === impl ParseError ===
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Node {
+        s.parse::<Expr>().unwrap().node
+    }
+
+    #[test]
+    fn parse_precedence() {
+        assert_eq!(
+            parse("busy / total"),
+            Node::Binary(
+                Box::new(Node::Stat(Stat::Busy)),
+                Op::Div,
+                Box::new(Node::Stat(Stat::Total)),
+            )
+        );
+        assert_eq!(
+            parse("1 + polls * 2"),
+            Node::Binary(
+                Box::new(Node::Number(1.0)),
+                Op::Add,
+                Box::new(Node::Binary(
+                    Box::new(Node::Stat(Stat::Polls)),
+                    Op::Mul,
+                    Box::new(Node::Number(2.0)),
+                )),
+            )
+        );
+        assert_eq!(
+            parse("-(idle_secs)"),
+            Node::Neg(Box::new(Node::Stat(Stat::Idle)))
+        );
+        assert_eq!(
+            parse("wakes_per_sec / polls_per_sec"),
+            Node::Binary(
+                Box::new(Node::Stat(Stat::WakesPerSec)),
+                Op::Div,
+                Box::new(Node::Stat(Stat::PollsPerSec)),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("".parse::<Expr>().is_err());
+        assert!("busy /".parse::<Expr>().is_err());
+        assert!("(busy".parse::<Expr>().is_err());
+        assert!("busy total".parse::<Expr>().is_err());
+        assert!("polls_secs".parse::<Expr>().is_err());
+        assert!("polls_per_sec_secs".parse::<Expr>().is_err());
+        assert!("latency".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn display_is_source() {
+        let expr = " polls / total_secs ".parse::<Expr>().unwrap();
+        assert_eq!(expr.to_string(), "polls / total_secs");
+    }
+}
@@ -0,0 +1,88 @@
+//! Builders for the messages which tests merge into a [`State`].
+//!
+//! [`State`]: super::State
+use console_api as proto;
+
+/// Returns the time `secs` seconds after a fixed point in time.
+pub(crate) fn timestamp(secs: i64) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: 1_700_000_000 + secs,
+        nanos: 0,
+    }
+}
+
+/// Returns the location of `line` in `src/main.rs`.
+pub(crate) fn location(line: u32) -> proto::Location {
+    proto::Location {
+        file: Some("src/main.rs".to_owned()),
+        line: Some(line),
+        column: Some(5),
+        ..Default::default()
+    }
+}
+
+/// Returns a spawned task with the ID `id`, which is also its `task.id`
+/// field.
+pub(crate) fn task(id: u64) -> proto::tasks::Task {
+    proto::tasks::Task {
+        id: Some(proto::Id { id }),
+        metadata: Some(proto::MetaId { id: 1 }),
+        fields: vec![proto::Field {
+            metadata_id: Some(proto::MetaId { id: 1 }),
+            name: Some(proto::field::Name::StrName("task.id".to_owned())),
+            value: Some(proto::field::Value::U64Val(id)),
+        }],
+        ..Default::default()
+    }
+}
+
+/// Returns the stats of a task which was created at [`timestamp(0)`] and has
+/// been polled `polls` times.
+///
+/// [`timestamp(0)`]: timestamp
+pub(crate) fn stats(polls: u64) -> proto::tasks::Stats {
+    proto::tasks::Stats {
+        created_at: Some(timestamp(0)),
+        poll_stats: Some(proto::PollStats {
+            polls,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Returns an update at [`timestamp(now)`], which registers the metadata of
+/// spawned tasks, and adds `tasks` with their stats.
+///
+/// [`timestamp(now)`]: timestamp
+pub(crate) fn update(
+    now: i64,
+    tasks: impl IntoIterator<Item = (proto::tasks::Task, proto::tasks::Stats)>,
+) -> proto::instrument::Update {
+    let (new_tasks, stats_update) = tasks
+        .into_iter()
+        .map(|(task, stats)| {
+            let id = task.id.as_ref().expect("tasks have an ID").id;
+            (task, (id, stats))
+        })
+        .unzip();
+    proto::instrument::Update {
+        now: Some(timestamp(now)),
+        new_metadata: Some(proto::RegisterMetadata {
+            metadata: vec![proto::register_metadata::NewMetadata {
+                id: Some(proto::MetaId { id: 1 }),
+                metadata: Some(proto::Metadata {
+                    name: "runtime.spawn".to_owned(),
+                    target: "tokio::task".to_owned(),
+                    ..Default::default()
+                }),
+            }],
+        }),
+        task_update: Some(proto::tasks::TaskUpdate {
+            new_tasks,
+            stats_update,
+            dropped_events: 0,
+        }),
+        ..Default::default()
+    }
+}
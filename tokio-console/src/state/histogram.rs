@@ -1,6 +1,12 @@
 use console_api::tasks as proto;
-use hdrhistogram::Histogram;
-use std::{io::Cursor, time::Duration};
+use hdrhistogram::{
+    serialization::{interval_log, V2DeflateSerializer},
+    Histogram,
+};
+use std::{
+    io::{self, Cursor},
+    time::{Duration, SystemTime},
+};
 
 #[derive(Debug)]
 pub(crate) struct DurationHistogram {
@@ -30,6 +36,26 @@ impl DurationHistogram {
         })
     }
 
+    /// Returns a histogram of `durations`, such as the mean poll times of
+    /// many tasks.
+    pub(crate) fn from_durations(durations: impl IntoIterator<Item = Duration>) -> Self {
+        // Three significant figures is always a valid precision.
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        for duration in durations {
+            let nanos = duration.as_nanos().try_into().unwrap_or(u64::MAX);
+            // The histogram grows to fit each duration, unless it's too long
+            // to be tracked at all.
+            if histogram.record(nanos).is_err() {
+                histogram.saturating_record(nanos);
+            }
+        }
+        Self {
+            histogram,
+            high_outliers: 0,
+            highest_outlier: None,
+        }
+    }
+
     pub(crate) fn from_proto(proto: &proto::DurationHistogram) -> Option<Self> {
         let histogram = deserialize_histogram(&proto.raw_histogram[..])?;
         Some(Self {
@@ -40,8 +66,80 @@ impl DurationHistogram {
     }
 }
 
+/// Writes `histograms` to `out` as an HdrHistogram interval log, with each
+/// histogram tagged with its name and covering the same interval.
+///
+/// Histograms are compressed as the Java implementation's
+/// `HistogramLogWriter` writes them, so that the log can be read by existing
+/// HdrHistogram tools. Values are in nanoseconds.
+pub(crate) fn write_interval_log(
+    out: &mut impl io::Write,
+    comment: &str,
+    start: SystemTime,
+    duration: Duration,
+    histograms: &[(&str, &DurationHistogram)],
+) -> color_eyre::Result<()> {
+    let mut serializer = V2DeflateSerializer::new();
+    let mut log = interval_log::IntervalLogWriterBuilder::new()
+        .add_comment(comment)
+        .add_comment("values are in nanoseconds, and the max column is in milliseconds")
+        .with_start_time(start)
+        .with_base_time(start)
+        .with_max_value_divisor(1_000_000.0)
+        .begin_log_with(out, &mut serializer)?;
+    for (name, histogram) in histograms {
+        log.write_histogram(
+            &histogram.histogram,
+            Duration::ZERO,
+            duration,
+            interval_log::Tag::new(name),
+        )?;
+    }
+    Ok(())
+}
+
 fn deserialize_histogram(bytes: &[u8]) -> Option<Histogram<u64>> {
     hdrhistogram::serialization::Deserializer::new()
         .deserialize(&mut Cursor::new(&bytes))
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_log_roundtrips() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record(1_000).unwrap();
+        histogram.record(5_000_000).unwrap();
+        let histogram = DurationHistogram {
+            histogram,
+            high_outliers: 0,
+            highest_outlier: None,
+        };
+
+        let mut log = Vec::new();
+        write_interval_log(
+            &mut log,
+            "task 1",
+            SystemTime::UNIX_EPOCH,
+            Duration::from_secs(2),
+            &[("poll", &histogram), ("scheduled", &histogram)],
+        )
+        .unwrap();
+
+        let entries = interval_log::IntervalLogIterator::new(&log)
+            .filter_map(|entry| match entry.unwrap() {
+                interval_log::LogEntry::Interval(interval) => Some(interval),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tag().unwrap().as_str(), "poll");
+        assert_eq!(entries[1].tag().unwrap().as_str(), "scheduled");
+        assert_eq!(entries[0].duration(), Duration::from_secs(2));
+        // The max is in milliseconds, at the histogram's precision.
+        assert!((entries[0].max() - 5.0).abs() < 0.01);
+    }
+}
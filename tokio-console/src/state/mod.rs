@@ -1,6 +1,9 @@
-use self::{async_ops::AsyncOpsState, resources::ResourcesState};
+use self::{async_ops::AsyncOpsState, clock::ClockSkew, resources::ResourcesState};
 use crate::{
+    event_log::EventLog,
     intern::{self, InternedStr},
+    slo::Slo,
+    trends::Trends,
     view,
     warnings::Linter,
 };
@@ -16,11 +19,15 @@ use std::{
     convert::{TryFrom, TryInto},
     fmt,
     rc::Rc,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
-use tasks::{Details, Task, TasksState};
+use tasks::{Column, Details, Dump, Task, TasksState};
 
 pub mod async_ops;
+pub mod clock;
+pub mod expr;
+#[cfg(test)]
+pub(crate) mod fixtures;
 pub mod histogram;
 pub mod resources;
 pub mod store;
@@ -34,12 +41,37 @@ pub(crate) type DetailsRef = Rc<RefCell<Option<Details>>>;
 pub(crate) struct State {
     metas: HashMap<u64, Metadata>,
     last_updated_at: Option<SystemTime>,
+    /// When the last update was received, by the console's clock.
+    last_received_at: Option<Instant>,
+    /// How far the target's clock is from the console's, as estimated by the
+    /// connection.
+    clock_skew: ClockSkew,
     temporality: Temporality,
+    /// When the target was paused, as reported by the target.
+    paused_at: Option<SystemTime>,
+    /// A description of the client which paused the target, as reported by
+    /// the target.
+    paused_by: Option<String>,
+    /// The number of updates which the target couldn't send to the console
+    /// because it was falling behind, as reported by the target.
+    coalesced_updates: u64,
     tasks_state: TasksState,
     resources_state: ResourcesState,
     async_ops_state: AsyncOpsState,
     current_task_details: DetailsRef,
+    /// Whether the target can't send tasks' details, so that their
+    /// histograms aren't shown.
+    task_details_unavailable: bool,
     retain_for: Option<Duration>,
+    /// Whether completed tasks, resources and async ops are retained for
+    /// `retain_for`, or the user has chosen otherwise while the console runs.
+    retention: Retention,
+    /// Latency objectives for tasks' histograms.
+    slos: Vec<Slo>,
+    /// Samples of metrics over the session, for spotting slow leaks.
+    trends: Trends,
+    /// What changed between consecutive updates.
+    event_log: EventLog,
     strings: intern::Strings,
 }
 
@@ -62,7 +94,7 @@ pub(crate) struct Field {
     pub(crate) value: FieldValue,
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) enum FieldValue {
     Bool(bool),
     Str(String),
@@ -71,20 +103,28 @@ pub(crate) enum FieldValue {
     Debug(String),
 }
 
-#[derive(Debug)]
+/// How long completed tasks, resources and async ops are retained for, which
+/// can be changed while the console is running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Retention {
+    /// For as long as `--retain-for` configures.
+    #[default]
+    Configured,
+    /// Until they're cleared, so that they can be inspected after the fact.
+    Forever,
+    /// Not at all, so that very busy targets' lists only show what's live.
+    Immediately,
+}
+
+#[derive(Debug, Default)]
 pub(crate) enum Temporality {
     Unpausing,
+    #[default]
     Live,
     Pausing,
     Paused,
 }
 
-impl Default for Temporality {
-    fn default() -> Self {
-        Self::Live
-    }
-}
-
 impl From<proto::instrument::Temporality> for Temporality {
     fn from(pb: proto::instrument::Temporality) -> Self {
         match pb {
@@ -106,6 +146,27 @@ impl State {
         self
     }
 
+    pub(crate) fn with_slos(mut self, slos: impl IntoIterator<Item = Slo>) -> Self {
+        self.slos.extend(slos);
+        self
+    }
+
+    pub(crate) fn slos(&self) -> &[Slo] {
+        &self.slos
+    }
+
+    pub(crate) fn trends(&self) -> &Trends {
+        &self.trends
+    }
+
+    pub(crate) fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    pub(crate) fn event_log_mut(&mut self) -> &mut EventLog {
+        &mut self.event_log
+    }
+
     pub(crate) fn with_task_linters(
         mut self,
         linters: impl IntoIterator<Item = Linter<Task>>,
@@ -114,10 +175,40 @@ impl State {
         self
     }
 
+    pub(crate) fn with_saved_bookmarks(
+        mut self,
+        bookmarks: HashMap<tasks::TaskKey, Option<String>>,
+    ) -> Self {
+        self.tasks_state.set_saved_bookmarks(bookmarks);
+        self
+    }
+
+    pub(crate) fn with_task_columns(mut self, columns: impl IntoIterator<Item = Column>) -> Self {
+        self.tasks_state.columns.extend(columns);
+        self
+    }
+
     pub(crate) fn last_updated_at(&self) -> Option<SystemTime> {
         self.last_updated_at
     }
 
+    /// Returns the current time by the target's clock, which tasks'
+    /// timestamps are taken by.
+    pub(crate) fn target_now(&self) -> SystemTime {
+        self.clock_skew.to_target(SystemTime::now())
+    }
+
+    /// Sets how far the target's clock is from the console's, or that it
+    /// isn't known.
+    pub(crate) fn set_clock_skew(&mut self, skew: Option<ClockSkew>) {
+        self.clock_skew = skew.unwrap_or_default();
+    }
+
+    /// Returns how long ago the last update was received, if one has been.
+    pub(crate) fn data_age(&self) -> Option<Duration> {
+        self.last_received_at.map(|at| at.elapsed())
+    }
+
     pub(crate) fn update(
         &mut self,
         styles: &view::Styles,
@@ -127,6 +218,10 @@ impl State {
         if let Some(now) = update.now.map(|v| v.try_into().unwrap()) {
             self.last_updated_at = Some(now);
         }
+        self.last_received_at = Some(Instant::now());
+        if let Some(lag) = update.lag {
+            self.coalesced_updates = lag.total_coalesced_updates;
+        }
 
         let strings = &mut self.strings;
         if let Some(new_metadata) = update.new_metadata {
@@ -150,10 +245,15 @@ impl State {
                 &self.metas,
                 tasks_update,
                 visibility,
+                self.last_updated_at,
             )
         }
 
-        if let Some(resources_update) = update.resource_update {
+        let mut poll_ops = Vec::new();
+        if let Some(mut resources_update) = update.resource_update {
+            // Poll ops are recorded on their async ops, which may be new in
+            // this update.
+            poll_ops = std::mem::take(&mut resources_update.new_poll_ops);
             let visibility = if matches!(current_view, view::ViewState::ResourcesList) {
                 Visibility::Show
             } else {
@@ -184,6 +284,19 @@ impl State {
                 visibility,
             )
         }
+
+        self.async_ops_state
+            .update_poll_ops(&mut self.strings, poll_ops);
+
+        self.tasks_state
+            .update_awaiting(&self.async_ops_state, &self.resources_state);
+
+        if let Some(now) = self.last_updated_at {
+            self.trends
+                .record(now, &self.tasks_state, &self.resources_state);
+            self.event_log
+                .record(now, &self.tasks_state, &self.resources_state);
+        }
     }
 
     pub(crate) fn retain_active(&mut self) {
@@ -191,7 +304,7 @@ impl State {
             return;
         }
 
-        if let (Some(now), Some(retain_for)) = (self.last_updated_at(), self.retain_for) {
+        if let (Some(now), Some(retain_for)) = (self.last_updated_at(), self.retained_for()) {
             self.tasks_state.retain_active(now, retain_for);
             self.resources_state.retain_active(now, retain_for);
             self.async_ops_state.retain_active(now, retain_for);
@@ -202,11 +315,42 @@ impl State {
         self.strings.retain_referenced();
     }
 
+    /// Returns how long completed tasks, resources and async ops are retained
+    /// for, or `None` if they're retained forever.
+    fn retained_for(&self) -> Option<Duration> {
+        match self.retention {
+            Retention::Configured => self.retain_for,
+            Retention::Forever => None,
+            Retention::Immediately => Some(Duration::ZERO),
+        }
+    }
+
+    pub(crate) fn retention(&self) -> Retention {
+        self.retention
+    }
+
+    /// Switches between retaining completed tasks, resources and async ops
+    /// for as long as configured, forever, and not at all.
+    pub(crate) fn cycle_retention(&mut self) {
+        self.retention = match self.retention {
+            Retention::Configured => Retention::Forever,
+            Retention::Forever => Retention::Immediately,
+            Retention::Immediately => Retention::Configured,
+        };
+    }
+
+    /// Drops every completed task which isn't bookmarked, regardless of how
+    /// long tasks are retained for.
+    pub(crate) fn clear_completed(&mut self) {
+        self.tasks_state.clear_completed();
+        self.strings.retain_referenced();
+    }
+
     pub(crate) fn task_details_ref(&self) -> DetailsRef {
         self.current_task_details.clone()
     }
 
-    pub(crate) fn tasks_state(&mut self) -> &TasksState {
+    pub(crate) fn tasks_state(&self) -> &TasksState {
         &self.tasks_state
     }
 
@@ -214,7 +358,7 @@ impl State {
         &mut self.tasks_state
     }
 
-    pub(crate) fn resources_state(&mut self) -> &ResourcesState {
+    pub(crate) fn resources_state(&self) -> &ResourcesState {
         &self.resources_state
     }
 
@@ -232,6 +376,27 @@ impl State {
 
     pub(crate) fn update_task_details(&mut self, update: proto::tasks::TaskDetails) {
         if let Some(id) = update.task_id {
+            // Keep the task's activity from its earlier updates, which aren't
+            // sent again.
+            let mut activity = self
+                .current_task_details
+                .borrow_mut()
+                .take()
+                .filter(|details| details.span_id == id.id)
+                .map(|details| details.activity)
+                .unwrap_or_default();
+            let now = update
+                .now
+                .and_then(|now| SystemTime::try_from(now).ok())
+                .or(self.last_updated_at);
+            let task = self
+                .tasks_state
+                .task_by_span(id.id)
+                .and_then(|task| task.upgrade());
+            if let (Some(task), Some(now)) = (task, now) {
+                let task = task.borrow();
+                activity.record(task.busy(now), task.wakes());
+            }
             let details = Details {
                 span_id: id.id,
                 poll_times_histogram: update
@@ -242,16 +407,55 @@ impl State {
                     .scheduled_times_histogram
                     .as_ref()
                     .and_then(histogram::DurationHistogram::from_proto),
+                recent_polls: update
+                    .recent_polls
+                    .into_iter()
+                    .filter_map(|poll| {
+                        Some(tasks::Poll {
+                            started: poll.started_at?.try_into().ok()?,
+                            duration: poll.duration?.try_into().ok()?,
+                        })
+                    })
+                    .collect(),
+                activity,
             };
 
             *self.current_task_details.borrow_mut() = Some(details);
         }
     }
 
+    /// Returns the task dump which the target sent, with its fields' names
+    /// resolved, if it describes a task.
+    pub(crate) fn task_dump(&mut self, dump: proto::instrument::TaskDump) -> Option<Dump> {
+        let task = dump.task?;
+        let meta = self.metas.get(&task.metadata?.id)?;
+        let fields = task
+            .fields
+            .into_iter()
+            .filter_map(|field| Field::from_proto(field, meta, &mut self.strings))
+            .map(|field| (field.name.to_string(), field.value.to_string()))
+            .collect();
+        Some(Dump {
+            span_id: task.id?.id,
+            taken_at: dump.now.and_then(|now| now.try_into().ok()),
+            location: format_location(task.location),
+            fields,
+            backtrace: dump.backtrace,
+        })
+    }
+
     pub(crate) fn unset_task_details(&mut self) {
         *self.current_task_details.borrow_mut() = None;
     }
 
+    pub(crate) fn task_details_unavailable(&self) -> bool {
+        self.task_details_unavailable
+    }
+
+    pub(crate) fn set_task_details_unavailable(&mut self, unavailable: bool) {
+        self.task_details_unavailable = unavailable;
+    }
+
     // temporality methods
     pub(crate) fn temporality(&self) -> &Temporality {
         &self.temporality
@@ -269,6 +473,31 @@ impl State {
         self.temporality = proto::instrument::Temporality::try_from(state.temporality)
             .expect("invalid temporality")
             .into();
+        self.paused_at = state.paused_at.and_then(|at| at.try_into().ok());
+        self.paused_by = state.paused_by;
+    }
+
+    /// Returns how long the target has been paused, if it is paused and the
+    /// target reported when it was paused.
+    pub(crate) fn paused_for(&self) -> Option<Duration> {
+        let paused_at = self.paused_at?;
+        Some(
+            self.target_now()
+                .duration_since(paused_at)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns a description of the client which paused the target, if it is
+    /// paused and the target reported who paused it.
+    pub(crate) fn paused_by(&self) -> Option<&str> {
+        self.paused_by.as_deref()
+    }
+
+    /// Returns the number of updates which the target had to merge together
+    /// because the console wasn't receiving them fast enough.
+    pub(crate) fn coalesced_updates(&self) -> u64 {
+        self.coalesced_updates
     }
 
     pub(crate) fn is_paused(&self) -> bool {
@@ -299,6 +528,7 @@ impl Field {
     const TASK_ID: &'static str = "task.id";
     const SIZE_BYTES: &'static str = "size.bytes";
     const ORIGINAL_SIZE_BYTES: &'static str = "original_size.bytes";
+    const FN: &'static str = "fn";
 
     /// Creates a new Field with a pre-interned `name` and a `FieldValue`.
     fn new(name: InternedStr, value: FieldValue) -> Self {
@@ -377,7 +607,7 @@ impl Field {
 
     fn make_formatted(styles: &view::Styles, fields: &mut [Field]) -> Vec<Vec<Span<'static>>> {
         let key_style = styles.fg(Color::LightBlue).add_modifier(Modifier::BOLD);
-        let delim_style = styles.fg(Color::LightBlue).add_modifier(Modifier::DIM);
+        let delim_style = styles.fg(Color::LightBlue).add_modifier(styles.dim());
         let val_style = styles.fg(Color::Yellow);
 
         fields.sort_unstable();
@@ -501,7 +731,7 @@ impl Attribute {
         attributes: &mut [Attribute],
     ) -> Vec<Vec<Span<'static>>> {
         let key_style = styles.fg(Color::LightBlue).add_modifier(Modifier::BOLD);
-        let delim_style = styles.fg(Color::LightBlue).add_modifier(Modifier::DIM);
+        let delim_style = styles.fg(Color::LightBlue).add_modifier(styles.dim());
         let val_style = styles.fg(Color::Yellow);
         let unit_style = styles.fg(Color::LightBlue);
 
@@ -585,6 +815,164 @@ fn pb_duration(dur: prost_types::Duration) -> Duration {
 mod tests {
     use super::*;
 
+    #[test]
+    fn poll_ops_are_recorded_on_new_async_ops() {
+        use clap::Parser;
+
+        let styles =
+            view::Styles::from_config(crate::config::ViewOptions::parse_from(["tokio-console"]));
+        let created_at = fixtures::timestamp(0);
+        let poll_op = |is_ready| proto::resources::PollOp {
+            metadata: Some(proto::MetaId { id: 1 }),
+            resource_id: Some(proto::Id { id: 2 }),
+            name: "poll_acquire".to_owned(),
+            task_id: None,
+            async_op_id: Some(proto::Id { id: 3 }),
+            is_ready,
+        };
+        let update = proto::instrument::Update {
+            now: Some(created_at),
+            new_metadata: Some(proto::RegisterMetadata {
+                metadata: vec![proto::register_metadata::NewMetadata {
+                    id: Some(proto::MetaId { id: 1 }),
+                    metadata: Some(proto::Metadata {
+                        name: "runtime.resource.async_op".to_owned(),
+                        target: "tokio::sync::semaphore".to_owned(),
+                        ..Default::default()
+                    }),
+                }],
+            }),
+            // The poll ops are sent with the resources, before the async op
+            // they're part of.
+            resource_update: Some(proto::resources::ResourceUpdate {
+                new_poll_ops: vec![poll_op(true), poll_op(false)],
+                ..Default::default()
+            }),
+            async_op_update: Some(proto::async_ops::AsyncOpUpdate {
+                new_async_ops: vec![proto::async_ops::AsyncOp {
+                    id: Some(proto::Id { id: 3 }),
+                    metadata: Some(proto::MetaId { id: 1 }),
+                    source: "Semaphore::acquire".to_owned(),
+                    resource_id: Some(proto::Id { id: 2 }),
+                    ..Default::default()
+                }],
+                stats_update: [(
+                    3,
+                    proto::async_ops::Stats {
+                        created_at: Some(created_at),
+                        poll_stats: Some(Default::default()),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+
+        let mut state = State::default();
+        state.update(&styles, &view::ViewState::TasksList, update);
+        let async_op = state
+            .async_ops_state()
+            .async_ops()
+            .next()
+            .and_then(|async_op| async_op.upgrade())
+            .expect("the async op should be in the state");
+        let async_op = async_op.borrow();
+        let poll_op = async_op.last_poll_op().expect("a poll op was recorded");
+        assert_eq!(&*poll_op.name, "poll_acquire");
+        // The most recent poll op is kept.
+        assert!(!poll_op.is_ready);
+    }
+
+    #[test]
+    fn fs_ops_are_indexed_by_task() {
+        use clap::Parser;
+
+        let styles =
+            view::Styles::from_config(crate::config::ViewOptions::parse_from(["tokio-console"]));
+        let created_at = fixtures::timestamp(0);
+        let async_op = |id, source: &str| proto::async_ops::AsyncOp {
+            id: Some(proto::Id { id }),
+            metadata: Some(proto::MetaId { id: 1 }),
+            source: source.to_owned(),
+            resource_id: Some(proto::Id { id: 2 }),
+            ..Default::default()
+        };
+        let stats = |path: Option<&str>| proto::async_ops::Stats {
+            created_at: Some(created_at),
+            task_id: Some(proto::Id { id: 4 }),
+            poll_stats: Some(Default::default()),
+            attributes: path
+                .map(|path| proto::Attribute {
+                    field: Some(proto::Field {
+                        name: Some(proto::field::Name::StrName("path".to_owned())),
+                        value: Some(proto::field::Value::StrVal(path.to_owned())),
+                        metadata_id: Some(proto::MetaId { id: 1 }),
+                    }),
+                    unit: None,
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let update = proto::instrument::Update {
+            now: Some(created_at),
+            new_metadata: Some(proto::RegisterMetadata {
+                metadata: vec![proto::register_metadata::NewMetadata {
+                    id: Some(proto::MetaId { id: 1 }),
+                    metadata: Some(proto::Metadata {
+                        name: "runtime.resource.async_op".to_owned(),
+                        target: "console_subscriber::fs".to_owned(),
+                        ..Default::default()
+                    }),
+                }],
+            }),
+            async_op_update: Some(proto::async_ops::AsyncOpUpdate {
+                new_async_ops: vec![
+                    async_op(3, "fs::read_to_string"),
+                    async_op(5, "Semaphore::acquire"),
+                ],
+                stats_update: [(3, stats(Some("config.toml"))), (5, stats(None))]
+                    .into_iter()
+                    .collect(),
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+
+        let mut state = State::default();
+        state.update(&styles, &view::ViewState::TasksList, update);
+        let task_id = state
+            .async_ops_state()
+            .async_ops()
+            .find_map(|async_op| async_op.upgrade()?.borrow().task_id())
+            .expect("the async ops should have a task");
+        let fs_ops = state.async_ops_state().fs_ops_issued_by(task_id);
+        assert_eq!(fs_ops.len(), 1, "only the fs op is indexed");
+        let fs_op = fs_ops[0]
+            .upgrade()
+            .expect("the fs op should be in the state");
+        let fs_op = fs_op.borrow();
+        assert_eq!(fs_op.source(), "fs::read_to_string");
+        assert_eq!(fs_op.path(), Some("config.toml"));
+    }
+
+    #[test]
+    fn retention_cycles_from_configured() {
+        let mut state = State::default().with_retain_for(Some(Duration::from_secs(6)));
+        assert_eq!(state.retained_for(), Some(Duration::from_secs(6)));
+        state.cycle_retention();
+        assert_eq!(state.retention(), Retention::Forever);
+        assert_eq!(state.retained_for(), None);
+        state.cycle_retention();
+        assert_eq!(state.retention(), Retention::Immediately);
+        assert_eq!(state.retained_for(), Some(Duration::ZERO));
+        state.cycle_retention();
+        assert_eq!(state.retention(), Retention::Configured);
+    }
+
     // This test should be run on all platforms. The console can display instrumentation data
     // from different console subscribers that may be running on different operating systems.
     // For instance, the console could be running on Windows, while the application is running on Linux.
@@ -1,13 +1,15 @@
+use crate::editor::SourceLocation;
 use crate::intern::{self, InternedStr};
 use crate::state::{
     format_location,
     store::{self, Id, SpanId, Store},
-    Attribute, Field, Metadata, Visibility,
+    Attribute, Field, FieldValue, Metadata, Visibility,
 };
 use crate::view;
 use console_api as proto;
 use ratatui::{style::Color, text::Span};
 use std::{
+    borrow::Cow,
     collections::HashMap,
     convert::{TryFrom, TryInto},
     rc::Rc,
@@ -26,9 +28,10 @@ pub(crate) enum TypeVisibility {
     Internal,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 #[repr(usize)]
 pub(crate) enum SortBy {
+    #[default]
     Id = 0,
     ParentId = 1,
     Kind = 2,
@@ -37,7 +40,20 @@ pub(crate) enum SortBy {
     ConcreteType = 5,
     Visibility = 6,
     Location = 7,
-    Attributes = 8,
+    Created = 8,
+    Attributes = 9,
+}
+
+/// A resource's value for one of the keys it can be sorted by.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SortKey {
+    Id(Id<Resource>),
+    Text(InternedStr),
+    Duration(Duration),
+    Visibility(TypeVisibility),
+    Location(String),
+    Time(SystemTime),
+    Attribute(Option<Cow<'static, str>>),
 }
 
 #[derive(Debug)]
@@ -54,12 +70,15 @@ pub(crate) struct Resource {
     id_str: InternedStr,
     parent: InternedStr,
     parent_id: InternedStr,
+    /// The sequential ID of this resource's parent resource, if it has one.
+    parent_resource_id: Option<Id<Resource>>,
     meta_id: u64,
     kind: InternedStr,
     stats: ResourceStats,
     target: InternedStr,
     concrete_type: InternedStr,
     location: String,
+    source_location: Option<SourceLocation>,
     visibility: TypeVisibility,
 }
 
@@ -70,53 +89,34 @@ struct ResourceStats {
     created_at: SystemTime,
     dropped_at: Option<SystemTime>,
     total: Option<Duration>,
+    attributes: Vec<Attribute>,
     formatted_attributes: Vec<Vec<Span<'static>>>,
 }
 
-impl Default for SortBy {
-    fn default() -> Self {
-        Self::Id
-    }
-}
-
 impl SortBy {
-    pub fn sort(&self, now: SystemTime, resources: &mut [ResourceRef]) {
+    /// Returns `resource`'s value for this key as of `now`.
+    pub fn key(&self, now: SystemTime, resource: &Resource) -> SortKey {
         match self {
-            Self::Id => {
-                resources.sort_unstable_by_key(|resource| resource.upgrade().map(|r| r.borrow().id))
-            }
-            Self::ParentId => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().map(|r| r.borrow().parent_id.clone())
-            }),
-            Self::Kind => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().map(|r| r.borrow().kind.clone())
-            }),
-            Self::Total => resources
-                .sort_unstable_by_key(|resource| resource.upgrade().map(|r| r.borrow().total(now))),
-            Self::Target => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().map(|r| r.borrow().target.clone())
-            }),
-            Self::ConcreteType => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().map(|r| r.borrow().concrete_type.clone())
-            }),
-            Self::Visibility => resources
-                .sort_unstable_by_key(|resource| resource.upgrade().map(|r| r.borrow().visibility)),
-            Self::Location => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().map(|r| r.borrow().location.clone())
-            }),
-            Self::Attributes => resources.sort_unstable_by_key(|resource| {
-                resource.upgrade().and_then(|r| {
-                    // FIXME - we are taking only the key of the first attribute as sorting key here.
-                    // Instead, attributes should probably be parsed and sorted according to their actual values.
-                    //
-                    // See https://github.com/tokio-rs/console/issues/496
-                    r.borrow()
-                        .formatted_attributes()
-                        .first()
-                        .and_then(|a| a.first())
-                        .map(|key| key.content.clone())
-                })
-            }),
+            Self::Id => SortKey::Id(resource.id),
+            Self::ParentId => SortKey::Text(resource.parent_id.clone()),
+            Self::Kind => SortKey::Text(resource.kind.clone()),
+            Self::Total => SortKey::Duration(resource.total(now)),
+            Self::Target => SortKey::Text(resource.target.clone()),
+            Self::ConcreteType => SortKey::Text(resource.concrete_type.clone()),
+            Self::Visibility => SortKey::Visibility(resource.visibility),
+            Self::Location => SortKey::Location(resource.location.clone()),
+            Self::Created => SortKey::Time(resource.created_at()),
+            // FIXME - we are taking only the key of the first attribute as sorting key here.
+            // Instead, attributes should probably be parsed and sorted according to their actual values.
+            //
+            // See https://github.com/tokio-rs/console/issues/496
+            Self::Attributes => SortKey::Attribute(
+                resource
+                    .formatted_attributes()
+                    .first()
+                    .and_then(|a| a.first())
+                    .map(|key| key.content.clone()),
+            ),
         }
     }
 }
@@ -133,6 +133,7 @@ impl TryFrom<usize> for SortBy {
             idx if idx == Self::ConcreteType as usize => Ok(Self::ConcreteType),
             idx if idx == Self::Visibility as usize => Ok(Self::Visibility),
             idx if idx == Self::Location as usize => Ok(Self::Location),
+            idx if idx == Self::Created as usize => Ok(Self::Created),
             idx if idx == Self::Attributes as usize => Ok(Self::Attributes),
             _ => Err(()),
         }
@@ -140,6 +141,8 @@ impl TryFrom<usize> for SortBy {
 }
 
 impl view::SortBy for SortBy {
+    type Key = SortKey;
+
     fn as_column(&self) -> usize {
         *self as usize
     }
@@ -213,9 +216,9 @@ impl ResourcesState {
                 );
 
                 let id = ids.id_for(span_id);
-                let parent_id = resource.parent_resource_id.map(|id| ids.id_for(id.id));
+                let parent_resource_id = resource.parent_resource_id.map(|id| ids.id_for(id.id));
 
-                let parent = strings.string(match parent_id {
+                let parent = strings.string(match parent_resource_id {
                     Some(id) => parents
                         .get(&id)
                         .and_then(|r| r.upgrade())
@@ -228,12 +231,16 @@ impl ResourcesState {
                 });
 
                 let parent_id = strings.string(
-                    parent_id
+                    parent_resource_id
                         .as_ref()
                         .map(Id::<Resource>::to_string)
                         .unwrap_or_else(|| "n/a".to_string()),
                 );
 
+                let source_location = resource
+                    .location
+                    .as_ref()
+                    .and_then(SourceLocation::from_proto);
                 let location = format_location(resource.location);
                 let visibility = if resource.is_internal {
                     TypeVisibility::Internal
@@ -247,12 +254,14 @@ impl ResourcesState {
                     id_str: strings.string(id.to_string()),
                     parent,
                     parent_id,
+                    parent_resource_id,
                     kind,
                     stats,
                     target: meta.target.clone(),
                     concrete_type: strings.string(resource.concrete_type),
                     meta_id,
                     location,
+                    source_location,
                     visibility,
                 };
                 Some((id, resource))
@@ -286,6 +295,16 @@ impl ResourcesState {
     pub(crate) fn dropped_events(&self) -> u64 {
         self.dropped_events
     }
+
+    /// Returns the resource with the given ID, if it exists.
+    pub(crate) fn resource(&self, id: Id<Resource>) -> Option<ResourceRef> {
+        self.resources.get(id).map(Rc::downgrade)
+    }
+
+    /// Returns all resources.
+    pub(crate) fn resources(&self) -> impl Iterator<Item = ResourceRef> + '_ {
+        self.resources.values().map(Rc::downgrade)
+    }
 }
 
 impl Resource {
@@ -309,6 +328,10 @@ impl Resource {
         &self.parent_id
     }
 
+    pub(crate) fn parent_resource_id(&self) -> Option<Id<Resource>> {
+        self.parent_resource_id
+    }
+
     pub(crate) fn type_visibility(&self) -> TypeVisibility {
         self.visibility
     }
@@ -329,6 +352,20 @@ impl Resource {
         &self.stats.formatted_attributes
     }
 
+    /// Returns the current value of the attribute named `name`, if the
+    /// resource has that attribute.
+    pub(crate) fn attribute(&self, name: &str) -> Option<&FieldValue> {
+        self.stats
+            .attributes
+            .iter()
+            .find(|attr| &*attr.field.name == name)
+            .map(|attr| &attr.field.value)
+    }
+
+    pub(crate) fn created_at(&self) -> SystemTime {
+        self.stats.created_at
+    }
+
     pub(crate) fn total(&self, since: SystemTime) -> Duration {
         self.stats.total.unwrap_or_else(|| {
             since
@@ -344,6 +381,10 @@ impl Resource {
     pub(crate) fn location(&self) -> &str {
         &self.location
     }
+
+    pub(crate) fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
 }
 
 impl ResourceStats {
@@ -380,6 +421,7 @@ impl ResourceStats {
             created_at,
             dropped_at,
             total,
+            attributes,
             formatted_attributes,
         }
     }
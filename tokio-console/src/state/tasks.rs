@@ -1,9 +1,13 @@
 use crate::{
+    editor::SourceLocation,
     intern::{self, InternedStr},
     state::{
+        async_ops::{AsyncOp, AsyncOpsState},
+        expr::Expr,
         format_location,
         histogram::DurationHistogram,
         pb_duration,
+        resources::{Resource, ResourcesState},
         store::{self, Id, SpanId, Store},
         Field, FieldValue, Metadata, Visibility,
     },
@@ -14,10 +18,12 @@ use crate::{
 use console_api as proto;
 use ratatui::{style::Color, text::Span};
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
+    borrow::Cow,
+    cmp::{self, Ordering},
+    collections::{HashMap, HashSet, VecDeque},
     convert::{TryFrom, TryInto},
-    rc::{Rc, Weak},
+    fmt,
+    rc::Rc,
     time::{Duration, SystemTime},
 };
 
@@ -26,6 +32,17 @@ pub(crate) struct TasksState {
     tasks: Store<Task>,
     pending_lint: HashSet<Id<Task>>,
     pub(crate) linters: Vec<Linter<Task>>,
+    /// Optional columns which are displayed in the task list.
+    pub(crate) columns: Vec<Column>,
+    /// The notes of bookmarked tasks, by the tasks' identities, including
+    /// bookmarks from previous sessions.
+    ///
+    /// New tasks with a bookmarked identity are bookmarked when they are
+    /// added.
+    saved_bookmarks: HashMap<TaskKey, Option<String>>,
+    /// The lifetimes of tasks which have been removed, oldest first, so that
+    /// the timeline can show tasks from the whole session.
+    removed_lifetimes: VecDeque<Lifetime>,
     dropped_events: u64,
 }
 
@@ -34,26 +51,135 @@ pub(crate) struct Details {
     pub(crate) span_id: SpanId,
     pub(crate) poll_times_histogram: Option<DurationHistogram>,
     pub(crate) scheduled_times_histogram: Option<DurationHistogram>,
+    /// The task's most recent polls, oldest first.
+    pub(crate) recent_polls: Vec<Poll>,
+    /// What the task did between each of its details updates.
+    pub(crate) activity: Activity,
+}
+
+/// How long a task was busy and how many times it was woken in each interval
+/// between its details updates, so that bursts and periodic work stand out.
+#[derive(Debug, Default)]
+pub(crate) struct Activity {
+    /// The task's busy time and wakes at the last details update.
+    last: Option<(Duration, u64)>,
+    /// The most recent intervals, oldest first.
+    intervals: VecDeque<Interval>,
+}
+
+/// What a task did in one interval between its details updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Interval {
+    pub(crate) busy: Duration,
+    pub(crate) wakes: u64,
+}
+
+/// A one-off dump of everything the target knew about a task when it was
+/// asked, for inspecting a task which seems to be stuck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Dump {
+    pub(crate) span_id: SpanId,
+    /// When the dump was taken, by the target's clock.
+    pub(crate) taken_at: Option<SystemTime>,
+    pub(crate) location: String,
+    /// The names and full values of all of the task's fields.
+    pub(crate) fields: Vec<(String, String)>,
+    /// Where the task's future was suspended, if the target could tell.
+    pub(crate) backtrace: Option<String>,
+}
+
+/// When a task was spawned, and when it completed, if it has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Lifetime {
+    pub(crate) id: Id<Task>,
+    pub(crate) location: String,
+    pub(crate) created_at: SystemTime,
+    pub(crate) dropped_at: Option<SystemTime>,
 }
 
-#[derive(Debug, Copy, Clone)]
-#[repr(usize)]
+/// One of a task's recent polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Poll {
+    pub(crate) started: SystemTime,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
 pub(crate) enum SortBy {
-    Warns = 0,
-    Tid = 1,
-    State = 2,
-    Name = 3,
-    Total = 4,
-    Busy = 5,
-    Scheduled = 6,
-    Idle = 7,
-    Polls = 8,
-    Target = 9,
-    Location = 10,
+    Bookmark,
+    Warns,
+    Tid,
+    State,
+    Name,
+    #[default]
+    Total,
+    Busy,
+    Scheduled,
+    Idle,
+    Polls,
+    Target,
+    Location,
+    /// Sort by the value in the given column of [`TasksState::columns`].
+    Column(usize),
+}
+
+/// A task's value for one of the keys it can be sorted by.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SortKey {
+    Flag(bool),
+    TaskId(Option<TaskId>),
+    Name(Option<InternedStr>),
+    State(TaskState),
+    Count(u64),
+    Duration(Duration),
+    Text(InternedStr),
+    Location(String),
+    Column(Option<ColumnValue<'static>>),
+}
+
+/// A column which is only displayed in the task list when it is configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Column {
+    /// The total number of times the task has been woken.
+    Wakes,
+    /// The number of wakers currently referencing the task.
+    Wakers,
+    /// The task's 99th percentile poll time.
+    P99Poll,
+    /// The task's longest poll time.
+    MaxPoll,
+    /// How many times per second the task has been polled recently.
+    PollsPerSec,
+    /// How many times per second the task has been woken recently.
+    WakesPerSec,
+    /// The smoothed percentage of time the task has recently been busy.
+    BusyPercent,
+    /// The smoothed percentage of time the task has recently spent scheduled
+    /// but not yet polled.
+    ScheduledPercent,
+    /// The async op which the task is waiting on.
+    Awaiting,
+    /// The value of the span field with the given name.
+    Field(String),
+    /// The value of an expression over the task's statistics.
+    Expr(Expr),
+}
+
+/// The value of a task in one of its [`Column`]s.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ColumnValue<'a> {
+    Field(Cow<'a, FieldValue>),
+    Duration(Duration),
+    /// The (finite) result of an expression.
+    Number(f64),
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) enum TaskState {
+    /// The task panicked, whether or not it has been dropped yet.
+    Panicked,
+    /// The task was aborted before it completed.
+    Cancelled,
     Completed,
     Idle,
     Running,
@@ -92,8 +218,24 @@ pub(crate) struct Task {
     short_desc: InternedStr,
     /// Fields that don't have their own column, pre-formatted
     formatted_fields: Vec<Vec<Span<'static>>>,
+    /// The values of the fields displayed in their own columns, in the order
+    /// of [`TasksState::columns`].
+    column_fields: Vec<Option<FieldValue>>,
+    /// Fields that have their own column in the task list, pre-formatted
+    formatted_column_fields: Vec<Vec<Span<'static>>>,
     /// The task statistics that are updated over the lifetime of the task
     stats: TaskStats,
+    /// Recent samples of the task's stats, from which its rates and recent
+    /// stats are computed.
+    history: StatsHistory,
+    /// The task's stats when the user last reset the displayed stats, which
+    /// are then displayed relative to them.
+    stats_baseline: Option<StatsSample>,
+    /// The smoothed fraction of time the task has recently been busy.
+    busy_average: TimeAverage,
+    /// The smoothed fraction of time the task has recently been scheduled
+    /// but not yet polled.
+    scheduled_average: TimeAverage,
     /// The target of the span representing the task
     target: InternedStr,
     /// The name of the task (when `tokio::task::Builder` is used)
@@ -102,12 +244,151 @@ pub(crate) struct Task {
     warnings: Vec<Linter<Task>>,
     /// The source file and line number the task was spawned from
     location: String,
+    /// Where the task was spawned from, to open in an editor, if the file is
+    /// known.
+    source_location: Option<SourceLocation>,
     /// The kind of task, currently one of task, blocking, block_on, local
     kind: InternedStr,
     /// The size of the future driving the task
     size_bytes: Option<usize>,
     /// The original size of the future (before runtime auto-boxing)
     original_size_bytes: Option<usize>,
+    /// If this is a blocking task running a `tokio::fs` operation, the name
+    /// of that operation (such as `fs::read`).
+    ///
+    /// Tokio runs filesystem operations on the blocking pool, so they show up
+    /// as blocking tasks. Tokio's instrumentation only records the closure's
+    /// type name, so the operation's path is only known for operations made
+    /// through `console_subscriber::fs`, which are recorded as async ops.
+    fs_op: Option<InternedStr>,
+    /// The span ID of the task that was running when this task was spawned,
+    /// if any.
+    parent_span_id: Option<SpanId>,
+    /// The user's bookmark on this task, if it is bookmarked.
+    bookmark: Option<Bookmark>,
+    /// The async op which the task's most recent poll returned `Pending` on,
+    /// if it's known.
+    awaiting: Option<Awaiting>,
+    /// Identifies this task across connections to the same target.
+    key: TaskKey,
+}
+
+/// An async op which a task is waiting on.
+#[derive(Debug)]
+struct Awaiting {
+    description: String,
+    /// The resource which the async op is on.
+    resource_id: Id<Resource>,
+    /// When the task started waiting, which is when the async op's last poll
+    /// ended.
+    since: SystemTime,
+}
+
+/// A task's identity, which is stable across connections to the target.
+///
+/// Unlike IDs, which are assigned as tasks are spawned, this identifies a task
+/// by where it was spawned and how it was described, so that bookmarks can be
+/// restored after reconnecting. Tasks spawned from the same location with the
+/// same name and fields have the same identity.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct TaskKey {
+    pub(crate) location: String,
+    pub(crate) name: Option<String>,
+    /// A hash of the names and values of the task's fields.
+    pub(crate) fields_hash: u64,
+}
+
+/// A task bookmarked by the user.
+///
+/// Bookmarked tasks are not removed from the console's state when they
+/// complete, for as long as they remain bookmarked.
+#[derive(Debug, Default)]
+pub(crate) struct Bookmark {
+    /// A short note attached to the bookmark, if one has been written.
+    note: Option<String>,
+}
+
+/// How a task panicked, as reported by the target.
+///
+/// Tasks which panicked are not removed from the console's state when they
+/// complete, so that they stay in the list of crashed tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Panic {
+    /// The panic's message, if its payload was a string.
+    pub(crate) message: Option<String>,
+    /// Where the task panicked.
+    pub(crate) location: String,
+}
+
+/// Who aborted a task, as reported by the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Abort {
+    /// The span ID of the task which aborted this task, if it was aborted
+    /// from inside a task.
+    pub(crate) aborted_by: Option<SpanId>,
+    /// Where the task was aborted.
+    pub(crate) location: String,
+}
+
+/// Which period of a task's life its displayed stats cover.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum StatsWindow {
+    /// Everything since the task was spawned.
+    #[default]
+    Lifetime,
+    /// The last [`StatsWindow::RECENT`].
+    Recent,
+}
+
+/// A task's stats over a [`StatsWindow`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WindowedStats {
+    pub(crate) total: Duration,
+    pub(crate) busy: Duration,
+    pub(crate) scheduled: Duration,
+    pub(crate) idle: Duration,
+    pub(crate) polls: u64,
+    pub(crate) wakes: u64,
+}
+
+/// How long a task has spent in each of its states over a [`StatsWindow`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct StateTimes {
+    pub(crate) running: Duration,
+    pub(crate) scheduled: Duration,
+    pub(crate) idle: Duration,
+    /// How long the task has been completed for, while it's retained.
+    pub(crate) completed: Duration,
+}
+
+/// Samples of a task's stats over roughly the last
+/// [`StatsHistory::MAX_WINDOW`], so that its current rates and recent stats
+/// aren't dominated by how long the task has existed.
+#[derive(Debug, Default)]
+struct StatsHistory {
+    samples: VecDeque<StatsSample>,
+}
+
+#[derive(Debug)]
+struct StatsSample {
+    at: SystemTime,
+    polls: u64,
+    wakes: u64,
+    busy: Duration,
+    scheduled: Duration,
+}
+
+/// An exponentially weighted moving average of the fraction of time a task
+/// has been busy, or scheduled, which changes smoothly enough to sort by
+/// without momentary spikes reordering the task list.
+#[derive(Debug)]
+struct TimeAverage {
+    /// When the average was last updated.
+    at: SystemTime,
+    /// The task's time in the state when the average was last updated.
+    spent: Duration,
+    /// The average fraction of time the task was busy, as of `at`.
+    average: f64,
 }
 
 #[derive(Debug)]
@@ -133,8 +414,28 @@ struct TaskStats {
 
     /// The timestamp of when the task was last woken.
     last_wake: Option<SystemTime>,
+    /// The task's 99th percentile poll time, if the target reported it.
+    poll_p99: Option<Duration>,
+    /// The task's longest poll time, if the target reported it.
+    poll_max: Option<Duration>,
     /// Total number of times the task has woken itself.
     self_wakes: u64,
+    /// How the task panicked, if it has.
+    panic: Option<Panic>,
+    /// Who aborted the task, if the target recorded it.
+    abort: Option<Abort>,
+    /// Why the task ended, if it has been dropped and the target said why.
+    end_reason: Option<proto::tasks::EndReason>,
+}
+
+/// How many removed tasks' lifetimes are remembered for the timeline.
+pub(crate) const MAX_REMOVED_LIFETIMES: usize = 10_000;
+
+fn remember_lifetime(lifetimes: &mut VecDeque<Lifetime>, task: &Task) {
+    if lifetimes.len() == MAX_REMOVED_LIFETIMES {
+        lifetimes.pop_front();
+    }
+    lifetimes.push_back(task.lifetime());
 }
 
 impl TasksState {
@@ -154,9 +455,15 @@ impl TasksState {
         metas: &HashMap<u64, Metadata>,
         update: proto::tasks::TaskUpdate,
         visibility: Visibility,
+        now: Option<SystemTime>,
     ) {
         let mut stats_update = update.stats_update;
         let linters = &self.linters;
+        let columns = &self.columns;
+        let saved_bookmarks = &self.saved_bookmarks;
+        // Tasks' timestamps are by the target's clock, so they're linted as of
+        // the update, unless the target didn't say when it was sent.
+        let lint_at = now.unwrap_or_else(SystemTime::now);
 
         // Gathers the tasks that need to be linted again on the next update cycle
         let mut next_pending_lint = HashSet::new();
@@ -190,6 +497,8 @@ impl TasksState {
                 let mut kind = strings.string(String::new());
                 let mut size_bytes = None;
                 let mut original_size_bytes = None;
+                let mut fs_op = None;
+                let mut column_field_list = Vec::new();
                 let target_field = Field::new(
                     strings.string_ref("target"),
                     FieldValue::Str(meta.target.to_string()),
@@ -234,6 +543,21 @@ impl TasksState {
                                 // Include size in pre-formatted fields
                                 Some(field)
                             }
+                            Field::FN => {
+                                fs_op = match field.value {
+                                    FieldValue::Str(ref f) | FieldValue::Debug(ref f) => {
+                                        fs_op_name(f).map(|op| strings.string(op))
+                                    }
+                                    _ => None,
+                                };
+                                Some(field)
+                            }
+                            // fields configured as columns get their own columns
+                            // in the task list.
+                            name if columns.iter().any(|column| column.is_field(name)) => {
+                                column_field_list.push(field);
+                                None
+                            }
                             _ => Some(field),
                         }
                     })
@@ -241,10 +565,26 @@ impl TasksState {
                     .chain([target_field])
                     .collect::<Vec<_>>();
 
+                let fields_hash =
+                    TaskKey::hash_fields(fields.iter().chain(column_field_list.iter()));
                 let formatted_fields = Field::make_formatted(styles, &mut fields);
+                let formatted_column_fields = Field::make_formatted(styles, &mut column_field_list);
+                let mut column_fields = columns.iter().map(|_| None).collect::<Vec<_>>();
+                for field in column_field_list {
+                    if let Some(column) = columns.iter().position(|c| c.is_field(&field.name)) {
+                        column_fields[column] = Some(field.value);
+                    }
+                }
 
-                let stats = stats_update.remove(&span_id)?.into();
+                let stats: TaskStats = stats_update.remove(&span_id)?.into();
+                // Nothing has happened to the task before it was created.
+                let mut history = StatsHistory::default();
+                history.record(StatsSample::empty(stats.created_at));
+                let busy_average = TimeAverage::new(stats.created_at);
+                let scheduled_average = TimeAverage::new(stats.created_at);
+                let source_location = task.location.as_ref().and_then(SourceLocation::from_proto);
                 let location = format_location(task.location);
+                let parent_span_id = task.parents.first().map(|parent| parent.id);
 
                 // remap the server's ID to a pretty, sequential task ID
                 let id = ids.id_for(span_id);
@@ -256,6 +596,15 @@ impl TasksState {
                     (None, None) => "".to_owned(),
                 });
 
+                let key = TaskKey {
+                    location: location.clone(),
+                    name: name.as_ref().map(|name| name.to_string()),
+                    fields_hash,
+                };
+                let bookmark = saved_bookmarks
+                    .get(&key)
+                    .map(|note| Bookmark { note: note.clone() });
+
                 let mut task = Task {
                     name,
                     id,
@@ -264,15 +613,30 @@ impl TasksState {
                     id_str: task_id.map(|id| id.to_string()).unwrap_or_default(),
                     short_desc,
                     formatted_fields,
+                    column_fields,
+                    formatted_column_fields,
                     stats,
+                    history,
+                    stats_baseline: None,
+                    busy_average,
+                    scheduled_average,
                     target: meta.target.clone(),
                     warnings: Vec::new(),
                     location,
+                    source_location,
                     kind,
                     size_bytes,
                     original_size_bytes,
+                    fs_op,
+                    parent_span_id,
+                    bookmark,
+                    awaiting: None,
+                    key,
                 };
-                if let TaskLintResult::RequiresRecheck = task.lint(linters) {
+                if let Some(now) = now {
+                    task.record_sample(now);
+                }
+                if let TaskLintResult::RequiresRecheck = task.lint(linters, lint_at) {
                     next_pending_lint.insert(task.id);
                 }
                 Some((id, task))
@@ -281,7 +645,10 @@ impl TasksState {
         for (stats, mut task) in self.tasks.updated(stats_update) {
             tracing::trace!(?task, ?stats, "processing stats update for");
             task.stats = stats.into();
-            match task.lint(linters) {
+            if let Some(now) = now {
+                task.record_sample(now);
+            }
+            match task.lint(linters, lint_at) {
                 TaskLintResult::RequiresRecheck => next_pending_lint.insert(task.id),
                 // Avoid linting this task again this cycle
                 _ => self.pending_lint.remove(&task.id),
@@ -290,7 +657,7 @@ impl TasksState {
 
         for id in &self.pending_lint {
             if let Some(task) = self.tasks.get(*id) {
-                if let TaskLintResult::RequiresRecheck = task.borrow_mut().lint(linters) {
+                if let TaskLintResult::RequiresRecheck = task.borrow_mut().lint(linters, lint_at) {
                     next_pending_lint.insert(*id);
                 }
             }
@@ -303,28 +670,210 @@ impl TasksState {
     pub(crate) fn retain_active(&mut self, now: SystemTime, retain_for: Duration) {
         self.tasks.retain(|_, task| {
             let task = task.borrow();
+            if task.is_bookmarked() || task.panic().is_some() {
+                return true;
+            }
 
-            task.stats
+            let retain = task
+                .stats
                 .dropped_at
                 .map(|d| {
                     let dropped_for = now.duration_since(d).unwrap_or_default();
                     retain_for > dropped_for
                 })
-                .unwrap_or(true)
+                .unwrap_or(true);
+            if !retain {
+                remember_lifetime(&mut self.removed_lifetimes, &task);
+            }
+            retain
         })
     }
 
+    /// Works out which async op each task is waiting on.
+    ///
+    /// An async op is only polled from within a poll of its task, so the async
+    /// op whose poll ended last during the task's most recent poll is the one
+    /// which returned `Pending` and is being waited on.
+    pub(crate) fn update_awaiting(&self, async_ops: &AsyncOpsState, resources: &ResourcesState) {
+        let mut awaiting = HashMap::new();
+        for async_op in async_ops.async_ops() {
+            let Some(async_op) = async_op.upgrade() else {
+                continue;
+            };
+            let async_op = async_op.borrow();
+            let (Some(task_id), Some(ended), false) = (
+                async_op.task_id(),
+                async_op.last_poll_ended(),
+                async_op.dropped(),
+            ) else {
+                continue;
+            };
+            awaiting
+                .entry(task_id)
+                .and_modify(|latest: &mut Awaiting| {
+                    if ended > latest.since {
+                        *latest = Awaiting::new(&async_op, ended, resources);
+                    }
+                })
+                .or_insert_with(|| Awaiting::new(&async_op, ended, resources));
+        }
+
+        for task in self.tasks.values() {
+            let mut task = task.borrow_mut();
+            let state = task.state();
+            task.awaiting = awaiting.remove(&task.id).filter(|awaiting| {
+                matches!(state, TaskState::Idle | TaskState::Scheduled)
+                    && task.stats.last_poll_started <= Some(awaiting.since)
+            });
+        }
+    }
+
+    /// Drops every completed task which isn't bookmarked and didn't panic.
+    pub(crate) fn clear_completed(&mut self) {
+        self.tasks.retain(|_, task| {
+            let task = task.borrow();
+            let retain = task.is_bookmarked() || task.panic().is_some() || !task.is_completed();
+            if !retain {
+                remember_lifetime(&mut self.removed_lifetimes, &task);
+            }
+            retain
+        })
+    }
+
+    /// Returns the lifetimes of every task seen this session, including tasks
+    /// which have since been removed, ordered by when they were spawned.
+    ///
+    /// Only the most recent [`MAX_REMOVED_LIFETIMES`] removed tasks are
+    /// remembered.
+    pub(crate) fn lifetimes(&self) -> Vec<Lifetime> {
+        let mut lifetimes = self
+            .removed_lifetimes
+            .iter()
+            .cloned()
+            .chain(self.tasks.values().map(|task| task.borrow().lifetime()))
+            .collect::<Vec<_>>();
+        lifetimes.sort_by_key(|lifetime| (lifetime.created_at, lifetime.id));
+        lifetimes
+    }
+
     pub(crate) fn warnings(&self) -> impl Iterator<Item = &Linter<Task>> {
         self.linters.iter().filter(|linter| linter.count() > 0)
     }
 
+    /// Returns all tasks.
+    pub(crate) fn tasks(&self) -> impl Iterator<Item = TaskRef> + '_ {
+        self.tasks.values().map(Rc::downgrade)
+    }
+
     pub(crate) fn task(&self, id: Id<Task>) -> Option<TaskRef> {
         self.tasks.get(id).map(Rc::downgrade)
     }
 
+    /// Returns the task with the given span ID, if it hasn't been removed.
+    pub(crate) fn task_by_span(&self, span_id: SpanId) -> Option<TaskRef> {
+        self.tasks.get_by_span(span_id).map(Rc::downgrade)
+    }
+
     pub(crate) fn dropped_events(&self) -> u64 {
         self.dropped_events
     }
+
+    pub(crate) fn saved_bookmarks(&self) -> &HashMap<TaskKey, Option<String>> {
+        &self.saved_bookmarks
+    }
+
+    pub(crate) fn set_saved_bookmarks(&mut self, bookmarks: HashMap<TaskKey, Option<String>>) {
+        self.saved_bookmarks = bookmarks;
+    }
+
+    /// Updates the saved bookmarks to match the bookmarks on current tasks,
+    /// returning `true` if they changed.
+    ///
+    /// Bookmarks on identities with no current tasks are kept, so that
+    /// bookmarks from earlier sessions are not lost before their tasks are
+    /// spawned again.
+    pub(crate) fn sync_bookmarks(&mut self) -> bool {
+        let mut current = HashMap::<&TaskKey, Option<Option<&str>>>::new();
+        let tasks = self
+            .tasks
+            .values()
+            .map(|task| task.borrow())
+            .collect::<Vec<_>>();
+        for task in &tasks {
+            let bookmark = current.entry(&task.key).or_default();
+            if bookmark.is_none() && task.is_bookmarked() {
+                *bookmark = Some(task.bookmark_note());
+            }
+        }
+
+        let mut changed = false;
+        for (key, bookmark) in current {
+            let saved = self.saved_bookmarks.get(key).map(Option::as_deref);
+            if saved == bookmark {
+                continue;
+            }
+            changed = true;
+            match bookmark {
+                Some(note) => {
+                    self.saved_bookmarks
+                        .insert(key.clone(), note.map(ToOwned::to_owned));
+                }
+                None => {
+                    self.saved_bookmarks.remove(key);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Resets the displayed stats of every task to zero at `now`.
+    pub(crate) fn reset_stats(&self, now: SystemTime) {
+        for task in self.tasks.values() {
+            task.borrow_mut().reset_stats(now);
+        }
+    }
+
+    /// Returns all bookmarked tasks, ordered by ID.
+    pub(crate) fn bookmarked(&self) -> Vec<TaskRef> {
+        let mut tasks = self
+            .tasks
+            .values()
+            .filter(|task| task.borrow().is_bookmarked())
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|task| task.borrow().id);
+        tasks.into_iter().map(Rc::downgrade).collect()
+    }
+
+    /// Returns all tasks which panicked, ordered by ID.
+    pub(crate) fn crashed(&self) -> Vec<TaskRef> {
+        let mut tasks = self
+            .tasks
+            .values()
+            .filter(|task| task.borrow().panic().is_some())
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|task| task.borrow().id);
+        tasks.into_iter().map(Rc::downgrade).collect()
+    }
+
+    /// Returns the tasks which are waiting on an async op on `resource` at
+    /// `now`, and how long they've been waiting, ordered from the longest
+    /// waiting.
+    pub(crate) fn waiting_on(
+        &self,
+        resource: Id<Resource>,
+        now: SystemTime,
+    ) -> Vec<(TaskRef, Duration)> {
+        let mut waiting = self
+            .tasks
+            .values()
+            .filter_map(|task| Some((task, task.borrow().waiting_on(resource, now)?)))
+            .collect::<Vec<_>>();
+        waiting.sort_by_key(|&(_, waited)| cmp::Reverse(waited));
+        waiting
+            .into_iter()
+            .map(|(task, waited)| (Rc::downgrade(task), waited))
+            .collect()
+    }
 }
 
 impl Details {
@@ -339,6 +888,39 @@ impl Details {
     pub(crate) fn scheduled_times_histogram(&self) -> Option<&DurationHistogram> {
         self.scheduled_times_histogram.as_ref()
     }
+
+    pub(crate) fn recent_polls(&self) -> &[Poll] {
+        &self.recent_polls
+    }
+
+    pub(crate) fn activity(&self) -> &Activity {
+        &self.activity
+    }
+}
+
+impl Activity {
+    /// How many intervals are kept, which is more than fit across most
+    /// terminals.
+    const MAX_INTERVALS: usize = 512;
+
+    /// Records the task's total busy time and wakes at a details update,
+    /// adding the interval since the last update.
+    pub(crate) fn record(&mut self, busy: Duration, wakes: u64) {
+        if let Some((last_busy, last_wakes)) = self.last.replace((busy, wakes)) {
+            if self.intervals.len() == Self::MAX_INTERVALS {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(Interval {
+                busy: busy.saturating_sub(last_busy),
+                wakes: wakes.saturating_sub(last_wakes),
+            });
+        }
+    }
+
+    /// Returns the most recent intervals, oldest first.
+    pub(crate) fn intervals(&self) -> &VecDeque<Interval> {
+        &self.intervals
+    }
 }
 
 impl Task {
@@ -346,10 +928,25 @@ impl Task {
         self.id
     }
 
+    pub(crate) fn lifetime(&self) -> Lifetime {
+        Lifetime {
+            id: self.id,
+            location: self.location.clone(),
+            created_at: self.stats.created_at,
+            dropped_at: self.stats.dropped_at,
+        }
+    }
+
     pub(crate) fn span_id(&self) -> SpanId {
         self.span_id
     }
 
+    /// Returns the span ID of the task which was running when this task was
+    /// spawned, if any.
+    pub(crate) fn parent_span_id(&self) -> Option<SpanId> {
+        self.parent_span_id
+    }
+
     pub(crate) fn id_str(&self) -> &str {
         &self.id_str
     }
@@ -374,6 +971,79 @@ impl Task {
         &self.formatted_fields
     }
 
+    /// Returns the value displayed in `column`, which is the `idx`th column in
+    /// [`TasksState::columns`], at `now`.
+    pub(crate) fn column_value(
+        &self,
+        now: SystemTime,
+        idx: usize,
+        column: &Column,
+    ) -> Option<ColumnValue<'_>> {
+        let field = match column {
+            Column::Wakes => Cow::Owned(FieldValue::U64(self.wakes())),
+            Column::Wakers => Cow::Owned(FieldValue::U64(self.waker_count())),
+            Column::P99Poll => return self.stats.poll_p99.map(ColumnValue::Duration),
+            Column::MaxPoll => return self.stats.poll_max.map(ColumnValue::Duration),
+            Column::PollsPerSec => return self.polls_per_sec(now).map(ColumnValue::Number),
+            Column::BusyPercent => return Some(ColumnValue::Number(self.busy_percent(now))),
+            Column::ScheduledPercent => {
+                return Some(ColumnValue::Number(self.scheduled_percent(now)))
+            }
+            Column::WakesPerSec => return self.wakes_per_sec(now).map(ColumnValue::Number),
+            Column::Awaiting => Cow::Owned(FieldValue::Str(self.awaiting()?.to_owned())),
+            Column::Field(_) => Cow::Borrowed(self.column_fields.get(idx)?.as_ref()?),
+            Column::Expr(expr) => return expr.eval(self, now).map(ColumnValue::Number),
+        };
+        Some(ColumnValue::Field(field))
+    }
+
+    pub(crate) fn formatted_column_fields(&self) -> &[Vec<Span<'static>>] {
+        &self.formatted_column_fields
+    }
+
+    pub(crate) fn is_bookmarked(&self) -> bool {
+        self.bookmark.is_some()
+    }
+
+    /// Returns a description of the async op which the task is waiting on, if
+    /// it's known.
+    pub(crate) fn awaiting(&self) -> Option<&str> {
+        Some(&self.awaiting.as_ref()?.description)
+    }
+
+    /// Returns how long the task has been waiting on `resource` at `now`, if
+    /// it's waiting on an async op on that resource.
+    pub(crate) fn waiting_on(&self, resource: Id<Resource>, now: SystemTime) -> Option<Duration> {
+        let awaiting = self
+            .awaiting
+            .as_ref()
+            .filter(|a| a.resource_id == resource)?;
+        Some(now.duration_since(awaiting.since).unwrap_or_default())
+    }
+
+    /// Returns the note attached to this task's bookmark, if it is bookmarked
+    /// and has a note.
+    pub(crate) fn bookmark_note(&self) -> Option<&str> {
+        self.bookmark.as_ref()?.note.as_deref()
+    }
+
+    /// Bookmarks this task if it is not bookmarked, or removes its bookmark
+    /// (and note) if it is.
+    pub(crate) fn toggle_bookmark(&mut self) {
+        self.bookmark = match self.bookmark {
+            Some(_) => None,
+            None => Some(Bookmark::default()),
+        };
+    }
+
+    /// Bookmarks this task with the given note, replacing any existing note.
+    ///
+    /// An empty note removes the note, but leaves the task bookmarked.
+    pub(crate) fn set_bookmark_note(&mut self, note: String) {
+        let note = Some(note).filter(|note| !note.is_empty());
+        self.bookmark.get_or_insert_with(Bookmark::default).note = note;
+    }
+
     /// Returns `true` if this task is currently being polled.
     pub(crate) fn is_running(&self) -> bool {
         self.stats.last_poll_started > self.stats.last_poll_ended
@@ -391,9 +1061,26 @@ impl Task {
         self.stats.total.is_some()
     }
 
+    /// Returns how the task panicked, if it has.
+    pub(crate) fn panic(&self) -> Option<&Panic> {
+        self.stats.panic.as_ref()
+    }
+
+    /// Returns who aborted the task, if the target recorded it.
+    pub(crate) fn abort(&self) -> Option<&Abort> {
+        self.stats.abort.as_ref()
+    }
+
     pub(crate) fn state(&self) -> TaskState {
+        if self.panic().is_some() {
+            return TaskState::Panicked;
+        }
+
         if self.is_completed() {
-            return TaskState::Completed;
+            return match self.stats.end_reason {
+                Some(proto::tasks::EndReason::Cancelled) => TaskState::Cancelled,
+                _ => TaskState::Completed,
+            };
         }
 
         if self.is_running() {
@@ -414,6 +1101,116 @@ impl Task {
             .unwrap_or_default()
     }
 
+    /// Returns the smoothed percentage of time the task has recently been
+    /// busy, at `now`.
+    pub(crate) fn busy_percent(&self, now: SystemTime) -> f64 {
+        self.busy_average.average_at(now, self.busy(now)) * 100.0
+    }
+
+    /// Returns the smoothed percentage of time the task has recently been
+    /// scheduled but not yet polled, at `now`.
+    ///
+    /// A task which is often left waiting to be polled after it's woken is a
+    /// sign that the runtime's workers are starved.
+    pub(crate) fn scheduled_percent(&self, now: SystemTime) -> f64 {
+        self.scheduled_average.average_at(now, self.scheduled(now)) * 100.0
+    }
+
+    /// Records the task's current stats in the samples its rates and
+    /// averages are computed from.
+    fn record_sample(&mut self, now: SystemTime) {
+        self.history.record(self.sample(now));
+        let busy = self.busy(now);
+        self.busy_average.record(now, busy);
+        let scheduled = self.scheduled(now);
+        self.scheduled_average.record(now, scheduled);
+    }
+
+    fn sample(&self, now: SystemTime) -> StatsSample {
+        StatsSample {
+            at: now,
+            polls: self.stats.polls,
+            wakes: self.stats.wakes,
+            busy: self.busy(now),
+            scheduled: self.scheduled(now),
+        }
+    }
+
+    /// Displays the task's stats relative to its stats at `now`, as though
+    /// they were zero then.
+    pub(crate) fn reset_stats(&mut self, now: SystemTime) {
+        self.stats_baseline = Some(self.sample(now));
+    }
+
+    /// Returns the task's stats over `window`, at `now`, since its stats were
+    /// last reset.
+    pub(crate) fn windowed(&self, window: StatsWindow, now: SystemTime) -> WindowedStats {
+        let lifetime = WindowedStats {
+            total: self.total(now),
+            busy: self.busy(now),
+            scheduled: self.scheduled(now),
+            idle: self.idle(now),
+            polls: self.stats.polls,
+            wakes: self.stats.wakes,
+        };
+        let Some(start) = self.window_start(window, now) else {
+            return lifetime;
+        };
+        let end = self.stats.dropped_at.unwrap_or(now);
+        let total = end.duration_since(start.at).unwrap_or_default();
+        let busy = lifetime.busy.saturating_sub(start.busy);
+        let scheduled = lifetime.scheduled.saturating_sub(start.scheduled);
+        WindowedStats {
+            total,
+            busy,
+            scheduled,
+            idle: total.saturating_sub(busy + scheduled),
+            polls: lifetime.polls.saturating_sub(start.polls),
+            wakes: lifetime.wakes.saturating_sub(start.wakes),
+        }
+    }
+
+    /// Returns how long the task has spent in each of its states over
+    /// `window`, at `now`, since its stats were last reset.
+    pub(crate) fn state_times(&self, window: StatsWindow, now: SystemTime) -> StateTimes {
+        let stats = self.windowed(window, now);
+        let mut times = StateTimes {
+            running: stats.busy,
+            scheduled: stats.scheduled,
+            idle: stats.idle,
+            completed: Duration::ZERO,
+        };
+        if let Some(total) = self.stats.total {
+            let completed_at = self.stats.created_at + total;
+            let end = self.stats.dropped_at.unwrap_or(now);
+            let start = self.window_start(window, now);
+            let since = start.map_or(completed_at, |start| start.at.max(completed_at));
+            times.completed = end.duration_since(since).unwrap_or_default();
+            // A window's idle time is whatever wasn't spent busy or
+            // scheduled, including the time since the task completed.
+            if start.is_some() {
+                times.idle = times.idle.saturating_sub(times.completed);
+            }
+        }
+        times
+    }
+
+    /// Returns the sample which the task's stats over `window` start from,
+    /// or `None` if they're its stats over its whole lifetime.
+    fn window_start(&self, window: StatsWindow, now: SystemTime) -> Option<&StatsSample> {
+        let recent = match window {
+            StatsWindow::Lifetime => None,
+            StatsWindow::Recent => self.history.baseline(StatsWindow::RECENT, now),
+        };
+        // Start from whichever of the window and the reset is later.
+        match (recent, self.stats_baseline.as_ref()) {
+            (Some(recent), Some(reset)) if recent.at > reset.at => Some(recent),
+            (_, Some(reset)) => Some(reset),
+            (Some(recent), None) => Some(recent),
+            (None, None) => None,
+        }
+    }
+
     pub(crate) fn busy(&self, since: SystemTime) -> Duration {
         if let Some(started) = self.stats.last_poll_started {
             if self.stats.last_poll_started > self.stats.last_poll_ended {
@@ -425,6 +1222,18 @@ impl Task {
         self.stats.busy
     }
 
+    /// Returns the duration of the task's in-progress poll, if it is being
+    /// polled, or of its most recent poll otherwise.
+    pub(crate) fn last_poll_duration(&self, since: SystemTime) -> Option<Duration> {
+        let started = self.stats.last_poll_started?;
+        let ended = match self.stats.last_poll_ended {
+            Some(ended) if ended >= started => ended,
+            // the task is being polled at the moment
+            _ => since,
+        };
+        Some(ended.duration_since(started).unwrap_or_default())
+    }
+
     pub(crate) fn scheduled(&self, since: SystemTime) -> Duration {
         if let Some(wake) = self.stats.last_wake {
             if self.stats.last_wake > self.stats.last_poll_started {
@@ -447,10 +1256,28 @@ impl Task {
     }
 
     /// Returns the total number of times the task has been polled.
+    /// Returns how many times per second the task has been polled recently,
+    /// or `None` if no time has passed since it was spawned.
+    pub(crate) fn polls_per_sec(&self, now: SystemTime) -> Option<f64> {
+        self.history.rate(now, |sample| sample.polls)
+    }
+
+    /// Returns how many times per second the task has been woken recently,
+    /// or `None` if no time has passed since it was spawned.
+    pub(crate) fn wakes_per_sec(&self, now: SystemTime) -> Option<f64> {
+        self.history.rate(now, |sample| sample.wakes)
+    }
+
     pub(crate) fn total_polls(&self) -> u64 {
         self.stats.polls
     }
 
+    /// Returns the task's 99th percentile poll time, if it has completed a
+    /// poll.
+    pub(crate) fn poll_p99(&self) -> Option<Duration> {
+        self.stats.poll_p99
+    }
+
     /// Returns the elapsed time since the task was last woken, relative to
     /// given `now` timestamp.
     ///
@@ -508,12 +1335,12 @@ impl Task {
         &self.warnings[..]
     }
 
-    fn lint(&mut self, linters: &[Linter<Task>]) -> TaskLintResult {
+    fn lint(&mut self, linters: &[Linter<Task>], now: SystemTime) -> TaskLintResult {
         self.warnings.clear();
         let mut recheck = false;
         for lint in linters {
             tracing::debug!(?lint, task = ?self, "checking...");
-            match lint.check(self) {
+            match lint.check(self, now) {
                 Lint::Warning(warning) => {
                     tracing::info!(?warning, task = ?self, "found a warning!");
                     self.warnings.push(warning);
@@ -533,6 +1360,10 @@ impl Task {
         &self.location
     }
 
+    pub(crate) fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
+
     pub(crate) fn size_bytes(&self) -> Option<usize> {
         self.size_bytes
     }
@@ -540,6 +1371,54 @@ impl Task {
     pub(crate) fn original_size_bytes(&self) -> Option<usize> {
         self.original_size_bytes
     }
+
+    /// Returns the name of the `tokio::fs` operation this task is running,
+    /// if it is a filesystem operation.
+    pub(crate) fn fs_op(&self) -> Option<&str> {
+        self.fs_op.as_ref().map(AsRef::as_ref)
+    }
+}
+
+/// Extracts the name of a `tokio::fs` operation from the type name of the
+/// closure passed to `spawn_blocking`, such as
+/// `tokio::fs::read::read<&str>::{{closure}}`.
+///
+/// Returns `None` if the closure is not part of `tokio::fs`.
+fn fs_op_name(fn_name: &str) -> Option<String> {
+    let path = fn_name.strip_prefix("tokio::fs::")?;
+
+    // Split the path on `::`, ignoring any separators inside generics or
+    // `<impl Trait for Type>` segments.
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' => depth += 1,
+            b'>' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                segments.push(&path[start..i]);
+                i += 2;
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    segments.push(&path[start..]);
+
+    let name = segments
+        .into_iter()
+        .rev()
+        .find(|segment| *segment != "{{closure}}" && !segment.starts_with('<'))?;
+    let name = name.split('<').next().unwrap_or(name);
+    if name.is_empty() {
+        return None;
+    }
+    Some(format!("fs::{name}"))
 }
 
 enum TaskLintResult {
@@ -562,7 +1441,10 @@ impl From<proto::tasks::Stats> for TaskStats {
         let busy = poll_stats.busy_time.map(pb_duration).unwrap_or_default();
         let scheduled = pb.scheduled_time.map(pb_duration).unwrap_or_default();
         let idle = total.map(|total| total.checked_sub(busy + scheduled).unwrap_or_default());
+        let summary = pb.poll_times_summary.unwrap_or_default();
         Self {
+            poll_p99: summary.p99.map(pb_duration),
+            poll_max: summary.max.map(pb_duration),
             total,
             idle,
             scheduled,
@@ -577,57 +1459,75 @@ impl From<proto::tasks::Stats> for TaskStats {
             waker_clones: pb.waker_clones,
             waker_drops: pb.waker_drops,
             self_wakes: pb.self_wakes,
+            panic: pb.panic.map(|panic| Panic {
+                message: panic.message,
+                location: format_location(panic.location),
+            }),
+            abort: pb.abort.map(|abort| Abort {
+                aborted_by: abort.aborted_by.map(|id| id.id),
+                location: format_location(abort.location),
+            }),
+            end_reason: pb
+                .end_reason
+                .and_then(|reason| proto::tasks::EndReason::try_from(reason).ok()),
         }
     }
 }
 
-impl Default for SortBy {
-    fn default() -> Self {
-        Self::Total
-    }
-}
-
 impl SortBy {
-    pub fn sort(&self, now: SystemTime, tasks: &mut [Weak<RefCell<Task>>]) {
+    /// The index of the first column from [`TasksState::columns`].
+    pub(crate) const FIRST_OPTIONAL_COLUMN: usize = 12;
+
+    /// Returns `task`'s value for this key as of `now`.
+    pub fn key(
+        &self,
+        now: SystemTime,
+        window: StatsWindow,
+        columns: &[Column],
+        task: &Task,
+    ) -> SortKey {
         match self {
-            Self::Tid => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().task_id))
-            }
-            Self::Name => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().name.clone()))
-            }
-            Self::State => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().state()))
-            }
-            Self::Warns => tasks
-                .sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().warnings().len())),
-            Self::Total => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().total(now)))
-            }
-            Self::Idle => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().idle(now)))
-            }
-            Self::Scheduled => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().scheduled(now)))
-            }
-            Self::Busy => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().busy(now)))
-            }
-            Self::Polls => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().stats.polls))
-            }
-            Self::Target => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().target.clone()))
-            }
-            Self::Location => tasks
-                .sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().location.clone())),
+            Self::Bookmark => SortKey::Flag(task.is_bookmarked()),
+            Self::Tid => SortKey::TaskId(task.task_id),
+            Self::Name => SortKey::Name(task.name.clone()),
+            Self::State => SortKey::State(task.state()),
+            Self::Warns => SortKey::Count(task.warnings().len() as u64),
+            Self::Total => SortKey::Duration(task.windowed(window, now).total),
+            Self::Idle => SortKey::Duration(task.windowed(window, now).idle),
+            Self::Scheduled => SortKey::Duration(task.windowed(window, now).scheduled),
+            Self::Busy => SortKey::Duration(task.windowed(window, now).busy),
+            Self::Polls => SortKey::Count(task.windowed(window, now).polls),
+            Self::Target => SortKey::Text(task.target.clone()),
+            Self::Location => SortKey::Location(task.location.clone()),
+            // The column after the last optional column holds the rest of
+            // the task's fields, which can't be sorted by.
+            Self::Column(idx) => SortKey::Column(columns.get(*idx).and_then(|column| {
+                task.column_value(now, *idx, column)
+                    .map(ColumnValue::into_owned)
+            })),
         }
     }
 }
 
 impl view::SortBy for SortBy {
+    type Key = SortKey;
+
     fn as_column(&self) -> usize {
-        *self as usize
+        match self {
+            Self::Bookmark => 0,
+            Self::Warns => 1,
+            Self::Tid => 2,
+            Self::State => 3,
+            Self::Name => 4,
+            Self::Total => 5,
+            Self::Busy => 6,
+            Self::Scheduled => 7,
+            Self::Idle => 8,
+            Self::Polls => 9,
+            Self::Target => 10,
+            Self::Location => 11,
+            Self::Column(idx) => Self::FIRST_OPTIONAL_COLUMN + idx,
+        }
     }
 }
 
@@ -635,18 +1535,258 @@ impl TryFrom<usize> for SortBy {
     type Error = ();
     fn try_from(idx: usize) -> Result<Self, Self::Error> {
         match idx {
-            idx if idx == Self::Tid as usize => Ok(Self::Tid),
-            idx if idx == Self::State as usize => Ok(Self::State),
-            idx if idx == Self::Warns as usize => Ok(Self::Warns),
-            idx if idx == Self::Name as usize => Ok(Self::Name),
-            idx if idx == Self::Total as usize => Ok(Self::Total),
-            idx if idx == Self::Busy as usize => Ok(Self::Busy),
-            idx if idx == Self::Scheduled as usize => Ok(Self::Scheduled),
-            idx if idx == Self::Idle as usize => Ok(Self::Idle),
-            idx if idx == Self::Polls as usize => Ok(Self::Polls),
-            idx if idx == Self::Target as usize => Ok(Self::Target),
-            idx if idx == Self::Location as usize => Ok(Self::Location),
-            _ => Err(()),
+            0 => Ok(Self::Bookmark),
+            1 => Ok(Self::Warns),
+            2 => Ok(Self::Tid),
+            3 => Ok(Self::State),
+            4 => Ok(Self::Name),
+            5 => Ok(Self::Total),
+            6 => Ok(Self::Busy),
+            7 => Ok(Self::Scheduled),
+            8 => Ok(Self::Idle),
+            9 => Ok(Self::Polls),
+            10 => Ok(Self::Target),
+            11 => Ok(Self::Location),
+            idx => Ok(Self::Column(idx - Self::FIRST_OPTIONAL_COLUMN)),
+        }
+    }
+}
+
+impl TaskKey {
+    /// Hashes the names and values of `fields`, regardless of their order.
+    ///
+    /// This uses FNV-1a rather than the standard library's hasher so that the
+    /// hash is stable across builds of the console.
+    fn hash_fields<'a>(fields: impl Iterator<Item = &'a Field>) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        let mut fields = fields
+            .map(|field| format!("{}={}", field.name, field.value))
+            .collect::<Vec<_>>();
+        fields.sort_unstable();
+        fields
+            .iter()
+            .flat_map(|field| field.bytes().chain([0]))
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+}
+
+impl Column {
+    /// Returns the header displayed for this column.
+    pub(crate) fn header(&self) -> &str {
+        match self {
+            Self::Wakes => "Wakes",
+            Self::Wakers => "Wakers",
+            Self::P99Poll => "P99 Poll",
+            Self::MaxPoll => "Max Poll",
+            Self::PollsPerSec => "Polls/s",
+            Self::WakesPerSec => "Wakes/s",
+            Self::BusyPercent => "Busy %",
+            Self::ScheduledPercent => "Sched %",
+            Self::Awaiting => "Awaiting",
+            Self::Field(name) => name,
+            Self::Expr(expr) => expr.source(),
+        }
+    }
+
+    fn is_field(&self, name: &str) -> bool {
+        matches!(self, Self::Field(field) if field == name)
+    }
+}
+
+impl StateTimes {
+    /// Returns the percentage of the time in all of the states which was
+    /// spent in one taking `time`.
+    pub(crate) fn percent(&self, time: Duration) -> f64 {
+        let total = self.running + self.scheduled + self.idle + self.completed;
+        if total.is_zero() {
+            return 0.0;
+        }
+        time.as_secs_f64().percent_of(total.as_secs_f64())
+    }
+}
+
+impl StatsWindow {
+    pub(crate) const RECENT: Duration = Duration::from_secs(30);
+
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            Self::Lifetime => Self::Recent,
+            Self::Recent => Self::Lifetime,
+        }
+    }
+}
+
+impl StatsSample {
+    /// Returns a sample of a task which hasn't done anything, at `at`.
+    fn empty(at: SystemTime) -> Self {
+        Self {
+            at,
+            polls: 0,
+            wakes: 0,
+            busy: Duration::ZERO,
+            scheduled: Duration::ZERO,
+        }
+    }
+}
+
+impl StatsHistory {
+    /// The longest window that stats are computed over.
+    const MAX_WINDOW: Duration = StatsWindow::RECENT;
+    /// The window that rates are computed over.
+    const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+    fn record(&mut self, sample: StatsSample) {
+        // Keep the newest sample that's at least the longest window old, so
+        // that every window is covered once the task is that old.
+        let start = sample
+            .at
+            .checked_sub(Self::MAX_WINDOW)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        self.samples.push_back(sample);
+        while self.samples.get(1).is_some_and(|sample| sample.at <= start) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns the sample that a `window` ending at `now` starts from, which
+    /// is the newest sample at least `window` old, or the oldest sample if
+    /// there isn't one.
+    ///
+    /// Tasks' stats are only sent when they change, so windows end at `now`
+    /// rather than at the last sample, which lets the stats of tasks that
+    /// have gone quiet decay.
+    fn baseline(&self, window: Duration, now: SystemTime) -> Option<&StatsSample> {
+        let start = now.checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH);
+        self.samples
+            .iter()
+            .rev()
+            .find(|sample| sample.at <= start)
+            .or_else(|| self.samples.front())
+    }
+
+    /// Returns the per-second rate at `now` of the count returned by `count`.
+    fn rate(&self, now: SystemTime, count: impl Fn(&StatsSample) -> u64) -> Option<f64> {
+        let first = self.baseline(Self::RATE_WINDOW, now)?;
+        let last = self.samples.back()?;
+        let elapsed = now.duration_since(first.at).ok()?.as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        Some(count(last).saturating_sub(count(first)) as f64 / elapsed)
+    }
+}
+
+impl TimeAverage {
+    /// How long it takes for the weight of a period in the average to decay
+    /// by a factor of `e`.
+    const TIME_CONSTANT: Duration = Duration::from_secs(10);
+
+    fn new(at: SystemTime) -> Self {
+        Self {
+            at,
+            spent: Duration::ZERO,
+            average: 0.0,
+        }
+    }
+
+    fn record(&mut self, at: SystemTime, spent: Duration) {
+        if at <= self.at {
+            return;
+        }
+        self.average = self.average_at(at, spent);
+        self.at = at;
+        self.spent = spent;
+    }
+
+    /// Returns the average at `at`, if the task's time in the state is
+    /// `spent` then.
+    fn average_at(&self, at: SystemTime, spent: Duration) -> f64 {
+        let elapsed = match at.duration_since(self.at) {
+            Ok(elapsed) if !elapsed.is_zero() => elapsed.as_secs_f64(),
+            _ => return self.average,
+        };
+        let fraction = (spent.saturating_sub(self.spent).as_secs_f64() / elapsed).min(1.0);
+        let weight = 1.0 - (-elapsed / Self::TIME_CONSTANT.as_secs_f64()).exp();
+        self.average + weight * (fraction - self.average)
+    }
+}
+
+impl Eq for ColumnValue<'_> {}
+
+impl PartialOrd for ColumnValue<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ColumnValue<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Field(a), Self::Field(b)) => a.cmp(b),
+            (Self::Duration(a), Self::Duration(b)) => a.cmp(b),
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            // A column only has one kind of value, so this doesn't matter.
+            (a, b) => a.kind().cmp(&b.kind()),
+        }
+    }
+}
+
+impl ColumnValue<'_> {
+    /// Returns the value without borrowing from the task it's the value of.
+    fn into_owned(self) -> ColumnValue<'static> {
+        match self {
+            Self::Field(value) => ColumnValue::Field(Cow::Owned(value.into_owned())),
+            Self::Duration(value) => ColumnValue::Duration(value),
+            Self::Number(value) => ColumnValue::Number(value),
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            Self::Field(_) => 0,
+            Self::Duration(_) => 1,
+            Self::Number(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for ColumnValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(value) => fmt::Display::fmt(value, f),
+            Self::Duration(value) => write!(f, "{value:.2?}"),
+            Self::Number(value) => write!(f, "{value:.2}"),
+        }
+    }
+}
+
+/// Describes an async op as the source of the op and the resource it's on,
+/// such as `Mutex::lock on Mutex 3`.
+impl Awaiting {
+    fn new(async_op: &AsyncOp, since: SystemTime, resources: &ResourcesState) -> Self {
+        let description = match resources
+            .resource(async_op.resource_id())
+            .and_then(|r| r.upgrade())
+        {
+            Some(resource) => {
+                let resource = resource.borrow();
+                format!(
+                    "{} on {} {}",
+                    async_op.source(),
+                    resource.concrete_type(),
+                    resource.id_str()
+                )
+            }
+            None => async_op.source().to_owned(),
+        };
+        Self {
+            description,
+            resource_id: async_op.resource_id(),
+            since,
         }
     }
 }
@@ -657,6 +1797,8 @@ impl TaskState {
         const SCHEDULED_UTF8: &str = "\u{23EB}";
         const IDLE_UTF8: &str = "\u{23F8}";
         const COMPLETED_UTF8: &str = "\u{23F9}";
+        const PANICKED_UTF8: &str = "\u{2717}";
+        const CANCELLED_UTF8: &str = "\u{2298}";
         match self {
             Self::Running => Span::styled(
                 styles.if_utf8(RUNNING_UTF8, "BUSY"),
@@ -665,6 +1807,150 @@ impl TaskState {
             Self::Scheduled => Span::raw(styles.if_utf8(SCHEDULED_UTF8, "SCHED")),
             Self::Idle => Span::raw(styles.if_utf8(IDLE_UTF8, "IDLE")),
             Self::Completed => Span::raw(styles.if_utf8(COMPLETED_UTF8, "DONE")),
+            Self::Cancelled => Span::styled(
+                styles.if_utf8(CANCELLED_UTF8, "CANCL"),
+                styles.fg(Color::Yellow),
+            ),
+            Self::Panicked => Span::styled(
+                styles.if_utf8(PANICKED_UTF8, "PANIC"),
+                styles.fg(Color::Red),
+            ),
+        }
+    }
+
+    /// Returns a short description of the state, for the task's details.
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Scheduled => "scheduled",
+            Self::Idle => "idle",
+            Self::Completed => "completed",
+            Self::Cancelled => "cancelled",
+            Self::Panicked => "panicked",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fs_op_names() {
+        assert_eq!(
+            fs_op_name("tokio::fs::read::read<&str>::{{closure}}::{{closure}}").as_deref(),
+            Some("fs::read")
+        );
+        assert_eq!(
+            fs_op_name("tokio::fs::metadata::metadata::{{closure}}").as_deref(),
+            Some("fs::metadata")
+        );
+        assert_eq!(
+            fs_op_name(
+                "tokio::fs::file::<impl tokio::io::async_read::AsyncRead for tokio::fs::file::File>::poll_read::{{closure}}"
+            )
+            .as_deref(),
+            Some("fs::poll_read")
+        );
+        assert_eq!(fs_op_name("my_crate::do_blocking_work::{{closure}}"), None);
+    }
+
+    #[test]
+    fn activity_intervals() {
+        let ms = Duration::from_millis;
+        let mut activity = Activity::default();
+        // There's no interval until the second update.
+        activity.record(ms(10), 1);
+        assert!(activity.intervals().is_empty());
+        activity.record(ms(25), 4);
+        activity.record(ms(25), 4);
+        assert_eq!(
+            activity.intervals().iter().copied().collect::<Vec<_>>(),
+            [
+                Interval {
+                    busy: ms(15),
+                    wakes: 3
+                },
+                Interval {
+                    busy: ms(0),
+                    wakes: 0
+                },
+            ]
+        );
+
+        for wakes in 5..Activity::MAX_INTERVALS as u64 + 10 {
+            activity.record(ms(25), wakes);
         }
+        assert_eq!(activity.intervals().len(), Activity::MAX_INTERVALS);
+        assert_eq!(activity.intervals().back().unwrap().wakes, 1);
+    }
+
+    #[test]
+    fn history_rates() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let at = |secs| start + Duration::from_secs(secs);
+        let sample = |secs, polls| StatsSample {
+            polls,
+            ..StatsSample::empty(at(secs))
+        };
+        let polls = |sample: &StatsSample| sample.polls;
+
+        let mut history = StatsHistory::default();
+        history.record(sample(0, 0));
+        assert_eq!(history.rate(start, polls), None);
+
+        // A task that was busy for its first minute, and then went quiet.
+        history.record(sample(60, 6000));
+        assert_eq!(history.rate(at(60), polls), Some(100.0));
+        history.record(sample(65, 6010));
+        history.record(sample(70, 6020));
+        assert_eq!(history.rate(at(70), polls), Some(2.0));
+        assert_eq!(history.rate(at(75), polls), Some(1.0));
+        assert_eq!(history.rate(at(80), polls), Some(0.0));
+
+        // Only the samples needed for the longest window are kept.
+        history.record(sample(100, 6020));
+        assert_eq!(history.samples.front().unwrap().at, at(70));
+        assert_eq!(
+            history
+                .baseline(StatsWindow::RECENT, at(100))
+                .unwrap()
+                .polls,
+            6020
+        );
+    }
+
+    #[test]
+    fn busy_average() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut average = TimeAverage::new(start);
+        // A task that has been busy for half of a long lifetime.
+        average.record(at(1000), Duration::from_secs(500));
+        assert!((average.average - 0.5).abs() < 1e-6);
+
+        // A momentary spike only moves the average a little.
+        let spike = average.average_at(at(1001), Duration::from_secs(501));
+        assert!(spike > 0.5 && spike < 0.6, "{spike}");
+
+        // But staying busy eventually does.
+        let busy = average.average_at(at(1060), Duration::from_secs(560));
+        assert!(busy > 0.99, "{busy}");
+    }
+
+    #[test]
+    fn state_time_percentages() {
+        let times = StateTimes {
+            running: Duration::from_secs(1),
+            scheduled: Duration::from_secs(3),
+            idle: Duration::from_secs(4),
+            completed: Duration::from_secs(2),
+        };
+        assert_eq!(times.percent(times.running), 10.0);
+        assert_eq!(times.percent(times.scheduled), 30.0);
+        assert_eq!(times.percent(times.idle + times.completed), 60.0);
+        // A task which was just spawned hasn't spent any time in any state.
+        assert_eq!(StateTimes::default().percent(Duration::ZERO), 0.0);
     }
 }
@@ -0,0 +1,552 @@
+//! Tabs, each of which holds its own connection to a target and everything
+//! the console knows about it, so that several targets, or several views of
+//! one target, can be watched from a single terminal.
+use crate::{
+    alert, bookmarks, config, conn, editor, notify, pause, persist, replay,
+    state::State,
+    view::{self, TaskRequest, UpdateKind, ViewState},
+};
+use console_api::{self as proto, client::coalesce, tasks::TaskDetails};
+use futures::stream::StreamExt;
+use std::{collections::VecDeque, path::PathBuf};
+use tokio::sync::{mpsc, watch};
+use tonic::transport::Uri;
+
+/// The most tabs which can be open at once, since they're switched to with
+/// the number keys.
+pub(crate) const MAX_TABS: usize = 9;
+
+/// Identifies a tab for as long as it's open, even as other tabs are closed.
+pub(crate) type TabId = usize;
+
+/// The tab the console starts with, which is the only tab that's relayed and
+/// recorded.
+pub(crate) const FIRST_TAB: TabId = 0;
+
+pub(crate) struct Tab {
+    id: TabId,
+    pub(crate) conn: conn::Connection,
+    pub(crate) state: State,
+    pub(crate) view: view::View,
+    pub(crate) notifier: notify::Notifier,
+    alerts: alert::Alerts,
+    /// A channel to send the outcome of `View::update_input` to the task
+    /// watching the details of this tab's selected task.
+    update_tx: watch::Sender<UpdateKind>,
+    bookmarks_path: Option<PathBuf>,
+    settings_path: Option<PathBuf>,
+    settings: Option<view::ViewSettings>,
+    /// The task linters which automatically pause the target when a task
+    /// triggers them, by their index in the state's task linters.
+    auto_pause_linters: Vec<usize>,
+    auto_pause_warning_count: usize,
+    pause_triggers: Vec<pause::PauseTrigger>,
+    auto_pause_details: bool,
+    /// Whether the target was paused automatically when opening a task's
+    /// details, and should be resumed when leaving them.
+    pub(crate) auto_paused: bool,
+    was_in_task_details: bool,
+    /// Whether the display is frozen, and the updates which have been
+    /// received but not yet applied, because it's frozen.
+    pub(crate) frozen: bool,
+    pub(crate) received: VecDeque<conn::Message>,
+    received_details: Option<TaskDetails>,
+}
+
+// === impl Tab ===
+
+impl Tab {
+    pub(crate) fn new(
+        id: TabId,
+        args: &config::Config,
+        styles: view::Styles,
+        target: Uri,
+        name: Option<String>,
+    ) -> Self {
+        let bookmarks_path = bookmarks::path_for(&target);
+        let settings_path = persist::path_for("view", &target);
+        let state = new_state(args, bookmarks_path.as_deref());
+        let view = view::View::new(styles)
+            .with_targets(args.named_targets())
+            .with_discover(args.discover.clone())
+            .with_keymap(args.keymap.clone())
+            .with_highlights(args.highlights.clone())
+            .with_editor_command(
+                args.editor_command
+                    .clone()
+                    .or_else(editor::EditorCommand::from_env),
+            )
+            .with_task_list_columns(args.show_columns.clone())
+            .with_task_list_sort(args.sort.as_ref(), &state);
+        let warnings = args.enabled_warnings();
+        let mut tab = Self {
+            id,
+            conn: conn::Connection::new(target)
+                .with_name(name)
+                .with_reconnect(args.reconnect_policy()),
+            state,
+            view,
+            notifier: notify::Notifier::new(args.notify, &args.notify_on),
+            alerts: args.alerts.monitor(),
+            update_tx: watch::channel(UpdateKind::Other).0,
+            bookmarks_path,
+            settings_path,
+            settings: None,
+            auto_pause_linters: warnings
+                .iter()
+                .enumerate()
+                .filter(|(_, lint)| args.auto_pause.warnings().contains(lint))
+                .map(|(idx, _)| idx)
+                .collect(),
+            auto_pause_warning_count: 0,
+            pause_triggers: args
+                .auto_pause
+                .conditions()
+                .iter()
+                .cloned()
+                .map(pause::PauseTrigger::new)
+                .collect(),
+            auto_pause_details: args.auto_pause.details(),
+            auto_paused: false,
+            was_in_task_details: false,
+            frozen: false,
+            received: VecDeque::new(),
+            received_details: None,
+        };
+        tab.load_settings();
+        tab
+    }
+
+    pub(crate) fn id(&self) -> TabId {
+        self.id
+    }
+
+    /// Returns the name shown for the tab in the tab bar.
+    pub(crate) fn title(&self) -> String {
+        match self.conn.name() {
+            Some(name) => name.to_owned(),
+            None => self.conn.target().to_string(),
+        }
+    }
+
+    /// Connects the tab to another target, forgetting everything about the
+    /// old one.
+    pub(crate) fn change_target(
+        &mut self,
+        args: &config::Config,
+        target: Uri,
+        name: Option<String>,
+    ) {
+        self.bookmarks_path = bookmarks::path_for(&target);
+        self.settings_path = persist::path_for("view", &target);
+        self.conn = conn::Connection::new(target)
+            .with_name(name)
+            .with_reconnect(args.reconnect_policy());
+        self.state = new_state(args, self.bookmarks_path.as_deref());
+        self.view.reset_for_new_target();
+        self.load_settings();
+        self.auto_paused = false;
+        self.auto_pause_warning_count = 0;
+        self.notifier.reset();
+        self.alerts.reset();
+        self.received.clear();
+        self.received_details = None;
+    }
+
+    /// Pauses the target, unless it can't be paused.
+    pub(crate) async fn pause(&mut self) -> bool {
+        if !self.conn.supports(conn::Feature::Pause) {
+            return false;
+        }
+        self.conn.pause().await;
+        self.state.start_pausing();
+        true
+    }
+
+    pub(crate) async fn resume(&mut self) {
+        if self.conn.supports(conn::Feature::Pause) {
+            self.conn.resume().await;
+            self.state.start_unpausing();
+        }
+    }
+
+    pub(crate) async fn step(&mut self) {
+        if self.state.is_paused() && self.conn.supports(conn::Feature::Step) {
+            self.conn.step().await;
+        }
+    }
+
+    /// Sends the target the request about a task which the view made, if it
+    /// made one, and shows the outcome in the task's details.
+    pub(crate) async fn send_task_request(&mut self) {
+        let Some((task_id, request)) = self.view.take_task_request() else {
+            return;
+        };
+        match request {
+            TaskRequest::Dump => {
+                let dump = if self.conn.supports(conn::Feature::DumpTask) {
+                    match self.conn.dump_task(task_id).await {
+                        Ok(dump) => self
+                            .state
+                            .task_dump(dump)
+                            .ok_or_else(|| "the target's dump didn't describe the task".to_owned()),
+                        Err(status) => Err(status.message().to_owned()),
+                    }
+                } else {
+                    Err("the target doesn't support task dumps".to_owned())
+                };
+                if let Some(view) = self.view.task_view(task_id) {
+                    view.dumped(dump);
+                }
+            }
+            TaskRequest::Abort => {
+                let aborted = if self.conn.supports(conn::Feature::AbortTask) {
+                    self.conn
+                        .abort_task(task_id)
+                        .await
+                        .map_err(|status| status.message().to_owned())
+                } else {
+                    Err("the target doesn't support aborting tasks".to_owned())
+                };
+                if let Some(view) = self.view.task_view(task_id) {
+                    view.aborted(aborted);
+                }
+            }
+        }
+    }
+
+    /// Seeks through the recording being replayed, if the tab is replaying
+    /// one.
+    pub(crate) fn seek(&mut self, args: &config::Config, back: bool) {
+        // The state can only be rebuilt by replaying the recording from the
+        // beginning.
+        if self.conn.seek(replay::SEEK_STEP, back) {
+            self.state = new_state(args, self.bookmarks_path.as_deref());
+            self.view.reset_for_new_target();
+            self.received.clear();
+        }
+    }
+
+    /// Saves the bookmarks and view settings, if they've changed.
+    pub(crate) fn save(&mut self) {
+        if self.state.tasks_state_mut().sync_bookmarks() {
+            if let Some(ref path) = self.bookmarks_path {
+                bookmarks::save(path, self.state.tasks_state().saved_bookmarks());
+            }
+        }
+        let current_settings = self.view.settings();
+        if self.settings.as_ref() != Some(&current_settings) {
+            if let Some(ref path) = self.settings_path {
+                persist::save(path, toml::to_string(&current_settings).map_err(Into::into));
+            }
+            self.settings = Some(current_settings);
+        }
+    }
+
+    /// Starts or stops watching the selected task's details, according to
+    /// the outcome of `View::update_input`, and pauses or resumes the target
+    /// when entering or leaving a task's details, if configured to.
+    pub(crate) async fn handle_update(
+        &mut self,
+        update_kind: UpdateKind,
+        details_tx: &mpsc::Sender<(TabId, TaskDetails)>,
+    ) {
+        self.update_tx.send_replace(update_kind);
+        match update_kind {
+            UpdateKind::SelectTask(task_id) => {
+                tracing::info!(task_id, "starting details watch");
+                match self.conn.watch_details(task_id).await {
+                    Ok(stream) => {
+                        tokio::spawn(watch_details_stream(
+                            self.id,
+                            task_id,
+                            stream,
+                            self.update_tx.subscribe(),
+                            details_tx.clone(),
+                        ));
+                    }
+                    Err(error) => {
+                        tracing::warn!(%error, "error watching task details");
+                        self.state.unset_task_details();
+                    }
+                }
+            }
+            UpdateKind::ExitTaskView => {
+                self.state.unset_task_details();
+            }
+            _ => {}
+        }
+
+        let in_task_details = matches!(self.view.current_view(), ViewState::TaskInstance(_));
+        if self.auto_pause_details && in_task_details != self.was_in_task_details {
+            if in_task_details && !self.state.is_paused() {
+                self.auto_paused = self.pause().await;
+            } else if !in_task_details && self.auto_paused {
+                self.resume().await;
+                self.auto_paused = false;
+            }
+        }
+        self.was_in_task_details = in_task_details;
+    }
+
+    pub(crate) fn receive_details(&mut self, details: TaskDetails) {
+        self.received_details = Some(details);
+    }
+
+    /// Returns the number of updates to the data which are held while the
+    /// display is frozen, or `None` if it isn't frozen.
+    pub(crate) fn frozen_pending(&self) -> Option<usize> {
+        // Only count the updates to the data, rather than changes to the
+        // target's state, which aren't displayed as data.
+        self.frozen.then(|| {
+            self.received
+                .iter()
+                .filter(|message| matches!(message, conn::Message::Update(_)))
+                .count()
+        })
+    }
+
+    /// Returns whether updates have been received which are waiting to be
+    /// applied.
+    pub(crate) fn has_pending(&self) -> bool {
+        !self.frozen && (!self.received.is_empty() || self.received_details.is_some())
+    }
+
+    /// Applies the updates which have been received if it's time for another
+    /// `frame`, unless the display is frozen, and pauses the target if they
+    /// trigger any of the configured conditions. Returns whether any updates
+    /// were applied.
+    pub(crate) async fn apply_received(&mut self, frame: bool) -> bool {
+        let applied = frame && self.has_pending();
+        // While the display is frozen, updates are kept until it's unfrozen,
+        // so that the screen can be read without pausing the target.
+        if applied {
+            if let Some(details_update) = self.received_details.take() {
+                self.state.update_task_details(details_update);
+            }
+            self.state.set_clock_skew(self.conn.clock_skew());
+            while let Some(instrument_message) = self.received.pop_front() {
+                match instrument_message {
+                    conn::Message::Update(mut update) => {
+                        // Only the latest state is drawn, so consecutive
+                        // updates are applied as one.
+                        while let Some(next) = pop_update(&mut self.received) {
+                            coalesce(&mut update, next);
+                        }
+                        self.state
+                            .update(&self.view.styles, self.view.current_view(), update);
+                        self.view.update_plugins(&self.state);
+                        self.notifier.check(&self.state);
+                        for message in self.alerts.check(&self.state) {
+                            let now = self
+                                .state
+                                .last_updated_at()
+                                .unwrap_or_else(|| self.state.target_now());
+                            self.state.event_log_mut().alert(now, message.clone());
+                            self.view.show_alert(message);
+                        }
+                        if !self.auto_pause_linters.is_empty() {
+                            let linters = &self.state.tasks_state().linters;
+                            let count = self
+                                .auto_pause_linters
+                                .iter()
+                                .map(|&idx| linters[idx].count())
+                                .sum::<usize>();
+                            // Pause as soon as another task has triggered one
+                            // of the warnings, so that the state that caused it
+                            // isn't lost.
+                            if count > self.auto_pause_warning_count && !self.state.is_paused() {
+                                tracing::info!(count, "pausing on warning");
+                                self.pause().await;
+                            }
+                            self.auto_pause_warning_count = count;
+                        }
+                        // Check every condition, so that each one tracks
+                        // whether it held for this update.
+                        let mut triggered = false;
+                        for trigger in &mut self.pause_triggers {
+                            if trigger.check(&mut self.state) {
+                                tracing::info!(condition = %trigger.condition(), "pause condition triggered");
+                                triggered = true;
+                            }
+                        }
+                        if triggered && !self.state.is_paused() {
+                            self.pause().await;
+                        }
+                    }
+                    conn::Message::State(state_update) => {
+                        self.state.update_state(state_update);
+                    }
+                }
+            }
+        }
+
+        self.state
+            .set_task_details_unavailable(!self.conn.supports(conn::Feature::TaskDetails));
+
+        // A snapshot's connection closes once it has been read, which
+        // isn't worth notifying anyone of.
+        if self.conn.is_live() {
+            self.notifier.check_connection(self.conn.is_connected());
+            let now = self.state.target_now();
+            self.state
+                .event_log_mut()
+                .check_connection(now, self.conn.is_connected());
+        }
+        applied
+    }
+
+    fn load_settings(&mut self) {
+        self.settings = self
+            .settings_path
+            .as_deref()
+            .and_then(|path| persist::load(path, |raw| Ok(toml::from_str(raw)?)));
+        if let Some(ref settings) = self.settings {
+            self.view.restore_settings(settings.clone());
+        }
+    }
+}
+
+/// Returns the next message received by any tab's connection, and the index
+/// of the tab which received it.
+pub(crate) async fn next_message(tabs: &mut [Tab]) -> (conn::Message, usize) {
+    let (message, idx, _) =
+        futures::future::select_all(tabs.iter_mut().map(|tab| Box::pin(tab.conn.next_message())))
+            .await;
+    (message, idx)
+}
+
+/// Removes the first of the `received` messages if it's an update, and
+/// returns it.
+fn pop_update(received: &mut VecDeque<conn::Message>) -> Option<proto::instrument::Update> {
+    match received.pop_front()? {
+        conn::Message::Update(update) => Some(update),
+        message => {
+            received.push_front(message);
+            None
+        }
+    }
+}
+
+fn new_state(args: &config::Config, bookmarks_path: Option<&std::path::Path>) -> State {
+    State::default()
+        .with_task_linters(
+            args.enabled_warnings()
+                .iter()
+                .copied()
+                .map(|lint| lint.into()),
+        )
+        .with_task_columns(args.task_columns())
+        .with_retain_for(args.retain_for())
+        .with_slos(args.slos.iter().cloned())
+        .with_saved_bookmarks(bookmarks_path.map(bookmarks::load).unwrap_or_default())
+}
+
+/// Given the task details stream for the given task id, sends the updates
+/// to the `details_tx` channel until the currently-viewed task changes.
+///
+/// This is a separate task from the main program loop mainly because there isn't
+/// always a details stream to poll and we need to react to user inputs to
+/// replace the details stream with another one.
+async fn watch_details_stream(
+    tab: TabId,
+    task_id: u64,
+    mut details_stream: tonic::Streaming<TaskDetails>,
+    mut watch_rx: watch::Receiver<UpdateKind>,
+    details_tx: mpsc::Sender<(TabId, TaskDetails)>,
+) {
+    loop {
+        tokio::select! { biased;
+            details = details_stream.next() => {
+                match details {
+                    Some(Ok(details)) => {
+                        if details_tx.send((tab, details)).await.is_err() {
+                            break;
+                        }
+                    },
+                    _ => {
+                        break;
+                    }
+                }
+            },
+            update = watch_rx.changed() => {
+                if update.is_ok() {
+                    match *watch_rx.borrow() {
+                        UpdateKind::ExitTaskView => {
+                            break;
+                        },
+                        UpdateKind::SelectTask(new_id) if new_id != task_id => {
+                            break;
+                        },
+                        _ => {}
+                    }
+                } else {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(now: i64, task: u64, polls: u64) -> proto::instrument::Update {
+        proto::instrument::Update {
+            now: Some(prost_types::Timestamp {
+                seconds: now,
+                nanos: 0,
+            }),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks: vec![proto::tasks::Task {
+                    id: Some(proto::Id { id: task }),
+                    ..Default::default()
+                }],
+                stats_update: [(
+                    task,
+                    proto::tasks::Stats {
+                        wakes: polls,
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+                dropped_events: 1,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn coalesces_consecutive_updates() {
+        // The first task is polled again, without being new.
+        let mut polled = update(2, 1, 5);
+        polled.task_update.as_mut().unwrap().new_tasks.clear();
+        let mut received = VecDeque::from([
+            conn::Message::Update(polled),
+            conn::Message::Update(update(3, 2, 1)),
+            conn::Message::State(Default::default()),
+            conn::Message::Update(update(4, 3, 1)),
+        ]);
+        let mut pending = update(1, 1, 2);
+        while let Some(next) = pop_update(&mut received) {
+            coalesce(&mut pending, next);
+        }
+        // Updates after a state change aren't merged across it.
+        assert_eq!(received.len(), 2);
+        assert!(matches!(received[0], conn::Message::State(_)));
+
+        assert_eq!(pending.now.unwrap().seconds, 3);
+        let tasks = pending.task_update.unwrap();
+        let ids = tasks
+            .new_tasks
+            .iter()
+            .map(|task| task.id.unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2]);
+        // The latest stats replace the earlier ones.
+        assert_eq!(tasks.stats_update[&1].wakes, 5);
+        assert_eq!(tasks.stats_update[&2].wakes, 1);
+        assert_eq!(tasks.dropped_events, 3);
+    }
+}
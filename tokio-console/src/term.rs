@@ -2,14 +2,12 @@ pub use color_eyre::eyre::WrapErr;
 pub use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 
-pub fn init_crossterm() -> color_eyre::Result<(Terminal<CrosstermBackend<io::Stdout>>, OnShutdown)>
-{
-    use crossterm::terminal::{self, EnterAlternateScreen};
-    terminal::enable_raw_mode().wrap_err("Failed to enable crossterm raw mode")?;
-
-    let mut stdout = std::io::stdout();
-    crossterm::execute!(stdout, EnterAlternateScreen)
-        .wrap_err("Failed to enable crossterm alternate screen")?;
+/// Sets up the terminal for the console, asking it to report when it gains
+/// and loses focus if `report_focus` is set.
+pub fn init_crossterm(
+    report_focus: bool,
+) -> color_eyre::Result<(Terminal<CrosstermBackend<io::Stdout>>, OnShutdown)> {
+    enter_crossterm(report_focus)?;
     let backend = CrosstermBackend::new(io::stdout());
     let term = Terminal::new(backend).wrap_err("Failed to create crossterm terminal")?;
 
@@ -18,10 +16,39 @@ pub fn init_crossterm() -> color_eyre::Result<(Terminal<CrosstermBackend<io::Std
     Ok((term, cleanup))
 }
 
+/// Takes over the terminal, as when the console starts, or when it's handed
+/// back after an editor has run in it.
+pub(crate) fn enter_crossterm(report_focus: bool) -> color_eyre::Result<()> {
+    use crossterm::{
+        event::{EnableFocusChange, EnableMouseCapture},
+        terminal::{self, EnterAlternateScreen},
+    };
+    terminal::enable_raw_mode().wrap_err("Failed to enable crossterm raw mode")?;
+
+    let mut stdout = std::io::stdout();
+    crossterm::execute!(stdout, EnterAlternateScreen)
+        .wrap_err("Failed to enable crossterm alternate screen")?;
+    crossterm::execute!(stdout, EnableMouseCapture)
+        .wrap_err("Failed to enable crossterm mouse capture")?;
+    if report_focus {
+        crossterm::execute!(stdout, EnableFocusChange)
+            .wrap_err("Failed to enable crossterm focus change events")?;
+    }
+    Ok(())
+}
+
 pub(crate) fn exit_crossterm() -> color_eyre::Result<()> {
-    use crossterm::terminal::{self, LeaveAlternateScreen};
+    use crossterm::{
+        event::{DisableFocusChange, DisableMouseCapture},
+        terminal::{self, LeaveAlternateScreen},
+    };
     // Be a good terminal citizen...
     let mut stdout = std::io::stdout();
+    // This is harmless if focus changes were never reported.
+    crossterm::execute!(stdout, DisableFocusChange)
+        .wrap_err("Failed to disable crossterm focus change events")?;
+    crossterm::execute!(stdout, DisableMouseCapture)
+        .wrap_err("Failed to disable crossterm mouse capture")?;
     crossterm::execute!(stdout, LeaveAlternateScreen)
         .wrap_err("Failed to disable crossterm alternate screen")?;
     terminal::disable_raw_mode().wrap_err("Failed to enable crossterm raw mode")?;
@@ -0,0 +1,203 @@
+//! Detecting metrics which rise steadily over a session, such as slow leaks
+//! of tasks or resources, which no single update makes obvious.
+use crate::{
+    i18n::{tr, tr_fmt},
+    state::{resources::ResourcesState, tasks::TasksState},
+};
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// How often the metrics are sampled, by the target's clock.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How many samples a metric must have risen steadily over to be reported.
+const SAMPLES: usize = 13;
+
+/// A metric whose trend is tracked over the session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Metric {
+    /// Tasks which haven't completed.
+    Tasks,
+    /// Idle tasks which have no wakers, so nothing can wake them.
+    IdleWithoutWakers,
+    /// Resources which haven't been dropped.
+    Resources,
+}
+
+/// A metric which has risen steadily over the last [`SAMPLES`] samples.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Rising {
+    pub(crate) metric: Metric,
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+    /// How long the metric has been rising for.
+    pub(crate) over: Duration,
+}
+
+/// Samples of each metric, taken every [`SAMPLE_INTERVAL`].
+#[derive(Debug, Default)]
+pub(crate) struct Trends {
+    last_sampled_at: Option<SystemTime>,
+    /// The most recent samples, oldest first, in the order of [`Metric::ALL`].
+    samples: VecDeque<[u64; Metric::ALL.len()]>,
+}
+
+// === impl Metric ===
+
+impl Metric {
+    const ALL: [Metric; 3] = [Metric::Tasks, Metric::IdleWithoutWakers, Metric::Resources];
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(tr(match self {
+            Metric::Tasks => "live tasks",
+            Metric::IdleWithoutWakers => "idle tasks with no wakers",
+            Metric::Resources => "live resources",
+        }))
+    }
+}
+
+// === impl Rising ===
+
+impl fmt::Display for Rising {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr_fmt(
+            "{} have risen steadily for {}, from {} to {}",
+            &[
+                &self.metric,
+                &humantime::format_duration(self.over),
+                &self.from,
+                &self.to,
+            ],
+        ))
+    }
+}
+
+// === impl Trends ===
+
+impl Trends {
+    /// Samples the metrics, if it has been at least [`SAMPLE_INTERVAL`] since
+    /// they were last sampled.
+    pub(crate) fn record(
+        &mut self,
+        now: SystemTime,
+        tasks: &TasksState,
+        resources: &ResourcesState,
+    ) {
+        if let Some(last) = self.last_sampled_at {
+            if now.duration_since(last).unwrap_or_default() < SAMPLE_INTERVAL {
+                return;
+            }
+        }
+        self.last_sampled_at = Some(now);
+
+        let mut live_tasks = 0;
+        let mut idle_without_wakers = 0;
+        for task in tasks.tasks() {
+            let Some(task) = task.upgrade() else {
+                continue;
+            };
+            let task = task.borrow();
+            if task.is_completed() {
+                continue;
+            }
+            live_tasks += 1;
+            if !task.is_blocking()
+                && task.waker_count() == 0
+                && !task.is_running()
+                && !task.is_awakened()
+            {
+                idle_without_wakers += 1;
+            }
+        }
+        let live_resources = resources
+            .resources()
+            .filter_map(|resource| resource.upgrade())
+            .filter(|resource| !resource.borrow().dropped())
+            .count() as u64;
+
+        self.push([live_tasks, idle_without_wakers, live_resources]);
+    }
+
+    fn push(&mut self, sample: [u64; Metric::ALL.len()]) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Returns the metrics which have risen steadily over the last
+    /// [`SAMPLES`] samples.
+    ///
+    /// A metric rises steadily if it never fell, and rose between at least
+    /// half of the samples, so that a single jump isn't mistaken for a leak.
+    pub(crate) fn rising(&self) -> impl Iterator<Item = Rising> + '_ {
+        let full = self.samples.len() == SAMPLES;
+        Metric::ALL
+            .iter()
+            .enumerate()
+            .filter(move |_| full)
+            .filter_map(|(idx, &metric)| {
+                let values = self.samples.iter().map(|sample| sample[idx]);
+                let mut rises = 0;
+                for (prev, next) in values.clone().zip(values.skip(1)) {
+                    if next < prev {
+                        return None;
+                    }
+                    if next > prev {
+                        rises += 1;
+                    }
+                }
+                if rises * 2 < SAMPLES - 1 {
+                    return None;
+                }
+                Some(Rising {
+                    metric,
+                    from: self.samples.front()?[idx],
+                    to: self.samples.back()?[idx],
+                    over: SAMPLE_INTERVAL * (SAMPLES as u32 - 1),
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trends(tasks: impl IntoIterator<Item = u64>) -> Trends {
+        let mut trends = Trends::default();
+        for tasks in tasks {
+            trends.push([tasks, 0, 0]);
+        }
+        trends
+    }
+
+    #[test]
+    fn steady_rise() {
+        let rising = trends((0..SAMPLES as u64).map(|n| 100 + n / 2))
+            .rising()
+            .collect::<Vec<_>>();
+        assert_eq!(rising.len(), 1);
+        assert_eq!(rising[0].metric, Metric::Tasks);
+        assert_eq!(rising[0].from, 100);
+        assert_eq!(rising[0].to, 106);
+    }
+
+    #[test]
+    fn not_rising() {
+        // Not enough samples yet.
+        assert_eq!(trends(0..SAMPLES as u64 - 1).rising().count(), 0);
+        // A single fall means it isn't a leak.
+        let mut samples = (0..SAMPLES as u64).collect::<Vec<_>>();
+        samples[6] = 0;
+        assert_eq!(trends(samples).rising().count(), 0);
+        // Nor is a single jump.
+        let jump = (0..SAMPLES as u64).map(|n| if n < 6 { 10 } else { 20 });
+        assert_eq!(trends(jump).rising().count(), 0);
+    }
+}
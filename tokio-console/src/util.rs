@@ -1,3 +1,8 @@
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
 pub(crate) trait Percentage {
     // Using an extension trait for this is maybe a bit excessive, but making it
     // a method has the nice advantage of making it *really* obvious which is
@@ -32,3 +37,73 @@ pub(crate) fn percentage(total: f64, amount: f64) -> f64 {
     );
     (amount / total) * 100.0
 }
+
+/// Formats a count in at most five characters, abbreviating counts of ten
+/// thousand or more with a unit suffix, such as `12.3k` or `456M`.
+///
+/// This is for columns of counts, such as polls, where the exact count of a
+/// busy task is too wide to fit and too precise to be useful at a glance.
+pub(crate) fn abbreviate_count(count: u64) -> String {
+    const UNITS: &[&str] = &["k", "M", "G", "T", "P", "E"];
+    if count < 10_000 {
+        return count.to_string();
+    }
+    let mut value = count as f64;
+    for unit in UNITS {
+        value /= 1000.0;
+        // Round first, so that a value such as 99.96 is shown as `100k`
+        // rather than `100.0k`, and 999.6 moves on to the next unit.
+        let tenths = (value * 10.0).round() / 10.0;
+        if tenths < 100.0 {
+            return format!("{tenths:.1}{unit}");
+        }
+        if value.round() < 1000.0 {
+            return format!("{:.0}{unit}", value.round());
+        }
+    }
+    unreachable!("a u64 is less than 1000E")
+}
+
+/// Spawns `command` without waiting for it to exit, with its standard input
+/// and output discarded, so that it can't interfere with the terminal.
+///
+/// The process is reaped once it exits, on a thread of its own, rather than
+/// being left behind as a zombie until the console exits.
+pub(crate) fn spawn_detached(command: &mut Command) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn spawns_detached() {
+        spawn_detached(&mut Command::new("true")).unwrap();
+        assert!(spawn_detached(&mut Command::new("/nonexistent/program")).is_err());
+    }
+
+    #[test]
+    fn abbreviates_counts() {
+        for (count, abbreviated) in [
+            (0, "0"),
+            (9_999, "9999"),
+            (10_000, "10.0k"),
+            (12_345, "12.3k"),
+            (99_960, "100k"),
+            (123_456, "123k"),
+            (999_600, "1.0M"),
+            (45_600_000, "45.6M"),
+            (u64::MAX, "18.4E"),
+        ] {
+            assert_eq!(abbreviate_count(count), abbreviated, "{count}");
+        }
+    }
+}
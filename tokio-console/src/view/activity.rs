@@ -0,0 +1,104 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ratatui::{
+    layout::{self, Layout, Rect},
+    style::Color,
+    text::{Line, Span},
+    widgets::{Sparkline, Widget},
+};
+
+use crate::{state::tasks::Interval, view};
+
+/// This is a Ratatui widget which charts how long a task was busy and how many
+/// times it was woken in each interval between its details updates, newest on
+/// the right, so that bursts and periodic work stand out in a way that the
+/// task's cumulative stats don't show.
+pub(crate) struct ActivityChart<'a> {
+    styles: &'a view::Styles,
+    /// The intervals to chart, oldest first
+    intervals: &'a VecDeque<Interval>,
+}
+
+impl Widget for ActivityChart<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let areas = Layout::default()
+            .direction(layout::Direction::Horizontal)
+            .constraints([
+                layout::Constraint::Percentage(50),
+                layout::Constraint::Percentage(50),
+            ])
+            .split(area);
+
+        // Only as many of the newest intervals as fit are charted, so that
+        // the chart scrolls left as updates arrive.
+        let busy = self.newest(areas[0], |interval| {
+            u64::try_from(interval.busy.as_micros()).unwrap_or(u64::MAX)
+        });
+        let max_busy = Duration::from_micros(busy.iter().copied().max().unwrap_or(0));
+        let title = Line::from(vec![
+            Span::raw("Busy per Update (max "),
+            self.styles
+                .time_units(max_busy, view::DUR_LIST_PRECISION, None),
+            Span::raw(")"),
+        ]);
+        Sparkline::default()
+            .block(self.styles.border_block().title(title))
+            .data(&busy)
+            .style(self.styles.fg(Color::Cyan))
+            .render(areas[0], buf);
+
+        let wakes = self.newest(areas[1], |interval| interval.wakes);
+        let max_wakes = wakes.iter().copied().max().unwrap_or(0);
+        Sparkline::default()
+            .block(
+                self.styles
+                    .border_block()
+                    .title(format!("Wakes per Update (max {max_wakes})")),
+            )
+            .data(&wakes)
+            .style(self.styles.fg(Color::Magenta))
+            .render(areas[1], buf);
+    }
+}
+
+impl<'a> ActivityChart<'a> {
+    pub(crate) fn new(styles: &'a view::Styles, intervals: &'a VecDeque<Interval>) -> Self {
+        Self { styles, intervals }
+    }
+
+    /// How many lines the chart takes up, including its borders.
+    pub(crate) const HEIGHT: u16 = 6;
+
+    /// Returns `value` of each of the newest intervals which fit in `area`,
+    /// inside its borders.
+    fn newest(&self, area: Rect, value: impl Fn(&Interval) -> u64) -> Vec<u64> {
+        let width = usize::from(area.width.saturating_sub(2));
+        let skip = self.intervals.len().saturating_sub(width);
+        self.intervals.iter().skip(skip).map(value).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ViewOptions;
+    use clap::Parser;
+
+    #[test]
+    fn charts_the_newest_intervals_which_fit() {
+        let styles = view::Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let intervals = (0..100)
+            .map(|wakes| Interval {
+                busy: Duration::from_millis(wakes),
+                wakes,
+            })
+            .collect();
+        let chart = ActivityChart::new(&styles, &intervals);
+        let wakes = chart.newest(Rect::new(0, 0, 12, ActivityChart::HEIGHT), |i| i.wakes);
+        assert_eq!(wakes, (90..100).collect::<Vec<_>>());
+        let busy = chart.newest(Rect::new(0, 0, 200, ActivityChart::HEIGHT), |i| {
+            i.busy.as_millis() as u64
+        });
+        assert_eq!(busy.len(), 100);
+    }
+}
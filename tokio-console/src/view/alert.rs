@@ -0,0 +1,74 @@
+use crate::{
+    i18n::{tr, tr_fmt},
+    view,
+};
+use ratatui::{
+    layout,
+    style::{Color, Modifier},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use std::time::{Duration, Instant};
+
+/// The banner shown above the current view when an alert is triggered, which
+/// flashes until a key is pressed, or until it has been shown for a while.
+#[derive(Debug)]
+pub(crate) struct AlertBanner {
+    /// The message of the newest alert.
+    message: String,
+    /// How many alerts have been triggered since the banner was shown.
+    count: usize,
+    since: Instant,
+}
+
+impl AlertBanner {
+    /// The height of the banner.
+    pub(crate) const HEIGHT: u16 = 1;
+
+    /// How long the banner is shown for, unless a key is pressed first.
+    const SHOWN_FOR: Duration = Duration::from_secs(30);
+
+    pub(crate) fn new(message: String) -> Self {
+        Self {
+            message,
+            count: 1,
+            since: Instant::now(),
+        }
+    }
+
+    /// Shows another alert in the banner, and shows it for longer.
+    pub(crate) fn push(&mut self, message: String) {
+        self.message = message;
+        self.count += 1;
+        self.since = Instant::now();
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.since.elapsed() >= Self::SHOWN_FOR
+    }
+
+    pub(crate) fn render(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+    ) {
+        // Flash by swapping the colors every second.
+        let mut style = styles.fg(Color::Red).add_modifier(Modifier::BOLD);
+        if self.since.elapsed().as_secs() % 2 == 0 {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let mut text = format!(" {}: {}", tr("ALERT"), self.message);
+        if self.count > 1 {
+            text.push_str(&format!(
+                " ({})",
+                tr_fmt("and {} more", &[&(self.count - 1)])
+            ));
+        }
+        let line = Line::from(vec![
+            Span::styled(text, style),
+            Span::raw(format!(" ({})", tr("press any key to dismiss"))),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+    }
+}
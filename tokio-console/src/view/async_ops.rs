@@ -1,13 +1,15 @@
 pub(crate) use crate::view::table::view_controls;
 use crate::{
+    query::Value,
     state::{
         async_ops::{AsyncOp, SortBy},
         resources::Resource,
         Id, State,
     },
+    util,
     view::{
         self, bold,
-        table::{TableList, TableListState},
+        table::{self, Search, TableList, TableListState},
         DUR_LEN, DUR_TABLE_PRECISION,
     },
 };
@@ -18,6 +20,7 @@ use ratatui::{
     text::Line,
     widgets::{Cell, Row, Table},
 };
+use std::time::SystemTime;
 
 #[derive(Debug, Default)]
 pub(crate) struct AsyncOpsTable {}
@@ -27,12 +30,12 @@ pub(crate) struct AsyncOpsTableCtx {
     pub(crate) resource_id: Id<Resource>,
 }
 
-impl TableList<9> for AsyncOpsTable {
+impl TableList<10> for AsyncOpsTable {
     type Row = AsyncOp;
     type Sort = SortBy;
     type Context = AsyncOpsTableCtx;
 
-    const HEADER: &'static [&'static str; 9] = &[
+    const HEADER: &'static [&'static str; 10] = &[
         "ID",
         "Parent",
         "Task",
@@ -41,10 +44,11 @@ impl TableList<9> for AsyncOpsTable {
         "Busy",
         "Idle",
         "Polls",
+        "Last Poll",
         "Attributes",
     ];
 
-    const WIDTHS: &'static [usize; 9] = &[
+    const WIDTHS: &'static [usize; 10] = &[
         Self::HEADER[0].len() + 1,
         Self::HEADER[1].len() + 1,
         Self::HEADER[2].len() + 1,
@@ -54,29 +58,48 @@ impl TableList<9> for AsyncOpsTable {
         Self::HEADER[6].len() + 1,
         Self::HEADER[7].len() + 1,
         Self::HEADER[8].len() + 1,
+        Self::HEADER[9].len() + 1,
     ];
 
-    fn render(
-        table_list_state: &mut TableListState<Self, 9>,
-        styles: &view::Styles,
-        frame: &mut ratatui::terminal::Frame,
-        area: layout::Rect,
-        state: &mut State,
-        ctx: Self::Context,
-    ) {
-        let now = if let Some(now) = state.last_updated_at() {
-            now
-        } else {
-            // If we have never gotten an update yet, skip...
-            return;
-        };
+    fn matches(op: &AsyncOp, search: &Search) -> bool {
+        search.matches([op.source(), op.task_id_str(), op.location()])
+            || search.matches_spans(op.formatted_attributes())
+    }
 
-        let AsyncOpsTableCtx {
-            initial_render,
-            resource_id,
-        } = ctx;
+    fn values(
+        async_op: &AsyncOp,
+        now: SystemTime,
+        state: &State,
+        _: &Self::Context,
+    ) -> Option<Vec<Value>> {
+        let task_id = async_op.task_id()?;
+        let task = state
+            .tasks_state()
+            .task(task_id)
+            .and_then(|t| t.upgrade())
+            .map(|t| t.borrow().short_desc().to_owned());
+        Some(vec![
+            Value::id(&async_op.id().to_string()),
+            Value::id(async_op.parent_id()),
+            Value::Text(task.unwrap_or_else(|| async_op.task_id_str().to_owned())),
+            Value::Text(async_op.source().to_owned()),
+            Value::Duration(async_op.total(now)),
+            Value::Duration(async_op.busy(now)),
+            Value::Duration(async_op.idle(now)),
+            Value::Number(async_op.total_polls() as f64),
+            last_poll(async_op).map_or(Value::Null, Value::Text),
+            Value::Text(table::spans_text(async_op.formatted_attributes())),
+        ])
+    }
 
-        if initial_render {
+    fn refresh(
+        table_list_state: &mut TableListState<Self, 10>,
+        now: SystemTime,
+        state: &mut State,
+        ctx: &Self::Context,
+    ) {
+        let resource_id = ctx.resource_id;
+        if ctx.initial_render {
             table_list_state
                 .sorted_items
                 .extend(state.async_ops_state().async_ops().filter(|op| {
@@ -96,15 +119,33 @@ impl TableList<9> for AsyncOpsTable {
                     }),
             )
         };
-        table_list_state
-            .sort_by
-            .sort(now, &mut table_list_state.sorted_items);
+        table_list_state.apply_search();
+        table_list_state.sort_items(now, |sort_by, op| sort_by.key(now, op));
+    }
+
+    fn render(
+        table_list_state: &mut TableListState<Self, 10>,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+        ctx: Self::Context,
+    ) {
+        let now = if let Some(now) = state.last_updated_at() {
+            now
+        } else {
+            // If we have never gotten an update yet, skip...
+            return;
+        };
+
+        Self::refresh(table_list_state, now, state, &ctx);
 
         let mut id_width = view::Width::new(Self::WIDTHS[0] as u16);
         let mut parent_width = view::Width::new(Self::WIDTHS[1] as u16);
         let mut task_width = view::Width::new(Self::WIDTHS[2] as u16);
         let mut source_width = view::Width::new(Self::WIDTHS[3] as u16);
         let mut polls_width = view::Width::new(Self::WIDTHS[7] as u16);
+        let mut last_poll_width = view::Width::new(Self::WIDTHS[8] as u16);
 
         let dur_cell = |dur: std::time::Duration| -> Cell<'static> {
             Cell::from(styles.time_units(dur, DUR_TABLE_PRECISION, Some(DUR_LEN)))
@@ -116,6 +157,7 @@ impl TableList<9> for AsyncOpsTable {
             let task_width = &mut task_width;
             let source_width = &mut source_width;
             let polls_width = &mut polls_width;
+            let last_poll_width = &mut last_poll_width;
 
             table_list_state
                 .sorted_items
@@ -143,7 +185,13 @@ impl TableList<9> for AsyncOpsTable {
                         dur_cell(async_op.total(now)),
                         dur_cell(async_op.busy(now)),
                         dur_cell(async_op.idle(now)),
-                        Cell::from(polls_width.update_str(async_op.total_polls().to_string())),
+                        Cell::from(
+                            polls_width.update_str(util::abbreviate_count(async_op.total_polls())),
+                        ),
+                        Cell::from(
+                            last_poll_width
+                                .update_str(last_poll(&async_op).unwrap_or_else(|| "-".to_owned())),
+                        ),
                         Cell::from(Line::from(
                             async_op
                                 .formatted_attributes()
@@ -169,17 +217,12 @@ impl TableList<9> for AsyncOpsTable {
         };
         let header_style = header_style.add_modifier(style::Modifier::BOLD);
 
-        let header = Row::new(Self::HEADER.iter().enumerate().map(|(idx, &value)| {
-            if idx == table_list_state.selected_column {
-                if table_list_state.sort_descending {
-                    Cell::from(styles.ascending(value))
-                } else {
-                    Cell::from(styles.descending(value))
-                }
-            } else {
-                Cell::from(value)
-            }
-        }))
+        let header = Row::new(
+            Self::HEADER
+                .iter()
+                .enumerate()
+                .map(|(idx, &value)| table_list_state.header_cell(styles, idx, value)),
+        )
         .height(1)
         .style(header_style);
 
@@ -189,10 +232,9 @@ impl TableList<9> for AsyncOpsTable {
             Table::default().rows(rows.rev())
         };
 
-        let block = styles.border_block().title(vec![bold(format!(
-            "Async Ops ({}) ",
-            table_list_state.len()
-        ))]);
+        let mut title = vec![bold(format!("Async Ops ({}) ", table_list_state.len()))];
+        title.extend(table_list_state.search_title(styles));
+        let block = styles.border_block().title(title);
 
         let attributes_width = layout::Constraint::Percentage(100);
         let widths = &[
@@ -204,9 +246,11 @@ impl TableList<9> for AsyncOpsTable {
             layout::Constraint::Length(DUR_LEN as u16),
             layout::Constraint::Length(DUR_LEN as u16),
             polls_width.constraint(),
+            last_poll_width.constraint(),
             attributes_width,
         ];
 
+        let table_area = block.inner(area);
         let table = table
             .header(header)
             .block(block)
@@ -215,9 +259,17 @@ impl TableList<9> for AsyncOpsTable {
             .highlight_style(Style::default().add_modifier(style::Modifier::BOLD));
 
         frame.render_stateful_widget(table, area, &mut table_list_state.table_state);
+        table_list_state.record_layout(table_area, &widths[..]);
 
-        table_list_state
-            .sorted_items
-            .retain(|t| t.upgrade().is_some());
+        table_list_state.remove_dropped();
     }
 }
+
+/// Returns the name of the last poll of the resource from within
+/// `async_op`, and whether the resource was ready, such as
+/// `poll_acquire: pending`.
+fn last_poll(async_op: &AsyncOp) -> Option<String> {
+    let poll_op = async_op.last_poll_op()?;
+    let readiness = if poll_op.is_ready { "ready" } else { "pending" };
+    Some(format!("{}: {readiness}", poll_op.name))
+}
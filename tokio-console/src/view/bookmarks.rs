@@ -0,0 +1,237 @@
+use crate::{
+    i18n::tr,
+    input,
+    state::{tasks::Task, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+        prompt::{Prompt, PromptInput},
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout},
+    style::{self, Style},
+    widgets::{Cell, Row, Table, TableState},
+};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+/// The controls for bookmarking tasks, which are available in every view
+/// that has a selected task.
+pub(crate) const BOOKMARK_CONTROLS: &[ControlDisplay] = &[
+    ControlDisplay {
+        action: "toggle bookmark",
+        keys: &[KeyDisplay {
+            base: "b",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "annotate bookmark",
+        keys: &[KeyDisplay {
+            base: "a",
+            utf8: None,
+        }],
+    },
+];
+
+/// A list of all bookmarked tasks, for quickly jumping to their details.
+#[derive(Debug, Default)]
+pub(crate) struct BookmarksView {
+    table_state: TableState,
+}
+
+/// A popup for editing the note attached to a task's bookmark.
+pub(crate) struct NoteEditor {
+    task: Weak<RefCell<Task>>,
+    prompt: Prompt,
+}
+
+impl BookmarksView {
+    const HEADER: &'static [&'static str] = &["ID", "State", "Name", "Location", "Note"];
+
+    pub(crate) fn update_input(&mut self, event: input::Event, state: &State) {
+        use input::KeyCode::*;
+        let len = state.tasks_state().bookmarked().len();
+        if len == 0 {
+            self.table_state.select(None);
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        if let input::Event::Key(event) = event {
+            let selected = match event.code {
+                Down | Char('j') => (selected + 1) % len,
+                Up | Char('k') => selected.checked_sub(1).unwrap_or(len - 1),
+                _ => return,
+            };
+            self.table_state.select(Some(selected));
+        }
+    }
+
+    /// Returns the currently selected bookmarked task.
+    pub(crate) fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        let selected = self.table_state.selected()?;
+        state.tasks_state().bookmarked().get(selected)?.upgrade()
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let tasks = state.tasks_state().bookmarked();
+        // Keep the selection in bounds as bookmarks are removed.
+        match self.table_state.selected() {
+            _ if tasks.is_empty() => self.table_state.select(None),
+            Some(selected) if selected >= tasks.len() => {
+                self.table_state.select(Some(tasks.len() - 1))
+            }
+            None => self.table_state.select(Some(0)),
+            Some(_) => {}
+        }
+
+        let rows = tasks.iter().filter_map(|task| {
+            let task = task.upgrade()?;
+            let task = task.borrow();
+            Some(Row::new(vec![
+                Cell::from(task.id_str().to_owned()),
+                Cell::from(task.state().render(styles)),
+                Cell::from(task.name().unwrap_or_default().to_owned()),
+                Cell::from(task.location().to_owned()),
+                Cell::from(task.bookmark_note().unwrap_or_default().to_owned()),
+            ]))
+        });
+
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(controls.height()), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(20),
+                Constraint::Length(40),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(Self::HEADER.iter().copied().map(tr))
+                .style(Style::default().add_modifier(style::Modifier::BOLD)),
+        )
+        .block(
+            styles
+                .border_block()
+                .title(vec![bold(format!("Bookmarks ({}) ", tasks.len()))]),
+        )
+        .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
+        .highlight_style(Style::default().add_modifier(style::Modifier::BOLD))
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl HelpText for BookmarksView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "view details",
+            keys: &[KeyDisplay {
+                base: "enter",
+                utf8: Some("\u{21B5}"),
+            }],
+        },
+        ControlDisplay {
+            action: "remove bookmark",
+            keys: &[KeyDisplay {
+                base: "b",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "annotate bookmark",
+            keys: &[KeyDisplay {
+                base: "a",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc",
+                utf8: Some("\u{238B} esc"),
+            }],
+        },
+    ]
+}
+
+// === impl NoteEditor ===
+
+impl NoteEditor {
+    /// Notes are meant to be short reminders, so they are limited to this
+    /// many characters.
+    const MAX_LEN: usize = 64;
+
+    pub(crate) fn new(task: &Rc<RefCell<Task>>) -> Self {
+        let task_ref = task.borrow();
+        let prompt = Prompt::new(
+            format!("Note for task {}", task_ref.id_str()),
+            task_ref.bookmark_note().unwrap_or_default(),
+        )
+        .with_max_len(Self::MAX_LEN);
+        Self {
+            task: Rc::downgrade(task),
+            prompt,
+        }
+    }
+
+    /// Handles a key event, returning `true` once editing has finished.
+    ///
+    /// Pressing enter bookmarks the task with the edited note, and pressing
+    /// escape discards the edit.
+    pub(crate) fn update_input(&mut self, event: input::Event) -> bool {
+        match self.prompt.update_input(event) {
+            PromptInput::Editing => false,
+            PromptInput::Submit(note) => {
+                if let Some(task) = self.task.upgrade() {
+                    task.borrow_mut().set_bookmark_note(note);
+                }
+                true
+            }
+            PromptInput::Cancel => true,
+        }
+    }
+
+    pub(crate) fn render(&self, styles: &view::Styles, frame: &mut ratatui::terminal::Frame) {
+        self.prompt.render(styles, frame)
+    }
+}
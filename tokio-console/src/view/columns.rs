@@ -0,0 +1,160 @@
+use crate::{
+    i18n::tr,
+    input,
+    view::{
+        self, bold,
+        controls::{ControlDisplay, KeyDisplay},
+        table::{TableList, TableListState},
+    },
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use std::cmp;
+
+/// The control for opening the column chooser.
+pub(crate) const COLUMN_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "choose columns",
+    keys: &[KeyDisplay {
+        base: "c",
+        utf8: None,
+    }],
+}];
+
+/// A popup for hiding, reordering and resizing a table's columns.
+///
+/// While the chooser is open, it receives all keyboard input.
+#[derive(Debug, Default)]
+pub(crate) struct ColumnChooser {
+    /// The index of the picked column in the table's columns.
+    selected: usize,
+}
+
+impl ColumnChooser {
+    /// Updates the columns of `table`, returning `true` if the chooser was
+    /// closed.
+    pub(crate) fn update_input<T: TableList<N>, const N: usize>(
+        &mut self,
+        event: input::Event,
+        table: &mut TableListState<T, N>,
+    ) -> bool {
+        use input::KeyCode::*;
+        let input::Event::Key(event) = event else {
+            return false;
+        };
+        let len = table.columns().len();
+        if len == 0 {
+            return true;
+        }
+        let selected = self.selected;
+        let shift = event.modifiers.contains(input::KeyModifiers::SHIFT);
+        match event.code {
+            Esc | Enter | Char('c') | Char('q') => return true,
+            // Holding shift, or using the uppercase key, moves the column
+            // rather than the selection.
+            Up | Char('K') if shift || event.code == Char('K') => {
+                let to = selected.saturating_sub(1);
+                table.move_column(selected, to);
+                self.selected = to;
+            }
+            Down | Char('J') if shift || event.code == Char('J') => {
+                let to = cmp::min(selected + 1, len - 1);
+                table.move_column(selected, to);
+                self.selected = to;
+            }
+            Up | Char('k') => self.selected = (selected + len - 1) % len,
+            Down | Char('j') => self.selected = (selected + 1) % len,
+            Char(' ') => table.toggle_column(selected),
+            Char('<') | Left => table.resize_column(selected, -1),
+            Char('>') | Right => table.resize_column(selected, 1),
+            Char('=') => table.reset_column_width(selected),
+            _ => {}
+        }
+        false
+    }
+
+    pub(crate) fn render<T: TableList<N>, const N: usize>(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        table: &TableListState<T, N>,
+    ) {
+        let mut lines = table
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let style = if idx == self.selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let shown = if column.hidden {
+                    styles.if_utf8("\u{2610}", "[ ]")
+                } else {
+                    styles.if_utf8("\u{2611}", "[x]")
+                };
+                let width = match column.width {
+                    Some(width) => format!(" ({width} wide)"),
+                    None => String::new(),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{shown} "), style),
+                    Span::styled(
+                        tr(&column.name).to_owned(),
+                        style.add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(width, style),
+                ])
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::from(vec![
+            bold(styles.if_utf8("\u{2191}\u{2193}", "up, down")),
+            Span::raw(" = pick, "),
+            bold("space"),
+            Span::raw(" = show/hide, "),
+            bold("J, K"),
+            Span::raw(" = move"),
+        ]));
+        lines.push(Line::from(vec![
+            bold("<, >"),
+            Span::raw(" = resize, "),
+            bold("="),
+            Span::raw(" = fit contents, "),
+            bold("esc"),
+            Span::raw(" = close"),
+        ]));
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Min(0),
+                    // add 2 for the top and bottom borders
+                    Constraint::Length(lines.len() as u16 + 2),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(frame.size());
+
+        let popup_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(60),
+                    Constraint::Percentage(20),
+                ]
+                .as_ref(),
+            )
+            .split(popup_layout[1])[1];
+
+        let chooser = Paragraph::new(lines).block(styles.border_block().title(tr("Columns")));
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(chooser, popup_area);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    input,
+    view::{
+        self, bold,
+        prompt::{Prompt, PromptInput},
+    },
+};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+/// A dialog for connecting to a new target, either by typing its address or
+/// by picking one of the named targets from the config file, or one of the
+/// targets which were discovered.
+pub(crate) struct ConnectDialog {
+    prompt: Prompt,
+    /// The named targets, as `(name, address)` pairs.
+    targets: Vec<(String, String)>,
+    /// The discovered targets, as `(name, address)` pairs, which are
+    /// connected to by address, since they aren't in the config file.
+    discovered: Vec<(String, String)>,
+    /// The index of the picked target in the targets matching the text, if
+    /// one has been picked.
+    selected: Option<usize>,
+}
+
+/// The outcome of [`ConnectDialog::update_input`].
+pub(crate) enum ConnectInput {
+    /// The dialog is still open.
+    Editing,
+    /// The user chose a target, either an address or a target name.
+    Connect(String),
+    /// The user closed the dialog without choosing a target.
+    Cancel,
+}
+
+impl ConnectDialog {
+    const TITLE: &'static str = "Connect to target";
+
+    pub(crate) fn new(targets: Vec<(String, String)>) -> Self {
+        Self {
+            prompt: Prompt::new(Self::TITLE, "").with_submit_action("connect"),
+            targets,
+            discovered: Vec::new(),
+            selected: None,
+        }
+    }
+
+    pub(crate) fn with_discovered(self, discovered: Vec<(String, String)>) -> Self {
+        Self { discovered, ..self }
+    }
+
+    /// Re-opens the dialog with a `target` which could not be connected to,
+    /// so that the user can correct it.
+    pub(crate) fn rejected(targets: Vec<(String, String)>, target: String, error: String) -> Self {
+        Self {
+            prompt: Prompt::new(Self::TITLE, target)
+                .with_submit_action("connect")
+                .with_error(error),
+            targets,
+            discovered: Vec::new(),
+            selected: None,
+        }
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event) -> ConnectInput {
+        use input::KeyCode::*;
+        let matching = self.matching().count();
+        if let input::Event::Key(key) = event {
+            match key.code {
+                Down if matching > 0 => {
+                    self.selected = Some(self.selected.map_or(0, |i| (i + 1) % matching));
+                    return ConnectInput::Editing;
+                }
+                Up if matching > 0 => {
+                    self.selected = Some(
+                        self.selected
+                            .map_or(matching - 1, |i| (i + matching - 1) % matching),
+                    );
+                    return ConnectInput::Editing;
+                }
+                Enter => {
+                    if let Some(choice) = self.selected.and_then(|i| self.matching().nth(i)) {
+                        return ConnectInput::Connect(choice.connect_to().to_owned());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match self.prompt.update_input(event) {
+            PromptInput::Editing => {
+                // The text may have changed, so the picked target may no
+                // longer match it.
+                self.selected = None;
+                ConnectInput::Editing
+            }
+            PromptInput::Submit(target) if target.is_empty() => ConnectInput::Cancel,
+            PromptInput::Submit(target) => ConnectInput::Connect(target),
+            PromptInput::Cancel => ConnectInput::Cancel,
+        }
+    }
+
+    /// Returns the named and discovered targets whose name or address
+    /// contains the text typed so far.
+    fn matching(&self) -> impl Iterator<Item = Choice<'_>> {
+        let text = self.prompt.text().trim();
+        let named = self
+            .targets
+            .iter()
+            .map(|(name, addr)| Choice::Named { name, addr });
+        let discovered = self
+            .discovered
+            .iter()
+            .map(|(name, addr)| Choice::Discovered { name, addr });
+        named
+            .chain(discovered)
+            .filter(move |choice| choice.name().contains(text) || choice.addr().contains(text))
+    }
+
+    pub(crate) fn render(&self, styles: &view::Styles, frame: &mut ratatui::terminal::Frame) {
+        let mut lines = Vec::new();
+        if !self.targets.is_empty() || !self.discovered.is_empty() {
+            lines.push(Line::from(vec![
+                bold("\u{2191}\u{2193}"),
+                Span::raw(" = pick a named or discovered target:"),
+            ]));
+        }
+        for (i, choice) in self.matching().enumerate() {
+            let (name, addr) = (choice.name(), choice.addr());
+            let style = if self.selected == Some(i) {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {name}"), style.add_modifier(Modifier::BOLD)),
+                Span::styled(format!(" ({addr})"), style),
+            ]));
+        }
+        self.prompt.render_with(styles, frame, lines);
+    }
+}
+
+/// A target which can be picked in the dialog.
+#[derive(Clone, Copy)]
+enum Choice<'a> {
+    Named { name: &'a str, addr: &'a str },
+    Discovered { name: &'a str, addr: &'a str },
+}
+
+impl<'a> Choice<'a> {
+    fn name(self) -> &'a str {
+        match self {
+            Self::Named { name, .. } | Self::Discovered { name, .. } => name,
+        }
+    }
+
+    fn addr(self) -> &'a str {
+        match self {
+            Self::Named { addr, .. } | Self::Discovered { addr, .. } => addr,
+        }
+    }
+
+    /// Returns what the console should connect to for this choice: a named
+    /// target's name, so that it's shown by name, or a discovered target's
+    /// address.
+    fn connect_to(self) -> &'a str {
+        match self {
+            Self::Named { name, .. } => name,
+            Self::Discovered { addr, .. } => addr,
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::view::{self, bold};
+use crate::{
+    i18n::tr,
+    keymap::{Key, Keymap},
+    view::{self, bold},
+};
 
 use ratatui::{
     layout,
@@ -15,6 +19,110 @@ const UNIVERSAL_CONTROLS: &[ControlDisplay] = &[
             utf8: None,
         }],
     },
+    ControlDisplay {
+        action: "step (when paused)",
+        keys: &[KeyDisplay {
+            base: "s",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "freeze display",
+        keys: &[
+            KeyDisplay {
+                base: "f",
+                utf8: None,
+            },
+            KeyDisplay {
+                base: "ctrl-space",
+                utf8: None,
+            },
+        ],
+    },
+    ControlDisplay {
+        action: "save screen",
+        keys: &[KeyDisplay {
+            base: "S",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "reconnect now",
+        keys: &[KeyDisplay {
+            base: "R",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "disconnect",
+        keys: &[KeyDisplay {
+            base: "D",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "connect to target",
+        keys: &[KeyDisplay {
+            base: "C",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "open tab",
+        keys: &[KeyDisplay {
+            base: "N",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "close tab",
+        keys: &[KeyDisplay {
+            base: "W",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "switch tab",
+        keys: &[KeyDisplay {
+            base: "1-9",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "toggle event log",
+        keys: &[KeyDisplay {
+            base: "V",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "filter event log by severity",
+        keys: &[KeyDisplay {
+            base: "!",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "clear completed tasks",
+        keys: &[KeyDisplay {
+            base: "X",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "keep completed tasks (configured/forever/never)",
+        keys: &[KeyDisplay {
+            base: "K",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "cycle color theme",
+        keys: &[KeyDisplay {
+            base: "M",
+            utf8: None,
+        }],
+    },
     ControlDisplay {
         action: "quit",
         keys: &[KeyDisplay {
@@ -33,7 +141,7 @@ pub(crate) struct Controls {
 
 impl Controls {
     pub(in crate::view) fn new(
-        view_controls: &'static [ControlDisplay],
+        view_controls: &[ControlDisplay],
         area: &layout::Rect,
         styles: &view::Styles,
     ) -> Self {
@@ -41,7 +149,10 @@ impl Controls {
         line_controls.extend(view_controls.iter().map(|c| c.to_spans(styles, 0)));
         line_controls.extend(UNIVERSAL_CONTROLS.iter().map(|c| c.to_spans(styles, 0)));
 
-        let mut lines = vec![Line::from(vec![Span::from("controls: ")])];
+        let mut lines = vec![Line::from(vec![Span::from(format!(
+            "{}: ",
+            tr("controls")
+        ))])];
         let mut current_line = lines.last_mut().expect("This vector is never empty");
         let separator = Span::from(", ");
 
@@ -101,16 +212,41 @@ impl Controls {
     }
 }
 
-pub(crate) fn controls_paragraph<'a>(
+/// Returns the lines of the help popup: every control available in the
+/// current view, followed by the keys which `keymap` binds.
+///
+/// A key which is bound to one of a control's keys is listed as another key
+/// for the control, so the list stays correct when keys are rebound.
+pub(crate) fn help_lines(
     view_controls: &[ControlDisplay],
     styles: &view::Styles,
-) -> Paragraph<'a> {
-    let mut spans = Vec::with_capacity(1 + view_controls.len() + UNIVERSAL_CONTROLS.len());
-    spans.push(Line::from(vec![Span::raw("controls:")]));
-    spans.extend(view_controls.iter().map(|c| c.to_spans(styles, 2)));
-    spans.extend(UNIVERSAL_CONTROLS.iter().map(|c| c.to_spans(styles, 2)));
+    keymap: &Keymap,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::with_capacity(3 + view_controls.len() + UNIVERSAL_CONTROLS.len());
+    lines.push(Line::from(vec![Span::raw(format!("{}:", tr("controls")))]));
+    for control in view_controls.iter().chain(UNIVERSAL_CONTROLS) {
+        let mut line = control.to_spans(styles, 2);
+        for key in control.bound_keys(keymap) {
+            line.spans.push(Span::from(format!(" {} ", tr("or"))));
+            line.spans.push(bold(key.to_string()));
+        }
+        lines.push(line);
+    }
 
-    Paragraph::new(spans)
+    let mut bindings = keymap.bindings().peekable();
+    if bindings.peek().is_some() {
+        lines.push(Line::default());
+        lines.push(Line::from(vec![Span::raw(format!("{}:", tr("keymap")))]));
+        lines.extend(bindings.map(|(key, to)| {
+            Line::from(vec![
+                Span::from("  "),
+                bold(key.to_string()),
+                Span::from(format!(" {} ", tr("acts as"))),
+                bold(to.to_string()),
+            ])
+        }));
+    }
+    lines
 }
 
 /// Construct span to display a control.
@@ -141,11 +277,11 @@ impl ControlDisplay {
         let mut spans = Vec::new();
 
         spans.push(Span::from(" ".repeat(indent)));
-        spans.push(Span::from(self.action));
+        spans.push(Span::from(tr(self.action)));
         spans.push(Span::from(" = "));
         for (idx, key_display) in self.keys.iter().enumerate() {
             if idx > 0 {
-                spans.push(Span::from(" or "))
+                spans.push(Span::from(format!(" {} ", tr("or"))))
             }
             spans.push(bold(match key_display.utf8 {
                 Some(utf8) => styles.if_utf8(utf8, key_display.base),
@@ -155,4 +291,25 @@ impl ControlDisplay {
 
         Line::from(spans)
     }
+
+    /// Returns the keys which `keymap` makes act as this control's keys,
+    /// other than the keys the control already lists.
+    ///
+    /// Keys which are described in a way that isn't the name of a single key,
+    /// such as `1-9`, are skipped.
+    fn bound_keys(&self, keymap: &Keymap) -> Vec<Key> {
+        let keys = self
+            .keys
+            .iter()
+            .flat_map(|key| key.base.split(", "))
+            .filter_map(|base| base.replace(' ', "").parse::<Key>().ok())
+            .collect::<Vec<_>>();
+        let mut bound = Vec::new();
+        for key in keys.iter().flat_map(|&key| keymap.keys_acting_as(key)) {
+            if !keys.contains(&key) && !bound.contains(&key) {
+                bound.push(key);
+            }
+        }
+        bound
+    }
 }
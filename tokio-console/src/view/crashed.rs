@@ -0,0 +1,156 @@
+use crate::{
+    i18n::tr,
+    input,
+    state::{tasks::Task, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout},
+    style::{self, Style},
+    widgets::{Cell, Row, Table, TableState},
+};
+use std::{borrow::Cow, cell::RefCell, rc::Rc};
+
+/// A list of all tasks which panicked, for finding out why they crashed.
+///
+/// Tasks which panicked are kept past the usual retention period, so this
+/// lists every task which has panicked since the console connected.
+#[derive(Debug, Default)]
+pub(crate) struct CrashedView {
+    table_state: TableState,
+}
+
+impl CrashedView {
+    const HEADER: &'static [&'static str] = &["ID", "Name", "Location", "Panicked At", "Message"];
+
+    pub(crate) fn update_input(&mut self, event: input::Event, state: &State) {
+        use input::KeyCode::*;
+        let len = state.tasks_state().crashed().len();
+        if len == 0 {
+            self.table_state.select(None);
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        if let input::Event::Key(event) = event {
+            let selected = match event.code {
+                Down | Char('j') => (selected + 1) % len,
+                Up | Char('k') => selected.checked_sub(1).unwrap_or(len - 1),
+                _ => return,
+            };
+            self.table_state.select(Some(selected));
+        }
+    }
+
+    /// Returns the currently selected crashed task.
+    pub(crate) fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        let selected = self.table_state.selected()?;
+        state.tasks_state().crashed().get(selected)?.upgrade()
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let tasks = state.tasks_state().crashed();
+        // Keep the selection in bounds, since crashed tasks are only removed
+        // when the console reconnects.
+        match self.table_state.selected() {
+            _ if tasks.is_empty() => self.table_state.select(None),
+            Some(selected) if selected >= tasks.len() => {
+                self.table_state.select(Some(tasks.len() - 1))
+            }
+            None => self.table_state.select(Some(0)),
+            Some(_) => {}
+        }
+
+        let rows = tasks.iter().filter_map(|task| {
+            let task = task.upgrade()?;
+            let task = task.borrow();
+            let panic = task.panic()?;
+            Some(Row::new(vec![
+                Cell::from(task.id_str().to_owned()),
+                Cell::from(task.name().unwrap_or_default().to_owned()),
+                Cell::from(task.location().to_owned()),
+                Cell::from(panic.location.clone()),
+                Cell::from(panic.message.clone().unwrap_or_default()),
+            ]))
+        });
+
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(controls.height()), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(20),
+                Constraint::Length(30),
+                Constraint::Length(30),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(Self::HEADER.iter().copied().map(tr))
+                .style(Style::default().add_modifier(style::Modifier::BOLD)),
+        )
+        .block(
+            styles
+                .border_block()
+                .title(vec![bold(format!("Crashed Tasks ({}) ", tasks.len()))]),
+        )
+        .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
+        .highlight_style(Style::default().add_modifier(style::Modifier::BOLD))
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl HelpText for CrashedView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "view details",
+            keys: &[KeyDisplay {
+                base: "enter",
+                utf8: Some("\u{21B5}"),
+            }],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc",
+                utf8: Some("\u{238B} esc"),
+            }],
+        },
+    ]
+}
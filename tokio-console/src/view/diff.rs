@@ -0,0 +1,462 @@
+use crate::{
+    i18n::{tr, tr_fmt},
+    input,
+    state::{tasks::Task, Id, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+        DUR_LEN, DUR_TABLE_PRECISION,
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout},
+    style::{self, Color, Style},
+    text::Span,
+    widgets::{Cell, Row, Table, TableState},
+};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// The controls for taking a snapshot of the tasks, and comparing them with
+/// it.
+pub(crate) const DIFF_CONTROLS: &[ControlDisplay] = &[
+    ControlDisplay {
+        action: "take snapshot",
+        keys: &[KeyDisplay {
+            base: "m",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "diff with snapshot",
+        keys: &[KeyDisplay {
+            base: "v",
+            utf8: None,
+        }],
+    },
+];
+
+/// Every task's stats at a point in time, which the diff view compares the
+/// tasks with, for hunting slow leaks and runaway tasks.
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    name: String,
+    taken_at: SystemTime,
+    tasks: HashMap<Id<Task>, Sample>,
+}
+
+/// A task's stats when a snapshot was taken.
+#[derive(Debug)]
+struct Sample {
+    id: String,
+    name: String,
+    location: String,
+    completed: bool,
+    busy: Duration,
+    polls: u64,
+    wakers: u64,
+}
+
+/// The tasks which changed since a snapshot was taken, with those which
+/// changed the most first.
+#[derive(Debug, Default)]
+pub(crate) struct DiffView {
+    table_state: TableState,
+    sort: DiffSort,
+    /// The task on each row, as of the last render.
+    rows: Vec<Id<Task>>,
+}
+
+/// Which change the diff is sorted by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum DiffSort {
+    #[default]
+    Busy,
+    Polls,
+    Wakers,
+}
+
+/// How a task changed since a snapshot was taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum ChangeKind {
+    /// The task was spawned after the snapshot.
+    New,
+    /// The task completed after the snapshot.
+    Ended,
+    /// The task has since been dropped from the console's state, so how much
+    /// it changed is unknown.
+    Gone,
+    /// The task was in the snapshot, and is still running.
+    Changed,
+}
+
+/// A row of the diff.
+#[derive(Debug)]
+struct Change {
+    id: Id<Task>,
+    id_str: String,
+    name: String,
+    location: String,
+    kind: ChangeKind,
+    busy: Duration,
+    polls: u64,
+    wakers: i64,
+}
+
+// === impl Snapshot ===
+
+impl Snapshot {
+    /// Takes a snapshot named `name` of every task in `state`.
+    pub(crate) fn take(name: String, state: &State) -> Self {
+        let taken_at = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let tasks = state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+            .map(|task| {
+                let task = task.borrow();
+                let sample = Sample {
+                    id: task.id_str().to_owned(),
+                    name: task.name().unwrap_or_else(|| task.short_desc()).to_owned(),
+                    location: task.location().to_owned(),
+                    completed: task.is_completed(),
+                    busy: task.busy(taken_at),
+                    polls: task.total_polls(),
+                    wakers: task.waker_count(),
+                };
+                (task.id(), sample)
+            })
+            .collect();
+        Self {
+            name,
+            taken_at,
+            tasks,
+        }
+    }
+
+    /// Returns how each task in `state` or the snapshot has changed since
+    /// the snapshot was taken, leaving out tasks which haven't.
+    fn changes(&self, state: &State) -> Vec<Change> {
+        let now = state.last_updated_at().unwrap_or(self.taken_at);
+        let mut changes = Vec::new();
+        let mut seen = HashSet::new();
+        for task in state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+        {
+            let task = task.borrow();
+            let (busy, polls, wakers) = (task.busy(now), task.total_polls(), task.waker_count());
+            let change = match self.tasks.get(&task.id()) {
+                Some(sample) => {
+                    seen.insert(task.id());
+                    let kind = if task.is_completed() && !sample.completed {
+                        ChangeKind::Ended
+                    } else {
+                        ChangeKind::Changed
+                    };
+                    Change {
+                        kind,
+                        busy: busy.saturating_sub(sample.busy),
+                        polls: polls.saturating_sub(sample.polls),
+                        wakers: wakers as i64 - sample.wakers as i64,
+                        ..Change::new(&task)
+                    }
+                }
+                None => Change {
+                    kind: ChangeKind::New,
+                    busy,
+                    polls,
+                    wakers: wakers as i64,
+                    ..Change::new(&task)
+                },
+            };
+            if change.kind != ChangeKind::Changed || !change.is_unchanged() {
+                changes.push(change);
+            }
+        }
+        changes.extend(
+            self.tasks
+                .iter()
+                .filter(|(id, _)| !seen.contains(id))
+                // Tasks which had completed are dropped as they're meant to
+                // be, so only those which were running are worth showing.
+                .filter(|(_, sample)| !sample.completed)
+                .map(|(&id, sample)| Change {
+                    id,
+                    id_str: sample.id.clone(),
+                    name: sample.name.clone(),
+                    location: sample.location.clone(),
+                    kind: ChangeKind::Gone,
+                    busy: Duration::ZERO,
+                    polls: 0,
+                    wakers: 0,
+                }),
+        );
+        changes
+    }
+}
+
+// === impl DiffView ===
+
+impl DiffView {
+    const HEADER: &'static [&'static str] = &[
+        "ID", "Change", "Busy", "Polls", "Wakers", "Name", "Location",
+    ];
+
+    pub(crate) fn update_input(&mut self, event: input::Event) {
+        use input::KeyCode::*;
+        let input::Event::Key(event) = event else {
+            return;
+        };
+        match event.code {
+            Left | Char('h') => self.sort = self.sort.prev(),
+            Right | Char('l') => self.sort = self.sort.next(),
+            _ => {}
+        }
+        let len = self.rows.len();
+        if len == 0 {
+            self.table_state.select(None);
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        match event.code {
+            Down | Char('j') => self.table_state.select(Some((selected + 1) % len)),
+            Up | Char('k') => {
+                self.table_state
+                    .select(Some(selected.checked_sub(1).unwrap_or(len - 1)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the currently selected task, if it's still in the state.
+    pub(crate) fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        let id = *self.rows.get(self.table_state.selected()?)?;
+        state.tasks_state().task(id)?.upgrade()
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+        snapshot: Option<&Snapshot>,
+    ) {
+        let mut changes = snapshot
+            .map(|snapshot| snapshot.changes(state))
+            .unwrap_or_default();
+        let sort = self.sort;
+        changes.sort_by(|a, b| {
+            sort.cmp(b, a)
+                .then_with(|| a.kind.cmp(&b.kind))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        self.rows = changes.iter().map(|change| change.id).collect();
+
+        match self.table_state.selected() {
+            _ if self.rows.is_empty() => self.table_state.select(None),
+            Some(selected) if selected >= self.rows.len() => {
+                self.table_state.select(Some(self.rows.len() - 1))
+            }
+            None => self.table_state.select(Some(0)),
+            Some(_) => {}
+        }
+
+        let rows = changes.into_iter().map(|change| {
+            let (label, style) = match change.kind {
+                ChangeKind::New => ("new", styles.fg(Color::Green)),
+                ChangeKind::Ended => ("ended", styles.terminated()),
+                ChangeKind::Gone => ("gone", styles.terminated()),
+                ChangeKind::Changed => ("", Style::default()),
+            };
+            let (busy, polls, wakers) = if change.kind == ChangeKind::Gone {
+                (Cell::from(""), Cell::from(""), Cell::from(""))
+            } else {
+                (
+                    Cell::from(styles.time_units(change.busy, DUR_TABLE_PRECISION, Some(DUR_LEN))),
+                    Cell::from(change.polls.to_string()),
+                    Cell::from(format!("{:+}", change.wakers)),
+                )
+            };
+            Row::new(vec![
+                Cell::from(change.id_str),
+                Cell::from(Span::styled(tr(label).to_owned(), style)),
+                busy,
+                polls,
+                wakers,
+                Cell::from(change.name),
+                Cell::from(change.location),
+            ])
+        });
+
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(controls.height()), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let title = match snapshot {
+            Some(snapshot) => {
+                let age = state
+                    .last_updated_at()
+                    .and_then(|now| now.duration_since(snapshot.taken_at).ok())
+                    .unwrap_or_default();
+                tr_fmt(
+                    "Changes since {} ({}s ago) ({}) ",
+                    &[&snapshot.name, &age.as_secs(), &self.rows.len()],
+                )
+            }
+            None => tr("No snapshot taken, press m to take one ").to_owned(),
+        };
+        let sort_col = match sort {
+            DiffSort::Busy => 2,
+            DiffSort::Polls => 3,
+            DiffSort::Wakers => 4,
+        };
+        let header = Self::HEADER.iter().enumerate().map(|(idx, &name)| {
+            if idx == sort_col {
+                Cell::from(styles.descending(tr(name)))
+            } else {
+                Cell::from(tr(name))
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(7),
+                Constraint::Length(DUR_LEN as u16),
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Length(24),
+                Constraint::Min(10),
+            ],
+        )
+        .header(Row::new(header).style(Style::default().add_modifier(style::Modifier::BOLD)))
+        .block(styles.border_block().title(vec![bold(title)]))
+        .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
+        .highlight_style(Style::default().add_modifier(style::Modifier::BOLD))
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl HelpText for DiffView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+// === impl DiffSort ===
+
+impl DiffSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Busy => Self::Polls,
+            Self::Polls => Self::Wakers,
+            Self::Wakers => Self::Busy,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::Busy => Self::Wakers,
+            Self::Polls => Self::Busy,
+            Self::Wakers => Self::Polls,
+        }
+    }
+
+    /// Compares how much `a` and `b` changed.
+    fn cmp(self, a: &Change, b: &Change) -> cmp::Ordering {
+        match self {
+            Self::Busy => a.busy.cmp(&b.busy),
+            Self::Polls => a.polls.cmp(&b.polls),
+            // Wakers leaking and wakers being dropped are both worth seeing.
+            Self::Wakers => a.wakers.unsigned_abs().cmp(&b.wakers.unsigned_abs()),
+        }
+    }
+}
+
+// === impl Change ===
+
+impl Change {
+    fn new(task: &Task) -> Self {
+        Self {
+            id: task.id(),
+            id_str: task.id_str().to_owned(),
+            name: task.name().unwrap_or_else(|| task.short_desc()).to_owned(),
+            location: task.location().to_owned(),
+            kind: ChangeKind::Changed,
+            busy: Duration::ZERO,
+            polls: 0,
+            wakers: 0,
+        }
+    }
+
+    fn is_unchanged(&self) -> bool {
+        self.busy.is_zero() && self.polls == 0 && self.wakers == 0
+    }
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "sort by busy/polls/wakers",
+            keys: &[
+                KeyDisplay {
+                    base: "left, right",
+                    utf8: Some("\u{2190}\u{2192}"),
+                },
+                KeyDisplay {
+                    base: "h, l",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "take snapshot",
+            keys: &[KeyDisplay {
+                base: "m",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "view details",
+            keys: &[KeyDisplay {
+                base: "enter",
+                utf8: Some("\u{21B5}"),
+            }],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc",
+                utf8: Some("\u{238B} esc"),
+            }],
+        },
+    ]
+}
@@ -0,0 +1,106 @@
+use crate::{
+    i18n::tr,
+    state::{
+        histogram::DurationHistogram,
+        tasks::{StatsWindow, Task},
+    },
+    view::{
+        self,
+        controls::{ControlDisplay, KeyDisplay},
+        durations::Durations,
+    },
+};
+use ratatui::layout::{self, Layout};
+use std::{cmp, time::SystemTime};
+
+/// The control for showing how the task list's poll and scheduled times are
+/// distributed.
+pub(crate) const DISTRIBUTION_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "toggle time distributions",
+    keys: &[KeyDisplay {
+        base: "d",
+        utf8: None,
+    }],
+}];
+
+/// How the mean poll times and mean scheduled times of a set of tasks are
+/// distributed, so that outliers among hundreds of tasks can be spotted
+/// without opening each of them.
+pub(crate) struct Distributions {
+    poll_times: DurationHistogram,
+    scheduled_times: DurationHistogram,
+    /// The number of tasks which have been polled, and are in the histograms.
+    tasks: u64,
+}
+
+impl Distributions {
+    /// The height of the pane, including its borders.
+    pub(crate) const HEIGHT: u16 = 10;
+
+    /// Returns the distributions of `tasks`' stats over `window`, as of
+    /// `now`.
+    ///
+    /// Tasks which haven't been polled in the window are left out, since
+    /// they don't have a mean poll time.
+    pub(crate) fn new<'a>(
+        tasks: impl IntoIterator<Item = &'a Task>,
+        window: StatsWindow,
+        now: SystemTime,
+    ) -> Self {
+        let stats = tasks
+            .into_iter()
+            .map(|task| task.windowed(window, now))
+            .filter(|stats| stats.polls > 0)
+            .collect::<Vec<_>>();
+        let mean = |total: std::time::Duration, polls: u64| {
+            total / u32::try_from(polls).unwrap_or(u32::MAX)
+        };
+        Self {
+            poll_times: DurationHistogram::from_durations(
+                stats.iter().map(|stats| mean(stats.busy, stats.polls)),
+            ),
+            scheduled_times: DurationHistogram::from_durations(
+                stats.iter().map(|stats| mean(stats.scheduled, stats.polls)),
+            ),
+            tasks: stats.len() as u64,
+        }
+    }
+
+    pub(crate) fn render(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+    ) {
+        let tasks = self.tasks;
+        let poll_title = format!("{} ({tasks})", tr("Mean Poll Times"));
+        let scheduled_title = format!("{} ({tasks})", tr("Mean Sched Times"));
+        // extra 2 characters for the border
+        let percentiles_width = cmp::max(poll_title.len(), scheduled_title.len()) as u16 + 2;
+        let (poll_times, scheduled_times) = if tasks > 0 {
+            (Some(&self.poll_times), Some(&self.scheduled_times))
+        } else {
+            (None, None)
+        };
+
+        let areas = Layout::default()
+            .direction(layout::Direction::Horizontal)
+            .constraints([
+                layout::Constraint::Percentage(50),
+                layout::Constraint::Percentage(50),
+            ])
+            .split(area);
+        let poll_times = Durations::new(styles)
+            .histogram(poll_times)
+            .percentiles_title(&poll_title)
+            .histogram_title(tr("Poll Times Histogram"))
+            .percentiles_width(percentiles_width);
+        let scheduled_times = Durations::new(styles)
+            .histogram(scheduled_times)
+            .percentiles_title(&scheduled_title)
+            .histogram_title(tr("Scheduled Times Histogram"))
+            .percentiles_width(percentiles_width);
+        frame.render_widget(poll_times, areas[0]);
+        frame.render_widget(scheduled_times, areas[1]);
+    }
+}
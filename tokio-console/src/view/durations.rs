@@ -2,10 +2,12 @@ use std::cmp;
 
 use ratatui::{
     layout::{self},
+    style::Color,
     widgets::Widget,
 };
 
 use crate::{
+    slo::SloStatus,
     state::histogram::DurationHistogram,
     view::{self, mini_histogram::MiniHistogram, percentiles::Percentiles},
 };
@@ -38,6 +40,8 @@ pub(crate) struct Durations<'a> {
     histogram_title: &'a str,
     /// Fixed width for percentiles block
     percentiles_width: u16,
+    /// How the histogram measures up to the latency objectives for it
+    slos: &'a [SloStatus<'a>],
 }
 
 impl Widget for Durations<'_> {
@@ -76,13 +80,17 @@ impl Widget for Durations<'_> {
 
         let percentiles_widget = Percentiles::new(self.styles)
             .title(self.percentiles_title)
-            .histogram(self.histogram);
+            .histogram(self.histogram)
+            .slos(self.slos);
         percentiles_widget.render(percentiles_area, buf);
 
         if let Some(histogram_area) = histogram_area {
             let histogram_widget = MiniHistogram::default()
                 .block(self.styles.border_block().title(self.histogram_title))
                 .histogram(self.histogram)
+                .thresholds(self.slos.iter().map(|status| status.slo.threshold()))
+                .threshold_style(self.styles.fg(Color::Red))
+                .graphics(self.styles.graphics())
                 .duration_precision(2);
             histogram_widget.render(histogram_area, buf);
         }
@@ -97,6 +105,7 @@ impl<'a> Durations<'a> {
             percentiles_title: "Percentiles",
             histogram_title: "Histogram",
             percentiles_width: 0,
+            slos: &[],
         }
     }
 
@@ -115,6 +124,11 @@ impl<'a> Durations<'a> {
         self
     }
 
+    pub(crate) fn slos(mut self, slos: &'a [SloStatus<'a>]) -> Self {
+        self.slos = slos;
+        self
+    }
+
     pub(crate) fn percentiles_width(mut self, width: u16) -> Self {
         self.percentiles_width = width;
         self
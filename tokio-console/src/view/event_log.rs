@@ -0,0 +1,70 @@
+use crate::{
+    event_log::{EventLog, Severity},
+    i18n::tr_fmt,
+    view,
+};
+use ratatui::{
+    layout,
+    style::Color,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+/// The pane below every view which shows the newest entries of the event
+/// log, so that tasks and resources which came and went between two redraws
+/// leave a trace.
+pub(crate) struct EventLogPane<'a> {
+    log: &'a EventLog,
+    /// The least severe entries which are shown.
+    severity: Severity,
+}
+
+impl<'a> EventLogPane<'a> {
+    /// The height of the pane, including its borders.
+    pub(crate) const HEIGHT: u16 = 8;
+
+    pub(crate) fn new(log: &'a EventLog, severity: Severity) -> Self {
+        Self { log, severity }
+    }
+
+    pub(crate) fn render(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+    ) {
+        // The newest entries are at the bottom, as in a terminal.
+        let visible = usize::from(area.height.saturating_sub(2));
+        let mut lines = self
+            .log
+            .entries(self.severity)
+            .rev()
+            .take(visible)
+            .map(|entry| {
+                let (label, color) = match entry.severity {
+                    Severity::Debug => ("DEBUG", Color::Blue),
+                    Severity::Info => ("INFO ", Color::Green),
+                    Severity::Warn => ("WARN ", Color::Yellow),
+                };
+                // The date is left out, since the log only covers the
+                // session.
+                let at = humantime::format_rfc3339_seconds(entry.at).to_string();
+                Line::from(vec![
+                    Span::styled(at[11..19].to_owned(), styles.fg(Color::DarkGray)),
+                    Span::raw(" "),
+                    Span::styled(label, styles.fg(color)),
+                    Span::raw(" "),
+                    Span::raw(entry.message.clone()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        lines.reverse();
+
+        let title = tr_fmt(
+            "Events ({} and above, ! to filter)",
+            &[&self.severity.label()],
+        );
+        let paragraph = Paragraph::new(lines).block(styles.border_block().title(title));
+        frame.render_widget(paragraph, area);
+    }
+}
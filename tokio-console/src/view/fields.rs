@@ -0,0 +1,135 @@
+use crate::{
+    input,
+    state::tasks::Task,
+    view::{
+        self, bold,
+        controls::{ControlDisplay, KeyDisplay},
+    },
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+};
+
+/// The controls for reading fields which don't fit in the task list's
+/// `Fields` column.
+pub(crate) const FIELDS_CONTROLS: &[ControlDisplay] = &[
+    ControlDisplay {
+        action: "scroll fields left/right",
+        keys: &[KeyDisplay {
+            base: "[, ]",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "show all fields",
+        keys: &[KeyDisplay {
+            base: "u",
+            utf8: None,
+        }],
+    },
+];
+
+/// How many characters the `Fields` column is scrolled by at a time.
+pub(crate) const SCROLL_STEP: usize = 8;
+
+/// A popup showing each of a task's fields in full, on lines of their own.
+///
+/// While the popup is open, it receives all keyboard input.
+#[derive(Debug)]
+pub(crate) struct FieldsPopup {
+    title: String,
+    lines: Vec<Line<'static>>,
+    /// How many lines the popup is scrolled down by.
+    scroll: u16,
+}
+
+impl FieldsPopup {
+    pub(crate) fn new(task: &Task) -> Self {
+        let lines = task
+            .formatted_fields()
+            .iter()
+            .map(|field| {
+                // Fields are separated by a space after their values, which
+                // isn't needed on lines of their own.
+                let mut spans = field.clone();
+                if let Some(last) = spans.last_mut() {
+                    let content = last.content.trim_end().to_owned();
+                    *last = Span::styled(content, last.style);
+                }
+                Line::from(spans)
+            })
+            .collect();
+        Self {
+            title: format!("Fields of task {}", task.short_desc()),
+            lines,
+            scroll: 0,
+        }
+    }
+
+    /// Scrolls the popup, returning `true` if it was closed.
+    pub(crate) fn update_input(&mut self, event: input::Event) -> bool {
+        use input::KeyCode::*;
+        let input::Event::Key(event) = event else {
+            return false;
+        };
+        let last = u16::try_from(self.lines.len().saturating_sub(1)).unwrap_or(u16::MAX);
+        match event.code {
+            Esc | Enter | Char('u') | Char('q') => return true,
+            Up | Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            Down | Char('j') => self.scroll = self.scroll.saturating_add(1).min(last),
+            Home => self.scroll = 0,
+            End => self.scroll = last,
+            _ => {}
+        }
+        false
+    }
+
+    pub(crate) fn render(&self, styles: &view::Styles, frame: &mut ratatui::terminal::Frame) {
+        let mut lines = if self.lines.is_empty() {
+            vec![Line::from("this task has no fields")]
+        } else {
+            self.lines.clone()
+        };
+        lines.push(Line::default());
+        lines.push(Line::from(vec![
+            bold(styles.if_utf8("\u{2191}\u{2193}", "up, down")),
+            Span::raw(" = scroll, "),
+            bold("esc"),
+            Span::raw(" = close"),
+        ]));
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Min(0),
+                    // add 2 for the top and bottom borders
+                    Constraint::Length(lines.len() as u16 + 2),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(frame.size());
+
+        let popup_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(70),
+                    Constraint::Percentage(15),
+                ]
+                .as_ref(),
+            )
+            .split(popup_layout[1])[1];
+
+        let fields = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(styles.border_block().title(self.title.clone()));
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(fields, popup_area);
+    }
+}
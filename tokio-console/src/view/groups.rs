@@ -0,0 +1,365 @@
+use crate::{
+    i18n::tr,
+    input,
+    state::{tasks::Task, Id, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+        DUR_LEN, DUR_TABLE_PRECISION,
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout},
+    style::{self, Style},
+    widgets::{Cell, Row, Table, TableState},
+};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// The control for switching from the task list to the task groups.
+pub(crate) const GROUP_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "group tasks (name/target/location)",
+    keys: &[KeyDisplay {
+        base: "g",
+        utf8: None,
+    }],
+}];
+
+/// The tasks, collapsed into one row for each name, target or spawn
+/// location, for programs which spawn many identical tasks.
+///
+/// Each group's row shows how many tasks are in it, their total busy time,
+/// their mean poll time and the longest any of them has been idle.
+#[derive(Debug, Default)]
+pub(crate) struct TaskGroupsView {
+    group_by: GroupBy,
+    table_state: TableState,
+    /// The groups whose tasks are listed under them.
+    expanded: HashSet<String>,
+    /// What's on each row, as of the last render.
+    rows: Vec<GroupRow>,
+}
+
+/// What tasks are grouped by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum GroupBy {
+    #[default]
+    Name,
+    Target,
+    Location,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GroupRow {
+    Group(String),
+    Task(Id<Task>),
+}
+
+/// The tasks in a group, and their totals.
+struct Group {
+    key: String,
+    tasks: Vec<Rc<RefCell<Task>>>,
+    busy: Duration,
+    polls: u64,
+    max_idle: Duration,
+}
+
+impl TaskGroupsView {
+    const HEADER: &'static [&'static str] = &["Group", "Tasks", "Busy", "Mean Poll", "Max Idle"];
+
+    /// Groups the tasks by the next way of grouping them, or returns `false`
+    /// if they've been grouped every way, to return to the task list.
+    pub(crate) fn next_group_by(&mut self) -> bool {
+        let next = match self.group_by {
+            GroupBy::Name => GroupBy::Target,
+            GroupBy::Target => GroupBy::Location,
+            GroupBy::Location => return false,
+        };
+        *self = Self {
+            group_by: next,
+            ..Self::default()
+        };
+        true
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event) {
+        use input::KeyCode::*;
+        let len = self.rows.len();
+        if len == 0 {
+            self.table_state.select(None);
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        let input::Event::Key(event) = event else {
+            return;
+        };
+        match event.code {
+            Down | Char('j') => self.table_state.select(Some((selected + 1) % len)),
+            Up | Char('k') => {
+                self.table_state
+                    .select(Some(selected.checked_sub(1).unwrap_or(len - 1)));
+            }
+            Enter => {
+                if let Some(GroupRow::Group(key)) = self.rows.get(selected) {
+                    if !self.expanded.remove(key) {
+                        self.expanded.insert(key.clone());
+                    }
+                }
+            }
+            Left | Char('h') => {
+                // Collapsing from one of a group's tasks selects the group.
+                let group = self.rows[..=selected]
+                    .iter()
+                    .rposition(|row| matches!(row, GroupRow::Group(_)));
+                if let Some(group) = group {
+                    if let GroupRow::Group(ref key) = self.rows[group] {
+                        self.expanded.remove(key);
+                    }
+                    self.table_state.select(Some(group));
+                }
+            }
+            Right | Char('l') => {
+                if let Some(GroupRow::Group(key)) = self.rows.get(selected) {
+                    self.expanded.insert(key.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the selected task, if a task rather than a group is selected.
+    pub(crate) fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        match self.rows.get(self.table_state.selected()?)? {
+            GroupRow::Task(id) => state.tasks_state().task(*id)?.upgrade(),
+            GroupRow::Group(_) => None,
+        }
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let groups = groups(state, self.group_by, now);
+
+        let dur_cell = |dur| Cell::from(styles.time_units(dur, DUR_TABLE_PRECISION, Some(DUR_LEN)));
+        let mean_poll = |busy: Duration, polls: u64| match polls {
+            0 => Cell::from(""),
+            polls => dur_cell(busy / u32::try_from(polls).unwrap_or(u32::MAX)),
+        };
+        let mut rows = Vec::new();
+        let mut table_rows = Vec::new();
+        let mut group_width = Self::HEADER[0].len();
+        for group in &groups {
+            let expanded = self.expanded.contains(&group.key);
+            let marker = if expanded {
+                styles.if_utf8("▾ ", "- ")
+            } else {
+                styles.if_utf8("▸ ", "+ ")
+            };
+            let name = format!("{marker}{}", group.key);
+            group_width = group_width.max(name.chars().count());
+            rows.push(GroupRow::Group(group.key.clone()));
+            table_rows.push(Row::new(vec![
+                Cell::from(name),
+                Cell::from(group.tasks.len().to_string()),
+                dur_cell(group.busy),
+                mean_poll(group.busy, group.polls),
+                dur_cell(group.max_idle),
+            ]));
+            if !expanded {
+                continue;
+            }
+            for task in &group.tasks {
+                let task = task.borrow();
+                let busy = task.busy(now);
+                let name = format!("    {}", task.short_desc());
+                group_width = group_width.max(name.chars().count());
+                rows.push(GroupRow::Task(task.id()));
+                let row = Row::new(vec![
+                    Cell::from(name),
+                    Cell::from(""),
+                    dur_cell(busy),
+                    mean_poll(busy, task.total_polls()),
+                    dur_cell(task.idle(now)),
+                ]);
+                table_rows.push(if task.is_completed() {
+                    row.style(styles.terminated())
+                } else {
+                    row
+                });
+            }
+        }
+
+        // Keep the selection on the same row when the groups are reordered
+        // as their busy times change.
+        let selected = self
+            .table_state
+            .selected()
+            .and_then(|selected| self.rows.get(selected))
+            .and_then(|row| rows.iter().position(|r| r == row))
+            .or_else(|| self.table_state.selected().map(|s| s.min(rows.len())));
+        self.rows = rows;
+        match selected {
+            _ if self.rows.is_empty() => self.table_state.select(None),
+            Some(selected) if selected >= self.rows.len() => {
+                self.table_state.select(Some(self.rows.len() - 1))
+            }
+            None => self.table_state.select(Some(0)),
+            Some(selected) => self.table_state.select(Some(selected)),
+        }
+
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(controls.height()), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Length(group_width as u16),
+                Constraint::Length(6),
+                Constraint::Length(DUR_LEN as u16),
+                Constraint::Length(Self::HEADER[3].len().max(DUR_LEN) as u16),
+                Constraint::Length(Self::HEADER[4].len().max(DUR_LEN) as u16),
+            ],
+        )
+        .header(
+            Row::new(Self::HEADER.iter().copied().map(tr))
+                .style(Style::default().add_modifier(style::Modifier::BOLD)),
+        )
+        .block(styles.border_block().title(vec![bold(format!(
+            "Task Groups by {} ({}) ",
+            self.group_by.describe(),
+            groups.len()
+        ))]))
+        .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
+        .highlight_style(Style::default().add_modifier(style::Modifier::BOLD))
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl HelpText for TaskGroupsView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+impl GroupBy {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Target => "target",
+            Self::Location => "location",
+        }
+    }
+
+    fn key(self, task: &Task) -> String {
+        match self {
+            Self::Name => task.name().unwrap_or("<unnamed>").to_owned(),
+            Self::Target => task.target().to_owned(),
+            Self::Location => task.location().to_owned(),
+        }
+    }
+}
+
+/// Returns the tasks grouped by `group_by`, with the totals of each group as
+/// of `now`, with the busiest groups first.
+fn groups(state: &State, group_by: GroupBy, now: SystemTime) -> Vec<Group> {
+    let mut groups = BTreeMap::<String, Group>::new();
+    for task in state
+        .tasks_state()
+        .tasks()
+        .filter_map(|task| task.upgrade())
+    {
+        let key = group_by.key(&task.borrow());
+        let group = groups.entry(key.clone()).or_insert_with(|| Group {
+            key,
+            tasks: Vec::new(),
+            busy: Duration::ZERO,
+            polls: 0,
+            max_idle: Duration::ZERO,
+        });
+        {
+            let task = task.borrow();
+            group.busy += task.busy(now);
+            group.polls += task.total_polls();
+            group.max_idle = group.max_idle.max(task.idle(now));
+        }
+        group.tasks.push(task);
+    }
+    let mut groups = groups.into_values().collect::<Vec<_>>();
+    // The sort is stable, so groups which are equally busy stay in the
+    // order of their keys.
+    groups.sort_by_key(|group| Reverse(group.busy));
+    for group in &mut groups {
+        group.tasks.sort_by_key(|task| task.borrow().id());
+    }
+    groups
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "expand/collapse group",
+            keys: &[
+                KeyDisplay {
+                    base: "enter",
+                    utf8: Some("\u{21B5}"),
+                },
+                KeyDisplay {
+                    base: "left, right",
+                    utf8: Some("\u{2190}\u{2192}"),
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "view details",
+            keys: &[KeyDisplay {
+                base: "enter",
+                utf8: Some("\u{21B5}"),
+            }],
+        },
+        ControlDisplay {
+            action: "group tasks (name/target/location)",
+            keys: &[KeyDisplay {
+                base: "g",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc",
+                utf8: Some("\u{238B} esc"),
+            }],
+        },
+    ]
+}
@@ -1,38 +1,48 @@
+use std::borrow::Cow;
+
 use ratatui::{
-    layout::{self, Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout},
+    text::Line,
     widgets::{Clear, Paragraph},
 };
 
-use crate::{state::State, view};
+use crate::{
+    keymap::Keymap,
+    view::{
+        self,
+        controls::{help_lines, ControlDisplay},
+    },
+};
 
 pub(crate) trait HelpText {
-    fn render_help_content(&self, styles: &view::Styles) -> Paragraph<'static>;
+    /// Returns the controls which are available in the view, besides the
+    /// controls available in every view.
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]>;
 }
 
 /// Simple view for help popup
-pub(crate) struct HelpView<'a> {
-    help_text: Option<Paragraph<'a>>,
+pub(crate) struct HelpView {
+    lines: Vec<Line<'static>>,
 }
 
-impl<'a> HelpView<'a> {
-    pub(super) fn new(help_text: Paragraph<'a>) -> Self {
+impl HelpView {
+    pub(super) fn new(help_text: &dyn HelpText, styles: &view::Styles, keymap: &Keymap) -> Self {
         HelpView {
-            help_text: Some(help_text),
+            lines: help_lines(&help_text.help_controls(), styles, keymap),
         }
     }
 
+    /// Renders the popup, scrolled down by `scroll` lines.
+    ///
+    /// `scroll` is clamped so that the last line is at the bottom of the
+    /// popup at the furthest, since how many lines fit is only known here.
     pub(crate) fn render(
-        &mut self,
+        self,
         styles: &view::Styles,
         frame: &mut ratatui::terminal::Frame,
-        _area: layout::Rect,
-        _state: &mut State,
+        scroll: &mut u16,
     ) {
         let r = frame.size();
-        let content = self
-            .help_text
-            .take()
-            .expect("help_text should be initialized");
 
         let popup_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -58,7 +68,23 @@ impl<'a> HelpView<'a> {
             )
             .split(popup_layout[1])[1];
 
-        let display_text = content.block(styles.border_block().title("Help"));
+        let visible = popup_area.height.saturating_sub(2);
+        let hidden = u16::try_from(self.lines.len())
+            .unwrap_or(u16::MAX)
+            .saturating_sub(visible);
+        *scroll = (*scroll).min(hidden);
+        let title = if hidden > 0 {
+            format!(
+                "Help ({} to scroll)",
+                styles.if_utf8("\u{2191}\u{2193}", "up, down")
+            )
+        } else {
+            "Help".to_owned()
+        };
+
+        let display_text = Paragraph::new(self.lines)
+            .scroll((*scroll, 0))
+            .block(styles.border_block().title(title));
 
         // Clear the help block area and render the popup
         frame.render_widget(Clear, popup_area);
@@ -0,0 +1,386 @@
+use crate::{
+    input,
+    state::{tasks::Lifetime, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+/// A chart of when each task was spawned and completed, as a bar for each
+/// task, grouped by where the tasks were spawned.
+///
+/// Tasks from the same location which never ran at the same time share a
+/// row, so a location with many rows is spawning tasks which run in
+/// parallel, and one with a single row of bars is running them one after
+/// another.
+#[derive(Debug)]
+pub(crate) struct LifetimesView {
+    /// The time being inspected, or `None` to follow the latest update.
+    cursor: Option<SystemTime>,
+    /// How far the chart is zoomed in, as a power of two of the whole session.
+    zoom: u32,
+    /// The first row of the chart which is shown.
+    scroll: usize,
+    /// How much time each column covered when the chart was last drawn, which
+    /// the cursor moves by.
+    column: Duration,
+}
+
+/// A row of the chart, holding tasks spawned at the same location which
+/// never overlap.
+struct Lane<'a> {
+    /// The location the tasks were spawned at, if this is its first row.
+    location: Option<&'a str>,
+    tasks: Vec<&'a Lifetime>,
+}
+
+impl Default for LifetimesView {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            zoom: 0,
+            scroll: 0,
+            column: Duration::from_secs(1),
+        }
+    }
+}
+
+impl LifetimesView {
+    /// The furthest the chart can be zoomed in.
+    const MAX_ZOOM: u32 = 16;
+
+    pub(crate) fn update_input(&mut self, event: input::Event, state: &State) {
+        use input::KeyCode::*;
+        let input::Event::Key(event) = event else {
+            return;
+        };
+        let latest = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        match event.code {
+            Left | Char('h') => {
+                let cursor = self.cursor.unwrap_or(latest);
+                self.cursor = Some(cursor.checked_sub(self.column).unwrap_or(cursor));
+            }
+            Right | Char('l') => {
+                self.cursor = self
+                    .cursor
+                    .map(|cursor| cursor + self.column)
+                    .filter(|&cursor| cursor < latest);
+            }
+            Char('+') | Char('=') => self.zoom = (self.zoom + 1).min(Self::MAX_ZOOM),
+            Char('-') => self.zoom = self.zoom.saturating_sub(1),
+            Down | Char('j') => self.scroll += 1,
+            Up | Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let lifetimes = state.tasks_state().lifetimes();
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(controls.height()),
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        frame.render_widget(controls.into_widget(), chunks[0]);
+
+        let block = styles
+            .border_block()
+            .title(vec![bold(format!("Task Lifetimes ({}) ", lifetimes.len()))]);
+        let inner = block.inner(chunks[2]);
+        frame.render_widget(block, chunks[2]);
+
+        let Some(start) = lifetimes.first().map(|lifetime| lifetime.created_at) else {
+            frame.render_widget(Paragraph::new("No tasks yet"), inner);
+            return;
+        };
+        let end = state
+            .last_updated_at()
+            .unwrap_or_else(SystemTime::now)
+            .max(start);
+        let lanes = lanes(&lifetimes);
+        let label_width = lanes
+            .iter()
+            .filter_map(|lane| lane.location)
+            .map(|location| location.len())
+            .max()
+            .unwrap_or_default()
+            .min(40) as u16
+            + 1;
+        let columns = inner.width.saturating_sub(label_width).max(1);
+
+        // Keep the cursor within the session, and the window around it.
+        let cursor = self.cursor.map(|cursor| cursor.clamp(start, end));
+        self.cursor = cursor.filter(|&cursor| cursor < end);
+        let cursor = cursor.unwrap_or(end);
+        let session = end.duration_since(start).unwrap_or_default();
+        let window = session / 2u32.pow(self.zoom);
+        let window_start = (cursor.checked_sub(window / 2).unwrap_or(start))
+            .clamp(start, end.checked_sub(window).unwrap_or(start).max(start));
+        let window_end = window_start + window;
+        self.column = (window / columns as u32).max(Duration::from_millis(1));
+
+        let alive = lifetimes
+            .iter()
+            .filter(|lifetime| {
+                lifetime.created_at <= cursor && lifetime.dropped_at.map_or(true, |d| d > cursor)
+            })
+            .count();
+        let time_ago = |time: SystemTime| {
+            let ago = end.duration_since(time).unwrap_or_default();
+            if ago.is_zero() {
+                Line::from("now")
+            } else {
+                Line::from(vec![
+                    styles.time_units(ago, view::DUR_LIST_PRECISION, None),
+                    Span::raw(" ago"),
+                ])
+            }
+        };
+        let mut summary = vec![bold("cursor: ")];
+        summary.extend(time_ago(cursor).spans);
+        summary.extend([
+            Span::raw(", "),
+            bold(alive.to_string()),
+            Span::raw(" tasks alive, "),
+            bold("window: "),
+            styles.time_units(window, view::DUR_LIST_PRECISION, None),
+        ]);
+        let summary = Line::from(summary);
+        frame.render_widget(Paragraph::new(summary), chunks[1]);
+
+        // The last line of the chart is its time axis.
+        let rows = inner.height.saturating_sub(1) as usize;
+        self.scroll = self.scroll.min(lanes.len().saturating_sub(rows));
+        let column_start =
+            |column: u16| window_start + window.mul_f64(column as f64 / columns as f64);
+        let (live, completed) = (
+            styles.if_utf8("\u{2588}", "#"),
+            styles.if_utf8("\u{2593}", "="),
+        );
+        let buf = frame.buffer_mut();
+        for (y, lane) in (inner.y..).zip(lanes.iter().skip(self.scroll).take(rows)) {
+            if let Some(location) = lane.location {
+                // Long locations are cut from the start, since the file name
+                // and line are the most useful parts.
+                let max = label_width as usize - 1;
+                let skip = location.chars().count().saturating_sub(max);
+                let location = location.chars().skip(skip).collect::<String>();
+                buf.set_string(inner.x, y, location, Style::default());
+            }
+            for column in 0..columns {
+                let (from, to) = (column_start(column), column_start(column + 1));
+                let tasks = lane.tasks.iter().filter(|lifetime| {
+                    lifetime.created_at < to && lifetime.dropped_at.map_or(true, |d| d >= from)
+                });
+                let mut symbol = None;
+                for lifetime in tasks {
+                    if lifetime.dropped_at.is_none() {
+                        symbol = Some((live, styles.fg(Color::Green)));
+                        break;
+                    }
+                    symbol = Some((completed, styles.fg(Color::Blue)));
+                }
+                if let Some((symbol, style)) = symbol {
+                    buf.get_mut(inner.x + label_width + column, y)
+                        .set_symbol(symbol)
+                        .set_style(style);
+                }
+            }
+        }
+
+        let axis_y = inner.y + inner.height.saturating_sub(1);
+        let axis = Rect::new(inner.x + label_width, axis_y, columns, 1);
+        Paragraph::new(time_ago(window_start)).render(axis, buf);
+        Paragraph::new(time_ago(window_end))
+            .alignment(layout::Alignment::Right)
+            .render(axis, buf);
+
+        if window_start <= cursor && cursor <= window_end {
+            let offset = cursor.duration_since(window_start).unwrap_or_default();
+            let column = (offset.as_secs_f64() / window.as_secs_f64().max(f64::MIN_POSITIVE)
+                * columns as f64) as u16;
+            let cursor_area = Rect::new(
+                inner.x + label_width + column.min(columns - 1),
+                inner.y,
+                1,
+                inner.height.saturating_sub(1),
+            );
+            buf.set_style(
+                cursor_area,
+                Style::default().add_modifier(Modifier::REVERSED),
+            );
+        }
+    }
+}
+
+impl HelpText for LifetimesView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+/// Packs `lifetimes`, which are ordered by when they were spawned, into rows,
+/// grouped by their locations in the order their first task was spawned.
+///
+/// Each task is put in the first row of its location whose tasks had all
+/// completed by the time it was spawned.
+fn lanes(lifetimes: &[Lifetime]) -> Vec<Lane<'_>> {
+    let mut groups: Vec<(&str, Vec<Vec<&Lifetime>>)> = Vec::new();
+    let mut by_location = HashMap::new();
+    for lifetime in lifetimes {
+        let group = *by_location
+            .entry(lifetime.location.as_str())
+            .or_insert_with(|| {
+                groups.push((lifetime.location.as_str(), Vec::new()));
+                groups.len() - 1
+            });
+        let lanes = &mut groups[group].1;
+        let free = lanes.iter_mut().find(|lane| {
+            lane.last()
+                .and_then(|last| last.dropped_at)
+                .is_some_and(|dropped_at| dropped_at <= lifetime.created_at)
+        });
+        match free {
+            Some(lane) => lane.push(lifetime),
+            None => lanes.push(vec![lifetime]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|(location, lanes)| {
+            lanes.into_iter().enumerate().map(move |(i, tasks)| Lane {
+                location: (i == 0).then_some(location),
+                tasks,
+            })
+        })
+        .collect()
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "move cursor",
+            keys: &[
+                KeyDisplay {
+                    base: "left, right",
+                    utf8: Some("\u{2190}\u{2192}"),
+                },
+                KeyDisplay {
+                    base: "h, l",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "zoom in/out",
+            keys: &[KeyDisplay {
+                base: "+, -",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc",
+                utf8: Some("\u{238B} esc"),
+            }],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::store::Ids;
+
+    /// Returns tasks spawned at the given locations and times, as seconds
+    /// since the session started, with IDs in the order they're given.
+    fn lifetimes(tasks: &[(&str, u64, Option<u64>)]) -> Vec<Lifetime> {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut ids = Ids::default();
+        (0..)
+            .zip(tasks)
+            .map(|(span_id, &(location, created, dropped))| Lifetime {
+                id: ids.id_for(span_id),
+                location: location.to_owned(),
+                created_at: at(created),
+                dropped_at: dropped.map(at),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_tasks_get_their_own_lanes() {
+        let lifetimes = lifetimes(&[
+            ("a.rs", 0, Some(10)),
+            ("b.rs", 1, None),
+            ("a.rs", 2, Some(5)),
+            ("a.rs", 10, None),
+            ("a.rs", 11, None),
+        ]);
+        let lanes = lanes(&lifetimes)
+            .into_iter()
+            .map(|lane| {
+                let created = lane
+                    .tasks
+                    .iter()
+                    .map(|task| {
+                        task.created_at
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs()
+                    })
+                    .collect::<Vec<_>>();
+                (lane.location, created)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lanes,
+            [
+                (Some("a.rs"), vec![0, 10]),
+                (None, vec![2, 11]),
+                (Some("b.rs"), vec![1]),
+            ]
+        );
+    }
+}
@@ -2,12 +2,20 @@ use std::time::Duration;
 
 use ratatui::{
     layout::Rect,
-    style::Style,
+    style::{Color, Style},
     symbols,
     widgets::{Block, Widget},
 };
 
-use crate::state::histogram::DurationHistogram;
+use crate::{
+    graphics::{self, Graphics, Image},
+    state::histogram::DurationHistogram,
+};
+
+/// The color of the bars when the histogram is drawn as an image.
+const BAR_COLOR: [u8; 4] = [0x56, 0xb4, 0xe9, 0xff];
+/// The color of the threshold lines when the histogram is drawn as an image.
+const THRESHOLD_COLOR: [u8; 4] = [0xd5, 0x5e, 0x00, 0xff];
 
 /// This is a Ratatui widget to visualize a latency histogram in a small area.
 /// It is based on the [`Sparkline`] widget, so it draws a mini bar chart with
@@ -29,6 +37,13 @@ pub(crate) struct MiniHistogram<'a> {
     bar_set: symbols::bar::Set,
     /// Duration precision for the labels
     duration_precision: usize,
+    /// Durations to mark with a line on the chart, such as latency
+    /// objectives' thresholds
+    thresholds: Vec<Duration>,
+    /// The style of the lines marking the thresholds
+    threshold_style: Style,
+    /// Where to place the bars as an image, instead of drawing them as text
+    graphics: Option<&'a Graphics>,
 }
 
 #[derive(Debug, Default)]
@@ -42,6 +57,10 @@ pub(crate) struct HistogramMetadata {
     /// Number of high outliers, if any
     pub(crate) high_outliers: u64,
     pub(crate) highest_outlier: Option<Duration>,
+    /// The range of values in each bucket
+    pub(crate) step_size: u64,
+    /// The number of empty buckets removed from the start of the chart
+    pub(crate) skipped_buckets: u64,
 }
 
 impl Default for MiniHistogram<'_> {
@@ -53,6 +72,9 @@ impl Default for MiniHistogram<'_> {
             max: None,
             bar_set: symbols::bar::NINE_LEVELS,
             duration_precision: 4,
+            thresholds: Vec::new(),
+            threshold_style: Style::default().fg(Color::Red),
+            graphics: None,
         }
     }
 }
@@ -116,7 +138,13 @@ impl Widget for MiniHistogram<'_> {
             width: inner_area.width - y_axis_label_width,
             height: inner_area.height - legend_height,
         };
-        self.render_bars(bars_area, buf, data);
+        match self.graphics {
+            Some(graphics) => graphics.place(bars_area, self.image(bars_area, &data, &metadata)),
+            None => {
+                self.render_bars(bars_area, buf, data);
+                self.render_thresholds(bars_area, buf, &metadata);
+            }
+        }
     }
 }
 
@@ -136,8 +164,7 @@ impl<'a> MiniHistogram<'a> {
             .iter()
             .take(max_index)
             .map(|e| {
-                if max != 0 {
-                    let r = e * u64::from(area.height) * 8 / max;
+                if let Some(r) = (e * u64::from(area.height) * 8).checked_div(max) {
                     // This is the only difference in the bar rendering logic
                     // between MiniHistogram and Sparkline. At least render a
                     // ONE_EIGHT, if the value is greater than 0, even if it's
@@ -178,6 +205,92 @@ impl<'a> MiniHistogram<'a> {
         }
     }
 
+    /// Draws the bars and thresholds as an image to fill `area`, with a bar
+    /// for each column of cells.
+    fn image(&self, area: Rect, data: &[u64], metadata: &HistogramMetadata) -> Image {
+        let mut image = Image::for_area(area);
+        let max = self
+            .max
+            .unwrap_or_else(|| data.iter().copied().max().unwrap_or(1));
+        for (column, &value) in data.iter().take(area.width as usize).enumerate() {
+            let Some(height) = (value * u64::from(image.height())).checked_div(max) else {
+                continue;
+            };
+            // As with the text bars, draw at least a sliver for any value.
+            let height = if value > 0 { height.max(1) } else { 0 } as u32;
+            image.fill(
+                column as u32 * graphics::CELL_WIDTH,
+                image.height() - height,
+                // Leave a gap between bars.
+                graphics::CELL_WIDTH - 1,
+                height,
+                BAR_COLOR,
+            );
+        }
+        for column in self.threshold_columns(area, metadata) {
+            let x = u32::from(column) * graphics::CELL_WIDTH + graphics::CELL_WIDTH / 2;
+            image.fill(x, 0, 1, image.height(), THRESHOLD_COLOR);
+        }
+        image
+    }
+
+    /// Returns the column of each threshold which falls within the chart.
+    fn threshold_columns<'b>(
+        &'b self,
+        area: Rect,
+        metadata: &'b HistogramMetadata,
+    ) -> impl Iterator<Item = u16> + 'b {
+        self.thresholds.iter().filter_map(move |threshold| {
+            let threshold = u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX);
+            let column = threshold
+                .checked_div(metadata.step_size)?
+                .checked_sub(metadata.skipped_buckets)?;
+            u16::try_from(column)
+                .ok()
+                .filter(|&column| column < area.width)
+        })
+    }
+
+    /// Draws a line at each threshold which falls within the chart.
+    fn render_thresholds(
+        &self,
+        area: ratatui::layout::Rect,
+        buf: &mut ratatui::buffer::Buffer,
+        metadata: &HistogramMetadata,
+    ) {
+        for column in self.threshold_columns(area, metadata) {
+            let x = area.left() + column;
+            for y in area.top()..area.bottom() {
+                let cell = buf.get_mut(x, y);
+                // Only draw the line where there's no bar, so that the bar
+                // is still visible.
+                if cell.symbol() == self.bar_set.empty {
+                    cell.set_symbol(symbols::line::VERTICAL);
+                }
+                cell.set_style(self.threshold_style);
+            }
+        }
+    }
+
+    pub(crate) fn thresholds(
+        mut self,
+        thresholds: impl IntoIterator<Item = Duration>,
+    ) -> MiniHistogram<'a> {
+        self.thresholds = thresholds.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn threshold_style(mut self, style: Style) -> MiniHistogram<'a> {
+        self.threshold_style = style;
+        self
+    }
+
+    /// Places the bars as an image, if charts are drawn as images.
+    pub(crate) fn graphics(mut self, graphics: Option<&'a Graphics>) -> MiniHistogram<'a> {
+        self.graphics = graphics;
+        self
+    }
+
     pub fn duration_precision(mut self, precision: usize) -> MiniHistogram<'a> {
         self.duration_precision = precision;
         self
@@ -276,6 +389,7 @@ fn chart_data(histogram: &DurationHistogram, width: u16) -> (Vec<u64>, Histogram
 
     let step_size = ((histogram.max() - histogram.min()) as f64 / width as f64).ceil() as u64 + 1;
     // `iter_linear` panics if step_size is 0
+    let mut skipped_buckets = 0;
     let data = if step_size > 0 {
         let mut found_first_nonzero = false;
         let data: Vec<u64> = histogram
@@ -286,6 +400,7 @@ fn chart_data(histogram: &DurationHistogram, width: u16) -> (Vec<u64>, Histogram
                 // Because HdrHistogram can return empty buckets depending
                 // on its internal state, as it approximates values.
                 if count == 0 && !found_first_nonzero {
+                    skipped_buckets += 1;
                     None
                 } else {
                     found_first_nonzero = true;
@@ -306,6 +421,8 @@ fn chart_data(histogram: &DurationHistogram, width: u16) -> (Vec<u64>, Histogram
             max_bucket,
             high_outliers,
             highest_outlier,
+            step_size,
+            skipped_buckets,
         },
     )
 }
@@ -1,29 +1,72 @@
 use crate::view::help::HelpView;
 use crate::view::{
-    help::HelpText, resources::ResourcesTable, table::TableListState, tasks::TasksTable,
+    distributions::Distributions,
+    event_log::EventLogPane,
+    help::HelpText,
+    prompt::PromptInput,
+    resources::ResourcesTable,
+    summary::Summary,
+    table::{TableList, TableListState, TableSettings},
+    tasks::{Follow, StatsDisplay, TasksContext, TasksTable},
+};
+use crate::{
+    clipboard,
+    discover::{self, Discover},
+    editor::{self, EditorCommand},
+    event_log::Severity,
+    export, flamegraph,
+    highlight::Highlight,
+    input,
+    keymap::Keymap,
+    query::Filter,
+    state::{tasks::Task, State},
 };
-use crate::{input, state::State};
 use ratatui::{
     layout,
     style::{self, Style},
     text::Span,
 };
-use std::{borrow::Cow, cmp};
+use std::{borrow::Cow, cell::RefCell, cmp, path::PathBuf, rc::Rc, time::SystemTime};
 
+mod activity;
+mod alert;
 mod async_ops;
+mod bookmarks;
+mod columns;
+mod connect;
 mod controls;
+mod crashed;
+mod diff;
+mod distributions;
 mod durations;
+mod event_log;
+mod fields;
+mod groups;
 mod help;
+mod lifetimes;
 mod mini_histogram;
+mod net;
 mod percentiles;
+mod plugin;
+mod prompt;
 mod resource;
 mod resources;
+mod sort;
+mod states;
 mod styles;
+mod summary;
 mod table;
 mod task;
 mod tasks;
-pub(crate) use self::styles::{Palette, Styles};
-pub(crate) use self::table::SortBy;
+mod timeline;
+mod tree;
+// Used by plugins, which may all be disabled.
+#[allow(unused_imports)]
+pub(crate) use self::controls::{ControlDisplay, KeyDisplay};
+pub(crate) use self::plugin::Plugin;
+pub(crate) use self::styles::{ColorVision, Palette, Styles, Theme};
+pub(crate) use self::table::{ColumnConfig, SortBy, SortConfig};
+pub(crate) use self::task::TaskRequest;
 
 // This data is only updated every second, so it doesn't make a ton of
 // sense to have a lot of precision in timestamps (and this makes sure
@@ -44,10 +87,88 @@ pub struct View {
     /// details view), we want to leave the task list's state the way we left it
     /// --- e.g., if the user previously selected a particular sorting, we want
     /// it to remain sorted that way when we return to it.
-    tasks_list: TableListState<TasksTable, 12>,
-    resources_list: TableListState<ResourcesTable, 9>,
+    tasks_list: TableListState<TasksTable, 13>,
+    resources_list: TableListState<ResourcesTable, 10>,
+    /// How the async ops of the last resource which was inspected were
+    /// sorted, which the next resource's async ops are sorted by.
+    async_ops_sort: Option<TableSettings>,
     state: ViewState,
     show_help_modal: bool,
+    /// How many lines the help popup is scrolled down by.
+    help_scroll: u16,
+    /// Which keys act as other keys, which the help popup lists.
+    keymap: Keymap,
+    /// The note being written for a bookmarked task, if any.
+    ///
+    /// While a note is being written, it receives all keyboard input.
+    note_editor: Option<self::bookmarks::NoteEditor>,
+    /// The prompt for naming a snapshot of the tasks, if one is being taken.
+    snapshot_prompt: Option<self::prompt::Prompt>,
+    /// The snapshot of the tasks which the diff view compares them with, if
+    /// one has been taken.
+    snapshot: Option<self::diff::Snapshot>,
+    /// How many snapshots have been taken, for naming the next one.
+    snapshots_taken: usize,
+    /// The rules which style the rows of the tasks they match in the task
+    /// list.
+    highlights: Rc<[Highlight]>,
+    /// The prompt for editing the highlight rules, if it is open.
+    highlight_prompt: Option<self::prompt::Prompt>,
+    /// The filter which hides the tasks that don't match it from the task
+    /// list, if one is applied.
+    task_filter: Option<Rc<Filter>>,
+    /// The prompt for editing the task list's filter, if it is open.
+    filter_prompt: Option<self::prompt::Prompt>,
+    /// The dialog for connecting to a new target, if it is open.
+    connect_dialog: Option<self::connect::ConnectDialog>,
+    /// The popup for choosing the task list's columns, if it is open.
+    column_chooser: Option<self::columns::ColumnChooser>,
+    /// The popup showing all of a task's fields, if it is open.
+    fields_popup: Option<self::fields::FieldsPopup>,
+    /// The banner showing the latest alert, if one was triggered since the
+    /// last key was pressed.
+    alert_banner: Option<self::alert::AlertBanner>,
+    /// The outcome of exporting a table, if one was exported since the last
+    /// input.
+    exported: Option<Result<PathBuf, String>>,
+    /// The outcome of copying to the clipboard, if anything was copied since
+    /// the last input: what was copied, or the error that prevented it.
+    copied: Option<Result<String, String>>,
+    /// The outcome of opening a task's location in an editor, if one was
+    /// opened since the last input: the location, or the error that
+    /// prevented it.
+    opened: Option<Result<String, String>>,
+    /// An editor which runs in the terminal, waiting for the terminal to be
+    /// handed over to it.
+    edit: Option<editor::Edit>,
+    /// The named targets from the config file, as `(name, address)` pairs,
+    /// which can be picked in the connect dialog.
+    targets: Vec<(String, String)>,
+    /// Where to look for targets to list in the connect dialog, besides the
+    /// named targets.
+    discover: Vec<Discover>,
+    /// A target address which the user has entered, but which has not yet
+    /// been connected to.
+    new_target: Option<String>,
+    /// The command which opens locations in the instrumented program's code,
+    /// if one is configured.
+    editor_command: Option<EditorCommand>,
+    /// Which of the tasks' stats are displayed.
+    task_stats: StatsDisplay,
+    /// Which task the task list's selection follows, if any.
+    follow: Follow,
+    /// How many characters the task list's `Fields` column is scrolled
+    /// right by.
+    fields_scroll: usize,
+    /// Whether the distributions of the task list's poll and scheduled times
+    /// are shown below it.
+    show_distributions: bool,
+    /// Whether the event log is shown below the current view.
+    show_event_log: bool,
+    /// The least severe entries of the event log which are shown.
+    event_log_severity: Severity,
+    /// The custom views compiled into the console.
+    plugins: Vec<Box<dyn Plugin>>,
     pub(crate) styles: Styles,
 }
 
@@ -56,10 +177,55 @@ pub(crate) enum ViewState {
     TasksList,
     /// The table list of all resources.
     ResourcesList,
+    /// The tasks, with each task under the task which spawned it.
+    TaskTree(self::tree::TaskTreeView),
+    /// The tasks, grouped by their names, targets or locations.
+    TaskGroups(self::groups::TaskGroupsView),
+    /// The list of bookmarked tasks.
+    Bookmarks(self::bookmarks::BookmarksView),
+    /// The list of tasks which panicked.
+    Crashed(self::crashed::CrashedView),
+    /// A summary of network listeners and connections.
+    NetSummary(self::net::NetView),
+    /// A chart of when every task was spawned and completed.
+    Lifetimes(self::lifetimes::LifetimesView),
+    /// How the tasks changed since a snapshot of them was taken.
+    Diff(self::diff::DiffView),
     /// Inspecting a single task instance.
     TaskInstance(self::task::TaskView),
     /// Inspecting a single resource instance.
-    ResourceInstance(self::resource::ResourceView),
+    ResourceInstance(Box<self::resource::ResourceView>),
+    /// A custom view, by its index in the registered plugins.
+    Plugin(usize),
+}
+
+/// The parts of the view which are saved between sessions, so that
+/// reconnecting to the same target restores them.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ViewSettings {
+    view: SavedView,
+    tasks: TableSettings,
+    resources: TableSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    async_ops: Option<TableSettings>,
+}
+
+/// The views which can be restored from [`ViewSettings`].
+///
+/// A task or resource's details can't be restored, since the task or resource
+/// will have a different ID in the next session, so its list is restored
+/// instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SavedView {
+    Tasks,
+    TaskTree,
+    TaskGroups,
+    Resources,
+    Network,
+    Bookmarks,
+    Crashed,
+    Timeline,
 }
 
 /// The outcome of the update_input method
@@ -100,37 +266,506 @@ impl View {
     pub fn new(styles: Styles) -> Self {
         Self {
             state: ViewState::TasksList,
-            tasks_list: TableListState::<TasksTable, 12>::default(),
-            resources_list: TableListState::<ResourcesTable, 9>::default(),
+            tasks_list: TableListState::<TasksTable, 13>::default(),
+            resources_list: TableListState::<ResourcesTable, 10>::default(),
+            async_ops_sort: None,
             show_help_modal: false,
+            help_scroll: 0,
+            keymap: Keymap::default(),
+            note_editor: None,
+            snapshot_prompt: None,
+            snapshot: None,
+            snapshots_taken: 0,
+            highlights: Rc::from([]),
+            highlight_prompt: None,
+            task_filter: None,
+            filter_prompt: None,
+            connect_dialog: None,
+            column_chooser: None,
+            fields_popup: None,
+            alert_banner: None,
+            exported: None,
+            copied: None,
+            opened: None,
+            edit: None,
+            targets: Vec::new(),
+            discover: Vec::new(),
+            new_target: None,
+            editor_command: None,
+            task_stats: StatsDisplay::default(),
+            follow: Follow::default(),
+            fields_scroll: 0,
+            show_distributions: false,
+            show_event_log: false,
+            event_log_severity: Severity::Info,
+            plugins: crate::plugins::registered(),
             styles,
         }
     }
 
+    /// Sets the named targets which can be picked in the connect dialog.
+    pub(crate) fn with_targets<'a>(
+        self,
+        targets: impl IntoIterator<Item = (&'a str, &'a tonic::transport::Uri)>,
+    ) -> Self {
+        let targets = targets
+            .into_iter()
+            .map(|(name, addr)| (name.to_owned(), addr.to_string()))
+            .collect();
+        Self { targets, ..self }
+    }
+
+    /// Sets which keys act as other keys, so that the help popup lists them.
+    pub(crate) fn with_keymap(self, keymap: Keymap) -> Self {
+        Self { keymap, ..self }
+    }
+
+    /// Sets the rules which style the rows of the tasks they match.
+    pub(crate) fn with_highlights(self, highlights: Vec<Highlight>) -> Self {
+        Self {
+            highlights: highlights.into(),
+            ..self
+        }
+    }
+
+    /// Sets where to look for targets to list in the connect dialog.
+    pub(crate) fn with_discover(self, discover: Vec<Discover>) -> Self {
+        Self { discover, ..self }
+    }
+
+    /// Sets the command which opens locations in the instrumented program's
+    /// code.
+    pub(crate) fn with_editor_command(self, editor_command: Option<EditorCommand>) -> Self {
+        Self {
+            editor_command,
+            ..self
+        }
+    }
+
+    /// Sets which of the task list's columns are displayed, in which order,
+    /// and how wide they are.
+    pub(crate) fn with_task_list_columns(mut self, columns: Vec<ColumnConfig>) -> Self {
+        self.tasks_list.configure_columns(columns);
+        self
+    }
+
+    /// Sorts the task list by the column named in `sort`, if it has one.
+    pub(crate) fn with_task_list_sort(mut self, sort: Option<&SortConfig>, state: &State) -> Self {
+        if let Some(sort) = sort {
+            self.tasks_list
+                .configure_sort(sort, &<TasksTable as TableList<13>>::headers(state));
+        }
+        self
+    }
+
     pub(crate) fn update_input(&mut self, event: input::Event, state: &State) -> UpdateKind {
         use ViewState::*;
         let mut update_kind = UpdateKind::Other;
+        self.exported = None;
+        self.copied = None;
+        self.opened = None;
+        if matches!(event, input::Event::Key(_)) {
+            self.alert_banner = None;
+        }
+        if let TaskInstance(ref mut view) = self.state {
+            view.cancel_abort(&event);
+        }
+
+        if let Some(ref mut editor) = self.note_editor {
+            if editor.update_input(event) {
+                self.note_editor = None;
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut prompt) = self.snapshot_prompt {
+            match prompt.update_input(event) {
+                PromptInput::Editing => {}
+                PromptInput::Submit(name) => {
+                    self.snapshots_taken += 1;
+                    self.snapshot = Some(self::diff::Snapshot::take(name, state));
+                    self.snapshot_prompt = None;
+                }
+                PromptInput::Cancel => self.snapshot_prompt = None,
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut prompt) = self.highlight_prompt {
+            match prompt.update_input(event) {
+                PromptInput::Editing => {}
+                PromptInput::Submit(rules) => match Highlight::parse_list(&rules) {
+                    Ok(highlights) => {
+                        self.highlights = highlights.into();
+                        self.highlight_prompt = None;
+                    }
+                    Err(error) => {
+                        self.highlight_prompt = Some(highlight_prompt(rules, Some(error)));
+                    }
+                },
+                PromptInput::Cancel => self.highlight_prompt = None,
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut prompt) = self.filter_prompt {
+            match prompt.update_input(event) {
+                PromptInput::Editing => {}
+                // An empty filter shows every task again.
+                PromptInput::Submit(filter) if filter.trim().is_empty() => {
+                    self.task_filter = None;
+                    self.filter_prompt = None;
+                }
+                PromptInput::Submit(filter) => match filter.parse::<Filter>() {
+                    Ok(filter) => {
+                        self.task_filter = Some(Rc::new(filter));
+                        self.filter_prompt = None;
+                    }
+                    Err(error) => {
+                        self.filter_prompt = Some(filter_prompt(filter, Some(error)));
+                    }
+                },
+                PromptInput::Cancel => self.filter_prompt = None,
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut dialog) = self.connect_dialog {
+            match dialog.update_input(event) {
+                self::connect::ConnectInput::Editing => {}
+                self::connect::ConnectInput::Connect(target) => {
+                    self.connect_dialog = None;
+                    self.new_target = Some(target);
+                }
+                self::connect::ConnectInput::Cancel => self.connect_dialog = None,
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut chooser) = self.column_chooser {
+            if chooser.update_input(event, &mut self.tasks_list) {
+                self.column_chooser = None;
+            }
+            return update_kind;
+        }
+
+        if let Some(ref mut popup) = self.fields_popup {
+            if popup.update_input(event) {
+                self.fields_popup = None;
+            }
+            return update_kind;
+        }
+
+        // While a list's search is being typed, it receives every key.
+        match self.state {
+            TasksList if self.tasks_list.is_editing_search() => {
+                self.tasks_list.update_input(event);
+                return update_kind;
+            }
+            ResourcesList if self.resources_list.is_editing_search() => {
+                self.resources_list.update_input(event);
+                return update_kind;
+            }
+            ResourceInstance(ref mut view) if view.async_ops_table.is_editing_search() => {
+                view.async_ops_table.update_input(event);
+                return update_kind;
+            }
+            _ => {}
+        }
+
+        let mut event = event;
+        if let input::Event::Mouse(mouse) = event {
+            let opened = match self.state {
+                TasksList => {
+                    let selected = self.tasks_list.selected_item();
+                    let opened = self.tasks_list.mouse_input(mouse);
+                    // Clicking or scrolling to another task stops following
+                    // a task, as moving the selection does.
+                    let reselected = self.tasks_list.selected_item();
+                    if !matches!((selected, reselected), (Some(a), Some(b)) if Rc::ptr_eq(&a, &b)) {
+                        self.follow = Follow::Off;
+                    }
+                    Some(opened)
+                }
+                ResourcesList => Some(self.resources_list.mouse_input(mouse)),
+                ResourceInstance(ref mut view) => Some(view.async_ops_table.mouse_input(mouse)),
+                _ => None,
+            };
+            match opened {
+                // Clicking the selected row again opens it, as Enter does.
+                Some(true) => {
+                    event = input::Event::Key(input::KeyEvent::new(
+                        input::KeyCode::Enter,
+                        input::KeyModifiers::NONE,
+                    ))
+                }
+                Some(false) => return update_kind,
+                None => {}
+            }
+        }
+
+        if matches!(event, key!(Char('C'))) {
+            self.connect_dialog = Some(
+                self::connect::ConnectDialog::new(self.targets.clone())
+                    .with_discovered(discover::targets(&self.discover)),
+            );
+            return update_kind;
+        }
 
         if self.should_toggle_help_modal(&event) {
             self.show_help_modal = !self.show_help_modal;
+            self.help_scroll = 0;
             return update_kind;
         }
 
+        if self.show_help_modal {
+            // The help popup is clamped to its last line when it's rendered.
+            let scrolled = match event {
+                key!(Up) | key!(Char('k')) => Some(self.help_scroll.saturating_sub(1)),
+                key!(Down) | key!(Char('j')) => Some(self.help_scroll.saturating_add(1)),
+                key!(PageUp) => Some(self.help_scroll.saturating_sub(10)),
+                key!(PageDown) => Some(self.help_scroll.saturating_add(10)),
+                key!(Home) => Some(0),
+                key!(End) => Some(u16::MAX),
+                _ => None,
+            };
+            if let Some(scroll) = scrolled {
+                self.help_scroll = scroll;
+                return update_kind;
+            }
+        }
+
         if matches!(event, key!(Char('t'))) {
-            self.state = TasksList;
+            // Pressing `t` again in the task list switches to the tree.
+            self.state = match self.state {
+                TasksList => TaskTree(self::tree::TaskTreeView::default()),
+                _ => TasksList,
+            };
             return update_kind;
         }
 
+        if matches!(event, key!(Char('g'))) {
+            // Pressing `g` in the task groups groups them another way, and
+            // returns to the task list after grouping them every way.
+            match self.state {
+                TasksList => {
+                    self.state = TaskGroups(self::groups::TaskGroupsView::default());
+                    return update_kind;
+                }
+                TaskGroups(ref mut view) => {
+                    if !view.next_group_by() {
+                        self.state = TasksList;
+                    }
+                    return update_kind;
+                }
+                _ => {}
+            }
+        }
+
         if matches!(event, key!(Char('r'))) {
             self.state = ResourcesList;
             return update_kind;
         }
 
+        if matches!(event, key!(Char('n'))) {
+            self.state = NetSummary(self::net::NetView::default());
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('B'))) {
+            self.state = Bookmarks(self::bookmarks::BookmarksView::default());
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('p'))) {
+            self.state = Crashed(self::crashed::CrashedView::default());
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('T'))) {
+            self.state = Lifetimes(self::lifetimes::LifetimesView::default());
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('v'))) {
+            self.state = Diff(self::diff::DiffView::default());
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('V'))) {
+            self.show_event_log = !self.show_event_log;
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('!'))) && self.show_event_log {
+            self.event_log_severity = self.event_log_severity.next();
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('A'))) && matches!(self.state, TasksList) {
+            let rules = self
+                .highlights
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
+            self.highlight_prompt = Some(highlight_prompt(rules, None));
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('|'))) && matches!(self.state, TasksList) {
+            let filter = self
+                .task_filter
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            self.filter_prompt = Some(filter_prompt(filter, None));
+            return update_kind;
+        }
+
+        if matches!(event, key!(Char('m'))) && matches!(self.state, TasksList | Diff(_)) {
+            let name = format!("snapshot {}", self.snapshots_taken + 1);
+            self.snapshot_prompt = Some(
+                self::prompt::Prompt::new("Snapshot name", name)
+                    .with_max_len(32)
+                    .with_submit_action("take snapshot"),
+            );
+            return update_kind;
+        }
+
+        if let input::Event::Key(input::KeyEvent {
+            code: input::KeyCode::Char(key),
+            ..
+        }) = event
+        {
+            if let Some(idx) = self.plugins.iter().position(|plugin| plugin.key() == key) {
+                self.state = Plugin(idx);
+                return update_kind;
+            }
+        }
+
+        if let Some(task) = self.selected_task(state) {
+            if matches!(event, key!(Char('b'))) {
+                task.borrow_mut().toggle_bookmark();
+                self.tasks_list.resort();
+                return update_kind;
+            }
+
+            if matches!(event, key!(Char('a'))) {
+                self.note_editor = Some(self::bookmarks::NoteEditor::new(&task));
+                return update_kind;
+            }
+
+            if matches!(event, key!(Char('y'))) {
+                let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+                let task = task.borrow();
+                let text = self::task::details_text(&task, self.task_stats, now);
+                self.copied = Some(copy(format!("task {}", task.id_str()), &text));
+                return update_kind;
+            }
+
+            if matches!(event, key!(Char('o'))) {
+                let location = task.borrow().source_location().cloned();
+                self.opened = Some(self.open_in_editor(location));
+                return update_kind;
+            }
+
+            if matches!(event, key!(Char('u'))) {
+                self.fields_popup = Some(self::fields::FieldsPopup::new(&task.borrow()));
+                return update_kind;
+            }
+        }
+
+        if let Some(format) = export_format(&event) {
+            let exported = match self.state {
+                TasksList => {
+                    let context = TasksContext {
+                        stats: self.task_stats,
+                        follow: self.follow,
+                        highlights: self.highlights.clone(),
+                        filter: self.task_filter.clone(),
+                        fields_scroll: self.fields_scroll,
+                    };
+                    Some(self.tasks_list.export("tasks", format, state, &context))
+                }
+                ResourcesList => Some(self.resources_list.export("resources", format, state, &())),
+                ResourceInstance(ref view) => Some(view.export_async_ops(format, state)),
+                _ => None,
+            };
+            if let Some(exported) = exported {
+                self.exported = Some(exported.map_err(|error| error.to_string()));
+                return update_kind;
+            }
+        }
+
+        if matches!(event, key!(Char('Y'))) {
+            let table = match self.state {
+                TasksList => {
+                    let context = TasksContext {
+                        stats: self.task_stats,
+                        follow: self.follow,
+                        highlights: self.highlights.clone(),
+                        filter: self.task_filter.clone(),
+                        fields_scroll: self.fields_scroll,
+                    };
+                    Some(("tasks", self.tasks_list.markdown(state, &context)))
+                }
+                ResourcesList => Some(("resources", self.resources_list.markdown(state, &()))),
+                ResourceInstance(ref view) => Some(("async ops", view.async_ops_markdown(state))),
+                _ => None,
+            };
+            if let Some((what, markdown)) = table {
+                self.copied = Some(
+                    markdown
+                        .map_err(|error| error.to_string())
+                        .and_then(|markdown| copy(what.to_owned(), &markdown)),
+                );
+                return update_kind;
+            }
+        }
+
         match self.state {
             TasksList => {
                 // The enter key changes views, so handle here since we can
                 // mutate the currently selected view.
                 match event {
+                    key!(Char('w')) => self.toggle_stats_window(),
+                    key!(Char('z')) => self.reset_task_stats(state),
+                    key!(Char('Z')) => {
+                        let stacks = flamegraph::from_state(state);
+                        self.exported =
+                            Some(flamegraph::save(&stacks).map_err(|error| error.to_string()));
+                    }
+                    key!(Char('F')) => self.follow = self.follow.next(),
+                    key!(Char('P')) => {
+                        if let Some(task) = self.tasks_list.selected_item() {
+                            self.follow = self.follow.toggle_pin(task.borrow().id());
+                        }
+                    }
+                    key!(Char('d')) => self.show_distributions = !self.show_distributions,
+                    key!(Char('c')) => self.column_chooser = Some(Default::default()),
+                    key!(Char('[')) => {
+                        self.fields_scroll =
+                            self.fields_scroll.saturating_sub(self::fields::SCROLL_STEP);
+                    }
+                    key!(Char(']')) => {
+                        // Stop scrolling once the longest fields are scrolled
+                        // out of the column.
+                        let longest = self
+                            .tasks_list
+                            .sorted_items
+                            .iter()
+                            .filter_map(|task| task.upgrade())
+                            .map(|task| {
+                                self::table::spans_text(task.borrow().formatted_fields())
+                                    .chars()
+                                    .count()
+                            })
+                            .max()
+                            .unwrap_or(0);
+                        self.fields_scroll = (self.fields_scroll + self::fields::SCROLL_STEP)
+                            .min(longest.saturating_sub(1));
+                    }
                     key!(Enter) => {
                         if let Some(task) = self.tasks_list.selected_item() {
                             update_kind = UpdateKind::SelectTask(task.borrow().span_id());
@@ -141,6 +776,22 @@ impl View {
                         }
                     }
                     _ => {
+                        // Moving the selection stops following a task, since
+                        // the user has chosen which task to select.
+                        if matches!(
+                            event,
+                            key!(Up)
+                                | key!(Down)
+                                | key!(Char('j'))
+                                | key!(Char('k'))
+                                | key!(Char('G'))
+                                | key!(PageUp)
+                                | key!(PageDown)
+                                | key!(Home)
+                                | key!(End)
+                        ) {
+                            self.follow = Follow::Off;
+                        }
                         // otherwise pass on to view
                         self.tasks_list.update_input(event);
                     }
@@ -151,7 +802,11 @@ impl View {
                     key!(Enter) => {
                         if let Some(res) = self.resources_list.selected_item() {
                             update_kind = UpdateKind::SelectResource(res.borrow().span_id());
-                            self.state = ResourceInstance(self::resource::ResourceView::new(res));
+                            let mut view = self::resource::ResourceView::new(res);
+                            if let Some(sort) = self.async_ops_sort.clone() {
+                                view.async_ops_table.restore_settings(sort);
+                            }
+                            self.state = ResourceInstance(Box::new(view));
                         }
                     }
                     _ => {
@@ -160,10 +815,96 @@ impl View {
                     }
                 }
             }
+            Bookmarks(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                key!(Enter) => {
+                    if let Some(task) = view.selected_task(state) {
+                        update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                        self.state =
+                            TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+                    }
+                }
+                _ => view.update_input(event, state),
+            },
+            TaskTree(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                key!(Enter) => {
+                    if let Some(task) = view.selected_task(state) {
+                        update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                        self.state =
+                            TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+                    }
+                }
+                _ => view.update_input(event),
+            },
+            TaskGroups(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                // Enter opens a task's details, or expands or collapses a
+                // group.
+                key!(Enter) => {
+                    if let Some(task) = view.selected_task(state) {
+                        update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                        self.state =
+                            TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+                    } else {
+                        view.update_input(event);
+                    }
+                }
+                _ => view.update_input(event),
+            },
+            Crashed(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                key!(Enter) => {
+                    if let Some(task) = view.selected_task(state) {
+                        update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                        self.state =
+                            TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+                    }
+                }
+                _ => view.update_input(event, state),
+            },
+            NetSummary(_) => {}
+            Plugin(idx) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                _ => self.plugins[idx].update_input(event, state),
+            },
+            Lifetimes(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                _ => view.update_input(event, state),
+            },
+            Diff(ref mut view) => match event {
+                key!(Esc) => {
+                    self.state = TasksList;
+                }
+                key!(Enter) => {
+                    if let Some(task) = view.selected_task(state) {
+                        update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                        self.state =
+                            TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+                    }
+                }
+                _ => view.update_input(event),
+            },
             ResourceInstance(ref mut view) => {
                 // The escape key changes views, so handle here since we can
                 // mutate the currently selected view.
                 match event {
+                    // Escape clears the async ops' search before leaving.
+                    key!(Esc) if view.async_ops_table.is_searched() => {
+                        view.async_ops_table.update_input(event);
+                    }
                     key!(Esc) => {
                         self.state = ResourcesList;
                         update_kind = UpdateKind::Other;
@@ -190,7 +931,8 @@ impl View {
                     }
                     _ => {
                         // otherwise pass on to view
-                        view.update_input(event);
+                        self.edit = view.update_input(event, self.editor_command.as_ref());
+                        self.async_ops_sort = Some(view.async_ops_table.settings());
                     }
                 }
             }
@@ -202,9 +944,22 @@ impl View {
                         self.state = TasksList;
                         update_kind = UpdateKind::ExitTaskView;
                     }
+                    key!(Char('w')) => self.toggle_stats_window(),
+                    key!(Char('z')) => self.reset_task_stats(state),
+                    key!(Char('Z')) => {
+                        let stacks = flamegraph::from_state(state);
+                        self.exported =
+                            Some(flamegraph::save(&stacks).map_err(|error| error.to_string()));
+                    }
                     _ => {
                         // otherwise pass on to view
-                        view.update_input(event);
+                        if let Some(task) = view.update_input(event, state) {
+                            update_kind = UpdateKind::SelectTask(task.borrow().span_id());
+                            self.state = TaskInstance(self::task::TaskView::new(
+                                task,
+                                state.task_details_ref(),
+                            ));
+                        }
                     }
                 }
             }
@@ -212,6 +967,214 @@ impl View {
         update_kind
     }
 
+    /// Returns the task which bookmarking keys apply to in the current view.
+    fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        match self.state {
+            ViewState::TasksList => self.tasks_list.selected_item(),
+            ViewState::Bookmarks(ref view) => view.selected_task(state),
+            ViewState::TaskTree(ref view) => view.selected_task(state),
+            ViewState::TaskGroups(ref view) => view.selected_task(state),
+            ViewState::Crashed(ref view) => view.selected_task(state),
+            ViewState::Diff(ref view) => view.selected_task(state),
+            ViewState::TaskInstance(ref view) => Some(view.task().clone()),
+            _ => None,
+        }
+    }
+
+    /// Opens a task's `location` in an editor, returning the location which
+    /// was opened.
+    fn open_in_editor(
+        &mut self,
+        location: Option<editor::SourceLocation>,
+    ) -> Result<String, String> {
+        let location = location.ok_or_else(|| "the task's location isn't known".to_owned())?;
+        self.edit = editor::open(self.editor_command.as_ref(), &location)?;
+        Ok(location.to_string())
+    }
+
+    /// Switches which window of time the task stats are displayed over.
+    fn toggle_stats_window(&mut self) {
+        self.task_stats.window = self.task_stats.window.toggle();
+        // The task list's times are compared over the window.
+        self.tasks_list.resort();
+    }
+
+    /// Zeroes the displayed stats of every task from the last update, so
+    /// that they show what has happened since.
+    fn reset_task_stats(&mut self, state: &State) {
+        if let Some(now) = state.last_updated_at() {
+            state.tasks_state().reset_stats(now);
+            self.task_stats.reset_at = Some(now);
+            self.tasks_list.resort();
+        }
+    }
+
+    /// Returns `true` if text is being typed into a note or prompt, or the
+    /// column chooser is open, so that keys should not trigger any global
+    /// actions.
+    pub(crate) fn is_editing_text(&self) -> bool {
+        self.note_editor.is_some()
+            || self.snapshot_prompt.is_some()
+            || self.highlight_prompt.is_some()
+            || self.filter_prompt.is_some()
+            || self.connect_dialog.is_some()
+            || self.column_chooser.is_some()
+            || match self.state {
+                ViewState::TasksList => self.tasks_list.is_editing_search(),
+                ViewState::ResourcesList => self.resources_list.is_editing_search(),
+                ViewState::ResourceInstance(ref view) => view.async_ops_table.is_editing_search(),
+                _ => false,
+            }
+    }
+
+    /// Shows `message` in a banner above the current view, for an alert
+    /// which has been triggered.
+    pub(crate) fn show_alert(&mut self, message: String) {
+        match self.alert_banner {
+            Some(ref mut banner) if !banner.is_expired() => banner.push(message),
+            _ => self.alert_banner = Some(self::alert::AlertBanner::new(message)),
+        }
+    }
+
+    /// Returns `true` if an alert's banner is shown, which flashes and so
+    /// has to be redrawn.
+    pub(crate) fn is_alerting(&self) -> bool {
+        self.alert_banner
+            .as_ref()
+            .is_some_and(|banner| !banner.is_expired())
+    }
+
+    /// Returns the outcome of exporting a table, if one was exported since
+    /// the last input.
+    pub(crate) fn exported(&self) -> Option<&Result<PathBuf, String>> {
+        self.exported.as_ref()
+    }
+
+    /// Returns the outcome of copying to the clipboard, if anything was
+    /// copied since the last input.
+    pub(crate) fn copied(&self) -> Option<&Result<String, String>> {
+        self.copied.as_ref()
+    }
+
+    /// Returns the outcome of opening a task's location in an editor, if one
+    /// was opened since the last input.
+    pub(crate) fn opened(&self) -> Option<&Result<String, String>> {
+        self.opened.as_ref()
+    }
+
+    /// Returns the editor which is waiting for the terminal to be handed over
+    /// to it, if one has been opened since this was last called.
+    pub(crate) fn take_edit(&mut self) -> Option<editor::Edit> {
+        self.edit.take()
+    }
+
+    /// Records that an editor which was handed the terminal failed.
+    pub(crate) fn edit_failed(&mut self, error: String) {
+        self.opened = Some(Err(error));
+    }
+
+    /// Returns the request about the displayed task to send to the target,
+    /// along with the task's span ID, if one has been made since this was
+    /// last called.
+    pub(crate) fn take_task_request(&mut self) -> Option<(u64, TaskRequest)> {
+        match self.state {
+            ViewState::TaskInstance(ref mut view) => {
+                let request = view.take_request()?;
+                Some((view.task().borrow().span_id(), request))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the details view of the task with the span ID `span_id`, if
+    /// it's still displayed.
+    pub(crate) fn task_view(&mut self, span_id: u64) -> Option<&mut self::task::TaskView> {
+        match self.state {
+            ViewState::TaskInstance(ref mut view) if view.task().borrow().span_id() == span_id => {
+                Some(view)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the task and resource lists as they would be displayed, for
+    /// printing them without starting the UI.
+    pub(crate) fn tables(&mut self, state: &mut State) -> Vec<export::Table> {
+        let context = TasksContext {
+            stats: self.task_stats,
+            follow: self.follow,
+            highlights: self.highlights.clone(),
+            filter: self.task_filter.clone(),
+            fields_scroll: self.fields_scroll,
+        };
+        self.tasks_list.refresh(state, &context);
+        self.resources_list.refresh(state, &());
+        let tasks = self.tasks_list.displayed_values(state, &context);
+        let resources = self.resources_list.displayed_values(state, &());
+        [("tasks", tasks), ("resources", resources)]
+            .into_iter()
+            .map(|(name, values)| {
+                let (header, rows) = values.unwrap_or_default();
+                export::Table { name, header, rows }
+            })
+            .collect()
+    }
+
+    /// Shows only the tasks which match `filter` in the task list, or every
+    /// task if it's `None`.
+    pub(crate) fn set_task_filter(&mut self, filter: Option<Filter>) {
+        self.task_filter = filter.map(Rc::new);
+    }
+
+    /// Returns the target address the user has entered, if they have entered
+    /// one since this was last called.
+    pub(crate) fn take_new_target(&mut self) -> Option<String> {
+        self.new_target.take()
+    }
+
+    /// Re-opens the connect dialog with a `target` which could not be
+    /// connected to, so that the user can correct it.
+    pub(crate) fn reject_target(&mut self, target: String, error: String) {
+        self.connect_dialog = Some(
+            self::connect::ConnectDialog::rejected(self.targets.clone(), target, error)
+                .with_discovered(discover::targets(&self.discover)),
+        );
+    }
+
+    /// Returns the key and name of each plugin.
+    pub(crate) fn plugins(&self) -> impl Iterator<Item = (char, &'static str)> + '_ {
+        self.plugins
+            .iter()
+            .map(|plugin| (plugin.key(), plugin.name()))
+    }
+
+    /// Passes the state after an update to every plugin.
+    pub(crate) fn update_plugins(&mut self, state: &State) {
+        for plugin in &mut self.plugins {
+            plugin.update(state);
+        }
+    }
+
+    /// Returns to the task list after connecting to a new target, since any
+    /// task or resource being viewed belongs to the old target.
+    pub(crate) fn reset_for_new_target(&mut self) {
+        if matches!(
+            self.state,
+            ViewState::TaskInstance(_) | ViewState::ResourceInstance(_)
+        ) {
+            self.state = ViewState::TasksList;
+        }
+        self.tasks_list.clear_items();
+        self.resources_list.clear_items();
+        self.task_stats.reset_at = None;
+        // The snapshot's tasks belong to the old target.
+        self.snapshot = None;
+        self.alert_banner = None;
+        for plugin in &mut self.plugins {
+            plugin.reset();
+        }
+    }
+
     /// The help modal should toggle on the `?` key and should exit on `Esc`
     fn should_toggle_help_modal(&mut self, event: &crossterm::event::Event) -> bool {
         input::is_help_toggle(event) || (self.show_help_modal && input::is_esc(event))
@@ -223,9 +1186,82 @@ impl View {
         area: layout::Rect,
         state: &mut State,
     ) {
+        let area = match self.alert_banner {
+            Some(ref banner) if !banner.is_expired() => {
+                let areas = layout::Layout::default()
+                    .direction(layout::Direction::Vertical)
+                    .constraints([
+                        layout::Constraint::Length(self::alert::AlertBanner::HEIGHT),
+                        layout::Constraint::Min(0),
+                    ])
+                    .split(area);
+                banner.render(&self.styles, frame, areas[0]);
+                areas[1]
+            }
+            _ => area,
+        };
+        let (area, event_log_area) = if self.show_event_log {
+            let areas = layout::Layout::default()
+                .direction(layout::Direction::Vertical)
+                .constraints([
+                    layout::Constraint::Min(0),
+                    layout::Constraint::Length(EventLogPane::HEIGHT),
+                ])
+                .split(area);
+            (areas[0], Some(areas[1]))
+        } else {
+            (area, None)
+        };
+
         let help_text: &dyn HelpText = match self.state {
             ViewState::TasksList => {
-                self.tasks_list.render(&self.styles, frame, area, state, ());
+                let context = TasksContext {
+                    stats: self.task_stats,
+                    follow: self.follow,
+                    highlights: self.highlights.clone(),
+                    filter: self.task_filter.clone(),
+                    fields_scroll: self.fields_scroll,
+                };
+                let areas = layout::Layout::default()
+                    .direction(layout::Direction::Vertical)
+                    .constraints([
+                        layout::Constraint::Length(Summary::HEIGHT),
+                        layout::Constraint::Min(0),
+                    ])
+                    .split(area);
+                let (summary_area, area) = (areas[0], areas[1]);
+                let (list_area, distributions_area) = if self.show_distributions {
+                    let areas = layout::Layout::default()
+                        .direction(layout::Direction::Vertical)
+                        .constraints([
+                            layout::Constraint::Min(0),
+                            layout::Constraint::Length(Distributions::HEIGHT),
+                        ])
+                        .split(area);
+                    (areas[0], Some(areas[1]))
+                } else {
+                    (area, None)
+                };
+                if let Some(now) = state.last_updated_at() {
+                    Summary::new(state, now).render(&self.styles, frame, summary_area);
+                }
+                self.tasks_list
+                    .render(&self.styles, frame, list_area, state, context);
+                if let (Some(area), Some(now)) = (distributions_area, state.last_updated_at()) {
+                    let tasks = self
+                        .tasks_list
+                        .sorted_items
+                        .iter()
+                        .filter_map(|task| task.upgrade())
+                        .collect::<Vec<_>>();
+                    let tasks = tasks.iter().map(|task| task.borrow()).collect::<Vec<_>>();
+                    Distributions::new(
+                        tasks.iter().map(|task| &**task),
+                        self.task_stats.window,
+                        now,
+                    )
+                    .render(&self.styles, frame, area);
+                }
                 &self.tasks_list
             }
             ViewState::ResourcesList => {
@@ -233,30 +1269,193 @@ impl View {
                     .render(&self.styles, frame, area, state, ());
                 &self.resources_list
             }
+            ViewState::Bookmarks(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::TaskTree(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::TaskGroups(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::Crashed(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::NetSummary(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::Lifetimes(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+                view
+            }
+            ViewState::Diff(ref mut view) => {
+                view.render(&self.styles, frame, area, state, self.snapshot.as_ref());
+                view
+            }
             ViewState::TaskInstance(ref mut view) => {
-                let now = state
-                    .last_updated_at()
-                    .expect("task view implies we've received an update");
-                view.render(&self.styles, frame, area, now);
+                view.render(&self.styles, frame, area, state, self.task_stats);
                 view
             }
             ViewState::ResourceInstance(ref mut view) => {
                 view.render(&self.styles, frame, area, state);
-                view
+                view.as_mut()
+            }
+            ViewState::Plugin(idx) => {
+                let plugin = &mut self.plugins[idx];
+                self::plugin::render(plugin.as_mut(), &self.styles, frame, area, state);
+                plugin
             }
         };
 
+        if let Some(area) = event_log_area {
+            EventLogPane::new(state.event_log(), self.event_log_severity).render(
+                &self.styles,
+                frame,
+                area,
+            );
+        }
+
         state.retain_active();
 
         if self.show_help_modal {
-            let mut help_view = HelpView::new(help_text.render_help_content(&self.styles));
-            help_view.render(&self.styles, frame, area, state);
+            HelpView::new(help_text, &self.styles, &self.keymap).render(
+                &self.styles,
+                frame,
+                &mut self.help_scroll,
+            );
+        }
+
+        if let Some(ref editor) = self.note_editor {
+            editor.render(&self.styles, frame);
+        }
+
+        if let Some(ref prompt) = self.snapshot_prompt {
+            prompt.render(&self.styles, frame);
+        }
+
+        if let Some(ref prompt) = self.highlight_prompt {
+            prompt.render_with(
+                &self.styles,
+                frame,
+                vec![ratatui::text::Line::from(
+                    "separate rules with `;`, such as `busy > 500ms => red; idle > 1h => dim`",
+                )],
+            );
+        }
+
+        if let Some(ref prompt) = self.filter_prompt {
+            prompt.render_with(
+                &self.styles,
+                frame,
+                vec![ratatui::text::Line::from(
+                    "such as `state == idle && busy > 1s && target ~ \"hyper\"`, or nothing to show every task",
+                )],
+            );
+        }
+
+        if let Some(ref dialog) = self.connect_dialog {
+            dialog.render(&self.styles, frame);
+        }
+
+        if let Some(ref chooser) = self.column_chooser {
+            chooser.render(&self.styles, frame, &self.tasks_list);
+        }
+
+        if let Some(ref popup) = self.fields_popup {
+            popup.render(&self.styles, frame);
         }
     }
 
     pub(crate) fn current_view(&self) -> &ViewState {
         &self.state
     }
+
+    pub(crate) fn settings(&self) -> ViewSettings {
+        let view = match self.state {
+            ViewState::TasksList | ViewState::TaskInstance(_) => SavedView::Tasks,
+            ViewState::ResourcesList | ViewState::ResourceInstance(_) => SavedView::Resources,
+            ViewState::NetSummary(_) => SavedView::Network,
+            ViewState::Bookmarks(_) => SavedView::Bookmarks,
+            ViewState::TaskTree(_) => SavedView::TaskTree,
+            ViewState::TaskGroups(_) => SavedView::TaskGroups,
+            ViewState::Crashed(_) => SavedView::Crashed,
+            ViewState::Lifetimes(_) => SavedView::Timeline,
+            // Plugins may not be compiled into the next session's console,
+            // and snapshots aren't saved.
+            ViewState::Plugin(_) | ViewState::Diff(_) => SavedView::Tasks,
+        };
+        ViewSettings {
+            view,
+            tasks: self.tasks_list.settings(),
+            resources: self.resources_list.settings(),
+            async_ops: self.async_ops_sort.clone(),
+        }
+    }
+
+    pub(crate) fn restore_settings(&mut self, settings: ViewSettings) {
+        self.state = match settings.view {
+            SavedView::Tasks => ViewState::TasksList,
+            SavedView::Resources => ViewState::ResourcesList,
+            SavedView::Network => ViewState::NetSummary(self::net::NetView::default()),
+            SavedView::Bookmarks => ViewState::Bookmarks(self::bookmarks::BookmarksView::default()),
+            SavedView::TaskTree => ViewState::TaskTree(self::tree::TaskTreeView::default()),
+            SavedView::TaskGroups => ViewState::TaskGroups(self::groups::TaskGroupsView::default()),
+            SavedView::Crashed => ViewState::Crashed(self::crashed::CrashedView::default()),
+            SavedView::Timeline => ViewState::Lifetimes(self::lifetimes::LifetimesView::default()),
+        };
+        self.tasks_list.restore_settings(settings.tasks);
+        self.resources_list.restore_settings(settings.resources);
+        self.async_ops_sort = settings.async_ops;
+    }
+}
+
+/// Returns the format to export a table in, if `event` exports one.
+fn export_format(event: &input::Event) -> Option<export::Format> {
+    match event {
+        key!(Char('e')) => Some(export::Format::Csv),
+        key!(Char('E')) => Some(export::Format::Json),
+        _ => None,
+    }
+}
+
+/// Copies `text` to the clipboard, returning a description of `what` was
+/// copied if it was.
+fn copy(what: String, text: &str) -> Result<String, String> {
+    clipboard::copy(text)
+        .map(|()| what)
+        .map_err(|error| error.to_string())
+}
+
+/// Returns the prompt for editing the task list's highlight rules, which
+/// starts with `rules`, and shows `error` if they were rejected.
+fn highlight_prompt(
+    rules: String,
+    error: Option<crate::highlight::ParseError>,
+) -> self::prompt::Prompt {
+    let prompt = self::prompt::Prompt::new("Highlight rules", rules)
+        .with_max_len(1024)
+        .with_submit_action("apply");
+    match error {
+        Some(error) => prompt.with_error(error.to_string()),
+        None => prompt,
+    }
+}
+
+/// Returns the prompt for editing the task list's filter, which starts with
+/// `filter`, and shows `error` if it was rejected.
+fn filter_prompt(filter: String, error: Option<crate::query::ParseError>) -> self::prompt::Prompt {
+    let prompt = self::prompt::Prompt::new("Filter tasks", filter)
+        .with_max_len(1024)
+        .with_submit_action("apply");
+    match error {
+        Some(error) => prompt.with_error(error.to_string()),
+        None => prompt,
+    }
 }
 
 pub(crate) fn bold<'a>(text: impl Into<Cow<'a, str>>) -> Span<'a> {
@@ -287,3 +1486,1068 @@ impl Width {
         self.curr
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ViewOptions,
+        state::{
+            fixtures::{self, location, timestamp},
+            tasks::Dump,
+        },
+    };
+    use clap::Parser;
+    use console_api as proto;
+    use ratatui::{backend::TestBackend, buffer::Buffer, style::Modifier, Terminal};
+    use std::{fs, path::PathBuf};
+
+    const WIDTH: u16 = 120;
+    const HEIGHT: u16 = 20;
+
+    /// Returns a state with a running task, an idle task, and a task which
+    /// panicked, the first of which is bookmarked.
+    fn state(styles: &Styles) -> State {
+        let metadata = proto::register_metadata::NewMetadata {
+            id: Some(proto::MetaId { id: 1 }),
+            metadata: Some(proto::Metadata {
+                name: "runtime.spawn".to_owned(),
+                target: "tokio::task".to_owned(),
+                location: Some(location(1)),
+                ..Default::default()
+            }),
+        };
+        let task = |id: u64, polls, panic: bool| {
+            let mut task = proto::tasks::Task {
+                location: Some(location(10 + id as u32)),
+                ..fixtures::task(id)
+            };
+            task.fields.push(proto::Field {
+                metadata_id: Some(proto::MetaId { id: 1 }),
+                name: Some(proto::field::Name::StrName("task.name".to_owned())),
+                value: Some(proto::field::Value::StrVal(format!("worker-{id}"))),
+            });
+            let started = if id == 1 {
+                timestamp(55)
+            } else {
+                timestamp(50)
+            };
+            let stats = proto::tasks::Stats {
+                created_at: Some(timestamp(0)),
+                dropped_at: panic.then(|| timestamp(50)),
+                wakes: polls,
+                poll_stats: Some(proto::PollStats {
+                    polls,
+                    first_poll: Some(timestamp(1)),
+                    last_poll_started: Some(started),
+                    last_poll_ended: (id != 1).then(|| timestamp(51)),
+                    busy_time: Some(prost_types::Duration {
+                        seconds: 3,
+                        nanos: 0,
+                    }),
+                }),
+                panic: panic.then(|| proto::tasks::Panic {
+                    message: Some("oh no".to_owned()),
+                    location: Some(location(40)),
+                }),
+                ..Default::default()
+            };
+            (task, (id, stats))
+        };
+        let (new_tasks, stats_update) = [task(1, 10, false), task(2, 4, false), task(3, 1, true)]
+            .into_iter()
+            .unzip();
+        let update = proto::instrument::Update {
+            now: Some(timestamp(60)),
+            new_metadata: Some(proto::RegisterMetadata {
+                metadata: vec![metadata],
+            }),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks,
+                stats_update,
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+
+        let mut state = State::default();
+        state.update(styles, &ViewState::TasksList, update);
+        first_task(&state).borrow_mut().toggle_bookmark();
+        state
+    }
+
+    fn first_task(state: &State) -> Rc<RefCell<Task>> {
+        state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+            .find(|task| task.borrow().span_id() == 1)
+            .expect("the first task should be in the state")
+    }
+
+    /// Renders the buffer's text, followed by its styles, with each cell's
+    /// style as a digit: 1 for bold, 2 for reversed and 4 for underlined,
+    /// added together, or `.` for no style.
+    fn snapshot(buffer: &Buffer) -> String {
+        let mut text = String::new();
+        let mut styles = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                let cell = buffer.get(x, y);
+                text.push_str(cell.symbol());
+                let mut style = 0;
+                for (bit, modifier) in [
+                    (1, Modifier::BOLD),
+                    (2, Modifier::REVERSED),
+                    (4, Modifier::UNDERLINED),
+                ] {
+                    if cell.modifier.contains(modifier) {
+                        style += bit;
+                    }
+                }
+                styles.push(match style {
+                    0 => '.',
+                    style => char::from_digit(style, 10).unwrap(),
+                });
+            }
+            text = text.trim_end().to_owned();
+            text.push('\n');
+            styles.push('\n');
+        }
+        format!("{text}\n{styles}")
+    }
+
+    #[test]
+    fn exports_displayed_rows() {
+        use crate::query::Value;
+
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles).with_task_list_columns(
+            ["name", "polls", "id"]
+                .iter()
+                .map(|column| column.parse().unwrap())
+                .collect(),
+        );
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut draw = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+        };
+        draw(&mut view, &mut state);
+        // Sort by the number of polls, the column after the name, with the
+        // most polls first, and search for two of the tasks.
+        let keys = [input::KeyCode::Right, input::KeyCode::Char('/')]
+            .into_iter()
+            .chain("worker-[13]".chars().map(input::KeyCode::Char))
+            .chain([input::KeyCode::Enter]);
+        for key in keys {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), &state);
+        }
+        draw(&mut view, &mut state);
+
+        let context = TasksContext::default();
+        let (header, rows) = view
+            .tasks_list
+            .displayed_values(&state, &context)
+            .expect("an update was received");
+        assert_eq!(header, ["Name", "Polls", "ID"]);
+        let text = |text: &str| Value::Text(text.to_owned());
+        assert_eq!(
+            rows,
+            [
+                vec![text("worker-1"), Value::Number(10.0), Value::Number(1.0)],
+                vec![text("worker-3"), Value::Number(1.0), Value::Number(3.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn copies_task_details_as_text() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let state = state(&styles);
+        let now = state.last_updated_at().unwrap();
+        let text = task::details_text(&first_task(&state).borrow(), StatsDisplay::default(), now);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "Task 1 (running)");
+        assert!(lines.contains(&"Name: worker-1"), "{text}");
+        assert!(lines.contains(&"Target: tokio::task"), "{text}");
+        assert!(lines.contains(&"Polls: 10"), "{text}");
+    }
+
+    #[test]
+    fn sorts_by_configured_column() {
+        use crate::query::Value;
+
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let sort = "Polls:asc".parse::<SortConfig>().unwrap();
+        let mut view = View::new(styles)
+            .with_task_list_columns(vec!["polls".parse().unwrap()])
+            .with_task_list_sort(Some(&sort), &state);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        terminal
+            .draw(|frame| view.render(frame, frame.size(), &mut state))
+            .unwrap();
+
+        let (_, rows) = view
+            .tasks_list
+            .displayed_values(&state, &TasksContext::default())
+            .expect("an update was received");
+        let polls = rows.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(
+            polls,
+            [Value::Number(1.0), Value::Number(4.0), Value::Number(10.0)]
+        );
+    }
+
+    #[test]
+    fn bookmarking_resorts_the_task_list() {
+        use crate::query::Value;
+
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let sort = "Mark:asc".parse::<SortConfig>().unwrap();
+        let mut view = View::new(styles)
+            .with_task_list_columns(vec!["mark".parse().unwrap()])
+            .with_task_list_sort(Some(&sort), &state);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut marks = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            let (_, rows) = view
+                .tasks_list
+                .displayed_values(state, &TasksContext::default())
+                .expect("an update was received");
+            rows.into_iter().flatten().collect::<Vec<_>>()
+        };
+        let press = |view: &mut View, state: &State, code| {
+            let key = input::KeyEvent::new(code, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+        };
+        let bookmarked = || Value::Text("bookmarked".to_owned());
+
+        assert_eq!(
+            marks(&mut view, &mut state),
+            [Value::Null, Value::Null, bookmarked()]
+        );
+        // Moving the selection doesn't sort the rows again, but bookmarking
+        // the selected task moves it among the other bookmarked tasks.
+        press(&mut view, &state, input::KeyCode::Home);
+        press(&mut view, &state, input::KeyCode::Char('b'));
+        assert_eq!(
+            marks(&mut view, &mut state),
+            [Value::Null, bookmarked(), bookmarked()]
+        );
+    }
+
+    #[test]
+    fn help_lists_rebound_keys_and_scrolls() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut keymap = Keymap::default();
+        keymap.bind("J".parse().unwrap(), "pagedown".parse().unwrap());
+        let mut view = View::new(styles).with_keymap(keymap);
+
+        let lines = controls::help_lines(&view.tasks_list.controls(), &view.styles, &view.keymap);
+        let text = |line: &ratatui::text::Line<'_>| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+        assert!(
+            lines.iter().any(|line| text(line)
+                == "  scroll half a page = page up, page down or ctrl-u, ctrl-d or J"),
+            "{:?}",
+            lines.iter().map(text).collect::<Vec<_>>()
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            snapshot(terminal.backend().buffer())
+        };
+
+        let help = press(&mut view, &mut state, input::KeyCode::Char('?'));
+        assert!(help.contains(" to scroll)"), "{help}");
+        assert!(!help.contains("J acts as pagedown"), "{help}");
+
+        let end = press(&mut view, &mut state, input::KeyCode::End);
+        assert!(end.contains("J acts as pagedown"), "{end}");
+
+        // Scrolling past the end stays at the end.
+        let down = press(&mut view, &mut state, input::KeyCode::Down);
+        assert_eq!(down, end);
+
+        let top = press(&mut view, &mut state, input::KeyCode::Home);
+        assert_eq!(top, help);
+    }
+
+    #[test]
+    fn event_log_shows_changes() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let task = proto::tasks::Task {
+            location: Some(location(14)),
+            ..fixtures::task(4)
+        };
+        let stats = |created, dropped| proto::tasks::Stats {
+            created_at: Some(timestamp(created)),
+            dropped_at: dropped,
+            poll_stats: Some(proto::PollStats::default()),
+            ..Default::default()
+        };
+        let update = proto::instrument::Update {
+            now: Some(timestamp(61)),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks: vec![task],
+                stats_update: [(4, stats(61, None)), (2, stats(0, Some(timestamp(61))))]
+                    .into_iter()
+                    .collect(),
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+        state.update(&styles, &ViewState::TasksList, update);
+
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, 30)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(input::KeyCode::Char(key), input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            snapshot(terminal.backend().buffer())
+        };
+
+        // The tasks in the first update weren't spawned since connecting.
+        let log = press(&mut view, &mut state, 'V');
+        assert!(log.contains("Events (info and above"), "{log}");
+        assert!(log.contains("INFO  task 4 spawned"), "{log}");
+        assert!(log.contains("INFO  task 2 (worker-2) completed"), "{log}");
+        assert!(!log.contains("worker-1"), "{log}");
+
+        let warnings = press(&mut view, &mut state, '!');
+        assert!(warnings.contains("Events (warn and above"), "{warnings}");
+        assert!(!warnings.contains("task 4 spawned"), "{warnings}");
+
+        let hidden = press(&mut view, &mut state, 'V');
+        assert!(!hidden.contains("Events ("), "{hidden}");
+    }
+
+    #[test]
+    fn highlights_matching_tasks() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view =
+            View::new(styles).with_highlights(vec!["polls > 5 => reversed".parse().unwrap()]);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, keys: &str| {
+            for key in keys.chars() {
+                let key = match key {
+                    '\n' => input::KeyCode::Enter,
+                    '\x08' => input::KeyCode::Backspace,
+                    key => input::KeyCode::Char(key),
+                };
+                let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+                view.update_input(input::Event::Key(key), state);
+            }
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            // Whether the row of each of the named tasks is highlighted.
+            ["worker-1", "worker-2", "worker-3"].map(|name| {
+                let text = snapshot(&buffer);
+                let y = text.lines().position(|line| line.contains(name)).unwrap();
+                let x = text.lines().nth(y).unwrap().find(name).unwrap();
+                buffer
+                    .get(x as u16, y as u16)
+                    .modifier
+                    .contains(Modifier::REVERSED)
+            })
+        };
+
+        // Only the task with 10 polls matches the rule.
+        assert_eq!(press(&mut view, &mut state, ""), [true, false, false]);
+
+        // The rules are edited in a prompt, which keeps them if a rule is
+        // invalid.
+        press(&mut view, &mut state, "A; nope\n");
+        assert!(view.highlight_prompt.is_some());
+        assert_eq!(
+            view.highlight_prompt.as_ref().unwrap().text(),
+            "polls > 5 => reversed; nope"
+        );
+        press(&mut view, &mut state, &"\x08".repeat(27));
+        assert_eq!(
+            press(&mut view, &mut state, "polls < 5 => reversed\n"),
+            [false, true, true]
+        );
+        assert!(view.highlight_prompt.is_none());
+    }
+
+    #[test]
+    fn filters_tasks() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, keys: &str| {
+            for key in keys.chars() {
+                let key = match key {
+                    '\n' => input::KeyCode::Enter,
+                    '\x08' => input::KeyCode::Backspace,
+                    key => input::KeyCode::Char(key),
+                };
+                let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+                view.update_input(input::Event::Key(key), state);
+            }
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            // Which of the named tasks are listed.
+            let text = snapshot(terminal.backend().buffer());
+            ["worker-1", "worker-2", "worker-3"].map(|name| text.contains(name))
+        };
+
+        assert_eq!(press(&mut view, &mut state, ""), [true, true, true]);
+
+        // An invalid filter is shown again to be fixed.
+        press(&mut view, &mut state, "|polls >\n");
+        assert!(view.filter_prompt.is_some());
+        assert!(view.task_filter.is_none());
+
+        assert_eq!(
+            press(&mut view, &mut state, " 5 && name ~ worker\n"),
+            [true, false, false]
+        );
+        assert!(view.filter_prompt.is_none());
+
+        // Submitting nothing shows every task again.
+        press(&mut view, &mut state, "|");
+        let filter = view.filter_prompt.as_ref().unwrap().text().to_owned();
+        assert_eq!(filter, "polls > 5 && name ~ worker");
+        let clear = "\x08".repeat(filter.len()) + "\n";
+        assert_eq!(press(&mut view, &mut state, &clear), [true, true, true]);
+        assert!(view.task_filter.is_none());
+    }
+
+    #[test]
+    fn remembers_async_ops_sort() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let resource = |id| proto::resources::Resource {
+            id: Some(proto::Id { id }),
+            metadata: Some(proto::MetaId { id: 1 }),
+            concrete_type: "Sleep".to_owned(),
+            kind: Some(proto::resources::resource::Kind {
+                kind: Some(proto::resources::resource::kind::Kind::Known(0)),
+            }),
+            ..Default::default()
+        };
+        let stats = proto::resources::Stats {
+            created_at: Some(timestamp(0)),
+            ..Default::default()
+        };
+        let update = proto::instrument::Update {
+            now: Some(timestamp(61)),
+            resource_update: Some(proto::resources::ResourceUpdate {
+                new_resources: vec![resource(10), resource(11)],
+                stats_update: [(10, stats.clone()), (11, stats)].into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        state.update(&styles, &ViewState::ResourcesList, update);
+
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+        };
+        let async_ops_sort = |view: &View| match view.state {
+            ViewState::ResourceInstance(ref resource) => resource.async_ops_table.settings(),
+            _ => panic!("a resource should be inspected"),
+        };
+
+        press(&mut view, &mut state, input::KeyCode::Char('r'));
+        press(&mut view, &mut state, input::KeyCode::Char('j'));
+        press(&mut view, &mut state, input::KeyCode::Enter);
+        let default_sort = async_ops_sort(&view);
+        press(&mut view, &mut state, input::KeyCode::Right);
+        press(&mut view, &mut state, input::KeyCode::Char('+'));
+        let sort = async_ops_sort(&view);
+        assert_ne!(sort, default_sort);
+
+        // Inspecting the other resource sorts its async ops the same way.
+        press(&mut view, &mut state, input::KeyCode::Esc);
+        press(&mut view, &mut state, input::KeyCode::Char('j'));
+        press(&mut view, &mut state, input::KeyCode::Enter);
+        assert_eq!(async_ops_sort(&view), sort);
+
+        let mut restored = View::new(view.styles.clone());
+        restored.restore_settings(view.settings());
+        assert_eq!(restored.async_ops_sort, Some(sort));
+    }
+
+    #[test]
+    fn task_tree_rolls_up_subtrees() {
+        let styles = Styles::from_config(ViewOptions::parse_from([
+            "tokio-console",
+            "--ascii-only",
+            "true",
+        ]));
+        let mut state = state(&styles);
+        // Task 4 was spawned by task 1, and task 5 by task 4.
+        let child = |id: u64, parent: u64| {
+            let task = proto::tasks::Task {
+                parents: vec![proto::SpanId { id: parent }],
+                location: Some(location(20 + id as u32)),
+                ..fixtures::task(id)
+            };
+            let stats = proto::tasks::Stats {
+                created_at: Some(timestamp(2)),
+                poll_stats: Some(proto::PollStats {
+                    polls: 1,
+                    busy_time: Some(prost_types::Duration {
+                        seconds: 1,
+                        nanos: 0,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            (task, (id, stats))
+        };
+        let (new_tasks, stats_update) = [child(4, 1), child(5, 4)].into_iter().unzip();
+        let update = proto::instrument::Update {
+            now: Some(timestamp(61)),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks,
+                stats_update,
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+        state.update(&styles, &ViewState::TasksList, update);
+
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let row = |lines: &[String], text: &str| {
+            lines
+                .iter()
+                .find(|line| line.contains(text))
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        };
+        let branch = |lines: &[String], location: &str| {
+            let line = lines.iter().find(|line| line.contains(location))?;
+            line.find('`')
+        };
+
+        let lines = press(&mut view, &mut state, input::KeyCode::Char('t'));
+        assert!(matches!(view.state, ViewState::TaskTree(_)));
+        // Task 1 and its subtree are 3 tasks. Task 1 has been busy for 9s,
+        // since it's being polled, and the others for 1s each.
+        let root = row(&lines, "- worker-1").expect("task 1 should be a root");
+        assert!(root.contains(" 3 11s "), "{root}");
+        let child = branch(&lines, "src/main.rs:24:5").expect("task 4 should be under task 1");
+        let grandchild = branch(&lines, "src/main.rs:25:5").expect("task 5 should be under task 4");
+        assert!(grandchild > child);
+
+        // Collapsing task 1 hides the tasks it spawned.
+        let lines = press(&mut view, &mut state, input::KeyCode::Char('h'));
+        let root = row(&lines, "+ worker-1").expect("task 1 should be collapsed");
+        assert!(root.contains(" 3 11s "), "{root}");
+        assert_eq!(branch(&lines, "src/main.rs:24:5"), None);
+
+        press(&mut view, &mut state, input::KeyCode::Char('t'));
+        assert!(matches!(view.state, ViewState::TasksList));
+    }
+
+    #[test]
+    fn task_groups_roll_up_members() {
+        let styles = Styles::from_config(ViewOptions::parse_from([
+            "tokio-console",
+            "--ascii-only",
+            "true",
+        ]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            snapshot(terminal.backend().buffer())
+        };
+        let row = |screen: &str, text: &str| {
+            screen
+                .lines()
+                .find(|line| line.contains(text))
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        };
+
+        // Each task has its own name.
+        let screen = press(&mut view, &mut state, input::KeyCode::Char('g'));
+        assert!(screen.contains("Task Groups by name (3)"), "{screen}");
+
+        // Every task has the same target. Task 1 has been busy for 8s, since
+        // it's being polled, and the others for 3s each, over 15 polls.
+        let screen = press(&mut view, &mut state, input::KeyCode::Char('g'));
+        let group = row(&screen, "+ tokio::task").expect("the tasks should be grouped");
+        assert!(group.contains(" 3 14s "), "{group}");
+        assert_eq!(row(&screen, "worker-1"), None);
+
+        let screen = press(&mut view, &mut state, input::KeyCode::Enter);
+        assert!(row(&screen, "- tokio::task").is_some(), "{screen}");
+        let member = row(&screen, "1 (worker-1)").expect("the group should be expanded");
+        assert!(member.contains(" 8s 800ms "), "{member}");
+        press(&mut view, &mut state, input::KeyCode::Down);
+        press(&mut view, &mut state, input::KeyCode::Enter);
+        assert!(matches!(view.state, ViewState::TaskInstance(_)));
+
+        press(&mut view, &mut state, input::KeyCode::Esc);
+        press(&mut view, &mut state, input::KeyCode::Char('g'));
+        press(&mut view, &mut state, input::KeyCode::Char('g'));
+        press(&mut view, &mut state, input::KeyCode::Char('g'));
+        assert!(matches!(view.state, ViewState::TaskGroups(_)));
+        press(&mut view, &mut state, input::KeyCode::Char('g'));
+        assert!(matches!(view.state, ViewState::TasksList));
+    }
+
+    #[test]
+    fn fields_scroll_and_popup() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            snapshot(terminal.backend().buffer())
+        };
+
+        // The tasks are only in the list once it has been drawn.
+        press(&mut view, &mut state, input::KeyCode::Home);
+        press(&mut view, &mut state, input::KeyCode::Home);
+        let screen = press(&mut view, &mut state, input::KeyCode::Char(']'));
+        assert!(screen.contains("fields scrolled by 8"), "{screen}");
+        assert!(screen.contains(" okio::task "), "{screen}");
+
+        // The popup shows the selected task's fields in full.
+        let screen = press(&mut view, &mut state, input::KeyCode::Char('u'));
+        assert!(screen.contains("Fields of task 2 (worker-2)"), "{screen}");
+        assert!(screen.contains("target=tokio::task "), "{screen}");
+        let screen = press(&mut view, &mut state, input::KeyCode::Esc);
+        assert!(!screen.contains("Fields of task"), "{screen}");
+
+        let screen = press(&mut view, &mut state, input::KeyCode::Char('['));
+        assert!(!screen.contains("fields scrolled"), "{screen}");
+        assert!(screen.contains(" target=tokio::task "), "{screen}");
+    }
+
+    #[test]
+    fn alert_banner_until_key() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut draw = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            snapshot(terminal.backend().buffer())
+        };
+
+        assert!(!view.is_alerting());
+        view.show_alert("alert `busy > 2s` triggered by task 1".to_owned());
+        let screen = draw(&mut view, &mut state);
+        assert!(view.is_alerting());
+        assert!(
+            screen.contains("ALERT: alert `busy > 2s` triggered by task 1"),
+            "{screen}"
+        );
+
+        // A newer alert replaces the message, and counts the older ones.
+        view.show_alert("alert `polls > 1` triggered by task 2".to_owned());
+        let screen = draw(&mut view, &mut state);
+        assert!(screen.contains("task 2 (and 1 more)"), "{screen}");
+
+        let key = input::KeyEvent::new(input::KeyCode::Home, input::KeyModifiers::NONE);
+        view.update_input(input::Event::Key(key), &state);
+        let screen = draw(&mut view, &mut state);
+        assert!(!view.is_alerting());
+        assert!(!screen.contains("ALERT"), "{screen}");
+    }
+
+    #[test]
+    fn diff_shows_changes_since_snapshot() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles.clone());
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut press = |view: &mut View, state: &mut State, key| {
+            let key = input::KeyEvent::new(key, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let row = |lines: &[String], location: &str| {
+            lines
+                .iter()
+                .position(|line| line.contains(location))
+                .map(|idx| {
+                    (
+                        idx,
+                        lines[idx].split_whitespace().collect::<Vec<_>>().join(" "),
+                    )
+                })
+        };
+
+        press(&mut view, &mut state, input::KeyCode::Char('m'));
+        press(&mut view, &mut state, input::KeyCode::Enter);
+
+        // Task 2 is polled twice more, for 2s, and task 4 is spawned.
+        let stats = |polls, busy| proto::tasks::Stats {
+            created_at: Some(timestamp(0)),
+            wakes: polls,
+            poll_stats: Some(proto::PollStats {
+                polls,
+                first_poll: Some(timestamp(1)),
+                last_poll_started: Some(timestamp(60)),
+                last_poll_ended: Some(timestamp(61)),
+                busy_time: Some(prost_types::Duration {
+                    seconds: busy,
+                    nanos: 0,
+                }),
+            }),
+            ..Default::default()
+        };
+        let update = proto::instrument::Update {
+            now: Some(timestamp(61)),
+            task_update: Some(proto::tasks::TaskUpdate {
+                new_tasks: vec![proto::tasks::Task {
+                    location: Some(location(14)),
+                    ..fixtures::task(4)
+                }],
+                stats_update: [(2, stats(6, 5)), (4, stats(1, 1))].into_iter().collect(),
+                dropped_events: 0,
+            }),
+            ..Default::default()
+        };
+        state.update(&styles, &ViewState::TasksList, update);
+
+        // Task 1 has been busy for another second, since it's being polled.
+        let lines = press(&mut view, &mut state, input::KeyCode::Char('v'));
+        assert!(matches!(view.state, ViewState::Diff(_)));
+        let (task_2, text) = row(&lines, "src/main.rs:12:5").expect("task 2 changed");
+        assert!(text.contains(" 2s 2 +0 "), "{text}");
+        let (task_4, text) = row(&lines, "src/main.rs:14:5").expect("task 4 is new");
+        assert!(text.contains("new 1s 1 +0 "), "{text}");
+        let (task_1, _) = row(&lines, "src/main.rs:11:5").expect("task 1 changed");
+        assert!(task_2 < task_4 && task_4 < task_1);
+        assert_eq!(
+            row(&lines, "src/main.rs:13:5"),
+            None,
+            "task 3 didn't change"
+        );
+
+        // Sorting by polls keeps task 1, which wasn't polled again, last.
+        let lines = press(&mut view, &mut state, input::KeyCode::Right);
+        let (task_1, text) = row(&lines, "src/main.rs:11:5").expect("task 1 changed");
+        assert!(text.contains(" 1s 0 +0 "), "{text}");
+        assert!(row(&lines, "src/main.rs:14:5").unwrap().0 < task_1);
+
+        press(&mut view, &mut state, input::KeyCode::Esc);
+        assert!(matches!(view.state, ViewState::TasksList));
+    }
+
+    #[test]
+    fn distributions_cover_displayed_tasks() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut draw = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        let type_keys = |view: &mut View, state: &State, keys: &str| {
+            for key in keys.chars() {
+                let key =
+                    input::KeyEvent::new(input::KeyCode::Char(key), input::KeyModifiers::NONE);
+                view.update_input(input::Event::Key(key), state);
+            }
+        };
+
+        assert!(!draw(&mut view, &mut state).contains("Mean Poll Times"));
+        type_keys(&mut view, &state, "d");
+        let text = draw(&mut view, &mut state);
+        assert!(text.contains("Mean Poll Times (3)"), "{text}");
+        assert!(text.contains("Mean Sched Times (3)"), "{text}");
+
+        // Only the tasks which match the search are included.
+        type_keys(&mut view, &state, "/worker-[13]");
+        let enter = input::KeyEvent::new(input::KeyCode::Enter, input::KeyModifiers::NONE);
+        view.update_input(input::Event::Key(enter), &state);
+        let text = draw(&mut view, &mut state);
+        assert!(text.contains("Mean Poll Times (2)"), "{text}");
+    }
+
+    #[test]
+    fn flamegraph_key_keeps_completed_tasks() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let key = input::Event::Key(input::KeyEvent::new(
+            input::KeyCode::Char('Z'),
+            input::KeyModifiers::NONE,
+        ));
+
+        // The console handles its global keys, such as clearing completed
+        // tasks, before the view sees the key.
+        if input::is_clear_completed(&key) {
+            state.clear_completed();
+        }
+        view.update_input(key, &state);
+
+        let path = view.exported().expect("the flamegraph is exported");
+        std::fs::remove_file(path.as_ref().unwrap()).unwrap();
+        assert_eq!(state.tasks_state().tasks().count(), 3);
+    }
+
+    #[test]
+    fn pinned_task_stays_selected() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut draw = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+        };
+        let press = |view: &mut View, state: &State, code: input::KeyCode| {
+            let key = input::KeyEvent::new(code, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+        };
+        let selected = |view: &View| {
+            let task = view.tasks_list.selected_item().expect("a task is selected");
+            let id = task.borrow().id();
+            id
+        };
+
+        draw(&mut view, &mut state);
+        press(&mut view, &state, input::KeyCode::Char('G'));
+        press(&mut view, &state, input::KeyCode::Char('P'));
+        let pinned = selected(&view);
+
+        // Reversing the sorting moves the pinned task to another row, but it
+        // stays selected.
+        press(&mut view, &state, input::KeyCode::Char('i'));
+        draw(&mut view, &mut state);
+        assert_eq!(selected(&view), pinned);
+
+        // Unpinned, the selection still stays on the task when the list is
+        // re-sorted.
+        press(&mut view, &state, input::KeyCode::Char('P'));
+        press(&mut view, &state, input::KeyCode::Char('i'));
+        draw(&mut view, &mut state);
+        assert_eq!(selected(&view), pinned);
+    }
+
+    #[test]
+    fn home_and_end_jump_to_first_and_last_rows() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let mut view = View::new(styles);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut draw = |view: &mut View, state: &mut State| {
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), state))
+                .unwrap();
+            view.tasks_list.table_state.selected()
+        };
+        let mut press = |view: &mut View, state: &mut State, code: input::KeyCode| {
+            let key = input::KeyEvent::new(code, input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+            draw(view, state)
+        };
+
+        press(&mut view, &mut state, input::KeyCode::Down);
+        assert_eq!(press(&mut view, &mut state, input::KeyCode::End), Some(2));
+        assert_eq!(press(&mut view, &mut state, input::KeyCode::Home), Some(0));
+    }
+
+    /// Renders each view in high contrast mode, checking that it only uses
+    /// bold, reversed and underlined text, and that it matches its snapshot
+    /// in `tests/snapshots`.
+    ///
+    /// Set `UPDATE_SNAPSHOTS` to write the snapshots instead.
+    #[test]
+    fn high_contrast_views() {
+        let options =
+            ViewOptions::parse_from(["tokio-console", "--high-contrast", "--lang", "en_US.UTF-8"]);
+        let styles = Styles::from_config(options);
+        let mut state = state(&styles);
+        let task = first_task(&state);
+        let views = [
+            ("tasks", ViewState::TasksList),
+            ("resources", ViewState::ResourcesList),
+            ("network", ViewState::NetSummary(Default::default())),
+            ("bookmarks", ViewState::Bookmarks(Default::default())),
+            ("tree", ViewState::TaskTree(Default::default())),
+            ("groups", ViewState::TaskGroups(Default::default())),
+            ("crashed", ViewState::Crashed(Default::default())),
+            ("timeline", ViewState::Lifetimes(Default::default())),
+            ("diff", ViewState::Diff(Default::default())),
+            (
+                "task",
+                ViewState::TaskInstance(self::task::TaskView::new(task, state.task_details_ref())),
+            ),
+        ];
+
+        let mut mismatched = Vec::new();
+        for (name, view_state) in views {
+            let mut view = View::new(styles.clone());
+            view.state = view_state;
+            let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+            terminal
+                .draw(|frame| view.render(frame, frame.size(), &mut state))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let allowed = Modifier::BOLD | Modifier::REVERSED | Modifier::UNDERLINED;
+            for cell in &buffer.content {
+                assert_eq!(cell.fg, style::Color::Reset, "{name} view used a color");
+                assert_eq!(cell.bg, style::Color::Reset, "{name} view used a color");
+                assert!(
+                    allowed.contains(cell.modifier),
+                    "{name} view used {:?}",
+                    cell.modifier
+                );
+            }
+
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/snapshots")
+                .join(format!("high_contrast_{name}.txt"));
+            let actual = snapshot(buffer);
+            if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, actual).unwrap();
+            } else if fs::read_to_string(&path).ok().as_deref() != Some(&actual) {
+                mismatched.push(format!("{}:\n{actual}", path.display()));
+            }
+        }
+        assert!(
+            mismatched.is_empty(),
+            "views didn't match their snapshots (set UPDATE_SNAPSHOTS to update them):\n{}",
+            mismatched.join("\n")
+        );
+    }
+
+    #[test]
+    fn dumps_and_aborts_the_displayed_task() {
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = state(&styles);
+        let task = first_task(&state);
+        let span_id = task.borrow().span_id();
+        let mut view = View::new(styles);
+        view.state =
+            ViewState::TaskInstance(self::task::TaskView::new(task, state.task_details_ref()));
+        let press = |view: &mut View, state: &State, key| {
+            let key = input::KeyEvent::new(input::KeyCode::Char(key), input::KeyModifiers::NONE);
+            view.update_input(input::Event::Key(key), state);
+        };
+
+        press(&mut view, &state, 'I');
+        assert_eq!(view.take_task_request(), Some((span_id, TaskRequest::Dump)));
+        assert_eq!(view.take_task_request(), None);
+
+        // Aborting has to be asked for twice in a row.
+        press(&mut view, &state, 'x');
+        assert_eq!(view.take_task_request(), None);
+        press(&mut view, &state, 'w');
+        press(&mut view, &state, 'x');
+        assert_eq!(view.take_task_request(), None);
+        press(&mut view, &state, 'x');
+        assert_eq!(
+            view.take_task_request(),
+            Some((span_id, TaskRequest::Abort))
+        );
+
+        let task_view = view.task_view(span_id).expect("the task is displayed");
+        task_view.dumped(Ok(Dump {
+            span_id,
+            taken_at: None,
+            location: "src/main.rs:11:5".to_owned(),
+            fields: vec![("task.name".to_owned(), "worker-1".to_owned())],
+            backtrace: None,
+        }));
+        task_view.aborted(Err("this server can't abort tasks".to_owned()));
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, 40)).unwrap();
+        terminal
+            .draw(|frame| view.render(frame, frame.size(), &mut state))
+            .unwrap();
+        let screen = snapshot(terminal.backend().buffer());
+        assert!(screen.contains("task.name = worker-1"), "{screen}");
+        assert!(
+            screen.contains("Backtrace: not captured; the target doesn't take Tokio task dumps"),
+            "{screen}"
+        );
+        assert!(
+            screen.contains("Failed to abort the task: this server can't abort tasks"),
+            "{screen}"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    i18n::tr,
+    state::{resources::Resource, store::Id, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls},
+        help::HelpText,
+        DUR_LEN, DUR_TABLE_PRECISION,
+    },
+};
+use ratatui::{
+    layout::{self, Layout},
+    style::{self, Color, Style},
+    widgets::{Cell, Row, Table},
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+
+/// A summary of the network resources recorded by the instrumented sockets in
+/// `console_subscriber::net`, grouped by listener and by peer.
+#[derive(Debug, Default)]
+pub(crate) struct NetView {}
+
+/// A listening (or unconnected) socket, and the connections it has accepted.
+struct Listener {
+    id: Id<Resource>,
+    proto: &'static str,
+    local_addr: String,
+    dropped: bool,
+    accepted: Option<String>,
+    conns: Conns,
+    accept_tasks: BTreeSet<String>,
+}
+
+/// The connections from a single peer address.
+struct Peer {
+    conns: Conns,
+    listeners: BTreeSet<Id<Resource>>,
+}
+
+/// Counts and ages of a set of live connections.
+#[derive(Default)]
+struct Conns {
+    count: usize,
+    oldest: Option<Duration>,
+    newest: Option<Duration>,
+}
+
+impl NetView {
+    const LISTENERS_HEADER: &'static [&'static str] = &[
+        "ID",
+        "Proto",
+        "Local",
+        "Conns",
+        "Accepted",
+        "Oldest",
+        "Newest",
+        "Accept Tasks",
+    ];
+    const PEERS_HEADER: &'static [&'static str] = &["Peer", "Conns", "Oldest", "Listeners"];
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let (listeners, peers) = summarize(state, now);
+
+        let controls = Controls::new(&[], &area, styles);
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(
+                [
+                    layout::Constraint::Length(controls.height()),
+                    layout::Constraint::Percentage(50),
+                    layout::Constraint::Percentage(50),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        let header_style = if styles.color(Color::Cyan).is_some() {
+            Style::default()
+        } else {
+            Style::default().add_modifier(style::Modifier::REVERSED)
+        };
+        let header_style = header_style.add_modifier(style::Modifier::BOLD);
+        let dur = |dur: Option<Duration>| match dur {
+            Some(dur) => Cell::from(styles.time_units(dur, DUR_TABLE_PRECISION, Some(DUR_LEN))),
+            None => Cell::from("-"),
+        };
+
+        let listener_rows = listeners.iter().map(|listener| {
+            let row = Row::new(vec![
+                Cell::from(listener.id.to_string()),
+                Cell::from(listener.proto),
+                Cell::from(listener.local_addr.clone()),
+                Cell::from(listener.conns.count.to_string()),
+                Cell::from(listener.accepted.clone().unwrap_or_else(|| "-".to_string())),
+                dur(listener.conns.oldest),
+                dur(listener.conns.newest),
+                Cell::from(join(&listener.accept_tasks)),
+            ]);
+            if listener.dropped {
+                row.style(styles.terminated())
+            } else {
+                row
+            }
+        });
+        let listeners_table = Table::new(
+            listener_rows,
+            [
+                layout::Constraint::Length(6),
+                layout::Constraint::Length(5),
+                layout::Constraint::Length(22),
+                layout::Constraint::Length(5),
+                layout::Constraint::Length(8),
+                layout::Constraint::Length(DUR_LEN as u16),
+                layout::Constraint::Length(DUR_LEN as u16),
+                layout::Constraint::Min(12),
+            ],
+        )
+        .header(Row::new(Self::LISTENERS_HEADER.iter().copied().map(tr)).style(header_style))
+        .block(
+            styles
+                .border_block()
+                .title(vec![bold(format!("Listeners ({}) ", listeners.len()))]),
+        )
+        .column_spacing(1);
+
+        let peer_rows = peers.iter().map(|(addr, peer)| {
+            Row::new(vec![
+                Cell::from(addr.clone()),
+                Cell::from(peer.conns.count.to_string()),
+                dur(peer.conns.oldest),
+                Cell::from(join(&peer.listeners)),
+            ])
+        });
+        let peers_table = Table::new(
+            peer_rows,
+            [
+                layout::Constraint::Length(40),
+                layout::Constraint::Length(5),
+                layout::Constraint::Length(DUR_LEN as u16),
+                layout::Constraint::Min(9),
+            ],
+        )
+        .header(Row::new(Self::PEERS_HEADER.iter().copied().map(tr)).style(header_style))
+        .block(
+            styles
+                .border_block()
+                .title(vec![bold(format!("Peers ({}) ", peers.len()))]),
+        )
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_widget(listeners_table, chunks[1]);
+        frame.render_widget(peers_table, chunks[2]);
+    }
+}
+
+impl HelpText for NetView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(&[])
+    }
+}
+
+fn summarize(state: &mut State, now: SystemTime) -> (Vec<Listener>, BTreeMap<String, Peer>) {
+    let resources = state
+        .resources_state()
+        .resources()
+        .filter_map(|resource| resource.upgrade())
+        .filter(|resource| resource.borrow().kind() == "net")
+        .collect::<Vec<_>>();
+
+    let mut listeners = resources
+        .iter()
+        .filter_map(|resource| {
+            let resource = resource.borrow();
+            let proto = match resource.concrete_type() {
+                "TcpListener" => "tcp",
+                "UdpSocket" => "udp",
+                _ => return None,
+            };
+            Some(Listener {
+                id: resource.id(),
+                proto,
+                local_addr: attribute_str(&resource, "local_addr").unwrap_or_default(),
+                dropped: resource.dropped(),
+                accepted: attribute_str(&resource, "accepted"),
+                conns: Conns::default(),
+                accept_tasks: BTreeSet::new(),
+            })
+        })
+        .collect::<Vec<_>>();
+    listeners.sort_by_key(|listener| listener.id);
+
+    let mut peers = BTreeMap::<String, Peer>::new();
+    for stream in &resources {
+        let stream = stream.borrow();
+        if stream.concrete_type() != "TcpStream" || stream.dropped() {
+            continue;
+        }
+        let age = stream.total(now);
+        let listener = stream.parent_resource_id();
+        if let Some(listener) = listener.and_then(|id| listeners.iter_mut().find(|l| l.id == id)) {
+            listener.conns.add(age);
+        }
+        if let Some(peer_addr) = attribute_str(&stream, "peer_addr") {
+            // Group connections by peer host, since each connection from the
+            // same host will use a different port.
+            let host = peer_addr
+                .parse::<SocketAddr>()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or(peer_addr);
+            let peer = peers.entry(host).or_insert_with(|| Peer {
+                conns: Conns::default(),
+                listeners: BTreeSet::new(),
+            });
+            peer.conns.add(age);
+            peer.listeners.extend(listener);
+        }
+    }
+
+    for op in state.async_ops_state().async_ops() {
+        let Some(op) = op.upgrade() else { continue };
+        let op = op.borrow();
+        if op.dropped() || op.task_id().is_none() {
+            continue;
+        }
+        if let Some(listener) = listeners.iter_mut().find(|l| l.id == op.resource_id()) {
+            listener.accept_tasks.insert(op.task_id_str().to_owned());
+        }
+    }
+
+    (listeners, peers)
+}
+
+fn attribute_str(resource: &Resource, name: &str) -> Option<String> {
+    resource.attribute(name).map(ToString::to_string)
+}
+
+fn join<T: ToString>(items: &BTreeSet<T>) -> String {
+    if items.is_empty() {
+        return "-".to_string();
+    }
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Conns {
+    fn add(&mut self, age: Duration) {
+        self.count += 1;
+        self.oldest = Some(self.oldest.map_or(age, |oldest| oldest.max(age)));
+        self.newest = Some(self.newest.map_or(age, |newest| newest.min(age)));
+    }
+}
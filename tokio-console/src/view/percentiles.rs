@@ -1,11 +1,13 @@
 use std::time::Duration;
 
 use ratatui::{
-    text::{Line, Text},
+    style::Color,
+    text::{Line, Span, Text},
     widgets::{Paragraph, Widget},
 };
 
 use crate::{
+    slo::SloStatus,
     state::histogram::DurationHistogram,
     view::{self, bold},
 };
@@ -19,6 +21,8 @@ pub(crate) struct Percentiles<'a> {
     histogram: Option<&'a DurationHistogram>,
     /// The title of the paragraph
     title: &'a str,
+    /// How the histogram measures up to the latency objectives for it
+    slos: &'a [SloStatus<'a>],
 }
 
 impl Widget for Percentiles<'_> {
@@ -36,13 +40,18 @@ impl<'a> Percentiles<'a> {
             styles,
             histogram: None,
             title: "Percentiles",
+            slos: &[],
         }
     }
 
     pub(crate) fn make_percentiles_inner(&self) -> Text<'static> {
         let mut text = Text::default();
-        let histogram = match self.histogram {
-            Some(DurationHistogram { histogram, .. }) => histogram,
+        let (histogram, highest_outlier) = match self.histogram {
+            Some(DurationHistogram {
+                histogram,
+                highest_outlier,
+                ..
+            }) => (histogram, *highest_outlier),
             _ => return text,
         };
 
@@ -62,6 +71,32 @@ impl<'a> Percentiles<'a> {
         });
 
         text.extend(percentiles);
+        // Outliers beyond the histogram's range are clamped to its maximum,
+        // so the highest of them is the actual maximum.
+        let max = highest_outlier.unwrap_or_else(|| Duration::from_nanos(histogram.max()));
+        text.extend([Line::from(vec![
+            bold("max: "),
+            self.styles.time_units(max, view::DUR_LIST_PRECISION, None),
+        ])]);
+
+        // Show how much of the histogram is within each objective's
+        // threshold, marked and in red if the objective isn't met.
+        let slos = self.slos.iter().map(|status| {
+            let (color, mark) = if status.is_met() {
+                (Color::Green, self.styles.if_utf8(" \u{2713}", " ok"))
+            } else {
+                (Color::Red, self.styles.if_utf8(" \u{2717}", " MISSED"))
+            };
+            Line::from(vec![
+                bold(format!("<{:.0?}: ", status.slo.threshold(),)),
+                Span::styled(
+                    format!("{:.1}%{mark}", status.within),
+                    self.styles.fg(color),
+                ),
+                Span::raw(format!(" (p{})", status.slo.percentile())),
+            ])
+        });
+        text.extend(slos);
         text
     }
 
@@ -76,6 +111,11 @@ impl<'a> Percentiles<'a> {
         self
     }
 
+    pub(crate) fn slos(mut self, slos: &'a [SloStatus<'a>]) -> Percentiles<'a> {
+        self.slos = slos;
+        self
+    }
+
     pub(crate) fn title(mut self, title: &'a str) -> Percentiles<'a> {
         self.title = title;
         self
@@ -0,0 +1,90 @@
+use crate::{
+    input,
+    state::State,
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls},
+        help::HelpText,
+    },
+};
+use ratatui::layout::{self, Layout};
+use std::borrow::Cow;
+
+/// A custom view, which is compiled into the console and registered in
+/// [`crate::plugins::registered`].
+///
+/// A plugin is shown when its [`key`] is pressed, below its controls and
+/// inside a border titled with its [`name`], and `Esc` returns to the task
+/// list. The plugin receives every other key while it's shown.
+///
+/// [`key`]: Plugin::key
+/// [`name`]: Plugin::name
+pub(crate) trait Plugin {
+    /// The name of the view, which is its title.
+    fn name(&self) -> &'static str;
+
+    /// The key which shows the view.
+    ///
+    /// This must be a key without a built-in binding in any view, such as `q`
+    /// or `t`; [`crate::plugins::registered`] leaves out plugins whose key
+    /// has one.
+    fn key(&self) -> char;
+
+    /// The view's own controls, which are listed above it and in its help.
+    fn controls(&self) -> &'static [ControlDisplay] {
+        &[]
+    }
+
+    /// Called after each update from the target is merged into `state`,
+    /// whether or not the view is shown.
+    fn update(&mut self, _state: &State) {}
+
+    /// Called when the console connects to a new target, whose tasks and
+    /// resources are unrelated to the old target's.
+    fn reset(&mut self) {}
+
+    /// Handles a key pressed while the view is shown.
+    fn update_input(&mut self, _event: input::Event, _state: &State) {}
+
+    /// Draws the view into `area`.
+    fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &State,
+    );
+}
+
+/// Draws `plugin` into `area`, below its controls and inside a border.
+pub(super) fn render(
+    plugin: &mut dyn Plugin,
+    styles: &view::Styles,
+    frame: &mut ratatui::terminal::Frame,
+    area: layout::Rect,
+    state: &State,
+) {
+    let controls = Controls::new(plugin.controls(), &area, styles);
+    let chunks = Layout::default()
+        .direction(layout::Direction::Vertical)
+        .constraints(
+            [
+                layout::Constraint::Length(controls.height()),
+                layout::Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    frame.render_widget(controls.into_widget(), chunks[0]);
+
+    let block = styles.border_block().title(bold(plugin.name()));
+    let inner = block.inner(chunks[1]);
+    frame.render_widget(block, chunks[1]);
+    plugin.render(styles, frame, inner, state);
+}
+
+impl HelpText for Box<dyn Plugin> {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(self.controls())
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    input,
+    view::{self, bold},
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Color,
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+};
+
+/// A popup which asks the user to type a line of text.
+///
+/// While a prompt is open, it receives all keyboard input.
+pub(crate) struct Prompt {
+    title: String,
+    text: String,
+    /// An error with the previously submitted text, if it was rejected.
+    error: Option<String>,
+    max_len: usize,
+    /// What pressing enter does, for the prompt's controls.
+    submit_action: &'static str,
+}
+
+/// The outcome of [`Prompt::update_input`].
+pub(crate) enum PromptInput {
+    /// The text is still being typed.
+    Editing,
+    /// The user pressed enter, submitting the (trimmed) text.
+    Submit(String),
+    /// The user pressed escape, discarding the text.
+    Cancel,
+}
+
+impl Prompt {
+    pub(crate) fn new(title: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            text: text.into(),
+            error: None,
+            max_len: 256,
+            submit_action: "save",
+        }
+    }
+
+    /// Limits the text to `max_len` characters.
+    pub(crate) fn with_max_len(self, max_len: usize) -> Self {
+        Self { max_len, ..self }
+    }
+
+    /// Describes what pressing enter does as `action`, rather than "save".
+    pub(crate) fn with_submit_action(self, submit_action: &'static str) -> Self {
+        Self {
+            submit_action,
+            ..self
+        }
+    }
+
+    /// Displays `error` below the text, such as when re-opening a prompt
+    /// whose text was rejected.
+    pub(crate) fn with_error(self, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..self
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event) -> PromptInput {
+        use input::KeyCode::*;
+        let input::Event::Key(event) = event else {
+            return PromptInput::Editing;
+        };
+        match event.code {
+            Enter => return PromptInput::Submit(self.text.trim().to_owned()),
+            Esc => return PromptInput::Cancel,
+            Backspace => {
+                self.text.pop();
+            }
+            Char(c)
+                if !event.modifiers.contains(input::KeyModifiers::CONTROL)
+                    && self.text.chars().count() < self.max_len =>
+            {
+                self.text.push(c);
+            }
+            _ => {}
+        }
+        PromptInput::Editing
+    }
+
+    pub(crate) fn render(&self, styles: &view::Styles, frame: &mut ratatui::terminal::Frame) {
+        self.render_with(styles, frame, Vec::new())
+    }
+
+    /// Renders the prompt with `lines` added below the text.
+    pub(crate) fn render_with(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        lines: Vec<Line<'_>>,
+    ) {
+        let mut text = vec![Line::from(vec![Span::raw(self.text.clone()), bold("_")])];
+        if let Some(ref error) = self.error {
+            text.push(Line::from(Span::styled(
+                error.clone(),
+                styles.fg(Color::Red),
+            )));
+        }
+        text.extend(lines);
+        text.push(Line::from(vec![
+            bold("enter"),
+            Span::raw(format!(" = {}, ", self.submit_action)),
+            bold("esc"),
+            Span::raw(" = cancel"),
+        ]));
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Percentage(40),
+                    // add 2 for the top and bottom borders
+                    Constraint::Length(text.len() as u16 + 2),
+                    Constraint::Percentage(40),
+                ]
+                .as_ref(),
+            )
+            .split(frame.size());
+
+        let popup_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(60),
+                    Constraint::Percentage(20),
+                ]
+                .as_ref(),
+            )
+            .split(popup_layout[1])[1];
+
+        let prompt = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(styles.border_block().title(self.title.clone()));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(prompt, popup_area);
+    }
+}
@@ -1,12 +1,13 @@
 use crate::{
-    input,
+    editor::{self, EditorCommand},
+    export, input,
     state::resources::Resource,
     state::State,
     view::{
         self,
         async_ops::{self, AsyncOpsTable, AsyncOpsTableCtx},
         bold,
-        controls::{controls_paragraph, ControlDisplay, Controls, KeyDisplay},
+        controls::{ControlDisplay, Controls, KeyDisplay},
         help::HelpText,
         TableListState,
     },
@@ -14,28 +15,92 @@ use crate::{
 use once_cell::sync::OnceCell;
 use ratatui::{
     layout::{self, Layout},
+    style::Color,
     text::{Line, Span, Text},
-    widgets::Paragraph,
+    widgets::{List, ListItem, Paragraph, Wrap},
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, cmp, path::PathBuf, rc::Rc};
 
 pub(crate) struct ResourceView {
     resource: Rc<RefCell<Resource>>,
-    pub(crate) async_ops_table: TableListState<AsyncOpsTable, 9>,
+    pub(crate) async_ops_table: TableListState<AsyncOpsTable, 10>,
     initial_render: bool,
+    /// The outcome of the last attempt to open a location in an editor, if
+    /// one has been opened.
+    opened: Option<Result<String, String>>,
 }
 
 impl ResourceView {
     pub(super) fn new(resource: Rc<RefCell<Resource>>) -> Self {
         ResourceView {
             resource,
-            async_ops_table: TableListState::<AsyncOpsTable, 9>::default(),
+            async_ops_table: TableListState::<AsyncOpsTable, 10>::default(),
             initial_render: true,
+            opened: None,
         }
     }
 
-    pub(crate) fn update_input(&mut self, event: input::Event) {
-        self.async_ops_table.update_input(event)
+    /// Handles an input, returning an editor which is waiting to be handed
+    /// the terminal, if one was opened.
+    pub(crate) fn update_input(
+        &mut self,
+        event: input::Event,
+        editor_command: Option<&EditorCommand>,
+    ) -> Option<editor::Edit> {
+        if let input::Event::Key(input::KeyEvent {
+            code: input::KeyCode::Char('o'),
+            ..
+        }) = event
+        {
+            let (opened, edit) = match self.open_in_editor(editor_command) {
+                Ok((location, edit)) => (Ok(location), edit),
+                Err(error) => (Err(error), None),
+            };
+            self.opened = Some(opened);
+            return edit;
+        }
+        self.async_ops_table.update_input(event);
+        None
+    }
+
+    /// Exports the resource's async ops, as they're displayed.
+    pub(crate) fn export_async_ops(
+        &self,
+        format: export::Format,
+        state: &State,
+    ) -> color_eyre::Result<PathBuf> {
+        let ctx = AsyncOpsTableCtx {
+            initial_render: false,
+            resource_id: self.resource.borrow().id(),
+        };
+        self.async_ops_table
+            .export("async-ops", format, state, &ctx)
+    }
+
+    /// Returns the resource's async ops as a Markdown table, as they're
+    /// displayed.
+    pub(crate) fn async_ops_markdown(&self, state: &State) -> color_eyre::Result<String> {
+        let ctx = AsyncOpsTableCtx {
+            initial_render: false,
+            resource_id: self.resource.borrow().id(),
+        };
+        self.async_ops_table.markdown(state, &ctx)
+    }
+
+    /// Opens where the selected async op was created in an editor, or where
+    /// the resource was created if the async op's location isn't known,
+    /// returning the location which was opened.
+    fn open_in_editor(
+        &self,
+        editor_command: Option<&EditorCommand>,
+    ) -> Result<(String, Option<editor::Edit>), String> {
+        let op = self.async_ops_table.selected_item();
+        let location = op
+            .and_then(|op| op.borrow().source_location().cloned())
+            .or_else(|| self.resource.borrow().source_location().cloned())
+            .ok_or_else(|| "the resource's location isn't known".to_owned())?;
+        let edit = editor::open(editor_command, &location)?;
+        Ok((location.to_string(), edit))
     }
 
     pub(crate) fn render(
@@ -48,23 +113,87 @@ impl ResourceView {
         let resource = &*self.resource.borrow();
         let controls = Controls::new(view_controls(), &area, styles);
 
-        let (controls_area, stats_area, async_ops_area) = {
-            let chunks = Layout::default()
-                .direction(layout::Direction::Vertical)
-                .constraints(
-                    [
-                        // controls
-                        layout::Constraint::Length(controls.height()),
-                        // resource stats
-                        layout::Constraint::Length(8),
-                        // async ops
-                        layout::Constraint::Percentage(60),
-                    ]
-                    .as_ref(),
-                )
-                .split(area);
-            (chunks[0], chunks[1], chunks[2])
-        };
+        let opened = self.opened.as_ref().map(|opened| match opened {
+            Ok(location) => Line::from(vec![bold("Opened "), Span::raw(location.clone())]),
+            Err(error) => Line::from(vec![
+                Span::styled("Failed to open in editor: ", styles.fg(Color::Red)),
+                Span::raw(error.clone()),
+            ]),
+        });
+
+        // Where the selected async op was created, if the instrumented
+        // program captures backtraces of async ops' creation.
+        let async_op = self.async_ops_table.selected_item().and_then(|op| {
+            let op = op.borrow();
+            let backtrace = op.backtrace()?;
+            let mut lines = vec![Line::from(vec![
+                bold("Location: "),
+                Span::raw(op.location().to_owned()),
+            ])];
+            lines.extend(backtrace.lines().map(|line| Line::from(line.to_owned())));
+            Some((op.id(), lines))
+        });
+
+        let waiting: Vec<_> = state
+            .last_updated_at()
+            .map(|now| state.tasks_state().waiting_on(resource.id(), now))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(task, waited)| {
+                let task = task.upgrade()?;
+                let task = task.borrow();
+                let mut line = vec![bold(format!("task {}", task.id_str()))];
+                if let Some(name) = task.name() {
+                    line.push(Span::raw(format!(" ({name})")));
+                }
+                line.push(Span::raw(" waiting "));
+                line.push(styles.time_units(waited, view::DUR_LIST_PRECISION, None));
+                Some(ListItem::new(Line::from(line)))
+            })
+            .collect();
+
+        let mut constraints = vec![
+            // controls
+            layout::Constraint::Length(controls.height()),
+        ];
+        if opened.is_some() {
+            constraints.push(layout::Constraint::Length(1));
+        }
+        constraints.push(
+            // resource stats
+            layout::Constraint::Length(8),
+        );
+        if !waiting.is_empty() {
+            // waiting tasks (add 2 for top and bottom borders), capped so
+            // that a heavily contended resource doesn't crowd out its async
+            // ops.
+            constraints.push(layout::Constraint::Length(
+                cmp::min(waiting.len(), 8) as u16 + 2,
+            ));
+        }
+        constraints.push(
+            // async ops
+            layout::Constraint::Percentage(60),
+        );
+        if async_op.is_some() {
+            // the selected async op's backtrace
+            constraints.push(layout::Constraint::Percentage(40));
+        }
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(constraints)
+            .split(area);
+        let mut chunks = chunks.iter().copied();
+        let controls_area = chunks.next().expect("controls area");
+        let opened_area = opened
+            .is_some()
+            .then(|| chunks.next().expect("opened area"));
+        let stats_area = chunks.next().expect("stats area");
+        let waiting_area = (!waiting.is_empty()).then(|| chunks.next().expect("waiting area"));
+        let async_ops_area = chunks.next().expect("async ops area");
+        let async_op_area = async_op
+            .is_some()
+            .then(|| chunks.next().expect("async op area"));
 
         let stats_area = Layout::default()
             .direction(layout::Direction::Horizontal)
@@ -105,21 +234,37 @@ impl ResourceView {
         let fields_widget = Paragraph::new(fields).block(styles.border_block().title("Attributes"));
 
         frame.render_widget(controls.into_widget(), controls_area);
+        if let (Some(opened), Some(opened_area)) = (opened, opened_area) {
+            frame.render_widget(Paragraph::new(opened), opened_area);
+        }
         frame.render_widget(resource_widget, stats_area[0]);
         frame.render_widget(fields_widget, stats_area[1]);
+        if let Some(waiting_area) = waiting_area {
+            let title = format!("Waiting Tasks ({})", waiting.len());
+            let waiting = List::new(waiting).block(styles.border_block().title(title));
+            frame.render_widget(waiting, waiting_area);
+        }
         let ctx = AsyncOpsTableCtx {
             initial_render: self.initial_render,
             resource_id: resource.id(),
         };
         self.async_ops_table
             .render(styles, frame, async_ops_area, state, ctx);
+        if let (Some((id, lines)), Some(async_op_area)) = (async_op, async_op_area) {
+            let async_op_widget = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+                styles
+                    .border_block()
+                    .title(format!("Async Op {id} Backtrace")),
+            );
+            frame.render_widget(async_op_widget, async_op_area);
+        }
         self.initial_render = false;
     }
 }
 
 impl HelpText for ResourceView {
-    fn render_help_content(&self, styles: &view::Styles) -> Paragraph<'static> {
-        controls_paragraph(view_controls(), styles)
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
     }
 }
 
@@ -127,13 +272,22 @@ fn view_controls() -> &'static [ControlDisplay] {
     static VIEW_CONTROLS: OnceCell<Vec<ControlDisplay>> = OnceCell::new();
 
     VIEW_CONTROLS.get_or_init(|| {
-        let resource_controls = &[ControlDisplay {
-            action: "return to task list",
-            keys: &[KeyDisplay {
-                base: "esc",
-                utf8: Some("\u{238B} esc"),
-            }],
-        }];
+        let resource_controls = &[
+            ControlDisplay {
+                action: "return to task list",
+                keys: &[KeyDisplay {
+                    base: "esc",
+                    utf8: Some("\u{238B} esc"),
+                }],
+            },
+            ControlDisplay {
+                action: "open in editor",
+                keys: &[KeyDisplay {
+                    base: "o",
+                    utf8: None,
+                }],
+            },
+        ];
         [resource_controls, async_ops::view_controls()].concat()
     })
 }
@@ -1,12 +1,13 @@
 use crate::{
+    query::Value,
     state::{
-        resources::{Resource, SortBy},
+        resources::{Resource, SortBy, TypeVisibility},
         State,
     },
     view::{
         self, bold,
         controls::Controls,
-        table::{view_controls, TableList, TableListState},
+        table::{self, view_controls, Search, TableList, TableListState},
         DUR_LEN, DUR_TABLE_PRECISION,
     },
 };
@@ -17,16 +18,17 @@ use ratatui::{
     text::Line,
     widgets::{Cell, Row, Table},
 };
+use std::time::SystemTime;
 
 #[derive(Debug, Default)]
 pub(crate) struct ResourcesTable {}
 
-impl TableList<9> for ResourcesTable {
+impl TableList<10> for ResourcesTable {
     type Row = Resource;
     type Sort = SortBy;
     type Context = ();
 
-    const HEADER: &'static [&'static str; 9] = &[
+    const HEADER: &'static [&'static str; 10] = &[
         "ID",
         "Parent",
         "Kind",
@@ -35,10 +37,11 @@ impl TableList<9> for ResourcesTable {
         "Type",
         "Vis",
         "Location",
+        "Created",
         "Attributes",
     ];
 
-    const WIDTHS: &'static [usize; 9] = &[
+    const WIDTHS: &'static [usize; 10] = &[
         Self::HEADER[0].len() + 1,
         Self::HEADER[1].len() + 1,
         Self::HEADER[2].len() + 1,
@@ -48,10 +51,58 @@ impl TableList<9> for ResourcesTable {
         Self::HEADER[6].len() + 1,
         Self::HEADER[7].len() + 1,
         Self::HEADER[8].len() + 1,
+        Self::HEADER[9].len() + 1,
     ];
 
+    fn matches(resource: &Resource, search: &Search) -> bool {
+        search.matches([
+            resource.id_str(),
+            resource.kind(),
+            resource.target(),
+            resource.concrete_type(),
+            resource.location(),
+        ]) || search.matches_spans(resource.formatted_attributes())
+    }
+
+    fn values(
+        resource: &Resource,
+        now: SystemTime,
+        _: &State,
+        _: &Self::Context,
+    ) -> Option<Vec<Value>> {
+        let visibility = match resource.type_visibility() {
+            TypeVisibility::Public => "public",
+            TypeVisibility::Internal => "internal",
+        };
+        Some(vec![
+            Value::id(resource.id_str()),
+            Value::id(resource.parent_id()),
+            Value::Text(resource.kind().to_owned()),
+            Value::Duration(resource.total(now)),
+            Value::Text(resource.target().to_owned()),
+            Value::Text(resource.concrete_type().to_owned()),
+            Value::Text(visibility.to_owned()),
+            Value::Text(resource.location().to_owned()),
+            Value::Text(created_at(resource)),
+            Value::Text(table::spans_text(resource.formatted_attributes())),
+        ])
+    }
+
+    fn refresh(
+        table_list_state: &mut TableListState<Self, 10>,
+        now: SystemTime,
+        state: &mut State,
+        _: &Self::Context,
+    ) {
+        table_list_state
+            .sorted_items
+            .extend(state.resources_state_mut().take_new_resources());
+        table_list_state.apply_search();
+        table_list_state.sort_items(now, |sort_by, resource| sort_by.key(now, resource));
+    }
+
     fn render(
-        table_list_state: &mut TableListState<Self, 9>,
+        table_list_state: &mut TableListState<Self, 10>,
         styles: &view::Styles,
         frame: &mut ratatui::terminal::Frame,
         area: layout::Rect,
@@ -65,12 +116,7 @@ impl TableList<9> for ResourcesTable {
             return;
         };
 
-        table_list_state
-            .sorted_items
-            .extend(state.resources_state_mut().take_new_resources());
-        table_list_state
-            .sort_by
-            .sort(now, &mut table_list_state.sorted_items);
+        Self::refresh(table_list_state, now, state, &());
 
         let viz_len: u16 = Self::WIDTHS[6] as u16;
 
@@ -81,6 +127,7 @@ impl TableList<9> for ResourcesTable {
         let mut target_width = view::Width::new(Self::WIDTHS[4] as u16);
         let mut type_width = view::Width::new(Self::WIDTHS[5] as u16);
         let mut location_width = view::Width::new(Self::WIDTHS[7] as u16);
+        let mut created_width = view::Width::new(Self::WIDTHS[8] as u16);
 
         let rows = {
             let id_width = &mut id_width;
@@ -89,6 +136,7 @@ impl TableList<9> for ResourcesTable {
             let target_width = &mut target_width;
             let type_width = &mut type_width;
             let location_width = &mut location_width;
+            let created_width = &mut created_width;
 
             table_list_state
                 .sorted_items
@@ -114,6 +162,7 @@ impl TableList<9> for ResourcesTable {
                         Cell::from(type_width.update_str(resource.concrete_type()).to_owned()),
                         Cell::from(resource.type_visibility().render(styles)),
                         Cell::from(location_width.update_str(resource.location()).to_owned()),
+                        Cell::from(created_width.update_str(created_at(&resource))),
                         Cell::from(
                             resource
                                 .formatted_attributes()
@@ -139,17 +188,12 @@ impl TableList<9> for ResourcesTable {
         };
         let header_style = header_style.add_modifier(style::Modifier::BOLD);
 
-        let header = Row::new(Self::HEADER.iter().enumerate().map(|(idx, &value)| {
-            if idx == table_list_state.selected_column {
-                if table_list_state.sort_descending {
-                    Cell::from(styles.ascending(value))
-                } else {
-                    Cell::from(styles.descending(value))
-                }
-            } else {
-                Cell::from(value)
-            }
-        }))
+        let header = Row::new(
+            Self::HEADER
+                .iter()
+                .enumerate()
+                .map(|(idx, &value)| table_list_state.header_cell(styles, idx, value)),
+        )
         .height(1)
         .style(header_style);
 
@@ -159,10 +203,9 @@ impl TableList<9> for ResourcesTable {
             Table::default().rows(rows.rev())
         };
 
-        let block = styles.border_block().title(vec![bold(format!(
-            "Resources ({}) ",
-            table_list_state.len()
-        ))]);
+        let mut title = vec![bold(format!("Resources ({}) ", table_list_state.len()))];
+        title.extend(table_list_state.search_title(styles));
+        let block = styles.border_block().title(title);
 
         let controls = Controls::new(view_controls(), &area, styles);
 
@@ -192,9 +235,11 @@ impl TableList<9> for ResourcesTable {
             type_width.constraint(),
             layout::Constraint::Length(viz_len),
             location_width.constraint(),
+            created_width.constraint(),
             attributes_width,
         ];
 
+        let table_area = block.inner(tasks_area);
         let table = table
             .header(header)
             .block(block)
@@ -203,10 +248,15 @@ impl TableList<9> for ResourcesTable {
             .highlight_style(Style::default().add_modifier(style::Modifier::BOLD));
 
         frame.render_stateful_widget(table, tasks_area, &mut table_list_state.table_state);
+        table_list_state.record_layout(table_area, &widths[..]);
         frame.render_widget(controls.into_widget(), controls_area);
 
-        table_list_state
-            .sorted_items
-            .retain(|t| t.upgrade().is_some());
+        table_list_state.remove_dropped();
     }
 }
+
+/// Returns when `resource` was created, by the target's clock, such as
+/// `2024-01-01T12:00:00Z`.
+fn created_at(resource: &Resource) -> String {
+    humantime::format_rfc3339_seconds(resource.created_at()).to_string()
+}
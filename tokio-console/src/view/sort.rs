@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+
+/// Puts `items` in order by `key`, compared by `cmp`, given the keys which
+/// the items at the start of `items` had when they were last put in order.
+///
+/// `sorted` holds those keys, in the same order as the items, and is
+/// replaced with every item's key. The items after them, which haven't been
+/// put in order yet, and the items whose keys have changed are sorted by
+/// themselves and merged back in among the rest, which are still in order.
+/// Repositioning `k` of `n` items this way takes O(n + k log k) time, rather
+/// than the O(n log n) time of sorting all of them again.
+///
+/// `cmp` must compare the keys the same way as when `sorted` was recorded.
+/// Items with equal keys stay in the order they were in, except that moved
+/// items are placed after the items they're equal to.
+pub(crate) fn reposition<T, K: PartialEq>(
+    items: &mut Vec<T>,
+    sorted: &mut Vec<K>,
+    mut key: impl FnMut(&T) -> K,
+    cmp: impl Fn(&K, &K) -> Ordering,
+) {
+    let mut old_keys = std::mem::take(sorted).into_iter();
+    let mut unmoved = Vec::with_capacity(items.len());
+    let mut moved = Vec::new();
+    for item in items.drain(..) {
+        let new_key = key(&item);
+        if old_keys.next().is_some_and(|old_key| old_key == new_key) {
+            unmoved.push((item, new_key));
+        } else {
+            moved.push((item, new_key));
+        }
+    }
+
+    moved.sort_by(|(_, a), (_, b)| cmp(a, b));
+    let mut moved = moved.into_iter().peekable();
+    for (item, item_key) in unmoved {
+        while let Some((moved_item, moved_key)) =
+            moved.next_if(|(_, moved_key)| cmp(moved_key, &item_key).is_lt())
+        {
+            items.push(moved_item);
+            sorted.push(moved_key);
+        }
+        items.push(item);
+        sorted.push(item_key);
+    }
+    for (moved_item, moved_key) in moved {
+        items.push(moved_item);
+        sorted.push(moved_key);
+    }
+}
+
+/// Removes the items which don't match `f` from `items`, along with their
+/// keys in `sorted`, which are the keys of the items at the start of `items`
+/// as [`reposition`] recorded them, and returns the removed items.
+///
+/// The items which are kept stay in order, so their keys still are the keys
+/// of the items at the start of `items`.
+pub(crate) fn partition<T, K>(
+    items: &mut Vec<T>,
+    sorted: &mut Vec<K>,
+    f: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let mut keys = std::mem::take(sorted).into_iter();
+    let mut kept = Vec::with_capacity(items.len());
+    let mut removed = Vec::new();
+    for item in items.drain(..) {
+        let key = keys.next();
+        if f(&item) {
+            kept.push(item);
+            sorted.extend(key);
+        } else {
+            removed.push(item);
+        }
+    }
+    *items = kept;
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn repositions_only_changed_and_new_items() {
+        let mut items = vec![5, 1, 4, 2, 3];
+        let mut sorted = Vec::new();
+        reposition(&mut items, &mut sorted, |&item| item, Ord::cmp);
+        assert_eq!(items, [1, 2, 3, 4, 5]);
+        assert_eq!(sorted, items);
+
+        // Items are keyed by their value divided by 10, so that changing an
+        // item's value by less than that doesn't change its key.
+        let keys = Cell::new(0);
+        let key = |&item: &i32| {
+            keys.set(keys.get() + 1);
+            item / 10
+        };
+        let mut items = vec![1, 12, 23, 34, 45];
+        let mut sorted = Vec::new();
+        reposition(&mut items, &mut sorted, key, Ord::cmp);
+        assert_eq!(keys.get(), 5);
+
+        items[3] = 5;
+        items.push(29);
+        items.push(0);
+        reposition(&mut items, &mut sorted, key, Ord::cmp);
+        assert_eq!(items, [1, 5, 0, 12, 23, 29, 45]);
+        assert_eq!(sorted, [0, 0, 0, 1, 2, 2, 4]);
+        // Every item's key is computed once.
+        assert_eq!(keys.get(), 12);
+    }
+
+    #[test]
+    fn repositions_like_a_full_sort() {
+        // A simple linear congruential generator, so that the test is
+        // repeatable.
+        let mut seed = 0x2545_f491_u32;
+        let mut random = move |below: u32| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) % below
+        };
+        let cmp = |a: &u32, b: &u32| b.cmp(a);
+        let mut items: Vec<u32> = (0..200).map(|_| random(50)).collect();
+        let mut sorted = Vec::new();
+        for _ in 0..50 {
+            for _ in 0..random(20) {
+                let idx = random(items.len() as u32) as usize;
+                items[idx] = random(50);
+            }
+            items.extend((0..random(5)).map(|_| random(50)));
+            reposition(&mut items, &mut sorted, |&item| item, cmp);
+
+            let mut expected = items.clone();
+            expected.sort_by(cmp);
+            assert_eq!(items, expected);
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn partition_keeps_keys_in_step() {
+        let mut items = vec![1, 2, 3, 4, 5];
+        let mut sorted = Vec::new();
+        reposition(&mut items, &mut sorted, |&item| item * 10, Ord::cmp);
+        items.push(0);
+
+        let removed = partition(&mut items, &mut sorted, |&item| item % 2 == 0);
+        assert_eq!(removed, [1, 3, 5]);
+        assert_eq!(items, [2, 4, 0]);
+        // The new item, which hasn't been put in order, has no key.
+        assert_eq!(sorted, [20, 40]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use ratatui::{
+    layout::Rect,
+    style::Color,
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::{
+    state::tasks::{StateTimes, TaskState},
+    view,
+};
+
+/// This is a Ratatui widget which breaks down how long a task has spent in
+/// each of its states as a stacked bar, with the percentage of time in each,
+/// so that a task which is often left scheduled but not polled, because the
+/// runtime is starved, stands out.
+pub(crate) struct StateBreakdown<'a> {
+    styles: &'a view::Styles,
+    times: StateTimes,
+}
+
+/// Each state in the bar, and how its segment is drawn, which differs by more
+/// than color so that the states can be told apart without colors.
+const SEGMENTS: &[(TaskState, &str, &str, Color)] = &[
+    (TaskState::Running, "\u{2588}", "#", Color::Green),
+    (TaskState::Scheduled, "\u{2593}", "=", Color::Yellow),
+    (TaskState::Idle, "\u{2591}", "-", Color::Cyan),
+    (TaskState::Completed, "\u{00B7}", ".", Color::Gray),
+];
+
+impl Widget for StateBreakdown<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let block = self.styles.border_block().title("Time in States");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let width = usize::from(inner.width);
+        let mut bar = Vec::new();
+        let mut legend = Vec::new();
+        // Each segment ends where the states so far end, rounded, so that the
+        // segments always add up to the width of the bar.
+        let mut percent = 0.0;
+        let mut filled = 0;
+        for &(state, utf8, ascii, color) in SEGMENTS {
+            let style = self.styles.fg(color);
+            let fill = self.styles.if_utf8(utf8, ascii);
+            let share = self.times.percent(self.time_in(state));
+            percent += share;
+            let end = ((percent / 100.0 * width as f64).round() as usize).min(width);
+            bar.push(Span::styled(fill.repeat(end - filled), style));
+            filled = end;
+
+            if !legend.is_empty() {
+                legend.push(Span::raw("  "));
+            }
+            legend.push(Span::styled(fill, style));
+            legend.push(Span::raw(format!(" {} {share:.1}%", state.description())));
+        }
+
+        Paragraph::new(vec![Line::from(bar), Line::from(legend)]).render(inner, buf);
+    }
+}
+
+impl<'a> StateBreakdown<'a> {
+    pub(crate) fn new(styles: &'a view::Styles, times: StateTimes) -> Self {
+        Self { styles, times }
+    }
+
+    /// How many lines the breakdown takes up, including its borders.
+    pub(crate) const HEIGHT: u16 = 4;
+
+    /// Returns how long the task has spent in `state`, counting every way
+    /// a task can end as completed.
+    fn time_in(&self, state: TaskState) -> Duration {
+        match state {
+            TaskState::Running => self.times.running,
+            TaskState::Scheduled => self.times.scheduled,
+            TaskState::Idle => self.times.idle,
+            TaskState::Completed | TaskState::Cancelled | TaskState::Panicked => {
+                self.times.completed
+            }
+        }
+    }
+}
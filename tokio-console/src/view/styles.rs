@@ -1,21 +1,30 @@
-use crate::config;
+use crate::{config, graphics::Graphics};
 use ratatui::{
     style::{Color, Modifier, Style},
     text::Span,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{rc::Rc, str::FromStr, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct Styles {
     palette: Palette,
+    vision: ColorVision,
+    theme: Theme,
+    /// Colors which the user has replaced with colors of their own, as
+    /// `(color, replacement)` pairs.
+    custom_colors: Vec<(Color, Color)>,
+    /// The images placed in the frame being rendered, if charts are drawn
+    /// as images.
+    graphics: Option<Rc<Graphics>>,
     toggles: config::ColorToggles,
     pub(crate) utf8: bool,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Deserialize, Serialize)]
 #[repr(u8)]
 pub enum Palette {
+    #[default]
     #[serde(rename = "off")]
     NoColors,
     /// Use ANSI 8 color palette only.
@@ -32,6 +41,38 @@ pub enum Palette {
     All,
 }
 
+/// Which colors are swapped for ones which are easier to tell apart, for
+/// users with red-green color blindness.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorVision {
+    /// Use the palette's colors as they are.
+    #[default]
+    Normal,
+    /// Swap reds and greens for oranges and blues.
+    Deuteranopia,
+    /// Swap reds and greens for oranges and blues, using brighter oranges,
+    /// since reds appear darker with protanopia.
+    Protanopia,
+}
+
+/// Which colors the console is drawn with, which can be switched between
+/// while it's running.
+#[derive(clap::ValueEnum, Debug, PartialEq, Eq, Copy, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// The palette's colors, for terminals with dark backgrounds.
+    #[default]
+    Dark,
+    /// Darker colors, which are readable on terminals with light backgrounds.
+    Light,
+    /// No colors or dimmed text, using only bold, reversed and underlined
+    /// text.
+    HighContrast,
+    /// No colors.
+    Monochrome,
+}
+
 /// Represents formatted time spans.
 ///
 /// Distinguishing between different units allows appropriate colouring.
@@ -70,8 +111,19 @@ fn fg_style(color: Color) -> Style {
 
 impl Styles {
     pub fn from_config(config: config::ViewOptions) -> Self {
+        let palette = config.determine_palette();
+        // Charts are drawn in color, so they're only drawn as images if
+        // colors are enabled.
+        let graphics = config
+            .graphics_protocol()
+            .filter(|_| palette != Palette::NoColors)
+            .map(|protocol| Rc::new(Graphics::new(protocol)));
         Self {
-            palette: config.determine_palette(),
+            palette,
+            vision: config.color_vision(),
+            theme: config.theme(),
+            custom_colors: config.custom_colors().to_vec(),
+            graphics,
             toggles: config.toggles(),
             utf8: config.is_utf8(),
         }
@@ -103,7 +155,7 @@ impl Styles {
         // Add all the config values to the GitHub issue metadata
         builder = cfg.add_issue_metadata(builder);
 
-        if self.palette == Palette::NoColors {
+        if self.palette == Palette::NoColors || !self.theme.has_colors() {
             // disable colors in error reports
             builder = builder.theme(Theme::new());
         }
@@ -174,21 +226,9 @@ impl Styles {
 
         let style = match self.palette {
             Palette::NoColors => return Span::raw(formatted.into_inner()),
-            Palette::Ansi8 | Palette::Ansi16 => match &formatted {
-                FormattedDuration::Days(_) => fg_style(Color::Blue),
-                FormattedDuration::DaysHours(_) => fg_style(Color::Blue),
-                FormattedDuration::HoursMinutes(_) => fg_style(Color::Cyan),
-                FormattedDuration::MinutesSeconds(_) => fg_style(Color::Green),
-                FormattedDuration::Debug(s) if s.ends_with("ps") => fg_style(Color::Gray),
-                FormattedDuration::Debug(s) if s.ends_with("ns") => fg_style(Color::Gray),
-                FormattedDuration::Debug(s) if s.ends_with("µs") || s.ends_with("us") => {
-                    fg_style(Color::Magenta)
-                }
-                FormattedDuration::Debug(s) if s.ends_with("ms") => fg_style(Color::Red),
-                FormattedDuration::Debug(s) if s.ends_with('s') => fg_style(Color::Yellow),
-                _ => Style::default(),
-            },
-            Palette::Ansi256 | Palette::All => match &formatted {
+            _ if !self.theme.has_colors() => return Span::raw(formatted.into_inner()),
+            // The 256-color gradient is too pale to read on light backgrounds.
+            Palette::Ansi256 | Palette::All if self.theme == Theme::Dark => match &formatted {
                 FormattedDuration::Days(_) => fg_style(Color::Indexed(33)), // dodger blue 1
                 FormattedDuration::DaysHours(_) => fg_style(Color::Indexed(33)), // dodger blue 1
                 FormattedDuration::HoursMinutes(_) => fg_style(Color::Indexed(39)), // deep sky blue 1
@@ -202,6 +242,20 @@ impl Styles {
                 FormattedDuration::Debug(s) if s.ends_with('s') => fg_style(Color::Indexed(44)), // dark turquoise,
                 _ => Style::default(),
             },
+            _ => match &formatted {
+                FormattedDuration::Days(_) => self.fg(Color::Blue),
+                FormattedDuration::DaysHours(_) => self.fg(Color::Blue),
+                FormattedDuration::HoursMinutes(_) => self.fg(Color::Cyan),
+                FormattedDuration::MinutesSeconds(_) => self.fg(Color::Green),
+                FormattedDuration::Debug(s) if s.ends_with("ps") => self.fg(Color::Gray),
+                FormattedDuration::Debug(s) if s.ends_with("ns") => self.fg(Color::Gray),
+                FormattedDuration::Debug(s) if s.ends_with("µs") || s.ends_with("us") => {
+                    self.fg(Color::Magenta)
+                }
+                FormattedDuration::Debug(s) if s.ends_with("ms") => self.fg(Color::Red),
+                FormattedDuration::Debug(s) if s.ends_with('s') => self.fg(Color::Yellow),
+                _ => Style::default(),
+            },
         };
 
         Span::styled(formatted.into_inner(), style)
@@ -257,7 +311,17 @@ impl Styles {
             return Style::default();
         }
 
-        Style::default().add_modifier(Modifier::DIM)
+        Style::default().add_modifier(self.dim())
+    }
+
+    /// Returns the modifier for text which is less important than the text
+    /// around it, which is nothing in high contrast mode.
+    pub fn dim(&self) -> Modifier {
+        if self.theme == Theme::HighContrast {
+            Modifier::empty()
+        } else {
+            Modifier::DIM
+        }
     }
 
     pub fn fg(&self, color: Color) -> Style {
@@ -282,6 +346,13 @@ impl Styles {
         )
     }
 
+    pub fn bookmark(&self) -> Span<'static> {
+        Span::styled(
+            self.if_utf8("\u{2605}", "*"),
+            self.fg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+        )
+    }
+
     pub fn selected(&self, value: &str) -> Span<'static> {
         let style = if let Some(cyan) = self.color(Color::Cyan) {
             Style::default().fg(cyan)
@@ -301,8 +372,31 @@ impl Styles {
         self.selected(&value)
     }
 
+    /// Switches to the next theme, so that themes can be tried out while the
+    /// console is running.
+    pub(crate) fn cycle_theme(&mut self) {
+        self.theme = match self.theme {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::HighContrast,
+            Theme::HighContrast => Theme::Monochrome,
+            Theme::Monochrome => Theme::Dark,
+        };
+    }
+
     pub fn color(&self, color: Color) -> Option<Color> {
         use Palette::*;
+        if !self.theme.has_colors() {
+            return None;
+        }
+        let custom = self
+            .custom_colors
+            .iter()
+            .find(|(replaced, _)| *replaced == color)
+            .map(|&(_, replacement)| replacement);
+        let color = custom.unwrap_or_else(|| {
+            let color = self.vision.swap(self.palette, color);
+            self.theme.adjust(self.palette, color)
+        });
         match (self.palette, color) {
             // If colors are disabled, no colors.
             (NoColors, _) => None,
@@ -332,6 +426,11 @@ impl Styles {
         }
     }
 
+    /// Returns where to place images, if charts are drawn as images.
+    pub(crate) fn graphics(&self) -> Option<&Graphics> {
+        self.graphics.as_deref().filter(|_| self.theme.has_colors())
+    }
+
     pub fn border_block(&self) -> ratatui::widgets::Block<'_> {
         if self.utf8 {
             ratatui::widgets::Block::default()
@@ -344,6 +443,83 @@ impl Styles {
     }
 }
 
+// === impl Theme ===
+
+impl Theme {
+    fn has_colors(self) -> bool {
+        matches!(self, Theme::Dark | Theme::Light)
+    }
+
+    /// Darkens the colors which are hard to read on light backgrounds, using
+    /// the colors available in `palette`.
+    fn adjust(self, palette: Palette, color: Color) -> Color {
+        let indexed = matches!(palette, Palette::Ansi256 | Palette::All);
+        match (self, color) {
+            (Theme::Light, Color::Yellow | Color::LightYellow) if indexed => Color::Indexed(136),
+            (Theme::Light, Color::Cyan | Color::LightCyan) if indexed => Color::Indexed(30),
+            (Theme::Light, Color::Green | Color::LightGreen) if indexed => Color::Indexed(28),
+            (Theme::Light, Color::LightYellow) => Color::Yellow,
+            (Theme::Light, Color::LightCyan) => Color::Cyan,
+            (Theme::Light, Color::LightRed) => Color::Red,
+            (Theme::Light, Color::LightGreen) => Color::Green,
+            (Theme::Light, Color::LightBlue) => Color::Blue,
+            (Theme::Light, Color::LightMagenta) => Color::Magenta,
+            (Theme::Light, Color::White | Color::Gray) => Color::Black,
+            (_, color) => color,
+        }
+    }
+}
+
+// === impl ColorVision ===
+
+impl ColorVision {
+    /// Swaps reds and greens for colors which can be told apart with this
+    /// kind of color vision, from those available in `palette`.
+    ///
+    /// The RGB colors are from the Okabe-Ito palette.
+    fn swap(self, palette: Palette, color: Color) -> Color {
+        let protanopia = self == ColorVision::Protanopia;
+        match (self, palette, color) {
+            (ColorVision::Normal, _, color) => color,
+            (_, Palette::All, Color::Green) => Color::Rgb(0, 114, 178),
+            (_, Palette::All, Color::LightGreen) => Color::Rgb(86, 180, 233),
+            (_, Palette::All, Color::Red | Color::LightRed) if protanopia => {
+                Color::Rgb(230, 159, 0)
+            }
+            (_, Palette::All, Color::Red) => Color::Rgb(213, 94, 0),
+            (_, Palette::All, Color::LightRed) => Color::Rgb(230, 159, 0),
+            (_, Palette::Ansi256, Color::Green) => Color::Indexed(32),
+            (_, Palette::Ansi256, Color::LightGreen) => Color::Indexed(74),
+            (_, Palette::Ansi256, Color::Red | Color::LightRed) if protanopia => {
+                Color::Indexed(214)
+            }
+            (_, Palette::Ansi256, Color::Red) => Color::Indexed(166),
+            (_, Palette::Ansi256, Color::LightRed) => Color::Indexed(214),
+            // There's no orange in the ANSI 16 colors, so magenta stands in
+            // for red.
+            (_, _, Color::Green) => Color::Blue,
+            (_, _, Color::LightGreen) => Color::LightBlue,
+            (_, _, Color::Red) if protanopia => Color::LightMagenta,
+            (_, _, Color::Red) => Color::Magenta,
+            (_, _, Color::LightRed) => Color::LightMagenta,
+            (_, _, color) => color,
+        }
+    }
+}
+
+impl FromStr for ColorVision {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("normal") => Ok(ColorVision::Normal),
+            s if s.eq_ignore_ascii_case("deuteranopia") => Ok(ColorVision::Deuteranopia),
+            s if s.eq_ignore_ascii_case("protanopia") => Ok(ColorVision::Protanopia),
+            _ => Err("invalid color vision"),
+        }
+    }
+}
+
 // === impl Palette ===
 
 impl FromStr for Palette {
@@ -362,8 +538,68 @@ impl FromStr for Palette {
     }
 }
 
-impl Default for Palette {
-    fn default() -> Self {
-        Self::NoColors
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_vision_swaps_red_and_green() {
+        let palettes = [
+            Palette::Ansi8,
+            Palette::Ansi16,
+            Palette::Ansi256,
+            Palette::All,
+        ];
+        for vision in [ColorVision::Deuteranopia, ColorVision::Protanopia] {
+            for palette in palettes {
+                for color in [Color::Red, Color::LightRed, Color::Green, Color::LightGreen] {
+                    let swapped = vision.swap(palette, color);
+                    assert!(
+                        !matches!(
+                            swapped,
+                            Color::Red | Color::LightRed | Color::Green | Color::LightGreen
+                        ),
+                        "{vision:?} with {palette:?} kept {color:?} as {swapped:?}"
+                    );
+                }
+                assert_eq!(vision.swap(palette, Color::Yellow), Color::Yellow);
+            }
+        }
+        assert_eq!(
+            ColorVision::Normal.swap(Palette::All, Color::Red),
+            Color::Red
+        );
+    }
+
+    #[test]
+    fn themes_cycle() {
+        let mut styles = Styles::from_config(config::ViewOptions::default());
+        assert_eq!(styles.color(Color::Yellow), Some(Color::Yellow));
+
+        styles.cycle_theme();
+        assert_eq!(styles.theme, Theme::Light);
+        assert_ne!(styles.color(Color::Yellow), Some(Color::Yellow));
+        assert_eq!(styles.color(Color::White), Some(Color::Black));
+
+        styles.cycle_theme();
+        assert_eq!(styles.theme, Theme::HighContrast);
+        assert_eq!(styles.color(Color::Yellow), None);
+        assert_eq!(styles.dim(), Modifier::empty());
+
+        styles.cycle_theme();
+        assert_eq!(styles.theme, Theme::Monochrome);
+        assert_eq!(styles.color(Color::Yellow), None);
+        assert_eq!(styles.dim(), Modifier::DIM);
+
+        styles.cycle_theme();
+        assert_eq!(styles.theme, Theme::Dark);
+    }
+
+    #[test]
+    fn custom_colors_replace_theme_colors() {
+        let mut styles = Styles::from_config(config::ViewOptions::default());
+        styles.custom_colors = vec![(Color::Yellow, Color::Rgb(215, 175, 0))];
+        styles.cycle_theme();
+        assert_eq!(styles.color(Color::Yellow), Some(Color::Rgb(215, 175, 0)));
     }
 }
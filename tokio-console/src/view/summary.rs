@@ -0,0 +1,93 @@
+use crate::{
+    i18n::{tr, tr_fmt},
+    state::{tasks::TaskState, State},
+    view::{self, bold},
+};
+use ratatui::{
+    layout,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use std::time::{Duration, SystemTime};
+
+/// Totals over the whole program, which are shown in a bar above the task
+/// list, so that they don't have to be added up from the table's columns.
+#[derive(Debug, Default)]
+pub(crate) struct Summary {
+    running: usize,
+    idle: usize,
+    completed: usize,
+    busy: Duration,
+    resources: usize,
+    dropped_events: u64,
+    /// How long ago the last update was received, if one has been.
+    data_age: Option<Duration>,
+}
+
+impl Summary {
+    /// The height of the bar.
+    pub(crate) const HEIGHT: u16 = 1;
+
+    /// Returns the totals of everything in `state`, as of `now`.
+    pub(crate) fn new(state: &State, now: SystemTime) -> Self {
+        let mut summary = Self {
+            resources: state.resources_state().resources().count(),
+            dropped_events: state.tasks_state().dropped_events()
+                + state.resources_state().dropped_events()
+                + state.async_ops_state().dropped_events(),
+            data_age: state.data_age(),
+            ..Self::default()
+        };
+        for task in state
+            .tasks_state()
+            .tasks()
+            .filter_map(|task| task.upgrade())
+        {
+            let task = task.borrow();
+            match task.state() {
+                // A scheduled task has been woken, so it's about to run.
+                TaskState::Running | TaskState::Scheduled => summary.running += 1,
+                TaskState::Idle => summary.idle += 1,
+                TaskState::Completed | TaskState::Cancelled | TaskState::Panicked => {
+                    summary.completed += 1
+                }
+            }
+            summary.busy += task.busy(now);
+        }
+        summary
+    }
+
+    pub(crate) fn render(
+        &self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+    ) {
+        let mut spans = vec![
+            bold(tr("Tasks: ")),
+            Span::raw(tr_fmt(
+                "{} running, {} idle, {} completed",
+                &[&self.running, &self.idle, &self.completed],
+            )),
+            Span::raw("  "),
+            bold(tr("Busy: ")),
+            styles.time_units(self.busy, view::DUR_LIST_PRECISION, None),
+            Span::raw("  "),
+            bold(tr("Resources: ")),
+            Span::raw(self.resources.to_string()),
+            Span::raw("  "),
+            bold(tr("Dropped Events: ")),
+            Span::raw(self.dropped_events.to_string()),
+        ];
+        if let Some(age) = self.data_age {
+            // Updates are published about once a second, so the age is only
+            // worth showing in whole seconds.
+            spans.extend([
+                Span::raw("  "),
+                bold(tr("Last Update: ")),
+                Span::raw(tr_fmt("{}s ago", &[&age.as_secs()])),
+            ]);
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
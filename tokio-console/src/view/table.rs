@@ -1,20 +1,32 @@
 use crate::{
-    input, state,
+    export,
+    i18n::tr,
+    input,
+    query::Value,
+    state,
     view::{
         self,
-        controls::{controls_paragraph, ControlDisplay, KeyDisplay},
+        controls::{ControlDisplay, KeyDisplay},
         help::HelpText,
+        sort,
     },
 };
 use ratatui::{
-    layout,
-    widgets::{Paragraph, TableState},
+    layout::{self, Position, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Cell, TableState},
 };
-use std::convert::TryFrom;
+use regex::Regex;
+use std::{borrow::Cow, convert::TryFrom, fmt, path::PathBuf, str::FromStr, time::SystemTime};
 
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
+/// How many rows are formatted above and below the displayed rows, in case
+/// the table widget scrolls a little differently than expected.
+const WINDOW_MARGIN: usize = 4;
+
 pub(crate) trait TableList<const N: usize> {
     type Row;
     type Sort: SortBy + TryFrom<usize>;
@@ -23,6 +35,46 @@ pub(crate) trait TableList<const N: usize> {
     const HEADER: &'static [&'static str; N];
     const WIDTHS: &'static [usize; N];
 
+    /// Groups of controls which are available in this table, in addition to
+    /// the controls shared by all tables.
+    const EXTRA_CONTROLS: &'static [&'static [ControlDisplay]] = &[];
+
+    /// Whether pressing `g` twice scrolls to the top of the table, which
+    /// tables that use `g` for something else turn off.
+    const GG_SCROLLS_TO_TOP: bool = true;
+
+    /// Returns `true` if `row` matches `search`, so that it's shown while
+    /// the table is being searched.
+    fn matches(row: &Self::Row, search: &Search) -> bool;
+
+    /// Returns the names of every column, including the columns which are
+    /// displayed in addition to the `HEADER`.
+    fn headers(_state: &state::State) -> Vec<String> {
+        Self::HEADER
+            .iter()
+            .map(|&header| header.to_owned())
+            .collect()
+    }
+
+    /// Returns the value of each of `row`'s columns at `now`, in the order of
+    /// [`Self::headers`], or `None` if the row isn't displayed.
+    fn values(
+        row: &Self::Row,
+        now: SystemTime,
+        state: &state::State,
+        cx: &Self::Context,
+    ) -> Option<Vec<Value>>;
+
+    /// Adds the rows which are new in `state` to the table, and re-sorts it,
+    /// as of `now`.
+    fn refresh(
+        table_list_state: &mut TableListState<Self, N>,
+        now: SystemTime,
+        state: &mut state::State,
+        cx: &Self::Context,
+    ) where
+        Self: Sized;
+
     fn render(
         state: &mut TableListState<Self, N>,
         styles: &view::Styles,
@@ -35,72 +87,763 @@ pub(crate) trait TableList<const N: usize> {
 }
 
 pub(crate) trait SortBy {
+    /// The value of a row which the row is sorted by.
+    type Key: Ord;
+
     fn as_column(&self) -> usize;
 }
 
+/// The sorting, search and hidden columns of a table, which are saved
+/// between sessions.
+///
+/// Columns are saved by name, since the columns which are displayed in
+/// addition to the table's `HEADER`, and so the positions of the columns
+/// after them, differ between sessions.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct TableSettings {
+    sort_column: String,
+    sort_descending: bool,
+    /// The query filtering the table's rows, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    search: Option<String>,
+    /// The columns which are hidden, or `None` if the table hadn't been
+    /// drawn yet, so which columns it has wasn't known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hidden_columns: Option<Vec<String>>,
+    /// Columns to sort by within rows which are equal in the sort column.
+    ///
+    /// This is last, since TOML tables can't be followed by plain values.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    then_by: Vec<SortKeySettings>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+struct SortKeySettings {
+    column: String,
+    descending: bool,
+}
+
+/// A query which filters a table's rows.
+///
+/// The query is a regular expression, or a plain substring if it isn't a
+/// valid one, and is case-insensitive unless it contains an uppercase
+/// letter.
+#[derive(Debug)]
+pub(crate) struct Search {
+    query: String,
+    regex: Regex,
+}
+
+/// Where a table was last drawn, so that mouse clicks can be matched to its
+/// columns and rows.
+#[derive(Debug, Default)]
+struct TableLayout {
+    header: Rect,
+    rows: Rect,
+    /// The start and width of each column.
+    columns: Vec<(u16, u16)>,
+}
+
+/// A column which a table is configured to display, written as its name,
+/// optionally followed by a fixed width, such as `name:24`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct ColumnConfig {
+    name: String,
+    width: Option<u16>,
+}
+
+/// The column which a table is configured to be sorted by, written as its
+/// name, optionally followed by `:asc` to show the lowest values first or
+/// `:desc` to show the highest values first, such as `busy:desc`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct SortConfig {
+    name: String,
+    ascending: bool,
+}
+
+/// Where a table's column is displayed, and how wide it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ColumnChoice {
+    /// The column's index in the table's header.
+    column: usize,
+    pub(crate) name: String,
+    pub(crate) hidden: bool,
+    /// The column's width, if it's fixed rather than fitting its contents.
+    pub(crate) width: Option<u16>,
+}
+
 pub(crate) struct TableListState<T: TableList<N>, const N: usize> {
     pub(crate) sorted_items: Vec<Weak<RefCell<T::Row>>>,
     pub(crate) sort_by: T::Sort,
+    /// Secondary sort keys, in order of priority, which order rows that are
+    /// equal in all of the previous keys.
+    ///
+    /// Each key's direction has the same meaning as `sort_descending`.
+    pub(crate) then_by: Vec<(T::Sort, bool)>,
     pub(crate) selected_column: usize,
     pub(crate) sort_descending: bool,
     pub(crate) table_state: TableState,
+    /// The number of columns displayed in addition to the table's `HEADER`.
+    pub(crate) extra_columns: usize,
+    /// The query filtering the table's rows, if any.
+    search: Option<Search>,
+    /// Whether the search query is being typed, so that it receives all
+    /// keyboard input.
+    editing_search: bool,
+    /// The items which don't match the search, which are kept so that they
+    /// are shown again when the search changes.
+    hidden: Vec<Weak<RefCell<T::Row>>>,
+    layout: TableLayout,
+    /// The columns which the table is configured to display, in order, or
+    /// nothing to display every column.
+    column_config: Vec<ColumnConfig>,
+    /// Every column, in the order they're displayed, including the hidden
+    /// columns.
+    ///
+    /// This is empty until the table is first drawn, since the columns
+    /// which are displayed in addition to the `HEADER` aren't known until
+    /// then.
+    columns: Vec<ColumnChoice>,
+    /// The selected item, and which of the displayed rows it was on, so that
+    /// the selection stays on it when the items are re-sorted or new items
+    /// are added.
+    anchor: Option<(Weak<RefCell<T::Row>>, usize)>,
+    /// Settings which were restored before the table's columns were known,
+    /// which are applied once they're chosen.
+    restored: Option<TableSettings>,
+    /// What the rows were last sorted by, so that they're only sorted again
+    /// once something which could reorder them has changed, rather than on
+    /// every frame.
+    sorted: Option<SortStamp>,
+    /// The sort keys of the items at the start of `sorted_items`, as of when
+    /// they were last sorted, so that only the items whose keys have changed
+    /// since, or which are new, are moved when they're sorted again.
+    sort_keys: Vec<RowKeys<T::Sort>>,
 
     last_key_event: Option<input::KeyEvent>,
 }
 
+/// The rows of a table which are formatted, because they're displayed or are
+/// close to it, and the state to render just those rows with.
+pub(crate) struct Window {
+    /// The first formatted row's index among all of the table's rows.
+    start: usize,
+    state: TableState,
+}
+
+/// The update and the sort keys which a table's rows were sorted as of.
+#[derive(Clone, Debug, PartialEq)]
+struct SortStamp {
+    /// The update the rows were sorted as of, or `None` if something has
+    /// changed how they compare since.
+    now: Option<SystemTime>,
+    /// Each sort key's column and direction, in order of priority.
+    keys: Vec<(usize, bool)>,
+}
+
+/// A row's value for each of the keys its table is sorted by, in order of
+/// priority, or `None`s if the row has been dropped.
+type RowKeys<S> = Box<[Option<<S as SortBy>::Key>]>;
+
 impl<T: TableList<N>, const N: usize> TableListState<T, N> {
     pub(in crate::view) fn len(&self) -> usize {
         self.sorted_items.len()
     }
 
     pub(in crate::view) fn update_input(&mut self, event: input::Event) {
-        // Clippy likes to remind us that we could use an `if let` here, since
-        // the match only has one arm...but this is a `match` because I
-        // anticipate adding more cases later...
-        #[allow(clippy::single_match)]
         match event {
             input::Event::Key(event) => self.key_input(event),
-            _ => {
-                // do nothing for now
-                // TODO(eliza): mouse input would be cool...
+            input::Event::Mouse(event) => {
+                self.mouse_input(event);
+            }
+            _ => {}
+        }
+    }
+
+    /// Handles a click or scroll in the table.
+    ///
+    /// Clicking a column's header sorts by it, or reverses the sorting if
+    /// the table is already sorted by it, and clicking a row selects it.
+    /// Returns `true` if the selected row was clicked again, which opens it.
+    pub(in crate::view) fn mouse_input(&mut self, event: input::MouseEvent) -> bool {
+        use input::MouseEventKind::*;
+        if self.editing_search {
+            return false;
+        }
+        let position = Position::new(event.column, event.row);
+        let in_table = self
+            .layout
+            .header
+            .union(self.layout.rows)
+            .contains(position);
+        match event.kind {
+            ScrollDown if in_table => self.scroll_with(|rows, i| (i + 1).min(rows.len() - 1)),
+            ScrollUp if in_table => self.scroll_with(|_, i| i.saturating_sub(1)),
+            Down(input::MouseButton::Left) if self.layout.header.contains(position) => {
+                let column = self
+                    .layout
+                    .columns
+                    .iter()
+                    .position(|&(x, width)| (x..x + width).contains(&event.column))
+                    .and_then(|idx| self.column_order().nth(idx));
+                if let Some(column) = column {
+                    if column == self.sort_by.as_column() {
+                        self.sort_descending = !self.sort_descending;
+                    } else {
+                        self.selected_column = column;
+                        self.sort_by_selected();
+                    }
+                }
             }
+            Down(input::MouseButton::Left) if self.layout.rows.contains(position) => {
+                let row = self.table_state.offset() + usize::from(event.row - self.layout.rows.y);
+                if row < self.sorted_items.len() {
+                    let reselected = self.table_state.selected() == Some(row);
+                    self.table_state.select(Some(row));
+                    self.anchor_selection();
+                    return reselected;
+                }
+            }
+            _ => {}
         }
+        false
+    }
+
+    /// Records where the table was drawn, inside its borders, and the widths
+    /// of its columns.
+    ///
+    /// This must be called after drawing the table, so that its scroll
+    /// offset is up to date.
+    pub(in crate::view) fn record_layout(&mut self, area: Rect, widths: &[layout::Constraint]) {
+        let selection_width = if self.table_state.selected().is_some() {
+            view::TABLE_HIGHLIGHT_SYMBOL.len() as u16
+        } else {
+            0
+        };
+        // This is how ratatui lays out the columns of a `Table` with one
+        // column of spacing.
+        let [_, columns] = layout::Layout::horizontal([
+            layout::Constraint::Length(selection_width),
+            layout::Constraint::Fill(0),
+        ])
+        .areas(area);
+        let columns = layout::Layout::horizontal(widths.iter().copied())
+            .flex(layout::Flex::Start)
+            .spacing(1)
+            .split(columns);
+        let [header, rows] =
+            layout::Layout::vertical([layout::Constraint::Length(1), layout::Constraint::Fill(0)])
+                .areas(area);
+        self.layout = TableLayout {
+            header,
+            rows,
+            columns: columns
+                .iter()
+                .map(|column| (column.x, column.width))
+                .collect(),
+        };
     }
 
     pub(in crate::view) fn key_input(&mut self, event: input::KeyEvent) {
         use input::KeyCode::*;
-        let header_len = T::HEADER.len();
+        if self.editing_search {
+            self.search_input(event);
+            return;
+        }
         let code = event.code;
+        // Holding shift while selecting a column moves the selection without
+        // changing the sorting, so that the column can be added as a
+        // secondary sort key.
+        let shift = event.modifiers.contains(input::KeyModifiers::SHIFT);
         match code {
-            Left | Char('h') => {
-                if self.selected_column == 0 {
-                    self.selected_column = header_len - 1;
-                } else {
-                    self.selected_column -= 1;
+            Left | Char('h') | Char('H') => {
+                self.select_column_with(|len, i| if i == 0 { len - 1 } else { i - 1 });
+                if !shift && code != Char('H') {
+                    self.sort_by_selected();
                 }
             }
-            Right | Char('l') => {
-                if self.selected_column == header_len - 1 {
-                    self.selected_column = 0;
-                } else {
-                    self.selected_column += 1;
+            Right | Char('l') | Char('L') => {
+                self.select_column_with(|len, i| if i == len - 1 { 0 } else { i + 1 });
+                if !shift && code != Char('L') {
+                    self.sort_by_selected();
+                }
+            }
+            Char('+') => self.then_by_selected(),
+            Char('-') => {
+                let selected = self.selected_column;
+                self.then_by.retain(|(key, _)| key.as_column() != selected);
+            }
+            Char('i') => {
+                let selected = self.selected_column;
+                match self
+                    .then_by
+                    .iter_mut()
+                    .find(|(key, _)| key.as_column() == selected)
+                {
+                    Some((_, descending)) => *descending = !*descending,
+                    None => self.sort_descending = !self.sort_descending,
                 }
             }
-            Char('i') => self.sort_descending = !self.sort_descending,
             Down | Char('j') => self.scroll_next(),
             Up | Char('k') => self.scroll_prev(),
-            Char('G') => self.scroll_to_last(),
-            Char('g') if self.last_key_event.map(|e| e.code) == Some(Char('g')) => {
+            PageDown => self.scroll_half_page(true),
+            PageUp => self.scroll_half_page(false),
+            Home => self.scroll_to_first(),
+            End | Char('G') => self.scroll_to_last(),
+            Char('g')
+                if T::GG_SCROLLS_TO_TOP
+                    && self.last_key_event.map(|e| e.code) == Some(Char('g')) =>
+            {
                 self.scroll_to_first()
             }
+            Char('/') => self.editing_search = true,
+            Esc => self.search = None,
             _ => {} // do nothing for now...
         }
 
+        self.last_key_event = Some(event);
+    }
+
+    /// Edits the search query, which is applied as it's typed.
+    fn search_input(&mut self, event: input::KeyEvent) {
+        use input::KeyCode::*;
+        let mut query = self
+            .search
+            .take()
+            .map(|search| search.query)
+            .unwrap_or_default();
+        match event.code {
+            Enter => self.editing_search = false,
+            Esc => {
+                self.editing_search = false;
+                query.clear();
+            }
+            Backspace => {
+                query.pop();
+            }
+            Char(c) => query.push(c),
+            _ => {}
+        }
+        self.search = Search::new(query);
+        // Show the first match, since the selected row may be hidden.
+        self.table_state.select(None);
+        self.anchor = None;
+    }
+
+    /// Returns `true` if the search query is being typed.
+    pub(in crate::view) fn is_editing_search(&self) -> bool {
+        self.editing_search
+    }
+
+    /// Returns `true` if the rows are filtered by a search.
+    pub(in crate::view) fn is_searched(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Hides the items which don't match the search, and shows the hidden
+    /// items which do.
+    ///
+    /// This must be called after new items are added, and before they're
+    /// sorted.
+    pub(in crate::view) fn apply_search(&mut self) {
+        self.sorted_items.append(&mut self.hidden);
+        let Some(ref search) = self.search else {
+            return;
+        };
+        // Items which have been dropped are left for the table to skip.
+        self.hidden = sort::partition(&mut self.sorted_items, &mut self.sort_keys, |item| {
+            item.upgrade()
+                .map_or(true, |row| T::matches(&row.borrow(), search))
+        });
+    }
+
+    /// Hides the shown items which don't match `f`, as well as those which
+    /// don't match the search.
+    ///
+    /// This must be called after [`TableListState::apply_search`], which
+    /// shows every item which matches the search again.
+    pub(in crate::view) fn hide_unless(&mut self, f: impl Fn(&T::Row) -> bool) {
+        let mut hidden = sort::partition(&mut self.sorted_items, &mut self.sort_keys, |item| {
+            item.upgrade().map_or(true, |row| f(&row.borrow()))
+        });
+        self.hidden.append(&mut hidden);
+    }
+
+    /// Removes the items which have been dropped.
+    pub(in crate::view) fn remove_dropped(&mut self) {
+        sort::partition(&mut self.sorted_items, &mut self.sort_keys, |item| {
+            item.strong_count() > 0
+        });
+    }
+
+    /// Returns the search, for the table's title, if one is being typed or
+    /// applied.
+    pub(in crate::view) fn search_title(&self, styles: &view::Styles) -> Option<Span<'static>> {
+        let query = self
+            .search
+            .as_ref()
+            .map_or("", |search| search.query.as_str());
+        let cursor = if self.editing_search {
+            styles.if_utf8("\u{2588}", "_")
+        } else if query.is_empty() {
+            return None;
+        } else {
+            ""
+        };
+        Some(view::bold(format!(" /{query}{cursor}")))
+    }
+
+    /// Removes every item, such as when connecting to a new target.
+    pub(in crate::view) fn clear_items(&mut self) {
+        self.sorted_items.clear();
+        self.hidden.clear();
+        self.anchor = None;
+        self.sorted = None;
+        self.sort_keys.clear();
+    }
+
+    /// Selects the column at `f(columns, position)` among the displayed
+    /// columns, where `position` is the selected column's position, in
+    /// their order on the screen.
+    fn select_column_with(&mut self, f: impl Fn(usize, usize) -> usize) {
+        let order = self.column_order().collect::<Vec<_>>();
+        if order.is_empty() {
+            return;
+        }
+        let position = order
+            .iter()
+            .position(|&column| column == self.selected_column)
+            .unwrap_or(0);
+        self.selected_column = order[f(order.len(), position)];
+    }
+
+    /// Returns the index in the header of each displayed column, in the order
+    /// they're displayed.
+    fn column_order(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        if self.columns.is_empty() {
+            return Box::new(0..T::HEADER.len() + self.extra_columns);
+        }
+        Box::new(
+            self.columns
+                .iter()
+                .filter(|choice| !choice.hidden)
+                .map(|choice| choice.column),
+        )
+    }
+
+    /// Configures which columns are displayed, in which order, and how wide
+    /// they are.
+    ///
+    /// Columns from the table's `HEADER` which aren't configured are hidden,
+    /// but the columns which are displayed in addition to them are displayed
+    /// after the configured columns, unless they're configured themselves.
+    pub(in crate::view) fn configure_columns(&mut self, config: Vec<ColumnConfig>) {
+        self.column_config = config;
+        self.columns.clear();
+    }
+
+    /// Returns the index in `headers` of each displayed column, in the order
+    /// they're displayed, where `headers` are the names of all of the table's
+    /// columns.
+    pub(in crate::view) fn visible_columns(&mut self, headers: &[&str]) -> Vec<usize> {
+        let unchanged = self.columns.len() == headers.len()
+            && self
+                .columns
+                .iter()
+                .all(|choice| headers[choice.column] == choice.name);
+        if !unchanged {
+            let mut columns = self.choose_columns(headers);
+            // Columns which were hidden stay hidden when other columns are
+            // added, such as when a task with a new field is spawned.
+            if !self.columns.is_empty() {
+                for choice in &mut columns {
+                    if let Some(previous) = self.columns.iter().find(|c| c.name == choice.name) {
+                        choice.hidden = previous.hidden;
+                    }
+                }
+            }
+            self.columns = columns;
+        }
+        if let Some(settings) = self.restored.take() {
+            self.apply_settings(settings, headers);
+        }
+        self.column_order().collect()
+    }
+
+    fn choose_columns(&self, headers: &[&str]) -> Vec<ColumnChoice> {
+        let choice = |column: usize, hidden, width| ColumnChoice {
+            column,
+            name: headers[column].to_owned(),
+            hidden,
+            width,
+        };
+        if self.column_config.is_empty() {
+            return (0..headers.len())
+                .map(|column| choice(column, false, None))
+                .collect();
+        }
+        let mut choices: Vec<ColumnChoice> = Vec::with_capacity(headers.len());
+        for config in &self.column_config {
+            let column = headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(&config.name));
+            if let Some(column) = column {
+                if !choices.iter().any(|choice| choice.column == column) {
+                    choices.push(choice(column, false, config.width));
+                }
+            }
+        }
+        for (column, header) in headers.iter().enumerate() {
+            if !choices.iter().any(|choice| choice.column == column) {
+                choices.push(choice(column, T::HEADER.contains(header), None));
+            }
+        }
+        choices
+    }
+
+    /// Returns every column, in the order they're displayed, including the
+    /// hidden columns.
+    pub(in crate::view) fn columns(&self) -> &[ColumnChoice] {
+        &self.columns
+    }
+
+    /// Returns the fixed width of the column at `column` in the header, if
+    /// it has one.
+    pub(in crate::view) fn column_width(&self, column: usize) -> Option<u16> {
+        self.columns
+            .iter()
+            .find(|choice| choice.column == column)
+            .and_then(|choice| choice.width)
+    }
+
+    /// Hides or shows the column at `idx` in [`Self::columns`], unless it's
+    /// the only column which is displayed.
+    pub(in crate::view) fn toggle_column(&mut self, idx: usize) {
+        let visible = self.columns.iter().filter(|choice| !choice.hidden).count();
+        if let Some(choice) = self.columns.get_mut(idx) {
+            if choice.hidden || visible > 1 {
+                choice.hidden = !choice.hidden;
+            }
+        }
+    }
+
+    /// Swaps the column at `idx` in [`Self::columns`] with the one at `to`.
+    pub(in crate::view) fn move_column(&mut self, idx: usize, to: usize) {
+        if idx < self.columns.len() && to < self.columns.len() {
+            self.columns.swap(idx, to);
+        }
+    }
+
+    /// Widens the column at `idx` in [`Self::columns`] by `delta`, or narrows
+    /// it if `delta` is negative, fixing its width if it fit its contents.
+    pub(in crate::view) fn resize_column(&mut self, idx: usize, delta: i16) {
+        let Some(choice) = self.columns.get(idx) else {
+            return;
+        };
+        let width = match choice.width {
+            Some(width) => width,
+            // Start from the width it was last drawn with.
+            None if choice.hidden => return,
+            None => {
+                let position = self.columns[..idx]
+                    .iter()
+                    .filter(|choice| !choice.hidden)
+                    .count();
+                match self.layout.columns.get(position) {
+                    Some(&(_, width)) => width,
+                    None => return,
+                }
+            }
+        };
+        self.columns[idx].width = Some(width.saturating_add_signed(delta).max(1));
+    }
+
+    /// Makes the column at `idx` in [`Self::columns`] fit its contents again.
+    pub(in crate::view) fn reset_column_width(&mut self, idx: usize) {
+        if let Some(choice) = self.columns.get_mut(idx) {
+            choice.width = None;
+        }
+    }
+
+    /// Sorts by the column named in `sort`, if it's one of `headers`.
+    pub(in crate::view) fn configure_sort(&mut self, sort: &SortConfig, headers: &[String]) {
+        let column = headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(&sort.name));
+        if let Some((column, sort_by)) =
+            column.and_then(|column| Some((column, T::Sort::try_from(column).ok()?)))
+        {
+            self.sort_by = sort_by;
+            self.selected_column = column;
+            self.then_by.clear();
+            // Rows are displayed in reverse unless `sort_descending` is set,
+            // so that the highest values are shown first by default.
+            self.sort_descending = sort.ascending;
+        }
+    }
+
+    /// Sorts by the selected column alone.
+    fn sort_by_selected(&mut self) {
         if let Ok(sort_by) = T::Sort::try_from(self.selected_column) {
             self.sort_by = sort_by;
+            self.then_by.clear();
         }
+    }
 
-        self.last_key_event = Some(event);
+    /// Adds the selected column as the lowest priority sort key, unless the
+    /// table is already sorted by it.
+    fn then_by_selected(&mut self) {
+        let selected = self.selected_column;
+        if self.sort_by.as_column() == selected
+            || self
+                .then_by
+                .iter()
+                .any(|(key, _)| key.as_column() == selected)
+        {
+            return;
+        }
+        if let Ok(key) = T::Sort::try_from(selected) {
+            self.then_by.push((key, false));
+        }
+    }
+
+    /// Sorts the table's items by each of its sort keys as of `now`, using
+    /// `key` to get a row's value for a single key.
+    ///
+    /// The items are only sorted if there's been an update, new items have
+    /// been added, or the table's sorting has changed, since they were last
+    /// sorted. Only the items whose keys have changed since are moved, unless
+    /// the sorting has changed.
+    pub(in crate::view) fn sort_items(
+        &mut self,
+        now: SystemTime,
+        key: impl Fn(&T::Sort, &T::Row) -> <T::Sort as SortBy>::Key,
+    ) {
+        let stamp = SortStamp {
+            now: Some(now),
+            keys: std::iter::once((self.sort_by.as_column(), self.sort_descending))
+                .chain(
+                    self.then_by
+                        .iter()
+                        .map(|(key, descending)| (key.as_column(), *descending)),
+                )
+                .collect(),
+        };
+        if self.sorted.as_ref() == Some(&stamp) && self.sort_keys.len() == self.sorted_items.len() {
+            return;
+        }
+        if self.sorted.as_ref().map(|sorted| &sorted.keys) != Some(&stamp.keys) {
+            // The rows compare differently, so every row is moved.
+            self.sort_keys.clear();
+        }
+        self.sorted = Some(stamp);
+
+        // Rows are displayed in the order of the primary sort key's
+        // direction, so a key in the other direction is compared in reverse.
+        let sort_keys: Vec<(&T::Sort, bool)> = std::iter::once((&self.sort_by, false))
+            .chain(
+                self.then_by
+                    .iter()
+                    .map(|(key, descending)| (key, *descending != self.sort_descending)),
+            )
+            .collect();
+        sort::reposition(
+            &mut self.sorted_items,
+            &mut self.sort_keys,
+            |item| {
+                let row = item.upgrade();
+                let row = row.as_ref().map(|row| row.borrow());
+                sort_keys
+                    .iter()
+                    .map(|(sort_by, _)| row.as_ref().map(|row| key(sort_by, row)))
+                    .collect()
+            },
+            |a, b| {
+                a.iter()
+                    .zip(b.iter())
+                    .zip(&sort_keys)
+                    .map(|((a, b), (_, reversed))| if *reversed { b.cmp(a) } else { a.cmp(b) })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            },
+        );
+        self.restore_anchor();
+    }
+
+    /// Sorts the items again when they're next refreshed, since something has
+    /// changed how they compare without an update, such as a task being
+    /// bookmarked.
+    pub(in crate::view) fn resort(&mut self) {
+        if let Some(ref mut sorted) = self.sorted {
+            sorted.now = None;
+        }
+    }
+
+    /// Remembers the selected item, and which of the displayed rows it's on.
+    fn anchor_selection(&mut self) {
+        self.anchor = self.table_state.selected().and_then(|row| {
+            let item = self.item_at(row)?.clone();
+            Some((item, row.saturating_sub(self.table_state.offset())))
+        });
+    }
+
+    /// Selects the remembered item again, wherever sorting has moved it,
+    /// keeping it on the same displayed row where possible, so that the
+    /// table doesn't jump around under the user.
+    fn restore_anchor(&mut self) {
+        let Some((ref item, displayed_row)) = self.anchor else {
+            return;
+        };
+        let Some(idx) = self
+            .sorted_items
+            .iter()
+            .position(|other| other.ptr_eq(item))
+        else {
+            return;
+        };
+        let len = self.sorted_items.len();
+        let row = if self.sort_descending {
+            idx
+        } else {
+            len - idx - 1
+        };
+        self.table_state.select(Some(row));
+        // Don't scroll past the last row, unless the table hasn't been drawn
+        // yet and its height isn't known.
+        let last_offset = len.saturating_sub(usize::from(self.layout.rows.height.max(1)));
+        *self.table_state.offset_mut() = row.saturating_sub(displayed_row).min(last_offset);
+    }
+
+    /// Returns the header cell for the column at `idx`, marked with its sort
+    /// direction if the table is sorted by it.
+    pub(in crate::view) fn header_cell<'a>(
+        &self,
+        styles: &view::Styles,
+        idx: usize,
+        value: &'a str,
+    ) -> Cell<'a> {
+        let value = tr(value);
+        let direction = if idx == self.sort_by.as_column() {
+            Some(self.sort_descending)
+        } else {
+            self.then_by
+                .iter()
+                .find(|(key, _)| key.as_column() == idx)
+                .map(|&(_, descending)| descending)
+        };
+        match direction {
+            Some(true) => Cell::from(styles.ascending(value)),
+            Some(false) => Cell::from(styles.descending(value)),
+            // The selected column isn't sorted by, because it was selected
+            // to be added as a sort key.
+            None if idx == self.selected_column => Cell::from(Span::styled(
+                value,
+                Style::default().add_modifier(Modifier::UNDERLINED),
+            )),
+            None => Cell::from(value),
+        }
     }
 
     pub(in crate::view) fn scroll_with(
@@ -118,6 +861,7 @@ impl<T: TableList<N>, const N: usize> TableListState<T, N> {
         let i = self.table_state.selected().unwrap_or(0);
         let i = f(&self.sorted_items, i);
         self.table_state.select(Some(i));
+        self.anchor_selection();
     }
 
     pub(in crate::view) fn scroll_next(&mut self) {
@@ -146,6 +890,19 @@ impl<T: TableList<N>, const N: usize> TableListState<T, N> {
         })
     }
 
+    /// Moves the selection by half of the rows which fit in the table, like
+    /// vi's `ctrl-d` and `ctrl-u`, stopping at the first and last rows.
+    pub(in crate::view) fn scroll_half_page(&mut self, down: bool) {
+        let half = usize::from(self.layout.rows.height / 2).max(1);
+        self.scroll_with(|resources, i| {
+            if down {
+                (i + half).min(resources.len() - 1)
+            } else {
+                i.saturating_sub(half)
+            }
+        })
+    }
+
     pub(in crate::view) fn scroll_to_last(&mut self) {
         self.scroll_with(|resources, _| resources.len() - 1)
     }
@@ -154,22 +911,244 @@ impl<T: TableList<N>, const N: usize> TableListState<T, N> {
         self.scroll_with(|_, _| 0)
     }
 
-    pub(in crate::view) fn selected_item(&self) -> Option<Rc<RefCell<T::Row>>> {
-        self.table_state
+    /// Selects the row displaying `sorted_items[idx]`.
+    pub(in crate::view) fn select_sorted(&mut self, idx: usize) {
+        let row = if self.sort_descending {
+            idx
+        } else {
+            self.sorted_items.len().saturating_sub(idx + 1)
+        };
+        self.table_state.select(Some(row));
+        self.anchor_selection();
+    }
+
+    /// Returns the items on the rows which are displayed when `height` rows
+    /// fit in the table, and a few rows either side, in the order they're
+    /// displayed, along with the window to render their rows in.
+    ///
+    /// Only these rows need to be formatted, which matters for tables with
+    /// many thousands of rows, of which only a screenful are drawn.
+    pub(in crate::view) fn visible_items(
+        &self,
+        height: u16,
+    ) -> (Vec<Weak<RefCell<T::Row>>>, Window) {
+        // Scroll to the selected row in the same way as the table widget, so
+        // that the rows it draws are among the formatted ones.
+        let len = self.sorted_items.len();
+        let height = usize::from(height).max(1);
+        let selected = self
+            .table_state
             .selected()
-            .and_then(|i| {
-                if self.sort_descending {
-                    if i < self.sorted_items.len() {
-                        Some(self.sorted_items[i].clone())
-                    } else {
-                        None
-                    }
-                } else {
-                    let adjusted_index = self.sorted_items.len().checked_sub(i + 1)?;
-                    self.sorted_items.get(adjusted_index).cloned()
+            .unwrap_or(0)
+            .min(len.saturating_sub(1));
+        let mut offset = self.table_state.offset().min(len.saturating_sub(1));
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+        let start = offset.saturating_sub(WINDOW_MARGIN);
+        let end = (offset + height + WINDOW_MARGIN).min(len);
+        let items = (start..end)
+            .filter_map(|row| self.item_at(row).cloned())
+            .collect();
+        let state = TableState::default()
+            .with_offset(offset - start)
+            .with_selected(
+                self.table_state
+                    .selected()
+                    .map(|row| row.saturating_sub(start)),
+            );
+        (items, Window { start, state })
+    }
+
+    /// Renders `table`, holding the rows of the items returned by
+    /// [`Self::visible_items`], in `window`.
+    pub(in crate::view) fn render_window(
+        &mut self,
+        frame: &mut ratatui::terminal::Frame,
+        area: Rect,
+        table: ratatui::widgets::Table<'_>,
+        window: Window,
+    ) {
+        let Window { start, mut state } = window;
+        frame.render_stateful_widget(table, area, &mut state);
+        *self.table_state.offset_mut() = start + state.offset();
+    }
+
+    pub(in crate::view) fn selected_item(&self) -> Option<Rc<RefCell<T::Row>>> {
+        self.item_at(self.table_state.selected()?)?.upgrade()
+    }
+
+    /// Returns the item displayed on `row`.
+    fn item_at(&self, row: usize) -> Option<&Weak<RefCell<T::Row>>> {
+        if self.sort_descending {
+            self.sorted_items.get(row)
+        } else {
+            let adjusted_index = self.sorted_items.len().checked_sub(row + 1)?;
+            self.sorted_items.get(adjusted_index)
+        }
+    }
+
+    pub(in crate::view) fn settings(&self) -> TableSettings {
+        if let Some(ref restored) = self.restored {
+            return restored.clone();
+        }
+        TableSettings {
+            sort_column: self.column_name(self.sort_by.as_column()),
+            sort_descending: self.sort_descending,
+            then_by: self
+                .then_by
+                .iter()
+                .map(|(key, descending)| SortKeySettings {
+                    column: self.column_name(key.as_column()),
+                    descending: *descending,
+                })
+                .collect(),
+            search: self.search.as_ref().map(|search| search.query.clone()),
+            hidden_columns: (!self.columns.is_empty()).then(|| {
+                self.columns
+                    .iter()
+                    .filter(|choice| choice.hidden)
+                    .map(|choice| choice.name.clone())
+                    .collect()
+            }),
+        }
+    }
+
+    /// Restores saved settings.
+    ///
+    /// The search is restored immediately, but the columns are restored once
+    /// the table's columns are next chosen, since their positions aren't
+    /// known until then.
+    pub(in crate::view) fn restore_settings(&mut self, settings: TableSettings) {
+        self.search = settings.search.clone().and_then(Search::new);
+        self.columns.clear();
+        self.restored = Some(settings);
+    }
+
+    /// Applies restored settings, where `headers` are the names of all of the
+    /// table's columns. Columns which the table no longer has are skipped.
+    fn apply_settings(&mut self, settings: TableSettings, headers: &[&str]) {
+        let sort_key = |name: &str| {
+            let column = headers.iter().position(|header| *header == name)?;
+            Some((column, T::Sort::try_from(column).ok()?))
+        };
+        if let Some(hidden_columns) = settings.hidden_columns {
+            for choice in &mut self.columns {
+                choice.hidden = hidden_columns.contains(&choice.name);
+            }
+            // At least one column is always displayed.
+            if self.columns.iter().all(|choice| choice.hidden) {
+                if let Some(choice) = self.columns.first_mut() {
+                    choice.hidden = false;
                 }
+            }
+        }
+        if let Some((column, sort_by)) = sort_key(&settings.sort_column) {
+            self.sort_by = sort_by;
+            // The sort column is selected, unless it's hidden.
+            if self.column_order().any(|displayed| displayed == column) {
+                self.selected_column = column;
+            }
+        }
+        self.sort_descending = settings.sort_descending;
+        self.then_by = settings
+            .then_by
+            .iter()
+            .filter_map(|key| Some((sort_key(&key.column)?.1, key.descending)))
+            .collect();
+    }
+
+    /// Returns the name of the column at `column` in the header.
+    fn column_name(&self, column: usize) -> String {
+        self.columns
+            .iter()
+            .find(|choice| choice.column == column)
+            .map(|choice| choice.name.as_str())
+            .or_else(|| T::HEADER.get(column).copied())
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    /// Writes the displayed columns of the displayed rows, in the order
+    /// they're displayed, to a file named after `table`, returning its path.
+    pub(in crate::view) fn export(
+        &self,
+        table: &str,
+        format: export::Format,
+        state: &state::State,
+        cx: &T::Context,
+    ) -> color_eyre::Result<PathBuf> {
+        let (header, rows) = self
+            .displayed_values(state, cx)
+            .ok_or_else(|| color_eyre::eyre::eyre!("nothing has been received yet"))?;
+        export::save(table, format, &header, &rows)
+    }
+
+    /// Returns the displayed columns of the displayed rows as a Markdown
+    /// table, in the order they're displayed.
+    pub(in crate::view) fn markdown(
+        &self,
+        state: &state::State,
+        cx: &T::Context,
+    ) -> color_eyre::Result<String> {
+        let (header, rows) = self
+            .displayed_values(state, cx)
+            .ok_or_else(|| color_eyre::eyre::eyre!("nothing has been received yet"))?;
+        Ok(export::markdown(header.iter().map(String::as_str), &rows))
+    }
+
+    /// Adds the rows which are new in `state` to the table, re-sorts it and
+    /// chooses its columns, without rendering it.
+    pub(in crate::view) fn refresh(&mut self, state: &mut state::State, cx: &T::Context) {
+        if let Some(now) = state.last_updated_at() {
+            T::refresh(self, now, state, cx);
+            let headers = T::headers(state);
+            self.visible_columns(&headers.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+    }
+
+    /// Returns the names of the displayed columns, and their values in each
+    /// of the displayed rows, in the order they're displayed.
+    ///
+    /// Returns `None` if nothing has been received yet.
+    pub(in crate::view) fn displayed_values(
+        &self,
+        state: &state::State,
+        cx: &T::Context,
+    ) -> Option<(Vec<String>, Vec<Vec<Value>>)> {
+        let now = state.last_updated_at()?;
+        let order = self.column_order().collect::<Vec<_>>();
+        let header = arrange(&order, T::headers(state));
+        let mut rows = self
+            .sorted_items
+            .iter()
+            .filter_map(|item| {
+                let values = T::values(&item.upgrade()?.borrow(), now, state, cx)?;
+                Some(arrange(&order, values))
             })
-            .and_then(|weak| weak.upgrade())
+            .collect::<Vec<_>>();
+        if !self.sort_descending {
+            rows.reverse();
+        }
+        Some((header, rows))
+    }
+
+    /// Returns the controls available in this table.
+    pub(in crate::view) fn controls(&self) -> Vec<ControlDisplay> {
+        let mut controls = [&[view_controls()], T::EXTRA_CONTROLS].concat().concat();
+        if !T::GG_SCROLLS_TO_TOP {
+            for control in &mut controls {
+                if control.action == "scroll to top" {
+                    control.keys = &[KeyDisplay {
+                        base: "home",
+                        utf8: None,
+                    }];
+                }
+            }
+        }
+        controls
     }
 
     pub(in crate::view) fn render(
@@ -180,7 +1159,8 @@ impl<T: TableList<N>, const N: usize> TableListState<T, N> {
         state: &mut state::State,
         ctx: T::Context,
     ) {
-        T::render(self, styles, frame, area, state, ctx)
+        T::render(self, styles, frame, area, state, ctx);
+        self.anchor_selection();
     }
 }
 
@@ -195,9 +1175,21 @@ where
         Self {
             sorted_items: Default::default(),
             sort_by,
+            then_by: Vec::new(),
             table_state: Default::default(),
             selected_column,
             sort_descending: false,
+            extra_columns: 0,
+            search: None,
+            editing_search: false,
+            hidden: Vec::new(),
+            layout: TableLayout::default(),
+            column_config: Vec::new(),
+            columns: Vec::new(),
+            anchor: None,
+            restored: None,
+            sorted: None,
+            sort_keys: Vec::new(),
             last_key_event: None,
         }
     }
@@ -207,8 +1199,8 @@ impl<T, const N: usize> HelpText for TableListState<T, N>
 where
     T: TableList<N>,
 {
-    fn render_help_content(&self, styles: &view::Styles) -> Paragraph<'static> {
-        controls_paragraph(view_controls(), styles)
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Owned(self.controls())
     }
 }
 
@@ -227,6 +1219,33 @@ pub(crate) const fn view_controls() -> &'static [ControlDisplay] {
                 },
             ],
         },
+        ControlDisplay {
+            action: "select column (keep sort)",
+            keys: &[
+                KeyDisplay {
+                    base: "shift + left, right",
+                    utf8: Some("shift + \u{2190}\u{2192}"),
+                },
+                KeyDisplay {
+                    base: "H, L",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "then sort by column",
+            keys: &[KeyDisplay {
+                base: "+",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "stop sorting by column",
+            keys: &[KeyDisplay {
+                base: "-",
+                utf8: None,
+            }],
+        },
         ControlDisplay {
             action: "scroll",
             keys: &[
@@ -240,6 +1259,19 @@ pub(crate) const fn view_controls() -> &'static [ControlDisplay] {
                 },
             ],
         },
+        ControlDisplay {
+            action: "scroll half a page",
+            keys: &[
+                KeyDisplay {
+                    base: "page up, page down",
+                    utf8: None,
+                },
+                KeyDisplay {
+                    base: "ctrl-u, ctrl-d",
+                    utf8: None,
+                },
+            ],
+        },
         ControlDisplay {
             action: "view details",
             keys: &[KeyDisplay {
@@ -255,18 +1287,531 @@ pub(crate) const fn view_controls() -> &'static [ControlDisplay] {
             }],
         },
         ControlDisplay {
-            action: "scroll to top",
+            action: "search (esc to clear)",
             keys: &[KeyDisplay {
-                base: "gg",
+                base: "/",
                 utf8: None,
             }],
         },
         ControlDisplay {
-            action: "scroll to bottom",
+            action: "export as CSV/JSON",
             keys: &[KeyDisplay {
-                base: "G",
+                base: "e, E",
                 utf8: None,
             }],
         },
+        ControlDisplay {
+            action: "copy as Markdown",
+            keys: &[KeyDisplay {
+                base: "Y",
+                utf8: None,
+            }],
+        },
+        ControlDisplay {
+            action: "scroll to top",
+            keys: &[
+                KeyDisplay {
+                    base: "home",
+                    utf8: None,
+                },
+                KeyDisplay {
+                    base: "gg",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "scroll to bottom",
+            keys: &[
+                KeyDisplay {
+                    base: "end",
+                    utf8: None,
+                },
+                KeyDisplay {
+                    base: "G",
+                    utf8: None,
+                },
+            ],
+        },
     ]
 }
+
+/// Returns the text of formatted fields or attributes, as they're displayed.
+pub(in crate::view) fn spans_text(fields: &[Vec<Span<'_>>]) -> String {
+    fields
+        .iter()
+        .flatten()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+/// Returns `spans` without their first `skip` characters, for scrolling a
+/// column horizontally.
+pub(in crate::view) fn skip_chars<'a>(
+    spans: impl IntoIterator<Item = Span<'a>>,
+    mut skip: usize,
+) -> Vec<Span<'a>> {
+    spans
+        .into_iter()
+        .filter_map(|span| {
+            if skip == 0 {
+                return Some(span);
+            }
+            let len = span.content.chars().count();
+            if len <= skip {
+                skip -= len;
+                return None;
+            }
+            let content = span.content.chars().skip(skip).collect::<String>();
+            skip = 0;
+            Some(Span::styled(content, span.style))
+        })
+        .collect()
+}
+
+/// Returns the items at each of `columns`, in that order, where `items` are
+/// the values of every column in a row.
+pub(in crate::view) fn arrange<I>(columns: &[usize], items: Vec<I>) -> Vec<I> {
+    let mut items = items.into_iter().map(Some).collect::<Vec<_>>();
+    columns
+        .iter()
+        .filter_map(|&column| items.get_mut(column)?.take())
+        .collect()
+}
+
+// === impl ColumnConfig ===
+
+impl FromStr for ColumnConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, width) = match s.rsplit_once(':') {
+            Some((name, width)) => {
+                let width = width
+                    .trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|&width| width > 0)
+                    .ok_or_else(|| {
+                        format!("invalid width `{width}` for column `{name}`, expected a number")
+                    })?;
+                (name, Some(width))
+            }
+            None => (s, None),
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("invalid column `{s}`, expected a column name"));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            width,
+        })
+    }
+}
+
+impl fmt::Display for ColumnConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.width {
+            Some(width) => write!(f, "{}:{width}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+impl TryFrom<String> for ColumnConfig {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ColumnConfig> for String {
+    fn from(config: ColumnConfig) -> Self {
+        config.to_string()
+    }
+}
+
+impl FromStr for SortConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, ascending) = match s.rsplit_once(':') {
+            Some((name, direction)) => match direction.trim() {
+                "asc" => (name, true),
+                "desc" => (name, false),
+                direction => {
+                    return Err(format!(
+                    "invalid direction `{direction}` for column `{name}`, expected `asc` or `desc`"
+                ))
+                }
+            },
+            None => (s, false),
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("invalid sort `{s}`, expected a column name"));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            ascending,
+        })
+    }
+}
+
+impl fmt::Display for SortConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.ascending { "asc" } else { "desc" };
+        write!(f, "{}:{direction}", self.name)
+    }
+}
+
+impl TryFrom<String> for SortConfig {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SortConfig> for String {
+    fn from(config: SortConfig) -> Self {
+        config.to_string()
+    }
+}
+
+// === impl Search ===
+
+impl Search {
+    /// Returns a search for `query`, or `None` if it's empty.
+    fn new(query: String) -> Option<Self> {
+        if query.is_empty() {
+            return None;
+        }
+        let case = if query.chars().any(char::is_uppercase) {
+            ""
+        } else {
+            "(?i)"
+        };
+        let regex = Regex::new(&format!("{case}{query}"))
+            .or_else(|_| Regex::new(&format!("{case}{}", regex::escape(&query))))
+            .expect("an escaped query is a valid regex");
+        Some(Self { query, regex })
+    }
+
+    /// Returns `true` if any of `texts` matches the query.
+    pub(crate) fn matches<'a>(&self, texts: impl IntoIterator<Item = &'a str>) -> bool {
+        texts.into_iter().any(|text| self.regex.is_match(text))
+    }
+
+    /// Returns `true` if any of `fields`, which are formatted as spans,
+    /// matches the query.
+    pub(crate) fn matches_spans(&self, fields: &[Vec<Span<'_>>]) -> bool {
+        fields.iter().any(|field| {
+            let text = field
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>();
+            self.regex.is_match(&text)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_is_smart_case() {
+        let search = Search::new("worker".to_owned()).unwrap();
+        assert!(search.matches(["Worker-1"]));
+        let search = Search::new("Worker".to_owned()).unwrap();
+        assert!(!search.matches(["worker-1"]));
+        assert!(Search::new(String::new()).is_none());
+    }
+
+    #[test]
+    fn search_falls_back_to_substring() {
+        let search = Search::new("conn-[0-9]+$".to_owned()).unwrap();
+        assert!(search.matches(["conn-12"]));
+        assert!(!search.matches(["conn-12a"]));
+        // An unclosed group isn't a valid regex, so it's matched literally.
+        let search = Search::new("poll(".to_owned()).unwrap();
+        assert!(search.matches(["fn=poll(cx)"]));
+        assert!(!search.matches(["poll"]));
+    }
+
+    #[test]
+    fn clicks_sort_and_select() {
+        use crate::{state::resources::SortBy, view::resources::ResourcesTable};
+        use input::{MouseButton, MouseEvent, MouseEventKind};
+
+        let event = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: input::KeyModifiers::NONE,
+        };
+        let click = |column, row| event(MouseEventKind::Down(MouseButton::Left), column, row);
+        let mut table = TableListState::<ResourcesTable, 10> {
+            sorted_items: (0..3).map(|_| Weak::new()).collect(),
+            ..Default::default()
+        };
+        table.record_layout(
+            Rect::new(0, 0, 60, 10),
+            &[layout::Constraint::Length(4); 10],
+        );
+
+        // Clicking a header sorts by its column, and clicking it again
+        // reverses the sort.
+        assert!(!table.mouse_input(click(6, 0)));
+        assert_eq!(table.sort_by.as_column(), SortBy::ParentId as usize);
+        assert!(!table.sort_descending);
+        table.mouse_input(click(7, 0));
+        assert!(table.sort_descending);
+
+        // Clicking a row selects it, and clicking it again opens it.
+        assert!(!table.mouse_input(click(1, 2)));
+        assert_eq!(table.table_state.selected(), Some(1));
+        assert!(table.mouse_input(click(1, 2)));
+        // There's no row to select below the last one.
+        table.mouse_input(click(1, 8));
+        assert_eq!(table.table_state.selected(), Some(1));
+
+        // Scrolling stops at the last row, rather than wrapping around.
+        for _ in 0..3 {
+            table.mouse_input(event(MouseEventKind::ScrollDown, 1, 2));
+        }
+        assert_eq!(table.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn only_sorts_once_rows_could_be_reordered() {
+        use crate::{
+            config::ViewOptions,
+            state::{
+                fixtures::{self, stats, task, timestamp},
+                tasks::{SortBy, StatsWindow},
+                State,
+            },
+            view::{tasks::TasksTable, Styles, ViewState},
+        };
+        use clap::Parser;
+        use console_api as proto;
+        use std::cell::Cell;
+
+        let styles = Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let mut state = State::default();
+        let update = fixtures::update(1, (1..=4).map(|id| (task(id), stats(id * 10))));
+        state.update(&styles, &ViewState::TasksList, update);
+        let mut table = TableListState::<TasksTable, 13> {
+            sort_by: SortBy::Polls,
+            ..Default::default()
+        };
+        table
+            .sorted_items
+            .extend(state.tasks_state_mut().take_new_tasks());
+
+        let keyed = Cell::new(0);
+        let sort = |table: &mut TableListState<TasksTable, 13>, state: &State| {
+            let now = state.last_updated_at().unwrap();
+            keyed.set(0);
+            table.sort_items(now, |sort_by, task| {
+                keyed.set(keyed.get() + 1);
+                sort_by.key(now, StatsWindow::default(), &[], task)
+            });
+            let ids = table
+                .sorted_items
+                .iter()
+                .map(|task| task.upgrade().unwrap().borrow().span_id())
+                .collect::<Vec<_>>();
+            (ids, keyed.get())
+        };
+        assert_eq!(sort(&mut table, &state), (vec![1, 2, 3, 4], 4));
+        // Drawing again before the next update doesn't sort again.
+        assert_eq!(sort(&mut table, &state), (vec![1, 2, 3, 4], 0));
+
+        let update = proto::instrument::Update {
+            now: Some(timestamp(2)),
+            task_update: Some(proto::tasks::TaskUpdate {
+                stats_update: [(1, stats(35))].into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        state.update(&styles, &ViewState::TasksList, update);
+        assert_eq!(sort(&mut table, &state), (vec![2, 3, 1, 4], 4));
+        assert_eq!(sort(&mut table, &state), (vec![2, 3, 1, 4], 0));
+
+        // Something which changes how rows compare without an update, or a
+        // change to the sorting, sorts them again.
+        table.resort();
+        assert_eq!(sort(&mut table, &state), (vec![2, 3, 1, 4], 4));
+        // The rows are kept in ascending order, and displayed in reverse.
+        table.sort_descending = true;
+        assert_eq!(sort(&mut table, &state), (vec![2, 3, 1, 4], 4));
+
+        // Hiding rows doesn't sort them, but showing them again does.
+        table.hide_unless(|task| task.span_id() != 3);
+        assert_eq!(sort(&mut table, &state), (vec![2, 1, 4], 0));
+        table.apply_search();
+        assert_eq!(sort(&mut table, &state), (vec![2, 3, 1, 4], 4));
+    }
+
+    #[test]
+    fn configured_columns() {
+        use crate::view::resources::ResourcesTable;
+
+        let headers = ResourcesTable::HEADER.to_vec();
+        let mut table = TableListState::<ResourcesTable, 10>::default();
+        table.configure_columns(
+            ["id", "kind:12", "TARGET", "missing", "id"]
+                .iter()
+                .map(|config| config.parse().unwrap())
+                .collect(),
+        );
+
+        // Configured columns are displayed in order, and the rest are hidden.
+        assert_eq!(table.visible_columns(&headers), [0, 2, 4]);
+        assert_eq!(table.column_width(2), Some(12));
+        assert_eq!(table.column_width(0), None);
+
+        // Selecting columns skips the hidden ones.
+        table.selected_column = 0;
+        table.select_column_with(|len, i| (i + 1) % len);
+        assert_eq!(table.selected_column, 2);
+        table.select_column_with(|len, i| (i + len - 1) % len);
+        table.select_column_with(|len, i| (i + len - 1) % len);
+        assert_eq!(table.selected_column, 4);
+
+        table.move_column(0, 2);
+        table.toggle_column(1);
+        table.toggle_column(3);
+        assert_eq!(table.visible_columns(&headers), [4, 0, 1]);
+
+        // The last column can't be hidden.
+        table.toggle_column(0);
+        table.toggle_column(2);
+        table.toggle_column(3);
+        assert_eq!(table.visible_columns(&headers), [1]);
+
+        let arranged = arrange(&[2, 0], vec!["a", "b", "c"]);
+        assert_eq!(arranged, ["c", "a"]);
+    }
+
+    #[test]
+    fn settings_are_restored_by_column_name() {
+        use crate::view::tasks::TasksTable;
+
+        let fixed = &TasksTable::HEADER[..12];
+        let headers: Vec<&str> = fixed.iter().copied().chain(["Fields"]).collect();
+        let mut table = TableListState::<TasksTable, 13>::default();
+        table.visible_columns(&headers);
+        table.selected_column = 12;
+        table.sort_by_selected();
+        table.selected_column = 6;
+        table.then_by_selected();
+        table.toggle_column(10);
+        table.search = Search::new("worker".to_owned());
+        let settings = table.settings();
+        let saved = toml::to_string(&settings).unwrap();
+        let settings: TableSettings = toml::from_str(&saved).unwrap();
+        assert_eq!(settings, table.settings());
+
+        // A new column before `Fields` changes its position.
+        let headers: Vec<&str> = fixed
+            .iter()
+            .copied()
+            .chain(["http.method", "Fields"])
+            .collect();
+        let mut restored = TableListState::<TasksTable, 13>::default();
+        restored.restore_settings(settings.clone());
+        assert!(restored.is_searched());
+        // Settings which haven't been applied yet are saved as they were.
+        assert_eq!(restored.settings(), settings);
+        restored.visible_columns(&headers);
+        assert_eq!(restored.sort_by.as_column(), 13);
+        assert_eq!(restored.selected_column, 13);
+        assert_eq!(
+            restored
+                .then_by
+                .iter()
+                .map(|(key, _)| key.as_column())
+                .collect::<Vec<_>>(),
+            [6]
+        );
+        assert!(!restored.visible_columns(&headers).contains(&10));
+        assert_eq!(restored.settings(), settings);
+
+        // A hidden sort column isn't selected.
+        let mut hidden = settings;
+        hidden.hidden_columns = Some(vec!["Fields".to_owned()]);
+        let mut restored = TableListState::<TasksTable, 13>::default();
+        let selected_column = restored.selected_column;
+        restored.restore_settings(hidden);
+        restored.visible_columns(&headers);
+        assert_eq!(restored.sort_by.as_column(), 13);
+        assert_eq!(restored.selected_column, selected_column);
+    }
+
+    #[test]
+    fn parses_column_config() {
+        let config = "name:24".parse::<ColumnConfig>().unwrap();
+        assert_eq!(config.width, Some(24));
+        assert_eq!(config.to_string(), "name:24");
+        assert_eq!("busy".parse::<ColumnConfig>().unwrap().width, None);
+        assert!("name:0".parse::<ColumnConfig>().is_err());
+        assert!("name:wide".parse::<ColumnConfig>().is_err());
+        assert!(":4".parse::<ColumnConfig>().is_err());
+    }
+
+    #[test]
+    fn only_formats_the_rows_around_the_displayed_ones() {
+        use crate::view::resources::ResourcesTable;
+
+        let mut table = TableListState::<ResourcesTable, 10> {
+            sorted_items: (0..10_000).map(|_| Weak::new()).collect(),
+            ..Default::default()
+        };
+        table.table_state.select(Some(5_000));
+        let (items, window) = table.visible_items(20);
+        // The selected row is scrolled to the bottom of the table, as the
+        // table widget would.
+        assert_eq!(window.start, 5_000 - 19 - WINDOW_MARGIN);
+        assert_eq!(items.len(), 20 + 2 * WINDOW_MARGIN);
+        assert_eq!(window.state.offset(), WINDOW_MARGIN);
+        assert_eq!(window.state.selected(), Some(19 + WINDOW_MARGIN));
+
+        // Moving the selection within the displayed rows doesn't scroll.
+        *table.table_state.offset_mut() = window.start + window.state.offset();
+        table.table_state.select(Some(4_990));
+        let (_, window) = table.visible_items(20);
+        assert_eq!(window.start + window.state.offset(), 5_000 - 19);
+
+        // Near the top, there are no rows above the displayed ones to format.
+        table.table_state.select(Some(0));
+        let (items, window) = table.visible_items(20);
+        assert_eq!(window.start, 0);
+        assert_eq!(items.len(), 20 + WINDOW_MARGIN);
+    }
+
+    #[test]
+    fn skip_chars_across_spans() {
+        let style = Style::default().add_modifier(Modifier::BOLD);
+        let spans = || {
+            vec![
+                Span::styled("task", style),
+                Span::raw("=1 "),
+                Span::raw("x"),
+            ]
+        };
+        let text = |spans: Vec<Span<'_>>| spans_text(&[spans]);
+        assert_eq!(text(skip_chars(spans(), 0)), "task=1 x");
+        assert_eq!(text(skip_chars(spans(), 4)), "=1 x");
+        let skipped = skip_chars(spans(), 2);
+        assert_eq!(text(skipped.clone()), "sk=1 x");
+        assert_eq!(skipped[0].style, style);
+        assert!(skip_chars(spans(), 20).is_empty());
+    }
+}
@@ -1,38 +1,225 @@
 use crate::{
     input,
-    state::{tasks::Task, DetailsRef},
+    slo::{SloHistogram, SloStatus},
+    state::{
+        histogram,
+        tasks::{Dump, Task, TaskRef},
+        DetailsRef, State,
+    },
     util::Percentage,
     view::{
-        self, bold,
-        controls::{controls_paragraph, ControlDisplay, Controls, KeyDisplay},
+        self,
+        activity::ActivityChart,
+        bold,
+        bookmarks::BOOKMARK_CONTROLS,
+        controls::{ControlDisplay, Controls, KeyDisplay},
         durations::Durations,
         help::HelpText,
+        states::StateBreakdown,
+        tasks::{StatsDisplay, STATS_CONTROLS},
+        timeline::PollTimeline,
     },
 };
 use ratatui::{
     layout::{self, Layout},
     text::{Line, Span, Text},
-    widgets::{List, ListItem, Paragraph},
+    widgets::{List, ListItem, Paragraph, Wrap},
 };
 use std::{
+    borrow::Cow,
     cell::RefCell,
     cmp,
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
     rc::Rc,
     time::{Duration, SystemTime},
 };
 
+/// The control for copying the selected task's details to the clipboard.
+pub(crate) const COPY_TASK_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "copy task details",
+    keys: &[KeyDisplay {
+        base: "y",
+        utf8: None,
+    }],
+}];
+
+/// The control for opening where the selected task was spawned in an editor.
+pub(crate) const OPEN_TASK_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "open in editor",
+    keys: &[KeyDisplay {
+        base: "o",
+        utf8: None,
+    }],
+}];
+
+/// A request about the task whose details are displayed, which the target
+/// is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TaskRequest {
+    /// Dump everything the target knows about the task.
+    Dump,
+    /// Abort the task.
+    Abort,
+}
+
 pub(crate) struct TaskView {
     task: Rc<RefCell<Task>>,
     details: DetailsRef,
+    /// The outcome of the last export of the task's histograms, if they have
+    /// been exported.
+    export: Option<Result<PathBuf, String>>,
+    /// Where the task which aborted this one was last drawn, which opens it
+    /// when clicked.
+    aborted_by: Option<(layout::Rect, TaskRef)>,
+    /// A request to send to the target, which hasn't been sent yet.
+    request: Option<TaskRequest>,
+    /// Whether aborting the task has been asked for once, so that asking
+    /// again aborts it.
+    abort_armed: bool,
+    /// The outcome of the last request to abort the task, if one was sent.
+    aborted: Option<Result<(), String>>,
+    /// The last dump of the task, or why it couldn't be dumped, if it has
+    /// been dumped.
+    dump: Option<Result<Dump, String>>,
 }
 
 impl TaskView {
     pub(super) fn new(task: Rc<RefCell<Task>>, details: DetailsRef) -> Self {
-        TaskView { task, details }
+        TaskView {
+            task,
+            details,
+            export: None,
+            aborted_by: None,
+            request: None,
+            abort_armed: false,
+            aborted: None,
+            dump: None,
+        }
+    }
+
+    pub(crate) fn task(&self) -> &Rc<RefCell<Task>> {
+        &self.task
+    }
+
+    /// Handles `event`, returning a task to open if one was clicked.
+    pub(crate) fn update_input(
+        &mut self,
+        event: input::Event,
+        state: &State,
+    ) -> Option<Rc<RefCell<Task>>> {
+        match event {
+            input::Event::Key(input::KeyEvent {
+                code: input::KeyCode::Char('x'),
+                ..
+            }) => {
+                // Aborting a task can't be undone, so it has to be asked for
+                // twice in a row.
+                if self.abort_armed {
+                    self.request = Some(TaskRequest::Abort);
+                }
+                self.abort_armed = !self.abort_armed;
+            }
+            input::Event::Key(input::KeyEvent {
+                code: input::KeyCode::Char('I'),
+                ..
+            }) => self.request = Some(TaskRequest::Dump),
+            input::Event::Key(input::KeyEvent {
+                code: input::KeyCode::Char('e'),
+                ..
+            }) => {
+                if let Some(now) = state.last_updated_at() {
+                    self.export = Some(
+                        self.export_histograms(now)
+                            .map_err(|error| error.to_string()),
+                    );
+                }
+            }
+            input::Event::Mouse(input::MouseEvent {
+                kind: input::MouseEventKind::Down(input::MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => {
+                let (area, task) = self.aborted_by.as_ref()?;
+                if area.contains(layout::Position::new(column, row)) {
+                    return task.upgrade();
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Stops waiting for aborting the task to be asked for again, unless
+    /// `event` asks for it.
+    pub(crate) fn cancel_abort(&mut self, event: &input::Event) {
+        let abort = matches!(
+            event,
+            input::Event::Key(input::KeyEvent {
+                code: input::KeyCode::Char('x'),
+                ..
+            })
+        );
+        if !abort && matches!(event, input::Event::Key(_)) {
+            self.abort_armed = false;
+        }
+    }
+
+    /// Returns the request to send to the target about the task, if one has
+    /// been made since this was last called.
+    pub(crate) fn take_request(&mut self) -> Option<TaskRequest> {
+        self.request.take()
+    }
+
+    /// Records the outcome of dumping the task.
+    pub(crate) fn dumped(&mut self, dump: Result<Dump, String>) {
+        self.dump = Some(dump);
     }
 
-    pub(crate) fn update_input(&mut self, _event: input::Event) {
-        // TODO :D
+    /// Records the outcome of asking the target to abort the task.
+    pub(crate) fn aborted(&mut self, aborted: Result<(), String>) {
+        self.aborted = Some(aborted);
+    }
+
+    /// Writes the task's poll and scheduled times histograms to an
+    /// HdrHistogram interval log in the current directory, returning the
+    /// log's path.
+    fn export_histograms(&self, now: SystemTime) -> color_eyre::Result<PathBuf> {
+        let task = self.task.borrow();
+        let details = self.details.borrow();
+        let details = details
+            .as_ref()
+            .filter(|details| details.span_id() == task.span_id())
+            .ok_or_else(|| {
+                color_eyre::eyre::eyre!("the task's histograms haven't been received")
+            })?;
+        let histograms = [
+            ("poll", details.poll_times_histogram()),
+            ("scheduled", details.scheduled_times_histogram()),
+        ];
+        let histograms = histograms
+            .iter()
+            .filter_map(|&(name, histogram)| Some((name, histogram?)))
+            .collect::<Vec<_>>();
+
+        let total = task.total(now);
+        let start = now.checked_sub(total).unwrap_or(now);
+        let comment = match task.name() {
+            Some(name) => format!("tokio-console task {} ({name})", task.id_str()),
+            None => format!("tokio-console task {}", task.id_str()),
+        };
+        let mut log = Vec::new();
+        histogram::write_interval_log(&mut log, &comment, start, total, &histograms)?;
+
+        let secs = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = PathBuf::from(format!("task-{}-{secs}.hlog", task.id_str()));
+        fs::write(&path, log)?;
+        Ok(path)
     }
 
     pub(crate) fn render(
@@ -40,8 +227,12 @@ impl TaskView {
         styles: &view::Styles,
         frame: &mut ratatui::terminal::Frame,
         area: layout::Rect,
-        now: SystemTime,
+        state: &mut State,
+        display: StatsDisplay,
     ) {
+        let now = state
+            .last_updated_at()
+            .expect("task view implies we've received an update");
         // Rows with the following info:
         // - Task main attributes
         // - task metadata
@@ -54,8 +245,47 @@ impl TaskView {
             .as_ref()
             .filter(|details| details.span_id() == task.span_id());
 
-        let controls = Controls::new(view_controls(), &area, styles);
+        let controls = Controls::new(&view_controls(), &area, styles);
 
+        let poll_times = details.and_then(|d| d.poll_times_histogram());
+        let recent_polls = details
+            .map(|d| d.recent_polls())
+            .filter(|polls| !polls.is_empty());
+        let scheduled_times = details.and_then(|d| d.scheduled_times_histogram());
+        let activity = details
+            .map(|d| d.activity().intervals())
+            .filter(|intervals| !intervals.is_empty());
+        let slos = |kind: SloHistogram| -> Vec<SloStatus<'_>> {
+            let histogram = match kind {
+                SloHistogram::Poll => poll_times,
+                SloHistogram::Scheduled => scheduled_times,
+            };
+            state
+                .slos()
+                .iter()
+                .filter(|slo| slo.histogram == kind)
+                .filter_map(|slo| slo.check(histogram?))
+                .collect()
+        };
+        let poll_slos = slos(SloHistogram::Poll);
+        let scheduled_slos = slos(SloHistogram::Scheduled);
+
+        let slo_warnings = poll_slos
+            .iter()
+            .chain(&scheduled_slos)
+            .filter(|status| !status.is_met())
+            .map(|status| {
+                ListItem::new(Text::from(Line::from(vec![
+                    styles.warning_wide(),
+                    Span::from(format!(
+                        "{} times p{} is {:.2?}, which exceeds the objective of {:.2?}",
+                        status.slo.histogram,
+                        status.slo.percentile(),
+                        status.value,
+                        status.slo.threshold(),
+                    )),
+                ])))
+            });
         let warnings: Vec<_> = task
             .warnings()
             .iter()
@@ -63,69 +293,195 @@ impl TaskView {
                 ListItem::new(Text::from(Line::from(vec![
                     styles.warning_wide(),
                     // TODO(eliza): it would be nice to handle singular vs plural...
-                    Span::from(linter.format(task)),
+                    Span::from(linter.format(task, now)),
                 ])))
             })
+            .chain(slo_warnings)
+            .collect();
+
+        let fs_ops: Vec<_> = state
+            .async_ops_state()
+            .fs_ops_issued_by(task.id())
+            .iter()
+            .filter_map(|op| op.upgrade())
+            .map(|op| {
+                let op = op.borrow();
+                let status = if op.dropped() { "done" } else { "pending" };
+                ListItem::new(Line::from(vec![
+                    bold(op.source().to_owned()),
+                    Span::raw(format!(" {} ", op.path().unwrap_or_default())),
+                    styles.time_units(op.total(now), view::DUR_LIST_PRECISION, None),
+                    Span::raw(format!(" ({status})")),
+                ]))
+            })
             .collect();
 
-        let (
-            controls_area,
-            stats_area,
-            poll_dur_area,
-            scheduled_dur_area,
-            fields_area,
-            warnings_area,
-        ) = if warnings.is_empty() {
-            let chunks = Layout::default()
-                .direction(layout::Direction::Vertical)
-                .constraints(
-                    [
-                        // controls
-                        layout::Constraint::Length(controls.height()),
-                        // task stats
-                        layout::Constraint::Length(10),
-                        // poll duration
-                        layout::Constraint::Length(9),
-                        // scheduled duration
-                        layout::Constraint::Length(9),
-                        // fields
-                        layout::Constraint::Percentage(60),
-                    ]
-                    .as_ref(),
-                )
-                .split(area);
-            (chunks[0], chunks[1], chunks[2], chunks[3], chunks[4], None)
+        let export = self.export.as_ref().map(|export| match export {
+            Ok(path) => Line::from(vec![
+                bold("Exported histograms to "),
+                Span::raw(path.display().to_string()),
+            ]),
+            Err(error) => Line::from(vec![
+                Span::styled(
+                    "Failed to export histograms: ",
+                    styles.fg(ratatui::style::Color::Red),
+                ),
+                Span::raw(error.clone()),
+            ]),
+        });
+
+        let abort_status = if self.abort_armed {
+            Some(Line::from(vec![bold(
+                "Press x again to abort the task, or any other key to cancel",
+            )]))
         } else {
-            let chunks = Layout::default()
-                .direction(layout::Direction::Vertical)
-                .constraints(
-                    [
-                        // controls
-                        layout::Constraint::Length(controls.height()),
-                        // warnings (add 2 for top and bottom borders)
-                        layout::Constraint::Length(warnings.len() as u16 + 2),
-                        // task stats
-                        layout::Constraint::Length(10),
-                        // poll duration
-                        layout::Constraint::Length(9),
-                        // scheduled duration
-                        layout::Constraint::Length(9),
-                        // fields
-                        layout::Constraint::Percentage(60),
-                    ]
-                    .as_ref(),
-                )
-                .split(area);
+            self.aborted.as_ref().map(|aborted| match aborted {
+                Ok(()) => Line::from(vec![bold("Asked the target to abort the task")]),
+                Err(error) => Line::from(vec![
+                    Span::styled(
+                        "Failed to abort the task: ",
+                        styles.fg(ratatui::style::Color::Red),
+                    ),
+                    Span::raw(error.clone()),
+                ]),
+            })
+        };
+
+        let dump = self.dump.as_ref().map(|dump| dump_text(dump, styles));
 
+        let panic = task.panic().map(|panic| {
+            Text::from(vec![
+                Line::from(vec![
+                    bold("Panicked at: "),
+                    Span::raw(panic.location.clone()),
+                ]),
+                Line::from(vec![
+                    bold("Message: "),
+                    Span::styled(
+                        panic
+                            .message
+                            .clone()
+                            .unwrap_or_else(|| "<not a string>".to_owned()),
+                        styles.fg(ratatui::style::Color::Red),
+                    ),
+                ]),
+            ])
+        });
+
+        let aborted_by_task = task
+            .abort()
+            .and_then(|abort| state.tasks_state().task_by_span(abort.aborted_by?));
+        let abort = task.abort().map(|abort| {
+            let aborted_by = match abort.aborted_by {
+                Some(_) => match aborted_by_task.as_ref().and_then(|task| task.upgrade()) {
+                    Some(task) => format!("task {} (click to open)", task.borrow().short_desc()),
+                    None => "a task which has since been removed".to_owned(),
+                },
+                None => "code outside of any task".to_owned(),
+            };
+            Text::from(vec![
+                Line::from(vec![bold("Aborted by: "), Span::raw(aborted_by)]),
+                Line::from(vec![
+                    bold("Aborted at: "),
+                    Span::raw(abort.location.clone()),
+                ]),
+            ])
+        });
+
+        let mut constraints = vec![
+            // controls
+            layout::Constraint::Length(controls.height()),
+        ];
+        if export.is_some() {
+            constraints.push(layout::Constraint::Length(1));
+        }
+        if abort_status.is_some() {
+            constraints.push(layout::Constraint::Length(1));
+        }
+        if panic.is_some() {
+            // panic location and message (add 2 for top and bottom borders)
+            constraints.push(layout::Constraint::Length(4));
+        }
+        if abort.is_some() {
+            // who aborted the task and where (add 2 for top and bottom borders)
+            constraints.push(layout::Constraint::Length(4));
+        }
+        if !warnings.is_empty() {
+            // warnings (add 2 for top and bottom borders)
+            constraints.push(layout::Constraint::Length(warnings.len() as u16 + 2));
+        }
+        if !fs_ops.is_empty() {
+            // filesystem ops (add 2 for top and bottom borders), capped so that
+            // a task issuing many ops doesn't crowd out its own stats.
+            constraints.push(layout::Constraint::Length(
+                cmp::min(fs_ops.len(), 8) as u16 + 2,
+            ));
+        }
+        if let Some(ref dump) = dump {
+            // the task's dump (add 2 for top and bottom borders), capped so
+            // that a long backtrace doesn't crowd out the task's stats.
+            constraints.push(layout::Constraint::Length(
+                cmp::min(dump.lines.len(), 16) as u16 + 2,
+            ));
+        }
+        constraints.extend([
+            // task stats
+            layout::Constraint::Length(10),
+            // time in each state
+            layout::Constraint::Length(StateBreakdown::HEIGHT),
+        ]);
+        if recent_polls.is_some() {
+            constraints.push(layout::Constraint::Length(PollTimeline::HEIGHT));
+        }
+        if activity.is_some() {
+            constraints.push(layout::Constraint::Length(ActivityChart::HEIGHT));
+        }
+        // Targets which can't send tasks' details have no histograms to show.
+        let show_histograms = !state.task_details_unavailable();
+        if show_histograms {
+            constraints.extend([
+                // poll duration, with a line for each objective
+                layout::Constraint::Length(10 + poll_slos.len() as u16),
+                // scheduled duration, with a line for each objective
+                layout::Constraint::Length(10 + scheduled_slos.len() as u16),
+            ]);
+        }
+        constraints.extend([
+            // fields
+            layout::Constraint::Percentage(60),
+        ]);
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(constraints)
+            .split(area);
+        let mut chunks = chunks.iter().copied();
+        let controls_area = chunks.next().expect("controls area");
+        let export_area = export
+            .is_some()
+            .then(|| chunks.next().expect("export area"));
+        let abort_status_area = abort_status
+            .is_some()
+            .then(|| chunks.next().expect("abort status area"));
+        let panic_area = panic.is_some().then(|| chunks.next().expect("panic area"));
+        let abort_area = abort.is_some().then(|| chunks.next().expect("abort area"));
+        let warnings_area = (!warnings.is_empty()).then(|| chunks.next().expect("warnings area"));
+        let fs_ops_area = (!fs_ops.is_empty()).then(|| chunks.next().expect("fs ops area"));
+        let dump_area = dump.is_some().then(|| chunks.next().expect("dump area"));
+        let stats_area = chunks.next().expect("stats area");
+        let states_area = chunks.next().expect("states area");
+        let timeline_area = recent_polls
+            .is_some()
+            .then(|| chunks.next().expect("timeline area"));
+        let activity_area = activity
+            .is_some()
+            .then(|| chunks.next().expect("activity area"));
+        let histogram_areas = show_histograms.then(|| {
             (
-                chunks[0],
-                chunks[2],
-                chunks[3],
-                chunks[4],
-                chunks[5],
-                Some(chunks[1]),
+                chunks.next().expect("poll duration area"),
+                chunks.next().expect("scheduled duration area"),
             )
-        };
+        });
+        let fields_area = chunks.next().expect("fields area");
 
         let stats_area = Layout::default()
             .direction(layout::Direction::Horizontal)
@@ -144,6 +500,7 @@ impl TaskView {
             bold("ID: "),
             Span::raw(format!("{} ", task.id_str())),
             task.state().render(styles),
+            Span::raw(format!(" {}", task.state().description())),
         ]));
 
         if let Some(name) = task.name() {
@@ -152,6 +509,10 @@ impl TaskView {
 
         overview.push(Line::from(vec![bold("Target: "), Span::raw(task.target())]));
 
+        if let Some(fs_op) = task.fs_op() {
+            overview.push(Line::from(vec![bold("Fs Op: "), Span::raw(fs_op)]));
+        }
+
         let title = "Location: ";
         let location_max_width = stats_area[0].width as usize - 2 - title.len(); // NOTE: -2 for the border
         let location = if task.location().len() > location_max_width {
@@ -164,7 +525,8 @@ impl TaskView {
 
         overview.push(Line::from(vec![bold(title), Span::raw(location)]));
 
-        let total = task.total(now);
+        let stats = task.windowed(display.window, now);
+        let total = stats.total;
 
         let dur_percent = |name: &'static str, amt: Duration| -> Line {
             let percent = amt.as_secs_f64().percent_of(total.as_secs_f64());
@@ -175,13 +537,17 @@ impl TaskView {
             ])
         };
 
-        overview.push(Line::from(vec![
+        let mut total_time = vec![
             bold("Total Time: "),
             styles.time_units(total, view::DUR_LIST_PRECISION, None),
-        ]));
-        overview.push(dur_percent("Busy: ", task.busy(now)));
-        overview.push(dur_percent("Scheduled: ", task.scheduled(now)));
-        overview.push(dur_percent("Idle: ", task.idle(now)));
+        ];
+        if let Some(period) = display.describe(now) {
+            total_time.push(Span::from(format!(" ({period})")));
+        }
+        overview.push(Line::from(total_time));
+        overview.push(dur_percent("Busy: ", stats.busy));
+        overview.push(dur_percent("Scheduled: ", stats.scheduled));
+        overview.push(dur_percent("Idle: ", stats.idle));
 
         let mut waker_stats = vec![Line::from(vec![
             bold("Current wakers: "),
@@ -194,7 +560,7 @@ impl TaskView {
 
         let mut wakeups = vec![
             bold("Woken: "),
-            Span::from(format!("{} times", task.wakes())),
+            Span::from(format!("{} times", stats.wakes)),
         ];
 
         // If the task has been woken, add the time since wake to its stats as well.
@@ -208,6 +574,13 @@ impl TaskView {
 
         waker_stats.push(Line::from(wakeups));
 
+        if let Some(awaiting) = task.awaiting() {
+            waker_stats.push(Line::from(vec![
+                bold("Awaiting: "),
+                Span::from(awaiting.to_owned()),
+            ]));
+        }
+
         if task.self_wakes() > 0 {
             waker_stats.push(Line::from(vec![
                 bold("Self Wakes: "),
@@ -220,13 +593,42 @@ impl TaskView {
         }
 
         let mut fields = Text::default();
-        fields.extend(task.formatted_fields().iter().cloned().map(Line::from));
+        fields.extend(
+            task.formatted_column_fields()
+                .iter()
+                .chain(task.formatted_fields())
+                .cloned()
+                .map(Line::from),
+        );
+
+        if let (Some(panic), Some(panic_area)) = (panic, panic_area) {
+            let panic = Paragraph::new(panic).block(styles.border_block().title("Panic"));
+            frame.render_widget(panic, panic_area);
+        }
+
+        if let (Some(abort), Some(abort_area)) = (abort, abort_area) {
+            let abort = Paragraph::new(abort).block(styles.border_block().title("Aborted"));
+            frame.render_widget(abort, abort_area);
+        }
+        self.aborted_by = abort_area.zip(aborted_by_task);
 
         if let Some(warnings_area) = warnings_area {
             let warnings = List::new(warnings).block(styles.border_block().title("Warnings"));
             frame.render_widget(warnings, warnings_area);
         }
 
+        if let Some(fs_ops_area) = fs_ops_area {
+            let fs_ops = List::new(fs_ops).block(styles.border_block().title("Filesystem Ops"));
+            frame.render_widget(fs_ops, fs_ops_area);
+        }
+
+        if let (Some(dump), Some(dump_area)) = (dump, dump_area) {
+            let dump = Paragraph::new(dump)
+                .wrap(Wrap { trim: false })
+                .block(styles.border_block().title("Dump"));
+            frame.render_widget(dump, dump_area);
+        }
+
         let task_widget = Paragraph::new(overview).block(styles.border_block().title("Task"));
         let wakers_widget = Paragraph::new(waker_stats).block(styles.border_block().title("Waker"));
 
@@ -238,12 +640,14 @@ impl TaskView {
         ) as u16
             + 2_u16; // extra 2 characters for the border
         let poll_durations_widget = Durations::new(styles)
-            .histogram(details.and_then(|d| d.poll_times_histogram()))
+            .histogram(poll_times)
+            .slos(&poll_slos)
             .percentiles_title(poll_percentiles_title)
             .histogram_title("Poll Times Histogram")
             .percentiles_width(percentiles_width);
         let scheduled_durations_widget = Durations::new(styles)
-            .histogram(details.and_then(|d| d.scheduled_times_histogram()))
+            .histogram(scheduled_times)
+            .slos(&scheduled_slos)
             .percentiles_title(scheduled_percentiles_title)
             .histogram_title("Scheduled Times Histogram")
             .percentiles_width(percentiles_width);
@@ -251,26 +655,160 @@ impl TaskView {
         let fields_widget = Paragraph::new(fields).block(styles.border_block().title("Fields"));
 
         frame.render_widget(controls.into_widget(), controls_area);
+        if let (Some(export), Some(export_area)) = (export, export_area) {
+            frame.render_widget(Paragraph::new(export), export_area);
+        }
+        if let (Some(abort_status), Some(abort_status_area)) = (abort_status, abort_status_area) {
+            frame.render_widget(Paragraph::new(abort_status), abort_status_area);
+        }
         frame.render_widget(task_widget, stats_area[0]);
         frame.render_widget(wakers_widget, stats_area[1]);
-        frame.render_widget(poll_durations_widget, poll_dur_area);
-        frame.render_widget(scheduled_durations_widget, scheduled_dur_area);
+        frame.render_widget(
+            StateBreakdown::new(styles, task.state_times(display.window, now)),
+            states_area,
+        );
+        if let (Some(polls), Some(timeline_area)) = (recent_polls, timeline_area) {
+            frame.render_widget(PollTimeline::new(styles, polls), timeline_area);
+        }
+        if let (Some(intervals), Some(activity_area)) = (activity, activity_area) {
+            frame.render_widget(ActivityChart::new(styles, intervals), activity_area);
+        }
+        if let Some((poll_dur_area, scheduled_dur_area)) = histogram_areas {
+            frame.render_widget(poll_durations_widget, poll_dur_area);
+            frame.render_widget(scheduled_durations_widget, scheduled_dur_area);
+        }
         frame.render_widget(fields_widget, fields_area);
     }
 }
 
 impl HelpText for TaskView {
-    fn render_help_content(&self, styles: &view::Styles) -> Paragraph<'static> {
-        controls_paragraph(view_controls(), styles)
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Owned(view_controls())
     }
 }
 
-const fn view_controls() -> &'static [ControlDisplay] {
-    &[ControlDisplay {
+fn view_controls() -> Vec<ControlDisplay> {
+    let return_to_list = ControlDisplay {
         action: "return to task list",
         keys: &[KeyDisplay {
             base: "esc",
             utf8: Some("\u{238B} esc"),
         }],
-    }]
+    };
+    let export = ControlDisplay {
+        action: "export histograms",
+        keys: &[KeyDisplay {
+            base: "e",
+            utf8: None,
+        }],
+    };
+    let dump = ControlDisplay {
+        action: "dump task",
+        keys: &[KeyDisplay {
+            base: "I",
+            utf8: None,
+        }],
+    };
+    let abort = ControlDisplay {
+        action: "abort task (press twice)",
+        keys: &[KeyDisplay {
+            base: "x",
+            utf8: None,
+        }],
+    };
+    [
+        &[return_to_list, export, dump, abort],
+        BOOKMARK_CONTROLS,
+        COPY_TASK_CONTROLS,
+        OPEN_TASK_CONTROLS,
+        STATS_CONTROLS,
+    ]
+    .concat()
+}
+
+/// Returns the lines describing a task's dump, or why it couldn't be dumped.
+fn dump_text(dump: &Result<Dump, String>, styles: &view::Styles) -> Text<'static> {
+    let dump = match dump {
+        Ok(dump) => dump,
+        Err(error) => {
+            return Text::from(Line::from(vec![
+                Span::styled(
+                    "Failed to dump the task: ",
+                    styles.fg(ratatui::style::Color::Red),
+                ),
+                Span::raw(error.clone()),
+            ]))
+        }
+    };
+    let mut lines = vec![Line::from(vec![
+        bold("Location: "),
+        Span::raw(dump.location.clone()),
+    ])];
+    if let Some(taken_at) = dump.taken_at {
+        lines.push(Line::from(vec![
+            bold("Taken at: "),
+            Span::raw(humantime::format_rfc3339_seconds(taken_at).to_string()),
+        ]));
+    }
+    lines.extend(dump.fields.iter().map(|(name, value)| {
+        Line::from(vec![
+            Span::styled(name.clone(), styles.fg(ratatui::style::Color::LightBlue)),
+            Span::raw(" = "),
+            Span::styled(value.clone(), styles.fg(ratatui::style::Color::Yellow)),
+        ])
+    }));
+    match dump.backtrace {
+        Some(ref backtrace) => {
+            lines.push(Line::from(bold("Backtrace:")));
+            lines.extend(backtrace.lines().map(|line| Line::from(line.to_owned())));
+        }
+        None => lines.push(Line::from(vec![
+            bold("Backtrace: "),
+            // Tokio only dumps where tasks are suspended through the
+            // runtime itself, which console-subscriber has no handle to.
+            Span::raw("not captured; the target doesn't take Tokio task dumps"),
+        ])),
+    }
+    Text::from(lines)
+}
+
+/// Returns a task's details as plain text, with its stats over the
+/// `display`ed window, for pasting into an issue or a chat.
+pub(super) fn details_text(task: &Task, display: StatsDisplay, now: SystemTime) -> String {
+    let stats = task.windowed(display.window, now);
+    let mut text = format!("Task {} ({})\n", task.id_str(), task.state().description());
+    let mut line = |name: &str, value: &dyn std::fmt::Display| {
+        let _ = writeln!(text, "{name}: {value}");
+    };
+    if let Some(name) = task.name() {
+        line("Name", &name);
+    }
+    line("Target", &task.target());
+    line("Location", &task.location());
+    if let Some(period) = display.describe(now) {
+        line("Stats", &period);
+    }
+    line("Total Time", &format_args!("{:.2?}", stats.total));
+    line("Busy", &format_args!("{:.2?}", stats.busy));
+    line("Scheduled", &format_args!("{:.2?}", stats.scheduled));
+    line("Idle", &format_args!("{:.2?}", stats.idle));
+    line("Polls", &stats.polls);
+    line("Woken", &format_args!("{} times", stats.wakes));
+    line("Self Wakes", &task.self_wakes());
+    line("Current Wakers", &task.waker_count());
+    for linter in task.warnings() {
+        line("Warning", &linter.format(task, now));
+    }
+    let fields = task
+        .formatted_column_fields()
+        .iter()
+        .chain(task.formatted_fields());
+    for field in fields {
+        let field = field
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect::<String>();
+        let _ = writeln!(text, "  {field}");
+    }
+    text
 }
@@ -1,12 +1,24 @@
 use crate::{
+    highlight::Highlight,
+    i18n::{tr, tr_fmt},
+    query::{Filter, Value},
     state::{
-        tasks::{SortBy, Task, TaskState},
-        State,
+        tasks::{Column, ColumnValue, SortBy, StatsWindow, Task, TaskState},
+        Id, State,
     },
+    util,
     view::{
         self, bold,
-        controls::Controls,
-        table::{view_controls, TableList, TableListState},
+        bookmarks::BOOKMARK_CONTROLS,
+        columns::COLUMN_CONTROLS,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        diff::DIFF_CONTROLS,
+        distributions::DISTRIBUTION_CONTROLS,
+        fields::FIELDS_CONTROLS,
+        groups::GROUP_CONTROLS,
+        table::{self, Search, TableList, TableListState},
+        task::{COPY_TASK_CONTROLS, OPEN_TASK_CONTROLS},
+        tree::TREE_CONTROLS,
         DUR_LEN, DUR_TABLE_PRECISION,
     },
 };
@@ -16,21 +28,130 @@ use ratatui::{
     text::{Line, Span, Text},
     widgets::{self, Cell, ListItem, Row, Table},
 };
+use std::{
+    rc::{Rc, Weak},
+    time::SystemTime,
+};
+
+/// The controls for choosing which of tasks' stats are displayed, which are
+/// available in the task list and a task's details.
+pub(crate) const STATS_CONTROLS: &[ControlDisplay] = &[
+    ControlDisplay {
+        action: "toggle recent stats",
+        keys: &[KeyDisplay {
+            base: "w",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "reset stats",
+        keys: &[KeyDisplay {
+            base: "z",
+            utf8: None,
+        }],
+    },
+];
+
+/// The controls for following the newest or the top task, or pinning the
+/// selected task.
+pub(crate) const FOLLOW_CONTROLS: &[ControlDisplay] = &[
+    ControlDisplay {
+        action: "follow newest/top task",
+        keys: &[KeyDisplay {
+            base: "F",
+            utf8: None,
+        }],
+    },
+    ControlDisplay {
+        action: "pin selected task",
+        keys: &[KeyDisplay {
+            base: "P",
+            utf8: None,
+        }],
+    },
+];
+
+/// The controls for editing the rules which highlight tasks in the task
+/// list.
+pub(crate) const HIGHLIGHT_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "edit highlight rules",
+    keys: &[KeyDisplay {
+        base: "A",
+        utf8: None,
+    }],
+}];
+
+/// The control for filtering the task list by a condition over tasks'
+/// columns.
+pub(crate) const FILTER_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "filter tasks",
+    keys: &[KeyDisplay {
+        base: "|",
+        utf8: None,
+    }],
+}];
+
+/// The control for exporting the tasks' busy time as a flamegraph.
+pub(crate) const FLAMEGRAPH_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "export busy time as flamegraph",
+    keys: &[KeyDisplay {
+        base: "Z",
+        utf8: None,
+    }],
+}];
+
+/// What the task list displays, in addition to its own state.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TasksContext {
+    pub(crate) stats: StatsDisplay,
+    pub(crate) follow: Follow,
+    /// The rules which style the rows of the tasks they match.
+    pub(crate) highlights: Rc<[Highlight]>,
+    /// The filter which hides the tasks that don't match it, if one is
+    /// applied.
+    pub(crate) filter: Option<Rc<Filter>>,
+    /// How many characters of each task's fields are scrolled out of the
+    /// `Fields` column, to the left.
+    pub(crate) fields_scroll: usize,
+}
+
+/// Which task the task list's selection follows as tasks are spawned and the
+/// list is re-sorted, like `tail -f`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Follow {
+    /// The selection stays where the user put it.
+    #[default]
+    Off,
+    /// The most recently spawned task.
+    Newest,
+    /// The task at the top of the list, in its current sorting.
+    Top,
+    /// A task which the user pinned, wherever sorting moves it.
+    Task(Id<Task>),
+}
+
+/// Which of tasks' stats are displayed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct StatsDisplay {
+    pub(crate) window: StatsWindow,
+    /// When the user last reset the displayed stats, if they have.
+    pub(crate) reset_at: Option<SystemTime>,
+}
 
 #[derive(Debug, Default)]
 pub(crate) struct TasksTable {}
 
-impl TableList<12> for TasksTable {
+impl TableList<13> for TasksTable {
     type Row = Task;
     type Sort = SortBy;
-    type Context = ();
+    type Context = TasksContext;
 
-    const HEADER: &'static [&'static str; 12] = &[
-        "Warn", "ID", "State", "Name", "Total", "Busy", "Sched", "Idle", "Polls", "Kind",
+    const HEADER: &'static [&'static str; 13] = &[
+        "Mark", "Warn", "ID", "State", "Name", "Total", "Busy", "Sched", "Idle", "Polls", "Kind",
         "Location", "Fields",
     ];
 
-    const WIDTHS: &'static [usize; 12] = &[
+    const WIDTHS: &'static [usize; 13] = &[
         Self::HEADER[0].len() + 1,
         Self::HEADER[1].len() + 1,
         Self::HEADER[2].len() + 1,
@@ -43,17 +164,121 @@ impl TableList<12> for TasksTable {
         Self::HEADER[9].len() + 1,
         Self::HEADER[10].len() + 1,
         Self::HEADER[11].len() + 1,
+        Self::HEADER[12].len() + 1,
+    ];
+
+    const EXTRA_CONTROLS: &'static [&'static [ControlDisplay]] = &[
+        BOOKMARK_CONTROLS,
+        COPY_TASK_CONTROLS,
+        OPEN_TASK_CONTROLS,
+        STATS_CONTROLS,
+        FOLLOW_CONTROLS,
+        DISTRIBUTION_CONTROLS,
+        TREE_CONTROLS,
+        GROUP_CONTROLS,
+        DIFF_CONTROLS,
+        COLUMN_CONTROLS,
+        HIGHLIGHT_CONTROLS,
+        FIELDS_CONTROLS,
+        FILTER_CONTROLS,
+        FLAMEGRAPH_CONTROLS,
     ];
 
+    // `g` groups the tasks instead.
+    const GG_SCROLLS_TO_TOP: bool = false;
+
+    fn matches(task: &Task, search: &Search) -> bool {
+        search.matches([
+            task.id_str(),
+            task.name().unwrap_or_default(),
+            task.target(),
+            task.kind(),
+            task.location(),
+        ]) || search.matches_spans(task.formatted_fields())
+    }
+
+    fn headers(state: &State) -> Vec<String> {
+        let (fixed_header, fields_header) = Self::HEADER.split_at(SortBy::FIRST_OPTIONAL_COLUMN);
+        fixed_header
+            .iter()
+            .copied()
+            .chain(state.tasks_state().columns.iter().map(Column::header))
+            .chain(fields_header.iter().copied())
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    fn values(
+        task: &Task,
+        now: SystemTime,
+        state: &State,
+        context: &Self::Context,
+    ) -> Option<Vec<Value>> {
+        let stats = task.windowed(context.stats.window, now);
+        let mut values = vec![
+            if task.is_bookmarked() {
+                Value::Text("bookmarked".to_owned())
+            } else {
+                Value::Null
+            },
+            Value::Number(task.warnings().len() as f64),
+            Value::id(task.id_str()),
+            Value::Text(task.state().description().to_owned()),
+            task.name()
+                .map_or(Value::Null, |name| Value::Text(name.to_owned())),
+            Value::Duration(stats.total),
+            Value::Duration(stats.busy),
+            Value::Duration(stats.scheduled),
+            Value::Duration(stats.idle),
+            Value::Number(stats.polls as f64),
+            Value::Text(task.kind().to_owned()),
+            Value::Text(task.location().to_owned()),
+        ];
+        let columns = &state.tasks_state().columns;
+        values.extend(columns.iter().enumerate().map(|(idx, column)| {
+            match task.column_value(now, idx, column) {
+                Some(ColumnValue::Duration(value)) => Value::Duration(value),
+                Some(ColumnValue::Number(value)) => Value::Number(value),
+                Some(value) => Value::Text(value.to_string()),
+                None => Value::Null,
+            }
+        }));
+        values.push(Value::Text(table::spans_text(task.formatted_fields())));
+        Some(values)
+    }
+
+    fn refresh(
+        table_list_state: &mut TableListState<Self, 13>,
+        now: SystemTime,
+        state: &mut State,
+        context: &Self::Context,
+    ) {
+        table_list_state
+            .sorted_items
+            .extend(state.tasks_state_mut().take_new_tasks());
+        table_list_state.apply_search();
+        if let Some(ref filter) = context.filter {
+            table_list_state.hide_unless(|task| filter.matches(task, now));
+        }
+
+        let columns = &state.tasks_state().columns;
+        table_list_state.extra_columns = columns.len();
+
+        table_list_state.sort_items(now, |sort_by, task| {
+            sort_by.key(now, context.stats.window, columns, task)
+        });
+    }
+
     fn render(
-        table_list_state: &mut TableListState<Self, 12>,
+        table_list_state: &mut TableListState<Self, 13>,
         styles: &view::Styles,
         frame: &mut ratatui::terminal::Frame,
         area: layout::Rect,
         state: &mut State,
-        _: Self::Context,
+        context: Self::Context,
     ) {
-        let state_len: u16 = Self::WIDTHS[2] as u16;
+        let display = context.stats;
+        let state_len: u16 = Self::WIDTHS[3] as u16;
         let now = if let Some(now) = state.last_updated_at() {
             now
         } else {
@@ -61,95 +286,77 @@ impl TableList<12> for TasksTable {
             return;
         };
 
-        table_list_state
-            .sorted_items
-            .extend(state.tasks_state_mut().take_new_tasks());
+        Self::refresh(table_list_state, now, state, &context);
+        let columns = state.tasks_state().columns.clone();
 
-        table_list_state
-            .sort_by
-            .sort(now, &mut table_list_state.sorted_items);
+        match context.follow {
+            Follow::Off => {}
+            Follow::Newest => {
+                let newest = table_list_state
+                    .sorted_items
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, task)| Some((idx, task.upgrade()?.borrow().id())))
+                    .max_by_key(|&(_, id)| id);
+                if let Some((idx, _)) = newest {
+                    table_list_state.select_sorted(idx);
+                }
+            }
+            Follow::Top if table_list_state.len() > 0 => {
+                table_list_state.table_state.select(Some(0));
+            }
+            Follow::Top => {}
+            Follow::Task(id) => {
+                let pinned = table_list_state
+                    .sorted_items
+                    .iter()
+                    .position(|task| task.upgrade().is_some_and(|task| task.borrow().id() == id));
+                if let Some(idx) = pinned {
+                    table_list_state.select_sorted(idx);
+                }
+            }
+        }
+
+        let (fixed_header, fields_header) = Self::HEADER.split_at(SortBy::FIRST_OPTIONAL_COLUMN);
+        let header_names = fixed_header
+            .iter()
+            .copied()
+            .chain(columns.iter().map(Column::header))
+            .chain(fields_header.iter().copied())
+            .collect::<Vec<_>>();
+        let visible = table_list_state.visible_columns(&header_names);
 
         let dur_cell = |dur: std::time::Duration| -> Cell<'static> {
             Cell::from(styles.time_units(dur, DUR_TABLE_PRECISION, Some(DUR_LEN)))
         };
 
         // Start out wide enough to display the column headers...
-        let mut warn_width = view::Width::new(Self::WIDTHS[0] as u16);
-        let mut id_width = view::Width::new(Self::WIDTHS[1] as u16);
-        let mut name_width = view::Width::new(Self::WIDTHS[3] as u16);
-        let mut polls_width = view::Width::new(Self::WIDTHS[7] as u16);
-        let mut kind_width = view::Width::new(Self::WIDTHS[8] as u16);
-        let mut location_width = view::Width::new(Self::WIDTHS[9] as u16);
+        let mut warn_width = view::Width::new(Self::WIDTHS[1] as u16);
+        let mut id_width = view::Width::new(Self::WIDTHS[2] as u16);
+        let mut name_width = view::Width::new(Self::WIDTHS[4] as u16);
+        let mut polls_width = view::Width::new(Self::WIDTHS[8] as u16);
+        let mut kind_width = view::Width::new(Self::WIDTHS[9] as u16);
+        let mut location_width = view::Width::new(Self::WIDTHS[10] as u16);
+        let mut column_widths = columns
+            .iter()
+            .map(|column| view::Width::new(column.header().len() as u16 + 1))
+            .collect::<Vec<_>>();
 
+        // Counting the tasks in each state is cheap, unlike formatting a row
+        // for each of them.
         let mut num_idle = 0;
         let mut num_running = 0;
-
-        let rows = {
-            let id_width = &mut id_width;
-            let kind_width = &mut kind_width;
-            let location_width = &mut location_width;
-            let name_width = &mut name_width;
-            let polls_width = &mut polls_width;
-            let warn_width = &mut warn_width;
-            let num_running = &mut num_running;
-            let num_idle = &mut num_idle;
-
-            table_list_state
-                .sorted_items
-                .iter()
-                .filter_map(move |task| {
-                    let task = task.upgrade()?;
-                    let task = task.borrow();
-                    let state = task.state();
-
-                    // Count task states
-                    match state {
-                        TaskState::Running => *num_running += 1,
-                        TaskState::Idle => *num_idle += 1,
-                        _ => {}
-                    };
-                    let n_warnings = task.warnings().len();
-                    let warnings = if n_warnings > 0 {
-                        let n_warnings = n_warnings.to_string();
-                        warn_width.update_len(n_warnings.len() + 2); // add 2 for the warning icon + whitespace
-                        Cell::from(Line::from(vec![
-                            styles.warning_narrow(),
-                            Span::from(n_warnings),
-                        ]))
-                    } else {
-                        Cell::from("")
-                    };
-
-                    let mut row = Row::new(vec![
-                        warnings,
-                        Cell::from(id_width.update_str(format!(
-                            "{:>width$}",
-                            task.id_str(),
-                            width = id_width.chars() as usize
-                        ))),
-                        Cell::from(task.state().render(styles)),
-                        Cell::from(name_width.update_str(task.name().unwrap_or("")).to_string()),
-                        dur_cell(task.total(now)),
-                        dur_cell(task.busy(now)),
-                        dur_cell(task.scheduled(now)),
-                        dur_cell(task.idle(now)),
-                        Cell::from(polls_width.update_str(task.total_polls().to_string())),
-                        Cell::from(kind_width.update_str(task.kind()).to_owned()),
-                        Cell::from(location_width.update_str(task.location()).to_owned()),
-                        Cell::from(Line::from(
-                            task.formatted_fields()
-                                .iter()
-                                .flatten()
-                                .cloned()
-                                .collect::<Vec<_>>(),
-                        )),
-                    ]);
-                    if state == TaskState::Completed {
-                        row = row.style(styles.terminated());
-                    }
-                    Some(row)
-                })
-        };
+        for task in table_list_state
+            .sorted_items
+            .iter()
+            .filter_map(Weak::upgrade)
+        {
+            match task.borrow().state() {
+                TaskState::Running => num_running += 1,
+                TaskState::Idle => num_idle += 1,
+                _ => {}
+            }
+        }
 
         let header_style = if styles.color(Color::Cyan).is_some() {
             Style::default()
@@ -158,33 +365,37 @@ impl TableList<12> for TasksTable {
         };
         let header_style = header_style.add_modifier(style::Modifier::BOLD);
 
-        let header = Row::new(Self::HEADER.iter().enumerate().map(|(idx, &value)| {
-            if idx == table_list_state.selected_column {
-                if table_list_state.sort_descending {
-                    Cell::from(styles.ascending(value))
-                } else {
-                    Cell::from(styles.descending(value))
-                }
-            } else {
-                Cell::from(value)
-            }
-        }))
-        .height(1)
-        .style(header_style);
+        let header_cells = header_names
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| table_list_state.header_cell(styles, idx, value))
+            .collect();
+        let header = Row::new(table::arrange(&visible, header_cells))
+            .height(1)
+            .style(header_style);
 
-        let table = if table_list_state.sort_descending {
-            Table::default().rows(rows)
-        } else {
-            Table::default().rows(rows.rev())
-        };
-
-        let block = styles.border_block().title(vec![
-            bold(format!("Tasks ({}) ", table_list_state.len())),
+        let mut title = vec![
+            bold(tr_fmt("Tasks ({}) ", &[&table_list_state.len()])),
             TaskState::Running.render(styles),
-            Span::from(format!(" Running ({}) ", num_running)),
+            Span::from(tr_fmt(" Running ({}) ", &[&num_running])),
             TaskState::Idle.render(styles),
-            Span::from(format!(" Idle ({})", num_idle)),
-        ]);
+            Span::from(tr_fmt(" Idle ({})", &[&num_idle])),
+        ];
+        if let Some(period) = display.describe(now) {
+            title.push(bold(format!(" {period}")));
+        }
+        if let Some(follow) = context.follow.describe() {
+            title.push(bold(format!(" {follow}")));
+        }
+        if context.fields_scroll > 0 {
+            let scroll = context.fields_scroll;
+            title.push(bold(format!(" fields scrolled by {scroll}")));
+        }
+        if let Some(ref filter) = context.filter {
+            title.push(bold(format!(" filtered by `{filter}`")));
+        }
+        title.extend(table_list_state.search_title(styles));
+        let block = styles.border_block().title(title);
 
         /* TODO: use this to adjust the max size of name and kind columns...
         // How many characters wide are the fixed-length non-field columns?
@@ -207,13 +418,19 @@ impl TableList<12> for TasksTable {
                     Span::from(format!("{} {}", warning.count(), warning.summary())),
                 ])))
             })
+            .chain(state.trends().rising().map(|rising| {
+                ListItem::new(Text::from(Line::from(vec![
+                    styles.warning_wide(),
+                    Span::from(rising.to_string()),
+                ])))
+            }))
             .collect::<Vec<_>>();
 
         let layout = layout::Layout::default()
             .direction(layout::Direction::Vertical)
             .margin(0);
 
-        let controls = Controls::new(view_controls(), &area, styles);
+        let controls = Controls::new(&table_list_state.controls(), &area, styles);
 
         let (controls_area, tasks_area, warnings_area) = if warnings.is_empty() {
             let chunks = layout
@@ -240,6 +457,100 @@ impl TableList<12> for TasksTable {
                 .split(area);
             (chunks[0], chunks[2], Some(chunks[1]))
         };
+        // Only the rows which fit in the table, below its header, are
+        // formatted.
+        let table_area = block.inner(tasks_area);
+        let (items, window) = table_list_state.visible_items(table_area.height.saturating_sub(1));
+
+        let rows = {
+            let id_width = &mut id_width;
+            let kind_width = &mut kind_width;
+            let location_width = &mut location_width;
+            let name_width = &mut name_width;
+            let polls_width = &mut polls_width;
+            let warn_width = &mut warn_width;
+            let column_widths = &mut column_widths;
+            let columns = &columns;
+            let visible = &visible;
+            let highlights = &context.highlights;
+            let fields_scroll = context.fields_scroll;
+
+            items
+                .iter()
+                .filter_map(move |task| {
+                    let task = task.upgrade()?;
+                    let task = task.borrow();
+                    let state = task.state();
+                    let stats = task.windowed(display.window, now);
+                    let n_warnings = task.warnings().len();
+                    let warnings = if n_warnings > 0 {
+                        let n_warnings = n_warnings.to_string();
+                        warn_width.update_len(n_warnings.len() + 2); // add 2 for the warning icon + whitespace
+                        Cell::from(Line::from(vec![
+                            styles.warning_narrow(),
+                            Span::from(n_warnings),
+                        ]))
+                    } else {
+                        Cell::from("")
+                    };
+
+                    let bookmark = if task.is_bookmarked() {
+                        Cell::from(styles.bookmark())
+                    } else {
+                        Cell::from("")
+                    };
+
+                    let mut cells = vec![
+                        bookmark,
+                        warnings,
+                        Cell::from(id_width.update_str(format!(
+                            "{:>width$}",
+                            task.id_str(),
+                            width = id_width.chars() as usize
+                        ))),
+                        Cell::from(task.state().render(styles)),
+                        Cell::from(name_width.update_str(task.name().unwrap_or("")).to_string()),
+                        dur_cell(stats.total),
+                        dur_cell(stats.busy),
+                        dur_cell(stats.scheduled),
+                        dur_cell(stats.idle),
+                        Cell::from(polls_width.update_str(util::abbreviate_count(stats.polls))),
+                        Cell::from(kind_width.update_str(task.kind()).to_owned()),
+                        Cell::from(location_width.update_str(task.location()).to_owned()),
+                    ];
+                    cells.extend(
+                        columns
+                            .iter()
+                            .zip(column_widths.iter_mut())
+                            .enumerate()
+                            .map(|(idx, (column, width))| {
+                                let value =
+                                    task.column_value(now, idx, column).map(|v| v.to_string());
+                                Cell::from(width.update_str(value.unwrap_or_default()))
+                            }),
+                    );
+                    cells.push(Cell::from(Line::from(table::skip_chars(
+                        task.formatted_fields().iter().flatten().cloned(),
+                        fields_scroll,
+                    ))));
+                    let mut row = Row::new(table::arrange(visible, cells));
+                    // Dim tasks which ended normally, but not ones which
+                    // panicked, so that crashes stand out.
+                    let mut style = Style::default();
+                    if matches!(state, TaskState::Completed | TaskState::Cancelled) {
+                        style = styles.terminated();
+                    }
+                    if let Some(highlight) = Highlight::style_task(highlights, styles, &task, now) {
+                        style = style.patch(highlight);
+                    }
+                    if style != Style::default() {
+                        row = row.style(style);
+                    }
+                    Some(row)
+                })
+                .collect::<Vec<_>>()
+        };
+
         // Fill all remaining characters in the frame with the task's fields.
         //
         // Ideally we'd use Min(0), and it would fill the rest of the space. But that is broken
@@ -247,7 +558,8 @@ impl TableList<12> for TasksTable {
         //
         // See https://github.com/fdehau/tui-rs/issues/525
         let fields_width = layout::Constraint::Percentage(100);
-        let widths = &[
+        let mut widths = vec![
+            layout::Constraint::Length(Self::WIDTHS[0] as u16),
             warn_width.constraint(),
             id_width.constraint(),
             layout::Constraint::Length(state_len),
@@ -259,28 +571,84 @@ impl TableList<12> for TasksTable {
             polls_width.constraint(),
             kind_width.constraint(),
             location_width.constraint(),
-            fields_width,
         ];
+        widths.extend(column_widths.iter().map(view::Width::constraint));
+        widths.push(fields_width);
+        for (column, width) in widths.iter_mut().enumerate() {
+            if let Some(fixed) = table_list_state.column_width(column) {
+                *width = layout::Constraint::Length(fixed);
+            }
+        }
+        let widths = table::arrange(&visible, widths);
 
-        let table = table
+        let table = Table::default()
+            .rows(rows)
             .header(header)
             .block(block)
-            .widths(widths)
+            .widths(&widths)
             .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
             .highlight_style(Style::default().add_modifier(style::Modifier::BOLD));
 
-        frame.render_stateful_widget(table, tasks_area, &mut table_list_state.table_state);
+        table_list_state.render_window(frame, tasks_area, table, window);
+        table_list_state.record_layout(table_area, &widths[..]);
         frame.render_widget(controls.into_widget(), controls_area);
 
         if let Some(area) = warnings_area {
             let block = styles
                 .border_block()
-                .title(Line::from(vec![bold("Warnings")]));
+                .title(Line::from(vec![bold(tr("Warnings"))]));
             frame.render_widget(widgets::List::new(warnings).block(block), area);
         }
 
-        table_list_state
-            .sorted_items
-            .retain(|t| t.upgrade().is_some());
+        table_list_state.remove_dropped();
+    }
+}
+
+impl Follow {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Newest,
+            Self::Newest => Self::Top,
+            Self::Top | Self::Task(_) => Self::Off,
+        }
+    }
+
+    /// Pins `task`, or unpins it if it's already pinned.
+    pub(crate) fn toggle_pin(self, task: Id<Task>) -> Self {
+        if self == Self::Task(task) {
+            Self::Off
+        } else {
+            Self::Task(task)
+        }
+    }
+
+    fn describe(self) -> Option<String> {
+        match self {
+            Self::Off => None,
+            Self::Newest => Some("following the newest task".to_owned()),
+            Self::Top => Some("following the top task".to_owned()),
+            Self::Task(id) => Some(format!("following task {id}")),
+        }
+    }
+}
+
+impl StatsDisplay {
+    /// Describes the period which the displayed stats cover at `now`, unless
+    /// it's the tasks' whole lives.
+    pub(crate) fn describe(&self, now: SystemTime) -> Option<String> {
+        let since_reset = self.reset_at.and_then(|at| now.duration_since(at).ok());
+        match (self.window, since_reset) {
+            (StatsWindow::Recent, Some(since)) if since < StatsWindow::RECENT => {
+                Some(format!("stats since reset {}s ago", since.as_secs()))
+            }
+            (StatsWindow::Recent, _) => Some(format!(
+                "stats for the last {}s",
+                StatsWindow::RECENT.as_secs()
+            )),
+            (StatsWindow::Lifetime, Some(since)) => {
+                Some(format!("stats since reset {}s ago", since.as_secs()))
+            }
+            (StatsWindow::Lifetime, None) => None,
+        }
     }
 }
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use ratatui::{
+    layout::Rect,
+    style::Color,
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::{
+    state::tasks::Poll,
+    view::{self, bold},
+};
+
+/// The symbols for a column of the timeline, from the least to the most of
+/// its time spent in polls. Any time in a poll at all is drawn, so that
+/// short polls aren't lost.
+const UTF8_LEVELS: [&str; 5] = [" ", "\u{2591}", "\u{2592}", "\u{2593}", "\u{2588}"];
+const ASCII_LEVELS: [&str; 5] = [" ", ".", "-", "=", "#"];
+
+/// This is a Ratatui widget which draws a task's recent polls as a timeline,
+/// with a column for each slice of time shaded by how much of it the task
+/// spent being polled, above a summary of how long the polls and the gaps
+/// between them took.
+pub(crate) struct PollTimeline<'a> {
+    styles: &'a view::Styles,
+    /// The polls to draw, oldest first
+    polls: &'a [Poll],
+}
+
+impl Widget for PollTimeline<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let block = self.styles.border_block().title("Recent Polls");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let (Some(first), Some(last)) = (self.polls.first(), self.polls.last()) else {
+            Paragraph::new("No polls recorded yet").render(inner, buf);
+            return;
+        };
+        let start = first.started;
+        let span = (last.started + last.duration)
+            .duration_since(start)
+            .unwrap_or_default();
+
+        let levels = if self.styles.utf8 {
+            UTF8_LEVELS
+        } else {
+            ASCII_LEVELS
+        };
+        let strip = busy_fractions(self.polls, inner.width as usize)
+            .into_iter()
+            .map(|busy| {
+                // Round up, so that any time in a poll shows up.
+                let level = (busy * (levels.len() - 1) as f64).ceil() as usize;
+                levels[level.min(levels.len() - 1)]
+            })
+            .collect::<String>();
+
+        let (mean_poll, mean_gap) = means(self.polls);
+        let summary = Line::from(vec![
+            bold(format!("{} polls", self.polls.len())),
+            Span::raw(" over "),
+            self.styles.time_units(span, view::DUR_LIST_PRECISION, None),
+            Span::raw(", "),
+            bold("mean poll: "),
+            self.styles
+                .time_units(mean_poll, view::DUR_LIST_PRECISION, None),
+            Span::raw(", "),
+            bold("mean gap: "),
+            self.styles
+                .time_units(mean_gap, view::DUR_LIST_PRECISION, None),
+        ]);
+        let lines = vec![
+            Line::from(Span::styled(strip, self.styles.fg(Color::Cyan))),
+            summary,
+        ];
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+impl<'a> PollTimeline<'a> {
+    pub(crate) fn new(styles: &'a view::Styles, polls: &'a [Poll]) -> Self {
+        Self { styles, polls }
+    }
+
+    /// How many lines the timeline takes up, including its borders.
+    pub(crate) const HEIGHT: u16 = 4;
+}
+
+/// Divides the time from the start of the first of `polls` to the end of the
+/// last into `columns` equal slices, and returns the fraction of each slice
+/// which was spent in a poll.
+fn busy_fractions(polls: &[Poll], columns: usize) -> Vec<f64> {
+    let mut busy = vec![0.0; columns];
+    let (Some(first), Some(last)) = (polls.first(), polls.last()) else {
+        return busy;
+    };
+    let start = first.started;
+    let span = (last.started + last.duration)
+        .duration_since(start)
+        .unwrap_or_default()
+        .as_secs_f64();
+    if columns == 0 || span == 0.0 {
+        return busy;
+    }
+    let width = span / columns as f64;
+    for poll in polls {
+        let from = poll
+            .started
+            .duration_since(start)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let to = from + poll.duration.as_secs_f64();
+        let first_column = (from / width) as usize;
+        for (column, busy) in busy.iter_mut().enumerate().skip(first_column) {
+            let column_start = column as f64 * width;
+            let column_end = column_start + width;
+            if column_start >= to {
+                break;
+            }
+            *busy += (to.min(column_end) - from.max(column_start)) / width;
+        }
+    }
+    for busy in &mut busy {
+        *busy = busy.clamp(0.0, 1.0);
+    }
+    busy
+}
+
+/// Returns the mean duration of `polls`, and the mean gap between the end of
+/// each poll and the start of the next.
+fn means(polls: &[Poll]) -> (Duration, Duration) {
+    let count = polls.len().max(1) as u32;
+    let mean_poll = polls.iter().map(|poll| poll.duration).sum::<Duration>() / count;
+    let gaps = polls
+        .windows(2)
+        .map(|pair| {
+            pair[1]
+                .started
+                .duration_since(pair[0].started + pair[0].duration)
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let mean_gap = gaps.iter().sum::<Duration>() / (gaps.len().max(1) as u32);
+    (mean_poll, mean_gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    /// Polls every 10ms, for 9ms each.
+    fn polls() -> Vec<Poll> {
+        let start = SystemTime::UNIX_EPOCH;
+        (0..10)
+            .map(|n| Poll {
+                started: start + Duration::from_millis(10 * n),
+                duration: Duration::from_millis(9),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn busy_columns() {
+        // 99ms over 11 columns is 9ms per column, so every column is mostly
+        // busy, and none is idle.
+        let busy = busy_fractions(&polls(), 11);
+        assert_eq!(busy.len(), 11);
+        assert!(
+            busy.iter().all(|&busy| busy > 0.7 && busy <= 1.0),
+            "{busy:?}"
+        );
+
+        // With a column per millisecond, every tenth one is idle.
+        let busy = busy_fractions(&polls(), 99);
+        assert!(busy[9] < 0.01, "{busy:?}");
+        assert!(busy[10] > 0.99, "{busy:?}");
+        assert!(busy[19] < 0.01, "{busy:?}");
+    }
+
+    #[test]
+    fn mean_poll_and_gap() {
+        assert_eq!(
+            means(&polls()),
+            (Duration::from_millis(9), Duration::from_millis(1))
+        );
+        assert_eq!(means(&[]), (Duration::ZERO, Duration::ZERO));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{
+    i18n::tr,
+    input,
+    state::{tasks::Task, Id, State},
+    view::{
+        self, bold,
+        controls::{ControlDisplay, Controls, KeyDisplay},
+        help::HelpText,
+        DUR_LEN, DUR_TABLE_PRECISION,
+    },
+};
+use ratatui::{
+    layout::{self, Constraint, Direction, Layout},
+    style::{self, Style},
+    widgets::{Cell, Row, Table, TableState},
+};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// The control for switching from the task list to the task tree.
+pub(crate) const TREE_CONTROLS: &[ControlDisplay] = &[ControlDisplay {
+    action: "show task tree",
+    keys: &[KeyDisplay {
+        base: "t",
+        utf8: None,
+    }],
+}];
+
+/// The tasks, with each task under the task which spawned it, for attributing
+/// load to the parts of a program which spawn tasks from tasks.
+///
+/// Each task's busy and idle times are rolled up over its whole subtree, so
+/// a collapsed subtree shows the load of everything it spawned.
+#[derive(Debug, Default)]
+pub(crate) struct TaskTreeView {
+    table_state: TableState,
+    /// The tasks whose subtrees are collapsed.
+    collapsed: HashSet<Id<Task>>,
+    /// The task on each row, as of the last render.
+    rows: Vec<Id<Task>>,
+}
+
+/// A task in the tree, and the totals of its subtree.
+struct Node {
+    task: Rc<RefCell<Task>>,
+    children: Vec<usize>,
+    tasks: usize,
+    busy: Duration,
+    idle: Duration,
+}
+
+impl TaskTreeView {
+    const HEADER: &'static [&'static str] = &["ID", "Name", "Tasks", "Busy", "Idle", "Location"];
+
+    pub(crate) fn update_input(&mut self, event: input::Event) {
+        use input::KeyCode::*;
+        let len = self.rows.len();
+        if len == 0 {
+            self.table_state.select(None);
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        let input::Event::Key(event) = event else {
+            return;
+        };
+        match event.code {
+            Down | Char('j') => self.table_state.select(Some((selected + 1) % len)),
+            Up | Char('k') => {
+                self.table_state
+                    .select(Some(selected.checked_sub(1).unwrap_or(len - 1)));
+            }
+            Left | Char('h') => {
+                if let Some(&id) = self.rows.get(selected) {
+                    self.collapsed.insert(id);
+                }
+            }
+            Right | Char('l') => {
+                if let Some(id) = self.rows.get(selected) {
+                    self.collapsed.remove(id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the currently selected task.
+    pub(crate) fn selected_task(&self, state: &State) -> Option<Rc<RefCell<Task>>> {
+        let id = *self.rows.get(self.table_state.selected()?)?;
+        state.tasks_state().task(id)?.upgrade()
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut ratatui::terminal::Frame,
+        area: layout::Rect,
+        state: &mut State,
+    ) {
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let (nodes, roots) = tree(state, now);
+
+        // Walk the tree depth first, skipping collapsed subtrees, with the
+        // lines leading to each task in front of its name. Each task on the
+        // stack has whether it's its parent's last child, or `None` for a
+        // root.
+        let mut rows = Vec::new();
+        let mut stack = roots
+            .iter()
+            .rev()
+            .map(|&root| (root, String::new(), None))
+            .collect::<Vec<_>>();
+        while let Some((idx, indent, last)) = stack.pop() {
+            let node = &nodes[idx];
+            let task = node.task.borrow();
+            let branch = match last {
+                None => "",
+                Some(true) => styles.if_utf8("└─ ", "`- "),
+                Some(false) => styles.if_utf8("├─ ", "|- "),
+            };
+            let collapsed = self.collapsed.contains(&task.id());
+            let marker = match (node.children.is_empty(), collapsed) {
+                (true, _) => "  ",
+                (false, true) => styles.if_utf8("▸ ", "+ "),
+                (false, false) => styles.if_utf8("▾ ", "- "),
+            };
+            let name = format!(
+                "{indent}{branch}{marker}{}",
+                task.name().unwrap_or_else(|| task.short_desc())
+            );
+            if !collapsed {
+                let indent = match last {
+                    None => indent.clone(),
+                    Some(true) => format!("{indent}   "),
+                    Some(false) => format!("{indent}{}", styles.if_utf8("│  ", "|  ")),
+                };
+                stack.extend(
+                    node.children
+                        .iter()
+                        .rev()
+                        .enumerate()
+                        .map(|(i, &child)| (child, indent.clone(), Some(i == 0))),
+                );
+            }
+            rows.push((idx, name));
+        }
+        self.rows = rows
+            .iter()
+            .map(|&(idx, _)| nodes[idx].task.borrow().id())
+            .collect();
+
+        match self.table_state.selected() {
+            _ if self.rows.is_empty() => self.table_state.select(None),
+            Some(selected) if selected >= self.rows.len() => {
+                self.table_state.select(Some(self.rows.len() - 1))
+            }
+            None => self.table_state.select(Some(0)),
+            Some(_) => {}
+        }
+
+        let name_width = rows
+            .iter()
+            .map(|(_, name)| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(Self::HEADER[1].len()) as u16;
+        let dur_cell = |dur| Cell::from(styles.time_units(dur, DUR_TABLE_PRECISION, Some(DUR_LEN)));
+        let rows = rows.into_iter().map(|(idx, name)| {
+            let node = &nodes[idx];
+            let task = node.task.borrow();
+            let row = Row::new(vec![
+                Cell::from(task.id_str().to_owned()),
+                Cell::from(name),
+                Cell::from(node.tasks.to_string()),
+                dur_cell(node.busy),
+                dur_cell(node.idle),
+                Cell::from(task.location().to_owned()),
+            ]);
+            if task.is_completed() {
+                row.style(styles.terminated())
+            } else {
+                row
+            }
+        });
+
+        let controls = Controls::new(view_controls(), &area, styles);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(controls.height()), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(name_width),
+                Constraint::Length(6),
+                Constraint::Length(DUR_LEN as u16),
+                Constraint::Length(DUR_LEN as u16),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(Self::HEADER.iter().copied().map(tr))
+                .style(Style::default().add_modifier(style::Modifier::BOLD)),
+        )
+        .block(
+            styles
+                .border_block()
+                .title(vec![bold(format!("Task Tree ({}) ", nodes.len()))]),
+        )
+        .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
+        .highlight_style(Style::default().add_modifier(style::Modifier::BOLD))
+        .column_spacing(1);
+
+        frame.render_widget(controls.into_widget(), chunks[0]);
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl HelpText for TaskTreeView {
+    fn help_controls(&self) -> Cow<'static, [ControlDisplay]> {
+        Cow::Borrowed(view_controls())
+    }
+}
+
+/// Returns every task, with the indexes of the tasks each one spawned and
+/// the totals of its subtree as of `now`, and the indexes of the tasks which
+/// weren't spawned by another task that's still in the state.
+fn tree(state: &State, now: SystemTime) -> (Vec<Node>, Vec<usize>) {
+    let mut tasks = state
+        .tasks_state()
+        .tasks()
+        .filter_map(|task| task.upgrade())
+        .collect::<Vec<_>>();
+    tasks.sort_by_key(|task| task.borrow().id());
+    let by_span = tasks
+        .iter()
+        .enumerate()
+        .map(|(idx, task)| (task.borrow().span_id(), idx))
+        .collect::<HashMap<_, _>>();
+
+    let mut nodes = tasks
+        .into_iter()
+        .map(|task| {
+            let (busy, idle) = {
+                let task = task.borrow();
+                (task.busy(now), task.idle(now))
+            };
+            Node {
+                task,
+                children: Vec::new(),
+                tasks: 1,
+                busy,
+                idle,
+            }
+        })
+        .collect::<Vec<_>>();
+    // A parent is always spawned before its children, so it has a lower ID.
+    // Checking that keeps a span ID which was reused by a later task from
+    // being taken for its parent, which could make a cycle.
+    let parents = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| {
+            let parent = node.task.borrow().parent_span_id()?;
+            by_span.get(&parent).copied().filter(|&parent| parent < idx)
+        })
+        .collect::<Vec<_>>();
+    let mut roots = Vec::new();
+    for (idx, parent) in parents.iter().enumerate() {
+        match *parent {
+            Some(parent) => nodes[parent].children.push(idx),
+            None => roots.push(idx),
+        }
+    }
+    // Children come after their parents, so adding each node's totals to its
+    // parent from the end rolls the totals up the whole tree.
+    for (idx, parent) in parents.iter().enumerate().rev() {
+        if let Some(parent) = *parent {
+            let (tasks, busy, idle) = (nodes[idx].tasks, nodes[idx].busy, nodes[idx].idle);
+            let parent = &mut nodes[parent];
+            parent.tasks += tasks;
+            parent.busy += busy;
+            parent.idle += idle;
+        }
+    }
+    (nodes, roots)
+}
+
+const fn view_controls() -> &'static [ControlDisplay] {
+    &[
+        ControlDisplay {
+            action: "scroll",
+            keys: &[
+                KeyDisplay {
+                    base: "up, down",
+                    utf8: Some("\u{2191}\u{2193}"),
+                },
+                KeyDisplay {
+                    base: "k, j",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "collapse/expand subtree",
+            keys: &[
+                KeyDisplay {
+                    base: "left, right",
+                    utf8: Some("\u{2190}\u{2192}"),
+                },
+                KeyDisplay {
+                    base: "h, l",
+                    utf8: None,
+                },
+            ],
+        },
+        ControlDisplay {
+            action: "view details",
+            keys: &[KeyDisplay {
+                base: "enter",
+                utf8: Some("\u{21B5}"),
+            }],
+        },
+        ControlDisplay {
+            action: "return to task list",
+            keys: &[KeyDisplay {
+                base: "esc, t",
+                utf8: Some("\u{238B} esc, t"),
+            }],
+        },
+    ]
+}
@@ -1,4 +1,7 @@
-use crate::state::tasks::{Task, TaskState};
+use crate::{
+    i18n::{tr, tr_fmt},
+    state::tasks::{Task, TaskState},
+};
 use std::{
     fmt::Debug,
     rc::Rc,
@@ -11,10 +14,12 @@ use std::{
 /// generating a warning message describing it. The [`Linter`] type wraps an
 /// instance of this trait to track active instances of the warning.
 pub trait Warn<T>: Debug {
-    /// Returns if the warning applies to `val`.
-    fn check(&self, val: &T) -> Warning;
+    /// Returns if the warning applies to `val`, as of `now` by the target's
+    /// clock.
+    fn check(&self, val: &T, now: SystemTime) -> Warning;
 
-    /// Formats a description of the warning detected for a *specific* `val`.
+    /// Formats a description of the warning detected for a *specific* `val`,
+    /// as of `now` by the target's clock.
     ///
     /// This may include dynamically formatted content specific to `val`, such
     /// as the specific numeric value that was over the line for detecting the
@@ -24,7 +29,7 @@ pub trait Warn<T>: Debug {
     /// for the [`SelfWakePercent`] warning, this returns a string like:
     ///
     /// > "This task has woken itself for more than 50% of its total wakeups (86%)"
-    fn format(&self, val: &T) -> String;
+    fn format(&self, val: &T, now: SystemTime) -> String;
 
     /// Returns a string summarizing the warning *in general*, suitable for
     /// displaying in a list of all detected warnings.
@@ -75,8 +80,8 @@ impl<T> Linter<T> {
     }
 
     /// Checks if the warning applies to a particular entity
-    pub(crate) fn check(&self, val: &T) -> Lint<T> {
-        match self.0.check(val) {
+    pub(crate) fn check(&self, val: &T, now: SystemTime) -> Lint<T> {
+        match self.0.check(val, now) {
             Warning::Ok => Lint::Ok,
             Warning::Warn => Lint::Warning(Self(self.0.clone())),
             Warning::Recheck => Lint::Recheck,
@@ -88,13 +93,13 @@ impl<T> Linter<T> {
         Rc::strong_count(&self.0) - 1
     }
 
-    pub(crate) fn format(&self, val: &T) -> String {
+    pub(crate) fn format(&self, val: &T, now: SystemTime) -> String {
         debug_assert!(
-            matches!(self.0.check(val), Warning::Warn),
+            matches!(self.0.check(val, now), Warning::Warn),
             "tried to format a warning for a {} that did not have that warning!",
             std::any::type_name::<T>()
         );
-        self.0.format(val)
+        self.0.format(val, now)
     }
 
     pub(crate) fn summary(&self) -> &str {
@@ -128,9 +133,9 @@ impl SelfWakePercent {
     pub(crate) fn new(min_percent: u64) -> Self {
         Self {
             min_percent,
-            description: format!(
+            description: tr_fmt(
                 "tasks have woken themselves over {}% of the time",
-                min_percent
+                &[&min_percent],
             ),
         }
     }
@@ -147,7 +152,7 @@ impl Warn<Task> for SelfWakePercent {
         self.description.as_str()
     }
 
-    fn check(&self, task: &Task) -> Warning {
+    fn check(&self, task: &Task, _: SystemTime) -> Warning {
         // Don't fire warning for tasks that are not async
         if task.is_blocking() {
             return Warning::Ok;
@@ -160,11 +165,11 @@ impl Warn<Task> for SelfWakePercent {
         }
     }
 
-    fn format(&self, task: &Task) -> String {
+    fn format(&self, task: &Task, _: SystemTime) -> String {
         let self_wakes = task.self_wake_percent();
-        format!(
+        tr_fmt(
             "This task has woken itself for more than {}% of its total wakeups ({}%)",
-            self.min_percent, self_wakes
+            &[&self.min_percent, &self_wakes],
         )
     }
 }
@@ -174,10 +179,10 @@ pub(crate) struct LostWaker;
 
 impl Warn<Task> for LostWaker {
     fn summary(&self) -> &str {
-        "tasks have lost their wakers"
+        tr("tasks have lost their wakers")
     }
 
-    fn check(&self, task: &Task) -> Warning {
+    fn check(&self, task: &Task, _: SystemTime) -> Warning {
         // Don't fire warning for tasks that are not async
         if task.is_blocking() {
             return Warning::Ok;
@@ -193,8 +198,8 @@ impl Warn<Task> for LostWaker {
         }
     }
 
-    fn format(&self, _: &Task) -> String {
-        "This task has lost its waker, and will never be woken again.".into()
+    fn format(&self, _: &Task, _: SystemTime) -> String {
+        tr("This task has lost its waker, and will never be woken again.").into()
     }
 }
 
@@ -210,9 +215,9 @@ impl NeverYielded {
     pub(crate) fn new(min_duration: Duration) -> Self {
         Self {
             min_duration,
-            description: format!(
+            description: tr_fmt(
                 "tasks have never yielded (threshold {}ms)",
-                min_duration.as_millis()
+                &[&min_duration.as_millis()],
             ),
         }
     }
@@ -229,7 +234,7 @@ impl Warn<Task> for NeverYielded {
         self.description.as_str()
     }
 
-    fn check(&self, task: &Task) -> Warning {
+    fn check(&self, task: &Task, now: SystemTime) -> Warning {
         // Don't fire warning for tasks that are not async
         if task.is_blocking() {
             return Warning::Ok;
@@ -244,17 +249,76 @@ impl Warn<Task> for NeverYielded {
         }
 
         // Avoid short-lived task false positives
-        if task.busy(SystemTime::now()) >= self.min_duration {
+        if task.busy(now) >= self.min_duration {
             Warning::Warn
         } else {
             Warning::Recheck
         }
     }
 
-    fn format(&self, task: &Task) -> String {
-        format!(
-            "This task has never yielded ({:?})",
-            task.busy(SystemTime::now()),
+    fn format(&self, task: &Task, now: SystemTime) -> String {
+        tr_fmt(
+            "This task has never yielded ({})",
+            &[&format!("{:?}", task.busy(now))],
+        )
+    }
+}
+
+/// Warning for if a task has been busy in a single poll for a long time,
+/// after having yielded before
+#[derive(Clone, Debug)]
+pub(crate) struct LongPoll {
+    min_duration: Duration,
+    description: String,
+}
+
+impl LongPoll {
+    pub(crate) const DEFAULT_DURATION: Duration = Duration::from_millis(500);
+    pub(crate) fn new(min_duration: Duration) -> Self {
+        Self {
+            min_duration,
+            description: tr_fmt(
+                "tasks have been busy without yielding (threshold {}ms)",
+                &[&min_duration.as_millis()],
+            ),
+        }
+    }
+}
+
+impl Default for LongPoll {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DURATION)
+    }
+}
+
+impl Warn<Task> for LongPoll {
+    fn summary(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn check(&self, task: &Task, now: SystemTime) -> Warning {
+        // Don't fire warning for tasks that are not async
+        if task.is_blocking() {
+            return Warning::Ok;
+        }
+        // A task's first poll is checked by `NeverYielded`
+        if task.is_running() && task.total_polls() <= 1 {
+            return Warning::Ok;
+        }
+
+        match task.last_poll_duration(now) {
+            Some(poll) if poll >= self.min_duration => Warning::Warn,
+            // The poll is still in progress, and may yet become too long
+            Some(_) if task.is_running() => Warning::Recheck,
+            _ => Warning::Ok,
+        }
+    }
+
+    fn format(&self, task: &Task, now: SystemTime) -> String {
+        let poll = task.last_poll_duration(now).unwrap_or_default();
+        tr_fmt(
+            "This task was busy for {} without yielding",
+            &[&format!("{:?}", poll)],
         )
     }
 }
@@ -265,10 +329,10 @@ pub(crate) struct AutoBoxedFuture;
 
 impl Warn<Task> for AutoBoxedFuture {
     fn summary(&self) -> &str {
-        "tasks have been boxed by the runtime due to their size"
+        tr("tasks have been boxed by the runtime due to their size")
     }
 
-    fn check(&self, task: &Task) -> Warning {
+    fn check(&self, task: &Task, _: SystemTime) -> Warning {
         let (Some(size_bytes), Some(original_size_bytes)) =
             (task.size_bytes(), task.original_size_bytes())
         else {
@@ -282,16 +346,17 @@ impl Warn<Task> for AutoBoxedFuture {
         }
     }
 
-    fn format(&self, task: &Task) -> String {
+    fn format(&self, task: &Task, _: SystemTime) -> String {
         let original_size = task
             .original_size_bytes()
             .expect("warning should not trigger if original size is None");
         let boxed_size = task
             .size_bytes()
             .expect("warning should not trigger if size is None");
-        format!(
-            "This task's future was auto-boxed by the runtime when spawning, due to its size (originally \
-            {original_size} bytes, boxed size {boxed_size} bytes)",
+        tr_fmt(
+            "This task's future was auto-boxed by the runtime when spawning, due to its size \
+            (originally {} bytes, boxed size {} bytes)",
+            &[&original_size, &boxed_size],
         )
     }
 }
@@ -307,7 +372,7 @@ impl LargeFuture {
     pub(crate) fn new(min_size: usize) -> Self {
         Self {
             min_size,
-            description: format!("tasks are {} bytes or larger", min_size),
+            description: tr_fmt("tasks are {} bytes or larger", &[&min_size]),
         }
     }
 }
@@ -323,7 +388,7 @@ impl Warn<Task> for LargeFuture {
         self.description.as_str()
     }
 
-    fn check(&self, task: &Task) -> Warning {
+    fn check(&self, task: &Task, _: SystemTime) -> Warning {
         // Don't fire warning for tasks that are not async
         if task.is_blocking() {
             return Warning::Ok;
@@ -337,11 +402,12 @@ impl Warn<Task> for LargeFuture {
         Warning::Ok
     }
 
-    fn format(&self, task: &Task) -> String {
-        format!(
+    fn format(&self, task: &Task, _: SystemTime) -> String {
+        tr_fmt(
             "This task occupies a large amount of stack space ({} bytes)",
-            task.size_bytes()
-                .expect("warning should not trigger if size is None"),
+            &[&task
+                .size_bytes()
+                .expect("warning should not trigger if size is None")],
         )
     }
 }
@@ -0,0 +1,267 @@
+//! Serving a live view of a target to browsers, for the `tokio-console serve`
+//! subcommand.
+//!
+//! The server watches the target as the console would, through a single
+//! connection, and answers each request from its state: the task and
+//! resource lists as the console would display them, and the details of a
+//! task or resource, as JSON, and a page which shows them.
+use crate::{
+    conn,
+    export::{self, Table},
+    httpd::{self, Request, Response},
+    query::{self, Filter},
+    state::State,
+    view,
+};
+use color_eyre::eyre::WrapErr;
+use ratatui::text::Span;
+use std::{
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+use tonic::transport::Uri;
+
+/// The page which shows the view, which polls the JSON endpoints.
+const INDEX: &str = include_str!("index.html");
+
+const JSON: &str = "application/json";
+
+/// A request, and where to send its response.
+type Pending = (Request, oneshot::Sender<Response>);
+
+/// Watches `target`, serving a view of it at `addr` until the process is
+/// stopped.
+///
+/// The task and resource lists are displayed as `view` would display them,
+/// and completed tasks and resources are dropped from `state` as they would
+/// be from the console's.
+pub(crate) async fn run(
+    addr: SocketAddr,
+    target: Uri,
+    styles: &view::Styles,
+    mut state: State,
+    mut view: view::View,
+) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .wrap_err_with(|| format!("could not listen for web UI requests on {addr}"))?;
+    // The state can't be shared between threads, so requests are sent to be
+    // answered between updates.
+    let (requests, mut pending) = mpsc::channel::<Pending>(64);
+    tokio::spawn(httpd::serve(listener, "web UI", move |request| {
+        let requests = requests.clone();
+        async move {
+            let (tx, rx) = oneshot::channel();
+            if requests.send((request, tx)).await.is_err() {
+                return unavailable();
+            }
+            rx.await.unwrap_or_else(|_| unavailable())
+        }
+    }));
+    tracing::info!(%addr, %target, "serving web UI");
+    println!("serving a view of {target} at http://{addr}/");
+
+    let mut conn = conn::Connection::new(target.clone());
+    loop {
+        tokio::select! {
+            message = conn.next_message() => {
+                state.set_clock_skew(conn.clock_skew());
+                match message {
+                    conn::Message::Update(update) => {
+                        // The task list is the only view whose data is kept
+                        // without being displayed.
+                        state.update(styles, &view::ViewState::TasksList, update);
+                        state.retain_active();
+                    }
+                    conn::Message::State(state_update) => state.update_state(state_update),
+                }
+            }
+            Some((request, respond)) = pending.recv() => {
+                let _ = respond.send(answer(&request, &target, &mut state, &mut view));
+            }
+        }
+    }
+}
+
+/// Answers `request` from `state`.
+fn answer(request: &Request, target: &Uri, state: &mut State, view: &mut view::View) -> Response {
+    let path = request.path.trim_end_matches('/');
+    let mut segments = path.split('/').skip(1);
+    match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (None | Some(""), ..) => Response::ok("text/html; charset=utf-8", INDEX),
+        (Some("api"), Some("status"), None, _) => {
+            let updated_at = state
+                .last_updated_at()
+                .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs_f64());
+            let status = serde_json::json!({
+                "target": target.to_string(),
+                "updated_at": updated_at,
+            });
+            Response::ok(JSON, format!("{status}\n"))
+        }
+        (Some("api"), Some(name @ ("tasks" | "resources")), None, _) => {
+            let filter = match request.param("filter").filter(|filter| !filter.is_empty()) {
+                Some(filter) => match filter.parse::<Filter>() {
+                    Ok(filter) => Some(filter),
+                    Err(error) => {
+                        return Response::new("400 Bad Request", "text/plain", format!("{error}\n"))
+                    }
+                },
+                None => None,
+            };
+            view.set_task_filter(filter);
+            let tables = view.tables(state);
+            view.set_task_filter(None);
+            match tables.iter().find(|table| table.name == name) {
+                Some(Table { header, rows, .. }) => {
+                    let header = header.iter().map(String::as_str);
+                    Response::ok(JSON, export::json(header, rows))
+                }
+                None => Response::not_found(),
+            }
+        }
+        (Some("api"), Some("tasks"), Some(id), None) => match task_details(state, id) {
+            Some(details) => Response::ok(JSON, format!("{details}\n")),
+            None => Response::not_found(),
+        },
+        (Some("api"), Some("resources"), Some(id), None) => match resource_details(state, id) {
+            Some(details) => Response::ok(JSON, format!("{details}\n")),
+            None => Response::not_found(),
+        },
+        _ => Response::not_found(),
+    }
+}
+
+/// Returns the details of the task whose ID is `id`, if it's in `state`.
+fn task_details(state: &State, id: &str) -> Option<serde_json::Value> {
+    let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+    let task = state
+        .tasks_state()
+        .tasks()
+        .filter_map(|task| task.upgrade())
+        .find(|task| task.borrow().id_str() == id)?;
+    let task = task.borrow();
+    let mut details = query::columns_json(&task, now);
+    let fields = task
+        .formatted_column_fields()
+        .iter()
+        .chain(task.formatted_fields())
+        .map(|field| plain(field))
+        .collect::<Vec<_>>();
+    let warnings = task
+        .warnings()
+        .iter()
+        .map(|linter| linter.format(&task, now))
+        .collect::<Vec<_>>();
+    details.insert("fields".to_owned(), fields.into());
+    details.insert("warnings".to_owned(), warnings.into());
+    Some(details.into())
+}
+
+/// Returns the details of the resource whose ID is `id`, if it's in
+/// `state`.
+fn resource_details(state: &State, id: &str) -> Option<serde_json::Value> {
+    let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+    let resource = state
+        .resources_state()
+        .resources()
+        .filter_map(|resource| resource.upgrade())
+        .find(|resource| resource.borrow().id_str() == id)?;
+    let resource = resource.borrow();
+    let attributes = resource
+        .formatted_attributes()
+        .iter()
+        .map(|attribute| plain(attribute))
+        .collect::<Vec<_>>();
+    Some(serde_json::json!({
+        "id": query::Value::id(resource.id_str()).to_json(),
+        "parent": query::Value::id(resource.parent_id()).to_json(),
+        "kind": resource.kind(),
+        "target": resource.target(),
+        "type": resource.concrete_type(),
+        "location": resource.location(),
+        "total": resource.total(now).as_secs_f64(),
+        "dropped": resource.dropped(),
+        "attributes": attributes,
+    }))
+}
+
+/// Returns the text of a formatted field, without its styles.
+fn plain(spans: &[Span<'_>]) -> String {
+    spans.iter().map(|span| span.content.as_ref()).collect()
+}
+
+fn unavailable() -> Response {
+    Response::new(
+        "503 Service Unavailable",
+        "text/plain",
+        "the server is shutting down\n",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ViewOptions,
+        state::fixtures::{stats, task, update},
+    };
+    use clap::Parser;
+
+    fn get(target: &str) -> Request {
+        Request::parse(&format!("GET {target} HTTP/1.1")).unwrap()
+    }
+
+    fn body(response: Response) -> serde_json::Value {
+        assert_eq!(response.status, "200 OK");
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn serves_tasks() {
+        let styles = view::Styles::from_config(ViewOptions::parse_from(["tokio-console"]));
+        let update = update(60, [(task(1), stats(4)), (task(2), stats(2))]);
+        let mut state = State::default();
+        state.update(&styles, &view::ViewState::TasksList, update);
+        let mut view = view::View::new(styles);
+        let target = "http://127.0.0.1:6669".parse::<Uri>().unwrap();
+        let mut answer =
+            |target_path: &str| answer(&get(target_path), &target, &mut state, &mut view);
+
+        let tasks = body(answer("/api/tasks"));
+        assert_eq!(tasks.as_array().unwrap().len(), 2);
+        let tasks = body(answer("/api/tasks?filter=polls+%3E+3"));
+        assert_eq!(tasks.as_array().unwrap().len(), 1);
+        assert_eq!(tasks[0]["ID"], 1);
+        // The filter only applies to the request it's given with.
+        assert_eq!(body(answer("/api/tasks/")).as_array().unwrap().len(), 2);
+        assert_eq!(
+            answer("/api/tasks?filter=polls+%3E").status,
+            "400 Bad Request"
+        );
+
+        let details = body(answer("/api/tasks/2"));
+        assert_eq!(details["id"], 2);
+        assert_eq!(details["polls"], 2);
+        assert_eq!(details["state"], "idle");
+        assert_eq!(answer("/api/tasks/3"), Response::not_found());
+
+        assert_eq!(body(answer("/api/resources")), serde_json::json!([]));
+        assert_eq!(
+            body(answer("/api/status"))["target"],
+            "http://127.0.0.1:6669/"
+        );
+        assert_eq!(answer("/").body, INDEX);
+        assert_eq!(answer("/api/nope"), Response::not_found());
+    }
+}